@@ -0,0 +1,75 @@
+//! Serves the state indexed by a Carbon pipeline over a REST API, turning the
+//! pipeline into a self-contained API for the programs it decodes.
+//!
+//! Pipelines built from a config file send the accounts and instructions
+//! decoded by each decoder, as JSON, to their sinks. `ApiSink` writes them to
+//! Postgres, keeping the latest state of each account and the history of
+//! instructions, and `router` serves them per decoder, with filters and
+//! pagination, without any code specific to the decoders.
+//!
+//! # Overview
+//!
+//! - **[`migrations`]**: `ApiMigration` creates the `carbon_accounts` and
+//!   `carbon_instructions` tables.
+//! - **[`sink`]**: `ApiSink` writes batches of decoded events to the tables.
+//! - **[`routes`]**: The axum routes of the API, served by `serve`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_api::{ApiMigration, ApiSink};
+//! use carbon_core::{config::ConfigRegistry, sink::SinkExt};
+//!
+//! let pg_client = PgClient::new(&database_url, 1, 10).await?;
+//! pg_client.migrate(vec![Box::new(ApiMigration)]).await?;
+//!
+//! let pool = pg_client.pool.clone();
+//! let pipeline = ConfigRegistry::new()
+//!     .sink("api", move |_| Ok(ApiSink::new(pool.clone()).buffer(500)))
+//!     // Datasources and decoders.
+//!     .build(&PipelineConfig::from_file("carbon.toml")?)?;
+//!
+//! tokio::try_join!(
+//!     pipeline.run(),
+//!     carbon_api::serve("0.0.0.0:8080".parse()?, pg_client.pool),
+//! )?;
+//! ```
+//!
+//! ```text
+//! GET /pumpfun/accounts?type=BondingCurve&min_slot=340000000&limit=50
+//! GET /pumpfun/accounts/<pubkey>
+//! GET /pumpfun/instructions?type=Buy&cursor=1200
+//! ```
+//!
+//! # Notes
+//!
+//! - The type of decoded data is the variant of the account or instruction enum
+//!   of the decoder, or the `type` of plugin values.
+//! - Pages are ordered by insertion, so accounts keep their position when their
+//!   state is updated.
+//! - Instructions are appended as received, so replaying slots already indexed
+//!   records their instructions again.
+
+pub mod migrations;
+pub mod routes;
+pub mod sink;
+
+use {
+    carbon_core::error::{CarbonResult, Error},
+    sqlx::PgPool,
+    std::net::SocketAddr,
+    tokio::net::TcpListener,
+};
+pub use {migrations::ApiMigration, routes::router, sink::ApiSink};
+
+/// Serves the API on `addr` until the server fails.
+pub async fn serve(addr: SocketAddr, pool: PgPool) -> CarbonResult<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| Error::Custom(format!("Failed to listen on {}: {}", addr, e)))?;
+    log::info!("Serving the API on {}", addr);
+
+    axum::serve(listener, router(pool))
+        .await
+        .map_err(|e| Error::Custom(format!("API server failed: {}", e)))
+}
@@ -0,0 +1,84 @@
+use sqlx_migrator::{error::Error, migration::Migration, operation::Operation};
+
+/// Creates the tables written by `ApiSink` and read by the API.
+pub struct ApiMigration;
+
+impl Migration<sqlx::Postgres> for ApiMigration {
+    fn app(&self) -> &str {
+        "carbon_api"
+    }
+
+    fn name(&self) -> &str {
+        "init_api_storage"
+    }
+
+    fn parents(&self) -> Vec<Box<dyn Migration<sqlx::Postgres>>> {
+        vec![]
+    }
+
+    fn operations(&self) -> Vec<Box<dyn Operation<sqlx::Postgres>>> {
+        vec![Box::new(InitOperation)]
+    }
+}
+
+struct InitOperation;
+
+#[async_trait::async_trait]
+impl Operation<sqlx::Postgres> for InitOperation {
+    async fn up(&self, connection: &mut sqlx::PgConnection) -> Result<(), Error> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS carbon_accounts (
+                id BIGSERIAL PRIMARY KEY,
+                decoder TEXT NOT NULL,
+                pubkey TEXT NOT NULL,
+                slot BIGINT NOT NULL,
+                type_name TEXT,
+                data JSONB NOT NULL,
+                UNIQUE (decoder, pubkey)
+            )",
+        )
+        .execute(&mut *connection)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS carbon_accounts_slot ON carbon_accounts (decoder, slot)",
+        )
+        .execute(&mut *connection)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS carbon_instructions (
+                id BIGSERIAL PRIMARY KEY,
+                decoder TEXT NOT NULL,
+                signature TEXT,
+                program_id TEXT NOT NULL,
+                slot BIGINT NOT NULL,
+                type_name TEXT,
+                data JSONB NOT NULL
+            )",
+        )
+        .execute(&mut *connection)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS carbon_instructions_slot
+                ON carbon_instructions (decoder, slot)",
+        )
+        .execute(&mut *connection)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS carbon_instructions_signature
+                ON carbon_instructions (signature)",
+        )
+        .execute(&mut *connection)
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, connection: &mut sqlx::PgConnection) -> Result<(), Error> {
+        sqlx::query("DROP TABLE carbon_instructions")
+            .execute(&mut *connection)
+            .await?;
+        sqlx::query("DROP TABLE carbon_accounts")
+            .execute(&mut *connection)
+            .await?;
+        Ok(())
+    }
+}
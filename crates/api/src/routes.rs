@@ -0,0 +1,241 @@
+use {
+    axum::{
+        extract::{Path, Query, State},
+        http::StatusCode,
+        response::{IntoResponse, Response},
+        routing::get,
+        Json, Router,
+    },
+    serde::{Deserialize, Serialize},
+    sqlx::{PgPool, Postgres, QueryBuilder},
+};
+
+/// The number of items of a page when the request doesn't set a `limit`.
+pub const DEFAULT_PAGE_SIZE: i64 = 100;
+/// The largest `limit` accepted.
+pub const MAX_PAGE_SIZE: i64 = 1_000;
+
+/// The latest decoded state of an account.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AccountState {
+    pub id: i64,
+    pub decoder: String,
+    pub pubkey: String,
+    pub slot: i64,
+    pub type_name: Option<String>,
+    pub data: serde_json::Value,
+}
+
+/// A decoded instruction.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct InstructionRecord {
+    pub id: i64,
+    pub decoder: String,
+    pub signature: Option<String>,
+    pub program_id: String,
+    pub slot: i64,
+    pub type_name: Option<String>,
+    pub data: serde_json::Value,
+}
+
+/// A page of items, `next_cursor` being passed as the `cursor` of the request
+/// for the next page, if any.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AccountFilters {
+    pub pubkey: Option<String>,
+    #[serde(rename = "type")]
+    pub type_name: Option<String>,
+    pub min_slot: Option<i64>,
+    pub max_slot: Option<i64>,
+    pub cursor: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InstructionFilters {
+    pub signature: Option<String>,
+    pub program_id: Option<String>,
+    #[serde(rename = "type")]
+    pub type_name: Option<String>,
+    pub min_slot: Option<i64>,
+    pub max_slot: Option<i64>,
+    pub cursor: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Returns the routes of the API:
+///
+/// - `GET /decoders`: The decoders with indexed accounts or instructions.
+/// - `GET /{decoder}/accounts`: The accounts of a decoder, filtered by
+///   `pubkey`, `type`, `min_slot` and `max_slot`.
+/// - `GET /{decoder}/accounts/{pubkey}`: The state of an account.
+/// - `GET /{decoder}/instructions`: The instructions of a decoder, filtered by
+///   `signature`, `program_id`, `type`, `min_slot` and `max_slot`.
+///
+/// Lists are paginated with `cursor` and `limit`.
+pub fn router(pool: PgPool) -> Router {
+    Router::new()
+        .route("/decoders", get(decoders))
+        .route("/{decoder}/accounts", get(accounts))
+        .route("/{decoder}/accounts/{pubkey}", get(account))
+        .route("/{decoder}/instructions", get(instructions))
+        .with_state(pool)
+}
+
+pub enum ApiError {
+    NotFound,
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND.into_response(),
+            Self::Database(error) => {
+                log::error!("API query failed: {}", error);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+async fn decoders(State(pool): State<PgPool>) -> Result<Json<Vec<String>>, ApiError> {
+    let decoders = sqlx::query_scalar(
+        "SELECT decoder FROM carbon_accounts
+         UNION
+         SELECT decoder FROM carbon_instructions
+         ORDER BY decoder",
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(decoders))
+}
+
+async fn accounts(
+    State(pool): State<PgPool>,
+    Path(decoder): Path<String>,
+    Query(filters): Query<AccountFilters>,
+) -> Result<Json<Page<AccountState>>, ApiError> {
+    let mut query = QueryBuilder::<Postgres>::new(
+        "SELECT id, decoder, pubkey, slot, type_name, data FROM carbon_accounts WHERE decoder = ",
+    );
+    query.push_bind(decoder);
+    if let Some(pubkey) = filters.pubkey {
+        query.push(" AND pubkey = ").push_bind(pubkey);
+    }
+    push_common_filters(
+        &mut query,
+        filters.type_name,
+        filters.min_slot,
+        filters.max_slot,
+        filters.cursor,
+    );
+    let limit = page_size(filters.limit);
+    query.push(" ORDER BY id LIMIT ").push_bind(limit + 1);
+
+    let items = query.build_query_as().fetch_all(&pool).await?;
+    Ok(Json(page(items, limit, |account: &AccountState| {
+        account.id
+    })))
+}
+
+async fn account(
+    State(pool): State<PgPool>,
+    Path((decoder, pubkey)): Path<(String, String)>,
+) -> Result<Json<AccountState>, ApiError> {
+    sqlx::query_as(
+        "SELECT id, decoder, pubkey, slot, type_name, data FROM carbon_accounts
+         WHERE decoder = $1 AND pubkey = $2",
+    )
+    .bind(decoder)
+    .bind(pubkey)
+    .fetch_optional(&pool)
+    .await?
+    .map(Json)
+    .ok_or(ApiError::NotFound)
+}
+
+async fn instructions(
+    State(pool): State<PgPool>,
+    Path(decoder): Path<String>,
+    Query(filters): Query<InstructionFilters>,
+) -> Result<Json<Page<InstructionRecord>>, ApiError> {
+    let mut query = QueryBuilder::<Postgres>::new(
+        "SELECT id, decoder, signature, program_id, slot, type_name, data \
+         FROM carbon_instructions WHERE decoder = ",
+    );
+    query.push_bind(decoder);
+    if let Some(signature) = filters.signature {
+        query.push(" AND signature = ").push_bind(signature);
+    }
+    if let Some(program_id) = filters.program_id {
+        query.push(" AND program_id = ").push_bind(program_id);
+    }
+    push_common_filters(
+        &mut query,
+        filters.type_name,
+        filters.min_slot,
+        filters.max_slot,
+        filters.cursor,
+    );
+    let limit = page_size(filters.limit);
+    query.push(" ORDER BY id LIMIT ").push_bind(limit + 1);
+
+    let items = query.build_query_as().fetch_all(&pool).await?;
+    Ok(Json(page(
+        items,
+        limit,
+        |instruction: &InstructionRecord| instruction.id,
+    )))
+}
+
+fn push_common_filters(
+    query: &mut QueryBuilder<'_, Postgres>,
+    type_name: Option<String>,
+    min_slot: Option<i64>,
+    max_slot: Option<i64>,
+    cursor: Option<i64>,
+) {
+    if let Some(type_name) = type_name {
+        query.push(" AND type_name = ").push_bind(type_name);
+    }
+    if let Some(min_slot) = min_slot {
+        query.push(" AND slot >= ").push_bind(min_slot);
+    }
+    if let Some(max_slot) = max_slot {
+        query.push(" AND slot <= ").push_bind(max_slot);
+    }
+    if let Some(cursor) = cursor {
+        query.push(" AND id > ").push_bind(cursor);
+    }
+}
+
+fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Builds a page from the items of a query fetching one more item than
+/// `limit`, which tells whether there is a next page.
+fn page<T>(mut items: Vec<T>, limit: i64, id: impl Fn(&T) -> i64) -> Page<T> {
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(id)
+    } else {
+        None
+    };
+
+    Page { items, next_cursor }
+}
@@ -0,0 +1,187 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        config::{DecodedEvent, DecodedEventKind},
+        error::CarbonResult,
+        sink::Sink,
+    },
+    carbon_postgres_client::{PgRow, PgSink},
+    sqlx::{query_builder::Separated, PgPool, Postgres},
+    std::collections::HashMap,
+};
+
+/// The latest state of an account, upserted into `carbon_accounts`.
+struct AccountRow {
+    decoder: String,
+    pubkey: String,
+    slot: i64,
+    type_name: Option<String>,
+    data: serde_json::Value,
+}
+
+impl PgRow for AccountRow {
+    const TABLE: &'static str = "carbon_accounts";
+    const COLUMNS: &'static [&'static str] = &["decoder", "pubkey", "slot", "type_name", "data"];
+
+    fn bind(self, row: &mut Separated<'_, '_, Postgres, &'static str>) {
+        row.push_bind(self.decoder)
+            .push_bind(self.pubkey)
+            .push_bind(self.slot)
+            .push_bind(self.type_name)
+            .push_bind(self.data);
+    }
+}
+
+/// A decoded instruction, appended to `carbon_instructions`.
+struct InstructionRow {
+    decoder: String,
+    signature: Option<String>,
+    program_id: String,
+    slot: i64,
+    type_name: Option<String>,
+    data: serde_json::Value,
+}
+
+impl PgRow for InstructionRow {
+    const TABLE: &'static str = "carbon_instructions";
+    const COLUMNS: &'static [&'static str] = &[
+        "decoder",
+        "signature",
+        "program_id",
+        "slot",
+        "type_name",
+        "data",
+    ];
+
+    fn bind(self, row: &mut Separated<'_, '_, Postgres, &'static str>) {
+        row.push_bind(self.decoder)
+            .push_bind(self.signature)
+            .push_bind(self.program_id)
+            .push_bind(self.slot)
+            .push_bind(self.type_name)
+            .push_bind(self.data);
+    }
+}
+
+/// A sink writing the decoded events of configured pipelines to the tables
+/// served by the API.
+///
+/// Accounts keep their latest state, older updates being ignored, while
+/// instructions are appended.
+pub struct ApiSink {
+    accounts: PgSink<AccountRow>,
+    instructions: PgSink<InstructionRow>,
+}
+
+impl ApiSink {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            accounts: PgSink::new(pool.clone()).on_conflict(
+                "(decoder, pubkey) DO UPDATE SET slot = EXCLUDED.slot, \
+                 type_name = EXCLUDED.type_name, data = EXCLUDED.data \
+                 WHERE carbon_accounts.slot <= EXCLUDED.slot",
+            ),
+            instructions: PgSink::new(pool),
+        }
+    }
+}
+
+#[async_trait]
+impl Sink<Vec<DecodedEvent>> for ApiSink {
+    async fn send(&mut self, batch: Vec<DecodedEvent>) -> CarbonResult<()> {
+        let (accounts, instructions) = split(batch);
+
+        self.accounts.send(accounts).await?;
+        self.instructions.send(instructions).await
+    }
+}
+
+/// Splits a batch into rows, keeping the latest update of each account since
+/// an upsert can't update a row twice.
+fn split(batch: Vec<DecodedEvent>) -> (Vec<AccountRow>, Vec<InstructionRow>) {
+    let mut accounts: HashMap<(String, String), AccountRow> = HashMap::new();
+    let mut instructions = Vec::new();
+
+    for event in batch {
+        let type_name = type_name(&event.data);
+        match event.kind {
+            DecodedEventKind::Account => {
+                let key = (event.decoder.clone(), event.pubkey.clone());
+                if accounts
+                    .get(&key)
+                    .is_some_and(|account| account.slot > event.slot as i64)
+                {
+                    continue;
+                }
+                accounts.insert(
+                    key,
+                    AccountRow {
+                        decoder: event.decoder,
+                        pubkey: event.pubkey,
+                        slot: event.slot as i64,
+                        type_name,
+                        data: event.data,
+                    },
+                );
+            }
+            DecodedEventKind::Instruction => instructions.push(InstructionRow {
+                decoder: event.decoder,
+                signature: event.signature,
+                program_id: event.pubkey,
+                slot: event.slot as i64,
+                type_name,
+                data: event.data,
+            }),
+        }
+    }
+
+    (accounts.into_values().collect(), instructions)
+}
+
+/// Returns the name of the type of decoded data: the `type` of plugin values,
+/// or the variant of the account and instruction enums of decoders.
+fn type_name(data: &serde_json::Value) -> Option<String> {
+    match data {
+        serde_json::Value::Object(object) => match object.get("type") {
+            Some(serde_json::Value::String(type_name)) => Some(type_name.clone()),
+            _ if object.len() == 1 => object.keys().next().cloned(),
+            _ => None,
+        },
+        serde_json::Value::String(variant) => Some(variant.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    fn account(slot: u64, data: serde_json::Value) -> DecodedEvent {
+        DecodedEvent {
+            decoder: "pumpfun".to_string(),
+            kind: DecodedEventKind::Account,
+            slot,
+            signature: None,
+            pubkey: "curve".to_string(),
+            data,
+        }
+    }
+
+    #[test]
+    fn test_split_keeps_latest_account_state() {
+        let (accounts, instructions) = split(vec![
+            account(2, json!({ "BondingCurve": { "complete": false } })),
+            account(3, json!({ "BondingCurve": { "complete": true } })),
+            account(1, json!({ "BondingCurve": { "complete": false } })),
+        ]);
+
+        assert!(instructions.is_empty());
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].slot, 3);
+        assert_eq!(accounts[0].type_name.as_deref(), Some("BondingCurve"));
+        assert_eq!(
+            type_name(&json!({ "type": "Buy", "data": {} })).as_deref(),
+            Some("Buy")
+        );
+    }
+}
@@ -0,0 +1,610 @@
+//! Manages archives of recorded raw updates, so they can be kept indefinitely
+//! without manual housekeeping.
+//!
+//! An archive is a directory of chunk files, each holding the recorded
+//! updates of a range of slots. Left alone, the number of chunks grows without
+//! bound and the disk eventually fills up. `Archive` writes chunks along with
+//! a checksum, compacts the chunks of each past day into a single daily file,
+//! and prunes the oldest files according to its retention policy.
+//!
+//! # Layout
+//!
+//! - Chunks are named `chunk-<first slot>-<last slot>-<unix millis>.bin`.
+//! - Daily files are named `daily-<YYYY-MM-DD>-<first slot>-<last slot>.bin`,
+//!   the date being the UTC day the compacted chunks were written.
+//! - Every file has a `<name>.sha256` sidecar holding the hex encoded SHA-256
+//!   checksum of its content.
+//!
+//! Archives are written by the `UpdateRecorder` of
+//! `carbon-file-replay-datasource`, and replayed by its `FileReplayDatasource`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_archive::Archive;
+//! use carbon_file_replay_datasource::UpdateRecorder;
+//! use std::time::Duration;
+//!
+//! let archive = Archive::open("./archive")?
+//!     .max_age(Duration::from_secs(30 * 24 * 60 * 60))
+//!     .max_size(500 * 1024 * 1024 * 1024);
+//!
+//! // Record the updates of every 1,000 slots to a chunk.
+//! let recorder = UpdateRecorder::archive(archive.clone(), 1_000);
+//!
+//! // Compact and prune the archive every hour.
+//! archive
+//!     .clone()
+//!     .spawn_maintenance(Duration::from_secs(60 * 60), cancellation_token.clone());
+//! ```
+//!
+//! # Notes
+//!
+//! - Compaction concatenates chunks in slot order, so the format of their
+//!   content must support concatenation, like newline-delimited or length
+//!   prefixed records.
+//! - Chunks failing their checksum are left out of compaction and kept in place
+//!   for inspection, and days whose daily file fails its checksum aren't
+//!   compacted. `Archive::verify` lists every corrupted file.
+//! - Files are written to a temporary file first and renamed once complete, so
+//!   readers never see a partially written file. A crash in the middle of a
+//!   compaction may however leave chunks next to the daily file they were
+//!   compacted into.
+//! - Pruning by age uses the time a chunk was written, or the end of the day of
+//!   a daily file. Pruning by size removes files from the oldest slots.
+
+use {
+    carbon_core::error::{CarbonResult, Error},
+    chrono::{DateTime, NaiveDate, TimeDelta, Utc},
+    sha2::{Digest, Sha256},
+    std::{
+        collections::BTreeMap,
+        fs,
+        io::{self, Read, Write},
+        path::{Path, PathBuf},
+        time::Duration,
+    },
+    tokio::task::JoinHandle,
+    tokio_util::sync::CancellationToken,
+};
+
+const CHUNK_PREFIX: &str = "chunk-";
+const DAILY_PREFIX: &str = "daily-";
+const FILE_EXTENSION: &str = ".bin";
+const CHECKSUM_EXTENSION: &str = ".sha256";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+/// The length of a date formatted with `DATE_FORMAT`.
+const DATE_LENGTH: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFileKind {
+    /// A chunk of recorded updates.
+    Chunk,
+    /// The compacted chunks of a day.
+    Daily,
+}
+
+/// A file of an archive.
+#[derive(Debug, Clone)]
+pub struct ArchiveFile {
+    pub path: PathBuf,
+    pub kind: ArchiveFileKind,
+    pub first_slot: u64,
+    pub last_slot: u64,
+    /// When the chunk was written, or the start of the day of a daily file.
+    pub created_at: DateTime<Utc>,
+    /// The size of the file, in bytes.
+    pub size: u64,
+}
+
+impl ArchiveFile {
+    fn parse(path: PathBuf, size: u64) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.strip_suffix(FILE_EXTENSION)?;
+
+        let (kind, created_at, slots) = if let Some(rest) = name.strip_prefix(CHUNK_PREFIX) {
+            let (slots, created_at) = rest.rsplit_once('-')?;
+            let created_at = DateTime::from_timestamp_millis(created_at.parse().ok()?)?;
+            (ArchiveFileKind::Chunk, created_at, slots)
+        } else if let Some(rest) = name.strip_prefix(DAILY_PREFIX) {
+            let (date, slots) = rest.split_at_checked(DATE_LENGTH)?;
+            let date = NaiveDate::parse_from_str(date, DATE_FORMAT).ok()?;
+            let created_at = date.and_hms_opt(0, 0, 0)?.and_utc();
+            (ArchiveFileKind::Daily, created_at, slots.strip_prefix('-')?)
+        } else {
+            return None;
+        };
+
+        let (first_slot, last_slot) = slots.split_once('-')?;
+
+        Some(Self {
+            kind,
+            first_slot: first_slot.parse().ok()?,
+            last_slot: last_slot.parse().ok()?,
+            created_at,
+            size,
+            path,
+        })
+    }
+
+    /// Returns the time after which the file doesn't receive new updates.
+    fn completed_at(&self) -> DateTime<Utc> {
+        match self.kind {
+            ArchiveFileKind::Chunk => self.created_at,
+            ArchiveFileKind::Daily => self.created_at + TimeDelta::days(1),
+        }
+    }
+
+    fn checksum_path(&self) -> PathBuf {
+        checksum_path(&self.path)
+    }
+}
+
+/// A directory of recorded update chunks, with its retention policy.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    pub dir: PathBuf,
+    /// Files older than this are pruned.
+    pub max_age: Option<Duration>,
+    /// The oldest files are pruned while the archive is larger than this, in
+    /// bytes.
+    pub max_size: Option<u64>,
+    /// Whether the chunks of past days are compacted into daily files.
+    pub compact_daily: bool,
+}
+
+impl Archive {
+    /// Opens the archive stored in `dir`, creating the directory if needed.
+    ///
+    /// By default, chunks are compacted into daily files and nothing is
+    /// pruned.
+    pub fn open(dir: impl Into<PathBuf>) -> CarbonResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, e))?;
+
+        Ok(Self {
+            dir,
+            max_age: None,
+            max_size: None,
+            compact_daily: true,
+        })
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    pub fn compact_daily(mut self, compact_daily: bool) -> Self {
+        self.compact_daily = compact_daily;
+        self
+    }
+
+    /// Writes a chunk holding the recorded updates of the slots from
+    /// `first_slot` to `last_slot`, along with its checksum.
+    pub fn write_chunk(
+        &self,
+        first_slot: u64,
+        last_slot: u64,
+        data: &[u8],
+    ) -> CarbonResult<ArchiveFile> {
+        self.write_chunk_at(first_slot, last_slot, data, Utc::now())
+    }
+
+    fn write_chunk_at(
+        &self,
+        first_slot: u64,
+        last_slot: u64,
+        data: &[u8],
+        created_at: DateTime<Utc>,
+    ) -> CarbonResult<ArchiveFile> {
+        let path = self.dir.join(format!(
+            "{}{:020}-{:020}-{}{}",
+            CHUNK_PREFIX,
+            first_slot,
+            last_slot,
+            created_at.timestamp_millis(),
+            FILE_EXTENSION
+        ));
+
+        write_atomically(&path, |file| file.write_all(data))?;
+        write_checksum(&path, &hex_digest(Sha256::digest(data).as_slice()))?;
+
+        Ok(ArchiveFile {
+            path,
+            kind: ArchiveFileKind::Chunk,
+            first_slot,
+            last_slot,
+            created_at,
+            size: data.len() as u64,
+        })
+    }
+
+    /// Returns the files of the archive, ordered by slot.
+    pub fn files(&self) -> CarbonResult<Vec<ArchiveFile>> {
+        let entries = fs::read_dir(&self.dir).map_err(|e| io_error(&self.dir, e))?;
+
+        let mut files = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| io_error(&self.dir, e))?;
+            let metadata = entry.metadata().map_err(|e| io_error(&entry.path(), e))?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            if let Some(file) = ArchiveFile::parse(entry.path(), metadata.len()) {
+                files.push(file);
+            }
+        }
+        files.sort_by_key(|file| (file.first_slot, file.created_at));
+
+        Ok(files)
+    }
+
+    /// Returns the files to read to replay the archive, ordered by slot.
+    ///
+    /// Chunks left next to the daily file of their day by an interrupted
+    /// compaction are already part of it, and are left out.
+    pub fn replay_files(&self) -> CarbonResult<Vec<ArchiveFile>> {
+        let files = self.files()?;
+        let daily_files: Vec<ArchiveFile> = files
+            .iter()
+            .filter(|file| file.kind == ArchiveFileKind::Daily)
+            .cloned()
+            .collect();
+
+        Ok(files
+            .into_iter()
+            .filter(|file| {
+                file.kind == ArchiveFileKind::Daily
+                    || !daily_files.iter().any(|daily_file| {
+                        daily_file.created_at.date_naive() == file.created_at.date_naive()
+                            && daily_file.first_slot <= file.first_slot
+                            && file.last_slot <= daily_file.last_slot
+                    })
+            })
+            .collect())
+    }
+
+    /// Returns `true` if the content of the file matches its checksum.
+    pub fn verify_file(&self, file: &ArchiveFile) -> CarbonResult<bool> {
+        let expected = match fs::read_to_string(file.checksum_path()) {
+            Ok(expected) => expected,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(io_error(&file.checksum_path(), e)),
+        };
+
+        let mut hasher = Sha256::new();
+        copy_hashed(&file.path, &mut io::sink(), &mut hasher)
+            .map_err(|e| io_error(&file.path, e))?;
+
+        Ok(hex_digest(hasher.finalize().as_slice()) == expected.trim())
+    }
+
+    /// Returns the paths of the files whose checksum is missing or doesn't
+    /// match their content.
+    pub fn verify(&self) -> CarbonResult<Vec<PathBuf>> {
+        let mut corrupted = Vec::new();
+        for file in self.files()? {
+            if !self.verify_file(&file)? {
+                corrupted.push(file.path);
+            }
+        }
+
+        Ok(corrupted)
+    }
+
+    /// Compacts the chunks written on past days into daily files, returning
+    /// the number of compacted chunks.
+    pub fn compact(&self) -> CarbonResult<usize> {
+        self.compact_at(Utc::now())
+    }
+
+    fn compact_at(&self, now: DateTime<Utc>) -> CarbonResult<usize> {
+        let today = now.date_naive();
+        let mut days: BTreeMap<NaiveDate, Vec<ArchiveFile>> = BTreeMap::new();
+        for file in self.files()? {
+            let day = file.created_at.date_naive();
+            if day < today {
+                days.entry(day).or_default().push(file);
+            }
+        }
+
+        let mut compacted = 0;
+        'days: for (day, files) in days {
+            let mut sources = Vec::with_capacity(files.len());
+            for file in files {
+                if self.verify_file(&file)? {
+                    sources.push(file);
+                } else if file.kind == ArchiveFileKind::Chunk {
+                    log::warn!(
+                        "Archive chunk {} failed its checksum and wasn't compacted",
+                        file.path.display()
+                    );
+                } else {
+                    log::warn!(
+                        "Archive daily file {} failed its checksum, its day wasn't compacted",
+                        file.path.display()
+                    );
+                    continue 'days;
+                }
+            }
+
+            let chunks = sources
+                .iter()
+                .filter(|file| file.kind == ArchiveFileKind::Chunk)
+                .count();
+            if chunks == 0 {
+                continue;
+            }
+
+            self.write_daily(day, &sources)?;
+            compacted += chunks;
+        }
+
+        Ok(compacted)
+    }
+
+    /// Concatenates `sources`, ordered by slot, into the daily file of `day`,
+    /// and removes them.
+    fn write_daily(&self, day: NaiveDate, sources: &[ArchiveFile]) -> CarbonResult<()> {
+        let first_slot = sources.iter().map(|file| file.first_slot).min();
+        let last_slot = sources.iter().map(|file| file.last_slot).max();
+        let path = self.dir.join(format!(
+            "{}{}-{:020}-{:020}{}",
+            DAILY_PREFIX,
+            day.format(DATE_FORMAT),
+            first_slot.unwrap_or_default(),
+            last_slot.unwrap_or_default(),
+            FILE_EXTENSION
+        ));
+
+        let mut hasher = Sha256::new();
+        write_atomically(&path, |output| {
+            sources
+                .iter()
+                .try_for_each(|source| copy_hashed(&source.path, output, &mut hasher))
+        })?;
+        write_checksum(&path, &hex_digest(hasher.finalize().as_slice()))?;
+
+        for source in sources.iter().filter(|source| source.path != path) {
+            remove_file(source)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the files exceeding the retention policy, returning them.
+    pub fn prune(&self) -> CarbonResult<Vec<ArchiveFile>> {
+        self.prune_at(Utc::now())
+    }
+
+    fn prune_at(&self, now: DateTime<Utc>) -> CarbonResult<Vec<ArchiveFile>> {
+        let mut files = self.files()?;
+        let mut pruned = Vec::new();
+
+        let cutoff = self.max_age.and_then(|max_age| {
+            now.checked_sub_signed(TimeDelta::from_std(max_age).unwrap_or(TimeDelta::MAX))
+        });
+        if let Some(cutoff) = cutoff {
+            let (expired, kept): (Vec<ArchiveFile>, Vec<ArchiveFile>) = files
+                .into_iter()
+                .partition(|file| file.completed_at() < cutoff);
+            files = kept;
+            pruned.extend(expired);
+        }
+
+        if let Some(max_size) = self.max_size {
+            let mut size: u64 = files.iter().map(|file| file.size).sum();
+            let oversized = files.iter().take_while(|file| {
+                let exceeds = size > max_size;
+                size = size.saturating_sub(file.size);
+                exceeds
+            });
+            pruned.extend(oversized.cloned());
+        }
+
+        for file in &pruned {
+            remove_file(file)?;
+        }
+
+        Ok(pruned)
+    }
+
+    /// Compacts the archive, if enabled, and prunes it.
+    pub fn maintain(&self) -> CarbonResult<()> {
+        if self.compact_daily {
+            let compacted = self.compact()?;
+            if compacted > 0 {
+                log::info!(
+                    "Compacted {} archive chunks into daily files in {}",
+                    compacted,
+                    self.dir.display()
+                );
+            }
+        }
+
+        let pruned = self.prune()?;
+        if !pruned.is_empty() {
+            log::info!(
+                "Pruned {} archive files ({} bytes) from {}",
+                pruned.len(),
+                pruned.iter().map(|file| file.size).sum::<u64>(),
+                self.dir.display()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Spawns a task maintaining the archive every `interval`, until
+    /// `cancellation_token` is cancelled.
+    ///
+    /// Maintenance errors are logged and retried on the next run.
+    pub fn spawn_maintenance(
+        self,
+        interval: Duration,
+        cancellation_token: CancellationToken,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    _ = tokio::time::sleep(interval) => {}
+                }
+
+                let archive = self.clone();
+                match tokio::task::spawn_blocking(move || archive.maintain()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => log::error!("Archive maintenance failed: {}", e),
+                    Err(e) => log::error!("Archive maintenance task failed: {}", e),
+                }
+            }
+        })
+    }
+}
+
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut checksum_path = path.as_os_str().to_owned();
+    checksum_path.push(CHECKSUM_EXTENSION);
+    checksum_path.into()
+}
+
+fn hex_digest(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn write_checksum(path: &Path, checksum: &str) -> CarbonResult<()> {
+    write_atomically(&checksum_path(path), |file| {
+        file.write_all(checksum.as_bytes())
+    })
+}
+
+/// Writes to a temporary file renamed to `path` once complete.
+fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> io::Result<()>,
+) -> CarbonResult<()> {
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let temporary_path = PathBuf::from(temporary_path);
+
+    let result = fs::File::create(&temporary_path).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()
+    });
+    if let Err(e) = result.and_then(|()| fs::rename(&temporary_path, path)) {
+        let _ = fs::remove_file(&temporary_path);
+        return Err(io_error(path, e));
+    }
+
+    Ok(())
+}
+
+fn copy_hashed(path: &Path, output: &mut impl Write, hasher: &mut Sha256) -> io::Result<()> {
+    let mut input = fs::File::open(path)?;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = input.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        hasher.update(&buffer[..read]);
+        output.write_all(&buffer[..read])?;
+    }
+}
+
+fn remove_file(file: &ArchiveFile) -> CarbonResult<()> {
+    fs::remove_file(&file.path).map_err(|e| io_error(&file.path, e))?;
+    match fs::remove_file(file.checksum_path()) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(io_error(&file.checksum_path(), e)),
+        _ => Ok(()),
+    }
+}
+
+fn io_error(path: &Path, error: io::Error) -> Error {
+    Error::Custom(format!(
+        "Archive I/O error on {}: {}",
+        path.display(),
+        error
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_and_prune() {
+        let dir = std::env::temp_dir().join(format!(
+            "carbon-archive-test-{}-{}",
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let archive = Archive::open(&dir).unwrap();
+        let now = Utc::now();
+        let yesterday = now - TimeDelta::days(1);
+
+        archive
+            .write_chunk_at(20, 29, b"second\n", yesterday)
+            .unwrap();
+        archive
+            .write_chunk_at(10, 19, b"first\n", yesterday)
+            .unwrap();
+        archive.write_chunk_at(30, 39, b"third\n", now).unwrap();
+
+        assert_eq!(archive.compact_at(now).unwrap(), 2);
+
+        let files = archive.files().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].kind, ArchiveFileKind::Daily);
+        assert_eq!((files[0].first_slot, files[0].last_slot), (10, 29));
+        assert_eq!(fs::read(&files[0].path).unwrap(), b"first\nsecond\n");
+        assert!(archive.verify().unwrap().is_empty());
+
+        let pruned = archive.max_size(6).prune_at(now).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].kind, ArchiveFileKind::Daily);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_files_skip_compacted_chunks() {
+        let dir = std::env::temp_dir().join(format!(
+            "carbon-archive-replay-test-{}-{}",
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let archive = Archive::open(&dir).unwrap();
+        let now = Utc::now();
+        let yesterday = now - TimeDelta::days(1);
+
+        let chunk = archive
+            .write_chunk_at(10, 19, b"first\n", yesterday)
+            .unwrap();
+        archive
+            .write_daily(yesterday.date_naive(), &[chunk])
+            .unwrap();
+        // A chunk left over by a compaction interrupted before removing it.
+        archive
+            .write_chunk_at(10, 19, b"first\n", yesterday)
+            .unwrap();
+        archive.write_chunk_at(20, 29, b"second\n", now).unwrap();
+
+        let files = archive.replay_files().unwrap();
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| (file.kind, file.first_slot))
+                .collect::<Vec<_>>(),
+            [(ArchiveFileKind::Daily, 10), (ArchiveFileKind::Chunk, 20)]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,7 +2,7 @@ use {
     crate::{
         idl::Idl,
         legacy_idl::LegacyIdl,
-        util::{idl_type_to_rust_type, is_big_array},
+        util::{gate_serde_attribute, idl_type_to_rust_type, is_big_array},
     },
     askama::Template,
     heck::{ToSnakeCase, ToUpperCamelCase},
@@ -17,6 +17,7 @@ pub struct AccountData {
     pub discriminator: String,
     pub fields: Vec<FieldData>,
     pub requires_imports: bool,
+    pub zero_copy_repr: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -31,6 +32,26 @@ pub struct FieldData {
 #[template(path = "accounts_struct.askama", escape = "none", ext = ".askama")]
 pub struct AccountsStructTemplate<'a> {
     pub account: &'a AccountData,
+    pub serde_feature: bool,
+    pub zero_copy: bool,
+}
+
+impl AccountsStructTemplate<'_> {
+    /// Returns the `repr` of the account if it is read in place.
+    fn zero_copy_repr(&self) -> Option<&str> {
+        self.account
+            .zero_copy_repr
+            .as_deref()
+            .filter(|_| self.zero_copy)
+    }
+
+    fn field_attributes(&self, attributes: &str) -> String {
+        if self.serde_feature {
+            gate_serde_attribute(attributes)
+        } else {
+            attributes.to_string()
+        }
+    }
 }
 
 #[derive(Template)]
@@ -39,6 +60,7 @@ pub struct AccountsModTemplate<'a> {
     pub accounts: &'a Vec<AccountData>,
     pub decoder_name: String,
     pub program_struct_name: String,
+    pub fixtures: &'a [&'a AccountData],
 }
 
 pub fn legacy_process_accounts(idl: &LegacyIdl) -> Vec<AccountData> {
@@ -79,6 +101,7 @@ pub fn legacy_process_accounts(idl: &LegacyIdl) -> Vec<AccountData> {
             discriminator,
             fields,
             requires_imports,
+            zero_copy_repr: None,
         });
     }
 
@@ -95,9 +118,11 @@ pub fn process_accounts(idl: &Idl) -> Vec<AccountData> {
         let discriminator = compute_account_discriminator(&account.discriminator);
 
         let mut account_fields = Vec::new();
+        let mut zero_copy_repr = None;
 
         for ty in &idl.types {
             if ty.name == struct_name {
+                zero_copy_repr = ty.zero_copy_repr();
                 if let Some(fields) = &ty.type_.fields {
                     for field in fields {
                         let rust_type = idl_type_to_rust_type(&field.type_);
@@ -125,6 +150,7 @@ pub fn process_accounts(idl: &Idl) -> Vec<AccountData> {
             discriminator,
             fields: account_fields,
             requires_imports,
+            zero_copy_repr,
         });
     }
 
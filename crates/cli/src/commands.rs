@@ -20,13 +20,34 @@ pub enum Commands {
     #[command(name = "scaffold")]
     #[command(about = "Generate skeleton of the project.")]
     Scaffold(ScaffoldOptions),
+    #[command(name = "doctor")]
+    #[command(about = "Diagnose environment and connectivity issues of a pipeline config.")]
+    Doctor(DoctorOptions),
+    #[command(name = "semver-check")]
+    #[command(about = "Check a regenerated decoder for breaking changes against a baseline.")]
+    SemverCheck(SemverCheckOptions),
+    #[command(name = "run")]
+    #[command(about = "Run the pipeline described by a config file.")]
+    Run(RunOptions),
+    #[command(name = "bench")]
+    #[command(about = "Measure the decode throughput of a decoder against recorded fixtures.")]
+    Bench(BenchOptions),
 }
 
 #[derive(Parser)]
 pub struct ParseOptions {
-    #[arg(short, long, required = true)]
+    #[arg(short, long, required_unless_present = "program_id")]
     #[arg(help = "Path to an IDL json file or a Solana program address.")]
-    pub idl: IdlSource,
+    pub idl: Option<IdlSource>,
+
+    #[arg(
+        short = 'p',
+        long = "program-id",
+        conflicts_with = "idl",
+        requires = "url"
+    )]
+    #[arg(help = "Address of an Anchor program to fetch the on-chain IDL account for.")]
+    pub program_id: Option<String>,
 
     #[arg(short, long, required = true)]
     #[arg(help = "Path to the desired output directory.")]
@@ -36,6 +57,10 @@ pub struct ParseOptions {
     #[arg(help = "Generate a directory or a crate.")]
     pub as_crate: bool,
 
+    #[arg(long = "serde", default_value_t = false)]
+    #[arg(help = "Gate serde derives behind an optional `serde` feature of the generated crate.")]
+    pub serde: bool,
+
     #[arg(short, long = "standard", default_value = "anchor")]
     #[arg(help = "Specify the IDL standard to parse.")]
     pub standard: IdlStandard,
@@ -47,6 +72,34 @@ pub struct ParseOptions {
     #[arg(short, long, required_if_eq("idl", "ProgramAddress"))]
     #[arg(help = "Network URL to fetch the IDL from. Required if input is a program address.")]
     pub url: Option<Url>,
+
+    #[arg(long = "with-tests", default_value_t = false, requires = "url")]
+    #[arg(
+        help = "Generate decoder tests from account and instruction samples fetched from --url."
+    )]
+    pub with_tests: bool,
+
+    #[arg(long = "emit-proto", default_value_t = false)]
+    #[arg(
+        help = "Emit a protobuf schema of the decoded types, with `From` conversions behind a `proto` feature of the generated crate."
+    )]
+    pub emit_proto: bool,
+
+    #[arg(long = "zero-copy", default_value_t = false)]
+    #[arg(
+        help = "Read the zero-copy accounts and types of the IDL in place, deriving `bytemuck::Pod` for them."
+    )]
+    pub zero_copy: bool,
+
+    #[arg(long = "rustfmt", default_value_t = false)]
+    #[arg(help = "Format the generated files with rustfmt.")]
+    pub rustfmt: bool,
+
+    #[arg(long = "regenerate", default_value_t = false)]
+    #[arg(
+        help = "Only rewrite the files that changed, keeping the `// carbon:keep-start` / `// carbon:keep-end` blocks of existing files."
+    )]
+    pub regenerate: bool,
 }
 
 #[derive(Parser)]
@@ -70,6 +123,54 @@ pub struct ScaffoldOptions {
     #[arg(short = 'm', long, default_value = "log")]
     #[arg(help = "Metrics to use.")]
     pub metrics: String,
+
+    #[arg(long = "docker", default_value_t = false)]
+    #[arg(
+        help = "Generate a Dockerfile and a docker-compose with Postgres for indexers using it, and Prometheus and Grafana for Prometheus metrics."
+    )]
+    pub docker: bool,
+}
+
+#[derive(Parser)]
+pub struct DoctorOptions {
+    #[arg(short, long, default_value = "pipeline.toml")]
+    #[arg(help = "Path to the pipeline config file.")]
+    pub config: String,
+}
+
+#[derive(Parser)]
+pub struct RunOptions {
+    #[arg(short, long, default_value = "carbon.toml")]
+    #[arg(help = "Path to the pipeline config file, in TOML or YAML.")]
+    pub config: String,
+}
+
+#[derive(Parser)]
+pub struct BenchOptions {
+    #[arg(short, long, required = true)]
+    #[arg(
+        help = "The decoder, `system-program`, `token-program` or the path to a WebAssembly plugin."
+    )]
+    pub decoder: String,
+
+    #[arg(short, long, required = true)]
+    #[arg(help = "Directory of `*_account.json` and `*_ix.json` fixtures.")]
+    pub fixtures: String,
+
+    #[arg(short, long, default_value_t = 10_000)]
+    #[arg(help = "Number of times each fixture is decoded.")]
+    pub iterations: u32,
+}
+
+#[derive(Parser)]
+pub struct SemverCheckOptions {
+    #[arg(short, long, required = true)]
+    #[arg(help = "Path to the previously released decoder.")]
+    pub baseline: String,
+
+    #[arg(short, long, required = true)]
+    #[arg(help = "Path to the regenerated decoder.")]
+    pub decoder: String,
 }
 
 #[derive(Clone, Debug)]
@@ -114,6 +215,16 @@ impl FromStr for Url {
     }
 }
 
+impl Url {
+    pub fn rpc_url(&self) -> &str {
+        match self {
+            Url::Mainnet => "https://api.mainnet-beta.solana.com",
+            Url::Devnet => "https://api.devnet.solana.com",
+            Url::CustomRpc(custom_url) => custom_url,
+        }
+    }
+}
+
 impl fmt::Display for Url {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -0,0 +1,100 @@
+use {
+    anyhow::{Context, Result},
+    serde::Deserialize,
+    std::{fs, path::Path},
+};
+
+/// A pipeline configuration file, usually named `pipeline.toml`.
+///
+/// ```toml
+/// [datasource]
+/// kind = "yellowstone-grpc"
+/// geyser_url = "https://grpc.example.com:443"
+/// x_token = "${X_TOKEN}"
+/// rpc_url = "https://api.mainnet-beta.solana.com"
+///
+/// [filters]
+/// program_ids = ["6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P"]
+///
+/// [project]
+/// manifest = "Cargo.toml"
+/// ```
+///
+/// String values of the form `$VAR` or `${VAR}` are read from the
+/// environment.
+#[derive(Debug, Deserialize)]
+pub struct PipelineConfig {
+    pub datasource: DatasourceConfig,
+    #[serde(default)]
+    pub filters: FiltersConfig,
+    #[serde(default)]
+    pub project: ProjectConfig,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DatasourceConfig {
+    pub kind: String,
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    #[serde(default)]
+    pub geyser_url: Option<String>,
+    #[serde(default)]
+    pub x_token: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FiltersConfig {
+    #[serde(default)]
+    pub program_ids: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub manifest: Option<String>,
+}
+
+impl PipelineConfig {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Couldn't read config file {}", path.display()))?;
+
+        let mut config: PipelineConfig = toml::from_str(&content)
+            .with_context(|| format!("Couldn't parse config file {}", path.display()))?;
+        config.resolve_env();
+
+        Ok(config)
+    }
+
+    fn resolve_env(&mut self) {
+        let datasource = &mut self.datasource;
+        for value in [
+            &mut datasource.rpc_url,
+            &mut datasource.ws_url,
+            &mut datasource.geyser_url,
+            &mut datasource.x_token,
+            &mut datasource.api_key,
+        ] {
+            *value = value.take().and_then(|value| resolve_env_value(&value));
+        }
+    }
+}
+
+/// Resolves `$VAR` and `${VAR}` values from the environment. Values referring
+/// to an unset variable resolve to `None`.
+fn resolve_env_value(value: &str) -> Option<String> {
+    let variable = value
+        .strip_prefix("${")
+        .and_then(|value| value.strip_suffix('}'))
+        .or_else(|| value.strip_prefix('$'));
+
+    match variable {
+        Some(variable) => std::env::var(variable).ok(),
+        None => Some(value.to_string()),
+    }
+}
@@ -26,6 +26,7 @@ pub struct ArgumentData {
 #[template(path = "events_struct.askama", escape = "none", ext = ".askama")]
 pub struct EventsStructTemplate<'a> {
     pub event: &'a EventData,
+    pub serde_feature: bool,
 }
 
 pub fn legacy_process_events(idl: &LegacyIdl) -> Vec<EventData> {
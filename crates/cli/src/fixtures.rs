@@ -0,0 +1,281 @@
+use {
+    crate::{accounts::AccountData, instructions::InstructionData},
+    anyhow::{Context, Result},
+    base64::{engine::general_purpose::STANDARD, Engine},
+    serde_json::json,
+    solana_account_decoder_client_types::UiDataSliceConfig,
+    solana_client::{
+        rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+        rpc_filter::{Memcmp, RpcFilterType},
+    },
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_transaction_status::{UiLoadedAddresses, UiTransactionEncoding},
+    std::{fs, str::FromStr},
+};
+
+/// Number of recent program transactions scanned for instruction samples.
+const TRANSACTION_SAMPLE_LIMIT: usize = 200;
+
+/// Where to fetch decoder test fixtures from.
+pub struct FixtureOptions {
+    pub rpc_url: String,
+    /// Overrides the program address declared by the IDL.
+    pub program_address: Option<String>,
+}
+
+/// Fixtures written for a generated decoder, used to render its tests.
+#[derive(Default)]
+pub struct Fixtures<'a> {
+    pub accounts: Vec<&'a AccountData>,
+    pub instructions: Vec<&'a InstructionData>,
+}
+
+/// Fetches one sample account of each type and one recent instruction of each
+/// kind from chain and writes them to `fixtures_dir`, in the format read by
+/// `carbon-test-utils`.
+///
+/// Types without an on-chain sample are reported and left without a test.
+pub fn write_fixtures<'a>(
+    options: &FixtureOptions,
+    program_address: &str,
+    accounts: &'a [AccountData],
+    instructions: &'a [InstructionData],
+    fixtures_dir: &str,
+) -> Result<Fixtures<'a>> {
+    let program_id =
+        &Pubkey::from_str(program_address).context("Couldn't parse program address from string")?;
+
+    fs::create_dir_all(fixtures_dir).context("Failed to create fixtures directory")?;
+
+    let client =
+        RpcClient::new_with_commitment(options.rpc_url.clone(), CommitmentConfig::confirmed());
+
+    println!(
+        "Fetching fixtures for program: {} from {}",
+        program_id, options.rpc_url
+    );
+
+    let mut fixtures = Fixtures {
+        accounts: Vec::new(),
+        instructions: Vec::new(),
+    };
+
+    for account in accounts {
+        match fetch_account_fixture(&client, program_id, account)? {
+            Some(fixture) => {
+                let filename = format!("{}/{}_account.json", fixtures_dir, account.module_name);
+                fs::write(&filename, serde_json::to_string_pretty(&fixture)?)
+                    .context("Failed to write account fixture")?;
+                println!("Generated {}", filename);
+                fixtures.accounts.push(account);
+            }
+            None => println!(
+                "No on-chain sample found for account {}",
+                account.struct_name
+            ),
+        }
+    }
+
+    let mut missing: Vec<&InstructionData> = instructions.iter().collect();
+    for signature in fetch_program_signatures(&client, program_id)? {
+        if missing.is_empty() {
+            break;
+        }
+
+        for instruction in fetch_program_instructions(&client, program_id, &signature) {
+            let Some(position) = missing.iter().position(|missing| {
+                decode_discriminator(&missing.discriminator)
+                    .is_some_and(|discriminator| instruction.data.starts_with(&discriminator))
+            }) else {
+                continue;
+            };
+            let ix = missing.remove(position);
+
+            let fixture = json!({
+                "signature": signature.to_string(),
+                "program_id": program_id.to_string(),
+                "accounts": instruction
+                    .accounts
+                    .iter()
+                    .map(|(pubkey, is_signer, is_writable)| json!({
+                        "pubkey": pubkey.to_string(),
+                        "is_signer": is_signer,
+                        "is_writable": is_writable,
+                    }))
+                    .collect::<Vec<_>>(),
+                "data": hex::encode(&instruction.data),
+            });
+            let filename = format!("{}/{}_ix.json", fixtures_dir, ix.module_name);
+            fs::write(&filename, serde_json::to_string_pretty(&fixture)?)
+                .context("Failed to write instruction fixture")?;
+            println!("Generated {}", filename);
+            fixtures.instructions.push(ix);
+        }
+    }
+
+    for ix in missing {
+        println!("No recent sample found for instruction {}", ix.struct_name);
+    }
+
+    Ok(fixtures)
+}
+
+/// An instruction invoking the program, as found in a fetched transaction.
+struct SampleInstruction {
+    accounts: Vec<(Pubkey, bool, bool)>,
+    data: Vec<u8>,
+}
+
+fn fetch_account_fixture(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    account: &AccountData,
+) -> Result<Option<serde_json::Value>> {
+    let Some(discriminator) = decode_discriminator(&account.discriminator) else {
+        return Ok(None);
+    };
+
+    // Only fetch the addresses of matching accounts, the sample itself is
+    // fetched separately.
+    let addresses = client
+        .get_program_accounts_with_config(
+            program_id,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    0,
+                    discriminator,
+                ))]),
+                account_config: RpcAccountInfoConfig {
+                    data_slice: Some(UiDataSliceConfig {
+                        offset: 0,
+                        length: 0,
+                    }),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .with_context(|| format!("Couldn't fetch {} accounts", account.struct_name))?;
+
+    let Some((address, _)) = addresses.first() else {
+        return Ok(None);
+    };
+
+    let sample = client
+        .get_account(address)
+        .with_context(|| format!("Couldn't fetch account {address}"))?;
+
+    Ok(Some(json!({
+        "lamports": sample.lamports,
+        "data": STANDARD.encode(&sample.data),
+        "owner": sample.owner.to_string(),
+        "executable": sample.executable,
+        "rent_epoch": sample.rent_epoch,
+    })))
+}
+
+/// Returns the signatures of the program's recent successful transactions,
+/// newest first.
+fn fetch_program_signatures(client: &RpcClient, program_id: &Pubkey) -> Result<Vec<Signature>> {
+    let signatures = client
+        .get_signatures_for_address_with_config(
+            program_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before: None,
+                until: None,
+                limit: Some(TRANSACTION_SAMPLE_LIMIT),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .context("Couldn't fetch program signatures")?;
+
+    signatures
+        .iter()
+        .filter(|status| status.err.is_none())
+        .map(|status| Signature::from_str(&status.signature).map_err(Into::into))
+        .collect()
+}
+
+/// Returns the top-level instructions of the program in the given
+/// transaction. Transactions that can't be fetched or decoded are skipped.
+fn fetch_program_instructions(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    signature: &Signature,
+) -> Vec<SampleInstruction> {
+    let Ok(fetched) = client.get_transaction_with_config(
+        signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        },
+    ) else {
+        return Vec::new();
+    };
+
+    let Some(transaction) = fetched.transaction.transaction.decode() else {
+        return Vec::new();
+    };
+    let Some(meta) = fetched.transaction.meta else {
+        return Vec::new();
+    };
+
+    let loaded_addresses = meta.loaded_addresses.unwrap_or_else(|| UiLoadedAddresses {
+        writable: vec![],
+        readonly: vec![],
+    });
+
+    let message = &transaction.message;
+    let static_keys = message.static_account_keys();
+    let account_keys: Vec<(Pubkey, bool, bool)> = static_keys
+        .iter()
+        .enumerate()
+        .map(|(index, key)| {
+            (
+                *key,
+                message.is_signer(index),
+                message.is_maybe_writable(index, None),
+            )
+        })
+        .chain(
+            loaded_addresses
+                .writable
+                .iter()
+                .filter_map(|key| Pubkey::from_str(key).ok())
+                .map(|key| (key, false, true)),
+        )
+        .chain(
+            loaded_addresses
+                .readonly
+                .iter()
+                .filter_map(|key| Pubkey::from_str(key).ok())
+                .map(|key| (key, false, false)),
+        )
+        .collect();
+
+    message
+        .instructions()
+        .iter()
+        .filter(|instruction| {
+            static_keys.get(instruction.program_id_index as usize) == Some(program_id)
+        })
+        .map(|instruction| SampleInstruction {
+            accounts: instruction
+                .accounts
+                .iter()
+                .filter_map(|index| account_keys.get(*index as usize).copied())
+                .collect(),
+            data: instruction.data.clone(),
+        })
+        .collect()
+}
+
+/// Parses a `0x`-prefixed discriminator as generated for account and
+/// instruction data.
+fn decode_discriminator(discriminator: &str) -> Option<Vec<u8>> {
+    hex::decode(discriminator.trim_start_matches("0x")).ok()
+}
@@ -0,0 +1,179 @@
+use {
+    anyhow::{bail, Context, Result},
+    carbon_core::{account::AccountDecoder, instruction::InstructionDecoder},
+    carbon_plugin::PluginDecoder,
+    carbon_system_program_decoder::SystemProgramDecoder,
+    carbon_token_program_decoder::TokenProgramDecoder,
+    solana_account::Account,
+    solana_instruction::Instruction,
+    std::{
+        fs,
+        hint::black_box,
+        path::Path,
+        time::{Duration, Instant},
+    },
+};
+
+/// Recorded accounts and instructions, as written to the `tests/fixtures`
+/// directory of generated decoders.
+struct Fixtures {
+    accounts: Vec<Account>,
+    instructions: Vec<Instruction>,
+}
+
+impl Fixtures {
+    /// Reads the `*_account.json` and `*_ix.json` files of a directory.
+    fn read(dir: &Path) -> Result<Self> {
+        let mut paths = fs::read_dir(dir)
+            .with_context(|| format!("Couldn't read fixtures directory {}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.sort();
+
+        let mut fixtures = Fixtures {
+            accounts: Vec::new(),
+            instructions: Vec::new(),
+        };
+        for path in paths {
+            let file_name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            if file_name.ends_with("_account.json") {
+                fixtures.accounts.push(
+                    carbon_test_utils::read_account(&path)
+                        .with_context(|| format!("Invalid fixture {}", path.display()))?,
+                );
+            } else if file_name.ends_with("_ix.json") {
+                fixtures.instructions.push(
+                    carbon_test_utils::read_instruction(&path)
+                        .with_context(|| format!("Invalid fixture {}", path.display()))?,
+                );
+            }
+        }
+
+        if fixtures.accounts.is_empty() && fixtures.instructions.is_empty() {
+            bail!(
+                "No `*_account.json` or `*_ix.json` fixtures in {}",
+                dir.display()
+            );
+        }
+
+        Ok(fixtures)
+    }
+}
+
+/// The throughput of decoding a set of fixtures.
+struct Measurement {
+    fixtures: usize,
+    decoded: usize,
+    bytes: usize,
+    iterations: u32,
+    elapsed: Duration,
+}
+
+impl Measurement {
+    fn print(&self, kind: &str) {
+        let seconds = self.elapsed.as_secs_f64();
+        let decodes = f64::from(self.iterations) * self.fixtures as f64;
+        let bytes = f64::from(self.iterations) * self.bytes as f64;
+        println!(
+            "{}: {} fixtures, {} decoded, {:.0} ns/decode, {:.0} decodes/s, {:.1} MiB/s",
+            kind,
+            self.fixtures,
+            self.decoded,
+            seconds * 1e9 / decodes,
+            decodes / seconds,
+            bytes / seconds / (1024.0 * 1024.0),
+        );
+        if self.decoded < self.fixtures {
+            println!(
+                "  warning: {} fixtures weren't decoded",
+                self.fixtures - self.decoded
+            );
+        }
+    }
+}
+
+/// Measures the account and instruction decode throughput of a decoder
+/// against recorded fixtures.
+///
+/// `decoder` is `system-program`, `token-program`, or the path to a
+/// WebAssembly plugin. Build the CLI in release mode for meaningful numbers,
+/// and compare them across releases of the decoder to catch regressions.
+pub fn bench(decoder: String, fixtures: String, iterations: u32) -> Result<()> {
+    if iterations == 0 {
+        bail!("The number of iterations must be positive");
+    }
+
+    let fixtures = Fixtures::read(Path::new(&fixtures))?;
+    println!(
+        "Benchmarking {} over {} account and {} instruction fixtures, {} iterations",
+        decoder,
+        fixtures.accounts.len(),
+        fixtures.instructions.len(),
+        iterations
+    );
+
+    match decoder.as_str() {
+        "system-program" => bench_decoder(&SystemProgramDecoder, &fixtures, iterations),
+        "token-program" => bench_decoder(&TokenProgramDecoder, &fixtures, iterations),
+        path if path.ends_with(".wasm") => {
+            bench_decoder(&PluginDecoder::wasm(path)?, &fixtures, iterations)
+        }
+        other => bail!(
+            "Unknown decoder `{}`, expected `system-program`, `token-program` or the path to a WebAssembly plugin",
+            other
+        ),
+    }
+
+    Ok(())
+}
+
+fn bench_decoder<D>(decoder: &D, fixtures: &Fixtures, iterations: u32)
+where
+    D: for<'a> AccountDecoder<'a> + for<'a> InstructionDecoder<'a>,
+{
+    if !fixtures.accounts.is_empty() {
+        measure(
+            &fixtures.accounts,
+            |account| account.data.len(),
+            |account| decoder.decode_account(account).is_some(),
+            iterations,
+        )
+        .print("accounts");
+    }
+    if !fixtures.instructions.is_empty() {
+        measure(
+            &fixtures.instructions,
+            |instruction| instruction.data.len(),
+            |instruction| decoder.decode_instruction(instruction).is_some(),
+            iterations,
+        )
+        .print("instructions");
+    }
+}
+
+fn measure<T>(
+    fixtures: &[T],
+    size: impl Fn(&T) -> usize,
+    decode: impl Fn(&T) -> bool,
+    iterations: u32,
+) -> Measurement {
+    let decoded = fixtures.iter().filter(|fixture| decode(fixture)).count();
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        for fixture in fixtures {
+            black_box(decode(black_box(fixture)));
+        }
+    }
+
+    Measurement {
+        fixtures: fixtures.len(),
+        decoded,
+        bytes: fixtures.iter().map(size).sum(),
+        iterations,
+        elapsed: start.elapsed(),
+    }
+}
@@ -9,8 +9,9 @@ use {
             utils::{parse_event_hints, read_codama_idl},
         },
         instructions::{InstructionsModTemplate, InstructionsStructTemplate},
-        types::TypeStructTemplate,
-        util::is_big_array,
+        semver::{keep_variant_order, EnumVariant},
+        types::{TypeData, TypeStructTemplate},
+        util::decoder_cargo_toml,
     },
     anyhow::{bail, Result},
     askama::Template,
@@ -23,6 +24,7 @@ pub fn parse_codama(
     output: String,
     as_crate: bool,
     event_hints: Option<String>,
+    serde_feature: bool,
 ) -> Result<()> {
     let (accounts_data, instructions_data, types_data, events_data, program_name) =
         match read_codama_idl(&path) {
@@ -75,20 +77,39 @@ pub fn parse_codama(
 
     fs::create_dir_all(&src_dir).expect("Failed to create src directory");
 
-    let needs_big_array = types_data.iter().any(|type_data| {
-        type_data.fields.iter().any(|field| {
-            field.rust_type.starts_with("[")
-                && field.rust_type.ends_with("]")
-                && is_big_array(&field.rust_type)
-        })
+    // Keep the variant order of a previous generation stable
+    let accounts_mod_filename = format!("{}/accounts/mod.rs", src_dir);
+    let instructions_mod_filename = format!("{}/instructions/mod.rs", src_dir);
+
+    let accounts_data = keep_variant_order(&accounts_mod_filename, accounts_data, |account| {
+        &account.struct_name
     });
+    let instruction_variants = keep_variant_order(
+        &instructions_mod_filename,
+        instructions_data
+            .iter()
+            .map(|instruction| EnumVariant::new(&instruction.struct_name, &instruction.module_name))
+            .chain(
+                events_data
+                    .iter()
+                    .map(|event| EnumVariant::new(&event.struct_name, &event.module_name)),
+            )
+            .collect(),
+        |variant| &variant.struct_name,
+    );
+
+    let needs_big_array = types_data.iter().any(TypeData::has_big_array);
 
     // Generate types
     let types_dir = format!("{}/types", src_dir);
     fs::create_dir_all(&types_dir).expect("Failed to create types directory");
 
     for type_data in &types_data {
-        let template = TypeStructTemplate { type_data };
+        let template = TypeStructTemplate {
+            type_data,
+            serde_feature,
+            zero_copy: false,
+        };
         let rendered = template
             .render()
             .expect("Failed to render type struct template");
@@ -119,7 +140,11 @@ pub fn parse_codama(
     fs::create_dir_all(&accounts_dir).expect("Failed to create accounts directory");
 
     for account in &accounts_data {
-        let template = AccountsStructTemplate { account };
+        let template = AccountsStructTemplate {
+            account,
+            serde_feature,
+            zero_copy: false,
+        };
         let rendered = template
             .render()
             .expect("Failed to render account struct template");
@@ -132,11 +157,11 @@ pub fn parse_codama(
         accounts: &accounts_data,
         decoder_name: decoder_name.clone(),
         program_struct_name: program_struct_name.clone(),
+        fixtures: &[],
     };
     let accounts_mod_rendered = accounts_mod_template
         .render()
         .expect("Failed to render accounts mod template");
-    let accounts_mod_filename = format!("{}/mod.rs", accounts_dir);
 
     fs::write(&accounts_mod_filename, accounts_mod_rendered)
         .expect("Failed to write accounts mod file");
@@ -148,7 +173,10 @@ pub fn parse_codama(
     fs::create_dir_all(&instructions_dir).expect("Failed to create instructions directory");
 
     for instruction in &instructions_data {
-        let template = InstructionsStructTemplate { instruction };
+        let template = InstructionsStructTemplate {
+            instruction,
+            serde_feature,
+        };
         let rendered = template
             .render()
             .expect("Failed to render instruction struct template");
@@ -158,7 +186,10 @@ pub fn parse_codama(
     }
 
     for event in &events_data {
-        let template = EventsStructTemplate { event };
+        let template = EventsStructTemplate {
+            event,
+            serde_feature,
+        };
         let rendered = template
             .render()
             .expect("Failed to render event struct template");
@@ -168,15 +199,16 @@ pub fn parse_codama(
     }
 
     let instructions_mod_template = InstructionsModTemplate {
-        instructions: &instructions_data,
+        variants: &instruction_variants,
         decoder_name: decoder_name.clone(),
         program_instruction_enum: program_instruction_enum.clone(),
-        events: &events_data,
+        serde_feature,
+        fixtures: &[],
+        program_id: false,
     };
     let instructions_mod_rendered = instructions_mod_template
         .render()
         .expect("Failed to render instructions mod template");
-    let instructions_mod_filename = format!("{}/mod.rs", instructions_dir);
 
     fs::write(&instructions_mod_filename, instructions_mod_rendered)
         .expect("Failed to write instructions mod file");
@@ -192,31 +224,12 @@ pub fn parse_codama(
         fs::write(&lib_rs_filename, lib_rs_content).expect("Failed to write lib.rs file");
         println!("Generated {}", lib_rs_filename);
 
-        let cargo_toml_content = format!(
-            r#"[package]
-name = "{decoder_name_kebab}-decoder"
-version = "0.8.1"
-edition = {{ workspace = true }}
-
-[lib]
-crate-type = ["rlib"]
-
-[dependencies]
-carbon-core = {{ workspace = true }}
-carbon-proc-macros = {{ workspace = true }}
-carbon-macros = {{ workspace = true }}
-solana-account = {{ workspace = true }}
-solana-instruction = {{ workspace = true }}
-solana-pubkey = {{ workspace = true }}
-serde = {{ workspace = true }}
-{big_array}
-"#,
-            decoder_name_kebab = decoder_name_kebab,
-            big_array = if needs_big_array {
-                "serde-big-array = { workspace = true }"
-            } else {
-                ""
-            }
+        let cargo_toml_content = decoder_cargo_toml(
+            &decoder_name_kebab,
+            needs_big_array,
+            serde_feature,
+            false,
+            false,
         );
         let cargo_toml_filename = format!("{}/Cargo.toml", crate_dir);
         fs::write(&cargo_toml_filename, cargo_toml_content)
@@ -49,6 +49,7 @@ pub fn process_codama_accounts(program: &ProgramNode) -> Vec<AccountData> {
             discriminator,
             fields,
             requires_imports,
+            zero_copy_repr: None,
         });
     }
 
@@ -234,9 +235,11 @@ pub fn process_codama_defined_types(
         } else {
             types_data.push(TypeData {
                 name,
+                generics: String::new(),
                 fields,
                 kind,
                 requires_imports,
+                zero_copy_repr: None,
             });
         }
     }
@@ -0,0 +1,492 @@
+use {
+    crate::{commands::Datasource, config::PipelineConfig},
+    anyhow::{bail, Result},
+    serde::Deserialize,
+    solana_client::rpc_client::RpcClient,
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::BTreeMap,
+        fs,
+        net::{TcpStream, ToSocketAddrs},
+        path::{Path, PathBuf},
+        str::FromStr,
+        time::Duration,
+    },
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug)]
+struct Check {
+    status: CheckStatus,
+    name: String,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Ok,
+            name: name.into(),
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Warn,
+            name: name.into(),
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Fail,
+            name: name.into(),
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn print(&self) {
+        let label = match self.status {
+            CheckStatus::Ok => "[ok]  ",
+            CheckStatus::Warn => "[warn]",
+            CheckStatus::Fail => "[fail]",
+        };
+        println!("{} {}: {}", label, self.name, self.detail);
+        if let Some(fix) = &self.fix {
+            println!("       fix: {}", fix);
+        }
+    }
+}
+
+pub fn doctor(config_path: String) -> Result<()> {
+    println!("Checking {}", config_path);
+
+    let config = PipelineConfig::read(&config_path)?;
+    let mut checks = Vec::new();
+
+    checks.extend(check_datasource(&config));
+
+    let rpc_client = rpc_url(&config).map(|url| {
+        RpcClient::new_with_timeout_and_commitment(
+            url,
+            CONNECT_TIMEOUT,
+            CommitmentConfig::confirmed(),
+        )
+    });
+
+    match &rpc_client {
+        Some(rpc_client) => checks.push(check_rpc(rpc_client)),
+        None => checks.push(Check::warn(
+            "rpc",
+            "no RPC endpoint configured",
+            "set `datasource.rpc_url` to enable on-chain filter checks",
+        )),
+    }
+
+    checks.extend(check_filters(&config, rpc_client.as_ref()));
+    checks.extend(check_versions(&config, Path::new(&config_path)));
+
+    for check in &checks {
+        check.print();
+    }
+
+    let failures = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+    let warnings = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Warn)
+        .count();
+
+    println!(
+        "\n{} checks, {} failed, {} warnings",
+        checks.len(),
+        failures,
+        warnings
+    );
+
+    if failures > 0 {
+        bail!("{failures} check(s) failed");
+    }
+
+    Ok(())
+}
+
+/// Returns the RPC URL used for diagnostics, deriving the Helius RPC URL from
+/// the API key when no explicit URL is configured.
+fn rpc_url(config: &PipelineConfig) -> Option<String> {
+    let datasource = &config.datasource;
+    datasource.rpc_url.clone().or_else(|| {
+        let kind = Datasource::from_str(&datasource.kind).ok()?;
+        let api_key = datasource.api_key.as_ref()?;
+        (kind == Datasource::HeliusAtlasWs)
+            .then(|| format!("https://mainnet.helius-rpc.com/?api-key={api_key}"))
+    })
+}
+
+fn check_datasource(config: &PipelineConfig) -> Vec<Check> {
+    let datasource = &config.datasource;
+
+    let kind = match Datasource::from_str(&datasource.kind) {
+        Ok(kind) => kind,
+        Err(_) => {
+            return vec![Check::fail(
+                "datasource",
+                format!("unknown datasource kind '{}'", datasource.kind),
+                "use one of: helius-atlas-ws, rpc-block-subscribe, rpc-program-subscribe, \
+                 rpc-transaction-crawler, yellowstone-grpc",
+            )]
+        }
+    };
+
+    let mut checks = vec![Check::ok("datasource", kind.to_string())];
+
+    match kind {
+        Datasource::RpcBlockSubscribe | Datasource::RpcProgramSubscribe => {
+            checks.push(check_endpoint(
+                "websocket",
+                datasource.ws_url.as_deref(),
+                "ws_url",
+            ));
+        }
+        Datasource::RpcTransactionCrawler => {
+            if datasource.rpc_url.is_none() {
+                checks.push(Check::fail(
+                    "rpc",
+                    "the transaction crawler requires an RPC endpoint",
+                    "set `datasource.rpc_url`",
+                ));
+            }
+        }
+        Datasource::YellowstoneGrpc => {
+            checks.push(check_endpoint(
+                "geyser",
+                datasource.geyser_url.as_deref(),
+                "geyser_url",
+            ));
+            if datasource.x_token.is_none() {
+                checks.push(Check::warn(
+                    "geyser auth",
+                    "no x_token configured",
+                    "most Yellowstone providers require a token; set `datasource.x_token`, \
+                     e.g. to \"${X_TOKEN}\"",
+                ));
+            }
+        }
+        Datasource::HeliusAtlasWs => {
+            if datasource.api_key.is_none() {
+                checks.push(Check::fail(
+                    "helius auth",
+                    "no api_key configured",
+                    "set `datasource.api_key`, e.g. to \"${HELIUS_API_KEY}\"",
+                ));
+            }
+        }
+    }
+
+    checks
+}
+
+/// Checks that the endpoint's host resolves and accepts TCP connections.
+fn check_endpoint(name: &str, url: Option<&str>, field: &str) -> Check {
+    let Some(url) = url else {
+        return Check::fail(
+            name,
+            "no endpoint configured",
+            format!("set `datasource.{field}`"),
+        );
+    };
+
+    let Some((host, port)) = host_and_port(url) else {
+        return Check::fail(
+            name,
+            format!("couldn't parse '{url}'"),
+            "use a full URL including the scheme, e.g. wss://host:port",
+        );
+    };
+
+    let address = match (host.as_str(), port)
+        .to_socket_addrs()
+        .map(|mut addresses| addresses.next())
+    {
+        Ok(Some(address)) => address,
+        Ok(None) | Err(_) => {
+            return Check::fail(
+                name,
+                format!("couldn't resolve host '{host}'"),
+                "check the hostname and your DNS configuration",
+            )
+        }
+    };
+
+    match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+        Ok(_) => Check::ok(name, format!("{host}:{port} is reachable")),
+        Err(error) => Check::fail(
+            name,
+            format!("couldn't connect to {host}:{port}: {error}"),
+            "check the port, firewall rules and that the endpoint is up",
+        ),
+    }
+}
+
+fn host_and_port(url: &str) -> Option<(String, u16)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?']).next()?;
+    let authority = authority.rsplit('@').next()?;
+
+    let default_port = match scheme {
+        "http" | "ws" => 80,
+        "https" | "wss" => 443,
+        _ => return None,
+    };
+
+    match authority.rsplit_once(':') {
+        Some((host, port)) => Some((host.to_string(), port.parse().ok()?)),
+        None => Some((authority.to_string(), default_port)),
+    }
+}
+
+fn check_rpc(rpc_client: &RpcClient) -> Check {
+    match rpc_client.get_version() {
+        Ok(version) => Check::ok(
+            "rpc",
+            format!("reachable, solana-core {}", version.solana_core),
+        ),
+        Err(error) => {
+            let message = error.to_string();
+            if message.contains("401") || message.contains("403") {
+                Check::fail(
+                    "rpc",
+                    format!("authentication rejected: {message}"),
+                    "check the API key or token embedded in `datasource.rpc_url`",
+                )
+            } else if message.contains("429") {
+                Check::warn(
+                    "rpc",
+                    format!("rate limited: {message}"),
+                    "use a dedicated RPC endpoint instead of a public one",
+                )
+            } else {
+                Check::fail(
+                    "rpc",
+                    format!("unreachable: {message}"),
+                    "check `datasource.rpc_url` and your network connection",
+                )
+            }
+        }
+    }
+}
+
+fn check_filters(config: &PipelineConfig, rpc_client: Option<&RpcClient>) -> Vec<Check> {
+    if config.filters.program_ids.is_empty() {
+        return vec![Check::warn(
+            "filters",
+            "no program ids configured",
+            "set `filters.program_ids` to avoid processing the whole firehose",
+        )];
+    }
+
+    config
+        .filters
+        .program_ids
+        .iter()
+        .map(|program_id| {
+            let name = format!("program {program_id}");
+
+            let Ok(pubkey) = Pubkey::from_str(program_id) else {
+                return Check::fail(name, "not a valid base58 pubkey", "fix the program id");
+            };
+
+            let Some(rpc_client) = rpc_client else {
+                return Check::ok(name, "valid pubkey (not checked on chain)");
+            };
+
+            match rpc_client.get_account_with_commitment(&pubkey, CommitmentConfig::confirmed()) {
+                Ok(response) => match response.value {
+                    Some(account) if account.executable => {
+                        Check::ok(name, format!("executable, owned by {}", account.owner))
+                    }
+                    Some(_) => Check::fail(
+                        name,
+                        "account exists but is not a program",
+                        "make sure you used the program id and not a program data or state \
+                         account",
+                    ),
+                    None => Check::fail(
+                        name,
+                        "account doesn't exist on this cluster",
+                        "check that `datasource.rpc_url` points to the cluster the program is \
+                         deployed on",
+                    ),
+                },
+                Err(error) => Check::warn(
+                    name,
+                    format!("couldn't fetch account: {error}"),
+                    "retry once the RPC endpoint is reachable",
+                ),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoLock {
+    #[serde(default)]
+    package: Vec<CargoLockPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoLockPackage {
+    name: String,
+    version: String,
+}
+
+/// Finds the `Cargo.lock` belonging to the given manifest, walking up to the
+/// workspace root if needed.
+fn find_lockfile(manifest: &Path) -> Option<PathBuf> {
+    manifest
+        .parent()
+        .into_iter()
+        .flat_map(Path::ancestors)
+        .map(|directory| directory.join("Cargo.lock"))
+        .find(|lockfile| lockfile.exists())
+}
+
+/// Returns the compatibility range of a version as `(major, minor)`, where
+/// the minor version only matters for `0.x` versions.
+fn compatibility_range(version: &str) -> (u64, u64) {
+    let mut parts = version
+        .split('.')
+        .map(|part| part.parse::<u64>().unwrap_or(0));
+    match (parts.next().unwrap_or(0), parts.next().unwrap_or(0)) {
+        (0, minor) => (0, minor),
+        (major, _) => (major, 0),
+    }
+}
+
+fn format_range((major, minor): (u64, u64)) -> String {
+    if major == 0 {
+        format!("0.{minor}")
+    } else {
+        format!("{major}.x")
+    }
+}
+
+fn check_versions(config: &PipelineConfig, config_path: &Path) -> Vec<Check> {
+    let manifest = match &config.project.manifest {
+        Some(manifest) => config_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(manifest),
+        None => config_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("Cargo.toml"),
+    };
+
+    let Some(lockfile) = find_lockfile(&manifest) else {
+        return vec![Check::warn(
+            "versions",
+            format!("no Cargo.lock found for {}", manifest.display()),
+            "run `cargo generate-lockfile` or set `project.manifest`",
+        )];
+    };
+
+    let lock: CargoLock = match fs::read_to_string(&lockfile)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| toml::from_str::<CargoLock>(&content).map_err(Into::into))
+    {
+        Ok(lock) => lock,
+        Err(error) => {
+            return vec![Check::warn(
+                "versions",
+                format!("couldn't read {}: {error}", lockfile.display()),
+                "make sure the lockfile is valid",
+            )]
+        }
+    };
+
+    let mut ranges: BTreeMap<(u64, u64), Vec<String>> = BTreeMap::new();
+    for package in lock
+        .package
+        .iter()
+        .filter(|package| package.name.starts_with("carbon-"))
+    {
+        ranges
+            .entry(compatibility_range(&package.version))
+            .or_default()
+            .push(format!("{} {}", package.name, package.version));
+    }
+
+    let cli_version = env!("CARGO_PKG_VERSION");
+    let mut checks = Vec::new();
+
+    match ranges.len() {
+        0 => checks.push(Check::warn(
+            "versions",
+            format!("no carbon crates found in {}", lockfile.display()),
+            "set `project.manifest` to your pipeline's Cargo.toml",
+        )),
+        1 => {
+            let (range, packages) = ranges.iter().next().expect("one range");
+            checks.push(Check::ok(
+                "versions",
+                format!(
+                    "{} carbon crates on {}",
+                    packages.len(),
+                    format_range(*range)
+                ),
+            ));
+
+            if *range != compatibility_range(cli_version) {
+                checks.push(Check::warn(
+                    "cli version",
+                    format!(
+                        "carbon-cli {cli_version} differs from the project's crates ({})",
+                        format_range(*range)
+                    ),
+                    "regenerate decoders with a carbon-cli matching your crates",
+                ));
+            }
+        }
+        _ => {
+            let newest = *ranges.keys().last().expect("several ranges");
+            let mismatched = ranges
+                .iter()
+                .filter(|(range, _)| **range != newest)
+                .flat_map(|(_, packages)| packages.iter().cloned())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            checks.push(Check::fail(
+                "versions",
+                format!(
+                    "incompatible carbon crate versions: {mismatched} (newest {})",
+                    format_range(newest)
+                ),
+                format!(
+                    "align every carbon-* dependency to {} and run `cargo update`",
+                    format_range(newest)
+                ),
+            ));
+        }
+    }
+
+    checks
+}
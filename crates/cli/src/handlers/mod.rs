@@ -1,8 +1,20 @@
 mod parse;
 pub use parse::*;
 
+mod doctor;
+pub use doctor::*;
+
+mod run;
+pub use run::*;
+
+mod semver_check;
+pub use semver_check::*;
+
 mod codama;
 pub use codama::*;
 
 mod process_pda_idl;
 pub use process_pda_idl::*;
+
+mod bench;
+pub use bench::*;
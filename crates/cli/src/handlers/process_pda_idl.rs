@@ -1,6 +1,6 @@
 use {
-    crate::{commands::Url, handlers},
-    anyhow::{Context, Result},
+    crate::{commands::Url, fixtures::FixtureOptions, handlers, output::WriteOptions},
+    anyhow::{bail, Context, Result},
     borsh::BorshDeserialize,
     flate2::read::ZlibDecoder,
     solana_client::rpc_client::RpcClient,
@@ -9,17 +9,19 @@ use {
     std::{fs, io::prelude::*, path::Path, str::FromStr},
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn process_pda_idl(
     program_address: String,
     url: &Url,
     output: String,
     as_crate: bool,
+    serde_feature: bool,
+    with_tests: bool,
+    emit_proto: bool,
+    zero_copy: bool,
+    write_options: WriteOptions,
 ) -> Result<()> {
-    let rpc_url = match url {
-        Url::Mainnet => "https://api.mainnet-beta.solana.com",
-        Url::Devnet => "https://api.devnet.solana.com",
-        Url::CustomRpc(custom_url) => custom_url,
-    };
+    let rpc_url = url.rpc_url();
 
     let program_address_pubkey =
         Pubkey::from_str(&program_address).context("Couldn't parse program address from string")?;
@@ -33,40 +35,84 @@ pub fn process_pda_idl(
         .map(|idl| serde_json::to_string_pretty(&idl))
         .context("Couldn't fetch Program Idl")??;
 
-    let idl_path = format!("./{}_idl.json", program_address);
+    let idl_path = std::env::temp_dir().join(format!("{}_idl.json", program_address));
 
     fs::write(&idl_path, idl)?;
 
-    handlers::parse(idl_path.clone(), output, as_crate).context("Couldn't parse IDL")?;
+    let parse_result = handlers::parse(
+        idl_path.to_string_lossy().into_owned(),
+        output,
+        as_crate,
+        serde_feature,
+        with_tests.then(|| FixtureOptions {
+            rpc_url: rpc_url.to_string(),
+            program_address: Some(program_address),
+        }),
+        emit_proto,
+        zero_copy,
+        write_options,
+    )
+    .context("Couldn't parse IDL");
 
     // Clean up: Delete the IDL file after parsing
     if Path::new(&idl_path).exists() {
         fs::remove_file(&idl_path).context("Failed to delete temporary IDL file")?;
     }
 
-    Ok(())
+    parse_result
+}
+
+/// Anchor account discriminator (8 bytes) + authority (32 bytes) + data length
+/// (4 bytes).
+const IDL_ACCOUNT_HEADER_LEN: usize = 44;
+
+/// Derives the address of the Anchor IDL account for the given program.
+pub fn idl_address(program_address: &Pubkey) -> Result<Pubkey> {
+    let program_signer = Pubkey::find_program_address(&[], program_address).0;
+    Pubkey::create_with_seed(&program_signer, "anchor:idl", program_address)
+        .context("Seed is always valid")
 }
 
 fn fetch_idl(program_address: Pubkey, rpc_url: String) -> Result<serde_json::Value> {
     let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
 
-    let mut account = client.get_account(&program_address)?;
+    let mut account = client
+        .get_account(&program_address)
+        .with_context(|| format!("Program account {program_address} not found"))?;
     if account.executable {
-        let program_signer = Pubkey::find_program_address(&[], &program_address).0;
-        let idl_address = Pubkey::create_with_seed(&program_signer, "anchor:idl", &program_address)
-            .context("Seed is always valid")?;
-        account = client.get_account(&idl_address)?;
+        let idl_address = idl_address(&program_address)?;
+        account = client.get_account(&idl_address).with_context(|| {
+            format!(
+                "IDL account {idl_address} not found, the program may not publish its IDL on-chain"
+            )
+        })?;
+    }
+
+    decode_idl_account_data(&account.data)
+}
+
+/// Inflates the zlib-compressed IDL stored in an Anchor IDL account.
+pub fn decode_idl_account_data(data: &[u8]) -> Result<serde_json::Value> {
+    if data.len() < IDL_ACCOUNT_HEADER_LEN {
+        bail!(
+            "IDL account data is too short: {} bytes, expected at least {}",
+            data.len(),
+            IDL_ACCOUNT_HEADER_LEN
+        );
     }
 
     // Cut off account discriminator.
-    let mut d: &[u8] = &account.data[8..];
+    let mut d: &[u8] = &data[8..];
     let idl_account: IdlAccount = BorshDeserialize::deserialize(&mut d)?;
 
     let compressed_len = idl_account.data_len as usize;
-    let compressed_bytes = &account.data[44..44 + compressed_len];
+    let compressed_bytes = data
+        .get(IDL_ACCOUNT_HEADER_LEN..IDL_ACCOUNT_HEADER_LEN + compressed_len)
+        .context("IDL account data length exceeds the account size")?;
     let mut z = ZlibDecoder::new(compressed_bytes);
     let mut s = Vec::new();
-    z.read_to_end(&mut s)?;
+    z.read_to_end(&mut s)
+        .context("Failed to inflate IDL account data")?;
     serde_json::from_slice(&s[..]).map_err(Into::into)
 }
 
@@ -0,0 +1,123 @@
+use {
+    anyhow::{Context, Result},
+    carbon_core::{
+        config::{ConfigOptions, ConfigRegistry, PipelineConfig},
+        error::{CarbonResult, Error},
+    },
+    carbon_file_replay_datasource::{FileReplayDatasource, ReplaySpeed},
+    carbon_log_metrics::LogMetrics,
+    carbon_plugin::PluginDecoder,
+    carbon_prometheus_metrics::PrometheusMetrics,
+    carbon_rpc_block_subscribe_datasource::{Filters, RpcBlockSubscribe},
+    carbon_system_program_decoder::SystemProgramDecoder,
+    carbon_token_program_decoder::TokenProgramDecoder,
+    solana_client::rpc_config::{RpcBlockSubscribeConfig, RpcBlockSubscribeFilter},
+    solana_commitment_config::{CommitmentConfig, CommitmentLevel},
+    std::str::FromStr,
+};
+
+/// Runs the pipeline described by a config file until it stops.
+///
+/// ```toml
+/// [[datasources]]
+/// type = "rpc-block-subscribe"
+/// ws_url = "${RPC_WS_URL}"
+/// mentions = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+/// commitment = "confirmed"
+///
+/// [[decoders]]
+/// type = "token-program"
+///
+/// [[decoders]]
+/// type = "plugin"
+/// path = "./plugins/my_program.wasm"
+///
+/// [[sinks]]
+/// name = "stdout"
+/// type = "log"
+///
+/// [[metrics]]
+/// type = "log"
+/// ```
+pub fn run(config: String) -> Result<()> {
+    env_logger::init();
+
+    let config = PipelineConfig::from_file(&config)?;
+    let pipeline = registry().build(&config)?;
+
+    tokio::runtime::Runtime::new()
+        .context("Couldn't start the runtime")?
+        .block_on(pipeline.run())?;
+
+    Ok(())
+}
+
+/// The components available to configs run by the CLI.
+fn registry() -> ConfigRegistry {
+    ConfigRegistry::new()
+        .datasource("rpc-block-subscribe", rpc_block_subscribe)
+        .datasource("file-replay", file_replay)
+        .instruction_decoder("system-program", |_| Ok(SystemProgramDecoder))
+        .instruction_decoder("token-program", |_| Ok(TokenProgramDecoder))
+        .account_decoder("plugin", plugin)
+        .instruction_decoder("plugin", plugin)
+        .metrics("log", |_| Ok(LogMetrics::new()))
+        .metrics("prometheus", |_| Ok(PrometheusMetrics::new()))
+}
+
+fn rpc_block_subscribe(options: &ConfigOptions) -> CarbonResult<RpcBlockSubscribe> {
+    let block_filter = match options.get::<String>("mentions")? {
+        Some(address) => RpcBlockSubscribeFilter::MentionsAccountOrProgram(address),
+        None => RpcBlockSubscribeFilter::All,
+    };
+    let commitment = options
+        .get::<String>("commitment")?
+        .map(|commitment| {
+            CommitmentLevel::from_str(&commitment)
+                .map(|commitment| CommitmentConfig { commitment })
+                .map_err(|_| Error::Custom(format!("Invalid commitment `{}`", commitment)))
+        })
+        .transpose()?;
+
+    Ok(RpcBlockSubscribe::new(
+        options.require("ws_url")?,
+        Filters::new(
+            block_filter,
+            Some(RpcBlockSubscribeConfig {
+                commitment,
+                max_supported_transaction_version: Some(0),
+                ..RpcBlockSubscribeConfig::default()
+            }),
+        ),
+    ))
+}
+
+fn file_replay(options: &ConfigOptions) -> CarbonResult<FileReplayDatasource> {
+    let mut paths = options.require::<Vec<String>>("paths")?.into_iter();
+    let first = paths
+        .next()
+        .ok_or_else(|| Error::Custom("Missing option `paths`".to_string()))?;
+    let speed = match options.get::<f64>("speed")? {
+        Some(multiplier) => ReplaySpeed::Multiplier(multiplier),
+        None => ReplaySpeed::Unthrottled,
+    };
+
+    Ok(paths
+        .fold(FileReplayDatasource::new(first), |datasource, path| {
+            datasource.file(path)
+        })
+        .speed(speed))
+}
+
+/// Loads a WebAssembly plugin, or a native plugin when `native = true`.
+fn plugin(options: &ConfigOptions) -> CarbonResult<PluginDecoder> {
+    let path = options.require::<String>("path")?;
+
+    if options.get::<bool>("native")?.unwrap_or(false) {
+        // SAFETY: Native plugins are trusted by the operator listing them in
+        // the config, as documented.
+        unsafe { PluginDecoder::native(path) }
+    } else {
+        PluginDecoder::wasm(path)
+    }
+}
@@ -0,0 +1,73 @@
+use {
+    crate::semver::{read_enum_variants, VariantChanges},
+    anyhow::{bail, Result},
+    std::path::Path,
+};
+
+/// Compares the account and instruction enums of two generations of a decoder
+/// and fails if the newer one requires a major version bump.
+pub fn semver_check(baseline: String, decoder: String) -> Result<()> {
+    let mut breaking = false;
+    let mut additive = false;
+
+    for module in ["accounts", "instructions"] {
+        let old = read_enum_variants(&mod_path(&baseline, module));
+        let new = read_enum_variants(&mod_path(&decoder, module));
+
+        let ((old_name, old_variants), (new_name, new_variants)) = match (old, new) {
+            (Some(old), Some(new)) => (old, new),
+            (Some((old_name, _)), None) => {
+                println!("{old_name}: enum was removed (breaking)");
+                breaking = true;
+                continue;
+            }
+            (None, Some((new_name, _))) => {
+                println!("{new_name}: enum was added (minor)");
+                additive = true;
+                continue;
+            }
+            (None, None) => continue,
+        };
+
+        if old_name != new_name {
+            println!("{old_name}: enum was renamed to {new_name} (breaking)");
+            breaking = true;
+        }
+
+        let changes = VariantChanges::between(&old_variants, &new_variants);
+        changes.print(&new_name);
+        breaking |= changes.is_breaking();
+        additive |= !changes.added.is_empty();
+    }
+
+    if breaking {
+        bail!("The decoder has breaking changes, bump its major version.");
+    }
+
+    if additive {
+        println!("The decoder has additive changes, bump its minor version.");
+    } else {
+        println!("No changes to the decoder enums.");
+    }
+
+    Ok(())
+}
+
+/// Returns the path of a generated `mod.rs`, for decoders generated either as
+/// a crate or as a module.
+fn mod_path(decoder_dir: &str, module: &str) -> String {
+    let crate_path = Path::new(decoder_dir)
+        .join("src")
+        .join(module)
+        .join("mod.rs");
+
+    if crate_path.exists() {
+        crate_path.to_string_lossy().to_string()
+    } else {
+        Path::new(decoder_dir)
+            .join(module)
+            .join("mod.rs")
+            .to_string_lossy()
+            .to_string()
+    }
+}
@@ -1,5 +1,5 @@
 use {
-    crate::legacy_idl::{LegacyIdlEnumFields, LegacyIdlType},
+    crate::legacy_idl::{IdlGenericParam, LegacyIdlEnumFields, LegacyIdlType},
     serde::{Deserialize, Serialize},
 };
 
@@ -125,6 +125,35 @@ pub struct IdlTypeDefinition {
     pub name: String,
     #[serde(rename = "type")]
     pub type_: IdlTypeDefinitionTy,
+    #[serde(default)]
+    pub generics: Vec<IdlGenericParam>,
+    /// `bytemuck` for the zero-copy accounts and types of Anchor programs.
+    #[serde(default)]
+    pub serialization: Option<String>,
+    #[serde(default)]
+    pub repr: Option<IdlRepr>,
+}
+
+impl IdlTypeDefinition {
+    /// Returns the `repr` of the type if it is a zero-copy type, read in place
+    /// by Anchor programs.
+    pub fn zero_copy_repr(&self) -> Option<String> {
+        if !self.serialization.as_deref()?.starts_with("bytemuck") {
+            return None;
+        }
+
+        match &self.repr {
+            Some(repr) if repr.packed => Some("C, packed".to_string()),
+            _ => Some("C".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdlRepr {
+    pub kind: String,
+    #[serde(default)]
+    pub packed: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -134,6 +163,8 @@ pub struct IdlTypeDefinitionTy {
     pub fields: Option<Vec<IdlTypeDefinitionField>>,
     #[serde(default)]
     pub variants: Option<Vec<IdlEnumVariant>>,
+    #[serde(default)]
+    pub alias: Option<LegacyIdlType>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1,8 +1,8 @@
 use {
     crate::{
-        events::EventData,
         idl::Idl,
         legacy_idl::{LegacyIdl, LegacyIdlInstructionDiscriminant},
+        semver::EnumVariant,
         util::idl_type_to_rust_type,
     },
     askama::Template,
@@ -41,15 +41,19 @@ pub struct AccountMetaData {
 #[template(path = "instructions_struct.askama", escape = "none", ext = ".askama")]
 pub struct InstructionsStructTemplate<'a> {
     pub instruction: &'a InstructionData,
+    pub serde_feature: bool,
 }
 
 #[derive(Template)]
 #[template(path = "instructions_mod.askama", escape = "none", ext = ".askama")]
 pub struct InstructionsModTemplate<'a> {
-    pub instructions: &'a Vec<InstructionData>,
+    pub variants: &'a [EnumVariant],
     pub decoder_name: String,
     pub program_instruction_enum: String,
-    pub events: &'a Vec<EventData>,
+    pub serde_feature: bool,
+    pub fixtures: &'a [&'a InstructionData],
+    /// Whether the parent module declares the `PROGRAM_ID` of the program.
+    pub program_id: bool,
 }
 
 pub fn legacy_process_instructions(idl: &LegacyIdl) -> Vec<InstructionData> {
@@ -17,6 +17,15 @@ pub struct LegacyIdl {
     pub events: Vec<LegacyIdlEvent>,
     #[serde(default)]
     pub errors: Vec<LegacyIdlError>,
+    #[serde(default)]
+    pub metadata: Option<LegacyIdlMetadata>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyIdlMetadata {
+    #[serde(default)]
+    pub address: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -80,7 +89,7 @@ pub struct LegacyIdlInstructionArgField {
 pub enum LegacyIdlType {
     Primitive(String),
     Array {
-        array: (Box<LegacyIdlType>, usize),
+        array: (Box<LegacyIdlType>, IdlArrayLen),
     },
     Vec {
         vec: Box<LegacyIdlType>,
@@ -94,6 +103,9 @@ pub enum LegacyIdlType {
     OptionPrimitive {
         option: String,
     },
+    Generic {
+        generic: String,
+    },
     Defined {
         defined: String,
     },
@@ -106,6 +118,13 @@ pub enum LegacyIdlType {
     },
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IdlArrayLen {
+    Value(usize),
+    Generic { generic: String },
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LegacyIdlAccountItem {
@@ -124,6 +143,8 @@ pub struct LegacyIdlAccountType {
     pub fields: Option<Vec<LegacyIdlTypeDefinitionField>>,
     #[serde(default)]
     pub variants: Option<Vec<LegacyIdlEnumVariant>>,
+    #[serde(default)]
+    pub alias: Option<LegacyIdlType>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -198,4 +219,31 @@ pub struct LegacyIdlError {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IdlDefinedType {
     pub name: String,
+    #[serde(default)]
+    pub generics: Vec<IdlGenericArg>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IdlGenericArg {
+    Type {
+        #[serde(rename = "type")]
+        type_: LegacyIdlType,
+    },
+    Const {
+        value: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IdlGenericParam {
+    Type {
+        name: String,
+    },
+    Const {
+        name: String,
+        #[serde(rename = "type")]
+        type_: String,
+    },
 }
@@ -5,19 +5,26 @@ use {
 
 pub mod accounts;
 pub mod commands;
+pub mod config;
 pub mod events;
+pub mod fixtures;
 pub mod handlers;
 pub mod idl;
 pub mod instructions;
 pub mod legacy_idl;
+pub mod output;
 pub mod project;
+pub mod proto;
+pub mod semver;
 pub mod types;
 pub mod util;
 
 use commands::{Datasource, Decoder, Metrics, Url};
+use fixtures::FixtureOptions;
 use inquire::{
     error::InquireResult, required, Confirm, CustomType, InquireError, MultiSelect, Select, Text,
 };
+use output::WriteOptions;
 
 fn main() -> InquireResult<()> {
     match Cli::try_parse() {
@@ -27,7 +34,18 @@ fn main() -> InquireResult<()> {
 }
 
 fn process_prompts() -> InquireResult<()> {
-    let cmd = Select::new("Chose mode:", vec!["parse", "scaffold"]).prompt()?;
+    let cmd = Select::new(
+        "Chose mode:",
+        vec![
+            "parse",
+            "scaffold",
+            "doctor",
+            "semver-check",
+            "run",
+            "bench",
+        ],
+    )
+    .prompt()?;
 
     match cmd {
         "parse" => {
@@ -49,9 +67,38 @@ fn process_prompts() -> InquireResult<()> {
                                 .with_validator(required!("Please type a path to output folder"))
                                 .prompt()?;
                             let as_crate = Confirm::new("Generate as crate?").prompt()?;
+                            let serde_feature =
+                                Confirm::new("Gate serde derives behind a `serde` feature?")
+                                    .with_default(false)
+                                    .prompt()?;
+                            let fixture_options = if as_crate
+                                && Confirm::new("Generate tests from on-chain samples?")
+                                    .with_default(false)
+                                    .prompt()?
+                            {
+                                let url = CustomType::<Url>::new("Network URL:").prompt()?;
+                                Some(FixtureOptions {
+                                    rpc_url: url.rpc_url().to_string(),
+                                    program_address: None,
+                                })
+                            } else {
+                                None
+                            };
+                            let emit_proto = prompt_emit_proto()?;
+                            let zero_copy = prompt_zero_copy()?;
+                            let write_options = prompt_write_options()?;
 
-                            handlers::parse(path, output_dir, as_crate)
-                                .map_err(|e| InquireError::Custom(e.into()))?;
+                            handlers::parse(
+                                path,
+                                output_dir,
+                                as_crate,
+                                serde_feature,
+                                fixture_options,
+                                emit_proto,
+                                zero_copy,
+                                write_options,
+                            )
+                            .map_err(|e| InquireError::Custom(e.into()))?;
                         }
                         IdlStandard::Codama => {
                             let event_hints = Text::new("Event hints:")
@@ -64,8 +111,18 @@ fn process_prompts() -> InquireResult<()> {
                                 .with_validator(required!("Please type a path to output folder"))
                                 .prompt()?;
                             let as_crate = Confirm::new("Generate as crate?").prompt()?;
-                            handlers::parse_codama(path, output_dir, as_crate, Some(event_hints))
-                                .map_err(|e| InquireError::Custom(e.into()))?;
+                            let serde_feature =
+                                Confirm::new("Gate serde derives behind a `serde` feature?")
+                                    .with_default(false)
+                                    .prompt()?;
+                            handlers::parse_codama(
+                                path,
+                                output_dir,
+                                as_crate,
+                                Some(event_hints),
+                                serde_feature,
+                            )
+                            .map_err(|e| InquireError::Custom(e.into()))?;
                         }
                     }
                 }
@@ -76,9 +133,30 @@ fn process_prompts() -> InquireResult<()> {
                         .with_validator(required!("Please type a path to output folder"))
                         .prompt()?;
                     let as_crate = Confirm::new("Generate as crate?").prompt()?;
+                    let serde_feature =
+                        Confirm::new("Gate serde derives behind a `serde` feature?")
+                            .with_default(false)
+                            .prompt()?;
+                    let with_tests = as_crate
+                        && Confirm::new("Generate tests from on-chain samples?")
+                            .with_default(false)
+                            .prompt()?;
+                    let emit_proto = prompt_emit_proto()?;
+                    let zero_copy = prompt_zero_copy()?;
+                    let write_options = prompt_write_options()?;
 
-                    handlers::process_pda_idl(program_address, &url, output_dir, as_crate)
-                        .map_err(|e| InquireError::Custom(e.into()))?;
+                    handlers::process_pda_idl(
+                        program_address,
+                        &url,
+                        output_dir,
+                        as_crate,
+                        serde_feature,
+                        with_tests,
+                        emit_proto,
+                        zero_copy,
+                        write_options,
+                    )
+                    .map_err(|e| InquireError::Custom(e.into()))?;
                 }
                 _ => unreachable!(),
             }
@@ -147,6 +225,9 @@ fn process_prompts() -> InquireResult<()> {
 
             let metrics =
                 Select::new("Select metrics:", vec![Metrics::Log, Metrics::Prometheus]).prompt()?;
+            let docker = Confirm::new("Generate Docker deployment files?")
+                .with_default(false)
+                .prompt()?;
             handlers::scaffold(
                 name,
                 output_dir,
@@ -157,25 +238,105 @@ fn process_prompts() -> InquireResult<()> {
                     .join(","),
                 datasource.to_string(),
                 metrics.to_string(),
+                docker,
             )
             .map_err(|e| InquireError::Custom(e.into()))?;
         }
+        "doctor" => {
+            let config = Text::new("Path to pipeline config:")
+                .with_default("pipeline.toml")
+                .prompt()?;
+
+            handlers::doctor(config).map_err(|e| InquireError::Custom(e.into()))?;
+        }
+        "semver-check" => {
+            let baseline = Text::new("Path to the previously released decoder:").prompt()?;
+            let decoder = Text::new("Path to the regenerated decoder:").prompt()?;
+
+            handlers::semver_check(baseline, decoder)
+                .map_err(|e| InquireError::Custom(e.into()))?;
+        }
+        "run" => {
+            let config = Text::new("Path to pipeline config:")
+                .with_default("carbon.toml")
+                .prompt()?;
+
+            handlers::run(config).map_err(|e| InquireError::Custom(e.into()))?;
+        }
+        "bench" => {
+            let decoder = Text::new("Decoder, or path to a WebAssembly plugin:")
+                .with_validator(required!("Please type a decoder"))
+                .prompt()?;
+            let fixtures = Text::new("Path to the fixtures directory:")
+                .with_default("tests/fixtures")
+                .prompt()?;
+            let iterations = CustomType::<u32>::new("Iterations per fixture:")
+                .with_default(10_000)
+                .prompt()?;
+
+            handlers::bench(decoder, fixtures, iterations)
+                .map_err(|e| InquireError::Custom(e.into()))?;
+        }
         _ => unreachable!(),
     }
 
     Ok(())
 }
 
+fn prompt_emit_proto() -> InquireResult<bool> {
+    Confirm::new("Emit a protobuf schema of the decoded types?")
+        .with_default(false)
+        .prompt()
+}
+
+fn prompt_zero_copy() -> InquireResult<bool> {
+    Confirm::new("Read zero-copy accounts in place?")
+        .with_default(false)
+        .prompt()
+}
+
+fn prompt_write_options() -> InquireResult<WriteOptions> {
+    let rustfmt = Confirm::new("Format the generated files with rustfmt?")
+        .with_default(false)
+        .prompt()?;
+    let regenerate = Confirm::new("Only rewrite changed files and keep `carbon:keep` blocks?")
+        .with_default(false)
+        .prompt()?;
+
+    Ok(WriteOptions {
+        rustfmt,
+        regenerate,
+    })
+}
+
 fn process_cli_params(cli: Cli) -> InquireResult<()> {
     match cli.command {
-        Commands::Parse(options) => match options.idl {
+        Commands::Parse(options) => match options
+            .idl
+            .or(options.program_id.map(IdlSource::ProgramAddress))
+            .ok_or(InquireError::InvalidConfiguration(
+                "Either '--idl' or '--program-id' is required.".to_string(),
+            ))? {
             IdlSource::FilePath(path) => match options.standard {
                 IdlStandard::Codama => {
+                    if options.with_tests {
+                        return Err(InquireError::InvalidConfiguration(
+                            "The '--with-tests' option is only supported for Anchor IDLs."
+                                .to_string(),
+                        ));
+                    }
+                    if options.zero_copy {
+                        return Err(InquireError::InvalidConfiguration(
+                            "The '--zero-copy' option is only supported for Anchor IDLs."
+                                .to_string(),
+                        ));
+                    }
                     handlers::parse_codama(
                         path,
                         options.output,
                         options.as_crate,
                         options.event_hints,
+                        options.serde,
                     )
                     .map_err(|e| InquireError::Custom(e.into()))?;
                 }
@@ -186,11 +347,36 @@ fn process_cli_params(cli: Cli) -> InquireResult<()> {
                                 .to_string(),
                         ));
                     }
-                    handlers::parse(path, options.output, options.as_crate)
-                        .map_err(|e| InquireError::Custom(e.into()))?;
+                    let fixture_options = match options.url {
+                        Some(url) if options.with_tests => Some(FixtureOptions {
+                            rpc_url: url.rpc_url().to_string(),
+                            program_address: None,
+                        }),
+                        _ => None,
+                    };
+                    handlers::parse(
+                        path,
+                        options.output,
+                        options.as_crate,
+                        options.serde,
+                        fixture_options,
+                        options.emit_proto,
+                        options.zero_copy,
+                        WriteOptions {
+                            rustfmt: options.rustfmt,
+                            regenerate: options.regenerate,
+                        },
+                    )
+                    .map_err(|e| InquireError::Custom(e.into()))?;
                 }
             },
             IdlSource::ProgramAddress(program_address) => {
+                if options.standard == IdlStandard::Codama {
+                    return Err(InquireError::InvalidConfiguration(
+                        "On-chain IDL accounts are only supported for Anchor programs.".to_string(),
+                    ));
+                }
+
                 let url = options
                     .url
                     .as_ref()
@@ -199,8 +385,21 @@ fn process_cli_params(cli: Cli) -> InquireResult<()> {
                             .to_string(),
                     ))?;
 
-                handlers::process_pda_idl(program_address, url, options.output, options.as_crate)
-                    .map_err(|e| InquireError::Custom(e.into()))?;
+                handlers::process_pda_idl(
+                    program_address,
+                    url,
+                    options.output,
+                    options.as_crate,
+                    options.serde,
+                    options.with_tests,
+                    options.emit_proto,
+                    options.zero_copy,
+                    WriteOptions {
+                        rustfmt: options.rustfmt,
+                        regenerate: options.regenerate,
+                    },
+                )
+                .map_err(|e| InquireError::Custom(e.into()))?;
             }
         },
         Commands::Scaffold(options) => {
@@ -210,9 +409,24 @@ fn process_cli_params(cli: Cli) -> InquireResult<()> {
                 options.decoders,
                 options.data_source,
                 options.metrics,
+                options.docker,
             )
             .map_err(|e| InquireError::Custom(e.into()))?;
         }
+        Commands::Doctor(options) => {
+            handlers::doctor(options.config).map_err(|e| InquireError::Custom(e.into()))?;
+        }
+        Commands::SemverCheck(options) => {
+            handlers::semver_check(options.baseline, options.decoder)
+                .map_err(|e| InquireError::Custom(e.into()))?;
+        }
+        Commands::Run(options) => {
+            handlers::run(options.config).map_err(|e| InquireError::Custom(e.into()))?;
+        }
+        Commands::Bench(options) => {
+            handlers::bench(options.decoder, options.fixtures, options.iterations)
+                .map_err(|e| InquireError::Custom(e.into()))?;
+        }
     };
 
     Ok(())
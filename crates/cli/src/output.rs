@@ -0,0 +1,536 @@
+use {
+    anyhow::{bail, Context, Result},
+    askama::Template,
+    indicatif::{ProgressBar, ProgressStyle},
+    rayon::prelude::*,
+    std::{
+        collections::{HashMap, HashSet},
+        fs,
+        io::{self, Write},
+        process::{Command, Stdio},
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// Marks the start of a hand-written block kept when a file is regenerated.
+pub const KEEP_START: &str = "// carbon:keep-start";
+/// Marks the end of a hand-written block kept when a file is regenerated.
+pub const KEEP_END: &str = "// carbon:keep-end";
+
+/// A file of a generated decoder, rendered when written.
+pub struct GeneratedFile<'a> {
+    pub path: String,
+    render: Box<dyn Fn() -> Result<String> + Send + Sync + 'a>,
+}
+
+impl<'a> GeneratedFile<'a> {
+    /// A file rendered from an askama template.
+    pub fn template(path: String, template: impl Template + Send + Sync + 'a) -> Self {
+        let description = path.clone();
+        Self {
+            path,
+            render: Box::new(move || {
+                template
+                    .render()
+                    .with_context(|| format!("Failed to render {}", description))
+            }),
+        }
+    }
+
+    /// A file with a fixed content.
+    pub fn content(path: String, content: String) -> Self {
+        Self {
+            path,
+            render: Box::new(move || Ok(content.clone())),
+        }
+    }
+}
+
+/// How generated files are written.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    /// Formats the Rust files with `rustfmt` before writing them.
+    pub rustfmt: bool,
+    /// Compares the files with the existing ones, only rewriting the files
+    /// that changed, and keeps the `carbon:keep` blocks of the existing files.
+    pub regenerate: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WriteOutcome {
+    Written,
+    Unchanged,
+}
+
+/// Renders and writes files in parallel, reporting the progress on a bar.
+///
+/// Every file is attempted even if others fail, and the errors of all failed
+/// files are reported together.
+pub fn write_files(files: &[GeneratedFile], options: WriteOptions) -> Result<()> {
+    let progress = ProgressBar::new(files.len() as u64).with_style(
+        ProgressStyle::with_template(
+            "{spinner:.green} [{bar:40.cyan/blue}] {pos}/{len} {wide_msg}",
+        )
+        .context("Invalid progress bar template")?
+        .progress_chars("=> "),
+    );
+    let unchanged = AtomicUsize::new(0);
+
+    let errors: Vec<anyhow::Error> = files
+        .par_iter()
+        .filter_map(|file| {
+            progress.set_message(file.path.clone());
+            let result = write_file(file, options);
+            progress.inc(1);
+            match result {
+                Ok(WriteOutcome::Unchanged) => {
+                    unchanged.fetch_add(1, Ordering::Relaxed);
+                    None
+                }
+                Ok(WriteOutcome::Written) => None,
+                Err(error) => Some(error),
+            }
+        })
+        .collect();
+
+    progress.finish_and_clear();
+
+    if !errors.is_empty() {
+        bail!(
+            "Failed to generate {} of {} files:\n{}",
+            errors.len(),
+            files.len(),
+            errors
+                .iter()
+                .map(|error| format!("  - {:#}", error))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
+    let unchanged = unchanged.into_inner();
+    if options.regenerate {
+        println!(
+            "Generated {} files, {} unchanged",
+            files.len() - unchanged,
+            unchanged
+        );
+    } else {
+        println!("Generated {} files", files.len());
+    }
+
+    Ok(())
+}
+
+fn write_file(file: &GeneratedFile, options: WriteOptions) -> Result<WriteOutcome> {
+    let mut content = (file.render)()?;
+    if options.rustfmt && file.path.ends_with(".rs") {
+        content =
+            format_source(&content).with_context(|| format!("Failed to format {}", file.path))?;
+    }
+
+    if options.regenerate {
+        let existing = match fs::read_to_string(&file.path) {
+            Ok(existing) => Some(existing),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", file.path)),
+        };
+
+        if let Some(existing) = existing {
+            content = preserve_keep_blocks(&existing, &content)
+                .with_context(|| format!("Failed to keep the blocks of {}", file.path))?;
+            if content == existing {
+                return Ok(WriteOutcome::Unchanged);
+            }
+        }
+    }
+
+    fs::write(&file.path, content).with_context(|| format!("Failed to write {}", file.path))?;
+
+    Ok(WriteOutcome::Written)
+}
+
+/// Formats Rust source code with `rustfmt`.
+fn format_source(source: &str) -> Result<String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2021", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run rustfmt, is it installed?")?;
+
+    child
+        .stdin
+        .take()
+        .context("Failed to open the stdin of rustfmt")?
+        .write_all(source.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "rustfmt failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).context("rustfmt returned invalid UTF-8")
+}
+
+/// A block of lines between `KEEP_START` and `KEEP_END` markers, included.
+struct KeepBlock<'a> {
+    /// The text following `KEEP_START`, identifying the block.
+    name: &'a str,
+    /// The first line of the top-level item the block follows, with the number
+    /// of items starting with the same line before it. `None` if the block
+    /// precedes every item.
+    anchor: Option<(&'a str, usize)>,
+    /// Whether the block is inside the body of its anchor.
+    nested: bool,
+    /// The number of blank lines between the anchor and the block.
+    gap: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Tracks the top-level items of formatted Rust code, to anchor keep blocks
+/// to them.
+#[derive(Default)]
+struct Items<'a> {
+    occurrences: HashMap<&'a str, usize>,
+    last: Option<(&'a str, usize)>,
+    open: bool,
+    gap: usize,
+}
+
+impl<'a> Items<'a> {
+    /// Records a line outside of keep blocks, returning the item it ends, if
+    /// any.
+    fn push(&mut self, line: &'a str) -> Option<(&'a str, usize)> {
+        let line = line.trim_end();
+        if line.is_empty() {
+            self.gap += 1;
+            return None;
+        }
+        self.gap = 0;
+
+        // Only unindented lines start or end items. Attributes and comments
+        // belong to the item they precede.
+        if line.starts_with(char::is_whitespace)
+            || line.starts_with(['#', '/'])
+            || line.starts_with("where")
+        {
+            return None;
+        }
+        if line.starts_with(['}', ')', ']']) {
+            return std::mem::take(&mut self.open)
+                .then_some(self.last)
+                .flatten();
+        }
+        if line == "{" {
+            self.open = true;
+            return None;
+        }
+
+        let occurrences = self.occurrences.entry(line).or_default();
+        self.last = Some((line, *occurrences));
+        *occurrences += 1;
+        self.open = line.ends_with(['{', '(', '[']);
+
+        if self.open {
+            None
+        } else {
+            self.last
+        }
+    }
+}
+
+fn keep_blocks(content: &str) -> Result<Vec<KeepBlock>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<KeepBlock> = None;
+    let mut items = Items::default();
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(name) = trimmed.strip_prefix(KEEP_START) {
+            if current.is_some() {
+                bail!("Nested '{}' on line {}", KEEP_START, index + 1);
+            }
+            current = Some(KeepBlock {
+                name: name.trim(),
+                anchor: items.last,
+                nested: items.open,
+                gap: items.gap,
+                lines: vec![line],
+            });
+        } else if trimmed.starts_with(KEEP_END) {
+            let Some(mut block) = current.take() else {
+                bail!("'{}' without a start on line {}", KEEP_END, index + 1);
+            };
+            block.lines.push(line);
+            blocks.push(block);
+            items.gap = 0;
+        } else if let Some(block) = &mut current {
+            block.lines.push(line);
+        } else {
+            items.push(line);
+        }
+    }
+
+    if current.is_some() {
+        bail!("Unterminated '{}'", KEEP_START);
+    }
+
+    Ok(blocks)
+}
+
+fn push_block(content: &mut String, block: &KeepBlock) {
+    for _ in 0..block.gap {
+        content.push('\n');
+    }
+    content.push_str(&block.lines.join("\n"));
+    content.push('\n');
+}
+
+/// Carries the keep blocks of an existing file over to its regenerated
+/// content.
+///
+/// A block replaces the block with the same name in the regenerated content.
+/// Other blocks are inserted after the top-level item they followed in the
+/// existing file, such as the struct their helper impl extends. The file is
+/// rejected if that item is no longer generated, or if such a block was inside
+/// the item, rather than moving the block somewhere it may not compile.
+fn preserve_keep_blocks(existing: &str, generated: &str) -> Result<String> {
+    let existing_blocks = keep_blocks(existing)?;
+    if existing_blocks.is_empty() {
+        return Ok(generated.to_string());
+    }
+    let generated_names: HashSet<&str> = keep_blocks(generated)?
+        .iter()
+        .map(|block| block.name)
+        .collect();
+
+    let mut anchored: Vec<&KeepBlock> = existing_blocks
+        .iter()
+        .filter(|block| !generated_names.contains(block.name))
+        .collect();
+    let nested: Vec<&KeepBlock> = anchored
+        .iter()
+        .copied()
+        .filter(|block| block.nested)
+        .collect();
+    if !nested.is_empty() {
+        bail!(
+            "Keep blocks {} are inside generated items, which only keep the blocks they generate",
+            block_names(&nested)
+        );
+    }
+
+    let mut content = String::with_capacity(generated.len());
+    let mut insert_anchored = |content: &mut String, anchor: Option<(&str, usize)>| {
+        anchored.retain(|block| {
+            if block.anchor != anchor {
+                return true;
+            }
+            push_block(content, block);
+            false
+        });
+    };
+    insert_anchored(&mut content, None);
+
+    let mut items = Items::default();
+    let mut skipping = false;
+    let mut in_block = false;
+    for line in generated.lines() {
+        let trimmed = line.trim_start();
+        if skipping {
+            skipping = !trimmed.starts_with(KEEP_END);
+            continue;
+        }
+
+        if let Some(name) = trimmed.strip_prefix(KEEP_START) {
+            if let Some(block) = existing_blocks
+                .iter()
+                .find(|block| block.name == name.trim())
+            {
+                content.push_str(&block.lines.join("\n"));
+                content.push('\n');
+                skipping = true;
+                continue;
+            }
+            in_block = true;
+        }
+
+        content.push_str(line);
+        content.push('\n');
+        if in_block {
+            in_block = !trimmed.starts_with(KEEP_END);
+        } else if let Some(item) = items.push(line) {
+            insert_anchored(&mut content, Some(item));
+        }
+    }
+    if !generated.ends_with('\n') {
+        content.pop();
+    }
+
+    if !anchored.is_empty() {
+        bail!(
+            "Keep blocks {} follow items which are no longer generated",
+            block_names(&anchored)
+        );
+    }
+
+    Ok(content)
+}
+
+fn block_names(blocks: &[&KeepBlock]) -> String {
+    blocks
+        .iter()
+        .map(|block| format!("'{}'", block.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the Rust files of `dirs` which aren't part of `files`, such as the
+/// files of instructions removed from the IDL.
+pub fn stale_files(dirs: &[&str], files: &[GeneratedFile]) -> Vec<String> {
+    let generated: HashSet<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    let mut stale = Vec::new();
+
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = format!("{}/{}", dir, entry.file_name().to_string_lossy());
+            if path.ends_with(".rs") && !generated.contains(path.as_str()) {
+                stale.push(path);
+            }
+        }
+    }
+    stale.sort();
+
+    stale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXISTING: &str = "pub struct Swap {
+    pub amount: u64,
+}
+
+// carbon:keep-start helpers
+impl Swap {
+    pub fn double(&self) -> u64 {
+        self.amount * 2
+    }
+}
+// carbon:keep-end
+
+pub struct Deposit {
+    pub amount: u64,
+}
+";
+
+    #[test]
+    fn test_keep_blocks_follow_their_anchor() {
+        let generated = "pub struct Swap {
+    pub amount: u64,
+    pub fee: u64,
+}
+
+pub struct Deposit {
+    pub amount: u64,
+}
+";
+
+        assert_eq!(
+            preserve_keep_blocks(EXISTING, generated).unwrap(),
+            "pub struct Swap {
+    pub amount: u64,
+    pub fee: u64,
+}
+
+// carbon:keep-start helpers
+impl Swap {
+    pub fn double(&self) -> u64 {
+        self.amount * 2
+    }
+}
+// carbon:keep-end
+
+pub struct Deposit {
+    pub amount: u64,
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_keep_blocks_replace_generated_blocks() {
+        let generated = "pub struct Deposit {
+    pub amount: u64,
+}
+
+// carbon:keep-start helpers
+// carbon:keep-end
+";
+
+        let content = preserve_keep_blocks(EXISTING, generated).unwrap();
+        assert!(content.starts_with("pub struct Deposit {"));
+        assert!(content.contains("        self.amount * 2\n"));
+        assert_eq!(content.matches(KEEP_START).count(), 1);
+    }
+
+    #[test]
+    fn test_keep_blocks_without_anchor_are_rejected() {
+        let generated = "pub struct Deposit {
+    pub amount: u64,
+}
+";
+
+        let error = preserve_keep_blocks(EXISTING, generated).unwrap_err();
+        assert!(error.to_string().contains("'helpers'"));
+    }
+
+    #[test]
+    fn test_regenerate_skips_unchanged_files() {
+        let path =
+            std::env::temp_dir().join(format!("carbon-cli-output-test-{}.rs", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        let options = WriteOptions {
+            rustfmt: false,
+            regenerate: true,
+        };
+        fs::write(&path, EXISTING).unwrap();
+
+        let generated = "pub struct Swap {
+    pub amount: u64,
+}
+
+pub struct Deposit {
+    pub amount: u64,
+}
+";
+        let file = GeneratedFile::content(path.clone(), generated.to_string());
+        assert_eq!(write_file(&file, options).unwrap(), WriteOutcome::Unchanged);
+        assert_eq!(fs::read_to_string(&path).unwrap(), EXISTING);
+
+        let file = GeneratedFile::content(path.clone(), generated.replace("Swap", "Trade"));
+        assert!(write_file(&file, options).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), EXISTING);
+
+        let file = GeneratedFile::content(
+            path.clone(),
+            format!("{}\npub struct Withdraw;\n", generated),
+        );
+        assert_eq!(write_file(&file, options).unwrap(), WriteOutcome::Written);
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .ends_with("}\n\npub struct Withdraw;\n"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
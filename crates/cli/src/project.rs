@@ -24,3 +24,17 @@ pub struct ProjectTemplate<'a> {
     pub decoders: &'a [DecoderData],
     pub metrics: &'a MetricsData,
 }
+
+#[derive(Template)]
+#[template(path = "dockerfile.askama", escape = "none", ext = ".askama")]
+pub struct DockerfileTemplate<'a> {
+    pub name: &'a str,
+    pub metrics: &'a MetricsData,
+}
+
+#[derive(Template)]
+#[template(path = "docker_compose.askama", escape = "none", ext = ".askama")]
+pub struct DockerComposeTemplate<'a> {
+    pub database: Option<&'a str>,
+    pub metrics: &'a MetricsData,
+}
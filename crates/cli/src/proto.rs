@@ -0,0 +1,534 @@
+use {
+    crate::{
+        accounts::AccountData,
+        events::EventData,
+        instructions::InstructionData,
+        types::{EnumVariantFields, TypeData, TypeKind},
+    },
+    heck::{ToSnakeCase, ToUpperCamelCase},
+    std::{
+        collections::{HashMap, HashSet},
+        fmt::Write,
+    },
+};
+
+/// How a Rust value is converted into its protobuf scalar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Conversion {
+    Into,
+    ToString,
+    Clone,
+    ToVec,
+}
+
+/// The protobuf type of a single value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProtoValue {
+    Scalar(&'static str, Conversion),
+    Message(String),
+}
+
+/// The protobuf representation of a field of a decoded type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProtoField {
+    Single(ProtoValue),
+    Optional(ProtoValue),
+    Repeated(ProtoValue),
+    /// Types without a protobuf counterpart, such as tuples, maps or nested
+    /// collections, are shipped as their `Debug` representation.
+    Debug(String),
+}
+
+struct Field {
+    name: String,
+    rust_type: String,
+}
+
+/// The fields of an enum variant, unnamed fields being named `field_<index>`.
+enum VariantFields {
+    Named(Vec<Field>),
+    Unnamed(Vec<Field>),
+}
+
+impl VariantFields {
+    fn fields(&self) -> &[Field] {
+        match self {
+            VariantFields::Named(fields) | VariantFields::Unnamed(fields) => fields,
+        }
+    }
+}
+
+enum MessageBody {
+    Struct(Vec<Field>),
+    Enum(Vec<(String, Option<VariantFields>)>),
+}
+
+struct Message {
+    name: String,
+    rust_path: String,
+    body: MessageBody,
+}
+
+/// The protobuf schema of the types, accounts, instructions and events of a
+/// decoder, with the conversions from the generated Rust types.
+pub struct ProtoSchema {
+    package: String,
+    messages: Vec<Message>,
+    /// The protobuf message of each non-generic defined type, by Rust name.
+    message_names: HashMap<String, String>,
+    /// The target of each type alias, by Rust name.
+    aliases: HashMap<String, String>,
+}
+
+impl ProtoSchema {
+    pub fn new(
+        program_name: &str,
+        types_data: &[TypeData],
+        accounts_data: &[AccountData],
+        instructions_data: &[InstructionData],
+        events_data: &[EventData],
+    ) -> Self {
+        let mut messages = Vec::new();
+        let mut message_names = HashMap::new();
+        let mut aliases = HashMap::new();
+        let mut used_names = HashSet::new();
+
+        let mut push = |messages: &mut Vec<Message>, name: &str, rust_path, body| {
+            let proto_name = name.to_upper_camel_case();
+            if !used_names.insert(proto_name.clone()) {
+                println!(
+                    "Skipping the protobuf message of {}, its name is already used",
+                    rust_path
+                );
+                return None;
+            }
+            messages.push(Message {
+                name: proto_name.clone(),
+                rust_path,
+                body,
+            });
+            Some(proto_name)
+        };
+
+        for type_data in types_data {
+            // Generic types have no single protobuf counterpart.
+            if !type_data.generics.is_empty() {
+                continue;
+            }
+
+            let body = match &type_data.kind {
+                TypeKind::Struct => MessageBody::Struct(
+                    type_data
+                        .fields
+                        .iter()
+                        .map(|field| Field {
+                            name: field.name.clone(),
+                            rust_type: field.rust_type.clone(),
+                        })
+                        .collect(),
+                ),
+                TypeKind::Enum(variants) => MessageBody::Enum(
+                    variants
+                        .iter()
+                        .map(|variant| {
+                            let fields = variant.fields.as_ref().map(|fields| match fields {
+                                EnumVariantFields::Named(fields) => VariantFields::Named(
+                                    fields
+                                        .iter()
+                                        .map(|field| Field {
+                                            name: field.name.clone(),
+                                            rust_type: field.rust_type.clone(),
+                                        })
+                                        .collect(),
+                                ),
+                                EnumVariantFields::Unnamed(rust_types) => VariantFields::Unnamed(
+                                    rust_types
+                                        .iter()
+                                        .enumerate()
+                                        .map(|(index, rust_type)| Field {
+                                            name: format!("field_{}", index),
+                                            rust_type: rust_type.clone(),
+                                        })
+                                        .collect(),
+                                ),
+                            });
+                            (variant.name.clone(), fields)
+                        })
+                        .collect(),
+                ),
+                TypeKind::Alias(rust_type) => {
+                    aliases.insert(type_data.name.clone(), rust_type.clone());
+                    continue;
+                }
+            };
+
+            if let Some(proto_name) = push(
+                &mut messages,
+                &type_data.name,
+                format!("crate::types::{}", type_data.name),
+                body,
+            ) {
+                message_names.insert(type_data.name.clone(), proto_name);
+            }
+        }
+
+        for account in accounts_data {
+            push(
+                &mut messages,
+                &account.struct_name,
+                format!(
+                    "crate::accounts::{}::{}",
+                    account.module_name, account.struct_name
+                ),
+                MessageBody::Struct(
+                    account
+                        .fields
+                        .iter()
+                        .map(|field| Field {
+                            name: field.name.clone(),
+                            rust_type: field.rust_type.clone(),
+                        })
+                        .collect(),
+                ),
+            );
+        }
+
+        for instruction in instructions_data {
+            push(
+                &mut messages,
+                &instruction.struct_name,
+                format!(
+                    "crate::instructions::{}::{}",
+                    instruction.module_name, instruction.struct_name
+                ),
+                MessageBody::Struct(
+                    instruction
+                        .args
+                        .iter()
+                        .map(|arg| Field {
+                            name: arg.name.clone(),
+                            rust_type: arg.rust_type.clone(),
+                        })
+                        .collect(),
+                ),
+            );
+        }
+
+        for event in events_data {
+            push(
+                &mut messages,
+                &event.struct_name,
+                format!(
+                    "crate::instructions::{}::{}",
+                    event.module_name, event.struct_name
+                ),
+                MessageBody::Struct(
+                    event
+                        .args
+                        .iter()
+                        .map(|arg| Field {
+                            name: arg.name.clone(),
+                            rust_type: arg.rust_type.clone(),
+                        })
+                        .collect(),
+                ),
+            );
+        }
+
+        Self {
+            package: program_name.to_snake_case(),
+            messages,
+            message_names,
+            aliases,
+        }
+    }
+
+    /// The protobuf package of the schema, also the name of the `.proto` file.
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    fn value(&self, rust_type: &str) -> Option<ProtoValue> {
+        let scalar = match rust_type {
+            "bool" => ProtoValue::Scalar("bool", Conversion::Into),
+            "u8" | "u16" | "u32" => ProtoValue::Scalar("uint32", Conversion::Into),
+            "i8" | "i16" | "i32" => ProtoValue::Scalar("int32", Conversion::Into),
+            "u64" => ProtoValue::Scalar("uint64", Conversion::Into),
+            "i64" => ProtoValue::Scalar("int64", Conversion::Into),
+            "f32" => ProtoValue::Scalar("float", Conversion::Into),
+            "f64" => ProtoValue::Scalar("double", Conversion::Into),
+            // 128-bit integers don't fit protobuf scalars, ship them as decimal
+            // strings.
+            "u128" | "i128" => ProtoValue::Scalar("string", Conversion::ToString),
+            "String" => ProtoValue::Scalar("string", Conversion::Clone),
+            "solana_pubkey::Pubkey" => ProtoValue::Scalar("string", Conversion::ToString),
+            "Vec<u8>" => ProtoValue::Scalar("bytes", Conversion::Clone),
+            _ if array_element(rust_type) == Some("u8") => {
+                ProtoValue::Scalar("bytes", Conversion::ToVec)
+            }
+            _ => {
+                if let Some(name) = self.message_names.get(rust_type) {
+                    return Some(ProtoValue::Message(name.clone()));
+                }
+                return self
+                    .aliases
+                    .get(rust_type)
+                    .and_then(|target| self.value(target));
+            }
+        };
+
+        Some(scalar)
+    }
+
+    fn field(&self, rust_type: &str) -> ProtoField {
+        if let Some(value) = self.value(rust_type) {
+            return ProtoField::Single(value);
+        }
+
+        let field = if let Some(inner) = generic_argument(rust_type, "Option") {
+            self.value(inner).map(ProtoField::Optional)
+        } else if let Some(element) =
+            generic_argument(rust_type, "Vec").or_else(|| array_element(rust_type))
+        {
+            self.value(element).map(ProtoField::Repeated)
+        } else if let Some(target) = self.aliases.get(rust_type) {
+            Some(self.field(target))
+        } else {
+            None
+        };
+
+        field.unwrap_or_else(|| ProtoField::Debug(rust_type.to_string()))
+    }
+
+    /// Renders the `.proto` file of the schema.
+    pub fn render_proto(&self) -> String {
+        let mut proto = format!("syntax = \"proto3\";\n\npackage {};\n", self.package);
+
+        for message in &self.messages {
+            let _ = writeln!(proto, "\nmessage {} {{", message.name);
+            match &message.body {
+                MessageBody::Struct(fields) => self.render_fields(&mut proto, fields, "  "),
+                MessageBody::Enum(variants) => {
+                    for (variant, fields) in variants {
+                        let _ = writeln!(proto, "  message {} {{", variant.to_upper_camel_case());
+                        if let Some(fields) = fields {
+                            self.render_fields(&mut proto, fields.fields(), "    ");
+                        }
+                        proto.push_str("  }\n");
+                    }
+                    proto.push_str("  oneof variant {\n");
+                    for (index, (variant, _)) in variants.iter().enumerate() {
+                        let _ = writeln!(
+                            proto,
+                            "    {} {} = {};",
+                            variant.to_upper_camel_case(),
+                            variant.to_snake_case(),
+                            index + 1
+                        );
+                    }
+                    proto.push_str("  }\n");
+                }
+            }
+            proto.push_str("}\n");
+        }
+
+        proto
+    }
+
+    fn render_fields(&self, proto: &mut String, fields: &[Field], indent: &str) {
+        for (index, field) in fields.iter().enumerate() {
+            let number = index + 1;
+            let _ = match self.field(&field.rust_type) {
+                ProtoField::Single(value) => writeln!(
+                    proto,
+                    "{indent}{} {} = {number};",
+                    proto_type(&value),
+                    field.name
+                ),
+                ProtoField::Optional(ProtoValue::Message(name)) => {
+                    writeln!(proto, "{indent}{} {} = {number};", name, field.name)
+                }
+                ProtoField::Optional(value) => writeln!(
+                    proto,
+                    "{indent}optional {} {} = {number};",
+                    proto_type(&value),
+                    field.name
+                ),
+                ProtoField::Repeated(value) => writeln!(
+                    proto,
+                    "{indent}repeated {} {} = {number};",
+                    proto_type(&value),
+                    field.name
+                ),
+                ProtoField::Debug(rust_type) => writeln!(
+                    proto,
+                    "{indent}// The `Debug` representation of `{}`.\n\
+                     {indent}string {} = {number};",
+                    rust_type, field.name
+                ),
+            };
+        }
+    }
+
+    /// Renders the `proto` module of a generated crate, including the code
+    /// generated by `prost-build` and the `From` conversions of the decoded
+    /// types.
+    pub fn render_module(&self) -> String {
+        let mut module = format!(
+            "//! Protobuf messages generated from `proto/{package}.proto`, and the\n\
+             //! conversions from the decoded types.\n\
+             #![allow(clippy::useless_conversion)]\n\n\
+             include!(concat!(env!(\"OUT_DIR\"), \"/{package}.rs\"));\n",
+            package = self.package
+        );
+
+        for message in &self.messages {
+            let _ = writeln!(
+                module,
+                "\nimpl From<&{rust_path}> for {name} {{\n    \
+                 fn from(value: &{rust_path}) -> Self {{",
+                rust_path = message.rust_path,
+                name = message.name
+            );
+            match &message.body {
+                MessageBody::Struct(fields) => {
+                    let _ = writeln!(
+                        module,
+                        "        let {} {} = value;\n        Self {}",
+                        message.rust_path,
+                        braced(&field_names(fields)),
+                        braced(&self.field_conversions(fields))
+                    );
+                }
+                MessageBody::Enum(variants) => {
+                    let message_module = message.name.to_snake_case();
+                    module.push_str("        let variant = match value {\n");
+                    for (variant, fields) in variants {
+                        let proto_variant = variant.to_upper_camel_case();
+                        let (pattern, conversions) = match fields {
+                            Some(VariantFields::Unnamed(fields)) => (
+                                format!("({})", field_names(fields)),
+                                self.field_conversions(fields),
+                            ),
+                            Some(VariantFields::Named(fields)) => (
+                                format!(" {}", braced(&field_names(fields))),
+                                self.field_conversions(fields),
+                            ),
+                            None => (String::new(), String::new()),
+                        };
+                        let proto_message =
+                            format!("{message_module}::{proto_variant} {}", braced(&conversions));
+                        let _ = writeln!(
+                            module,
+                            "            {}::{variant}{pattern} => \
+                             {message_module}::Variant::{proto_variant}({proto_message}),",
+                            message.rust_path,
+                        );
+                    }
+                    module.push_str("        };\n        Self { variant: Some(variant) }\n");
+                }
+            }
+            module.push_str("    }\n}\n");
+        }
+
+        module
+    }
+
+    fn field_conversions(&self, fields: &[Field]) -> String {
+        fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{}: {}",
+                    field.name,
+                    self.field_conversion(&field.name, &field.rust_type)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Converts a reference to a field, bound to `binding`, into its protobuf
+    /// representation.
+    fn field_conversion(&self, binding: &str, rust_type: &str) -> String {
+        match self.field(rust_type) {
+            ProtoField::Single(ProtoValue::Message(_)) => format!("Some({}.into())", binding),
+            ProtoField::Single(value) => value_conversion(binding, &value),
+            ProtoField::Optional(value) => format!(
+                "{}.as_ref().map(|value| {})",
+                binding,
+                value_conversion("value", &value)
+            ),
+            ProtoField::Repeated(value) => format!(
+                "{}.iter().map(|value| {}).collect()",
+                binding,
+                value_conversion("value", &value)
+            ),
+            ProtoField::Debug(_) => format!("format!(\"{{:?}}\", {})", binding),
+        }
+    }
+}
+
+fn proto_type(value: &ProtoValue) -> &str {
+    match value {
+        ProtoValue::Scalar(proto_type, _) => proto_type,
+        ProtoValue::Message(name) => name,
+    }
+}
+
+/// Converts a reference to a value, bound to `binding`, into its protobuf
+/// type.
+fn value_conversion(binding: &str, value: &ProtoValue) -> String {
+    match value {
+        ProtoValue::Scalar(_, Conversion::Into) => format!("(*{}).into()", binding),
+        ProtoValue::Scalar(_, Conversion::ToString) => format!("{}.to_string()", binding),
+        ProtoValue::Scalar(_, Conversion::Clone) => format!("{}.clone()", binding),
+        ProtoValue::Scalar(_, Conversion::ToVec) => format!("{}.to_vec()", binding),
+        ProtoValue::Message(_) => format!("{}.into()", binding),
+    }
+}
+
+fn field_names(fields: &[Field]) -> String {
+    fields
+        .iter()
+        .map(|field| field.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Wraps a list of fields in braces, e.g. `{ a, b }`, or `{}` if it's empty.
+fn braced(fields: &str) -> String {
+    if fields.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{ {} }}", fields)
+    }
+}
+
+/// Returns the argument of a single-argument generic type, e.g. `T` for
+/// `Option<T>`.
+fn generic_argument<'a>(rust_type: &'a str, generic: &str) -> Option<&'a str> {
+    rust_type
+        .strip_prefix(generic)?
+        .strip_prefix('<')?
+        .strip_suffix('>')
+}
+
+/// Returns the element type of an array type, e.g. `T` for `[T; 32]`.
+fn array_element(rust_type: &str) -> Option<&str> {
+    let inner = rust_type.strip_prefix('[')?.strip_suffix(']')?;
+    inner.rsplit_once(';').map(|(element, _)| element.trim())
+}
+
+/// Renders the `build.rs` of a generated crate, compiling its protobuf schema
+/// when the `proto` feature is enabled.
+pub fn build_script(package: &str) -> String {
+    format!(
+        r#"fn main() {{
+    #[cfg(feature = "proto")]
+    prost_build::compile_protos(&["proto/{package}.proto"], &["proto"])
+        .expect("Failed to compile the protobuf schema, is protoc installed?");
+}}
+"#
+    )
+}
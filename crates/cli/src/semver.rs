@@ -0,0 +1,208 @@
+use std::{collections::HashSet, fs};
+
+/// A variant of a generated account or instruction enum.
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    pub struct_name: String,
+    pub module_name: String,
+}
+
+impl EnumVariant {
+    pub fn new(struct_name: &str, module_name: &str) -> Self {
+        Self {
+            struct_name: struct_name.to_string(),
+            module_name: module_name.to_string(),
+        }
+    }
+}
+
+/// Reads the name and variants of the enum declared in a generated
+/// `accounts/mod.rs` or `instructions/mod.rs` file.
+///
+/// Returns `None` if the file doesn't exist or doesn't declare an enum.
+pub fn read_enum_variants(mod_path: &str) -> Option<(String, Vec<String>)> {
+    let content = fs::read_to_string(mod_path).ok()?;
+    let mut lines = content.lines().map(str::trim);
+
+    let enum_name = lines.by_ref().find_map(|line| {
+        line.strip_prefix("pub enum ")
+            .and_then(|rest| rest.split_whitespace().next())
+            .map(|name| name.trim_end_matches('{').to_string())
+    })?;
+
+    let variants = lines
+        .take_while(|line| !line.starts_with('}'))
+        .filter(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with('#'))
+        .filter_map(|line| line.split(['(', ',', ' ', '{']).next())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    Some((enum_name, variants))
+}
+
+/// Orders freshly generated items so that the variants of a previous
+/// generation keep their position, and new variants are appended in IDL
+/// order.
+pub fn stable_order<T>(items: Vec<T>, previous: &[String], name: impl Fn(&T) -> &str) -> Vec<T> {
+    let mut items: Vec<(usize, T)> = items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let position = previous
+                .iter()
+                .position(|variant| variant == name(&item))
+                .unwrap_or(previous.len() + index);
+            (position, item)
+        })
+        .collect();
+    items.sort_by_key(|(position, _)| *position);
+
+    items.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Orders the variants of an enum about to be regenerated at `mod_path` after
+/// the variants of its previous generation, printing the resulting changes.
+///
+/// Items are returned unchanged if the enum wasn't generated before.
+pub fn keep_variant_order<T>(mod_path: &str, items: Vec<T>, name: impl Fn(&T) -> &str) -> Vec<T> {
+    let Some((enum_name, previous)) = read_enum_variants(mod_path) else {
+        return items;
+    };
+
+    let items = stable_order(items, &previous, &name);
+    let generated: Vec<String> = items.iter().map(|item| name(item).to_string()).collect();
+
+    let changes = VariantChanges::between(&previous, &generated);
+    changes.print(&enum_name);
+    if changes.is_breaking() {
+        println!(
+            "Warning: regenerating {} introduces breaking changes, bump the decoder's major version.",
+            enum_name
+        );
+    }
+
+    items
+}
+
+/// The differences between two generations of a decoder enum.
+#[derive(Debug, Default)]
+pub struct VariantChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub moved: Vec<String>,
+}
+
+impl VariantChanges {
+    /// Compares two generations of an enum.
+    ///
+    /// Variants kept by both generations are compared by their relative order,
+    /// so adding or removing a variant doesn't report the following ones as
+    /// moved. The moved variants are those outside of the longest run of kept
+    /// variants whose order is unchanged.
+    pub fn between(old: &[String], new: &[String]) -> Self {
+        let old_set: HashSet<&String> = old.iter().collect();
+        let new_set: HashSet<&String> = new.iter().collect();
+
+        let old_kept: Vec<&String> = old
+            .iter()
+            .filter(|variant| new_set.contains(variant))
+            .collect();
+        let new_kept: Vec<&String> = new
+            .iter()
+            .filter(|variant| old_set.contains(variant))
+            .collect();
+        let in_order = longest_common_subsequence(&old_kept, &new_kept);
+
+        Self {
+            added: new
+                .iter()
+                .filter(|variant| !old_set.contains(variant))
+                .cloned()
+                .collect(),
+            removed: old
+                .iter()
+                .filter(|variant| !new_set.contains(variant))
+                .cloned()
+                .collect(),
+            moved: new_kept
+                .into_iter()
+                .filter(|variant| !in_order.contains(variant))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Removed or reordered variants break downstream matches and
+    /// non self-describing serialization formats. Added variants don't, as
+    /// generated enums are `#[non_exhaustive]`.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed.is_empty() || !self.moved.is_empty()
+    }
+
+    pub fn print(&self, enum_name: &str) {
+        for variant in &self.added {
+            println!("{enum_name}: added variant {variant} (minor)");
+        }
+        for variant in &self.removed {
+            println!("{enum_name}: removed variant {variant} (breaking)");
+        }
+        for variant in &self.moved {
+            println!("{enum_name}: moved variant {variant} (breaking)");
+        }
+    }
+}
+
+/// Returns the items of the longest subsequence shared by `a` and `b`.
+fn longest_common_subsequence<'a>(a: &[&'a String], b: &[&'a String]) -> HashSet<&'a String> {
+    // lengths[i][j] is the length of the longest common subsequence of a[i..]
+    // and b[j..].
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut common = HashSet::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            common.insert(a[i]);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    common
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variants(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_removal_doesnt_move_following_variants() {
+        let old = variants(&["Buy", "Sell", "Create", "Withdraw"]);
+
+        let changes = VariantChanges::between(&old, &variants(&["Buy", "Create", "Withdraw"]));
+        assert_eq!(changes.removed, ["Sell"]);
+        assert!(changes.moved.is_empty());
+
+        let changes =
+            VariantChanges::between(&old, &variants(&["Buy", "Withdraw", "Sell", "Create"]));
+        assert_eq!(changes.moved, ["Withdraw"]);
+    }
+}
@@ -2,7 +2,7 @@ use {
     crate::{
         idl::Idl,
         legacy_idl::{LegacyIdl, LegacyIdlEnumFields},
-        util::{idl_type_to_rust_type, is_big_array},
+        util::{gate_serde_attribute, generic_params_to_rust, idl_type_to_rust_type, is_big_array},
     },
     askama::Template,
     heck::ToSnakeCase,
@@ -12,9 +12,34 @@ use {
 #[derive(Debug)]
 pub struct TypeData {
     pub name: String,
+    pub generics: String,
     pub fields: Vec<FieldData>,
     pub kind: TypeKind,
     pub requires_imports: bool,
+    pub zero_copy_repr: Option<String>,
+}
+
+impl TypeData {
+    /// Whether any field, including named enum variant fields, is an array
+    /// requiring `serde_big_array`.
+    pub fn has_big_array(&self) -> bool {
+        let variant_fields: Vec<&FieldData> = match &self.kind {
+            TypeKind::Enum(variants) => variants
+                .iter()
+                .filter_map(|variant| match &variant.fields {
+                    Some(EnumVariantFields::Named(fields)) => Some(fields.iter()),
+                    _ => None,
+                })
+                .flatten()
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        self.fields
+            .iter()
+            .chain(variant_fields)
+            .any(|field| is_big_array(&field.rust_type))
+    }
 }
 
 #[allow(dead_code)]
@@ -22,6 +47,7 @@ pub struct TypeData {
 pub enum TypeKind {
     Struct,
     Enum(Vec<EnumVariantData>),
+    Alias(String),
 }
 
 #[allow(dead_code)]
@@ -51,6 +77,26 @@ pub enum EnumVariantFields {
 #[template(path = "types_struct.askama", escape = "none", ext = ".askama")]
 pub struct TypeStructTemplate<'a> {
     pub type_data: &'a TypeData,
+    pub serde_feature: bool,
+    pub zero_copy: bool,
+}
+
+impl TypeStructTemplate<'_> {
+    /// Returns the `repr` of the type if it is read in place.
+    fn zero_copy_repr(&self) -> Option<&str> {
+        self.type_data
+            .zero_copy_repr
+            .as_deref()
+            .filter(|_| self.zero_copy)
+    }
+
+    fn field_attributes(&self, attributes: &str) -> String {
+        if self.serde_feature {
+            gate_serde_attribute(attributes)
+        } else {
+            attributes.to_string()
+        }
+    }
 }
 
 pub fn legacy_process_types(idl: &LegacyIdl) -> Vec<TypeData> {
@@ -100,11 +146,19 @@ pub fn legacy_process_types(idl: &LegacyIdl) -> Vec<TypeData> {
                                             requires_imports = true;
                                         }
                                         let is_pubkey = rust_type.0.contains("Pubkey");
+                                        let attributes = if is_big_array(&rust_type.0) {
+                                            Some(
+                                                "#[serde(with = \"serde_big_array::BigArray\")]"
+                                                    .to_string(),
+                                            )
+                                        } else {
+                                            None
+                                        };
                                         variant_field_data.push(FieldData {
                                             name: field.name.to_snake_case(),
                                             rust_type: rust_type.0,
                                             is_pubkey,
-                                            attributes: None,
+                                            attributes,
                                         });
                                     }
                                     Some(EnumVariantFields::Named(variant_field_data))
@@ -134,14 +188,25 @@ pub fn legacy_process_types(idl: &LegacyIdl) -> Vec<TypeData> {
                 }
                 kind = TypeKind::Enum(variants);
             }
+            "type" => {
+                if let Some(ref alias) = idl_type_def.type_.alias {
+                    let rust_type = idl_type_to_rust_type(alias);
+                    if rust_type.1 {
+                        requires_imports = true;
+                    }
+                    kind = TypeKind::Alias(rust_type.0);
+                }
+            }
             _ => {}
         }
 
         types_data.push(TypeData {
             name,
+            generics: String::new(),
             fields,
             kind,
             requires_imports,
+            zero_copy_repr: None,
         });
     }
 
@@ -195,11 +260,19 @@ pub fn process_types(idl: &Idl) -> Vec<TypeData> {
                                             requires_imports = true;
                                         }
                                         let is_pubkey = rust_type.0.contains("Pubkey");
+                                        let attributes = if is_big_array(&rust_type.0) {
+                                            Some(
+                                                "#[serde(with = \"serde_big_array::BigArray\")]"
+                                                    .to_string(),
+                                            )
+                                        } else {
+                                            None
+                                        };
                                         variant_field_data.push(FieldData {
                                             name: field.name.to_snake_case(),
                                             rust_type: rust_type.0,
                                             is_pubkey,
-                                            attributes: None,
+                                            attributes,
                                         });
                                     }
                                     Some(EnumVariantFields::Named(variant_field_data))
@@ -229,14 +302,25 @@ pub fn process_types(idl: &Idl) -> Vec<TypeData> {
                 }
                 kind = TypeKind::Enum(variants);
             }
+            "type" => {
+                if let Some(ref alias) = idl_type_def.type_.alias {
+                    let rust_type = idl_type_to_rust_type(alias);
+                    if rust_type.1 {
+                        requires_imports = true;
+                    }
+                    kind = TypeKind::Alias(rust_type.0);
+                }
+            }
             _ => {}
         }
 
         types_data.push(TypeData {
             name,
+            generics: generic_params_to_rust(&idl_type_def.generics),
             fields,
             kind,
             requires_imports,
+            zero_copy_repr: idl_type_def.zero_copy_repr(),
         });
     }
 
@@ -1,7 +1,7 @@
 use {
     crate::{
         idl::Idl,
-        legacy_idl::{LegacyIdl, LegacyIdlType},
+        legacy_idl::{IdlArrayLen, IdlGenericArg, IdlGenericParam, LegacyIdl, LegacyIdlType},
     },
     anyhow::Result,
     std::fs::File,
@@ -55,6 +55,10 @@ pub fn idl_type_to_rust_type(idl_type: &LegacyIdlType) -> (String, bool) {
         LegacyIdlType::Array { array } => {
             let (elem_type, size) = array;
             let rust_type = idl_type_to_rust_type(elem_type);
+            let size = match size {
+                IdlArrayLen::Value(size) => size.to_string(),
+                IdlArrayLen::Generic { generic } => generic.clone(),
+            };
             (format!("[{}; {}]", rust_type.0, size), rust_type.1)
         }
         LegacyIdlType::Vec { vec } => {
@@ -81,7 +85,24 @@ pub fn idl_type_to_rust_type(idl_type: &LegacyIdlType) -> (String, bool) {
             (format!("Option<{}>", rust_type.0), rust_type.1)
         }
         LegacyIdlType::Defined { defined } => (defined.clone(), true),
-        LegacyIdlType::DefinedWithName { defined } => (defined.name.clone(), true),
+        LegacyIdlType::Generic { generic } => (generic.clone(), false),
+        LegacyIdlType::DefinedWithName { defined } => {
+            if defined.generics.is_empty() {
+                return (defined.name.clone(), true);
+            }
+
+            let generic_args = defined
+                .generics
+                .iter()
+                .map(|generic| match generic {
+                    IdlGenericArg::Type { type_ } => idl_type_to_rust_type(type_).0,
+                    IdlGenericArg::Const { value } => value.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            (format!("{}<{}>", defined.name, generic_args), true)
+        }
         LegacyIdlType::HashMap { hash_map } => {
             let (key_type, value_type) = hash_map;
             let rust_key_type = idl_type_to_rust_type(key_type);
@@ -94,39 +115,133 @@ pub fn idl_type_to_rust_type(idl_type: &LegacyIdlType) -> (String, bool) {
                 rust_key_type.1 || rust_value_type.1,
             )
         }
-        LegacyIdlType::OptionPrimitive { option } => match option.as_str() {
-            "bool" => ("bool".to_string(), false),
-            "u8" => ("u8".to_string(), false),
-            "i8" => ("i8".to_string(), false),
-            "u16" => ("u16".to_string(), false),
-            "i16" => ("i16".to_string(), false),
-            "u32" => ("u32".to_string(), false),
-            "i32" => ("i32".to_string(), false),
-            "u64" => ("u64".to_string(), false),
-            "i64" => ("i64".to_string(), false),
-            "u128" => ("u128".to_string(), false),
-            "i128" => ("i128".to_string(), false),
-            "f32" => ("f32".to_string(), false),
-            "f64" => ("f64".to_string(), false),
-            "bytes" => ("Vec<u8>".to_string(), false),
-            "string" => ("String".to_string(), false),
-            "publicKey" => ("solana_pubkey::Pubkey".to_string(), false),
-            "pubkey" => ("solana_pubkey::Pubkey".to_string(), false),
-            _ => (option.clone(), true),
-        },
+        LegacyIdlType::OptionPrimitive { option } => {
+            let rust_type = idl_type_to_rust_type(&LegacyIdlType::Primitive(option.clone()));
+
+            (format!("Option<{}>", rust_type.0), rust_type.1)
+        }
     }
 }
 
 pub fn is_big_array(rust_type: &str) -> bool {
     if rust_type.starts_with("[") && rust_type.ends_with("]") {
-        if let Some(semicolon_index) = rust_type.find(';') {
-            if let Ok(size_str) = rust_type[semicolon_index + 1..rust_type.len() - 1]
-                .trim()
-                .parse::<usize>()
-            {
-                return size_str > 32;
+        if let Some(semicolon_index) = rust_type.rfind(';') {
+            let size_str = rust_type[semicolon_index + 1..rust_type.len() - 1].trim();
+            if let Ok(size) = size_str.parse::<usize>() {
+                return size > 32;
             }
+            // Const generic lengths may exceed serde's built-in array support.
+            return size_str
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
         }
     }
     false
 }
+
+/// Renders the generic parameter list of a defined type, e.g.
+/// `<T, const N: usize>`, or an empty string for non-generic types.
+pub fn generic_params_to_rust(generics: &[IdlGenericParam]) -> String {
+    if generics.is_empty() {
+        return String::new();
+    }
+
+    let params = generics
+        .iter()
+        .map(|generic| match generic {
+            IdlGenericParam::Type { name } => name.clone(),
+            IdlGenericParam::Const { name, type_ } => format!("const {name}: {type_}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("<{params}>")
+}
+
+/// Wraps a `#[serde(...)]` field attribute so it only applies when the
+/// generated crate is built with its `serde` feature.
+pub fn gate_serde_attribute(attribute: &str) -> String {
+    match attribute
+        .strip_prefix("#[")
+        .and_then(|attribute| attribute.strip_suffix(']'))
+    {
+        Some(inner) if inner.starts_with("serde(") => {
+            format!("#[cfg_attr(feature = \"serde\", {inner})]")
+        }
+        _ => attribute.to_string(),
+    }
+}
+
+/// Renders the `Cargo.toml` of a generated decoder crate.
+///
+/// With `serde_feature` set, serde becomes an optional dependency enabled
+/// through the crate's `serde` feature. With `proto_feature` set, the crate
+/// gets a `proto` feature compiling its protobuf schema with `prost`.
+pub fn decoder_cargo_toml(
+    decoder_name_kebab: &str,
+    needs_big_array: bool,
+    serde_feature: bool,
+    proto_feature: bool,
+    with_tests: bool,
+) -> String {
+    let optional = if serde_feature {
+        ", optional = true"
+    } else {
+        ""
+    };
+    let big_array = if needs_big_array {
+        format!("serde-big-array = {{ workspace = true{optional} }}\n")
+    } else {
+        String::new()
+    };
+    let mut features = String::new();
+    if serde_feature {
+        features.push_str(&format!(
+            "serde = [\"dep:serde\"{}]\n",
+            if needs_big_array {
+                ", \"dep:serde-big-array\""
+            } else {
+                ""
+            }
+        ));
+    }
+    if proto_feature {
+        features.push_str("proto = [\"dep:prost\", \"dep:prost-build\"]\n");
+    }
+    if !features.is_empty() {
+        features = format!("\n[features]\ndefault = []\n{features}");
+    }
+    let (proto, build_dependencies) = if proto_feature {
+        (
+            "prost = { workspace = true, optional = true }\n",
+            "\n[build-dependencies]\nprost-build = { workspace = true, optional = true }\n",
+        )
+    } else {
+        ("", "")
+    };
+    let dev_dependencies = if with_tests {
+        "\n[dev-dependencies]\ncarbon-test-utils = { workspace = true }\n"
+    } else {
+        ""
+    };
+
+    format!(
+        r#"[package]
+name = "{decoder_name_kebab}-decoder"
+version = "0.8.1"
+edition = {{ workspace = true }}
+
+[lib]
+crate-type = ["rlib"]
+{features}
+[dependencies]
+carbon-core = {{ workspace = true }}
+carbon-proc-macros = {{ workspace = true }}
+carbon-macros = {{ workspace = true }}
+solana-account = {{ workspace = true }}
+solana-instruction = {{ workspace = true }}
+solana-pubkey = {{ workspace = true }}
+serde = {{ workspace = true{optional} }}
+{big_array}{proto}{build_dependencies}{dev_dependencies}"#
+    )
+}
@@ -0,0 +1,170 @@
+//! Decode throughput of the deserialization macros.
+//!
+//! Run with `cargo bench -p carbon-core`, and compare against a baseline saved
+//! from the previous release with `--save-baseline` and `--baseline` to catch
+//! regressions in the code generated by `CarbonDeserialize` and
+//! `try_decode_instructions!`.
+
+use {
+    carbon_core::{
+        borsh, bytemuck,
+        deserialize::{CarbonDeserialize as _, ZeroCopy},
+        try_decode_instructions, CarbonDeserialize,
+    },
+    criterion::{criterion_group, criterion_main, Criterion, Throughput},
+    solana_instruction::{AccountMeta, Instruction},
+    solana_pubkey::Pubkey,
+    std::hint::black_box,
+};
+
+const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+
+#[derive(CarbonDeserialize, Debug, Clone, PartialEq, Eq)]
+#[carbon(discriminator = "0xf19a6d0411b16dbc")]
+pub struct Pool {
+    pub authority: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    pub fees: [u64; 4],
+    pub bump: u8,
+    pub padding: [u8; 7],
+}
+
+#[derive(
+    CarbonDeserialize, Debug, Clone, Copy, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable,
+)]
+#[bytemuck(crate = "carbon_core::bytemuck")]
+#[repr(C)]
+#[carbon(discriminator = "0xf19a6d0411b16dbc", zero_copy)]
+pub struct ZeroCopyPool {
+    pub authority: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    pub fees: [u64; 4],
+    pub bump: u8,
+    pub padding: [u8; 7],
+}
+
+#[derive(CarbonDeserialize, Debug, Clone, PartialEq, Eq)]
+#[carbon(discriminator = "0x01")]
+pub struct Deposit {
+    pub amount: u64,
+}
+
+#[derive(CarbonDeserialize, Debug, Clone, PartialEq, Eq)]
+#[carbon(discriminator = "0x02")]
+pub struct Withdraw {
+    pub amount: u64,
+}
+
+#[derive(CarbonDeserialize, Debug, Clone, PartialEq, Eq)]
+#[carbon(discriminator = "0x03")]
+pub struct Swap {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+    pub route: Vec<Pubkey>,
+    pub referrer: Option<Pubkey>,
+}
+
+#[derive(Debug)]
+pub enum PoolInstruction {
+    Deposit(Deposit),
+    Withdraw(Withdraw),
+    Swap(Swap),
+}
+
+fn pool_data() -> Vec<u8> {
+    let mut data = POOL_DISCRIMINATOR.to_vec();
+    for _ in 0..3 {
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+    }
+    for value in [1_000_000u64, 2_000_000, 25, 5, 0, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.push(255);
+    data.extend_from_slice(&[0; 7]);
+    data
+}
+
+fn swap_instruction() -> Instruction {
+    let mut data = vec![3];
+    data.extend_from_slice(&1_000u64.to_le_bytes());
+    data.extend_from_slice(&990u64.to_le_bytes());
+    data.extend_from_slice(&3u32.to_le_bytes());
+    for _ in 0..3 {
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+    }
+    data.push(1);
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+
+    Instruction {
+        program_id: Pubkey::new_unique(),
+        accounts: (0..8)
+            .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+            .collect(),
+        data,
+    }
+}
+
+fn bench_accounts(c: &mut Criterion) {
+    let data = pool_data();
+
+    let mut group = c.benchmark_group("account");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("borsh", |b| {
+        b.iter(|| Pool::deserialize(black_box(&data)).unwrap())
+    });
+    group.bench_function("zero_copy_owned", |b| {
+        b.iter(|| ZeroCopyPool::deserialize(black_box(&data)).unwrap())
+    });
+    // Borrows the data when the allocation is aligned, and copies it otherwise.
+    group.bench_function("zero_copy_load", |b| {
+        b.iter(|| ZeroCopyPool::load(black_box(&data)).unwrap().base_reserve)
+    });
+    group.finish();
+}
+
+fn bench_instructions(c: &mut Criterion) {
+    let instruction = swap_instruction();
+    let unknown = Instruction {
+        data: vec![9; instruction.data.len()],
+        ..instruction.clone()
+    };
+
+    let mut group = c.benchmark_group("instruction");
+    group.throughput(Throughput::Bytes(instruction.data.len() as u64));
+    group.bench_function("deserialize", |b| {
+        b.iter(|| Swap::deserialize(black_box(&instruction.data)).unwrap())
+    });
+    // `Swap` is the last variant tried, so the other variants are rejected
+    // first.
+    group.bench_function("try_decode_instructions", |b| {
+        b.iter(|| {
+            let instruction = black_box(&instruction);
+            try_decode_instructions!(instruction,
+                PoolInstruction::Deposit => Deposit,
+                PoolInstruction::Withdraw => Withdraw,
+                PoolInstruction::Swap => Swap,
+            )
+            .unwrap()
+        })
+    });
+    group.bench_function("try_decode_instructions_unknown", |b| {
+        b.iter(|| {
+            let instruction = black_box(&unknown);
+            try_decode_instructions!(instruction,
+                PoolInstruction::Deposit => Deposit,
+                PoolInstruction::Withdraw => Withdraw,
+                PoolInstruction::Swap => Swap,
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_accounts, bench_instructions);
+criterion_main!(benches);
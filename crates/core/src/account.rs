@@ -51,10 +51,11 @@
 //!   handling in the pipeline.
 
 use {
-    crate::{error::CarbonResult, metrics::MetricsCollection, processor::Processor},
+    crate::{error::CarbonResult, metrics::MetricsCollection, processor::Processor, telemetry},
     async_trait::async_trait,
     solana_pubkey::Pubkey,
     std::sync::Arc,
+    tracing::Instrument,
 };
 
 /// Holds metadata for an account update, including the slot and public key.
@@ -68,10 +69,30 @@ use {
 ///
 /// - `slot`: The Solana slot number where the account was updated.
 /// - `pubkey`: The public key of the account.
+/// - `created`: Whether this is the first update of the account seen by the
+///   pipeline. Always `false` unless the pipeline tracks account creation, see
+///   `PipelineBuilder::track_account_creation`.
+///
+/// New fields may be added in minor releases, so `AccountMetadata` is built
+/// with `AccountMetadata::new` outside of `carbon-core`.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct AccountMetadata {
     pub slot: u64,
     pub pubkey: Pubkey,
+    pub created: bool,
+}
+
+impl AccountMetadata {
+    /// Creates the metadata of an update of `pubkey` at `slot`, not flagged as
+    /// a creation.
+    pub fn new(slot: u64, pubkey: Pubkey) -> Self {
+        Self {
+            slot,
+            pubkey,
+            created: false,
+        }
+    }
 }
 
 /// Represents the decoded data of a Solana account, including account-specific
@@ -113,6 +134,10 @@ pub struct DecodedAccount<T> {
 ///
 /// - `AccountType`: The data type resulting from decoding the account, specific
 ///   to the application.
+///
+/// # Provided Methods
+///
+/// - `decoder_name`: The name of the decoder in the spans of the pipeline.
 pub trait AccountDecoder<'a> {
     type AccountType;
 
@@ -120,6 +145,11 @@ pub trait AccountDecoder<'a> {
         &self,
         account: &'a solana_account::Account,
     ) -> Option<DecodedAccount<Self::AccountType>>;
+
+    /// Returns the name of the decoder, its type name by default.
+    fn decoder_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// The input type for the account processor.
@@ -192,6 +222,12 @@ pub trait AccountPipes: Send + Sync {
         account_with_metadata: (AccountMetadata, solana_account::Account),
         metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()>;
+
+    /// Returns whether the pipe decodes accounts incrementally, and so still
+    /// receives the accounts over the `max_account_data_size` of the pipeline.
+    fn streams_large_accounts(&self) -> bool {
+        false
+    }
 }
 
 #[async_trait]
@@ -207,7 +243,12 @@ impl<T: Send> AccountPipes for AccountPipe<T> {
         );
 
         if let Some(decoded_account) = self.decoder.decode_account(&account_with_metadata.1) {
-            self.processor
+            let span = telemetry::process_span(
+                &account_with_metadata.1.owner,
+                self.decoder.decoder_name(),
+            );
+            let result = self
+                .processor
                 .process(
                     (
                         account_with_metadata.0.clone(),
@@ -216,7 +257,12 @@ impl<T: Send> AccountPipes for AccountPipe<T> {
                     ),
                     metrics.clone(),
                 )
-                .await?;
+                .instrument(span.clone())
+                .await;
+            if result.is_err() {
+                telemetry::record_error(&span);
+            }
+            result?;
         }
         Ok(())
     }
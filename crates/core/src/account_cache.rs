@@ -0,0 +1,354 @@
+//! Provides an LRU cache of the latest decoded account states, shared between
+//! the account pipe populating it and the processors reading from it.
+//!
+//! Instruction processors frequently need the latest state of a related
+//! account, such as the pool targeted by a swap. Fetching it over RPC for
+//! every instruction is slow, while every processor keeping its own map of
+//! accounts duplicates memory and logic. The `account_cache` module keeps the
+//! most recently updated accounts of a decoder in a bounded cache that any
+//! processor holding a handle can query.
+//!
+//! # Overview
+//!
+//! - **`AccountCache`**: A cloneable handle to the shared cache, with a
+//!   configurable capacity and an optional maximum age after which entries are
+//!   considered stale.
+//! - **`CachedAccount`**: A cached account state along with its metadata.
+//! - **`AccountCacheProcessor`**: A `Processor` inserting every decoded account
+//!   into the cache before forwarding it to the wrapped processor. It is set up
+//!   automatically by `PipelineBuilder::account_with_cache`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use carbon_core::account_cache::AccountCache;
+//!
+//! let pools = AccountCache::new(10_000, Some(Duration::from_secs(60)));
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .account_with_cache(RaydiumAmmV4Decoder, PoolProcessor, pools.clone())
+//!     .instruction(RaydiumAmmV4Decoder, SwapProcessor { pools })
+//!     .build()?
+//!     .run()
+//!     .await?;
+//!
+//! // Inside `SwapProcessor::process`:
+//! if let Some(pool) = self.pools.get(&swap_accounts.amm).await {
+//!     // Use the latest pool state.
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - Updates for a slot older than the cached one are ignored, so an account
+//!   arriving out of order never overwrites a newer state.
+//! - Reading an entry marks it as recently used. When the cache is full, the
+//!   least recently used entry is evicted.
+//! - Stale entries are evicted lazily when they are looked up. Until then they
+//!   count towards the capacity and are evicted like any other entry.
+
+use {
+    crate::{
+        account::{AccountMetadata, AccountProcessorInputType, DecodedAccount},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    async_trait::async_trait,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::Mutex,
+};
+
+/// A cached account state.
+///
+/// # Fields
+///
+/// - `metadata`: The metadata of the update that produced the state.
+/// - `account`: The decoded account.
+/// - `updated_at`: When the state was inserted into the cache.
+#[derive(Debug, Clone)]
+pub struct CachedAccount<T> {
+    pub metadata: AccountMetadata,
+    pub account: DecodedAccount<T>,
+    pub updated_at: Instant,
+}
+
+struct CacheEntry<T> {
+    cached: CachedAccount<T>,
+    last_used: u64,
+}
+
+/// The cache state, tracking the recency of entries with a monotonic counter.
+struct LruState<T> {
+    capacity: usize,
+    max_age: Option<Duration>,
+    entries: HashMap<Pubkey, CacheEntry<T>>,
+    recency: BTreeMap<u64, Pubkey>,
+    counter: u64,
+}
+
+impl<T: Clone> LruState<T> {
+    fn new(capacity: usize, max_age: Option<Duration>) -> Self {
+        Self {
+            capacity,
+            max_age,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            counter: 0,
+        }
+    }
+
+    fn touch(&mut self, pubkey: Pubkey) -> u64 {
+        self.counter += 1;
+        self.recency.insert(self.counter, pubkey);
+        self.counter
+    }
+
+    fn is_stale(&self, cached: &CachedAccount<T>, now: Instant) -> bool {
+        self.max_age
+            .is_some_and(|max_age| now.duration_since(cached.updated_at) > max_age)
+    }
+
+    fn remove(&mut self, pubkey: &Pubkey) -> Option<CachedAccount<T>> {
+        let entry = self.entries.remove(pubkey)?;
+        self.recency.remove(&entry.last_used);
+        Some(entry.cached)
+    }
+
+    fn get(&mut self, pubkey: &Pubkey, now: Instant) -> Option<CachedAccount<T>> {
+        let entry = self.entries.get(pubkey)?;
+        if self.is_stale(&entry.cached, now) {
+            self.remove(pubkey);
+            return None;
+        }
+
+        let previous = entry.last_used;
+        self.recency.remove(&previous);
+        let last_used = self.touch(*pubkey);
+        let entry = self.entries.get_mut(pubkey)?;
+        entry.last_used = last_used;
+
+        Some(entry.cached.clone())
+    }
+
+    /// Inserts an account state, returning `false` if a newer state is
+    /// already cached.
+    fn insert(&mut self, cached: CachedAccount<T>) -> bool {
+        let pubkey = cached.metadata.pubkey;
+
+        if let Some(existing) = self.entries.get(&pubkey) {
+            if existing.cached.metadata.slot > cached.metadata.slot
+                && !self.is_stale(&existing.cached, cached.updated_at)
+            {
+                return false;
+            }
+        }
+        self.remove(&pubkey);
+
+        while self.capacity > 0 && self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+
+        if self.capacity == 0 {
+            return false;
+        }
+
+        let last_used = self.touch(pubkey);
+        self.entries
+            .insert(pubkey, CacheEntry { cached, last_used });
+
+        true
+    }
+}
+
+/// A cloneable handle to an LRU cache of decoded account states.
+///
+/// Clones share the same cache, so a handle can be given to the account pipe
+/// populating the cache and to any number of processors reading from it.
+///
+/// # Type Parameters
+///
+/// - `T`: The decoded account type, as produced by the decoder.
+pub struct AccountCache<T> {
+    state: Arc<Mutex<LruState<T>>>,
+}
+
+impl<T> Clone for AccountCache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> AccountCache<T> {
+    /// Creates a new cache holding at most `capacity` accounts.
+    ///
+    /// # Parameters
+    ///
+    /// - `capacity`: The maximum number of cached accounts.
+    /// - `max_age`: The maximum age of cached states. States inserted longer
+    ///   ago are treated as missing and evicted. `None` keeps states until they
+    ///   are evicted by newer ones.
+    pub fn new(capacity: usize, max_age: Option<Duration>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(LruState::new(capacity, max_age))),
+        }
+    }
+
+    /// Returns the latest cached state of the account, marking it as recently
+    /// used.
+    pub async fn get(&self, pubkey: &Pubkey) -> Option<CachedAccount<T>> {
+        self.state.lock().await.get(pubkey, Instant::now())
+    }
+
+    /// Returns the decoded data of the latest cached state of the account.
+    pub async fn get_data(&self, pubkey: &Pubkey) -> Option<T> {
+        self.get(pubkey).await.map(|cached| cached.account.data)
+    }
+
+    /// Inserts an account state. Returns `false` if the cache already holds a
+    /// state from a newer slot.
+    pub async fn insert(&self, metadata: AccountMetadata, account: DecodedAccount<T>) -> bool {
+        self.state.lock().await.insert(CachedAccount {
+            metadata,
+            account,
+            updated_at: Instant::now(),
+        })
+    }
+
+    /// Removes the account from the cache, returning its cached state.
+    pub async fn remove(&self, pubkey: &Pubkey) -> Option<CachedAccount<T>> {
+        self.state.lock().await.remove(pubkey)
+    }
+
+    /// Returns the number of cached accounts, including stale ones not yet
+    /// evicted.
+    pub async fn len(&self) -> usize {
+        self.state.lock().await.entries.len()
+    }
+
+    /// Returns `true` if the cache holds no accounts.
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Creates a processor populating this cache before forwarding every
+    /// account to `processor`.
+    pub fn processor(
+        &self,
+        processor: impl Processor<InputType = AccountProcessorInputType<T>> + Send + Sync + 'static,
+    ) -> AccountCacheProcessor<T> {
+        AccountCacheProcessor {
+            cache: self.clone(),
+            processor: Box::new(processor),
+        }
+    }
+}
+
+/// A processor inserting decoded accounts into an `AccountCache` before
+/// forwarding them to a wrapped processor.
+pub struct AccountCacheProcessor<T> {
+    cache: AccountCache<T>,
+    processor: Box<dyn Processor<InputType = AccountProcessorInputType<T>> + Send + Sync>,
+}
+
+#[async_trait]
+impl<T> Processor for AccountCacheProcessor<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type InputType = AccountProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, decoded_account, _) = &data;
+
+        if !self
+            .cache
+            .insert(metadata.clone(), decoded_account.clone())
+            .await
+        {
+            metrics
+                .increment_counter("account_cache_outdated_updates", 1)
+                .await?;
+        }
+
+        metrics
+            .update_gauge("account_cache_entries", self.cache.len().await as f64)
+            .await?;
+
+        self.processor.process(data, metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(pubkey: Pubkey, slot: u64, data: u64, updated_at: Instant) -> CachedAccount<u64> {
+        CachedAccount {
+            metadata: AccountMetadata {
+                slot,
+                pubkey,
+                created: false,
+            },
+            account: DecodedAccount {
+                lamports: 0,
+                data,
+                owner: Pubkey::default(),
+                executable: false,
+                rent_epoch: 0,
+            },
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_least_recently_used_entry_is_evicted() {
+        let mut state = LruState::new(2, None);
+        let (first, second, third) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let now = Instant::now();
+
+        assert!(state.insert(cached(first, 1, 1, now)));
+        assert!(state.insert(cached(second, 1, 2, now)));
+        assert!(state.get(&first, now).is_some());
+        assert!(state.insert(cached(third, 1, 3, now)));
+
+        assert!(state.get(&second, now).is_none());
+        assert_eq!(state.get(&first, now).map(|c| c.account.data), Some(1));
+        assert_eq!(state.get(&third, now).map(|c| c.account.data), Some(3));
+    }
+
+    #[test]
+    fn test_older_slot_and_stale_entries() {
+        let mut state = LruState::new(10, Some(Duration::from_secs(5)));
+        let pubkey = Pubkey::new_unique();
+        let now = Instant::now();
+
+        assert!(state.insert(cached(pubkey, 10, 1, now)));
+        assert!(!state.insert(cached(pubkey, 9, 2, now)));
+        assert_eq!(state.get(&pubkey, now).map(|c| c.account.data), Some(1));
+
+        let later = now + Duration::from_secs(6);
+        assert!(state.get(&pubkey, later).is_none());
+        assert!(state.entries.is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+//! Flags the first update of each account, so processors can tell account
+//! creations from updates.
+//!
+//! Account updates carry the latest state of an account, without telling
+//! whether the account was just created. Processors running one-time work on
+//! new accounts, such as fetching token metadata or inserting a row, would
+//! otherwise have to track the accounts they have seen themselves. The
+//! `account_creation` module keeps the set of seen accounts in a
+//! `SeenAccountsStore`, and the pipeline sets `AccountMetadata::created` on the
+//! first update of every account.
+//!
+//! # Overview
+//!
+//! - **`SeenAccountsStore`**: Keeps the set of accounts the pipeline has seen.
+//!   Implementations backed by a database keep the set across restarts.
+//! - **`InMemorySeenAccounts`**: A `SeenAccountsStore` keeping the set in
+//!   memory.
+//! - **`PipelineBuilder::track_account_creation`**: Enables the `created` flag
+//!   of account updates, using the given store.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::account_creation::InMemorySeenAccounts;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .track_account_creation(InMemorySeenAccounts::new())
+//!     .account(TokenProgramDecoder, MintProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//!
+//! // Inside `MintProcessor::process`:
+//! let (metadata, mint, _) = data;
+//! if metadata.created {
+//!     fetch_and_store_metadata(&metadata.pubkey).await?;
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - Without tracking, `AccountMetadata::created` is always `false`.
+//! - Closing an account, either through an account deletion update or an update
+//!   leaving it without lamports, removes it from the store, so an account
+//!   created again at the same address is flagged again.
+//! - With an in-memory store, every account is flagged on its first update
+//!   after a restart. Processors should treat creations idempotently, or use a
+//!   persistent store.
+
+use {
+    crate::error::CarbonResult, async_trait::async_trait, solana_pubkey::Pubkey,
+    std::collections::HashSet, tokio::sync::RwLock,
+};
+
+/// Keeps the set of accounts seen by a pipeline.
+#[async_trait]
+pub trait SeenAccountsStore: Send + Sync {
+    /// Marks an account as seen, returning `true` if it wasn't seen before.
+    async fn insert(&self, pubkey: Pubkey) -> CarbonResult<bool>;
+
+    /// Forgets an account, typically once it has been closed.
+    async fn remove(&self, pubkey: &Pubkey) -> CarbonResult<()>;
+}
+
+/// A `SeenAccountsStore` keeping the set of seen accounts in memory.
+#[derive(Debug, Default)]
+pub struct InMemorySeenAccounts {
+    accounts: RwLock<HashSet<Pubkey>>,
+}
+
+impl InMemorySeenAccounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a store considering the given accounts as already seen, such
+    /// as the accounts stored by a previous run.
+    pub fn with_accounts(accounts: impl IntoIterator<Item = Pubkey>) -> Self {
+        Self {
+            accounts: RwLock::new(accounts.into_iter().collect()),
+        }
+    }
+
+    /// Returns the number of seen accounts.
+    pub async fn len(&self) -> usize {
+        self.accounts.read().await.len()
+    }
+
+    /// Returns `true` if no account was seen.
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+#[async_trait]
+impl SeenAccountsStore for InMemorySeenAccounts {
+    async fn insert(&self, pubkey: Pubkey) -> CarbonResult<bool> {
+        if self.accounts.read().await.contains(&pubkey) {
+            return Ok(false);
+        }
+
+        Ok(self.accounts.write().await.insert(pubkey))
+    }
+
+    async fn remove(&self, pubkey: &Pubkey) -> CarbonResult<()> {
+        self.accounts.write().await.remove(pubkey);
+        Ok(())
+    }
+}
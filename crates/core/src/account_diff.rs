@@ -0,0 +1,784 @@
+//! Provides a diffing engine for decoded accounts, processors publishing
+//! field-level changes as events and processors delivering the previous value
+//! of each account along with the new one.
+//!
+//! Audit-log style tables ("field X changed from A to B at slot S") usually
+//! require a custom processor that remembers the previous state of every
+//! account. The `account_diff` module packages that logic: it keeps the last
+//! decoded snapshot of each account, compares it with the next one and hands
+//! one `AccountFieldChange` per modified field to a downstream processor, such
+//! as a database sink.
+//!
+//! Processors reacting to state transitions, such as a pool being paused or a
+//! bonding curve completing, rather need the typed value an update replaces.
+//! `AccountDeltaProcessor` tracks the same states and hands the previous
+//! decoded value to the processor next to the new one. Types deriving
+//! `CarbonDiff` then compute the fields that changed, so sinks can write only
+//! the changed columns.
+//!
+//! # Overview
+//!
+//! - **`diff_values`**: Compares two JSON snapshots and returns every changed
+//!   leaf field, addressed by a dotted path such as `reserves.base` or
+//!   `fees[1]`.
+//! - **`AccountFieldChange`**: A single field change, including the account,
+//!   the slot and both values.
+//! - **`AccountDiffProcessor`**: A `Processor` registered on an account pipe
+//!   which tracks snapshots and forwards changes to the wrapped processor.
+//! - **`AccountDeltaInputType`**: The input of delta processors, holding the
+//!   previous decoded value of the account, if any, and the new one.
+//! - **`AccountDeltaProcessor`**: A `Processor` registered on an account pipe
+//!   which tracks the previous values and forwards the deltas to the wrapped
+//!   processor. It is set up automatically by
+//!   `PipelineBuilder::account_deltas`.
+//! - **`CarbonDiff`**: Compares two values of a type, returning a changeset
+//!   holding the new value of each changed field. `#[derive(CarbonDiff)]`
+//!   implements it for structs with named fields, generating a
+//!   `<Name>Changeset` struct.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::account_diff::AccountDiffProcessor;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .account(
+//!         PumpfunDecoder,
+//!         AccountDiffProcessor::with_snapshot(
+//!             |account: &PumpfunAccount| match account {
+//!                 PumpfunAccount::BondingCurve(curve) => Ok(Some((
+//!                     "BondingCurve".to_string(),
+//!                     serde_json::to_value(curve)
+//!                         .map_err(|error| Error::Custom(error.to_string()))?,
+//!                 ))),
+//!                 _ => Ok(None),
+//!             },
+//!             AuditLogSink,
+//!         ),
+//!     )
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! Delta processors compare the typed values instead:
+//!
+//! ```ignore
+//! use carbon_core::{
+//!     account_diff::{AccountDeltaInputType, CarbonDiff as _},
+//!     CarbonDiff,
+//! };
+//!
+//! #[derive(CarbonDiff, CarbonDeserialize, Debug, Clone, PartialEq)]
+//! pub struct BondingCurve {
+//!     pub virtual_token_reserves: u64,
+//!     pub virtual_sol_reserves: u64,
+//!     pub complete: bool,
+//! }
+//!
+//! #[async_trait]
+//! impl Processor for CurveProcessor {
+//!     type InputType = AccountDeltaInputType<PumpfunAccount>;
+//!
+//!     async fn process(
+//!         &mut self,
+//!         (metadata, old, new, _): Self::InputType,
+//!         _metrics: Arc<MetricsCollection>,
+//!     ) -> CarbonResult<()> {
+//!         if let (
+//!             Some(PumpfunAccount::BondingCurve(old)),
+//!             PumpfunAccount::BondingCurve(new),
+//!         ) = (old, &new.data)
+//!         {
+//!             let changeset = old.diff(new);
+//!             if changeset.complete == Some(true) {
+//!                 self.notify_migration(metadata.pubkey).await?;
+//!             }
+//!             self.sink.update_columns(metadata.pubkey, changeset.changed_fields()).await?;
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .account_deltas(PumpfunDecoder, CurveProcessor::new(sink))
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - `AccountDiffProcessor::new` snapshots accounts through `serde::Serialize`
+//!   and takes the account type name from the serialized enum variant. Account
+//!   types without a `Serialize` implementation can provide their own snapshot
+//!   function through `AccountDiffProcessor::with_snapshot`.
+//! - The first snapshot observed for an account is stored without emitting
+//!   changes, since there is nothing to compare it with. Likewise, the previous
+//!   value of a delta is `None` for the first update of an account seen by the
+//!   pipe, including after a restart.
+//! - Updates for a slot older than the tracked one are dropped, so changes and
+//!   deltas never go back in time.
+//! - Closed accounts, left without lamports, and deleted accounts are no longer
+//!   tracked, so an account reopened at the same address starts over. Deletions
+//!   are only seen through the account deletion pipe returned by `deletions`,
+//!   which `PipelineBuilder::account_deltas` registers automatically.
+//! - The last state of every account seen is kept in memory, so these
+//!   processors are best restricted to decoders of a bounded set of accounts.
+
+use {
+    crate::{
+        account::{AccountMetadata, AccountProcessorInputType, DecodedAccount},
+        datasource::AccountDeletion,
+        error::{CarbonResult, Error},
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    async_trait::async_trait,
+    serde::Serialize,
+    serde_json::Value,
+    solana_pubkey::Pubkey,
+    std::{collections::HashMap, sync::Arc},
+    tokio::sync::Mutex,
+};
+
+/// A single field that changed between two snapshots.
+///
+/// # Fields
+///
+/// - `path`: The dotted path of the field, with array indices in brackets.
+/// - `old`: The previous value, or `Value::Null` if the field was added.
+/// - `new`: The new value, or `Value::Null` if the field was removed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Compares two JSON snapshots and returns the changed leaf fields.
+///
+/// Objects are compared key by key and arrays index by index. Any other value
+/// is compared as a whole. The returned changes are ordered by path.
+pub fn diff_values(old: &Value, new: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_into(String::new(), old, new, &mut changes);
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn diff_into(path: String, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(old_fields), Value::Object(new_fields)) => {
+            for (key, old_value) in old_fields {
+                let new_value = new_fields.get(key).unwrap_or(&Value::Null);
+                diff_into(join_path(&path, key), old_value, new_value, changes);
+            }
+            for (key, new_value) in new_fields {
+                if !old_fields.contains_key(key) {
+                    diff_into(join_path(&path, key), &Value::Null, new_value, changes);
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                diff_into(
+                    format!("{path}[{index}]"),
+                    old_items.get(index).unwrap_or(&Value::Null),
+                    new_items.get(index).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        _ if old != new => changes.push(FieldChange {
+            path,
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// The error returned when an update is older than the tracked state of its
+/// account.
+struct OutdatedUpdate;
+
+/// The last state of each account seen by an account pipe, along with its
+/// slot.
+///
+/// Clones share the same states, so that the account deletion pipe returned by
+/// `deletions` can forget deleted accounts.
+struct AccountStates<V> {
+    states: Arc<Mutex<HashMap<Pubkey, (u64, V)>>>,
+}
+
+impl<V> Clone for AccountStates<V> {
+    fn clone(&self) -> Self {
+        Self {
+            states: self.states.clone(),
+        }
+    }
+}
+
+impl<V> AccountStates<V> {
+    fn new() -> Self {
+        Self {
+            states: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets the state of an account at `slot`, or forgets the account if
+    /// `state` is `None`, and returns its previous state.
+    ///
+    /// Nothing changes if the account has a state from a newer slot.
+    async fn update(
+        &self,
+        pubkey: Pubkey,
+        slot: u64,
+        state: Option<V>,
+    ) -> Result<Option<V>, OutdatedUpdate> {
+        let mut states = self.states.lock().await;
+        if let Some((tracked_slot, _)) = states.get(&pubkey) {
+            if *tracked_slot > slot {
+                return Err(OutdatedUpdate);
+            }
+        }
+
+        let previous = match state {
+            Some(state) => states.insert(pubkey, (slot, state)),
+            None => states.remove(&pubkey),
+        };
+
+        Ok(previous.map(|(_, state)| state))
+    }
+
+    async fn len(&self) -> usize {
+        self.states.lock().await.len()
+    }
+}
+
+/// A processor forgetting the states tracked for deleted accounts.
+///
+/// It is returned by `AccountDiffProcessor::deletions` and
+/// `AccountDeltaProcessor::deletions`, to be registered as an account deletion
+/// pipe.
+pub struct TrackedAccountDeletions<V> {
+    states: AccountStates<V>,
+}
+
+#[async_trait]
+impl<V> Processor for TrackedAccountDeletions<V>
+where
+    V: Send + Sync + 'static,
+{
+    type InputType = AccountDeletion;
+
+    async fn process(
+        &mut self,
+        account_deletion: AccountDeletion,
+        _metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        // Deletions older than the tracked state are ignored.
+        let _ = self
+            .states
+            .update(account_deletion.pubkey, account_deletion.slot, None)
+            .await;
+
+        Ok(())
+    }
+}
+
+/// A field change of a decoded account, ready to be published to a sink.
+///
+/// # Fields
+///
+/// - `pubkey`: The public key of the changed account.
+/// - `slot`: The slot of the update introducing the change.
+/// - `account_type`: The name of the decoded account type.
+/// - `field`: The dotted path of the changed field.
+/// - `old`: The value before the update.
+/// - `new`: The value after the update.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountFieldChange {
+    pub pubkey: Pubkey,
+    pub slot: u64,
+    pub account_type: String,
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// A function snapshotting decoded accounts, returning the account type name
+/// along with its fields.
+type SnapshotFn<T> = dyn Fn(&T) -> CarbonResult<Option<(String, Value)>> + Send + Sync;
+
+/// Tracks decoded account snapshots and forwards their field changes to a
+/// wrapped processor.
+///
+/// # Type Parameters
+///
+/// - `T`: The decoded account type, as produced by the decoder.
+pub struct AccountDiffProcessor<T> {
+    processor: Box<dyn Processor<InputType = AccountFieldChange> + Send + Sync>,
+    account_types: Option<Vec<String>>,
+    snapshots: AccountStates<(String, Value)>,
+    snapshot: Box<SnapshotFn<T>>,
+}
+
+impl<T: Serialize> AccountDiffProcessor<T> {
+    /// Creates a new `AccountDiffProcessor` forwarding every change to
+    /// `processor`, snapshotting accounts through their `Serialize`
+    /// implementation.
+    pub fn new(
+        processor: impl Processor<InputType = AccountFieldChange> + Send + Sync + 'static,
+    ) -> Self {
+        Self::with_snapshot(
+            |account: &T| {
+                let value = serde_json::to_value(account)
+                    .map_err(|error| Error::Custom(error.to_string()))?;
+                Ok(Some(split_account_type::<T>(value)))
+            },
+            processor,
+        )
+    }
+}
+
+impl<T> AccountDiffProcessor<T> {
+    /// Creates a new `AccountDiffProcessor` with a custom snapshot function.
+    ///
+    /// This is useful for account types that do not implement `Serialize`,
+    /// such as decoder account enums whose variants do. The function returns
+    /// the account type name along with its fields, or `None` to skip the
+    /// account.
+    pub fn with_snapshot(
+        snapshot: impl Fn(&T) -> CarbonResult<Option<(String, Value)>> + Send + Sync + 'static,
+        processor: impl Processor<InputType = AccountFieldChange> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            processor: Box::new(processor),
+            account_types: None,
+            snapshots: AccountStates::new(),
+            snapshot: Box::new(snapshot),
+        }
+    }
+
+    /// Restricts diffing to the given account types, identified by the name
+    /// of the decoder's account enum variant. All account types are diffed by
+    /// default.
+    pub fn with_account_types(mut self, account_types: Vec<&str>) -> Self {
+        self.account_types = Some(account_types.into_iter().map(String::from).collect());
+        self
+    }
+
+    /// Returns a processor forgetting the snapshots of deleted accounts, to be
+    /// registered with `PipelineBuilder::account_deletions`.
+    pub fn deletions(&self) -> TrackedAccountDeletions<(String, Value)> {
+        TrackedAccountDeletions {
+            states: self.snapshots.clone(),
+        }
+    }
+
+    fn is_selected(&self, account_type: &str) -> bool {
+        self.account_types
+            .as_ref()
+            .is_none_or(|types| types.iter().any(|selected| selected == account_type))
+    }
+}
+
+/// Splits a serialized account into its type name and its fields.
+///
+/// Decoder account enums serialize as `{ "Variant": { ...fields } }`; any
+/// other shape is diffed as a whole under the Rust type name.
+fn split_account_type<T>(value: Value) -> (String, Value) {
+    match value {
+        Value::Object(fields) if fields.len() == 1 => fields
+            .into_iter()
+            .next()
+            .expect("object has exactly one field"),
+        value => (std::any::type_name::<T>().to_string(), value),
+    }
+}
+
+#[async_trait]
+impl<T> Processor for AccountDiffProcessor<T>
+where
+    T: Send + 'static,
+{
+    type InputType = AccountProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        (metadata, decoded_account, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let Some((account_type, snapshot)) = (self.snapshot)(&decoded_account.data)? else {
+            return Ok(());
+        };
+
+        if !self.is_selected(&account_type) {
+            return Ok(());
+        }
+
+        // Closed accounts are diffed one last time, then forgotten.
+        let state =
+            (decoded_account.lamports != 0).then(|| (account_type.clone(), snapshot.clone()));
+
+        // Updates can arrive out of order; never diff against a newer
+        // snapshot.
+        let Ok(previous) = self
+            .snapshots
+            .update(metadata.pubkey, metadata.slot, state)
+            .await
+        else {
+            return Ok(());
+        };
+
+        if let Some((previous_type, previous_snapshot)) = previous {
+            if previous_type == account_type {
+                let changes = diff_values(&previous_snapshot, &snapshot);
+
+                metrics
+                    .increment_counter("account_diff_changes", changes.len() as u64)
+                    .await?;
+
+                for change in changes {
+                    self.processor
+                        .process(
+                            AccountFieldChange {
+                                pubkey: metadata.pubkey,
+                                slot: metadata.slot,
+                                account_type: account_type.clone(),
+                                field: change.path,
+                                old: change.old,
+                                new: change.new,
+                            },
+                            metrics.clone(),
+                        )
+                        .await?;
+                }
+            }
+        }
+
+        metrics
+            .update_gauge(
+                "account_diff_tracked_accounts",
+                self.snapshots.len().await as f64,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// The input type for account delta processors.
+///
+/// - `AccountMetadata`: Metadata associated with the account.
+/// - `Option<T>`: The previous decoded value of the account, if any.
+/// - `DecodedAccount<T>`: The newly decoded account.
+/// - `solana_account::Account`: The raw account.
+pub type AccountDeltaInputType<T> = (
+    AccountMetadata,
+    Option<T>,
+    DecodedAccount<T>,
+    solana_account::Account,
+);
+
+/// Compares two values of a type field by field.
+///
+/// `#[derive(CarbonDiff)]` implements this trait for structs with named
+/// fields. The generated `<Name>Changeset` struct has an `Option` of each
+/// field, set to the new value of the fields which changed, along with
+/// `is_empty` and `changed_fields` methods.
+pub trait CarbonDiff {
+    /// The fields which changed between two values.
+    type Changeset;
+
+    /// Returns the fields of `new` which differ from `self`.
+    fn diff(&self, new: &Self) -> Self::Changeset;
+}
+
+/// Tracks the last decoded value of each account, and forwards it to a wrapped
+/// processor along with the new one.
+///
+/// # Type Parameters
+///
+/// - `T`: The decoded account type, as produced by the decoder.
+pub struct AccountDeltaProcessor<T> {
+    processor: Box<dyn Processor<InputType = AccountDeltaInputType<T>> + Send + Sync>,
+    previous: AccountStates<T>,
+}
+
+impl<T> AccountDeltaProcessor<T> {
+    /// Creates a new `AccountDeltaProcessor` forwarding every delta to
+    /// `processor`.
+    pub fn new(
+        processor: impl Processor<InputType = AccountDeltaInputType<T>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            processor: Box::new(processor),
+            previous: AccountStates::new(),
+        }
+    }
+
+    /// Returns a processor forgetting the values of deleted accounts, to be
+    /// registered with `PipelineBuilder::account_deletions`.
+    pub fn deletions(&self) -> TrackedAccountDeletions<T> {
+        TrackedAccountDeletions {
+            states: self.previous.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Processor for AccountDeltaProcessor<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type InputType = AccountProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        (metadata, decoded_account, account): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        // Closed accounts are delivered one last time, then forgotten.
+        let state = (decoded_account.lamports != 0).then(|| decoded_account.data.clone());
+
+        let Ok(old) = self
+            .previous
+            .update(metadata.pubkey, metadata.slot, state)
+            .await
+        else {
+            metrics
+                .increment_counter("account_delta_out_of_order", 1)
+                .await?;
+            return Ok(());
+        };
+
+        metrics
+            .update_gauge(
+                "account_delta_tracked_accounts",
+                self.previous.len().await as f64,
+            )
+            .await?;
+
+        self.processor
+            .process((metadata, old, decoded_account, account), metrics)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json, std::sync::Mutex as StdMutex};
+
+    #[test]
+    fn test_diff_values_reports_nested_changes() {
+        // Arrange
+        let old =
+            json!({ "reserves": { "base": 10, "quote": 20 }, "fees": [1, 2], "paused": false });
+        let new =
+            json!({ "reserves": { "base": 15, "quote": 20 }, "fees": [1, 3, 4], "paused": false });
+
+        // Act
+        let changes = diff_values(&old, &new);
+
+        // Assert
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    path: "fees[1]".to_string(),
+                    old: json!(2),
+                    new: json!(3),
+                },
+                FieldChange {
+                    path: "fees[2]".to_string(),
+                    old: Value::Null,
+                    new: json!(4),
+                },
+                FieldChange {
+                    path: "reserves.base".to_string(),
+                    old: json!(10),
+                    new: json!(15),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_account_type_uses_enum_variant() {
+        let (account_type, fields) =
+            split_account_type::<()>(json!({ "BondingCurve": { "complete": true } }));
+
+        assert_eq!(account_type, "BondingCurve");
+        assert_eq!(fields, json!({ "complete": true }));
+    }
+
+    fn account(
+        pubkey: Pubkey,
+        slot: u64,
+        lamports: u64,
+        data: u64,
+    ) -> AccountProcessorInputType<u64> {
+        (
+            AccountMetadata {
+                slot,
+                pubkey,
+                created: false,
+            },
+            DecodedAccount {
+                lamports,
+                data,
+                owner: Pubkey::default(),
+                executable: false,
+                rent_epoch: 0,
+            },
+            solana_account::Account::default(),
+        )
+    }
+
+    type Deltas = Arc<StdMutex<Vec<(Option<u64>, u64)>>>;
+
+    struct RecordingProcessor(Deltas);
+
+    #[async_trait]
+    impl Processor for RecordingProcessor {
+        type InputType = AccountDeltaInputType<u64>;
+
+        async fn process(
+            &mut self,
+            (_, old, new, _): Self::InputType,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            self.0.lock().unwrap().push((old, new.data));
+            Ok(())
+        }
+    }
+
+    struct ChangeRecorder(Arc<StdMutex<Vec<AccountFieldChange>>>);
+
+    #[async_trait]
+    impl Processor for ChangeRecorder {
+        type InputType = AccountFieldChange;
+
+        async fn process(
+            &mut self,
+            change: AccountFieldChange,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            self.0.lock().unwrap().push(change);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forwards_previous_value() {
+        let deltas = Arc::new(StdMutex::new(Vec::new()));
+        let mut processor = AccountDeltaProcessor::new(RecordingProcessor(deltas.clone()));
+        let pubkey = Pubkey::new_unique();
+
+        for (slot, value) in [(1, 10), (3, 30), (2, 20)] {
+            processor
+                .process(
+                    account(pubkey, slot, 1, value),
+                    Arc::new(MetricsCollection::new(vec![])),
+                )
+                .await
+                .unwrap();
+        }
+
+        // The update of slot 2 arrives after the one of slot 3 and is dropped.
+        assert_eq!(*deltas.lock().unwrap(), vec![(None, 10), (Some(10), 30)]);
+    }
+
+    #[tokio::test]
+    async fn test_closed_and_deleted_accounts_are_forgotten() {
+        let deltas = Arc::new(StdMutex::new(Vec::new()));
+        let mut processor = AccountDeltaProcessor::new(RecordingProcessor(deltas.clone()));
+        let mut deletions = processor.deletions();
+        let metrics = Arc::new(MetricsCollection::new(vec![]));
+        let (closed, deleted) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        for update in [
+            account(closed, 1, 1, 10),
+            account(closed, 2, 0, 10),
+            account(closed, 3, 1, 30),
+            account(deleted, 1, 1, 40),
+        ] {
+            processor.process(update, metrics.clone()).await.unwrap();
+        }
+        for slot in [1, 2] {
+            deletions
+                .process(
+                    AccountDeletion {
+                        pubkey: deleted,
+                        slot,
+                    },
+                    metrics.clone(),
+                )
+                .await
+                .unwrap();
+        }
+        processor
+            .process(account(deleted, 3, 1, 50), metrics.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *deltas.lock().unwrap(),
+            vec![
+                (None, 10),
+                (Some(10), 10),
+                // The account was reopened after being closed.
+                (None, 30),
+                (None, 40),
+                (None, 50),
+            ]
+        );
+        assert_eq!(processor.previous.len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_diff_processor_forgets_deleted_accounts() {
+        let changes = Arc::new(StdMutex::new(Vec::new()));
+        let mut processor = AccountDiffProcessor::<u64>::new(ChangeRecorder(changes.clone()));
+        let mut deletions = processor.deletions();
+        let metrics = Arc::new(MetricsCollection::new(vec![]));
+        let pubkey = Pubkey::new_unique();
+
+        for update in [account(pubkey, 1, 1, 10), account(pubkey, 2, 1, 20)] {
+            processor.process(update, metrics.clone()).await.unwrap();
+        }
+        deletions
+            .process(AccountDeletion { pubkey, slot: 3 }, metrics.clone())
+            .await
+            .unwrap();
+        processor
+            .process(account(pubkey, 4, 1, 40), metrics.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![AccountFieldChange {
+                pubkey,
+                slot: 2,
+                account_type: "u64".to_string(),
+                field: String::new(),
+                old: json!(10),
+                new: json!(20),
+            }]
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Resolves the addresses v0 transactions load from address lookup tables.
+//!
+//! Instructions of v0 transactions reference accounts loaded from address
+//! lookup tables by index. The runtime reports the loaded addresses in the
+//! transaction status metadata, but some datasources, such as shred streams,
+//! deliver transactions without it. Instructions then reference accounts the
+//! decoders can't see, and their account arrangement fails. The
+//! `address_lookup_table` module fetches the lookup tables of such
+//! transactions and fills in their loaded addresses before instructions are
+//! extracted and matched.
+//!
+//! # Overview
+//!
+//! - **`LookupTableSource`**: Fetches lookup table accounts, typically over
+//!   RPC. `carbon-rpc-client` provides an implementation.
+//! - **`AddressLookupTableResolver`**: Resolves the loaded addresses of
+//!   transactions, keeping the fetched lookup tables in an internal cache. It
+//!   is registered through `PipelineBuilder::address_lookup_table_resolver`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::address_lookup_table::AddressLookupTableResolver;
+//! use carbon_rpc_client::RpcLookupTableSource;
+//!
+//! let resolver = AddressLookupTableResolver::new(Arc::new(RpcLookupTableSource::new(
+//!     Arc::new(RpcClient::new(rpc_url)),
+//! )));
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(shredstream_datasource)
+//!     .address_lookup_table_resolver(resolver)
+//!     .instruction(JupiterSwapDecoder, SwapProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Transactions whose metadata already holds loaded addresses are left
+//!   untouched, so the resolver doesn't fetch anything for datasources that
+//!   provide them.
+//! - Lookup tables are append-only, so cached tables are only refetched when a
+//!   transaction references an index past their cached length.
+//! - `TransactionMetadata::account_keys` returns the fully resolved account
+//!   keys of a transaction.
+
+use {
+    crate::{
+        datasource::TransactionUpdate,
+        error::{CarbonResult, Error},
+    },
+    async_trait::async_trait,
+    solana_program::message::{
+        v0::{LoadedAddresses, MessageAddressTableLookup},
+        VersionedMessage,
+    },
+    solana_pubkey::Pubkey,
+    std::{collections::HashMap, sync::Arc},
+    tokio::sync::RwLock,
+};
+
+/// The size of the metadata preceding the addresses of a lookup table.
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+/// The discriminator of an initialized lookup table.
+const LOOKUP_TABLE_DISCRIMINATOR: u32 = 1;
+
+/// Fetches the data of address lookup table accounts.
+#[async_trait]
+pub trait LookupTableSource: Send + Sync {
+    /// Returns the data of each lookup table, in order, or `None` for tables
+    /// that don't exist.
+    async fn fetch_lookup_tables(&self, tables: &[Pubkey]) -> CarbonResult<Vec<Option<Vec<u8>>>>;
+}
+
+/// Parses the addresses stored in a lookup table account.
+pub fn parse_lookup_table_addresses(data: &[u8]) -> CarbonResult<Vec<Pubkey>> {
+    let discriminator = data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+    if discriminator != Some(LOOKUP_TABLE_DISCRIMINATOR) || data.len() < LOOKUP_TABLE_META_SIZE {
+        return Err(Error::Custom(
+            "Account is not an initialized address lookup table".to_string(),
+        ));
+    }
+
+    data[LOOKUP_TABLE_META_SIZE..]
+        .chunks(32)
+        .map(|chunk| {
+            Pubkey::try_from(chunk).map_err(|_| {
+                Error::Custom("Address lookup table has a truncated address".to_string())
+            })
+        })
+        .collect()
+}
+
+/// Resolves the addresses loaded from address lookup tables by v0
+/// transactions, caching the fetched tables.
+pub struct AddressLookupTableResolver {
+    source: Arc<dyn LookupTableSource>,
+    tables: RwLock<HashMap<Pubkey, Arc<Vec<Pubkey>>>>,
+}
+
+impl AddressLookupTableResolver {
+    pub fn new(source: Arc<dyn LookupTableSource>) -> Self {
+        Self {
+            source,
+            tables: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Fills in the loaded addresses of a v0 transaction lacking them.
+    ///
+    /// Returns `true` if the loaded addresses were resolved.
+    pub async fn resolve_transaction(
+        &self,
+        transaction_update: &mut TransactionUpdate,
+    ) -> CarbonResult<bool> {
+        let loaded_addresses = &transaction_update.meta.loaded_addresses;
+        if !loaded_addresses.writable.is_empty() || !loaded_addresses.readonly.is_empty() {
+            return Ok(false);
+        }
+
+        let VersionedMessage::V0(message) = &transaction_update.transaction.message else {
+            return Ok(false);
+        };
+        if message.address_table_lookups.is_empty() {
+            return Ok(false);
+        }
+
+        transaction_update.meta.loaded_addresses =
+            self.resolve(&message.address_table_lookups).await?;
+
+        Ok(true)
+    }
+
+    /// Resolves the addresses loaded by a message's lookups, writable
+    /// addresses of every table first, like the runtime does.
+    pub async fn resolve(
+        &self,
+        lookups: &[MessageAddressTableLookup],
+    ) -> CarbonResult<LoadedAddresses> {
+        let tables = self.tables_for(lookups).await?;
+        let mut loaded_addresses = LoadedAddresses::default();
+
+        for (lookup, table) in lookups.iter().zip(&tables) {
+            loaded_addresses.writable.extend(select(
+                table,
+                &lookup.writable_indexes,
+                &lookup.account_key,
+            )?);
+        }
+        for (lookup, table) in lookups.iter().zip(&tables) {
+            loaded_addresses.readonly.extend(select(
+                table,
+                &lookup.readonly_indexes,
+                &lookup.account_key,
+            )?);
+        }
+
+        Ok(loaded_addresses)
+    }
+
+    /// Returns the number of cached lookup tables.
+    pub async fn cached_tables(&self) -> usize {
+        self.tables.read().await.len()
+    }
+
+    /// Returns the addresses of the tables of each lookup, fetching the tables
+    /// which aren't cached or are too short for the lookup.
+    async fn tables_for(
+        &self,
+        lookups: &[MessageAddressTableLookup],
+    ) -> CarbonResult<Vec<Arc<Vec<Pubkey>>>> {
+        let missing: Vec<Pubkey> = {
+            let tables = self.tables.read().await;
+            lookups
+                .iter()
+                .filter(|lookup| {
+                    tables
+                        .get(&lookup.account_key)
+                        .is_none_or(|table| table.len() <= max_index(lookup))
+                })
+                .map(|lookup| lookup.account_key)
+                .collect()
+        };
+
+        if !missing.is_empty() {
+            let fetched = self.source.fetch_lookup_tables(&missing).await?;
+            let mut tables = self.tables.write().await;
+            for (address, data) in missing.iter().zip(fetched) {
+                let data = data.ok_or_else(|| {
+                    Error::Custom(format!("Address lookup table {} not found", address))
+                })?;
+                tables.insert(*address, Arc::new(parse_lookup_table_addresses(&data)?));
+            }
+        }
+
+        let tables = self.tables.read().await;
+        lookups
+            .iter()
+            .map(|lookup| {
+                tables.get(&lookup.account_key).cloned().ok_or_else(|| {
+                    Error::Custom(format!(
+                        "Address lookup table {} not found",
+                        lookup.account_key
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+fn max_index(lookup: &MessageAddressTableLookup) -> usize {
+    lookup
+        .writable_indexes
+        .iter()
+        .chain(&lookup.readonly_indexes)
+        .max()
+        .map_or(0, |index| *index as usize)
+}
+
+fn select(table: &[Pubkey], indexes: &[u8], table_address: &Pubkey) -> CarbonResult<Vec<Pubkey>> {
+    indexes
+        .iter()
+        .map(|index| {
+            table.get(*index as usize).copied().ok_or_else(|| {
+                Error::Custom(format!(
+                    "Index {} out of bounds of address lookup table {}",
+                    index, table_address
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticSource(HashMap<Pubkey, Vec<u8>>);
+
+    #[async_trait]
+    impl LookupTableSource for StaticSource {
+        async fn fetch_lookup_tables(
+            &self,
+            tables: &[Pubkey],
+        ) -> CarbonResult<Vec<Option<Vec<u8>>>> {
+            Ok(tables
+                .iter()
+                .map(|table| self.0.get(table).cloned())
+                .collect())
+        }
+    }
+
+    fn lookup_table_data(addresses: &[Pubkey]) -> Vec<u8> {
+        let mut data = vec![0; LOOKUP_TABLE_META_SIZE];
+        data[..4].copy_from_slice(&LOOKUP_TABLE_DISCRIMINATOR.to_le_bytes());
+        for address in addresses {
+            data.extend_from_slice(address.as_ref());
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_resolve_orders_writable_before_readonly() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let first_addresses: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let second_addresses: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+        let resolver = AddressLookupTableResolver::new(Arc::new(StaticSource(HashMap::from([
+            (first, lookup_table_data(&first_addresses)),
+            (second, lookup_table_data(&second_addresses)),
+        ]))));
+
+        let loaded_addresses = resolver
+            .resolve(&[
+                MessageAddressTableLookup {
+                    account_key: first,
+                    writable_indexes: vec![2],
+                    readonly_indexes: vec![0],
+                },
+                MessageAddressTableLookup {
+                    account_key: second,
+                    writable_indexes: vec![1],
+                    readonly_indexes: vec![],
+                },
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            loaded_addresses.writable,
+            [first_addresses[2], second_addresses[1]]
+        );
+        assert_eq!(loaded_addresses.readonly, [first_addresses[0]]);
+        assert_eq!(resolver.cached_tables().await, 2);
+
+        assert!(resolver
+            .resolve(&[MessageAddressTableLookup {
+                account_key: second,
+                writable_indexes: vec![5],
+                readonly_indexes: vec![],
+            }])
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,354 @@
+//! Watches numeric fields of decoded accounts, such as pool reserves or oracle
+//! prices, and alerts on abnormal changes within a time window.
+//!
+//! Risk teams monitoring the health of a protocol care less about the value of
+//! a field than about how fast it moves: a pool losing half of its reserves in
+//! a minute or an oracle price jumping far outside its recent range. An
+//! `AnomalyDetector` wraps the processor of an account pipe, extracts the
+//! watched fields from each decoded account, keeps their recent values per
+//! account, and notifies `AnomalyAlertSink`s when a value breaks the rule of
+//! its field.
+//!
+//! # Overview
+//!
+//! - **`AnomalyRule`**: The change considered abnormal, either a percent change
+//!   from the oldest value of the window, or a z-score against the values of
+//!   the window.
+//! - **`AnomalyDetector`**: A `Processor` evaluating the rules of the watched
+//!   fields before forwarding accounts to the wrapped processor.
+//! - **`AnomalyAlertSink`**: Receives an `AnomalyAlert` for each abnormal
+//!   value. `LogAnomalySink` logs them.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::anomaly::{AnomalyDetector, AnomalyRule, LogAnomalySink};
+//! use std::time::Duration;
+//!
+//! let detector = AnomalyDetector::new(PoolProcessor)
+//!     .window(Duration::from_secs(60))
+//!     .field(
+//!         "base_reserve",
+//!         |account: &RaydiumAmmV4Account| match account {
+//!             RaydiumAmmV4Account::AmmInfo(pool) => Some(pool.base_reserve as f64),
+//!             _ => None,
+//!         },
+//!         AnomalyRule::PercentChange(30.0),
+//!     )
+//!     .alert_sink(LogAnomalySink);
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .account(RaydiumAmmV4Decoder, detector)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Values are tracked separately for each field of each account, so a rule
+//!   compares a pool with its own history only.
+//! - The current value is compared with the values of the window before being
+//!   added to it, so a single outlier alerts once and doesn't mask the next.
+//! - Z-scores are only computed once the window holds `min_samples` values, and
+//!   never for windows of constant values.
+//! - Alerts are counted by the `anomaly_alerts` counter. The wrapped processor
+//!   receives every account, whether or not it raised an alert.
+
+use {
+    crate::{
+        account::AccountProcessorInputType, error::CarbonResult, metrics::MetricsCollection,
+        processor::Processor,
+    },
+    async_trait::async_trait,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+};
+
+/// The window values are compared against unless configured otherwise.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// The change of a field considered abnormal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnomalyRule {
+    /// The value changed by more than this percentage, up or down, from the
+    /// oldest value of the window.
+    PercentChange(f64),
+    /// The value is more than `max` standard deviations away from the mean of
+    /// the window, once the window holds at least `min_samples` values.
+    ZScore { max: f64, min_samples: usize },
+}
+
+/// A value of a watched field breaking the rule of the field.
+///
+/// # Fields
+///
+/// - `field`: The name of the field.
+/// - `pubkey`: The account the value was decoded from.
+/// - `slot`: The slot of the account update.
+/// - `value`: The abnormal value.
+/// - `baseline`: The oldest value of the window for percent changes, or the
+///   mean of the window for z-scores.
+/// - `score`: The percent change or the z-score of the value.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct AnomalyAlert {
+    pub field: String,
+    pub pubkey: Pubkey,
+    pub slot: u64,
+    pub value: f64,
+    pub baseline: f64,
+    pub score: f64,
+}
+
+/// Receives the alerts of an `AnomalyDetector`, such as to page a risk team.
+#[async_trait]
+pub trait AnomalyAlertSink: Send + Sync {
+    async fn notify(&self, alert: &AnomalyAlert) -> CarbonResult<()>;
+}
+
+/// Logs anomaly alerts as warnings.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogAnomalySink;
+
+#[async_trait]
+impl AnomalyAlertSink for LogAnomalySink {
+    async fn notify(&self, alert: &AnomalyAlert) -> CarbonResult<()> {
+        log::warn!(
+            "anomaly on {} of {} at slot {}: {} against {} (score {:.2})",
+            alert.field,
+            alert.pubkey,
+            alert.slot,
+            alert.value,
+            alert.baseline,
+            alert.score
+        );
+        Ok(())
+    }
+}
+
+impl AnomalyRule {
+    /// Evaluates a value against the values of the window, returning its
+    /// baseline and score if it is abnormal.
+    fn evaluate(&self, value: f64, window: &VecDeque<(Instant, f64)>) -> Option<(f64, f64)> {
+        match *self {
+            AnomalyRule::PercentChange(max) => {
+                let (_, oldest) = *window.front()?;
+                if oldest == 0.0 {
+                    return None;
+                }
+
+                let change = (value - oldest) / oldest.abs() * 100.0;
+                (change.abs() > max).then_some((oldest, change))
+            }
+            AnomalyRule::ZScore { max, min_samples } => {
+                if window.is_empty() || window.len() < min_samples {
+                    return None;
+                }
+
+                let count = window.len() as f64;
+                let mean = window.iter().map(|(_, value)| value).sum::<f64>() / count;
+                let variance = window
+                    .iter()
+                    .map(|(_, value)| (value - mean).powi(2))
+                    .sum::<f64>()
+                    / count;
+                if variance == 0.0 {
+                    return None;
+                }
+
+                let z_score = (value - mean) / variance.sqrt();
+                (z_score.abs() > max).then_some((mean, z_score))
+            }
+        }
+    }
+}
+
+struct WatchedField<T> {
+    name: String,
+    extract: Box<dyn Fn(&T) -> Option<f64> + Send + Sync>,
+    rule: AnomalyRule,
+    windows: HashMap<Pubkey, VecDeque<(Instant, f64)>>,
+}
+
+impl<T> WatchedField<T> {
+    /// Records a value of the field, returning its baseline and score if it
+    /// is abnormal.
+    fn observe(
+        &mut self,
+        pubkey: Pubkey,
+        value: f64,
+        now: Instant,
+        window: Duration,
+    ) -> Option<(f64, f64)> {
+        let values = self.windows.entry(pubkey).or_default();
+        while values
+            .front()
+            .is_some_and(|(observed_at, _)| now.duration_since(*observed_at) > window)
+        {
+            values.pop_front();
+        }
+
+        let anomaly = self.rule.evaluate(value, values);
+        values.push_back((now, value));
+        anomaly
+    }
+}
+
+/// Evaluates the rules of the watched fields of decoded accounts before
+/// forwarding them to a wrapped processor.
+///
+/// # Type Parameters
+///
+/// - `T`: The decoded account type, as produced by the decoder.
+pub struct AnomalyDetector<T> {
+    processor: Box<dyn Processor<InputType = AccountProcessorInputType<T>> + Send + Sync>,
+    fields: Vec<WatchedField<T>>,
+    window: Duration,
+    alert_sinks: Vec<Arc<dyn AnomalyAlertSink>>,
+}
+
+impl<T> AnomalyDetector<T> {
+    /// Creates a detector without watched fields, forwarding every account to
+    /// `processor`, with a window of `DEFAULT_WINDOW`.
+    pub fn new(
+        processor: impl Processor<InputType = AccountProcessorInputType<T>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            processor: Box::new(processor),
+            fields: Vec::new(),
+            window: DEFAULT_WINDOW,
+            alert_sinks: Vec::new(),
+        }
+    }
+
+    /// Watches a field of the decoded accounts.
+    ///
+    /// # Parameters
+    ///
+    /// - `name`: The name of the field, reported in alerts.
+    /// - `extract`: Extracts the value of the field from a decoded account, or
+    ///   returns `None` for accounts without the field.
+    /// - `rule`: The change of the field considered abnormal.
+    pub fn field(
+        mut self,
+        name: impl Into<String>,
+        extract: impl Fn(&T) -> Option<f64> + Send + Sync + 'static,
+        rule: AnomalyRule,
+    ) -> Self {
+        self.fields.push(WatchedField {
+            name: name.into(),
+            extract: Box::new(extract),
+            rule,
+            windows: HashMap::new(),
+        });
+        self
+    }
+
+    /// Sets the duration of the window values are compared against.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Adds a sink notified of each abnormal value.
+    pub fn alert_sink(mut self, alert_sink: impl AnomalyAlertSink + 'static) -> Self {
+        self.alert_sinks.push(Arc::new(alert_sink));
+        self
+    }
+}
+
+#[async_trait]
+impl<T> Processor for AnomalyDetector<T>
+where
+    T: Send + Sync + 'static,
+{
+    type InputType = AccountProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, decoded_account, _) = &data;
+        let now = Instant::now();
+
+        let mut alerts = Vec::new();
+        for field in self.fields.iter_mut() {
+            let Some(value) = (field.extract)(&decoded_account.data) else {
+                continue;
+            };
+
+            if let Some((baseline, score)) = field.observe(metadata.pubkey, value, now, self.window)
+            {
+                alerts.push(AnomalyAlert {
+                    field: field.name.clone(),
+                    pubkey: metadata.pubkey,
+                    slot: metadata.slot,
+                    value,
+                    baseline,
+                    score,
+                });
+            }
+        }
+
+        for alert in alerts {
+            metrics.increment_counter("anomaly_alerts", 1).await?;
+            for alert_sink in &self.alert_sinks {
+                alert_sink.notify(&alert).await?;
+            }
+        }
+
+        self.processor.process(data, metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_compare_values_with_their_window() {
+        let mut field = WatchedField::<()> {
+            name: "price".to_string(),
+            extract: Box::new(|_| None),
+            rule: AnomalyRule::PercentChange(10.0),
+            windows: HashMap::new(),
+        };
+        let (pubkey, now, window) = (
+            Pubkey::new_unique(),
+            Instant::now(),
+            Duration::from_secs(60),
+        );
+
+        assert_eq!(field.observe(pubkey, 100.0, now, window), None);
+        assert_eq!(field.observe(pubkey, 109.0, now, window), None);
+        assert_eq!(
+            field.observe(pubkey, 80.0, now, window),
+            Some((100.0, -20.0))
+        );
+        assert_eq!(
+            field.observe(pubkey, 80.0, now + Duration::from_secs(61), window),
+            None
+        );
+
+        let z_score = AnomalyRule::ZScore {
+            max: 3.0,
+            min_samples: 4,
+        };
+        let values: VecDeque<_> = [9.0, 11.0, 9.0, 11.0]
+            .into_iter()
+            .map(|value| (now, value))
+            .collect();
+        assert_eq!(z_score.evaluate(12.0, &values), None);
+        assert_eq!(z_score.evaluate(14.0, &values), Some((10.0, 4.0)));
+        assert_eq!(
+            z_score.evaluate(14.0, &values.range(..3).copied().collect()),
+            None
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Signals overload of the pipeline back to its datasources, so they can slow
+//! down their upstream instead of having updates buffered or dropped.
+//!
+//! The pipeline watches the depth of its update queue. When it reaches the
+//! high watermark, the pipeline is overloaded until the queue drains down to
+//! the low watermark. Datasources receive a `Backpressure` signal following
+//! this state in `Datasource::consume_with_backpressure`, and pause their
+//! subscriptions while it is set.
+//!
+//! # Overview
+//!
+//! - **`Backpressure`**: The signal received by datasources, which can check it
+//!   with `is_overloaded`, or wait for the pipeline with `ready`.
+//! - **`BackpressureWatermarks`**: The queue depths at which the pipeline is
+//!   overloaded, and at which it recovers. By default, 80% and 50% of the
+//!   channel buffer size.
+//!
+//! # Example
+//!
+//! ```ignore
+//! #[async_trait]
+//! impl Datasource for PollingDatasource {
+//!     async fn consume(
+//!         &self,
+//!         sender: Sender<Update>,
+//!         cancellation_token: CancellationToken,
+//!         metrics: Arc<MetricsCollection>,
+//!     ) -> CarbonResult<()> {
+//!         self.consume_with_backpressure(
+//!             sender,
+//!             cancellation_token,
+//!             metrics,
+//!             Backpressure::disabled(),
+//!         )
+//!         .await
+//!     }
+//!
+//!     async fn consume_with_backpressure(
+//!         &self,
+//!         sender: Sender<Update>,
+//!         cancellation_token: CancellationToken,
+//!         _metrics: Arc<MetricsCollection>,
+//!         backpressure: Backpressure,
+//!     ) -> CarbonResult<()> {
+//!         while !cancellation_token.is_cancelled() {
+//!             backpressure.ready().await;
+//!             for update in self.poll().await? {
+//!                 sender.send(update).await?;
+//!             }
+//!         }
+//!         Ok(())
+//!     }
+//!
+//!     fn update_types(&self) -> Vec<UpdateType> {
+//!         vec![UpdateType::Transaction]
+//!     }
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - Push-based datasources, like webhook receivers, can reject deliveries with
+//!   `429 Too Many Requests` while `is_overloaded` returns `true`.
+//! - The state of the signal is reported by the `datasources_paused` gauge.
+//! - Datasources ignoring the signal keep sending updates, which are then
+//!   subject to the `OverflowPolicy` of the pipeline.
+
+use tokio::sync::watch;
+
+/// The queue depths at which the pipeline signals and clears overload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackpressureWatermarks {
+    /// The queue depth at which the pipeline is overloaded.
+    pub high: usize,
+    /// The queue depth at which an overloaded pipeline recovers.
+    pub low: usize,
+}
+
+impl BackpressureWatermarks {
+    /// Returns the default watermarks for a queue of `capacity` updates, at
+    /// 80% and 50% of its capacity.
+    pub fn for_capacity(capacity: usize) -> Self {
+        Self {
+            high: capacity * 4 / 5,
+            low: capacity / 2,
+        }
+    }
+}
+
+/// The overload signal of a pipeline, received by its datasources.
+#[derive(Debug, Clone)]
+pub struct Backpressure {
+    overloaded: watch::Receiver<bool>,
+}
+
+impl Backpressure {
+    /// Returns a signal that never reports overload, for datasources consumed
+    /// outside of a pipeline.
+    pub fn disabled() -> Self {
+        Self {
+            overloaded: watch::channel(false).1,
+        }
+    }
+
+    /// Returns `true` while the pipeline is overloaded.
+    pub fn is_overloaded(&self) -> bool {
+        *self.overloaded.borrow()
+    }
+
+    /// Waits until the pipeline isn't overloaded, returning immediately if it
+    /// isn't, or if the pipeline stopped.
+    pub async fn ready(&self) {
+        let _ = self
+            .overloaded
+            .clone()
+            .wait_for(|overloaded| !overloaded)
+            .await;
+    }
+}
+
+/// Sets the overload signal of a pipeline from the depth of its queue.
+pub(crate) struct BackpressureController {
+    overloaded: watch::Sender<bool>,
+    watermarks: BackpressureWatermarks,
+}
+
+impl BackpressureController {
+    pub(crate) fn new(watermarks: BackpressureWatermarks) -> Self {
+        let high = watermarks.high.max(1);
+        Self {
+            overloaded: watch::channel(false).0,
+            watermarks: BackpressureWatermarks {
+                high,
+                low: watermarks.low.min(high - 1),
+            },
+        }
+    }
+
+    pub(crate) fn signal(&self) -> Backpressure {
+        Backpressure {
+            overloaded: self.overloaded.subscribe(),
+        }
+    }
+
+    /// Updates the signal for a queue of `queued` updates, returning the new
+    /// state if it changed.
+    pub(crate) fn update(&self, queued: usize) -> Option<bool> {
+        let overloaded = *self.overloaded.borrow();
+        let next = if overloaded {
+            queued > self.watermarks.low
+        } else {
+            queued >= self.watermarks.high
+        };
+        if next == overloaded {
+            return None;
+        }
+
+        self.overloaded.send_replace(next);
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_signal_follows_watermarks() {
+        let controller = BackpressureController::new(BackpressureWatermarks { high: 8, low: 4 });
+        let backpressure = controller.signal();
+
+        assert_eq!(controller.update(7), None);
+        assert_eq!(controller.update(8), Some(true));
+        assert!(backpressure.is_overloaded());
+        // The signal stays set until the queue drains down to the low
+        // watermark.
+        assert_eq!(controller.update(5), None);
+        assert_eq!(controller.update(4), Some(false));
+        assert!(!backpressure.is_overloaded());
+        backpressure.ready().await;
+
+        assert!(!Backpressure::disabled().is_overloaded());
+        Backpressure::disabled().ready().await;
+    }
+}
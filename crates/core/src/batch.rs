@@ -0,0 +1,369 @@
+//! Batches processor inputs before writing them to a sink, optionally aligning
+//! flushes to slot boundaries.
+//!
+//! Writing every decoded update to a database individually is slow, so sinks
+//! usually buffer rows and write them in batches. Flushing on batch size alone
+//! splits slots across batches though, and queries running between two writes
+//! see slots that are only partially written. The `batch` module buffers the
+//! inputs of a processor and hands them over to a `BatchWriter`, flushing at
+//! slot boundaries when requested.
+//!
+//! # Overview
+//!
+//! - **`BatchWriter`**: Writes a batch of items, typically in a single database
+//!   transaction.
+//! - **`BatchProcessor`**: A cloneable `Processor` buffering its inputs and
+//!   writing them in batches of up to `max_batch_size` items. With
+//!   `slot_aligned`, a batch only ever contains complete slots.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::batch::BatchProcessor;
+//!
+//! let swaps = BatchProcessor::new(PostgresSwapWriter::new(pool), 1_000).slot_aligned();
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .instruction(RaydiumAmmV4Decoder, swaps.clone())
+//!     // Write the inputs still buffered once the datasources finished.
+//!     .flush_on_shutdown(swaps)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - With slot alignment, the slot of the latest input is considered in
+//!   progress until an input of a later slot arrives. Reaching the batch size
+//!   flushes the buffered inputs of earlier slots only, so a slot with more
+//!   inputs than `max_batch_size` is buffered until it completes.
+//! - Inputs delivered out of order for a slot already written are written right
+//!   away in a batch of their own, and counted by the `batch_late_inputs`
+//!   metric. They are never mixed with the slot in progress.
+//! - When writing a batch fails, the error is returned by the processor and its
+//!   inputs stay buffered, to be written again with the next batch.
+//! - Buffered inputs are lost if the process exits without flushing them,
+//!   either through `PipelineBuilder::flush_on_shutdown` or `flush`.
+
+use {
+    crate::{
+        error::CarbonResult,
+        finality::HasSlot,
+        metrics::MetricsCollection,
+        processor::{Flush, Processor},
+    },
+    async_trait::async_trait,
+    std::sync::Arc,
+    tokio::sync::Mutex,
+};
+
+/// Writes batches of items to a sink.
+///
+/// The batch is only borrowed, so its items stay buffered if the write fails.
+#[async_trait]
+pub trait BatchWriter<T>: Send + Sync {
+    async fn write_batch(&mut self, batch: &[T]) -> CarbonResult<()>;
+}
+
+struct BatchState<T> {
+    writer: Box<dyn BatchWriter<T>>,
+    buffer: Vec<T>,
+    late: Vec<T>,
+    current_slot: Option<u64>,
+}
+
+impl<T: HasSlot + Send + Sync> BatchState<T> {
+    /// Buffers `item`, returning `true` if its slot was already written.
+    ///
+    /// With slot alignment, the buffer is kept ordered by slot.
+    fn push(&mut self, item: T, slot_aligned: bool) -> bool {
+        if !slot_aligned {
+            self.buffer.push(item);
+            return false;
+        }
+
+        let slot = item.slot();
+        match self.current_slot {
+            Some(current_slot) if slot < current_slot => {
+                let index = self
+                    .buffer
+                    .partition_point(|buffered| buffered.slot() <= slot);
+                if index == 0 || self.buffer[index - 1].slot() != slot {
+                    self.late.push(item);
+                    return true;
+                }
+
+                // The slot is still buffered, after a failed write.
+                self.buffer.insert(index, item);
+            }
+            Some(current_slot) if slot == current_slot => self.buffer.push(item),
+            _ => {
+                self.current_slot = Some(slot);
+                self.buffer.push(item);
+            }
+        }
+
+        false
+    }
+
+    /// Returns the number of buffered inputs due to be written.
+    fn due(&self, max_batch_size: usize, slot_aligned: bool) -> usize {
+        match self.current_slot {
+            Some(current_slot) if slot_aligned => self
+                .buffer
+                .partition_point(|item| item.slot() < current_slot),
+            _ if self.buffer.len() >= max_batch_size => self.buffer.len(),
+            _ => 0,
+        }
+    }
+
+    /// Writes the late inputs, then the first `due` buffered inputs, each in a
+    /// batch of their own.
+    ///
+    /// Inputs are only removed once written, so a failed write is retried with
+    /// the next batch.
+    async fn write(&mut self, due: usize, metrics: Option<&MetricsCollection>) -> CarbonResult<()> {
+        if !self.late.is_empty() {
+            self.writer.write_batch(&self.late).await?;
+            let batch_size = self.late.len();
+            self.late.clear();
+            record_batch(metrics, batch_size).await?;
+        }
+
+        if due > 0 {
+            self.writer.write_batch(&self.buffer[..due]).await?;
+            self.buffer.drain(..due);
+            record_batch(metrics, due).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn record_batch(metrics: Option<&MetricsCollection>, batch_size: usize) -> CarbonResult<()> {
+    let Some(metrics) = metrics else {
+        return Ok(());
+    };
+
+    metrics.increment_counter("batches_written", 1).await?;
+    metrics
+        .record_histogram("batch_size", batch_size as f64)
+        .await
+}
+
+/// A processor writing its inputs to a `BatchWriter` in batches.
+///
+/// Clones share the same buffer, so a handle can be passed to
+/// `PipelineBuilder::flush_on_shutdown` to write the remaining inputs once the
+/// pipeline stops.
+pub struct BatchProcessor<T> {
+    state: Arc<Mutex<BatchState<T>>>,
+    max_batch_size: usize,
+    slot_aligned: bool,
+}
+
+impl<T> Clone for BatchProcessor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            max_batch_size: self.max_batch_size,
+            slot_aligned: self.slot_aligned,
+        }
+    }
+}
+
+impl<T: HasSlot + Send + Sync + 'static> BatchProcessor<T> {
+    /// Creates a processor writing batches of at most `max_batch_size` inputs
+    /// to `writer`.
+    pub fn new(writer: impl BatchWriter<T> + 'static, max_batch_size: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BatchState {
+                writer: Box::new(writer),
+                buffer: Vec::new(),
+                late: Vec::new(),
+                current_slot: None,
+            })),
+            max_batch_size: max_batch_size.max(1),
+            slot_aligned: false,
+        }
+    }
+
+    /// Flushes batches when the slot advances, or when `max_batch_size` is
+    /// reached, whichever comes first, without ever splitting a slot across
+    /// batches.
+    pub fn slot_aligned(mut self) -> Self {
+        self.slot_aligned = true;
+        self
+    }
+
+    /// Writes every buffered input, including those of the slot in progress.
+    pub async fn flush(&self) -> CarbonResult<()> {
+        let mut state = self.state.lock().await;
+        let due = state.buffer.len();
+        state.write(due, None).await
+    }
+
+    /// Returns the number of buffered inputs.
+    pub async fn len(&self) -> usize {
+        let state = self.state.lock().await;
+        state.buffer.len() + state.late.len()
+    }
+
+    /// Returns `true` if no input is buffered.
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+#[async_trait]
+impl<T: HasSlot + Send + Sync + 'static> Processor for BatchProcessor<T> {
+    type InputType = T;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let mut state = self.state.lock().await;
+        if state.push(data, self.slot_aligned) {
+            metrics.increment_counter("batch_late_inputs", 1).await?;
+        }
+
+        let due = state.due(self.max_batch_size, self.slot_aligned);
+        state.write(due, Some(&metrics)).await
+    }
+}
+
+#[async_trait]
+impl<T: HasSlot + Send + Sync + 'static> Flush for BatchProcessor<T> {
+    async fn flush_pending(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        let mut state = self.state.lock().await;
+        let due = state.buffer.len();
+        state.write(due, Some(&metrics)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item(u64);
+
+    impl HasSlot for Item {
+        fn slot(&self) -> u64 {
+            self.0
+        }
+    }
+
+    /// Records the slots of every batch written, failing the next `failures`
+    /// writes.
+    #[derive(Default)]
+    struct Recorder {
+        batches: Arc<std::sync::Mutex<Vec<Vec<u64>>>>,
+        failures: usize,
+    }
+
+    #[async_trait]
+    impl BatchWriter<Item> for Recorder {
+        async fn write_batch(&mut self, batch: &[Item]) -> CarbonResult<()> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(crate::error::Error::Custom("write failed".to_string()));
+            }
+
+            self.batches
+                .lock()
+                .unwrap()
+                .push(batch.iter().map(|item| item.0).collect());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slot_aligned_batches_never_split_slots() {
+        let recorder = Recorder::default();
+        let batches = recorder.batches.clone();
+        let mut processor = BatchProcessor::new(recorder, 3).slot_aligned();
+        let metrics = Arc::new(MetricsCollection::new(vec![]));
+
+        for slot in [1, 1, 2, 2, 2, 2, 3, 3, 3] {
+            processor
+                .process(Item(slot), metrics.clone())
+                .await
+                .unwrap();
+        }
+        processor.flush().await.unwrap();
+
+        assert_eq!(
+            *batches.lock().unwrap(),
+            [vec![1, 1], vec![2, 2, 2, 2], vec![3, 3, 3]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_keeps_batch() {
+        let recorder = Recorder {
+            failures: 1,
+            ..Default::default()
+        };
+        let batches = recorder.batches.clone();
+        let mut processor = BatchProcessor::new(recorder, 10).slot_aligned();
+        let metrics = Arc::new(MetricsCollection::new(vec![]));
+
+        processor.process(Item(1), metrics.clone()).await.unwrap();
+        processor.process(Item(1), metrics.clone()).await.unwrap();
+        assert!(processor.process(Item(2), metrics.clone()).await.is_err());
+        assert_eq!(processor.len().await, 3);
+
+        // Slot 1 is still buffered, so its late input joins its batch.
+        processor.process(Item(1), metrics.clone()).await.unwrap();
+        processor.flush().await.unwrap();
+
+        assert_eq!(*batches.lock().unwrap(), [vec![1, 1, 1], vec![2]]);
+        assert!(processor.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_late_inputs_written_separately() {
+        let recorder = Recorder::default();
+        let batches = recorder.batches.clone();
+        let mut processor = BatchProcessor::new(recorder, 10).slot_aligned();
+        let metrics = Arc::new(MetricsCollection::new(vec![]));
+
+        for slot in [1, 2, 1, 2, 3] {
+            processor
+                .process(Item(slot), metrics.clone())
+                .await
+                .unwrap();
+        }
+        processor.flush().await.unwrap();
+
+        assert_eq!(
+            *batches.lock().unwrap(),
+            [vec![1], vec![1], vec![2, 2], vec![3]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flush_pending_writes_tail() {
+        let recorder = Recorder::default();
+        let batches = recorder.batches.clone();
+        let mut processor = BatchProcessor::new(recorder, 2);
+        let metrics = Arc::new(MetricsCollection::new(vec![]));
+
+        for slot in [1, 2, 3] {
+            processor
+                .process(Item(slot), metrics.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(*batches.lock().unwrap(), [vec![1, 2]]);
+
+        processor.flush_pending(metrics).await.unwrap();
+
+        assert_eq!(*batches.lock().unwrap(), [vec![1, 2], vec![3]]);
+        assert!(processor.is_empty().await);
+    }
+}
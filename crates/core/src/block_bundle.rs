@@ -0,0 +1,394 @@
+//! Provides a block-level processing mode, delivering every update of a slot
+//! to a processor as a single `BlockBundle`.
+//!
+//! Regular pipes see updates one at a time, which is a poor fit for consumers
+//! whose invariants only hold at block boundaries, such as per-block
+//! accounting. The `block_bundle` module buffers the transactions, account
+//! updates and account deletions of each slot and hands them over together
+//! once the slot is complete.
+//!
+//! # Overview
+//!
+//! - **`BlockBundle`**: All updates received for a slot, in arrival order,
+//!   along with the block details if the datasource provides them.
+//! - **`BlockBundler`**: The buffer grouping updates by slot and deciding when
+//!   a slot is complete.
+//! - **`BlockBundlePipe`**: Feeds updates into a `BlockBundler` and passes the
+//!   completed bundles to a processor. It is registered through
+//!   `PipelineBuilder::block_bundles`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::block_bundle::BlockBundle;
+//!
+//! struct BlockAccountingProcessor;
+//!
+//! #[async_trait]
+//! impl Processor for BlockAccountingProcessor {
+//!     type InputType = BlockBundle;
+//!
+//!     async fn process(
+//!         &mut self,
+//!         bundle: BlockBundle,
+//!         _metrics: Arc<MetricsCollection>,
+//!     ) -> CarbonResult<()> {
+//!         // Every transaction and account update of `bundle.slot`.
+//!         Ok(())
+//!     }
+//! }
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .block_bundles(0, BlockAccountingProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - A slot is considered complete when an update for a slot more than
+//!   `slot_lag` slots ahead is received. Its `BlockDetails` don't complete it,
+//!   since datasources may send them before the transactions of the block. Use
+//!   a non-zero `slot_lag` with datasources that interleave slots.
+//! - Updates arriving after their slot was delivered are delivered in a
+//!   separate bundle for the same slot, counted by the
+//!   `block_bundle_late_updates` metric.
+//! - Pending bundles are delivered when the datasources finish, unless the
+//!   pipeline shuts down with `ShutdownStrategy::Immediate`.
+
+use {
+    crate::{
+        datasource::{AccountDeletion, AccountUpdate, BlockDetails, TransactionUpdate, Update},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    async_trait::async_trait,
+    std::{collections::BTreeMap, sync::Arc},
+};
+
+/// All updates received for a single slot.
+///
+/// # Fields
+///
+/// - `slot`: The slot of the bundled updates.
+/// - `block_details`: The details of the block, if provided by the datasource.
+/// - `transactions`: The transactions of the slot, in arrival order.
+/// - `accounts`: The account updates of the slot, in arrival order.
+/// - `account_deletions`: The account deletions of the slot, in arrival order.
+#[derive(Debug, Clone)]
+pub struct BlockBundle {
+    pub slot: u64,
+    pub block_details: Option<BlockDetails>,
+    pub transactions: Vec<TransactionUpdate>,
+    pub accounts: Vec<AccountUpdate>,
+    pub account_deletions: Vec<AccountDeletion>,
+}
+
+impl BlockBundle {
+    fn new(slot: u64) -> Self {
+        Self {
+            slot,
+            block_details: None,
+            transactions: Vec::new(),
+            accounts: Vec::new(),
+            account_deletions: Vec::new(),
+        }
+    }
+
+    /// Returns the number of updates in the bundle, excluding block details.
+    pub fn len(&self) -> usize {
+        self.transactions.len() + self.accounts.len() + self.account_deletions.len()
+    }
+
+    /// Returns `true` if the bundle holds no transactions, account updates or
+    /// account deletions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Groups updates by slot and releases complete slots as `BlockBundle`s.
+pub struct BlockBundler {
+    slot_lag: u64,
+    pending: BTreeMap<u64, BlockBundle>,
+    highest_slot: u64,
+    last_delivered_slot: Option<u64>,
+}
+
+impl BlockBundler {
+    /// Creates a new `BlockBundler`.
+    ///
+    /// # Parameters
+    ///
+    /// - `slot_lag`: How many slots behind the highest received slot a slot
+    ///   stays open for late updates.
+    pub fn new(slot_lag: u64) -> Self {
+        Self {
+            slot_lag,
+            pending: BTreeMap::new(),
+            highest_slot: 0,
+            last_delivered_slot: None,
+        }
+    }
+
+    /// Adds an update to the bundle of its slot and returns the bundles that
+    /// became complete, ordered by slot.
+    ///
+    /// The second returned value is `true` if the update arrived after its
+    /// slot was already delivered. Slot status updates aren't bundled.
+    pub fn push(&mut self, update: Update) -> (Vec<BlockBundle>, bool) {
+        if matches!(update, Update::SlotStatus(_)) {
+            return (Vec::new(), false);
+        }
+
+        let slot = update.slot();
+        let late = !self.pending.contains_key(&slot)
+            && self
+                .last_delivered_slot
+                .is_some_and(|delivered| slot <= delivered);
+
+        let bundle = self
+            .pending
+            .entry(slot)
+            .or_insert_with(|| BlockBundle::new(slot));
+        match update {
+            Update::Account(account_update) => bundle.accounts.push(account_update),
+            Update::Transaction(transaction_update) => {
+                bundle.transactions.push(*transaction_update)
+            }
+            Update::AccountDeletion(account_deletion) => {
+                bundle.account_deletions.push(account_deletion)
+            }
+            Update::BlockDetails(block_details) => bundle.block_details = Some(block_details),
+            Update::SlotStatus(_) => {}
+        }
+
+        self.highest_slot = self.highest_slot.max(slot);
+
+        let mut ready = self.take_until(self.highest_slot.saturating_sub(self.slot_lag));
+        if late {
+            if let Some(bundle) = self.pending.remove(&slot) {
+                self.mark_delivered(slot);
+                ready.push(bundle);
+            }
+        }
+
+        (ready, late)
+    }
+
+    /// Returns every pending bundle, ordered by slot.
+    pub fn flush(&mut self) -> Vec<BlockBundle> {
+        self.take_until(u64::MAX)
+            .into_iter()
+            .chain(self.pending.remove(&u64::MAX))
+            .collect()
+    }
+
+    /// Returns the number of slots waiting to be completed.
+    pub fn pending_slots(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Removes the pending bundles of all slots lower than `slot`.
+    fn take_until(&mut self, slot: u64) -> Vec<BlockBundle> {
+        let remaining = self.pending.split_off(&slot);
+        let ready = std::mem::replace(&mut self.pending, remaining);
+
+        if let Some(highest) = ready.keys().next_back() {
+            self.mark_delivered(*highest);
+        }
+
+        ready.into_values().collect()
+    }
+
+    fn mark_delivered(&mut self, slot: u64) {
+        self.last_delivered_slot = Some(
+            self.last_delivered_slot
+                .map_or(slot, |delivered| delivered.max(slot)),
+        );
+    }
+}
+
+/// A pipe grouping updates into `BlockBundle`s and passing them to a
+/// processor.
+///
+/// ## Fields
+///
+/// - `bundler`: The `BlockBundler` grouping updates by slot.
+/// - `processor`: A `Processor` that processes complete block bundles.
+pub struct BlockBundlePipe {
+    pub bundler: BlockBundler,
+    pub processor: Box<dyn Processor<InputType = BlockBundle> + Send + Sync>,
+}
+
+/// A trait for grouping pipeline updates into block bundles.
+///
+/// `BlockBundlePipes` receives every update processed by the pipeline through
+/// `run` and delivers the pending bundles when `flush` is called on shutdown.
+#[async_trait]
+pub trait BlockBundlePipes: Send + Sync {
+    /// Adds an update to its slot, processing any bundle that became complete.
+    async fn run(&mut self, update: Update, metrics: Arc<MetricsCollection>) -> CarbonResult<()>;
+
+    /// Processes every pending bundle, regardless of whether its slot is
+    /// complete.
+    async fn flush(&mut self, metrics: Arc<MetricsCollection>) -> CarbonResult<()>;
+}
+
+impl BlockBundlePipe {
+    async fn process_bundles(
+        &mut self,
+        bundles: Vec<BlockBundle>,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        for bundle in bundles {
+            metrics
+                .record_histogram("block_bundle_updates", bundle.len() as f64)
+                .await?;
+            self.processor.process(bundle, metrics.clone()).await?;
+            metrics
+                .increment_counter("block_bundles_processed", 1)
+                .await?;
+        }
+
+        metrics
+            .update_gauge(
+                "block_bundle_pending_slots",
+                self.bundler.pending_slots() as f64,
+            )
+            .await
+    }
+}
+
+#[async_trait]
+impl BlockBundlePipes for BlockBundlePipe {
+    async fn run(&mut self, update: Update, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        log::trace!("BlockBundlePipe::run(update: {:?}, metrics)", update);
+
+        let (bundles, late) = self.bundler.push(update);
+        if late {
+            metrics
+                .increment_counter("block_bundle_late_updates", 1)
+                .await?;
+        }
+
+        self.process_bundles(bundles, metrics).await
+    }
+
+    async fn flush(&mut self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        log::trace!("BlockBundlePipe::flush(metrics)");
+
+        let bundles = self.bundler.flush();
+        self.process_bundles(bundles, metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_account::Account, solana_pubkey::Pubkey, solana_signature::Signature,
+        solana_transaction::versioned::VersionedTransaction,
+        solana_transaction_status::TransactionStatusMeta,
+    };
+
+    fn account_update(slot: u64) -> Update {
+        Update::Account(AccountUpdate {
+            pubkey: Pubkey::new_unique(),
+            account: Account::default(),
+            slot,
+        })
+    }
+
+    fn transaction_update(slot: u64) -> Update {
+        Update::Transaction(Box::new(TransactionUpdate {
+            signature: Signature::new_unique(),
+            transaction: VersionedTransaction::default(),
+            meta: TransactionStatusMeta::default(),
+            is_vote: false,
+            slot,
+            block_time: None,
+            block_hash: None,
+        }))
+    }
+
+    fn block_details(slot: u64) -> Update {
+        Update::BlockDetails(BlockDetails {
+            slot,
+            block_hash: None,
+            previous_block_hash: None,
+            rewards: None,
+            num_reward_partitions: None,
+            block_time: None,
+            block_height: None,
+        })
+    }
+
+    #[test]
+    fn test_slot_completes_on_next_slot() {
+        let mut bundler = BlockBundler::new(0);
+
+        assert!(bundler.push(account_update(10)).0.is_empty());
+        assert!(bundler.push(account_update(10)).0.is_empty());
+
+        let (ready, late) = bundler.push(account_update(11));
+        assert!(!late);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].slot, 10);
+        assert_eq!(ready[0].accounts.len(), 2);
+        assert_eq!(bundler.pending_slots(), 1);
+    }
+
+    #[test]
+    fn test_block_details_before_transactions() {
+        let mut bundler = BlockBundler::new(0);
+
+        // Block crawlers send the details of a block before its transactions.
+        for update in [
+            block_details(10),
+            transaction_update(10),
+            transaction_update(10),
+        ] {
+            let (ready, late) = bundler.push(update);
+            assert!(ready.is_empty());
+            assert!(!late);
+        }
+
+        let (ready, _) = bundler.push(block_details(11));
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].slot, 10);
+        assert!(ready[0].block_details.is_some());
+        assert_eq!(ready[0].transactions.len(), 2);
+    }
+
+    #[test]
+    fn test_slot_lag_and_late_updates() {
+        let mut bundler = BlockBundler::new(2);
+
+        assert!(bundler.push(account_update(10)).0.is_empty());
+        assert!(bundler.push(account_update(12)).0.is_empty());
+        assert!(bundler.push(account_update(11)).0.is_empty());
+
+        let (ready, _) = bundler.push(account_update(13));
+        assert_eq!(
+            ready.iter().map(|bundle| bundle.slot).collect::<Vec<_>>(),
+            [10]
+        );
+
+        let (ready, late) = bundler.push(account_update(10));
+        assert!(late);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].slot, 10);
+
+        assert_eq!(
+            bundler
+                .flush()
+                .iter()
+                .map(|bundle| bundle.slot)
+                .collect::<Vec<_>>(),
+            [11, 12, 13]
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Parses the compute budget requested by a transaction.
+//!
+//! Transactions set their compute unit limit and priority fee through
+//! instructions of the ComputeBudget program. The `compute_budget` module
+//! reads these instructions from a transaction message, so that the limits and
+//! the priority fee are available on `TransactionMetadata` without decoding
+//! them in every processor.
+//!
+//! # Example
+//!
+//! ```ignore
+//! async fn process(
+//!     &mut self,
+//!     (metadata, instructions, _): TransactionProcessorInputType<MyInstruction>,
+//!     _metrics: Arc<MetricsCollection>,
+//! ) -> CarbonResult<()> {
+//!     log::info!(
+//!         "{} paid {} lamports, {:?} of them as priority fee, for {:?} compute units",
+//!         metadata.fee_payer,
+//!         metadata.fee,
+//!         metadata.compute_budget.priority_fee(),
+//!         metadata.compute_units_consumed,
+//!     );
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - Only top-level instructions are read, as the runtime ignores ComputeBudget
+//!   instructions invoked through CPI.
+//! - When an instruction is repeated, the runtime rejects the transaction. The
+//!   last occurrence is kept here.
+
+use {solana_message::VersionedMessage, solana_pubkey::Pubkey};
+
+/// The id of the ComputeBudget program.
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
+
+/// The compute unit limit of each instruction when a transaction doesn't set
+/// one.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// The maximum compute unit limit of a transaction.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// The compute budget requested by a transaction's ComputeBudget
+/// instructions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// The compute unit limit set with `SetComputeUnitLimit`.
+    pub compute_unit_limit: Option<u32>,
+    /// The compute unit price, in micro-lamports, set with
+    /// `SetComputeUnitPrice`.
+    pub compute_unit_price: Option<u64>,
+    /// The heap size, in bytes, requested with `RequestHeapFrame`.
+    pub heap_frame_size: Option<u32>,
+    /// The loaded accounts data size limit, in bytes, set with
+    /// `SetLoadedAccountsDataSizeLimit`.
+    pub loaded_accounts_data_size_limit: Option<u32>,
+    /// The number of top-level instructions which aren't ComputeBudget
+    /// instructions, used to derive the default compute unit limit.
+    pub non_compute_budget_instructions: u32,
+}
+
+impl ComputeBudget {
+    /// Reads the ComputeBudget instructions of a message.
+    ///
+    /// Malformed ComputeBudget instructions are ignored.
+    pub fn from_message(message: &VersionedMessage) -> Self {
+        let account_keys = message.static_account_keys();
+        let mut compute_budget = ComputeBudget::default();
+
+        for instruction in message.instructions() {
+            let program_id = account_keys.get(instruction.program_id_index as usize);
+            if program_id != Some(&COMPUTE_BUDGET_PROGRAM_ID) {
+                compute_budget.non_compute_budget_instructions += 1;
+                continue;
+            }
+
+            let data = instruction.data.as_slice();
+            match data.first() {
+                Some(1) => compute_budget.heap_frame_size = read_u32(data),
+                Some(2) => compute_budget.compute_unit_limit = read_u32(data),
+                Some(3) => {
+                    compute_budget.compute_unit_price = data
+                        .get(1..9)
+                        .and_then(|bytes| bytes.try_into().ok())
+                        .map(u64::from_le_bytes)
+                }
+                Some(4) => compute_budget.loaded_accounts_data_size_limit = read_u32(data),
+                _ => {}
+            }
+        }
+
+        compute_budget
+    }
+
+    /// Returns the compute unit limit the runtime applies to the transaction,
+    /// which defaults to 200,000 units per non-ComputeBudget instruction.
+    pub fn effective_compute_unit_limit(&self) -> u32 {
+        self.compute_unit_limit
+            .unwrap_or_else(|| {
+                self.non_compute_budget_instructions
+                    .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+            })
+            .min(MAX_COMPUTE_UNIT_LIMIT)
+    }
+
+    /// Returns the priority fee, in lamports, paid for the compute unit limit
+    /// of the transaction, or `None` if it doesn't set a compute unit price.
+    pub fn priority_fee(&self) -> Option<u64> {
+        let compute_unit_price = self.compute_unit_price?;
+        let micro_lamports =
+            compute_unit_price as u128 * self.effective_compute_unit_limit() as u128;
+
+        Some(micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64)
+    }
+}
+
+fn read_u32(data: &[u8]) -> Option<u32> {
+    data.get(1..5)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_message::{compiled_instruction::CompiledInstruction, Message},
+    };
+
+    #[test]
+    fn test_from_message_reads_limit_and_price() {
+        let mut unit_price = vec![3];
+        unit_price.extend_from_slice(&50_000u64.to_le_bytes());
+        let mut unit_limit = vec![2];
+        unit_limit.extend_from_slice(&300_000u32.to_le_bytes());
+
+        let message = VersionedMessage::Legacy(Message {
+            account_keys: vec![
+                Pubkey::new_unique(),
+                COMPUTE_BUDGET_PROGRAM_ID,
+                Pubkey::new_unique(),
+            ],
+            instructions: vec![
+                CompiledInstruction::new_from_raw_parts(1, unit_price, vec![]),
+                CompiledInstruction::new_from_raw_parts(1, unit_limit, vec![]),
+                CompiledInstruction::new_from_raw_parts(2, vec![0], vec![0]),
+            ],
+            ..Message::default()
+        });
+
+        let compute_budget = ComputeBudget::from_message(&message);
+        assert_eq!(compute_budget.compute_unit_limit, Some(300_000));
+        assert_eq!(compute_budget.compute_unit_price, Some(50_000));
+        assert_eq!(compute_budget.non_compute_budget_instructions, 1);
+        assert_eq!(compute_budget.priority_fee(), Some(15_000));
+
+        let compute_budget = ComputeBudget {
+            compute_unit_limit: None,
+            ..compute_budget
+        };
+        assert_eq!(compute_budget.effective_compute_unit_limit(), 200_000);
+        assert_eq!(compute_budget.priority_fee(), Some(10_000));
+    }
+}
@@ -0,0 +1,740 @@
+//! Builds pipelines from TOML or YAML files, so operational changes such as
+//! switching datasources, enabling decoders or routing their output to other
+//! sinks don't require recompiling the indexer.
+//!
+//! A `PipelineConfig` describes the datasources, decoders, sinks, filters and
+//! metrics of a pipeline by type name. A `ConfigRegistry` maps these names to
+//! the factories creating each component from its options, and builds the
+//! pipeline: every decoder gets a pipe sending its decoded accounts and
+//! instructions, as JSON, to the sinks it is routed to.
+//!
+//! # Overview
+//!
+//! - **`PipelineConfig`**: The description of a pipeline, read with
+//!   `PipelineConfig::from_file`.
+//! - **`ConfigRegistry`**: The factories available to configurations. The `log`
+//!   sink is registered by default.
+//! - **`DecodedEvent`**: A decoded account or instruction, as received by the
+//!   sinks of configured pipelines.
+//! - **`ConfiguredPipeline`**: A pipeline built from a configuration, flushing
+//!   its sinks once it stopped.
+//!
+//! # Example
+//!
+//! ```toml
+//! [pipeline]
+//! channel_buffer_size = 10000
+//! shutdown_strategy = "process-pending"
+//!
+//! [[datasources]]
+//! type = "rpc-block-subscribe"
+//! ws_url = "${RPC_WS_URL}"
+//!
+//! [[decoders]]
+//! type = "token-program"
+//! sinks = ["stdout"]
+//!
+//! [[sinks]]
+//! name = "stdout"
+//! type = "log"
+//!
+//! [filters]
+//! program_ids = ["TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"]
+//!
+//! [[metrics]]
+//! type = "log"
+//! ```
+//!
+//! ```ignore
+//! use carbon_core::config::{ConfigRegistry, PipelineConfig};
+//!
+//! let registry = ConfigRegistry::new()
+//!     .datasource("rpc-block-subscribe", |options| {
+//!         Ok(RpcBlockSubscribe::new(options.require("ws_url")?, Filters::default()))
+//!     })
+//!     .instruction_decoder("token-program", |_| Ok(TokenProgramDecoder))
+//!     .metrics("log", |_| Ok(LogMetrics::new()));
+//!
+//! let config = PipelineConfig::from_file("carbon.toml")?;
+//! registry.build(&config)?.run().await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Option values of the form `$VAR` or `${VAR}` are read from the
+//!   environment, and are missing when the variable is unset.
+//! - Decoders registered both as account and instruction decoders get both
+//!   pipes, unless `accounts = false` or `instructions = false`.
+//! - Decoders are routed to every sink unless their `sinks` are listed.
+//! - This module is only available with the `config` feature.
+
+use {
+    crate::{
+        account::{AccountDecoder, AccountProcessorInputType},
+        datasource::Datasource,
+        error::{CarbonResult, Error},
+        instruction::{InstructionDecoder, InstructionProcessorInputType},
+        metrics::{Metrics, MetricsCollection},
+        pipeline::{Pipeline, PipelineBuilder, ShutdownStrategy},
+        processor::Processor,
+        sink::Sink,
+    },
+    async_trait::async_trait,
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    solana_pubkey::Pubkey,
+    std::{collections::HashMap, marker::PhantomData, path::Path, str::FromStr, sync::Arc},
+    tokio::sync::Mutex,
+};
+
+/// The description of a pipeline.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PipelineConfig {
+    #[serde(default)]
+    pub pipeline: PipelineSettings,
+    pub datasources: Vec<ComponentConfig>,
+    #[serde(default)]
+    pub decoders: Vec<DecoderConfig>,
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+    #[serde(default)]
+    pub filters: FiltersConfig,
+    #[serde(default)]
+    pub metrics: Vec<ComponentConfig>,
+}
+
+/// The settings of the pipeline itself, the builder defaults being used for
+/// those missing.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PipelineSettings {
+    pub channel_buffer_size: Option<usize>,
+    pub metrics_flush_interval: Option<u64>,
+    pub shutdown_strategy: Option<ShutdownStrategyConfig>,
+}
+
+/// The `ShutdownStrategy` of a configured pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShutdownStrategyConfig {
+    Immediate,
+    ProcessPending,
+}
+
+impl From<ShutdownStrategyConfig> for ShutdownStrategy {
+    fn from(config: ShutdownStrategyConfig) -> Self {
+        match config {
+            ShutdownStrategyConfig::Immediate => ShutdownStrategy::Immediate,
+            ShutdownStrategyConfig::ProcessPending => ShutdownStrategy::ProcessPending,
+        }
+    }
+}
+
+/// A datasource or metrics backend, created by the factory registered for
+/// its type.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ComponentConfig {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(flatten)]
+    pub options: ConfigOptions,
+}
+
+/// A decoder to enable, and the sinks receiving its output.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DecoderConfig {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The names of the sinks receiving the decoded accounts and
+    /// instructions, every sink when empty.
+    #[serde(default)]
+    pub sinks: Vec<String>,
+    #[serde(default = "enabled")]
+    pub accounts: bool,
+    #[serde(default = "enabled")]
+    pub instructions: bool,
+    #[serde(flatten)]
+    pub options: ConfigOptions,
+}
+
+/// A sink, named so decoders can be routed to it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SinkConfig {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(flatten)]
+    pub options: ConfigOptions,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct FiltersConfig {
+    /// The programs whose transactions are processed, every program when
+    /// empty.
+    #[serde(default)]
+    pub program_ids: Vec<String>,
+    pub max_account_data_size: Option<usize>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// The options of a component: the keys of its table besides those read by
+/// the configuration itself, such as `type`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct ConfigOptions(serde_json::Map<String, serde_json::Value>);
+
+impl ConfigOptions {
+    /// Returns the option `key`, if set.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> CarbonResult<Option<T>> {
+        match self.0.get(key) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(|e| Error::Custom(format!("Invalid option `{}`: {}", key, e))),
+        }
+    }
+
+    /// Returns the option `key`, failing if it isn't set.
+    pub fn require<T: DeserializeOwned>(&self, key: &str) -> CarbonResult<T> {
+        self.get(key)?
+            .ok_or_else(|| Error::Custom(format!("Missing option `{}`", key)))
+    }
+
+    fn resolve_env(&mut self) {
+        self.0.values_mut().for_each(resolve_env_value);
+    }
+}
+
+/// Resolves the `$VAR` and `${VAR}` strings of a value from the environment,
+/// to null when the variable is unset.
+fn resolve_env_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(string) => {
+            let variable = string
+                .strip_prefix("${")
+                .and_then(|string| string.strip_suffix('}'))
+                .or_else(|| string.strip_prefix('$'));
+            if let Some(variable) = variable {
+                *value = std::env::var(variable)
+                    .map(serde_json::Value::String)
+                    .unwrap_or(serde_json::Value::Null);
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(resolve_env_value),
+        serde_json::Value::Object(values) => values.values_mut().for_each(resolve_env_value),
+        _ => {}
+    }
+}
+
+impl PipelineConfig {
+    /// Reads a configuration from a `.toml`, `.yaml` or `.yml` file.
+    pub fn from_file(path: impl AsRef<Path>) -> CarbonResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            Error::Custom(format!(
+                "Couldn't read config file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&content),
+            Some("yaml" | "yml") => Self::from_yaml(&content),
+            _ => Err(Error::Custom(format!(
+                "Unsupported config file {}, expected a .toml, .yaml or .yml file",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn from_toml(content: &str) -> CarbonResult<Self> {
+        let config: Self = toml::from_str(content)
+            .map_err(|e| Error::Custom(format!("Couldn't parse config: {}", e)))?;
+        Ok(config.with_env())
+    }
+
+    pub fn from_yaml(content: &str) -> CarbonResult<Self> {
+        let config: Self = serde_yaml::from_str(content)
+            .map_err(|e| Error::Custom(format!("Couldn't parse config: {}", e)))?;
+        Ok(config.with_env())
+    }
+
+    fn with_env(mut self) -> Self {
+        let options = self
+            .datasources
+            .iter_mut()
+            .chain(self.metrics.iter_mut())
+            .map(|component| &mut component.options)
+            .chain(self.decoders.iter_mut().map(|decoder| &mut decoder.options))
+            .chain(self.sinks.iter_mut().map(|sink| &mut sink.options));
+        options.for_each(ConfigOptions::resolve_env);
+        self
+    }
+}
+
+/// Whether a `DecodedEvent` holds an account or an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodedEventKind {
+    Account,
+    Instruction,
+}
+
+/// A decoded account or instruction, sent to the sinks of configured
+/// pipelines.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecodedEvent {
+    /// The type of the decoder, as configured.
+    pub decoder: String,
+    pub kind: DecodedEventKind,
+    pub slot: u64,
+    /// The signature of the transaction of instructions.
+    pub signature: Option<String>,
+    /// The address of accounts, or the program ID of instructions.
+    pub pubkey: String,
+    pub data: serde_json::Value,
+}
+
+/// A sink logging the events it receives as JSON.
+pub struct LogEventSink;
+
+#[async_trait]
+impl Sink<DecodedEvent> for LogEventSink {
+    async fn send(&mut self, event: DecodedEvent) -> CarbonResult<()> {
+        let event = serde_json::to_string(&event)
+            .map_err(|e| Error::Custom(format!("Couldn't serialize event: {}", e)))?;
+        log::info!("{}", event);
+        Ok(())
+    }
+}
+
+type SharedSink = Arc<Mutex<Box<dyn Sink<DecodedEvent>>>>;
+type Factory<T> = Box<dyn Fn(&ConfigOptions) -> CarbonResult<T> + Send + Sync>;
+type PipeFactory = Box<
+    dyn Fn(PipelineBuilder, &DecoderConfig, Vec<SharedSink>) -> CarbonResult<PipelineBuilder>
+        + Send
+        + Sync,
+>;
+
+/// The factories creating the components of configured pipelines, by type.
+pub struct ConfigRegistry {
+    datasources: HashMap<String, Factory<Arc<dyn Datasource + Send + Sync>>>,
+    account_decoders: HashMap<String, PipeFactory>,
+    instruction_decoders: HashMap<String, PipeFactory>,
+    sinks: HashMap<String, Factory<Box<dyn Sink<DecodedEvent>>>>,
+    metrics: HashMap<String, Factory<Arc<dyn Metrics>>>,
+}
+
+impl Default for ConfigRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigRegistry {
+    /// Creates a registry holding the `log` sink.
+    pub fn new() -> Self {
+        Self {
+            datasources: HashMap::new(),
+            account_decoders: HashMap::new(),
+            instruction_decoders: HashMap::new(),
+            sinks: HashMap::new(),
+            metrics: HashMap::new(),
+        }
+        .sink("log", |_| Ok(LogEventSink))
+    }
+
+    /// Registers the factory of the datasources of type `kind`.
+    pub fn datasource<D: Datasource + 'static>(
+        mut self,
+        kind: impl Into<String>,
+        factory: impl Fn(&ConfigOptions) -> CarbonResult<D> + Send + Sync + 'static,
+    ) -> Self {
+        self.datasources.insert(
+            kind.into(),
+            Box::new(move |options| {
+                Ok(Arc::new(factory(options)?) as Arc<dyn Datasource + Send + Sync>)
+            }),
+        );
+        self
+    }
+
+    /// Registers the factory of the account decoders of type `kind`.
+    pub fn account_decoder<T, D>(
+        mut self,
+        kind: impl Into<String>,
+        factory: impl Fn(&ConfigOptions) -> CarbonResult<D> + Send + Sync + 'static,
+    ) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        D: for<'a> AccountDecoder<'a, AccountType = T> + Send + Sync + 'static,
+    {
+        self.account_decoders.insert(
+            kind.into(),
+            Box::new(move |builder, config, sinks| {
+                Ok(builder.account(
+                    factory(&config.options)?,
+                    EventProcessor::<AccountProcessorInputType<T>>::new(config, sinks),
+                ))
+            }),
+        );
+        self
+    }
+
+    /// Registers the factory of the instruction decoders of type `kind`.
+    pub fn instruction_decoder<T, D>(
+        mut self,
+        kind: impl Into<String>,
+        factory: impl Fn(&ConfigOptions) -> CarbonResult<D> + Send + Sync + 'static,
+    ) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        D: for<'a> InstructionDecoder<'a, InstructionType = T> + Send + Sync + 'static,
+    {
+        self.instruction_decoders.insert(
+            kind.into(),
+            Box::new(move |builder, config, sinks| {
+                Ok(builder.instruction(
+                    factory(&config.options)?,
+                    EventProcessor::<InstructionProcessorInputType<T>>::new(config, sinks),
+                ))
+            }),
+        );
+        self
+    }
+
+    /// Registers the factory of the sinks of type `kind`.
+    pub fn sink<S: Sink<DecodedEvent> + 'static>(
+        mut self,
+        kind: impl Into<String>,
+        factory: impl Fn(&ConfigOptions) -> CarbonResult<S> + Send + Sync + 'static,
+    ) -> Self {
+        self.sinks.insert(
+            kind.into(),
+            Box::new(move |options| Ok(Box::new(factory(options)?) as Box<dyn Sink<DecodedEvent>>)),
+        );
+        self
+    }
+
+    /// Registers the factory of the metrics backends of type `kind`.
+    pub fn metrics<M: Metrics + 'static>(
+        mut self,
+        kind: impl Into<String>,
+        factory: impl Fn(&ConfigOptions) -> CarbonResult<M> + Send + Sync + 'static,
+    ) -> Self {
+        self.metrics.insert(
+            kind.into(),
+            Box::new(move |options| Ok(Arc::new(factory(options)?) as Arc<dyn Metrics>)),
+        );
+        self
+    }
+
+    /// Builds the pipeline described by `config`, failing on types missing
+    /// from the registry.
+    pub fn build(&self, config: &PipelineConfig) -> CarbonResult<ConfiguredPipeline> {
+        let mut builder = Pipeline::builder();
+
+        for datasource in &config.datasources {
+            let factory = lookup(&self.datasources, "datasource", &datasource.kind)?;
+            builder.datasources.push(factory(&datasource.options)?);
+        }
+
+        let mut sinks: Vec<(&str, SharedSink)> = Vec::with_capacity(config.sinks.len());
+        for sink in &config.sinks {
+            if sinks.iter().any(|(name, _)| *name == sink.name) {
+                return Err(Error::Custom(format!("Duplicate sink `{}`", sink.name)));
+            }
+            let factory = lookup(&self.sinks, "sink", &sink.kind)?;
+            sinks.push((&sink.name, Arc::new(Mutex::new(factory(&sink.options)?))));
+        }
+
+        for decoder in &config.decoders {
+            let routed = if decoder.sinks.is_empty() {
+                sinks.iter().map(|(_, sink)| sink.clone()).collect()
+            } else {
+                decoder
+                    .sinks
+                    .iter()
+                    .map(|routed| {
+                        sinks
+                            .iter()
+                            .find(|(name, _)| name == routed)
+                            .map(|(_, sink)| sink.clone())
+                            .ok_or_else(|| Error::Custom(format!("Unknown sink `{}`", routed)))
+                    })
+                    .collect::<CarbonResult<Vec<_>>>()?
+            };
+
+            let account = self.account_decoders.get(&decoder.kind);
+            let instruction = self.instruction_decoders.get(&decoder.kind);
+            if account.is_none() && instruction.is_none() {
+                return Err(unknown("decoder", &decoder.kind));
+            }
+            if let Some(factory) = account.filter(|_| decoder.accounts) {
+                builder = factory(builder, decoder, routed.clone())?;
+            }
+            if let Some(factory) = instruction.filter(|_| decoder.instructions) {
+                builder = factory(builder, decoder, routed)?;
+            }
+        }
+
+        if !config.filters.program_ids.is_empty() {
+            let program_ids = config
+                .filters
+                .program_ids
+                .iter()
+                .map(|program_id| {
+                    Pubkey::from_str(program_id).map_err(|e| {
+                        Error::Custom(format!("Invalid program ID `{}`: {}", program_id, e))
+                    })
+                })
+                .collect::<CarbonResult<Vec<_>>>()?;
+            builder = builder.program_id_filter(program_ids);
+        }
+        if let Some(max_account_data_size) = config.filters.max_account_data_size {
+            builder = builder.max_account_data_size(max_account_data_size);
+        }
+
+        for metrics in &config.metrics {
+            let factory = lookup(&self.metrics, "metrics", &metrics.kind)?;
+            builder = builder.metrics(factory(&metrics.options)?);
+        }
+
+        let settings = &config.pipeline;
+        if let Some(channel_buffer_size) = settings.channel_buffer_size {
+            builder = builder.channel_buffer_size(channel_buffer_size);
+        }
+        if let Some(metrics_flush_interval) = settings.metrics_flush_interval {
+            builder = builder.metrics_flush_interval(metrics_flush_interval);
+        }
+        if let Some(shutdown_strategy) = settings.shutdown_strategy {
+            builder = builder.shutdown_strategy(shutdown_strategy.into());
+        }
+
+        Ok(ConfiguredPipeline {
+            pipeline: builder.build()?,
+            sinks: sinks.into_iter().map(|(_, sink)| sink).collect(),
+        })
+    }
+}
+
+fn lookup<'a, T>(
+    factories: &'a HashMap<String, Factory<T>>,
+    component: &str,
+    kind: &str,
+) -> CarbonResult<&'a Factory<T>> {
+    factories.get(kind).ok_or_else(|| unknown(component, kind))
+}
+
+fn unknown(component: &str, kind: &str) -> Error {
+    Error::Custom(format!("Unknown {} type `{}`", component, kind))
+}
+
+/// A pipeline built from a configuration.
+pub struct ConfiguredPipeline {
+    pipeline: Pipeline,
+    sinks: Vec<SharedSink>,
+}
+
+impl ConfiguredPipeline {
+    /// Runs the pipeline, then flushes its sinks.
+    pub async fn run(mut self) -> CarbonResult<()> {
+        let result = self.pipeline.run().await;
+        for sink in &self.sinks {
+            sink.lock().await.flush().await?;
+        }
+        result
+    }
+}
+
+/// A processor converting decoded accounts or instructions to events for the
+/// sinks of a decoder.
+struct EventProcessor<I> {
+    decoder: String,
+    sinks: Vec<SharedSink>,
+    _input: PhantomData<fn(I)>,
+}
+
+impl<I> EventProcessor<I> {
+    fn new(config: &DecoderConfig, sinks: Vec<SharedSink>) -> Self {
+        Self {
+            decoder: config.kind.clone(),
+            sinks,
+            _input: PhantomData,
+        }
+    }
+
+    async fn send(&self, event: DecodedEvent, metrics: &MetricsCollection) -> CarbonResult<()> {
+        for sink in &self.sinks {
+            sink.lock().await.send(event.clone()).await?;
+        }
+
+        metrics.increment_counter("config_events_sent", 1).await
+    }
+}
+
+fn to_json(data: &impl Serialize) -> CarbonResult<serde_json::Value> {
+    serde_json::to_value(data)
+        .map_err(|e| Error::Custom(format!("Couldn't serialize decoded data: {}", e)))
+}
+
+#[async_trait]
+impl<T: Serialize + Send + Sync + 'static> Processor
+    for EventProcessor<AccountProcessorInputType<T>>
+{
+    type InputType = AccountProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        (metadata, account, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let event = DecodedEvent {
+            decoder: self.decoder.clone(),
+            kind: DecodedEventKind::Account,
+            slot: metadata.slot,
+            signature: None,
+            pubkey: metadata.pubkey.to_string(),
+            data: to_json(&account.data)?,
+        };
+
+        self.send(event, &metrics).await
+    }
+}
+
+#[async_trait]
+impl<T: Serialize + Send + Sync + 'static> Processor
+    for EventProcessor<InstructionProcessorInputType<T>>
+{
+    type InputType = InstructionProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        (metadata, instruction, _, _): Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let event = DecodedEvent {
+            decoder: self.decoder.clone(),
+            kind: DecodedEventKind::Instruction,
+            slot: metadata.transaction_metadata.slot,
+            signature: Some(metadata.transaction_metadata.signature.to_string()),
+            pubkey: instruction.program_id.to_string(),
+            data: to_json(&instruction.data)?,
+        };
+
+        self.send(event, &metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            datasource::{Update, UpdateType},
+            instruction::DecodedInstruction,
+        },
+        tokio_util::sync::CancellationToken,
+    };
+
+    struct EmptyDatasource;
+
+    #[async_trait]
+    impl Datasource for EmptyDatasource {
+        async fn consume(
+            &self,
+            _sender: tokio::sync::mpsc::Sender<Update>,
+            _cancellation_token: CancellationToken,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            Ok(())
+        }
+
+        fn update_types(&self) -> Vec<UpdateType> {
+            vec![UpdateType::Transaction]
+        }
+    }
+
+    struct MemoDecoder;
+
+    impl InstructionDecoder<'_> for MemoDecoder {
+        type InstructionType = String;
+
+        fn decode_instruction(
+            &self,
+            instruction: &solana_instruction::Instruction,
+        ) -> Option<DecodedInstruction<String>> {
+            Some(DecodedInstruction {
+                program_id: instruction.program_id,
+                data: String::from_utf8(instruction.data.clone()).ok()?,
+                accounts: instruction.accounts.clone(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_build_from_toml_and_yaml() {
+        std::env::set_var("CARBON_CONFIG_TEST_URL", "wss://example.com");
+        let toml = PipelineConfig::from_toml(
+            r#"
+            [pipeline]
+            shutdown_strategy = "immediate"
+
+            [[datasources]]
+            type = "empty"
+            url = "${CARBON_CONFIG_TEST_URL}"
+
+            [[decoders]]
+            type = "memo"
+            sinks = ["stdout"]
+
+            [[sinks]]
+            name = "stdout"
+            type = "log"
+            "#,
+        )
+        .unwrap();
+        let yaml = PipelineConfig::from_yaml(
+            r#"
+            pipeline:
+              shutdown_strategy: immediate
+            datasources:
+              - type: empty
+                url: $CARBON_CONFIG_TEST_URL
+            decoders:
+              - type: memo
+                sinks: [stdout]
+            sinks:
+              - name: stdout
+                type: log
+            "#,
+        )
+        .unwrap();
+        assert_eq!(toml, yaml);
+        assert_eq!(
+            toml.datasources[0]
+                .options
+                .require::<String>("url")
+                .unwrap(),
+            "wss://example.com"
+        );
+
+        let registry = ConfigRegistry::new()
+            .datasource("empty", |options| {
+                options.require::<String>("url")?;
+                Ok(EmptyDatasource)
+            })
+            .instruction_decoder("memo", |_| Ok(MemoDecoder));
+        assert!(registry.build(&toml).is_ok());
+
+        let mut unknown_sink = toml.clone();
+        unknown_sink.decoders[0].sinks = vec!["kafka".to_string()];
+        assert!(registry.build(&unknown_sink).is_err());
+
+        let mut unknown_decoder = toml;
+        unknown_decoder.decoders[0].kind = "pumpfun".to_string();
+        assert!(registry.build(&unknown_decoder).is_err());
+    }
+}
@@ -36,7 +36,7 @@
 use solana_program::hash::Hash;
 use solana_transaction_status::Rewards;
 use {
-    crate::{error::CarbonResult, metrics::MetricsCollection},
+    crate::{backpressure::Backpressure, error::CarbonResult, metrics::MetricsCollection},
     async_trait::async_trait,
     solana_account::Account,
     solana_pubkey::Pubkey,
@@ -63,6 +63,11 @@ use {
 /// - `update_types`: Returns a list of `UpdateType` variants indicating the
 ///   types of updates the datasource can provide.
 ///
+/// # Provided Methods
+///
+/// - `consume_with_backpressure`: Consumes updates while following the overload
+///   signal of the pipeline. See [`crate::backpressure`].
+///
 /// # Example
 ///
 /// ```ignore
@@ -107,6 +112,22 @@ pub trait Datasource: Send + Sync {
         metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()>;
 
+    /// Consumes updates like `consume`, pausing while `backpressure` signals
+    /// that the pipeline is overloaded.
+    ///
+    /// The pipeline consumes its datasources through this method. Datasources
+    /// able to slow down their upstream override it, and the default
+    /// implementation ignores the signal.
+    async fn consume_with_backpressure(
+        &self,
+        sender: tokio::sync::mpsc::Sender<Update>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+        _backpressure: Backpressure,
+    ) -> CarbonResult<()> {
+        self.consume(sender, cancellation_token, metrics).await
+    }
+
     fn update_types(&self) -> Vec<UpdateType>;
 }
 
@@ -117,12 +138,31 @@ pub trait Datasource: Send + Sync {
 /// - `Transaction`: Represents a transaction-related update, including
 ///   transaction metadata.
 /// - `AccountDeletion`: Represents an event where an account has been deleted.
+/// - `SlotStatus`: Represents a change of the commitment status of a slot.
+///
+/// New kinds of updates may be added in minor releases, so matches outside of
+/// `carbon-core` need a wildcard arm.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Update {
     Account(AccountUpdate),
     Transaction(Box<TransactionUpdate>),
     AccountDeletion(AccountDeletion),
     BlockDetails(BlockDetails),
+    SlotStatus(SlotStatusUpdate),
+}
+
+impl Update {
+    /// Returns the slot the update belongs to.
+    pub fn slot(&self) -> u64 {
+        match self {
+            Update::Account(account_update) => account_update.slot,
+            Update::Transaction(transaction_update) => transaction_update.slot,
+            Update::AccountDeletion(account_deletion) => account_deletion.slot,
+            Update::BlockDetails(block_details) => block_details.slot,
+            Update::SlotStatus(slot_status) => slot_status.slot,
+        }
+    }
 }
 
 /// Enumerates the types of updates a datasource can provide.
@@ -132,11 +172,17 @@ pub enum Update {
 /// - `Transaction`: Indicates that the datasource provides transaction updates.
 /// - `AccountDeletion`: Indicates that the datasource provides account deletion
 ///   events.
+/// - `SlotStatus`: Indicates that the datasource provides slot status updates.
+///
+/// New kinds of updates may be added in minor releases, so matches outside of
+/// `carbon-core` need a wildcard arm.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum UpdateType {
     AccountUpdate,
     Transaction,
     AccountDeletion,
+    SlotStatus,
 }
 
 /// Represents an update to a Solana account, including its public key, data,
@@ -179,6 +225,33 @@ pub struct BlockDetails {
     pub block_height: Option<u64>,
 }
 
+/// The commitment status of a slot.
+///
+/// - `Processed`: The slot was processed by the node, and may still be skipped.
+/// - `Confirmed`: The slot was voted on by a supermajority of the cluster.
+/// - `Finalized`: The slot was rooted and can no longer be rolled back.
+/// - `Forked`: The slot was abandoned, and its updates won't be part of the
+///   finalized chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlotStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+    Forked,
+}
+
+/// Represents a change of the commitment status of a slot.
+///
+/// - `slot`: The slot whose status changed.
+/// - `parent`: The parent slot, if known.
+/// - `status`: The new status of the slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotStatusUpdate {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    pub status: SlotStatus,
+}
+
 /// Represents the deletion of a Solana account, containing the account's public
 /// key and slot information.
 ///
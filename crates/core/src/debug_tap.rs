@@ -0,0 +1,367 @@
+//! Samples decoded updates into a debug channel, so operators can look at live
+//! decoded data without attaching a debugger or adding printing processors.
+//!
+//! # Overview
+//!
+//! - **`DebugTap`**: A cloneable handle sampling at most `samples_per_minute`
+//!   decoded updates of each type per minute. Samples are broadcast to
+//!   subscribers, such as a TUI, and the latest ones are kept for snapshots.
+//! - **`Tapped`**: A `Processor` wrapping another processor, offering every
+//!   input to a `DebugTap` before forwarding it.
+//! - **`DebugSampled`**: Implemented by the processor inputs that can be
+//!   sampled, the decoded instructions and accounts of a pipe.
+//!
+//! The type of a sample is the name of the decoded value, the variant name for
+//! enums such as the instruction enum of a decoder, so each instruction and
+//! account type gets its own quota.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::debug_tap::DebugTap;
+//!
+//! let tap = DebugTap::new(5);
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(yellowstone_grpc)
+//!     .instruction(PumpfunDecoder, tap.tap(PumpfunInstructionProcessor))
+//!     .account(PumpfunDecoder, tap.tap(PumpfunAccountProcessor))
+//!     .build()?
+//!     .run()
+//!     .await?;
+//!
+//! // Elsewhere, print the samples as they arrive.
+//! let mut samples = tap.subscribe();
+//! while let Ok(sample) = samples.recv().await {
+//!     println!("{} {} {}", sample.type_name, sample.key, sample.data);
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - Sampling formats the decoded value with its `Debug` implementation, so the
+//!   decoded types must implement `Debug`. Updates over the quota of their type
+//!   are only formatted up to their type name.
+//! - The channel is bounded: subscribers lagging behind lose the oldest samples
+//!   instead of slowing the pipeline down.
+//! - With the `debug-server` feature, `DebugTap::serve` exposes the latest
+//!   samples as JSON over HTTP on `/debug/samples`, optionally filtered with
+//!   `?type=<type name>`.
+
+use {
+    crate::{
+        account::AccountProcessorInputType, error::CarbonResult, finality::HasSlot,
+        instruction::InstructionProcessorInputType, metrics::MetricsCollection,
+        processor::Processor,
+    },
+    async_trait::async_trait,
+    serde::Serialize,
+    std::{
+        collections::{HashMap, VecDeque},
+        fmt::{self, Debug, Write},
+        sync::Arc,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    tokio::sync::{broadcast, Mutex},
+};
+
+/// The number of samples kept for snapshots, and buffered for each
+/// subscriber, unless configured otherwise.
+pub const DEFAULT_CAPACITY: usize = 1_000;
+
+/// A decoded update sampled by a `DebugTap`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DebugSample {
+    /// The name of the decoded value, or of its variant for enums.
+    pub type_name: String,
+    pub slot: u64,
+    /// The signature of the transaction of an instruction, or the address of
+    /// an account.
+    pub key: String,
+    /// The Unix timestamp of the sampling, in milliseconds.
+    pub sampled_at: u64,
+    /// The decoded value, pretty-printed with its `Debug` implementation.
+    pub data: String,
+}
+
+/// A processor input which can be sampled by a `DebugTap`.
+pub trait DebugSampled: HasSlot {
+    /// Returns the signature or address identifying the input.
+    fn key(&self) -> String;
+
+    /// Returns the decoded value of the input.
+    fn decoded(&self) -> &dyn Debug;
+}
+
+impl<T: Debug> DebugSampled for InstructionProcessorInputType<T> {
+    fn key(&self) -> String {
+        self.0.transaction_metadata.signature.to_string()
+    }
+
+    fn decoded(&self) -> &dyn Debug {
+        &self.1.data
+    }
+}
+
+impl<T: Debug> DebugSampled for AccountProcessorInputType<T> {
+    fn key(&self) -> String {
+        self.0.pubkey.to_string()
+    }
+
+    fn decoded(&self) -> &dyn Debug {
+        &self.1.data
+    }
+}
+
+/// Collects the leading identifier of a `Debug` output, failing the formatting
+/// as soon as it ends so the rest of the value isn't formatted.
+struct TypeName(String);
+
+impl Write for TypeName {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if !(c.is_alphanumeric() || c == '_') {
+                return Err(fmt::Error);
+            }
+            self.0.push(c);
+        }
+        Ok(())
+    }
+}
+
+fn type_name(value: &dyn Debug) -> String {
+    let mut type_name = TypeName(String::new());
+    // The error only signals the end of the type name.
+    let _ = write!(type_name, "{:?}", value);
+    type_name.0
+}
+
+struct TapState {
+    /// The minute of the quota of each type, and the samples taken in it.
+    quotas: HashMap<String, (u64, usize)>,
+    recent: VecDeque<DebugSample>,
+}
+
+/// A cloneable handle sampling decoded updates into a debug channel.
+#[derive(Clone)]
+pub struct DebugTap {
+    state: Arc<Mutex<TapState>>,
+    sender: broadcast::Sender<DebugSample>,
+    samples_per_minute: usize,
+    capacity: usize,
+}
+
+impl DebugTap {
+    /// Creates a tap sampling at most `samples_per_minute` updates of each
+    /// type per minute.
+    pub fn new(samples_per_minute: usize) -> Self {
+        Self::with_capacity(samples_per_minute, DEFAULT_CAPACITY)
+    }
+
+    /// Creates a tap keeping the latest `capacity` samples.
+    pub fn with_capacity(samples_per_minute: usize, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            state: Arc::new(Mutex::new(TapState {
+                quotas: HashMap::new(),
+                recent: VecDeque::with_capacity(capacity),
+            })),
+            sender: broadcast::channel(capacity).0,
+            samples_per_minute,
+            capacity,
+        }
+    }
+
+    /// Wraps a processor, offering its inputs to the tap.
+    pub fn tap<P>(&self, processor: P) -> Tapped<P> {
+        Tapped {
+            tap: self.clone(),
+            processor,
+        }
+    }
+
+    /// Returns a receiver of the samples taken from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<DebugSample> {
+        self.sender.subscribe()
+    }
+
+    /// Returns the latest samples, oldest first, optionally of a single type.
+    pub async fn recent(&self, type_name: Option<&str>) -> Vec<DebugSample> {
+        self.state
+            .lock()
+            .await
+            .recent
+            .iter()
+            .filter(|sample| type_name.is_none_or(|type_name| sample.type_name == type_name))
+            .cloned()
+            .collect()
+    }
+
+    /// Samples an input if the quota of its type for the current minute isn't
+    /// exhausted, and returns whether it was sampled.
+    pub async fn offer(&self, input: &impl DebugSampled) -> bool {
+        let sampled_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let minute = sampled_at / 60_000;
+        let type_name = type_name(input.decoded());
+
+        let mut state = self.state.lock().await;
+        let (quota_minute, count) = state.quotas.entry(type_name.clone()).or_insert((minute, 0));
+        if *quota_minute != minute {
+            *quota_minute = minute;
+            *count = 0;
+        }
+        if *count >= self.samples_per_minute {
+            return false;
+        }
+        *count += 1;
+
+        let sample = DebugSample {
+            type_name,
+            slot: input.slot(),
+            key: input.key(),
+            sampled_at,
+            data: format!("{:#?}", input.decoded()),
+        };
+
+        if state.recent.len() >= self.capacity {
+            state.recent.pop_front();
+        }
+        state.recent.push_back(sample.clone());
+        // Sending only fails without subscribers.
+        let _ = self.sender.send(sample);
+
+        true
+    }
+}
+
+/// A processor offering its inputs to a `DebugTap` before forwarding them to
+/// the wrapped processor.
+pub struct Tapped<P> {
+    tap: DebugTap,
+    processor: P,
+}
+
+#[async_trait]
+impl<P> Processor for Tapped<P>
+where
+    P: Processor + Send + Sync,
+    P::InputType: DebugSampled + Send + Sync,
+{
+    type InputType = P::InputType;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        if self.tap.offer(&data).await {
+            metrics.increment_counter("debug_tap_samples", 1).await?;
+        }
+
+        self.processor.process(data, metrics).await
+    }
+}
+
+#[cfg(feature = "debug-server")]
+mod server {
+    use {
+        super::DebugTap,
+        crate::error::{CarbonResult, Error},
+        std::net::SocketAddr,
+        tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::{TcpListener, TcpStream},
+        },
+        tokio_util::sync::CancellationToken,
+    };
+
+    impl DebugTap {
+        /// Serves the latest samples as JSON over HTTP on `/debug/samples`,
+        /// until `cancellation_token` is cancelled.
+        pub async fn serve(
+            &self,
+            addr: SocketAddr,
+            cancellation_token: CancellationToken,
+        ) -> CarbonResult<()> {
+            let listener = TcpListener::bind(addr)
+                .await
+                .map_err(|e| Error::Custom(format!("Failed to listen on {}: {}", addr, e)))?;
+            log::info!("Debug tap listening on {}", addr);
+
+            loop {
+                let stream = tokio::select! {
+                    _ = cancellation_token.cancelled() => return Ok(()),
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(e) => {
+                            log::warn!("Failed to accept debug tap connection: {}", e);
+                            continue;
+                        }
+                    },
+                };
+
+                let tap = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = tap.respond(stream).await {
+                        log::warn!("Failed to answer debug tap request: {}", e);
+                    }
+                });
+            }
+        }
+
+        async fn respond(&self, mut stream: TcpStream) -> std::io::Result<()> {
+            let mut request = vec![0; 4096];
+            let length = stream.read(&mut request).await?;
+            let request = String::from_utf8_lossy(&request[..length]);
+            let target = request
+                .lines()
+                .next()
+                .and_then(|line| line.split_whitespace().nth(1))
+                .unwrap_or_default();
+            let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+            let (status, body) = if path == "/debug/samples" {
+                let type_name = query
+                    .split('&')
+                    .find_map(|parameter| parameter.strip_prefix("type="));
+                let samples = self.recent(type_name).await;
+                (
+                    "200 OK",
+                    serde_json::to_string(&samples).unwrap_or_else(|_| "[]".to_string()),
+                )
+            } else {
+                ("404 Not Found", "{\"error\":\"not found\"}".to_string())
+            };
+
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await?;
+            stream.shutdown().await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    enum TestInstruction {
+        Buy { amount: u64 },
+        Sell(u64),
+    }
+
+    #[test]
+    fn test_type_name() {
+        assert_eq!(type_name(&TestInstruction::Buy { amount: 1 }), "Buy");
+        assert_eq!(type_name(&TestInstruction::Sell(1)), "Sell");
+    }
+}
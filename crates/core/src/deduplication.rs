@@ -0,0 +1,215 @@
+//! Drops transaction and account updates already processed, by this pipeline
+//! or by other instances sharing the same store.
+//!
+//! Indexers often subscribe to several datasources for redundancy, or run
+//! several instances behind a load balancer, and receive the same updates more
+//! than once. The `deduplication` module records a key for every transaction
+//! and account update in a `DedupeStore`, and the pipeline skips the updates
+//! whose key was recorded within the dedupe window of the store.
+//!
+//! # Overview
+//!
+//! - **`DedupeKey`**: Identifies an update: the signature of a transaction, or
+//!   the address, slot and data hash of an account update.
+//! - **`DedupeStore`**: Records keys for the duration of its window. Stores
+//!   shared by several instances, such as the Redis store of `carbon-dedupe`,
+//!   deduplicate updates across all of them.
+//! - **`InMemoryDedupeStore`**: A `DedupeStore` keeping the keys in memory, for
+//!   a single instance with redundant datasources.
+//! - **`PipelineBuilder::deduplicate`**: Enables deduplication, using the given
+//!   store.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::deduplication::InMemoryDedupeStore;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(yellowstone_grpc)
+//!     .datasource(helius_laserstream)
+//!     .deduplicate(InMemoryDedupeStore::new(Duration::from_secs(120)))
+//!     .instruction(PumpfunDecoder, PumpfunInstructionProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Keys are recorded before the update is processed, so an update whose
+//!   processing fails isn't retried when received again.
+//! - The window should cover the delay between datasources delivering the same
+//!   update, typically a few slots, while keeping the number of stored keys
+//!   bounded.
+//! - Stores shared by several pipelines should be given a namespace per
+//!   indexer, so unrelated indexers don't drop each other's updates.
+
+use {
+    crate::error::CarbonResult,
+    async_trait::async_trait,
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{
+        collections::{HashSet, VecDeque},
+        time::{Duration, Instant},
+    },
+    tokio::sync::Mutex,
+};
+
+/// Identifies an update for deduplication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DedupeKey {
+    /// A transaction, identified by its signature.
+    Transaction(Signature),
+    /// An account update, identified by the account address, the slot of the
+    /// update and the hash of the account data, as an account can be updated
+    /// several times in a slot.
+    Account {
+        pubkey: Pubkey,
+        slot: u64,
+        data_hash: Hash,
+    },
+}
+
+impl DedupeKey {
+    /// Builds the key of an account update.
+    pub fn account(pubkey: Pubkey, slot: u64, data: &[u8]) -> Self {
+        DedupeKey::Account {
+            pubkey,
+            slot,
+            data_hash: solana_program::hash::hash(data),
+        }
+    }
+
+    /// Encodes the key as bytes, for stores keyed by byte strings.
+    ///
+    /// The first byte tells the kind of update, so transaction and account
+    /// keys can't collide.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            DedupeKey::Transaction(signature) => {
+                let mut bytes = Vec::with_capacity(65);
+                bytes.push(0);
+                bytes.extend_from_slice(signature.as_ref());
+                bytes
+            }
+            DedupeKey::Account {
+                pubkey,
+                slot,
+                data_hash,
+            } => {
+                let mut bytes = Vec::with_capacity(73);
+                bytes.push(1);
+                bytes.extend_from_slice(pubkey.as_ref());
+                bytes.extend_from_slice(&slot.to_be_bytes());
+                bytes.extend_from_slice(data_hash.as_ref());
+                bytes
+            }
+        }
+    }
+}
+
+/// Records the keys of processed updates for the duration of a window.
+#[async_trait]
+pub trait DedupeStore: Send + Sync {
+    /// Records a key, returning `true` if it wasn't recorded within the window
+    /// of the store, meaning the update should be processed.
+    ///
+    /// Stores shared by several pipelines must check and record the key
+    /// atomically, so exactly one of them processes each update.
+    async fn insert(&self, key: DedupeKey) -> CarbonResult<bool>;
+}
+
+#[derive(Debug, Default)]
+struct RecordedKeys {
+    keys: HashSet<DedupeKey>,
+    expirations: VecDeque<(Instant, DedupeKey)>,
+}
+
+/// A `DedupeStore` keeping the keys recorded within its window in memory.
+#[derive(Debug)]
+pub struct InMemoryDedupeStore {
+    window: Duration,
+    recorded: Mutex<RecordedKeys>,
+}
+
+impl InMemoryDedupeStore {
+    /// Creates a store recording keys for `window`.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            recorded: Mutex::new(RecordedKeys::default()),
+        }
+    }
+
+    /// Returns the number of keys recorded within the window.
+    pub async fn len(&self) -> usize {
+        let mut recorded = self.recorded.lock().await;
+        Self::evict_expired(&mut recorded, Instant::now());
+        recorded.keys.len()
+    }
+
+    /// Returns `true` if no key was recorded within the window.
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    fn evict_expired(recorded: &mut RecordedKeys, now: Instant) {
+        while let Some((expiration, key)) = recorded.expirations.front().copied() {
+            if expiration > now {
+                break;
+            }
+            recorded.expirations.pop_front();
+            recorded.keys.remove(&key);
+        }
+    }
+}
+
+#[async_trait]
+impl DedupeStore for InMemoryDedupeStore {
+    async fn insert(&self, key: DedupeKey) -> CarbonResult<bool> {
+        let now = Instant::now();
+        let mut recorded = self.recorded.lock().await;
+        Self::evict_expired(&mut recorded, now);
+
+        if !recorded.keys.insert(key) {
+            return Ok(false);
+        }
+        recorded.expirations.push_back((now + self.window, key));
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_store_drops_keys_within_window() {
+        let store = InMemoryDedupeStore::new(Duration::from_millis(50));
+        let transaction = DedupeKey::Transaction(Signature::new_unique());
+        let pubkey = Pubkey::new_unique();
+
+        assert!(store.insert(transaction).await.unwrap());
+        assert!(!store.insert(transaction).await.unwrap());
+        assert!(store
+            .insert(DedupeKey::account(pubkey, 1, &[1]))
+            .await
+            .unwrap());
+        assert!(store
+            .insert(DedupeKey::account(pubkey, 1, &[2]))
+            .await
+            .unwrap());
+        assert!(!store
+            .insert(DedupeKey::account(pubkey, 1, &[1]))
+            .await
+            .unwrap());
+        assert_eq!(store.len().await, 3);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(store.is_empty().await);
+        assert!(store.insert(transaction).await.unwrap());
+    }
+}
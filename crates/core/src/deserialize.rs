@@ -16,6 +16,12 @@
 //! - **`ArrangeAccounts`**: A trait that allows for defining a specific
 //!   arrangement of accounts, suitable for handling Solana account metadata in
 //!   a customized way.
+//! - **`RemainingAccounts`**: The accounts following the declared accounts of
+//!   an instruction, with their original indices preserved.
+//! - **`LayoutVersion`**: Reports which layout a type with fields marked
+//!   `#[carbon(since_version = N)]` was decoded from.
+//! - **`ZeroCopy`**: Reads `#[repr(C)]` types marked `#[carbon(zero_copy)]` in
+//!   place from their data.
 //!
 //! # Notes
 //!
@@ -27,6 +33,7 @@
 //!   Solana instructions.
 
 use std::{
+    borrow::Cow,
     io::{Error, ErrorKind, Read, Result},
     ops::Deref,
 };
@@ -51,6 +58,95 @@ where
     fn deserialize(data: &[u8]) -> Option<Self>;
 }
 
+/// A type decoding several layouts of the same account or instruction.
+///
+/// `#[derive(CarbonDeserialize)]` implements this trait for structs with
+/// fields marked `#[carbon(since_version = N)]`, the fields added to the layout
+/// by a program upgrade. These fields are `Option`s, `None` when the data was
+/// written before the upgrade.
+///
+/// # Example
+///
+/// ```ignore
+/// use carbon_core::{borsh, deserialize::LayoutVersion, CarbonDeserialize};
+///
+/// #[derive(CarbonDeserialize, Debug)]
+/// #[carbon(discriminator = "0xf19a6d0411b16dbc")]
+/// pub struct Pool {
+///     pub authority: Pubkey,
+///     pub fee_bps: u16,
+///     #[carbon(since_version = 1)]
+///     pub fee_recipient: Option<Pubkey>,
+/// }
+///
+/// let pool = Pool::deserialize(&data)?;
+/// if pool.layout_version() == 0 {
+///     // A pool created before fee recipients existed.
+/// }
+/// ```
+///
+/// # Notes
+///
+/// - Bytes remaining after the fields of the newest layout are ignored.
+pub trait LayoutVersion {
+    /// Returns the `since_version` of the newest fields present in the data, or
+    /// `0` if the data only holds the fields without a version.
+    fn layout_version(&self) -> u32;
+}
+
+/// A type read in place from its data, without deserializing it field by
+/// field.
+///
+/// Large accounts with a fixed layout, such as tick array bitmaps or orderbook
+/// slabs, are `#[repr(C)]` structs of plain data.
+/// `#[derive(CarbonDeserialize)]` implements this trait for such structs marked
+/// `#[carbon(zero_copy)]`, which must also derive `bytemuck::Pod`. Their
+/// `CarbonDeserialize` implementation then copies the data in a single step
+/// instead of decoding each field.
+///
+/// # Example
+///
+/// ```ignore
+/// use carbon_core::{borsh, bytemuck, deserialize::ZeroCopy, CarbonDeserialize};
+///
+/// #[derive(CarbonDeserialize, Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+/// #[bytemuck(crate = "carbon_core::bytemuck")]
+/// #[repr(C)]
+/// #[carbon(discriminator = "0x3c96f0a2b0cd9b1a", zero_copy)]
+/// pub struct TickArrayBitmapExtension {
+///     pub pool_id: Pubkey,
+///     pub positive_tick_array_bitmap: [[u64; 8]; 14],
+///     pub negative_tick_array_bitmap: [[u64; 8]; 14],
+/// }
+///
+/// let bitmap = TickArrayBitmapExtension::load(&account.data)?;
+/// ```
+///
+/// # Notes
+///
+/// - The data is borrowed when it is aligned for the type, and copied
+///   otherwise.
+/// - Bytes remaining after the type are ignored.
+pub trait ZeroCopy: bytemuck::Pod {
+    /// Returns the type read from `data`, after its discriminator, or `None` if
+    /// the discriminator doesn't match or the data is too short.
+    fn load(data: &[u8]) -> Option<Cow<'_, Self>>;
+}
+
+/// Reads a plain data type from the start of `data`, borrowing it if `data`
+/// is aligned for the type.
+///
+/// This is the implementation of `ZeroCopy::load` once the discriminator is
+/// stripped.
+pub fn load_pod<T: bytemuck::Pod>(data: &[u8]) -> Option<Cow<'_, T>> {
+    let bytes = data.get(..std::mem::size_of::<T>())?;
+
+    match bytemuck::try_from_bytes(bytes) {
+        Ok(value) => Some(Cow::Borrowed(value)),
+        Err(_) => bytemuck::try_pod_read_unaligned(bytes).ok().map(Cow::Owned),
+    }
+}
+
 /// Extracts a discriminator from the beginning of a byte slice and returns the
 /// discriminator and remaining data.
 ///
@@ -108,6 +204,82 @@ pub trait ArrangeAccounts {
     ) -> Option<Self::ArrangedAccounts>;
 }
 
+/// An account passed to an instruction after its declared accounts.
+///
+/// # Fields
+///
+/// - `index`: The position of the account in the instruction's account list.
+/// - `pubkey`: The public key of the account.
+/// - `is_signer`: Whether the account signed the transaction.
+/// - `is_writable`: Whether the account is writable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct RemainingAccount {
+    pub index: usize,
+    pub pubkey: solana_pubkey::Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// The accounts following the declared accounts of an instruction.
+///
+/// Protocols such as Jupiter or Meteora pack meaningful accounts, like route
+/// hops or bin arrays, after the accounts declared in their IDL. Generated
+/// `ArrangeAccounts` implementations expose them as `RemainingAccounts`, which
+/// dereferences to a slice and keeps the index of every account in the
+/// instruction's account list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct RemainingAccounts {
+    offset: usize,
+    accounts: Vec<RemainingAccount>,
+}
+
+impl RemainingAccounts {
+    /// Creates `RemainingAccounts` from the accounts following the first
+    /// `offset` declared accounts of an instruction.
+    pub fn new(offset: usize, accounts: &[solana_instruction::AccountMeta]) -> Self {
+        Self {
+            offset,
+            accounts: accounts
+                .iter()
+                .enumerate()
+                .map(|(position, account)| RemainingAccount {
+                    index: offset + position,
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the index of the first remaining account in the instruction's
+    /// account list, which equals the number of declared accounts.
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the remaining account at the given index of the instruction's
+    /// account list.
+    pub fn get_by_index(&self, index: usize) -> Option<&RemainingAccount> {
+        index
+            .checked_sub(self.offset)
+            .and_then(|position| self.accounts.get(position))
+    }
+
+    /// Returns the public keys of the remaining accounts, in order.
+    pub fn pubkeys(&self) -> Vec<solana_pubkey::Pubkey> {
+        self.accounts.iter().map(|account| account.pubkey).collect()
+    }
+}
+
+impl Deref for RemainingAccounts {
+    type Target = [RemainingAccount];
+
+    fn deref(&self) -> &Self::Target {
+        &self.accounts
+    }
+}
+
 /// A wrapper type for strings that are prefixed with their length.
 
 #[derive(serde::Serialize, serde::Deserialize, Default, PartialEq, Eq, Clone)]
@@ -191,3 +363,19 @@ impl crate::borsh::BorshDeserialize for U64PrefixString {
         })?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_pod_borrows_aligned_data() {
+        let words = [7u64, 9];
+        let bytes: &[u8] = bytemuck::cast_slice(&words);
+
+        assert!(matches!(load_pod::<u64>(bytes), Some(Cow::Borrowed(&7))));
+        // Unaligned data is copied.
+        assert!(matches!(load_pod::<u32>(&bytes[1..]), Some(Cow::Owned(_))));
+        assert!(load_pod::<[u64; 3]>(bytes).is_none());
+    }
+}
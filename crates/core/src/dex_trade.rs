@@ -0,0 +1,512 @@
+//! Normalizes the swaps and liquidity changes of DEX programs into a single,
+//! venue-independent stream.
+//!
+//! Every DEX program encodes swaps differently: some take the input amount as
+//! an argument, some only the output amount, and most only reveal the amounts
+//! actually traded through token balance changes or events. Decoders of DEX
+//! programs implement `DexInstruction` to turn their swap and liquidity
+//! instructions into `NormalizedTrade`s and `NormalizedLiquidityChange`s, and a
+//! `DexEventStream` merges the events of every venue into a single processor.
+//!
+//! # Overview
+//!
+//! - **`DexTrade`**: Exposes the pool, trader, mints and amounts of a swap.
+//! - **`LiquidityEvent`**: Exposes the pool, provider and amounts of a deposit
+//!   or withdrawal of liquidity.
+//! - **`NormalizedTrade`** and **`NormalizedLiquidityChange`**: Owned events,
+//!   with the transaction they were executed in.
+//! - **`DexInstruction`**: Implemented by the instruction types of DEX decoders
+//!   to normalize their instructions.
+//! - **`DexEventStream`**: A cloneable handle owning the processor of the
+//!   unified stream. `DexEventStream::venue` creates the processor to register
+//!   on the instruction pipe of each venue.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::dex_trade::{DexEvent, DexEventStream};
+//!
+//! let dex_events = DexEventStream::new(DexEventProcessor);
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .instruction(RaydiumAmmV4Decoder, dex_events.venue())
+//!     .instruction(OrcaWhirlpoolDecoder, dex_events.venue())
+//!     .instruction(MeteoraDlmmDecoder, dex_events.venue())
+//!     .instruction(WeightedSwapDecoder, dex_events.venue())
+//!     .build()?
+//!     .run()
+//!     .await?;
+//!
+//! // Inside `DexEventProcessor::process`:
+//! if let DexEvent::Trade(trade) = data {
+//!     log::info!(
+//!         "{}: {} {} for {} {}",
+//!         trade.venue,
+//!         trade.amount_in,
+//!         trade.mint_in,
+//!         trade.amount_out,
+//!         trade.mint_out,
+//!     );
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - Amounts are in base units of their mint.
+//! - Events computed from token balance changes attribute every change of the
+//!   accounts involved to the instruction, so the amounts of transactions
+//!   swapping several times through the same accounts are combined.
+//! - Events are emitted in the order their instructions are processed, and the
+//!   processor of the stream is shared by every venue, so it processes one
+//!   event at a time.
+
+use {
+    crate::{
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionProcessorInputType},
+        metrics::MetricsCollection,
+        processor::Processor,
+        transaction::TransactionMetadata,
+    },
+    async_trait::async_trait,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{marker::PhantomData, sync::Arc},
+    tokio::sync::Mutex,
+};
+
+/// A fee charged by a swap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct TradeFee {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// A swap on a DEX, independent of the venue it was executed on.
+pub trait DexTrade {
+    /// Returns the name of the venue, such as `stabble-weighted-swap`.
+    fn venue(&self) -> &'static str;
+
+    /// Returns the address of the pool the swap was executed against.
+    fn pool(&self) -> Pubkey;
+
+    /// Returns the wallet which sent the input tokens.
+    fn trader(&self) -> Pubkey;
+
+    /// Returns the mint of the tokens sent by the trader.
+    fn mint_in(&self) -> Pubkey;
+
+    /// Returns the mint of the tokens received by the trader.
+    fn mint_out(&self) -> Pubkey;
+
+    /// Returns the amount of tokens sent by the trader.
+    fn amount_in(&self) -> u64;
+
+    /// Returns the amount of tokens received by the trader.
+    fn amount_out(&self) -> u64;
+
+    /// Returns the fee charged by the swap, if the venue reports it.
+    fn fee(&self) -> Option<TradeFee> {
+        None
+    }
+}
+
+/// An amount of tokens of a mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct TokenAmount {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Whether liquidity was added to or removed from a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+pub enum LiquidityChangeKind {
+    Deposit,
+    Withdrawal,
+}
+
+/// A deposit or withdrawal of liquidity on a DEX, independent of the venue it
+/// was executed on.
+pub trait LiquidityEvent {
+    /// Returns the name of the venue, such as `orca-whirlpool`.
+    fn venue(&self) -> &'static str;
+
+    /// Returns the address of the pool.
+    fn pool(&self) -> Pubkey;
+
+    /// Returns the wallet which provided or withdrew the liquidity.
+    fn provider(&self) -> Pubkey;
+
+    /// Returns whether liquidity was added or removed.
+    fn kind(&self) -> LiquidityChangeKind;
+
+    /// Returns the amounts of each token deposited or withdrawn.
+    fn amounts(&self) -> Vec<TokenAmount>;
+}
+
+/// A swap, with the transaction it was executed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedTrade {
+    pub venue: &'static str,
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub pool: Pubkey,
+    pub trader: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: Option<TradeFee>,
+}
+
+impl NormalizedTrade {
+    /// Captures a trade executed in a transaction.
+    pub fn new(trade: &impl DexTrade, transaction_metadata: &TransactionMetadata) -> Self {
+        Self {
+            venue: trade.venue(),
+            signature: transaction_metadata.signature,
+            slot: transaction_metadata.slot,
+            block_time: transaction_metadata.block_time,
+            pool: trade.pool(),
+            trader: trade.trader(),
+            mint_in: trade.mint_in(),
+            mint_out: trade.mint_out(),
+            amount_in: trade.amount_in(),
+            amount_out: trade.amount_out(),
+            fee: trade.fee(),
+        }
+    }
+
+    /// Computes a trade from the balance changes of the token accounts of the
+    /// trader, for venues whose instructions don't carry the traded amounts.
+    ///
+    /// `amount_in` overrides the balance change of the input account when the
+    /// instruction specifies the exact input amount. Returns `None` if the
+    /// transaction metadata lacks the balances of the accounts.
+    pub fn from_token_accounts(
+        venue: &'static str,
+        pool: Pubkey,
+        trader: Pubkey,
+        token_in: &Pubkey,
+        token_out: &Pubkey,
+        amount_in: Option<u64>,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<Self> {
+        let token_in = transaction_metadata.token_balance_change(token_in)?;
+        let token_out = transaction_metadata.token_balance_change(token_out)?;
+
+        Some(Self {
+            venue,
+            signature: transaction_metadata.signature,
+            slot: transaction_metadata.slot,
+            block_time: transaction_metadata.block_time,
+            pool,
+            trader,
+            mint_in: token_in.mint,
+            mint_out: token_out.mint,
+            amount_in: amount_in.unwrap_or_else(|| token_in.decrease()),
+            amount_out: token_out.increase(),
+            fee: None,
+        })
+    }
+}
+
+impl DexTrade for NormalizedTrade {
+    fn venue(&self) -> &'static str {
+        self.venue
+    }
+
+    fn pool(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn trader(&self) -> Pubkey {
+        self.trader
+    }
+
+    fn mint_in(&self) -> Pubkey {
+        self.mint_in
+    }
+
+    fn mint_out(&self) -> Pubkey {
+        self.mint_out
+    }
+
+    fn amount_in(&self) -> u64 {
+        self.amount_in
+    }
+
+    fn amount_out(&self) -> u64 {
+        self.amount_out
+    }
+
+    fn fee(&self) -> Option<TradeFee> {
+        self.fee
+    }
+}
+
+/// A deposit or withdrawal of liquidity, with the transaction it was executed
+/// in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedLiquidityChange {
+    pub venue: &'static str,
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub pool: Pubkey,
+    pub provider: Pubkey,
+    pub kind: LiquidityChangeKind,
+    pub amounts: Vec<TokenAmount>,
+}
+
+impl NormalizedLiquidityChange {
+    /// Computes a liquidity change from the balance changes of the token
+    /// accounts of the provider: the amounts they sent for deposits, and
+    /// received for withdrawals.
+    ///
+    /// Returns `None` if the transaction metadata lacks the balances of any of
+    /// the accounts.
+    pub fn from_token_accounts(
+        venue: &'static str,
+        pool: Pubkey,
+        provider: Pubkey,
+        kind: LiquidityChangeKind,
+        token_accounts: &[Pubkey],
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<Self> {
+        let amounts = token_accounts
+            .iter()
+            .map(|token_account| {
+                let change = transaction_metadata.token_balance_change(token_account)?;
+                Some(TokenAmount {
+                    mint: change.mint,
+                    amount: match kind {
+                        LiquidityChangeKind::Deposit => change.decrease(),
+                        LiquidityChangeKind::Withdrawal => change.increase(),
+                    },
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            venue,
+            signature: transaction_metadata.signature,
+            slot: transaction_metadata.slot,
+            block_time: transaction_metadata.block_time,
+            pool,
+            provider,
+            kind,
+            amounts,
+        })
+    }
+}
+
+impl LiquidityEvent for NormalizedLiquidityChange {
+    fn venue(&self) -> &'static str {
+        self.venue
+    }
+
+    fn pool(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn provider(&self) -> Pubkey {
+        self.provider
+    }
+
+    fn kind(&self) -> LiquidityChangeKind {
+        self.kind
+    }
+
+    fn amounts(&self) -> Vec<TokenAmount> {
+        self.amounts.clone()
+    }
+}
+
+/// An event of the unified DEX stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DexEvent {
+    Trade(NormalizedTrade),
+    Liquidity(NormalizedLiquidityChange),
+}
+
+/// Normalizes the instructions of a DEX program.
+///
+/// Implemented by the instruction types of DEX decoders.
+pub trait DexInstruction: Sized {
+    /// Returns the swap executed by an instruction, or `None` if it isn't a
+    /// swap.
+    fn trade(
+        instruction: &DecodedInstruction<Self>,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<NormalizedTrade>;
+
+    /// Returns the liquidity change executed by an instruction, or `None` if
+    /// it doesn't add or remove liquidity.
+    fn liquidity_change(
+        _instruction: &DecodedInstruction<Self>,
+        _transaction_metadata: &TransactionMetadata,
+    ) -> Option<NormalizedLiquidityChange> {
+        None
+    }
+}
+
+/// A cloneable handle to the processor of the unified DEX event stream.
+///
+/// Clones share the same processor, so every venue feeds the same stream.
+pub struct DexEventStream {
+    processor: Arc<Mutex<Box<dyn Processor<InputType = DexEvent> + Send + Sync>>>,
+}
+
+impl Clone for DexEventStream {
+    fn clone(&self) -> Self {
+        Self {
+            processor: self.processor.clone(),
+        }
+    }
+}
+
+impl DexEventStream {
+    /// Creates a stream delivering the events of every venue to `processor`.
+    pub fn new(processor: impl Processor<InputType = DexEvent> + Send + Sync + 'static) -> Self {
+        Self {
+            processor: Arc::new(Mutex::new(Box::new(processor))),
+        }
+    }
+
+    /// Creates the processor feeding the stream from the instruction pipe of a
+    /// venue.
+    pub fn venue<T: DexInstruction>(&self) -> DexEventNormalizer<T> {
+        DexEventNormalizer {
+            stream: self.clone(),
+            _instruction: PhantomData,
+        }
+    }
+
+    /// Delivers an event to the processor of the stream.
+    pub async fn push(&self, event: DexEvent, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        let counter = match &event {
+            DexEvent::Trade(_) => "dex_trades_normalized",
+            DexEvent::Liquidity(_) => "dex_liquidity_changes_normalized",
+        };
+        metrics.increment_counter(counter, 1).await?;
+
+        self.processor.lock().await.process(event, metrics).await
+    }
+}
+
+/// A processor normalizing the instructions of a venue into a
+/// `DexEventStream`.
+pub struct DexEventNormalizer<T> {
+    stream: DexEventStream,
+    _instruction: PhantomData<fn() -> T>,
+}
+
+#[async_trait]
+impl<T> Processor for DexEventNormalizer<T>
+where
+    T: DexInstruction + Send + Sync + 'static,
+{
+    type InputType = InstructionProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, instruction, _, _) = data;
+        let transaction_metadata = &metadata.transaction_metadata;
+
+        let event = if let Some(trade) = T::trade(&instruction, transaction_metadata) {
+            DexEvent::Trade(trade)
+        } else if let Some(change) = T::liquidity_change(&instruction, transaction_metadata) {
+            DexEvent::Liquidity(change)
+        } else {
+            return Ok(());
+        };
+
+        self.stream.push(event, metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_account_decoder_client_types::token::UiTokenAmount,
+        solana_message::{legacy::Message, VersionedMessage},
+        solana_transaction_status::TransactionTokenBalance,
+    };
+
+    fn token_balance(account_index: u8, mint: &Pubkey, amount: u64) -> TransactionTokenBalance {
+        TransactionTokenBalance {
+            account_index,
+            mint: mint.to_string(),
+            ui_token_amount: UiTokenAmount {
+                ui_amount: None,
+                decimals: 6,
+                amount: amount.to_string(),
+                ui_amount_string: String::new(),
+            },
+            owner: String::new(),
+            program_id: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_trade_from_token_accounts() {
+        // Arrange
+        let (trader, token_in, token_out) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (mint_in, mint_out) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut transaction_metadata = TransactionMetadata {
+            message: VersionedMessage::Legacy(Message {
+                account_keys: vec![trader, token_in, token_out],
+                ..Message::default()
+            }),
+            ..TransactionMetadata::default()
+        };
+        transaction_metadata.meta.pre_token_balances = Some(vec![
+            token_balance(1, &mint_in, 1_000),
+            token_balance(2, &mint_out, 50),
+        ]);
+        transaction_metadata.meta.post_token_balances = Some(vec![
+            token_balance(1, &mint_in, 400),
+            token_balance(2, &mint_out, 350),
+        ]);
+        let pool = Pubkey::new_unique();
+
+        // Act
+        let trade = NormalizedTrade::from_token_accounts(
+            "test",
+            pool,
+            trader,
+            &token_in,
+            &token_out,
+            None,
+            &transaction_metadata,
+        );
+
+        // Assert
+        let trade = trade.expect("token balances are recorded");
+        assert_eq!(trade.mint_in, mint_in);
+        assert_eq!(trade.mint_out, mint_out);
+        assert_eq!(trade.amount_in, 600);
+        assert_eq!(trade.amount_out, 300);
+        assert!(NormalizedTrade::from_token_accounts(
+            "test",
+            pool,
+            trader,
+            &token_in,
+            &Pubkey::new_unique(),
+            None,
+            &transaction_metadata,
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,190 @@
+//! Defines how decoders identify the type of instruction or account data.
+//!
+//! Most programs prefix their data with a discriminator, such as the 8-byte
+//! sighash of Anchor programs or the single tag byte of native programs, but
+//! some store it at an offset or don't have one at all and are told apart by
+//! the size of their data. The `discriminator` module captures these layouts
+//! as `DiscriminatorStrategy` implementations, used both by
+//! `#[derive(CarbonDeserialize)]` and by hand-written decoders.
+//!
+//! # Overview
+//!
+//! - **`DiscriminatorStrategy`**: Matches data against a discriminator and
+//!   returns the bytes to deserialize the type from.
+//! - **`Prefix`**: The discriminator prefixes the data, whatever its length.
+//!   This is the default strategy of `CarbonDeserialize`.
+//! - **`AnchorSighash`**: An 8-byte prefix, as computed by
+//!   `anchor_discriminator`.
+//! - **`FirstByte`**: A single tag byte prefixing the data.
+//! - **`ByteAtOffset`**: A tag byte at a fixed offset, part of the type's
+//!   layout.
+//! - **`NoDiscriminator`** and **`DataSize`**: Types without a discriminator,
+//!   optionally recognized by the exact size of their data.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::{borsh, CarbonDeserialize};
+//!
+//! // Token-2022 accounts with extensions store their type after the base
+//! // account, at byte 165.
+//! #[derive(CarbonDeserialize, Debug)]
+//! #[carbon(discriminator = "0x02", strategy = "ByteAtOffset<165>")]
+//! pub struct TokenAccountWithExtensions {
+//!     // ...
+//! }
+//!
+//! // Multisig accounts are only recognized by their size.
+//! #[derive(CarbonDeserialize, Debug)]
+//! #[carbon(strategy = "DataSize<355>")]
+//! pub struct Multisig {
+//!     // ...
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - Strategy names given to `#[carbon(strategy = "...")]` refer to this module
+//!   unless they are a path, so custom strategies can be used with their full
+//!   path.
+//! - Strategies are stateless and resolved at compile time, so matching adds no
+//!   overhead over a hand-written comparison.
+
+use crate::borsh::BorshDeserialize;
+
+/// Matches data against a discriminator.
+pub trait DiscriminatorStrategy {
+    /// Returns the bytes to deserialize the type from if `data` matches
+    /// `discriminator`, or `None` otherwise.
+    fn strip<'a>(discriminator: &[u8], data: &'a [u8]) -> Option<&'a [u8]>;
+
+    /// Returns `true` if `data` matches `discriminator`.
+    fn matches(discriminator: &[u8], data: &[u8]) -> bool {
+        Self::strip(discriminator, data).is_some()
+    }
+}
+
+/// The discriminator prefixes the data, and the type follows it.
+pub struct Prefix;
+
+impl DiscriminatorStrategy for Prefix {
+    fn strip<'a>(discriminator: &[u8], data: &'a [u8]) -> Option<&'a [u8]> {
+        data.strip_prefix(discriminator)
+    }
+}
+
+/// The 8-byte sighash of an Anchor instruction, account or event prefixes the
+/// data.
+pub struct AnchorSighash;
+
+impl DiscriminatorStrategy for AnchorSighash {
+    fn strip<'a>(discriminator: &[u8], data: &'a [u8]) -> Option<&'a [u8]> {
+        if discriminator.len() != 8 {
+            return None;
+        }
+
+        data.strip_prefix(discriminator)
+    }
+}
+
+/// A single tag byte prefixes the data, as in most native programs.
+pub struct FirstByte;
+
+impl DiscriminatorStrategy for FirstByte {
+    fn strip<'a>(discriminator: &[u8], data: &'a [u8]) -> Option<&'a [u8]> {
+        let [tag] = discriminator else {
+            return None;
+        };
+
+        match data.split_first() {
+            Some((first, rest)) if first == tag => Some(rest),
+            _ => None,
+        }
+    }
+}
+
+/// A single tag byte is stored at `OFFSET`.
+///
+/// The tag is part of the type's layout, so the type is deserialized from the
+/// whole data.
+pub struct ByteAtOffset<const OFFSET: usize>;
+
+impl<const OFFSET: usize> DiscriminatorStrategy for ByteAtOffset<OFFSET> {
+    fn strip<'a>(discriminator: &[u8], data: &'a [u8]) -> Option<&'a [u8]> {
+        let [tag] = discriminator else {
+            return None;
+        };
+
+        (data.get(OFFSET) == Some(tag)).then_some(data)
+    }
+}
+
+/// The type has no discriminator, and any data is deserialized.
+pub struct NoDiscriminator;
+
+impl DiscriminatorStrategy for NoDiscriminator {
+    fn strip<'a>(_discriminator: &[u8], data: &'a [u8]) -> Option<&'a [u8]> {
+        Some(data)
+    }
+}
+
+/// The type has no discriminator, and is recognized by its data being exactly
+/// `LEN` bytes long.
+pub struct DataSize<const LEN: usize>;
+
+impl<const LEN: usize> DiscriminatorStrategy for DataSize<LEN> {
+    fn strip<'a>(_discriminator: &[u8], data: &'a [u8]) -> Option<&'a [u8]> {
+        (data.len() == LEN).then_some(data)
+    }
+}
+
+/// Computes the discriminator Anchor derives for an item, the first 8 bytes of
+/// the SHA-256 hash of `<namespace>:<name>`.
+///
+/// Instructions use the `global` namespace and their snake case name, while
+/// accounts and events use the `account` and `event` namespaces and their
+/// type name.
+pub fn anchor_discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let hash = solana_program::hash::hashv(&[namespace.as_bytes(), b":", name.as_bytes()]);
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+
+    discriminator
+}
+
+/// Deserializes `T` with Borsh from `data` if it matches `discriminator`
+/// under the strategy `S`, for hand-written decoders.
+///
+/// Trailing bytes after `T` are ignored.
+pub fn deserialize_with<S: DiscriminatorStrategy, T: BorshDeserialize>(
+    discriminator: &[u8],
+    data: &[u8],
+) -> Option<T> {
+    let mut data = S::strip(discriminator, data)?;
+    T::deserialize(&mut data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strategies() {
+        let data = [2, 7, 0, 1];
+
+        assert_eq!(Prefix::strip(&[2, 7], &data), Some(&data[2..]));
+        assert_eq!(FirstByte::strip(&[2], &data), Some(&data[1..]));
+        assert_eq!(FirstByte::strip(&[7], &data), None);
+        assert_eq!(ByteAtOffset::<3>::strip(&[1], &data), Some(&data[..]));
+        assert_eq!(ByteAtOffset::<4>::strip(&[1], &data), None);
+        assert!(DataSize::<4>::matches(&[], &data));
+        assert!(!DataSize::<5>::matches(&[], &data));
+        assert!(!AnchorSighash::matches(&[2], &data));
+
+        // The sighash of the `initialize` instruction.
+        assert_eq!(
+            anchor_discriminator("global", "initialize"),
+            [0xaf, 0xaf, 0x6d, 0x1f, 0x0d, 0x98, 0x9b, 0xed]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Resolves wallet addresses to domain names, used to enrich decoded updates
+//! with human-readable names.
+//!
+//! Alerts and explorer-style sinks are easier to read with `toly.sol` than
+//! with a base58 address. Looking a name up requires fetching the name service
+//! accounts of each wallet though, which is too slow to do for every update.
+//! The `domain_names` module resolves the primary domain of wallets through a
+//! `DomainNameSource` and caches the results, including wallets without a
+//! domain.
+//!
+//! # Overview
+//!
+//! - **`DomainNameSource`**: Looks up the primary domain of wallets, typically
+//!   over RPC. `carbon-rpc-client` provides an implementation for the Solana
+//!   Name Service.
+//! - **`DomainNameResolver`**: A cloneable handle to the cache of resolved
+//!   names, fetching the names of unknown or expired wallets from its source.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::domain_names::DomainNameResolver;
+//! use carbon_rpc_client::RpcDomainNameSource;
+//!
+//! let names = DomainNameResolver::new(Arc::new(RpcDomainNameSource::new(rpc_client)))
+//!     .ttl(Duration::from_secs(6 * 60 * 60));
+//!
+//! // Inside `SwapAlertProcessor::process`:
+//! log::info!(
+//!     "{} swapped {} lamports",
+//!     names.label(&metadata.fee_payer).await,
+//!     swap.amount_in,
+//! );
+//! ```
+//!
+//! # Notes
+//!
+//! - Domains can be transferred or their primary domain changed, so resolved
+//!   names expire after the configured TTL, one hour by default.
+//! - Other naming services, such as AllDomains, can be supported by
+//!   implementing `DomainNameSource`, possibly falling back from one service to
+//!   another.
+//! - Once the cache holds `capacity` wallets, expired entries are evicted
+//!   before inserting new ones.
+
+use {
+    crate::error::CarbonResult,
+    async_trait::async_trait,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::RwLock,
+};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_CAPACITY: usize = 100_000;
+
+/// Looks up the primary domain names of wallets.
+#[async_trait]
+pub trait DomainNameSource: Send + Sync {
+    /// Returns the primary domain of each wallet, in order, such as
+    /// `toly.sol`, or `None` for wallets without one.
+    async fn primary_domains(&self, wallets: &[Pubkey]) -> CarbonResult<Vec<Option<String>>>;
+}
+
+#[derive(Debug, Clone)]
+struct CachedName {
+    name: Option<String>,
+    resolved_at: Instant,
+}
+
+/// A cloneable handle to a cache of the domain names of wallets.
+///
+/// Clones share the same cache, so a single resolver can be given to every
+/// processor enriching its updates.
+#[derive(Clone)]
+pub struct DomainNameResolver {
+    source: Arc<dyn DomainNameSource>,
+    names: Arc<RwLock<HashMap<Pubkey, CachedName>>>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl DomainNameResolver {
+    pub fn new(source: Arc<dyn DomainNameSource>) -> Self {
+        Self {
+            source,
+            names: Arc::new(RwLock::new(HashMap::new())),
+            ttl: DEFAULT_TTL,
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// Sets how long resolved names are cached.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the number of wallets above which expired names are evicted.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Returns the primary domain of a wallet, or `None` if it has none.
+    pub async fn resolve(&self, wallet: &Pubkey) -> CarbonResult<Option<String>> {
+        Ok(self
+            .resolve_many(std::slice::from_ref(wallet))
+            .await?
+            .remove(wallet))
+    }
+
+    /// Returns the primary domains of the given wallets, fetching the names
+    /// which aren't cached or have expired in a single lookup.
+    ///
+    /// Wallets without a domain are left out of the returned map.
+    pub async fn resolve_many(&self, wallets: &[Pubkey]) -> CarbonResult<HashMap<Pubkey, String>> {
+        let now = Instant::now();
+        let mut resolved = HashMap::new();
+        let mut missing = Vec::new();
+
+        {
+            let names = self.names.read().await;
+            for wallet in wallets {
+                match names.get(wallet) {
+                    Some(cached) if now.duration_since(cached.resolved_at) < self.ttl => {
+                        if let Some(name) = &cached.name {
+                            resolved.insert(*wallet, name.clone());
+                        }
+                    }
+                    _ if !missing.contains(wallet) => missing.push(*wallet),
+                    _ => {}
+                }
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(resolved);
+        }
+
+        let fetched = self.source.primary_domains(&missing).await?;
+        let mut names = self.names.write().await;
+        if names.len() + missing.len() > self.capacity {
+            names.retain(|_, cached| now.duration_since(cached.resolved_at) < self.ttl);
+        }
+
+        for (wallet, name) in missing.into_iter().zip(fetched) {
+            if let Some(name) = &name {
+                resolved.insert(wallet, name.clone());
+            }
+            names.insert(
+                wallet,
+                CachedName {
+                    name,
+                    resolved_at: now,
+                },
+            );
+        }
+
+        Ok(resolved)
+    }
+
+    /// Returns the primary domain of a wallet, or its address if it has none
+    /// or the lookup fails.
+    pub async fn label(&self, wallet: &Pubkey) -> String {
+        match self.resolve(wallet).await {
+            Ok(Some(name)) => name,
+            Ok(None) => wallet.to_string(),
+            Err(e) => {
+                log::warn!("Failed to resolve the domain name of {}: {}", wallet, e);
+                wallet.to_string()
+            }
+        }
+    }
+
+    /// Returns the number of cached wallets, including those without a domain.
+    pub async fn cached_names(&self) -> usize {
+        self.names.read().await.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    struct StaticSource {
+        names: HashMap<Pubkey, String>,
+        lookups: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl DomainNameSource for StaticSource {
+        async fn primary_domains(&self, wallets: &[Pubkey]) -> CarbonResult<Vec<Option<String>>> {
+            self.lookups.fetch_add(wallets.len(), Ordering::Relaxed);
+            Ok(wallets
+                .iter()
+                .map(|wallet| self.names.get(wallet).cloned())
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_caches_names_and_missing_domains() {
+        let named = Pubkey::new_unique();
+        let unnamed = Pubkey::new_unique();
+        let source = Arc::new(StaticSource {
+            names: HashMap::from([(named, "toly.sol".to_string())]),
+            lookups: AtomicUsize::new(0),
+        });
+        let resolver = DomainNameResolver::new(source.clone());
+
+        let resolved = resolver.resolve_many(&[named, unnamed]).await.unwrap();
+        assert_eq!(resolved.get(&named).map(String::as_str), Some("toly.sol"));
+        assert!(!resolved.contains_key(&unnamed));
+
+        assert_eq!(resolver.label(&named).await, "toly.sol");
+        assert_eq!(resolver.label(&unnamed).await, unnamed.to_string());
+        assert_eq!(source.lookups.load(Ordering::Relaxed), 2);
+        assert_eq!(resolver.cached_names().await, 2);
+    }
+}
@@ -0,0 +1,680 @@
+//! Provides a packaged pattern for running a low-latency pipeline alongside a
+//! finalized one and reconciling their outputs.
+//!
+//! Indexers frequently want both speed and correctness: a pipeline subscribed
+//! at `processed` commitment reacts within milliseconds, while a pipeline at
+//! `finalized` commitment only sees data that can no longer be rolled back.
+//! Wiring the two together by hand is error-prone, so this module provides
+//! the coordination and the comparison logic.
+//!
+//! # Overview
+//!
+//! - **`DualCommitmentPipeline`**: Runs a fast and a finalized `Pipeline`
+//!   concurrently and stops once both have shut down.
+//! - **`Reconciler`**: A cloneable handle that records what each side observed
+//!   and emits a `ReconciliationRecord` whenever the two disagree.
+//! - **`ReconciledProcessor`**: Wraps a regular processor on either side,
+//!   forwarding every input to it while recording a `(key, value)` pair for
+//!   reconciliation.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use carbon_core::dual_pipeline::{DualCommitmentPipeline, Reconciler};
+//!
+//! let reconciler = Reconciler::new(Duration::from_secs(120), MismatchAlertProcessor);
+//!
+//! let fast = carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(processed_datasource)
+//!     .instruction(
+//!         MyDecoder,
+//!         reconciler.fast(
+//!             |(metadata, instruction, _, _)| {
+//!                 Some((metadata.transaction_metadata.signature, instruction.data.clone()))
+//!             },
+//!             LowLatencyProcessor,
+//!         ),
+//!     )
+//!     .build()?;
+//!
+//! let finalized = carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(finalized_datasource)
+//!     .instruction(
+//!         MyDecoder,
+//!         reconciler.finalized(
+//!             |(metadata, instruction, _, _)| {
+//!                 Some((metadata.transaction_metadata.signature, instruction.data.clone()))
+//!             },
+//!             CorrectnessProcessor,
+//!         ),
+//!     )
+//!     .build()?;
+//!
+//! let _ticker = reconciler.spawn_ticker(Duration::from_secs(10), metrics.clone());
+//!
+//! DualCommitmentPipeline::new(fast, finalized)
+//!     .flush_on_shutdown(reconciler)
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Only disagreements are forwarded to the reconciliation processor. Matches
+//!   are counted through the `reconciliation_matched` metric.
+//! - A key may be observed several times on each side, for example once per
+//!   instruction of a transaction. Each observation is matched against an equal
+//!   observation of the same key on the other side.
+//! - Observations left unmatched once `finality_timeout` elapses are paired up
+//!   in arrival order and reported as mismatches, and the remaining ones as
+//!   missing from the other side, which on the fast side usually means the slot
+//!   was skipped or forked away.
+//! - Elapsed timeouts are checked whenever an observation is recorded, on every
+//!   tick of `Reconciler::spawn_ticker`, and when `Reconciler::flush_expired`
+//!   is called. `Reconciler::flush` reports every pending observation, which
+//!   `DualCommitmentPipeline::run` does once both pipelines returned for the
+//!   reconcilers registered with `DualCommitmentPipeline::flush_on_shutdown`.
+
+use {
+    crate::{
+        error::CarbonResult,
+        metrics::MetricsCollection,
+        pipeline::Pipeline,
+        processor::{Flush, Processor},
+    },
+    async_trait::async_trait,
+    std::{
+        collections::{HashMap, VecDeque},
+        hash::Hash,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::Mutex,
+};
+
+/// Runs a fast (e.g. `processed`) and a finalized pipeline side by side.
+///
+/// # Fields
+///
+/// - `fast`: The low-latency pipeline.
+/// - `finalized`: The pipeline consuming finalized data.
+/// - `shutdown_flushes`: The reconcilers flushed once both pipelines returned.
+pub struct DualCommitmentPipeline {
+    pub fast: Pipeline,
+    pub finalized: Pipeline,
+    shutdown_flushes: Vec<Box<dyn Flush>>,
+}
+
+impl DualCommitmentPipeline {
+    pub fn new(fast: Pipeline, finalized: Pipeline) -> Self {
+        Self {
+            fast,
+            finalized,
+            shutdown_flushes: Vec::new(),
+        }
+    }
+
+    /// Flushes `reconciler` once both pipelines returned, so the observations
+    /// still waiting for their counterpart are reported.
+    pub fn flush_on_shutdown(mut self, reconciler: impl Flush + 'static) -> Self {
+        log::trace!(
+            "flush_on_shutdown(self, reconciler: {:?})",
+            stringify!(reconciler)
+        );
+        self.shutdown_flushes.push(Box::new(reconciler));
+        self
+    }
+
+    /// Runs both pipelines concurrently until both have shut down, then
+    /// flushes the reconcilers registered with
+    /// `DualCommitmentPipeline::flush_on_shutdown`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the fast pipeline if it failed, otherwise the
+    /// error of the finalized pipeline, if any.
+    pub async fn run(&mut self) -> CarbonResult<()> {
+        log::info!("starting dual commitment pipeline");
+
+        let (fast_result, finalized_result) = tokio::join!(self.fast.run(), self.finalized.run());
+
+        if let Err(error) = &fast_result {
+            log::error!("fast pipeline failed: {:?}", error);
+        }
+        if let Err(error) = &finalized_result {
+            log::error!("finalized pipeline failed: {:?}", error);
+        }
+
+        for flush in &self.shutdown_flushes {
+            if let Err(error) = flush.flush_pending(self.finalized.metrics.clone()).await {
+                log::error!("error flushing reconciler: {:?}", error);
+            }
+        }
+
+        fast_result.and(finalized_result)
+    }
+}
+
+/// Identifies which side of a `DualCommitmentPipeline` produced an
+/// observation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitmentSide {
+    Fast,
+    Finalized,
+}
+
+/// Describes how the fast and finalized observations for a key disagree.
+///
+/// - `Mismatch`: Both sides observed the key, but with different values.
+///   Reported once the finality timeout elapses.
+/// - `MissingFromFinalized`: Only the fast side observed the key before the
+///   finality timeout elapsed.
+/// - `MissingFromFast`: Only the finalized side observed the key before the
+///   finality timeout elapsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReconciliationOutcome<V> {
+    Mismatch { fast: V, finalized: V },
+    MissingFromFinalized { fast: V },
+    MissingFromFast { finalized: V },
+}
+
+/// A disagreement between the fast and the finalized pipeline.
+#[derive(Debug, Clone)]
+pub struct ReconciliationRecord<K, V> {
+    pub key: K,
+    pub outcome: ReconciliationOutcome<V>,
+}
+
+struct Observation<V> {
+    value: V,
+    seen_at: Instant,
+}
+
+struct ReconcilerState<K, V> {
+    finality_timeout: Duration,
+    fast: HashMap<K, Vec<Observation<V>>>,
+    finalized: HashMap<K, Vec<Observation<V>>>,
+    /// The keys of every observation recorded, in arrival order. Keys whose
+    /// observations were matched or reported in the meantime are skipped.
+    expirations: VecDeque<(Instant, K)>,
+    pending: usize,
+}
+
+impl<K, V> ReconcilerState<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: PartialEq,
+{
+    fn new(finality_timeout: Duration) -> Self {
+        Self {
+            finality_timeout,
+            fast: HashMap::new(),
+            finalized: HashMap::new(),
+            expirations: VecDeque::new(),
+            pending: 0,
+        }
+    }
+
+    /// Records an observation, returning whether it matched an observation of
+    /// the other side.
+    fn observe(&mut self, side: CommitmentSide, key: K, value: V, now: Instant) -> bool {
+        let (own, other) = match side {
+            CommitmentSide::Fast => (&mut self.fast, &mut self.finalized),
+            CommitmentSide::Finalized => (&mut self.finalized, &mut self.fast),
+        };
+
+        if let Some(counterparts) = other.get_mut(&key) {
+            if let Some(position) = counterparts
+                .iter()
+                .position(|counterpart| counterpart.value == value)
+            {
+                counterparts.remove(position);
+                if counterparts.is_empty() {
+                    other.remove(&key);
+                }
+                self.pending -= 1;
+                return true;
+            }
+        }
+
+        own.entry(key.clone()).or_default().push(Observation {
+            value,
+            seen_at: now,
+        });
+        self.expirations.push_back((now, key));
+        self.pending += 1;
+        false
+    }
+
+    /// Removes the observations of every key with an observation older than
+    /// the finality timeout, returning the resulting disagreements in the
+    /// order the keys were first observed.
+    fn take_expired(&mut self, now: Instant) -> Vec<ReconciliationRecord<K, V>> {
+        let mut records = Vec::new();
+        while let Some((seen_at, _)) = self.expirations.front() {
+            if now.duration_since(*seen_at) < self.finality_timeout {
+                break;
+            }
+            let (_, key) = self.expirations.pop_front().expect("front exists");
+
+            // The oldest observation left for the key may be more recent than
+            // this one, which was then matched.
+            let oldest = [self.fast.get(&key), self.finalized.get(&key)]
+                .into_iter()
+                .flatten()
+                .filter_map(|observations| observations.first())
+                .map(|observation| observation.seen_at)
+                .min();
+            if oldest.is_some_and(|oldest| now.duration_since(oldest) >= self.finality_timeout) {
+                self.take_key(&key, &mut records);
+            }
+        }
+
+        records
+    }
+
+    /// Removes every pending observation, returning the resulting
+    /// disagreements in the order the keys were first observed.
+    fn take_all(&mut self) -> Vec<ReconciliationRecord<K, V>> {
+        let mut records = Vec::new();
+        while let Some((_, key)) = self.expirations.pop_front() {
+            self.take_key(&key, &mut records);
+        }
+
+        records
+    }
+
+    /// Removes the observations of `key`, pairing up the observations of both
+    /// sides in arrival order.
+    fn take_key(&mut self, key: &K, records: &mut Vec<ReconciliationRecord<K, V>>) {
+        let mut fast = self.fast.remove(key).unwrap_or_default().into_iter();
+        let mut finalized = self.finalized.remove(key).unwrap_or_default().into_iter();
+        loop {
+            let outcome = match (fast.next(), finalized.next()) {
+                (Some(fast), Some(finalized)) => {
+                    self.pending -= 2;
+                    ReconciliationOutcome::Mismatch {
+                        fast: fast.value,
+                        finalized: finalized.value,
+                    }
+                }
+                (Some(fast), None) => {
+                    self.pending -= 1;
+                    ReconciliationOutcome::MissingFromFinalized { fast: fast.value }
+                }
+                (None, Some(finalized)) => {
+                    self.pending -= 1;
+                    ReconciliationOutcome::MissingFromFast {
+                        finalized: finalized.value,
+                    }
+                }
+                (None, None) => break,
+            };
+            records.push(ReconciliationRecord {
+                key: key.clone(),
+                outcome,
+            });
+        }
+    }
+}
+
+/// A cloneable handle comparing what the fast and finalized pipelines
+/// observed.
+///
+/// # Type Parameters
+///
+/// - `K`: The key identifying an observation, such as a transaction signature
+///   or an account pubkey and slot.
+/// - `V`: The value compared between both sides.
+pub struct Reconciler<K, V> {
+    state: Arc<Mutex<ReconcilerState<K, V>>>,
+    processor: Arc<Mutex<ReconciliationProcessor<K, V>>>,
+}
+
+type ReconciliationProcessor<K, V> =
+    Box<dyn Processor<InputType = ReconciliationRecord<K, V>> + Send + Sync>;
+
+impl<K, V> Clone for Reconciler<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            processor: self.processor.clone(),
+        }
+    }
+}
+
+impl<K, V> Reconciler<K, V>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+    V: PartialEq + Send + 'static,
+{
+    /// Creates a new reconciler.
+    ///
+    /// # Parameters
+    ///
+    /// - `finality_timeout`: How long an observation waits for its counterpart
+    ///   before it is reported as missing from the other side.
+    /// - `processor`: Receives a `ReconciliationRecord` for every disagreement.
+    pub fn new(
+        finality_timeout: Duration,
+        processor: impl Processor<InputType = ReconciliationRecord<K, V>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ReconcilerState::new(finality_timeout))),
+            processor: Arc::new(Mutex::new(Box::new(processor))),
+        }
+    }
+
+    /// Wraps a processor of the fast pipeline.
+    ///
+    /// # Parameters
+    ///
+    /// - `extractor`: Maps the pipe's input into the key and value to
+    ///   reconcile. Returning `None` skips reconciliation for that input.
+    /// - `processor`: The processor receiving every input of the pipe.
+    pub fn fast<I>(
+        &self,
+        extractor: impl Fn(&I) -> Option<(K, V)> + Send + Sync + 'static,
+        processor: impl Processor<InputType = I> + Send + Sync + 'static,
+    ) -> ReconciledProcessor<I, K, V> {
+        self.side(CommitmentSide::Fast, extractor, processor)
+    }
+
+    /// Wraps a processor of the finalized pipeline.
+    ///
+    /// See `Reconciler::fast` for the parameters.
+    pub fn finalized<I>(
+        &self,
+        extractor: impl Fn(&I) -> Option<(K, V)> + Send + Sync + 'static,
+        processor: impl Processor<InputType = I> + Send + Sync + 'static,
+    ) -> ReconciledProcessor<I, K, V> {
+        self.side(CommitmentSide::Finalized, extractor, processor)
+    }
+
+    fn side<I>(
+        &self,
+        side: CommitmentSide,
+        extractor: impl Fn(&I) -> Option<(K, V)> + Send + Sync + 'static,
+        processor: impl Processor<InputType = I> + Send + Sync + 'static,
+    ) -> ReconciledProcessor<I, K, V> {
+        ReconciledProcessor {
+            side,
+            reconciler: self.clone(),
+            extractor: Box::new(extractor),
+            processor: Box::new(processor),
+        }
+    }
+
+    /// Records an observation from one side and emits the disagreements of
+    /// the observations whose finality timeout elapsed.
+    pub async fn observe(
+        &self,
+        side: CommitmentSide,
+        key: K,
+        value: V,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (matched, records, pending) = {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            let records = state.take_expired(now);
+            let matched = state.observe(side, key, value, now);
+            (matched, records, state.pending)
+        };
+
+        if matched {
+            metrics
+                .increment_counter("reconciliation_matched", 1)
+                .await?;
+        }
+
+        self.emit(records, pending, metrics).await
+    }
+
+    /// Emits the disagreements of the observations whose finality timeout
+    /// elapsed.
+    ///
+    /// Elapsed timeouts are also checked on every observation, so calling this
+    /// is only needed when a pipeline may stay idle, see
+    /// `Reconciler::spawn_ticker`.
+    pub async fn flush_expired(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        let (records, pending) = {
+            let mut state = self.state.lock().await;
+            (state.take_expired(Instant::now()), state.pending)
+        };
+
+        self.emit(records, pending, metrics).await
+    }
+
+    /// Emits the disagreements of every pending observation, including those
+    /// whose finality timeout didn't elapse yet.
+    pub async fn flush(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        let (records, pending) = {
+            let mut state = self.state.lock().await;
+            (state.take_all(), state.pending)
+        };
+
+        self.emit(records, pending, metrics).await
+    }
+
+    /// Spawns a task calling `Reconciler::flush_expired` every `interval`, so
+    /// disagreements are reported when their timeout elapses even if neither
+    /// pipeline records an observation.
+    ///
+    /// The task runs until the returned handle is aborted.
+    pub fn spawn_ticker(
+        &self,
+        interval: Duration,
+        metrics: Arc<MetricsCollection>,
+    ) -> tokio::task::JoinHandle<()> {
+        let reconciler = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = reconciler.flush_expired(metrics.clone()).await {
+                    log::error!("error flushing expired observations: {:?}", e);
+                }
+            }
+        })
+    }
+
+    async fn emit(
+        &self,
+        records: Vec<ReconciliationRecord<K, V>>,
+        pending: usize,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        if !records.is_empty() {
+            metrics
+                .increment_counter("reconciliation_disagreements", records.len() as u64)
+                .await?;
+        }
+        metrics
+            .update_gauge("reconciliation_pending", pending as f64)
+            .await?;
+
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut processor = self.processor.lock().await;
+        for record in records {
+            processor.process(record, metrics.clone()).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<K, V> Flush for Reconciler<K, V>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+    V: PartialEq + Send + 'static,
+{
+    async fn flush_pending(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        self.flush(metrics).await
+    }
+}
+
+/// Maps the input of a pipe into the key and value to reconcile.
+type Extractor<I, K, V> = dyn Fn(&I) -> Option<(K, V)> + Send + Sync;
+
+/// A processor that forwards its input to a wrapped processor and records an
+/// observation for reconciliation.
+pub struct ReconciledProcessor<I, K, V> {
+    side: CommitmentSide,
+    reconciler: Reconciler<K, V>,
+    extractor: Box<Extractor<I, K, V>>,
+    processor: Box<dyn Processor<InputType = I> + Send + Sync>,
+}
+
+#[async_trait]
+impl<I, K, V> Processor for ReconciledProcessor<I, K, V>
+where
+    I: Send + 'static,
+    K: Eq + Hash + Clone + Send + 'static,
+    V: PartialEq + Send + 'static,
+{
+    type InputType = I;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let observation = (self.extractor)(&data);
+
+        self.processor.process(data, metrics.clone()).await?;
+
+        if let Some((key, value)) = observation {
+            self.reconciler
+                .observe(self.side, key, value, metrics)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::sync::Mutex as StdMutex};
+
+    struct RecordingProcessor(Arc<StdMutex<Vec<ReconciliationRecord<u64, u8>>>>);
+
+    #[async_trait]
+    impl Processor for RecordingProcessor {
+        type InputType = ReconciliationRecord<u64, u8>;
+
+        async fn process(
+            &mut self,
+            data: Self::InputType,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            self.0.lock().unwrap().push(data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_multi_instruction_transaction() {
+        let mut state = ReconcilerState::new(Duration::from_secs(60));
+        let now = Instant::now();
+
+        // Two instructions of transaction 1, observed in a different order by
+        // each side, and transaction 2, whose second instruction differs.
+        assert!(!state.observe(CommitmentSide::Fast, 1, 10, now));
+        assert!(!state.observe(CommitmentSide::Fast, 1, 11, now));
+        assert!(!state.observe(CommitmentSide::Fast, 2, 20, now));
+        assert!(!state.observe(CommitmentSide::Fast, 2, 21, now));
+        assert!(state.observe(CommitmentSide::Finalized, 1, 11, now));
+        assert!(state.observe(CommitmentSide::Finalized, 1, 10, now));
+        assert!(state.observe(CommitmentSide::Finalized, 2, 20, now));
+        assert!(!state.observe(CommitmentSide::Finalized, 2, 22, now));
+        assert_eq!(state.pending, 2);
+
+        assert!(state.take_expired(now).is_empty());
+        let records = state.take_expired(now + Duration::from_secs(60));
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].key, 2);
+        assert_eq!(
+            records[0].outcome,
+            ReconciliationOutcome::Mismatch {
+                fast: 21,
+                finalized: 22
+            }
+        );
+        assert_eq!(state.pending, 0);
+        assert!(state.expirations.is_empty());
+    }
+
+    #[test]
+    fn test_expired_in_first_seen_order() {
+        let mut state = ReconcilerState::new(Duration::from_secs(60));
+        let now = Instant::now();
+
+        for key in [5, 3, 9, 1] {
+            state.observe(CommitmentSide::Fast, key, 0, now);
+        }
+        // Key 7 is matched, then observed again later.
+        state.observe(CommitmentSide::Finalized, 7, 1, now);
+        state.observe(CommitmentSide::Fast, 7, 1, now);
+        state.observe(
+            CommitmentSide::Finalized,
+            7,
+            2,
+            now + Duration::from_secs(30),
+        );
+
+        let records = state.take_expired(now + Duration::from_secs(60));
+        assert_eq!(
+            records.iter().map(|record| record.key).collect::<Vec<_>>(),
+            [5, 3, 9, 1]
+        );
+        assert_eq!(state.pending, 1);
+
+        let records = state.take_expired(now + Duration::from_secs(90));
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].outcome,
+            ReconciliationOutcome::MissingFromFast { finalized: 2 }
+        );
+        assert_eq!(state.pending, 0);
+    }
+
+    #[tokio::test]
+    async fn test_flush_reports_pending_observations() {
+        let records = Arc::new(StdMutex::new(Vec::new()));
+        let reconciler =
+            Reconciler::new(Duration::from_secs(60), RecordingProcessor(records.clone()));
+        let metrics = Arc::new(MetricsCollection::new(vec![]));
+
+        reconciler
+            .observe(CommitmentSide::Fast, 1, 10, metrics.clone())
+            .await
+            .unwrap();
+        reconciler
+            .observe(CommitmentSide::Finalized, 2, 20, metrics.clone())
+            .await
+            .unwrap();
+        reconciler.flush_expired(metrics.clone()).await.unwrap();
+        assert!(records.lock().unwrap().is_empty());
+
+        reconciler.flush_pending(metrics).await.unwrap();
+        assert_eq!(
+            records
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|record| (record.key, record.outcome.clone()))
+                .collect::<Vec<_>>(),
+            [
+                (1, ReconciliationOutcome::MissingFromFinalized { fast: 10 }),
+                (2, ReconciliationOutcome::MissingFromFast { finalized: 20 }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,589 @@
+//! Provides finality-aware processing, holding updates back until their slot
+//! is finalized or notifying processors when a fork drops a processed slot.
+//!
+//! Datasources streaming at `processed` or `confirmed` commitment deliver
+//! updates from slots that may later be skipped. Processors writing to a
+//! database either have to accept phantom rows or wait for finalization
+//! themselves. The `finality` module tracks the `SlotStatusUpdate`s emitted by
+//! datasources and lets processors elect how to handle unfinalized slots.
+//!
+//! # Overview
+//!
+//! - **`HasSlot`**: Implemented by processor inputs, returning the slot they
+//!   belong to.
+//! - **`FinalityEvent`**: The input of rollback-aware processors, which receive
+//!   every update as it arrives and are notified when its slot is finalized or
+//!   rolled back.
+//! - **`FinalityGate`**: A `Processor` wrapping another processor, either
+//!   buffering its inputs until their slot is finalized or forwarding them with
+//!   rollback notifications. It is driven by the slot statuses it receives
+//!   through its `status_processor`.
+//! - **`SlotStatusPipe`**: Passes the slot status updates of the pipeline to a
+//!   processor. It is registered through `PipelineBuilder::slot_status`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::finality::{FinalityEvent, FinalityGate};
+//!
+//! // Only receives swaps once their slot is finalized.
+//! let finalized_swaps = FinalityGate::finalized(SwapProcessor);
+//!
+//! // Receives swaps immediately, and a rollback for slots dropped by a fork.
+//! let live_swaps = FinalityGate::with_rollbacks(LiveSwapProcessor);
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .instruction(PumpfunDecoder, finalized_swaps.clone())
+//!     .slot_status(finalized_swaps.status_processor())
+//!     .instruction(PumpfunDecoder, live_swaps.clone())
+//!     .slot_status(live_swaps.status_processor())
+//!     .build()?
+//!     .run()
+//!     .await?;
+//!
+//! // Inside `LiveSwapProcessor::process`:
+//! match event {
+//!     FinalityEvent::Update(swap) => { /* Insert the swap. */ }
+//!     FinalityEvent::Finalized(slot) => { /* Mark the rows of `slot` final. */ }
+//!     FinalityEvent::RolledBack(slot) => { /* Delete the rows of `slot`. */ }
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - The datasource must provide `UpdateType::SlotStatus` updates, with a
+//!   `Finalized` status for every finalized slot. Otherwise buffered updates
+//!   are never released.
+//! - Slots are rolled back when they receive a `Forked` status, or when a later
+//!   slot is finalized and the chain of its parents, as reported by the
+//!   `parent` of the slot statuses, skips them. Pending slots lower than the
+//!   finalized slot and below the known chain of its parents are considered
+//!   finalized, as their own `Finalized` status may have been dropped.
+//! - Updates of slots which are already finalized when they arrive are
+//!   forwarded immediately.
+
+use {
+    crate::{
+        account::AccountProcessorInputType,
+        datasource::{AccountDeletion, BlockDetails, SlotStatus, SlotStatusUpdate},
+        error::CarbonResult,
+        instruction::InstructionProcessorInputType,
+        metrics::MetricsCollection,
+        processor::Processor,
+        transaction::{TransactionInstructionsInputType, TransactionProcessorInputType},
+    },
+    async_trait::async_trait,
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::Arc,
+    },
+    tokio::sync::Mutex,
+};
+
+/// A processor input belonging to a slot.
+pub trait HasSlot {
+    fn slot(&self) -> u64;
+}
+
+impl<T> HasSlot for AccountProcessorInputType<T> {
+    fn slot(&self) -> u64 {
+        self.0.slot
+    }
+}
+
+impl<T> HasSlot for InstructionProcessorInputType<T> {
+    fn slot(&self) -> u64 {
+        self.0.transaction_metadata.slot
+    }
+}
+
+impl<T, U> HasSlot for TransactionProcessorInputType<T, U> {
+    fn slot(&self) -> u64 {
+        self.0.slot
+    }
+}
+
+impl<T> HasSlot for TransactionInstructionsInputType<T> {
+    fn slot(&self) -> u64 {
+        self.0.slot
+    }
+}
+
+impl HasSlot for AccountDeletion {
+    fn slot(&self) -> u64 {
+        self.slot
+    }
+}
+
+impl HasSlot for BlockDetails {
+    fn slot(&self) -> u64 {
+        self.slot
+    }
+}
+
+/// The input of a rollback-aware processor.
+///
+/// - `Update`: An update, delivered as soon as it arrives.
+/// - `Finalized`: The slot of previously delivered updates was finalized, and
+///   can no longer be rolled back.
+/// - `RolledBack`: The slot of previously delivered updates was dropped by a
+///   fork, and their effects should be reverted.
+#[derive(Debug, Clone)]
+pub enum FinalityEvent<T> {
+    Update(T),
+    Finalized(u64),
+    RolledBack(u64),
+}
+
+enum GateMode<T> {
+    Finalized {
+        pending: BTreeMap<u64, Vec<T>>,
+        processor: Box<dyn Processor<InputType = T> + Send + Sync>,
+    },
+    Rollback {
+        delivered: BTreeSet<u64>,
+        processor: Box<dyn Processor<InputType = FinalityEvent<T>> + Send + Sync>,
+    },
+}
+
+struct GateState<T> {
+    mode: GateMode<T>,
+    finalized_slot: Option<u64>,
+    forked_slots: BTreeSet<u64>,
+    parents: BTreeMap<u64, u64>,
+}
+
+impl<T: HasSlot + Send + 'static> GateState<T> {
+    fn is_finalized(&self, slot: u64) -> bool {
+        self.finalized_slot
+            .is_some_and(|finalized| slot <= finalized)
+    }
+
+    async fn process(&mut self, data: T, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        let slot = data.slot();
+
+        if self.forked_slots.contains(&slot) {
+            return metrics
+                .increment_counter("finality_forked_updates_dropped", 1)
+                .await;
+        }
+
+        let finalized = self.is_finalized(slot);
+        match &mut self.mode {
+            GateMode::Finalized { pending, processor } => {
+                if finalized {
+                    return processor.process(data, metrics).await;
+                }
+
+                pending.entry(slot).or_default().push(data);
+                metrics
+                    .update_gauge("finality_pending_slots", pending.len() as f64)
+                    .await
+            }
+            GateMode::Rollback {
+                delivered,
+                processor,
+            } => {
+                if !finalized {
+                    delivered.insert(slot);
+                }
+
+                processor
+                    .process(FinalityEvent::Update(data), metrics)
+                    .await
+            }
+        }
+    }
+
+    async fn update_status(
+        &mut self,
+        update: SlotStatusUpdate,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        if let Some(parent) = update.parent {
+            if !self.is_finalized(update.slot) {
+                self.parents.insert(update.slot, parent);
+            }
+        }
+
+        match update.status {
+            SlotStatus::Finalized => self.finalize(update.slot, metrics).await,
+            SlotStatus::Forked => self.roll_back(update.slot, metrics).await,
+            SlotStatus::Processed | SlotStatus::Confirmed => Ok(()),
+        }
+    }
+
+    /// Returns the known ancestors of `slot`, and the lowest of them.
+    fn ancestors(&self, slot: u64) -> (BTreeSet<u64>, u64) {
+        let mut ancestors = BTreeSet::new();
+        let mut current = slot;
+        while let Some(parent) = self.parents.get(&current) {
+            ancestors.insert(*parent);
+            current = *parent;
+        }
+
+        (ancestors, current)
+    }
+
+    async fn finalize(&mut self, slot: u64, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        if self.is_finalized(slot) {
+            return Ok(());
+        }
+        let (ancestors, lowest_ancestor) = self.ancestors(slot);
+        let lower_slots: Vec<u64> = match &self.mode {
+            GateMode::Finalized { pending, .. } => {
+                pending.range(..slot).map(|(slot, _)| *slot).collect()
+            }
+            GateMode::Rollback { delivered, .. } => delivered.range(..slot).copied().collect(),
+        };
+        // A lower slot is only known to be abandoned if the chain of parents of
+        // the finalized slot reaches below it without going through it.
+        let abandoned: BTreeSet<u64> = lower_slots
+            .into_iter()
+            .filter(|lower| *lower > lowest_ancestor && !ancestors.contains(lower))
+            .collect();
+
+        self.finalized_slot = Some(slot);
+        self.forked_slots = self.forked_slots.split_off(&slot);
+        self.parents = self.parents.split_off(&slot);
+
+        match &mut self.mode {
+            GateMode::Finalized { pending, processor } => {
+                let remaining = pending.split_off(&(slot + 1));
+                let ready = std::mem::replace(pending, remaining);
+
+                for (ready_slot, updates) in ready {
+                    if abandoned.contains(&ready_slot) {
+                        metrics
+                            .increment_counter("finality_abandoned_slots", 1)
+                            .await?;
+                        continue;
+                    }
+
+                    for data in updates {
+                        processor.process(data, metrics.clone()).await?;
+                    }
+                    metrics
+                        .increment_counter("finality_slots_released", 1)
+                        .await?;
+                }
+
+                metrics
+                    .update_gauge("finality_pending_slots", pending.len() as f64)
+                    .await
+            }
+            GateMode::Rollback {
+                delivered,
+                processor,
+            } => {
+                let remaining = delivered.split_off(&(slot + 1));
+                let finalized = std::mem::replace(delivered, remaining);
+
+                for finalized_slot in finalized {
+                    if abandoned.contains(&finalized_slot) {
+                        processor
+                            .process(FinalityEvent::RolledBack(finalized_slot), metrics.clone())
+                            .await?;
+                        metrics
+                            .increment_counter("finality_slots_rolled_back", 1)
+                            .await?;
+                    } else {
+                        processor
+                            .process(FinalityEvent::Finalized(finalized_slot), metrics.clone())
+                            .await?;
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    async fn roll_back(&mut self, slot: u64, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        if self.is_finalized(slot) {
+            log::warn!("ignoring fork of finalized slot {}", slot);
+            return Ok(());
+        }
+        self.forked_slots.insert(slot);
+
+        match &mut self.mode {
+            GateMode::Finalized { pending, .. } => {
+                if pending.remove(&slot).is_some() {
+                    metrics
+                        .increment_counter("finality_forked_slots_dropped", 1)
+                        .await?;
+                }
+                Ok(())
+            }
+            GateMode::Rollback {
+                delivered,
+                processor,
+            } => {
+                if delivered.remove(&slot) {
+                    processor
+                        .process(FinalityEvent::RolledBack(slot), metrics.clone())
+                        .await?;
+                    metrics
+                        .increment_counter("finality_slots_rolled_back", 1)
+                        .await?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A processor gating the inputs of a wrapped processor on the finality of
+/// their slot.
+///
+/// Clones share the same state, so that one clone can be registered as the
+/// processor of a pipe and the `status_processor` of another can receive the
+/// slot statuses of the pipeline.
+///
+/// # Type Parameters
+///
+/// - `T`: The input type of the gated pipe, such as
+///   `InstructionProcessorInputType<T>`.
+pub struct FinalityGate<T> {
+    state: Arc<Mutex<GateState<T>>>,
+}
+
+impl<T> Clone for FinalityGate<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T: HasSlot + Send + 'static> FinalityGate<T> {
+    fn new(mode: GateMode<T>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(GateState {
+                mode,
+                finalized_slot: None,
+                forked_slots: BTreeSet::new(),
+                parents: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// Creates a gate buffering inputs until their slot is finalized, and
+    /// discarding the inputs of forked slots.
+    pub fn finalized(processor: impl Processor<InputType = T> + Send + Sync + 'static) -> Self {
+        Self::new(GateMode::Finalized {
+            pending: BTreeMap::new(),
+            processor: Box::new(processor),
+        })
+    }
+
+    /// Creates a gate forwarding inputs immediately, and notifying `processor`
+    /// once their slot is finalized or rolled back.
+    pub fn with_rollbacks(
+        processor: impl Processor<InputType = FinalityEvent<T>> + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(GateMode::Rollback {
+            delivered: BTreeSet::new(),
+            processor: Box::new(processor),
+        })
+    }
+
+    /// Creates the processor receiving the slot statuses driving this gate, to
+    /// be registered with `PipelineBuilder::slot_status`.
+    pub fn status_processor(&self) -> FinalityStatusProcessor<T> {
+        FinalityStatusProcessor { gate: self.clone() }
+    }
+}
+
+#[async_trait]
+impl<T> Processor for FinalityGate<T>
+where
+    T: HasSlot + Send + Sync + 'static,
+{
+    type InputType = T;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        self.state.lock().await.process(data, metrics).await
+    }
+}
+
+/// A processor passing slot statuses to a `FinalityGate`.
+pub struct FinalityStatusProcessor<T> {
+    gate: FinalityGate<T>,
+}
+
+#[async_trait]
+impl<T> Processor for FinalityStatusProcessor<T>
+where
+    T: HasSlot + Send + Sync + 'static,
+{
+    type InputType = SlotStatusUpdate;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        self.gate
+            .state
+            .lock()
+            .await
+            .update_status(data, metrics)
+            .await
+    }
+}
+
+/// A pipe passing slot status updates to a processor.
+///
+/// ## Fields
+///
+/// - `processor`: A `Processor` that processes slot status updates.
+pub struct SlotStatusPipe {
+    pub processor: Box<dyn Processor<InputType = SlotStatusUpdate> + Send + Sync>,
+}
+
+/// A trait for handling slot status updates in the pipeline.
+#[async_trait]
+pub trait SlotStatusPipes: Send + Sync {
+    async fn run(
+        &mut self,
+        slot_status: SlotStatusUpdate,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()>;
+}
+
+#[async_trait]
+impl SlotStatusPipes for SlotStatusPipe {
+    async fn run(
+        &mut self,
+        slot_status: SlotStatusUpdate,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        log::trace!(
+            "SlotStatusPipe::run(slot_status: {:?}, metrics)",
+            slot_status
+        );
+
+        self.processor.process(slot_status, metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Recorder(Arc<std::sync::Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Processor for Recorder {
+        type InputType = FinalityEvent<BlockDetails>;
+
+        async fn process(
+            &mut self,
+            data: Self::InputType,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            let event = match data {
+                FinalityEvent::Update(block_details) => format!("update {}", block_details.slot),
+                FinalityEvent::Finalized(slot) => format!("finalized {}", slot),
+                FinalityEvent::RolledBack(slot) => format!("rolled back {}", slot),
+            };
+            self.0.lock().unwrap().push(event);
+            Ok(())
+        }
+    }
+
+    fn block(slot: u64) -> BlockDetails {
+        BlockDetails {
+            slot,
+            block_hash: None,
+            previous_block_hash: None,
+            rewards: None,
+            num_reward_partitions: None,
+            block_time: None,
+            block_height: None,
+        }
+    }
+
+    fn status(slot: u64, parent: Option<u64>, status: SlotStatus) -> SlotStatusUpdate {
+        SlotStatusUpdate {
+            slot,
+            parent,
+            status,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollbacks_and_finalization() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut gate = FinalityGate::with_rollbacks(Recorder(events.clone()));
+        let mut statuses = gate.status_processor();
+        let metrics = Arc::new(MetricsCollection::default());
+
+        for slot in [10, 11, 12] {
+            gate.process(block(slot), metrics.clone()).await.unwrap();
+        }
+        statuses
+            .process(status(11, None, SlotStatus::Forked), metrics.clone())
+            .await
+            .unwrap();
+        gate.process(block(11), metrics.clone()).await.unwrap();
+        statuses
+            .process(status(12, None, SlotStatus::Finalized), metrics.clone())
+            .await
+            .unwrap();
+        gate.process(block(9), metrics.clone()).await.unwrap();
+
+        // The `Finalized` status of slot 10 was never received, and without
+        // parents it is assumed to be an ancestor of slot 12.
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "update 10",
+                "update 11",
+                "update 12",
+                "rolled back 11",
+                "finalized 10",
+                "finalized 12",
+                "update 9",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parents_skipping_a_slot_roll_it_back() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut gate = FinalityGate::with_rollbacks(Recorder(events.clone()));
+        let mut statuses = gate.status_processor();
+        let metrics = Arc::new(MetricsCollection::default());
+
+        for slot in [10, 11, 12, 13] {
+            gate.process(block(slot), metrics.clone()).await.unwrap();
+        }
+        // Slot 12 builds on slot 10, so slot 11 is on an abandoned fork, and
+        // slot 13 is on the finalized chain.
+        for (slot, parent) in [(11, 10), (12, 10), (10, 9)] {
+            statuses
+                .process(
+                    status(slot, Some(parent), SlotStatus::Confirmed),
+                    metrics.clone(),
+                )
+                .await
+                .unwrap();
+        }
+        statuses
+            .process(status(12, None, SlotStatus::Finalized), metrics.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events.lock().unwrap()[4..],
+            ["finalized 10", "rolled back 11", "finalized 12"]
+        );
+    }
+}
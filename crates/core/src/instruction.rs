@@ -21,7 +21,7 @@
 
 use {
     crate::{
-        error::CarbonResult, metrics::MetricsCollection, processor::Processor,
+        error::CarbonResult, metrics::MetricsCollection, processor::Processor, telemetry,
         transaction::TransactionMetadata,
     },
     async_trait::async_trait,
@@ -29,9 +29,11 @@ use {
     solana_instruction::AccountMeta,
     solana_pubkey::Pubkey,
     std::{
+        collections::HashMap,
         ops::{Deref, DerefMut},
         sync::Arc,
     },
+    tracing::Instrument,
 };
 
 /// Metadata associated with a specific instruction, including transaction-level
@@ -102,6 +104,13 @@ pub struct DecodedInstruction<T> {
 ///
 /// - `decode_instruction`: Decodes a raw Solana `Instruction` into a
 ///   `DecodedInstruction`.
+///
+/// # Provided Methods
+///
+/// - `program_ids`: The programs whose instructions the decoder decodes, used
+///   by the pipeline to only consult the decoders of the programs invoked by
+///   each instruction.
+/// - `decoder_name`: The name of the decoder in the spans of the pipeline.
 pub trait InstructionDecoder<'a> {
     type InstructionType;
 
@@ -109,6 +118,17 @@ pub trait InstructionDecoder<'a> {
         &self,
         instruction: &'a solana_instruction::Instruction,
     ) -> Option<DecodedInstruction<Self::InstructionType>>;
+
+    /// Returns the programs whose instructions the decoder decodes, or `None`
+    /// if it may decode the instructions of any program.
+    fn program_ids(&self) -> Option<&[Pubkey]> {
+        None
+    }
+
+    /// Returns the name of the decoder, its type name by default.
+    fn decoder_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// The input type for the instruction processor.
@@ -152,6 +172,11 @@ pub struct InstructionPipe<T: Send> {
 ///
 /// - `run`: Processes a `NestedInstruction`, recursively processing any inner
 ///   instructions.
+///
+/// # Provided Methods
+///
+/// - `program_ids`: The programs whose instructions the pipe decodes, or `None`
+///   if the pipe runs for every instruction.
 #[async_trait]
 pub trait InstructionPipes<'a>: Send + Sync {
     async fn run(
@@ -159,6 +184,10 @@ pub trait InstructionPipes<'a>: Send + Sync {
         nested_instruction: &NestedInstruction,
         metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()>;
+
+    fn program_ids(&self) -> Option<Vec<Pubkey>> {
+        None
+    }
 }
 
 #[async_trait]
@@ -177,7 +206,12 @@ impl<T: Send + 'static> InstructionPipes<'_> for InstructionPipe<T> {
             .decoder
             .decode_instruction(&nested_instruction.instruction)
         {
-            self.processor
+            let span = telemetry::process_span(
+                &nested_instruction.instruction.program_id,
+                self.decoder.decoder_name(),
+            );
+            let result = self
+                .processor
                 .process(
                     (
                         nested_instruction.metadata.clone(),
@@ -187,7 +221,12 @@ impl<T: Send + 'static> InstructionPipes<'_> for InstructionPipe<T> {
                     ),
                     metrics.clone(),
                 )
-                .await?;
+                .instrument(span.clone())
+                .await;
+            if result.is_err() {
+                telemetry::record_error(&span);
+            }
+            result?;
         }
 
         for nested_inner_instruction in nested_instruction.inner_instructions.iter() {
@@ -196,6 +235,69 @@ impl<T: Send + 'static> InstructionPipes<'_> for InstructionPipe<T> {
 
         Ok(())
     }
+
+    fn program_ids(&self) -> Option<Vec<Pubkey>> {
+        self.decoder.program_ids().map(<[Pubkey]>::to_vec)
+    }
+}
+
+/// A dispatch table of the instruction pipes of a pipeline, keyed by the
+/// program ids of their decoders.
+///
+/// Pipes whose decoders don't declare their program ids run for every
+/// instruction, and the other pipes only for the instructions invoking one of
+/// their programs, directly or through their inner instructions.
+#[derive(Debug, Default)]
+pub(crate) struct InstructionDispatch {
+    by_program_id: HashMap<Pubkey, Vec<usize>>,
+    any_program: Vec<usize>,
+}
+
+impl InstructionDispatch {
+    pub(crate) fn new(pipes: &[Box<dyn for<'a> InstructionPipes<'a>>]) -> Self {
+        let mut dispatch = Self::default();
+        for (index, pipe) in pipes.iter().enumerate() {
+            match pipe.program_ids() {
+                Some(program_ids) => {
+                    for program_id in program_ids {
+                        dispatch
+                            .by_program_id
+                            .entry(program_id)
+                            .or_default()
+                            .push(index);
+                    }
+                }
+                None => dispatch.any_program.push(index),
+            }
+        }
+
+        dispatch
+    }
+
+    /// Returns the indexes of the pipes to run for an instruction, in the
+    /// order the pipes were added.
+    pub(crate) fn pipes(&self, nested_instruction: &NestedInstruction) -> Vec<usize> {
+        let mut indexes = self.any_program.clone();
+        if !self.by_program_id.is_empty() {
+            self.collect_pipes(nested_instruction, &mut indexes);
+            indexes.sort_unstable();
+            indexes.dedup();
+        }
+
+        indexes
+    }
+
+    fn collect_pipes(&self, nested_instruction: &NestedInstruction, indexes: &mut Vec<usize>) {
+        if let Some(pipes) = self
+            .by_program_id
+            .get(&nested_instruction.instruction.program_id)
+        {
+            indexes.extend(pipes);
+        }
+        for inner_instruction in nested_instruction.inner_instructions.iter() {
+            self.collect_pipes(inner_instruction, indexes);
+        }
+    }
 }
 
 /// Represents a nested instruction with metadata, including potential inner
@@ -413,4 +515,45 @@ mod tests {
         assert_eq!(nested_instructions.len(), 2);
         assert_eq!(nested_instructions.0[1].inner_instructions.len(), 1);
     }
+
+    struct ProgramPipe(Option<Vec<Pubkey>>);
+
+    #[async_trait]
+    impl InstructionPipes<'_> for ProgramPipe {
+        async fn run(
+            &mut self,
+            _nested_instruction: &NestedInstruction,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            Ok(())
+        }
+
+        fn program_ids(&self) -> Option<Vec<Pubkey>> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_dispatch_by_program_id() {
+        let instructions = vec![
+            create_instruction_with_metadata(1, 1),
+            create_instruction_with_metadata(1, 2),
+            create_instruction_with_metadata(2, 1),
+        ];
+        let inner_program = instructions[1].1.program_id;
+        let second_program = instructions[2].1.program_id;
+        let nested_instructions: NestedInstructions = instructions.into();
+
+        let pipes: Vec<Box<dyn for<'a> InstructionPipes<'a>>> = vec![
+            Box::new(ProgramPipe(Some(vec![Pubkey::new_unique()]))),
+            Box::new(ProgramPipe(Some(vec![inner_program]))),
+            Box::new(ProgramPipe(None)),
+            Box::new(ProgramPipe(Some(vec![second_program]))),
+        ];
+        let dispatch = InstructionDispatch::new(&pipes);
+
+        // The first instruction invokes `inner_program` through a CPI.
+        assert_eq!(dispatch.pipes(&nested_instructions[0]), vec![1, 2]);
+        assert_eq!(dispatch.pipes(&nested_instructions[1]), vec![2, 3]);
+    }
 }
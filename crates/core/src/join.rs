@@ -0,0 +1,511 @@
+//! Provides a utility for correlating decoded outputs coming from different
+//! pipes into a single bundle.
+//!
+//! Pipes in a `carbon-core` pipeline run independently: an instruction pipe
+//! sees a swap instruction while an account pipe sees the resulting pool
+//! account update, but no single processor sees both. The `join` module fills
+//! that gap by buffering outputs from several pipes and emitting them together
+//! once the window of their key elapses.
+//!
+//! # Overview
+//!
+//! - **`JoinKey`**: The correlation key shared by all sides of a join, either a
+//!   transaction signature or a `(slot, pubkey)` pair.
+//! - **`JoinedBundle`**: The correlated output handed to the downstream
+//!   processor, containing every item collected for a key.
+//! - **`StreamJoin`**: A cloneable handle to the shared join state. It owns the
+//!   downstream processor and hands out sides via `StreamJoin::side`.
+//! - **`JoinSide`**: A `Processor` registered on a regular pipe which maps the
+//!   pipe's input into a `(JoinKey, T)` pair and pushes it into the join.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use carbon_core::join::{JoinKey, StreamJoin};
+//!
+//! enum SwapWithPool {
+//!     Swap(MyInstruction),
+//!     Pool(MyAccount),
+//! }
+//!
+//! let join = StreamJoin::new(
+//!     vec!["swap", "pool"],
+//!     Duration::from_secs(2),
+//!     SwapWithPoolProcessor,
+//! );
+//! let _ticker = join.spawn_ticker(Duration::from_millis(500), metrics.clone());
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .instruction(
+//!         MyDecoder,
+//!         join.side("swap", |(metadata, instruction, _, _)| {
+//!             Some((
+//!                 JoinKey::SlotPubkey {
+//!                     slot: metadata.transaction_metadata.slot,
+//!                     pubkey: instruction.accounts.first()?.pubkey,
+//!                 },
+//!                 SwapWithPool::Swap(instruction.data.clone()),
+//!             ))
+//!         }),
+//!     )
+//!     .account(
+//!         MyDecoder,
+//!         join.side("pool", |(metadata, account, _)| {
+//!             Some((
+//!                 JoinKey::SlotPubkey {
+//!                     slot: metadata.slot,
+//!                     pubkey: metadata.pubkey,
+//!                 },
+//!                 SwapWithPool::Pool(account.data.clone()),
+//!             ))
+//!         }),
+//!     )
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - A key collects items for the duration of the window, starting with its
+//!   first item, so a source may contribute several items for the same key, for
+//!   example when a transaction contains multiple swaps. The bundle is marked
+//!   incomplete if a source contributed none.
+//! - Elapsed windows are checked whenever a new item is pushed, on every tick
+//!   of `StreamJoin::spawn_ticker`, and when `StreamJoin::flush_expired` is
+//!   called. `StreamJoin::flush` emits every pending bundle, for example once
+//!   the pipeline stopped.
+//! - Bundles are emitted in the order their keys were first seen. `StreamJoin`
+//!   implements `Flush`, so registering it with
+//!   `PipelineBuilder::flush_on_shutdown` emits the pending bundles once the
+//!   pipeline stopped.
+
+use {
+    crate::{
+        error::CarbonResult,
+        metrics::MetricsCollection,
+        processor::{Flush, Processor},
+    },
+    async_trait::async_trait,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::Mutex,
+};
+
+/// The key used to correlate items coming from different pipes.
+///
+/// - `Signature`: Correlates everything produced by a single transaction.
+/// - `SlotPubkey`: Correlates items that touch the same account in the same
+///   slot, e.g. an instruction and the account update it caused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JoinKey {
+    Signature(Signature),
+    SlotPubkey { slot: u64, pubkey: Pubkey },
+}
+
+/// A set of items correlated under the same `JoinKey`.
+///
+/// # Fields
+///
+/// - `key`: The key the items were correlated under.
+/// - `items`: The collected items, paired with the name of the side that
+///   produced them, in arrival order.
+/// - `complete`: Whether every expected side contributed at least one item
+///   before the bundle was emitted.
+#[derive(Debug, Clone)]
+pub struct JoinedBundle<T> {
+    pub key: JoinKey,
+    pub items: Vec<(String, T)>,
+    pub complete: bool,
+}
+
+impl<T> JoinedBundle<T> {
+    /// Returns an iterator over the items contributed by the given side.
+    pub fn from_side<'a>(&'a self, side: &'a str) -> impl Iterator<Item = &'a T> + 'a {
+        self.items
+            .iter()
+            .filter(move |(name, _)| name == side)
+            .map(|(_, item)| item)
+    }
+}
+
+/// The buffering state of a join, kept free of any async concerns so that the
+/// correlation logic stays deterministic.
+pub(crate) struct JoinBuffer<T> {
+    sources: Vec<String>,
+    window: Duration,
+    pending: HashMap<JoinKey, Vec<(String, T)>>,
+    /// The opening time of every window, in first-seen order.
+    expirations: VecDeque<(Instant, JoinKey)>,
+}
+
+impl<T> JoinBuffer<T> {
+    pub(crate) fn new(sources: Vec<String>, window: Duration) -> Self {
+        Self {
+            sources,
+            window,
+            pending: HashMap::new(),
+            expirations: VecDeque::new(),
+        }
+    }
+
+    /// Buffers `item` under `key`, opening the window of the key if it is the
+    /// first item.
+    pub(crate) fn insert(&mut self, source: &str, key: JoinKey, item: T, now: Instant) {
+        let expirations = &mut self.expirations;
+        self.pending
+            .entry(key)
+            .or_insert_with(|| {
+                expirations.push_back((now, key));
+                Vec::new()
+            })
+            .push((source.to_string(), item));
+    }
+
+    /// Removes and returns every bundle whose window elapsed, in the order
+    /// their keys were first seen.
+    pub(crate) fn take_expired(&mut self, now: Instant) -> Vec<JoinedBundle<T>> {
+        let mut bundles = Vec::new();
+        while let Some(&(first_seen, key)) = self.expirations.front() {
+            if now.duration_since(first_seen) < self.window {
+                break;
+            }
+            self.expirations.pop_front();
+            bundles.extend(self.take(key));
+        }
+        bundles
+    }
+
+    /// Removes and returns every pending bundle, whether its window elapsed
+    /// or not, in the order their keys were first seen.
+    pub(crate) fn take_all(&mut self) -> Vec<JoinedBundle<T>> {
+        let keys: Vec<JoinKey> = self.expirations.drain(..).map(|(_, key)| key).collect();
+        keys.into_iter().filter_map(|key| self.take(key)).collect()
+    }
+
+    fn take(&mut self, key: JoinKey) -> Option<JoinedBundle<T>> {
+        let items = self.pending.remove(&key)?;
+        let complete = self
+            .sources
+            .iter()
+            .all(|expected| items.iter().any(|(name, _)| name == expected));
+
+        Some(JoinedBundle {
+            key,
+            items,
+            complete,
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+struct JoinState<T> {
+    buffer: JoinBuffer<T>,
+    processor: Box<dyn Processor<InputType = JoinedBundle<T>> + Send + Sync>,
+}
+
+/// A cloneable handle to a join shared between several pipes.
+///
+/// `StreamJoin` owns the downstream processor that receives `JoinedBundle`s.
+/// Each pipe taking part in the join is registered with a `JoinSide` created
+/// through `StreamJoin::side`.
+///
+/// # Type Parameters
+///
+/// - `T`: The item type collected by the join. Since sides usually decode
+///   different types, this is typically an enum defined by the user.
+pub struct StreamJoin<T> {
+    state: Arc<Mutex<JoinState<T>>>,
+}
+
+impl<T> Clone for StreamJoin<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> StreamJoin<T> {
+    /// Creates a new join expecting the given sides.
+    ///
+    /// # Parameters
+    ///
+    /// - `sources`: The names of all sides that must contribute for a bundle to
+    ///   be complete.
+    /// - `window`: How long a key collects items, from its first item, before
+    ///   its bundle is emitted.
+    /// - `processor`: The processor receiving the correlated bundles.
+    pub fn new(
+        sources: Vec<&str>,
+        window: Duration,
+        processor: impl Processor<InputType = JoinedBundle<T>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(JoinState {
+                buffer: JoinBuffer::new(sources.into_iter().map(String::from).collect(), window),
+                processor: Box::new(processor),
+            })),
+        }
+    }
+
+    /// Creates a side of the join that can be registered as the processor of
+    /// a regular pipe.
+    ///
+    /// # Parameters
+    ///
+    /// - `name`: The name of the side, which must be one of the sources passed
+    ///   to `StreamJoin::new`.
+    /// - `extractor`: Maps the pipe's input into the key and item pushed into
+    ///   the join. Returning `None` skips the input.
+    pub fn side<I>(
+        &self,
+        name: &str,
+        extractor: impl Fn(&I) -> Option<(JoinKey, T)> + Send + Sync + 'static,
+    ) -> JoinSide<I, T> {
+        JoinSide {
+            name: name.to_string(),
+            join: self.clone(),
+            extractor: Box::new(extractor),
+        }
+    }
+
+    /// Pushes an item into the join, emitting any bundle whose window elapsed.
+    pub async fn push(
+        &self,
+        source: &str,
+        key: JoinKey,
+        item: T,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+
+        state.buffer.insert(source, key, item, now);
+        let expired = state.buffer.take_expired(now);
+        state.emit(expired, metrics).await
+    }
+
+    /// Emits every bundle whose window elapsed.
+    ///
+    /// Elapsed windows are also checked on every push, so calling this is only
+    /// needed when the join may stay idle, see `StreamJoin::spawn_ticker`.
+    pub async fn flush_expired(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        let mut state = self.state.lock().await;
+        let expired = state.buffer.take_expired(Instant::now());
+        state.emit(expired, metrics).await
+    }
+
+    /// Emits every pending bundle, including those whose window is still
+    /// open.
+    pub async fn flush(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        let mut state = self.state.lock().await;
+        let pending = state.buffer.take_all();
+        state.emit(pending, metrics).await
+    }
+
+    /// Spawns a task calling `StreamJoin::flush_expired` every `interval`, so
+    /// bundles are emitted when their window elapses even if no item is pushed.
+    ///
+    /// The task runs until the returned handle is aborted.
+    pub fn spawn_ticker(
+        &self,
+        interval: Duration,
+        metrics: Arc<MetricsCollection>,
+    ) -> tokio::task::JoinHandle<()> {
+        let join = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = join.flush_expired(metrics.clone()).await {
+                    log::error!("error flushing expired join bundles: {:?}", e);
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl<T: Send + 'static> Flush for StreamJoin<T> {
+    async fn flush_pending(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        self.flush(metrics).await
+    }
+}
+
+impl<T: Send + 'static> JoinState<T> {
+    async fn emit(
+        &mut self,
+        bundles: Vec<JoinedBundle<T>>,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let completed = bundles.iter().filter(|bundle| bundle.complete).count();
+        if completed > 0 {
+            metrics
+                .increment_counter("join_bundles_completed", completed as u64)
+                .await?;
+        }
+        if bundles.len() > completed {
+            metrics
+                .increment_counter("join_bundles_expired", (bundles.len() - completed) as u64)
+                .await?;
+        }
+        metrics
+            .update_gauge("join_bundles_pending", self.buffer.len() as f64)
+            .await?;
+
+        for bundle in bundles {
+            self.processor.process(bundle, metrics.clone()).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps the input of a side into the key and item pushed into the join.
+type Extractor<I, T> = dyn Fn(&I) -> Option<(JoinKey, T)> + Send + Sync;
+
+/// A processor that feeds a pipe's output into a `StreamJoin`.
+///
+/// # Type Parameters
+///
+/// - `I`: The input type of the pipe this side is registered on.
+/// - `T`: The item type collected by the join.
+pub struct JoinSide<I, T> {
+    name: String,
+    join: StreamJoin<T>,
+    extractor: Box<Extractor<I, T>>,
+}
+
+#[async_trait]
+impl<I, T> Processor for JoinSide<I, T>
+where
+    I: Send + 'static,
+    T: Send + 'static,
+{
+    type InputType = I;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        if let Some((key, item)) = (self.extractor)(&data) {
+            self.join.push(&self.name, key, item, metrics).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources() -> Vec<String> {
+        vec!["swap".to_string(), "pool".to_string()]
+    }
+
+    #[test]
+    fn test_bundle_collects_items_until_window_elapses() {
+        let mut buffer = JoinBuffer::new(sources(), Duration::from_secs(5));
+        let key = JoinKey::Signature(Signature::new_unique());
+        let now = Instant::now();
+
+        // A transaction with two swaps: the second one arrives after the pool
+        // update and still joins the same bundle.
+        buffer.insert("swap", key, 1, now);
+        buffer.insert("pool", key, 2, now);
+        buffer.insert("swap", key, 3, now + Duration::from_secs(1));
+        assert!(buffer.take_expired(now + Duration::from_secs(4)).is_empty());
+
+        let bundles = buffer.take_expired(now + Duration::from_secs(5));
+        assert_eq!(bundles.len(), 1);
+        assert!(bundles[0].complete);
+        assert_eq!(
+            bundles[0].from_side("swap").copied().collect::<Vec<_>>(),
+            [1, 3]
+        );
+        assert_eq!(
+            bundles[0].from_side("pool").copied().collect::<Vec<_>>(),
+            [2]
+        );
+        assert_eq!(buffer.len(), 0);
+    }
+
+    #[test]
+    fn test_incomplete_bundle_expires() {
+        let mut buffer = JoinBuffer::new(sources(), Duration::from_secs(5));
+        let key = JoinKey::SlotPubkey {
+            slot: 42,
+            pubkey: Pubkey::new_unique(),
+        };
+        let now = Instant::now();
+
+        buffer.insert("swap", key, 1, now);
+        assert!(buffer.take_expired(now).is_empty());
+
+        let expired = buffer.take_expired(now + Duration::from_secs(5));
+        assert_eq!(expired.len(), 1);
+        assert!(!expired[0].complete);
+        assert_eq!(expired[0].key, key);
+        assert_eq!(buffer.len(), 0);
+    }
+
+    #[test]
+    fn test_expired_in_first_seen_order() {
+        let mut buffer = JoinBuffer::new(sources(), Duration::from_secs(5));
+        let keys: Vec<JoinKey> = (0..4)
+            .map(|_| JoinKey::Signature(Signature::new_unique()))
+            .collect();
+        let now = Instant::now();
+
+        for (offset, key) in keys.iter().enumerate() {
+            buffer.insert(
+                "swap",
+                *key,
+                offset,
+                now + Duration::from_secs(offset as u64),
+            );
+        }
+        // The window of a key opens with its first item only.
+        buffer.insert("pool", keys[0], 4, now + Duration::from_secs(3));
+
+        let expired = buffer.take_expired(now + Duration::from_secs(6));
+        assert_eq!(
+            expired.iter().map(|bundle| bundle.key).collect::<Vec<_>>(),
+            keys[..2]
+        );
+        assert!(expired[0].complete);
+
+        let rest = buffer.take_all();
+        assert_eq!(
+            rest.iter().map(|bundle| bundle.key).collect::<Vec<_>>(),
+            keys[2..]
+        );
+        assert_eq!(buffer.len(), 0);
+    }
+
+    #[test]
+    fn test_take_all_flushes_open_windows() {
+        let mut buffer = JoinBuffer::new(sources(), Duration::from_secs(5));
+        let now = Instant::now();
+        buffer.insert("swap", JoinKey::Signature(Signature::new_unique()), 1, now);
+        buffer.insert("pool", JoinKey::Signature(Signature::new_unique()), 2, now);
+
+        let bundles = buffer.take_all();
+        assert_eq!(bundles.len(), 2);
+        assert!(bundles.iter().all(|bundle| !bundle.complete));
+        assert_eq!(buffer.len(), 0);
+    }
+}
@@ -0,0 +1,312 @@
+//! Decodes large accounts in chunks of records, so a single giant account
+//! doesn't spike memory or hold the pipeline for the whole decode.
+//!
+//! Some accounts, such as event queues or large address lookup tables, are
+//! megabytes of fixed-size records behind a header. Decoding them into a single
+//! value allocates a second copy of the whole account and keeps the pipeline
+//! busy until the last record is decoded. A `ChunkedAccountPipe` decodes the
+//! records of such accounts a chunk at a time, passing each chunk to its
+//! processor and yielding to the runtime in between.
+//!
+//! # Overview
+//!
+//! - **`ChunkedAccountDecoder`**: Describes the record layout of the accounts
+//!   it handles and decodes a single record.
+//! - **`AccountChunk`**: The input of chunk processors, holding consecutive
+//!   decoded records of an account.
+//! - **`ChunkedAccountPipe`**: An account pipe decoding the records of an
+//!   account in chunks of `records_per_chunk` records.
+//!
+//! Combined with `PipelineBuilder::max_account_data_size`, accounts over the
+//! size limit are only passed to chunked pipes, and skipped by the other
+//! pipes.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::large_account::{ChunkedAccountDecoder, RecordLayout};
+//!
+//! struct LookupTableAddresses;
+//!
+//! impl ChunkedAccountDecoder for LookupTableAddresses {
+//!     type Record = Pubkey;
+//!
+//!     fn layout(&self, account: &solana_account::Account) -> Option<RecordLayout> {
+//!         (account.owner == ADDRESS_LOOKUP_TABLE_PROGRAM_ID).then_some(RecordLayout {
+//!             offset: 56,
+//!             record_size: 32,
+//!         })
+//!     }
+//!
+//!     fn decode_record(&self, record: &[u8]) -> Option<Pubkey> {
+//!         Pubkey::try_from(record).ok()
+//!     }
+//! }
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .max_account_data_size(1024 * 1024)
+//!     .account_chunks(LookupTableAddresses, LookupTableAddressesProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Trailing bytes shorter than a record are ignored.
+//! - Records failing to decode are skipped, so the records of a chunk may not
+//!   be contiguous. Each record is paired with its index in the account.
+
+use {
+    crate::{
+        account::{AccountMetadata, AccountPipes},
+        error::CarbonResult,
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    async_trait::async_trait,
+    solana_pubkey::Pubkey,
+    std::sync::Arc,
+};
+
+/// The number of records passed to chunk processors at once, unless
+/// configured otherwise.
+pub const DEFAULT_RECORDS_PER_CHUNK: usize = 1_024;
+
+/// Where the fixed-size records of an account are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordLayout {
+    /// The offset of the first record, past the header of the account.
+    pub offset: usize,
+    /// The size of each record, in bytes.
+    pub record_size: usize,
+}
+
+/// Decodes the records of large accounts one at a time.
+pub trait ChunkedAccountDecoder: Send + Sync {
+    type Record;
+
+    /// Returns the layout of the records of an account, or `None` if the
+    /// decoder doesn't handle the account.
+    fn layout(&self, account: &solana_account::Account) -> Option<RecordLayout>;
+
+    /// Decodes a record, or returns `None` if the record is empty or invalid.
+    fn decode_record(&self, record: &[u8]) -> Option<Self::Record>;
+}
+
+/// Consecutive decoded records of an account.
+///
+/// # Fields
+///
+/// - `metadata`: The metadata of the account update.
+/// - `owner`: The owner of the account.
+/// - `lamports`: The lamports of the account.
+/// - `records`: The decoded records, paired with their index in the account.
+/// - `last`: Whether this is the last chunk of the account. An account without
+///   records is passed as a single empty last chunk.
+#[derive(Debug, Clone)]
+pub struct AccountChunk<T> {
+    pub metadata: AccountMetadata,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub records: Vec<(usize, T)>,
+    pub last: bool,
+}
+
+/// An account pipe decoding the records of accounts in chunks.
+pub struct ChunkedAccountPipe<T: Send> {
+    pub decoder: Box<dyn ChunkedAccountDecoder<Record = T>>,
+    pub processor: Box<dyn Processor<InputType = AccountChunk<T>> + Send + Sync>,
+    pub records_per_chunk: usize,
+}
+
+#[async_trait]
+impl<T: Send> AccountPipes for ChunkedAccountPipe<T> {
+    async fn run(
+        &mut self,
+        account_with_metadata: (AccountMetadata, solana_account::Account),
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, account) = account_with_metadata;
+        log::trace!(
+            "ChunkedAccountPipe::run(metadata: {:?}, data_len: {})",
+            metadata,
+            account.data.len()
+        );
+
+        let Some(layout) = self.decoder.layout(&account) else {
+            return Ok(());
+        };
+        if layout.record_size == 0 {
+            return Ok(());
+        }
+
+        let records_per_chunk = self.records_per_chunk.max(1);
+        let records_data = account.data.get(layout.offset..).unwrap_or_default();
+        let record_count = records_data.len() / layout.record_size;
+        // An account without records still produces an empty last chunk, so
+        // processors learn about every account.
+        let chunk_count = record_count.div_ceil(records_per_chunk).max(1);
+        let chunk_size = layout.record_size * records_per_chunk;
+        let records_len = record_count * layout.record_size;
+
+        for chunk_index in 0..chunk_count {
+            let start = chunk_index * chunk_size;
+            let chunk = &records_data[start..(start + chunk_size).min(records_len)];
+            let first_record = chunk_index * records_per_chunk;
+            let records = chunk
+                .chunks_exact(layout.record_size)
+                .enumerate()
+                .filter_map(|(index, record)| {
+                    self.decoder
+                        .decode_record(record)
+                        .map(|record| (first_record + index, record))
+                })
+                .collect();
+
+            self.processor
+                .process(
+                    AccountChunk {
+                        metadata: metadata.clone(),
+                        owner: account.owner,
+                        lamports: account.lamports,
+                        records,
+                        last: chunk_index + 1 == chunk_count,
+                    },
+                    metrics.clone(),
+                )
+                .await?;
+
+            // Let the runtime run other tasks between chunks.
+            tokio::task::yield_now().await;
+        }
+
+        metrics
+            .increment_counter("account_chunks_decoded", chunk_count as u64)
+            .await
+    }
+
+    fn streams_large_accounts(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct U32Records;
+
+    impl ChunkedAccountDecoder for U32Records {
+        type Record = u32;
+
+        fn layout(&self, _account: &solana_account::Account) -> Option<RecordLayout> {
+            Some(RecordLayout {
+                offset: 8,
+                record_size: 4,
+            })
+        }
+
+        fn decode_record(&self, record: &[u8]) -> Option<u32> {
+            let value = u32::from_le_bytes(record.try_into().ok()?);
+            (value != 0).then_some(value)
+        }
+    }
+
+    type Chunks = Arc<std::sync::Mutex<Vec<(Vec<(usize, u32)>, bool)>>>;
+
+    struct Recorder(Chunks);
+
+    #[async_trait]
+    impl Processor for Recorder {
+        type InputType = AccountChunk<u32>;
+
+        async fn process(
+            &mut self,
+            data: Self::InputType,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            self.0.lock().unwrap().push((data.records, data.last));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_are_decoded_in_chunks() {
+        let chunks = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut pipe = ChunkedAccountPipe {
+            decoder: Box::new(U32Records),
+            processor: Box::new(Recorder(chunks.clone())),
+            records_per_chunk: 2,
+        };
+
+        let mut data = vec![0xff; 8];
+        for value in [1u32, 0, 3, 4, 5] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        // A trailing partial record.
+        data.push(6);
+
+        let account = solana_account::Account {
+            lamports: 1,
+            data,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        let metadata = AccountMetadata {
+            slot: 1,
+            pubkey: Pubkey::new_unique(),
+            created: false,
+        };
+
+        pipe.run(
+            (metadata, account),
+            Arc::new(MetricsCollection::new(vec![])),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *chunks.lock().unwrap(),
+            [
+                (vec![(0, 1)], false),
+                (vec![(2, 3), (3, 4)], false),
+                (vec![(4, 5)], true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_account_without_records_is_a_single_last_chunk() {
+        let chunks = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut pipe = ChunkedAccountPipe {
+            decoder: Box::new(U32Records),
+            processor: Box::new(Recorder(chunks.clone())),
+            records_per_chunk: 2,
+        };
+
+        let account = solana_account::Account {
+            lamports: 1,
+            data: vec![0xff; 8],
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        let metadata = AccountMetadata {
+            slot: 1,
+            pubkey: Pubkey::new_unique(),
+            created: false,
+        };
+
+        pipe.run(
+            (metadata, account),
+            Arc::new(MetricsCollection::new(vec![])),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(*chunks.lock().unwrap(), [(vec![], true)]);
+    }
+}
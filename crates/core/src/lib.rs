@@ -16,45 +16,136 @@
 //!   updates. Account data is processed through pipes that support custom
 //!   decoders and processors.
 //!
+//! - **[`account_cache`]**: Keeps the latest decoded account states in an LRU
+//!   cache that processors can query for related accounts.
+//!
+//! - **[`account_creation`]**: Flags the first update of each account, so
+//!   processors can run one-time work when an account is created.
+//!
 //! - **[`account_deletion`]**: Handles the deletion of accounts and processes
 //!   these events in the pipeline.
 //!
+//! - **[`account_diff`]**: Compares decoded account snapshots and publishes
+//!   field-level changes, enabling audit-log style tables, and delivers the
+//!   previous decoded value of each account along with the new one, compared
+//!   with `CarbonDiff`.
+//!
+//! - **[`address_lookup_table`]**: Resolves the addresses loaded from lookup
+//!   tables by v0 transactions delivered without them, caching the fetched
+//!   tables.
+//!
+//! - **[`anomaly`]**: Watches numeric fields of decoded accounts and alerts on
+//!   percent-change or z-score anomalies within a time window.
+//!
+//! - **[`backpressure`]**: Signals overload of the pipeline to its datasources,
+//!   so they pause their subscriptions until it recovers.
+//!
+//! - **[`batch`]**: Buffers processor inputs and writes them to a sink in
+//!   batches, optionally flushing at slot boundaries so that slots are never
+//!   partially written.
+//!
+//! - **[`block_bundle`]**: Groups every update of a slot into a single
+//!   `BlockBundle`, for consumers whose invariants hold at block boundaries.
+//!
 //! - **[`collection`]**: Defines collections for instruction decoding, allowing
 //!   for customized instruction parsers that handle specific instruction sets.
 //!
+//! - **[`compute_budget`]**: Parses the compute unit limit and price set by a
+//!   transaction, exposed on `TransactionMetadata` for fee analytics.
+//!
+//! - **[`config`]**: Builds pipelines from TOML or YAML files describing their
+//!   datasources, decoders, sinks, filters and metrics, with the `config`
+//!   feature.
+//!
 //! - **[`datasource`]**: Provides data ingestion capabilities, enabling the
 //!   integration of external data sources into the pipeline. Supports
 //!   Solana-specific data structures.
 //!
+//! - **[`debug_tap`]**: Samples a few decoded updates of each type per minute
+//!   into a debug channel, to look at live decoded data.
+//!
+//! - **[`deduplication`]**: Drops transaction and account updates already
+//!   processed, by this pipeline or by other instances sharing a store.
+//!
 //! - **[`deserialize`]**: Contains utilities for data deserialization,
 //!   including helper functions for parsing Solana transactions and other
 //!   binary data formats.
 //!
+//! - **[`dex_trade`]**: Normalizes the swaps and liquidity changes of DEX
+//!   decoders into a single, venue-independent stream.
+//!
+//! - **[`discriminator`]**: Defines the strategies decoders use to identify
+//!   types from their data, such as Anchor sighashes, tag bytes at an offset or
+//!   the size of the data.
+//!
+//! - **[`domain_names`]**: Resolves wallet addresses to their primary domain
+//!   names, cached, to make alerts and explorer-style sinks readable.
+//!
+//! - **[`dual_pipeline`]**: Runs a low-latency and a finalized pipeline side by
+//!   side and reports where their outputs disagree.
+//!
 //! - **[`error`]**: Defines error types used throughout the crate, providing
 //!   consistent error handling for the framework.
 //!
+//! - **[`finality`]**: Tracks slot statuses so that processors only receive
+//!   finalized updates, or are notified when a fork drops a processed slot.
+//!
 //! - **[`instruction`]**: Supports instruction parsing and processing within
 //!   transactions. This module includes structures and traits for decoding and
 //!   handling transaction instructions.
 //!
+//! - **[`join`]**: Correlates decoded outputs from multiple pipes into bundles
+//!   keyed by signature or slot and pubkey, so processors can observe related
+//!   updates together.
+//!
+//! - **[`large_account`]**: Decodes the fixed-size records of large accounts in
+//!   chunks, so giant accounts don't spike memory.
+//!
 //! - **[`metrics`]**: Facilitates performance monitoring and metric recording
 //!   within the pipeline. Metrics can be customized and are recorded at each
 //!   processing stage for monitoring and debugging purposes.
 //!
+//! - **[`middleware`]**: Wraps processors with before and after hooks, for
+//!   concerns such as timing or rate limiting shared by many processors.
+//!
+//! - **[`overflow`]**: Bounds the queue of updates waiting for the pipeline,
+//!   with policies for the updates arriving while it is full.
+//!
 //! - **[`pipeline`]**: Represents the core of the framework, defining the main
 //!   pipeline structure that manages data flow and processing. The pipeline
 //!   integrates data sources, processing pipes, and metrics to provide a
 //!   complete data processing solution.
 //!
+//! - **[`price_cache`]**: Keeps the latest oracle prices, indexed from price
+//!   feed accounts, to enrich decoded token amounts with their USD value.
+//!
 //! - **[`processor`]**: Contains traits and implementations for processing data
 //!   in the pipeline. This module allows for the creation of custom data
 //!   processors that can be integrated into various stages of the pipeline.
 //!
+//! - **[`program_ids`]**: Runs decoders against devnet deployments or forks of
+//!   their program, decoding other program IDs than the generated one.
+//!
+//! - **[`resource_metrics`]**: Attributes poll time and allocations to pipes
+//!   and processors, exposing them as metrics to identify heavy components.
+//!
 //! - **[`schema`]**: Defines transaction schemas, allowing for structured
 //!   parsing and validation of transaction data based on specified rules.
 //!   Supports complex nested instruction matching for comprehensive transaction
 //!   analysis.
 //!
+//! - **[`sink`]**: Defines typed sinks for the outputs of a pipeline, with
+//!   combinators such as `tee`, `filter_map` and `buffer` to compose them.
+//!
+//! - **[`slo`]**: Tracks latency and freshness objectives, exporting the burn
+//!   rate of their error budget and alerting when it burns too fast.
+//!
+//! - **[`telemetry`]**: Traces each update through decoders and processors with
+//!   `tracing` spans, with an optional OpenTelemetry exporter.
+//!
+//! - **[`template`]**: Renders Handlebars-like templates against decoded data,
+//!   so that message bodies can be configured per event type at runtime.
+//!
 //! - **[`transaction`]**: Manages transaction data, including metadata
 //!   extraction and parsing. This module supports transaction validation and
 //!   processing, enabling detailed transaction insights.
@@ -64,6 +155,9 @@
 //!   transaction data into formats suitable for processing within the
 //!   framework.
 //!
+//! - **[`wallet_pnl`]**: Maintains the positions and PnL of a set of wallets
+//!   from their DEX trades and token transfers, valued with oracle prices.
+//!
 //! ## Quick Start
 //!
 //! To create a new `carbon-core` pipeline, start by configuring data sources,
@@ -113,21 +207,52 @@
 //! data processing requirements.
 
 pub mod account;
+pub mod account_cache;
+pub mod account_creation;
 pub mod account_deletion;
+pub mod account_diff;
+pub mod address_lookup_table;
+pub mod anomaly;
+pub mod backpressure;
+pub mod batch;
+pub mod block_bundle;
 mod block_details;
 pub mod collection;
+pub mod compute_budget;
+#[cfg(feature = "config")]
+pub mod config;
 pub mod datasource;
+pub mod debug_tap;
+pub mod deduplication;
 pub mod deserialize;
+pub mod dex_trade;
+pub mod discriminator;
+pub mod domain_names;
+pub mod dual_pipeline;
 pub mod error;
+pub mod finality;
 pub mod instruction;
+pub mod join;
+pub mod large_account;
 pub mod metrics;
+pub mod middleware;
+pub mod overflow;
 pub mod pipeline;
+pub mod price_cache;
 pub mod processor;
+pub mod program_ids;
+pub mod resource_metrics;
 pub mod schema;
+pub mod sink;
+pub mod slo;
+pub mod telemetry;
+pub mod template;
 pub mod transaction;
 pub mod transformers;
+pub mod wallet_pnl;
 
 pub use borsh;
+pub use bytemuck;
 #[cfg(feature = "macros")]
 pub use carbon_macros::*;
 #[cfg(feature = "macros")]
@@ -63,6 +63,11 @@ pub trait Metrics: Send + Sync {
     async fn record_histogram(&self, name: &str, value: f64) -> CarbonResult<()>;
 }
 
+/// Fans metrics out to every registered `Metrics` backend.
+///
+/// Every backend receives each metric even if another one fails, so that an
+/// unreachable backend doesn't blind the others. The first error is returned
+/// once all backends were called, and later errors are logged.
 #[derive(Default)]
 pub struct MetricsCollection {
     pub metrics: Vec<Arc<dyn Metrics>>,
@@ -74,44 +79,60 @@ impl MetricsCollection {
     }
 
     pub async fn initialize_metrics(&self) -> CarbonResult<()> {
+        let mut result = Ok(());
         for metric in &self.metrics {
-            metric.initialize().await?;
+            keep_first_error(&mut result, metric.initialize().await);
         }
-        Ok(())
+        result
     }
 
     pub async fn shutdown_metrics(&self) -> CarbonResult<()> {
+        let mut result = Ok(());
         for metric in &self.metrics {
-            metric.shutdown().await?;
+            keep_first_error(&mut result, metric.shutdown().await);
         }
-        Ok(())
+        result
     }
 
     pub async fn flush_metrics(&self) -> CarbonResult<()> {
+        let mut result = Ok(());
         for metric in &self.metrics {
-            metric.flush().await?;
+            keep_first_error(&mut result, metric.flush().await);
         }
-        Ok(())
+        result
     }
 
     pub async fn update_gauge(&self, name: &str, value: f64) -> CarbonResult<()> {
+        let mut result = Ok(());
         for metric in &self.metrics {
-            metric.update_gauge(name, value).await?;
+            keep_first_error(&mut result, metric.update_gauge(name, value).await);
         }
-        Ok(())
+        result
     }
 
     pub async fn increment_counter(&self, name: &str, value: u64) -> CarbonResult<()> {
+        let mut result = Ok(());
         for metric in &self.metrics {
-            metric.increment_counter(name, value).await?;
+            keep_first_error(&mut result, metric.increment_counter(name, value).await);
         }
-        Ok(())
+        result
     }
 
     pub async fn record_histogram(&self, name: &str, value: f64) -> CarbonResult<()> {
+        let mut result = Ok(());
         for metric in &self.metrics {
-            metric.record_histogram(name, value).await?;
+            keep_first_error(&mut result, metric.record_histogram(name, value).await);
+        }
+        result
+    }
+}
+
+fn keep_first_error(result: &mut CarbonResult<()>, next: CarbonResult<()>) {
+    if let Err(error) = next {
+        if result.is_ok() {
+            *result = Err(error);
+        } else {
+            log::error!("Metrics backend failed: {:?}", error);
         }
-        Ok(())
     }
 }
@@ -0,0 +1,353 @@
+//! Wraps processor invocations with before and after hooks, so cross-cutting
+//! concerns aren't copy-pasted into every processor.
+//!
+//! Timing, rate limiting, tracing or enriching the decoded data are concerns
+//! of most processors of a pipeline, unrelated to what each of them does. A
+//! `ProcessorMiddleware` implements such a concern once, and is layered onto
+//! the processors needing it.
+//!
+//! # Overview
+//!
+//! - **`ProcessorMiddleware`**: Hooks run before and after a processor. The
+//!   `before` hook can modify the input or skip the processor, and the `after`
+//!   hook sees the result of the processor.
+//! - **`WithMiddleware`**: A `Processor` wrapping another processor with a
+//!   stack of middlewares.
+//! - **`Timing`**: Records the duration and errors of a processor.
+//! - **`RateLimit`**: Delays the processor so it runs at most a given number of
+//!   times per second.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::middleware::{RateLimit, Timing};
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(yellowstone_grpc)
+//!     .instruction_with_middleware(
+//!         PumpfunDecoder,
+//!         PumpfunWebhookProcessor,
+//!         vec![
+//!             Box::new(Timing::new("pumpfun_webhook")),
+//!             Box::new(RateLimit::per_second(50)),
+//!         ],
+//!     )
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - `before` hooks run in the order the middlewares were added, and `after`
+//!   hooks in the reverse order, so the first middleware wraps all the others.
+//! - Only the middlewares whose `before` hook ran successfully get their
+//!   `after` hook called. A middleware skipping the processor gets its `after`
+//!   hook called with `Ok(())`.
+//! - An error of an `after` hook is returned if the processor succeeded, the
+//!   error of the processor taking precedence otherwise.
+
+use {
+    crate::{error::CarbonResult, metrics::MetricsCollection, processor::Processor},
+    async_trait::async_trait,
+    std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+};
+
+/// Whether a processor runs after the `before` hook of a middleware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    /// Skips the processor, and the `before` hooks of the next middlewares.
+    Skip,
+}
+
+/// Hooks run before and after the invocations of a processor.
+#[async_trait]
+pub trait ProcessorMiddleware<T: Send>: Send + Sync {
+    /// Runs before the processor, with its input.
+    async fn before(
+        &mut self,
+        _data: &mut T,
+        _metrics: &Arc<MetricsCollection>,
+    ) -> CarbonResult<Flow> {
+        Ok(Flow::Continue)
+    }
+
+    /// Runs after the processor, with its result.
+    async fn after(
+        &mut self,
+        _result: &CarbonResult<()>,
+        _metrics: &Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        Ok(())
+    }
+}
+
+/// A processor wrapping another processor with a stack of middlewares.
+pub struct WithMiddleware<P: Processor>
+where
+    P::InputType: Send,
+{
+    processor: P,
+    middlewares: Vec<Box<dyn ProcessorMiddleware<P::InputType>>>,
+}
+
+impl<P: Processor> WithMiddleware<P>
+where
+    P::InputType: Send,
+{
+    /// Wraps a processor without middlewares.
+    pub fn new(processor: P) -> Self {
+        Self {
+            processor,
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Adds a middleware, wrapped by the middlewares added before it.
+    pub fn layer(mut self, middleware: impl ProcessorMiddleware<P::InputType> + 'static) -> Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    /// Adds middlewares, in order.
+    pub fn layers(
+        mut self,
+        middlewares: impl IntoIterator<Item = Box<dyn ProcessorMiddleware<P::InputType>>>,
+    ) -> Self {
+        self.middlewares.extend(middlewares);
+        self
+    }
+}
+
+#[async_trait]
+impl<P> Processor for WithMiddleware<P>
+where
+    P: Processor + Send + Sync,
+    P::InputType: Send,
+{
+    type InputType = P::InputType;
+
+    async fn process(
+        &mut self,
+        mut data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let mut entered = 0;
+        let mut result = Ok(());
+        let mut skipped = false;
+
+        for middleware in self.middlewares.iter_mut() {
+            match middleware.before(&mut data, &metrics).await {
+                Ok(Flow::Continue) => entered += 1,
+                Ok(Flow::Skip) => {
+                    entered += 1;
+                    skipped = true;
+                    break;
+                }
+                Err(e) => {
+                    result = Err(e);
+                    skipped = true;
+                    break;
+                }
+            }
+        }
+
+        if !skipped {
+            result = self.processor.process(data, metrics.clone()).await;
+        }
+
+        for middleware in self.middlewares[..entered].iter_mut().rev() {
+            let after = middleware.after(&result, &metrics).await;
+            if result.is_ok() {
+                result = after;
+            }
+        }
+
+        result
+    }
+}
+
+/// Records the duration of a processor in the
+/// `<name>_duration_milliseconds` histogram, and its errors in the
+/// `<name>_errors` counter.
+pub struct Timing {
+    name: String,
+    started_at: Option<Instant>,
+}
+
+impl Timing {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            started_at: None,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Send> ProcessorMiddleware<T> for Timing {
+    async fn before(
+        &mut self,
+        _data: &mut T,
+        _metrics: &Arc<MetricsCollection>,
+    ) -> CarbonResult<Flow> {
+        self.started_at = Some(Instant::now());
+        Ok(Flow::Continue)
+    }
+
+    async fn after(
+        &mut self,
+        result: &CarbonResult<()>,
+        metrics: &Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        if let Some(started_at) = self.started_at.take() {
+            metrics
+                .record_histogram(
+                    &format!("{}_duration_milliseconds", self.name),
+                    started_at.elapsed().as_secs_f64() * 1_000.0,
+                )
+                .await?;
+        }
+        if result.is_err() {
+            metrics
+                .increment_counter(&format!("{}_errors", self.name), 1)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Delays a processor so it runs at most `per_second` times per second,
+/// applying backpressure to the pipeline instead of dropping updates.
+pub struct RateLimit {
+    interval: Duration,
+    next_run: Option<Instant>,
+}
+
+impl RateLimit {
+    pub fn per_second(per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / per_second.max(1),
+            next_run: None,
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Send> ProcessorMiddleware<T> for RateLimit {
+    async fn before(
+        &mut self,
+        _data: &mut T,
+        _metrics: &Arc<MetricsCollection>,
+    ) -> CarbonResult<Flow> {
+        let now = Instant::now();
+        let run_at = self.next_run.map_or(now, |next_run| next_run.max(now));
+        self.next_run = Some(run_at + self.interval);
+
+        if run_at > now {
+            tokio::time::sleep_until(run_at.into()).await;
+        }
+
+        Ok(Flow::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::sync::Mutex};
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Processor for Recorder {
+        type InputType = u64;
+
+        async fn process(
+            &mut self,
+            data: Self::InputType,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            self.0.lock().unwrap().push(format!("process {}", data));
+            Ok(())
+        }
+    }
+
+    struct Hooks {
+        name: &'static str,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ProcessorMiddleware<u64> for Hooks {
+        async fn before(
+            &mut self,
+            data: &mut u64,
+            _metrics: &Arc<MetricsCollection>,
+        ) -> CarbonResult<Flow> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("before {}", self.name));
+            if *data > 100 {
+                return Ok(Flow::Skip);
+            }
+            *data += 1;
+            Ok(Flow::Continue)
+        }
+
+        async fn after(
+            &mut self,
+            result: &CarbonResult<()>,
+            _metrics: &Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("after {} {}", self.name, result.is_ok()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middlewares_wrap_the_processor() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut processor = WithMiddleware::new(Recorder(events.clone()))
+            .layer(Hooks {
+                name: "outer",
+                events: events.clone(),
+            })
+            .layer(Hooks {
+                name: "inner",
+                events: events.clone(),
+            });
+        let metrics = Arc::new(MetricsCollection::new(vec![]));
+
+        processor.process(1, metrics.clone()).await.unwrap();
+        assert_eq!(
+            events.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            [
+                "before outer",
+                "before inner",
+                "process 3",
+                "after inner true",
+                "after outer true",
+            ]
+        );
+
+        processor.process(100, metrics).await.unwrap();
+        assert_eq!(
+            events.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            [
+                "before outer",
+                "before inner",
+                "after inner true",
+                "after outer true"
+            ]
+        );
+    }
+}
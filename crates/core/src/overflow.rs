@@ -0,0 +1,213 @@
+//! Bounds the queue of updates between the datasources and the pipeline, and
+//! decides what happens to the updates arriving while it is full.
+//!
+//! Datasources send their updates through a channel of `channel_buffer_size`
+//! updates. When the pipeline processes updates slower than they arrive, the
+//! queue fills up, and the `OverflowPolicy` of the pipeline applies.
+//!
+//! # Overview
+//!
+//! - **`OverflowPolicy::Block`**: Datasources wait for room in the queue, so no
+//!   update is lost. This is the default.
+//! - **`OverflowPolicy::DropOldest`**: The oldest queued update is dropped to
+//!   make room, for pipelines where fresh data matters more than completeness.
+//! - **`OverflowPolicy::DropNewest`**: The arriving update is dropped.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::overflow::OverflowPolicy;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(yellowstone_grpc)
+//!     .channel_buffer_size(50_000)
+//!     .overflow_policy(OverflowPolicy::DropOldest)
+//!     .instruction(PumpfunDecoder, PumpfunInstructionProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Dropped updates are counted by the `updates_dropped` counter, and the
+//!   depth of the queue is reported by the `updates_queued` gauge.
+//! - With the drop policies, updates are moved from the datasource channel to
+//!   the queue by a separate task, so datasources are never blocked by the
+//!   pipeline.
+//! - Datasources supporting backpressure pause before the queue is full, see
+//!   [`crate::backpressure`].
+
+use {
+    crate::{datasource::Update, metrics::MetricsCollection},
+    std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    },
+    tokio::sync::{mpsc, Notify},
+};
+
+/// What happens to the updates arriving while the update queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Datasources wait until the pipeline makes room in the queue.
+    #[default]
+    Block,
+    /// The oldest queued update is dropped.
+    DropOldest,
+    /// The arriving update is dropped.
+    DropNewest,
+}
+
+struct QueueState<T> {
+    items: VecDeque<T>,
+    closed: bool,
+}
+
+/// A bounded queue dropping items according to an `OverflowPolicy` when full.
+pub(crate) struct BoundedQueue<T> {
+    state: Mutex<QueueState<T>>,
+    notify: Notify,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+impl<T> BoundedQueue<T> {
+    pub(crate) fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                items: VecDeque::new(),
+                closed: false,
+            }),
+            notify: Notify::new(),
+            capacity: capacity.max(1),
+            policy,
+        }
+    }
+
+    /// Pushes an item, returning whether an item was dropped to respect the
+    /// capacity of the queue.
+    pub(crate) fn push(&self, item: T) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let dropped = if state.items.len() < self.capacity {
+            state.items.push_back(item);
+            false
+        } else if self.policy == OverflowPolicy::DropNewest {
+            true
+        } else {
+            state.items.pop_front();
+            state.items.push_back(item);
+            true
+        };
+        drop(state);
+
+        self.notify.notify_one();
+        dropped
+    }
+
+    /// Marks the queue as closed, so `recv` returns `None` once it is empty.
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).closed = true;
+        self.notify.notify_one();
+    }
+
+    /// Waits for the next item, or returns `None` once the queue is closed
+    /// and empty.
+    pub(crate) async fn recv(&self) -> Option<T> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(item) = state.items.pop_front() {
+                    return Some(item);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+
+            self.notify.notified().await;
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .items
+            .len()
+    }
+}
+
+/// The receiving end of the updates of the datasources.
+pub(crate) enum UpdateReceiver {
+    Channel(mpsc::Receiver<Update>),
+    Queue(Arc<BoundedQueue<Update>>),
+}
+
+impl UpdateReceiver {
+    /// Applies `policy` to the updates of `receiver`, spawning the task moving
+    /// them to a queue of `capacity` updates for the drop policies.
+    pub(crate) fn new(
+        mut receiver: mpsc::Receiver<Update>,
+        capacity: usize,
+        policy: OverflowPolicy,
+        metrics: Arc<MetricsCollection>,
+    ) -> Self {
+        if policy == OverflowPolicy::Block {
+            return Self::Channel(receiver);
+        }
+
+        let queue = Arc::new(BoundedQueue::new(capacity, policy));
+        let forwarded_queue = Arc::clone(&queue);
+        tokio::spawn(async move {
+            while let Some(update) = receiver.recv().await {
+                if forwarded_queue.push(update) {
+                    if let Err(e) = metrics.increment_counter("updates_dropped", 1).await {
+                        log::error!("error recording dropped update: {:?}", e);
+                    }
+                }
+            }
+            forwarded_queue.close();
+        });
+
+        Self::Queue(queue)
+    }
+
+    pub(crate) async fn recv(&mut self) -> Option<Update> {
+        match self {
+            Self::Channel(receiver) => receiver.recv().await,
+            Self::Queue(queue) => queue.recv().await,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::Channel(receiver) => receiver.len(),
+            Self::Queue(queue) => queue.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drop_policies() {
+        let oldest = BoundedQueue::new(2, OverflowPolicy::DropOldest);
+        let newest = BoundedQueue::new(2, OverflowPolicy::DropNewest);
+        for item in 1..=3 {
+            assert_eq!(oldest.push(item), item == 3);
+            assert_eq!(newest.push(item), item == 3);
+        }
+        oldest.close();
+        newest.close();
+
+        assert_eq!(oldest.recv().await, Some(2));
+        assert_eq!(oldest.recv().await, Some(3));
+        assert_eq!(oldest.recv().await, None);
+        assert_eq!(newest.recv().await, Some(1));
+        assert_eq!(newest.recv().await, Some(2));
+        assert_eq!(newest.recv().await, None);
+    }
+}
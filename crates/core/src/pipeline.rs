@@ -57,24 +57,46 @@ use {
         account::{
             AccountDecoder, AccountMetadata, AccountPipe, AccountPipes, AccountProcessorInputType,
         },
+        account_cache::AccountCache,
+        account_creation::SeenAccountsStore,
         account_deletion::{AccountDeletionPipe, AccountDeletionPipes},
+        account_diff::{AccountDeltaInputType, AccountDeltaProcessor},
+        address_lookup_table::AddressLookupTableResolver,
+        backpressure::{BackpressureController, BackpressureWatermarks},
+        block_bundle::{BlockBundle, BlockBundlePipe, BlockBundlePipes, BlockBundler},
         collection::InstructionDecoderCollection,
-        datasource::{AccountDeletion, Datasource, Update},
+        datasource::{AccountDeletion, Datasource, SlotStatusUpdate, TransactionUpdate, Update},
+        deduplication::{DedupeKey, DedupeStore},
         error::CarbonResult,
+        finality::{SlotStatusPipe, SlotStatusPipes},
         instruction::{
-            InstructionDecoder, InstructionPipe, InstructionPipes, InstructionProcessorInputType,
-            InstructionsWithMetadata, NestedInstructions,
+            InstructionDecoder, InstructionDispatch, InstructionPipe, InstructionPipes,
+            InstructionProcessorInputType, InstructionsWithMetadata, NestedInstructions,
+        },
+        large_account::{
+            AccountChunk, ChunkedAccountDecoder, ChunkedAccountPipe, DEFAULT_RECORDS_PER_CHUNK,
         },
         metrics::{Metrics, MetricsCollection},
-        processor::Processor,
+        middleware::{ProcessorMiddleware, WithMiddleware},
+        overflow::{OverflowPolicy, UpdateReceiver},
+        price_cache::{Price, PriceCache},
+        processor::{Flush, Processor},
+        resource_metrics,
         schema::TransactionSchema,
-        transaction::{TransactionPipe, TransactionPipes, TransactionProcessorInputType},
+        slo::SloTracker,
+        telemetry,
+        transaction::{
+            TransactionInstructionsInputType, TransactionInstructionsPipe, TransactionPipe,
+            TransactionPipes, TransactionProcessorInputType,
+        },
         transformers,
     },
     core::time,
     serde::de::DeserializeOwned,
-    std::{convert::TryInto, sync::Arc, time::Instant},
+    solana_pubkey::Pubkey,
+    std::{collections::HashSet, convert::TryInto, sync::Arc, time::Instant},
     tokio_util::sync::CancellationToken,
+    tracing::Instrument,
 };
 
 /// Defines the shutdown behavior for the pipeline.
@@ -157,10 +179,15 @@ pub const DEFAULT_CHANNEL_BUFFER_SIZE: usize = 1_000;
 ///   deletion events.
 /// - `block_details_pipes`: A vector of `BlockDetailsPipes` to handle
 ///   block details.
+/// - `block_bundle_pipes`: A vector of `BlockBundlePipes` grouping every update
+///   of a slot into a single `BlockBundle`.
+/// - `slot_status_pipes`: A vector of `SlotStatusPipes` to handle slot status
+///   updates.
 /// - `instruction_pipes`: A vector of `InstructionPipes` for processing
 ///   instructions within transactions. These pipes work with nested
 ///   instructions and are generically defined to support varied instruction
-///   types.
+///   types. Pipes whose decoders declare their program ids only run for the
+///   instructions invoking one of them.
 /// - `transaction_pipes`: A vector of `TransactionPipes` responsible for
 ///   processing complete transaction payloads.
 /// - `metrics`: A vector of `Metrics` implementations to record and track
@@ -171,6 +198,29 @@ pub const DEFAULT_CHANNEL_BUFFER_SIZE: usize = 1_000;
 ///   used.
 /// - `channel_buffer_size`: The size of the channel buffer for the pipeline. If
 ///   not set, a default size of 10_000 will be used.
+/// - `overflow_policy`: What happens to the updates arriving while the channel
+///   buffer is full. By default, datasources wait for room in the buffer.
+/// - `backpressure_watermarks`: The queue depths at which the pipeline signals
+///   overload to its datasources, and at which it recovers. If not set, 80% and
+///   50% of the channel buffer size are used.
+/// - `program_id_filter`: An optional set of program ids. When set, transaction
+///   updates whose static account keys contain none of them are skipped before
+///   any lookup table is resolved or instruction is decoded.
+/// - `address_lookup_table_resolver`: An optional resolver filling in the
+///   addresses loaded from lookup tables by v0 transactions lacking them.
+/// - `resource_metrics`: Whether the poll time and allocations of each pipe are
+///   recorded.
+/// - `seen_accounts`: An optional store of the accounts seen by the pipeline,
+///   used to flag the first update of each account as a creation.
+/// - `dedupe_store`: An optional store of the keys of processed updates, used
+///   to skip transaction and account updates received more than once.
+/// - `slo_tracker`: An optional tracker of the latency and freshness objectives
+///   of the pipeline, evaluated each time metrics are flushed.
+/// - `max_account_data_size`: An optional limit on the data size of accounts.
+///   Larger accounts skip the block bundle pipes and the account pipes which
+///   don't decode them in chunks.
+/// - `shutdown_flushes`: The buffering processors flushed once the datasources
+///   finished and every pending update was processed.
 ///
 /// ## Example
 ///
@@ -211,6 +261,8 @@ pub struct Pipeline {
     pub account_pipes: Vec<Box<dyn AccountPipes>>,
     pub account_deletion_pipes: Vec<Box<dyn AccountDeletionPipes>>,
     pub block_details_pipes: Vec<Box<dyn BlockDetailsPipes>>,
+    pub block_bundle_pipes: Vec<Box<dyn BlockBundlePipes>>,
+    pub slot_status_pipes: Vec<Box<dyn SlotStatusPipes>>,
     pub instruction_pipes: Vec<Box<dyn for<'a> InstructionPipes<'a>>>,
     pub transaction_pipes: Vec<Box<dyn for<'a> TransactionPipes<'a>>>,
     pub metrics: Arc<MetricsCollection>,
@@ -218,6 +270,17 @@ pub struct Pipeline {
     pub datasource_cancellation_token: Option<CancellationToken>,
     pub shutdown_strategy: ShutdownStrategy,
     pub channel_buffer_size: usize,
+    pub overflow_policy: OverflowPolicy,
+    pub backpressure_watermarks: Option<BackpressureWatermarks>,
+    pub program_id_filter: Option<HashSet<Pubkey>>,
+    pub address_lookup_table_resolver: Option<Arc<AddressLookupTableResolver>>,
+    pub resource_metrics: bool,
+    pub seen_accounts: Option<Arc<dyn SeenAccountsStore>>,
+    pub dedupe_store: Option<Arc<dyn DedupeStore>>,
+    pub slo_tracker: Option<SloTracker>,
+    pub max_account_data_size: Option<usize>,
+    pub shutdown_flushes: Vec<Box<dyn Flush>>,
+    instruction_dispatch: InstructionDispatch,
 }
 
 impl Pipeline {
@@ -257,6 +320,8 @@ impl Pipeline {
             account_pipes: Vec::new(),
             account_deletion_pipes: Vec::new(),
             block_details_pipes: Vec::new(),
+            block_bundle_pipes: Vec::new(),
+            slot_status_pipes: Vec::new(),
             instruction_pipes: Vec::new(),
             transaction_pipes: Vec::new(),
             metrics: MetricsCollection::default(),
@@ -264,6 +329,16 @@ impl Pipeline {
             datasource_cancellation_token: None,
             shutdown_strategy: ShutdownStrategy::default(),
             channel_buffer_size: DEFAULT_CHANNEL_BUFFER_SIZE,
+            overflow_policy: OverflowPolicy::default(),
+            backpressure_watermarks: None,
+            program_id_filter: None,
+            address_lookup_table_resolver: None,
+            resource_metrics: false,
+            seen_accounts: None,
+            dedupe_store: None,
+            slo_tracker: None,
+            max_account_data_size: None,
+            shutdown_flushes: Vec::new(),
         }
     }
 
@@ -324,21 +399,41 @@ impl Pipeline {
     ///   `metrics_flush_interval`.
     /// - The `run` method operates in an infinite loop, handling updates until
     ///   a termination condition occurs.
+    /// - Each update is processed within a `tracing` span, see the
+    ///   [`telemetry`](crate::telemetry) module.
     pub async fn run(&mut self) -> CarbonResult<()> {
-        log::info!("starting pipeline. num_datasources: {}, num_metrics: {}, num_account_pipes: {}, num_account_deletion_pipes: {}, num_instruction_pipes: {}, num_transaction_pipes: {}",
+        log::info!("starting pipeline. num_datasources: {}, num_metrics: {}, num_account_pipes: {}, num_account_deletion_pipes: {}, num_instruction_pipes: {}, num_transaction_pipes: {}, num_block_bundle_pipes: {}, num_slot_status_pipes: {}",
             self.datasources.len(),
             self.metrics.metrics.len(),
             self.account_pipes.len(),
             self.account_deletion_pipes.len(),
             self.instruction_pipes.len(),
             self.transaction_pipes.len(),
+            self.block_bundle_pipes.len(),
+            self.slot_status_pipes.len(),
         );
 
         log::trace!("run(self)");
 
+        self.instruction_dispatch = InstructionDispatch::new(&self.instruction_pipes);
         self.metrics.initialize_metrics().await?;
-        let (update_sender, mut update_receiver) =
-            tokio::sync::mpsc::channel::<Update>(self.channel_buffer_size);
+        let channel_buffer_size = match self.channel_buffer_size {
+            0 => DEFAULT_CHANNEL_BUFFER_SIZE,
+            channel_buffer_size => channel_buffer_size,
+        };
+        let (update_sender, update_receiver) =
+            tokio::sync::mpsc::channel::<Update>(channel_buffer_size);
+        let mut update_receiver = UpdateReceiver::new(
+            update_receiver,
+            channel_buffer_size,
+            self.overflow_policy,
+            self.metrics.clone(),
+        );
+
+        let backpressure = BackpressureController::new(
+            self.backpressure_watermarks
+                .unwrap_or_else(|| BackpressureWatermarks::for_capacity(channel_buffer_size)),
+        );
 
         let datasource_cancellation_token = self
             .datasource_cancellation_token
@@ -350,13 +445,15 @@ impl Pipeline {
             let sender_clone = update_sender.clone();
             let datasource_clone = Arc::clone(datasource);
             let metrics_collection = self.metrics.clone();
+            let backpressure_signal = backpressure.signal();
 
             tokio::spawn(async move {
                 if let Err(e) = datasource_clone
-                    .consume(
+                    .consume_with_backpressure(
                         sender_clone,
                         datasource_cancellation_token_clone,
                         metrics_collection,
+                        backpressure_signal,
                     )
                     .await
                 {
@@ -387,6 +484,9 @@ impl Pipeline {
                     }
                 }
                 _ = interval.tick() => {
+                    if let Some(slo_tracker) = &mut self.slo_tracker {
+                        slo_tracker.evaluate(&self.metrics).await?;
+                    }
                     self.metrics.flush_metrics().await?;
                 }
                 update = update_receiver.recv() => {
@@ -396,8 +496,9 @@ impl Pipeline {
                                 .metrics.increment_counter("updates_received", 1)
                                 .await?;
 
+                            let span = telemetry::update_span(&update);
                             let start = Instant::now();
-                            let process_result = self.process(update.clone()).await;
+                            let process_result = self.process(update.clone()).instrument(span.clone()).await;
                             let time_taken_nanoseconds = start.elapsed().as_nanos();
                             let time_taken_milliseconds = time_taken_nanoseconds / 1_000_000;
 
@@ -417,10 +518,19 @@ impl Pipeline {
                                         .metrics.increment_counter("updates_successful", 1)
                                         .await?;
 
+                                    if let (Some(slo_tracker), Update::Transaction(transaction_update)) =
+                                        (&mut self.slo_tracker, &update)
+                                    {
+                                        if let Some(block_time) = transaction_update.block_time {
+                                            slo_tracker.record_block_time(block_time, &self.metrics).await?;
+                                        }
+                                    }
+
                                     log::trace!("processed update")
                                 }
                                 Err(error) => {
-                                    log::error!("error processing update ({:?}): {:?}", update, error);
+                                    telemetry::record_error(&span);
+                                    span.in_scope(|| log::error!("error processing update ({:?}): {:?}", update, error));
                                     self.metrics.increment_counter("updates_failed", 1).await?;
                                 }
                             };
@@ -429,12 +539,34 @@ impl Pipeline {
                                 .metrics.increment_counter("updates_processed", 1)
                                 .await?;
 
+                            let queued = update_receiver.len();
                             self
-                                .metrics.update_gauge("updates_queued", update_receiver.len() as f64)
+                                .metrics.update_gauge("updates_queued", queued as f64)
                                 .await?;
+
+                            if let Some(overloaded) = backpressure.update(queued) {
+                                if overloaded {
+                                    log::warn!("pipeline overloaded with {} queued updates, pausing datasources.", queued);
+                                } else {
+                                    log::info!("pipeline recovered, resuming datasources.");
+                                }
+                                self
+                                    .metrics.update_gauge("datasources_paused", if overloaded { 1.0 } else { 0.0 })
+                                    .await?;
+                            }
                         }
                         None => {
                             log::info!("update_receiver closed, shutting down.");
+                            for pipe in self.block_bundle_pipes.iter_mut() {
+                                if let Err(error) = pipe.flush(self.metrics.clone()).await {
+                                    log::error!("error flushing block bundles: {:?}", error);
+                                }
+                            }
+                            for processor in self.shutdown_flushes.iter() {
+                                if let Err(error) = processor.flush_pending(self.metrics.clone()).await {
+                                    log::error!("error flushing buffered inputs: {:?}", error);
+                                }
+                            }
                             self.metrics.flush_metrics().await?;
                             self.metrics.shutdown_metrics().await?;
                             break;
@@ -498,19 +630,118 @@ impl Pipeline {
     /// Returns an error if any of the pipes fail during processing, or if an
     /// issue arises while incrementing counters or updating metrics. Handle
     /// errors gracefully to ensure continuous pipeline operation.
-    async fn process(&mut self, update: Update) -> CarbonResult<()> {
+    async fn process(&mut self, mut update: Update) -> CarbonResult<()> {
         log::trace!("process(self, update: {:?})", update);
+
+        if let Update::Transaction(transaction_update) = &update {
+            if let Some(program_id_filter) = &self.program_id_filter {
+                if !touches_program_ids(transaction_update, program_id_filter) {
+                    self.metrics
+                        .increment_counter("transaction_updates_filtered", 1)
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+
+        if let (Some(resolver), Update::Transaction(transaction_update)) =
+            (&self.address_lookup_table_resolver, &mut update)
+        {
+            if resolver.resolve_transaction(transaction_update).await? {
+                self.metrics
+                    .increment_counter("address_lookup_tables_resolved", 1)
+                    .await?;
+            }
+        }
+
+        if let Some(dedupe_store) = &self.dedupe_store {
+            let key = match &update {
+                Update::Transaction(transaction_update) => {
+                    Some(DedupeKey::Transaction(transaction_update.signature))
+                }
+                Update::Account(account_update) => Some(DedupeKey::account(
+                    account_update.pubkey,
+                    account_update.slot,
+                    &account_update.account.data,
+                )),
+                _ => None,
+            };
+            if let Some(key) = key {
+                if !dedupe_store.insert(key).await? {
+                    self.metrics
+                        .increment_counter("updates_deduplicated", 1)
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+
+        let oversized_account = match (&update, self.max_account_data_size) {
+            (Update::Account(account_update), Some(max_account_data_size)) => {
+                account_update.account.data.len() > max_account_data_size
+            }
+            _ => false,
+        };
+        if let (true, Update::Account(account_update)) = (oversized_account, &update) {
+            log::warn!(
+                "account {} has {} bytes of data, only passing it to chunked account pipes",
+                account_update.pubkey,
+                account_update.account.data.len()
+            );
+            self.metrics
+                .increment_counter("accounts_oversized", 1)
+                .await?;
+        }
+
+        if !oversized_account {
+            for (index, pipe) in self.block_bundle_pipes.iter_mut().enumerate() {
+                resource_metrics::run_pipe(
+                    self.resource_metrics,
+                    "block_bundle",
+                    index,
+                    &self.metrics,
+                    pipe.run(update.clone(), self.metrics.clone()),
+                )
+                .await?;
+            }
+        }
+
         match update {
             Update::Account(account_update) => {
+                let created = match &self.seen_accounts {
+                    Some(seen_accounts) if account_update.account.lamports == 0 => {
+                        seen_accounts.remove(&account_update.pubkey).await?;
+                        false
+                    }
+                    Some(seen_accounts) => seen_accounts.insert(account_update.pubkey).await?,
+                    None => false,
+                };
+                if created {
+                    self.metrics
+                        .increment_counter("accounts_created", 1)
+                        .await?;
+                }
+
                 let account_metadata = AccountMetadata {
                     slot: account_update.slot,
                     pubkey: account_update.pubkey,
+                    created,
                 };
 
-                for pipe in self.account_pipes.iter_mut() {
-                    pipe.run(
-                        (account_metadata.clone(), account_update.account.clone()),
-                        self.metrics.clone(),
+                for (index, pipe) in self.account_pipes.iter_mut().enumerate() {
+                    if oversized_account && !pipe.streams_large_accounts() {
+                        continue;
+                    }
+
+                    resource_metrics::run_pipe(
+                        self.resource_metrics,
+                        "account",
+                        index,
+                        &self.metrics,
+                        pipe.run(
+                            (account_metadata.clone(), account_update.account.clone()),
+                            self.metrics.clone(),
+                        ),
                     )
                     .await?;
                 }
@@ -530,17 +761,44 @@ impl Pipeline {
 
                 let nested_instructions: NestedInstructions = instructions_with_metadata.into();
 
-                for pipe in self.instruction_pipes.iter_mut() {
-                    for nested_instruction in nested_instructions.iter() {
-                        pipe.run(nested_instruction, self.metrics.clone()).await?;
+                // Each pipe runs over all the instructions dispatched to it
+                // before the next pipe, in the order the pipes were added.
+                let dispatched: Vec<Vec<usize>> = nested_instructions
+                    .iter()
+                    .map(|nested_instruction| self.instruction_dispatch.pipes(nested_instruction))
+                    .collect();
+                let mut indexes: Vec<usize> = dispatched.iter().flatten().copied().collect();
+                indexes.sort_unstable();
+                indexes.dedup();
+
+                for index in indexes {
+                    let pipe = &mut self.instruction_pipes[index];
+                    for (nested_instruction, pipes) in nested_instructions.iter().zip(&dispatched) {
+                        if !pipes.contains(&index) {
+                            continue;
+                        }
+                        resource_metrics::run_pipe(
+                            self.resource_metrics,
+                            "instruction",
+                            index,
+                            &self.metrics,
+                            pipe.run(nested_instruction, self.metrics.clone()),
+                        )
+                        .await?;
                     }
                 }
 
-                for pipe in self.transaction_pipes.iter_mut() {
-                    pipe.run(
-                        transaction_metadata.clone(),
-                        &nested_instructions,
-                        self.metrics.clone(),
+                for (index, pipe) in self.transaction_pipes.iter_mut().enumerate() {
+                    resource_metrics::run_pipe(
+                        self.resource_metrics,
+                        "transaction",
+                        index,
+                        &self.metrics,
+                        pipe.run(
+                            transaction_metadata.clone(),
+                            &nested_instructions,
+                            self.metrics.clone(),
+                        ),
                     )
                     .await?;
                 }
@@ -550,9 +808,19 @@ impl Pipeline {
                     .await?;
             }
             Update::AccountDeletion(account_deletion) => {
-                for pipe in self.account_deletion_pipes.iter_mut() {
-                    pipe.run(account_deletion.clone(), self.metrics.clone())
-                        .await?;
+                if let Some(seen_accounts) = &self.seen_accounts {
+                    seen_accounts.remove(&account_deletion.pubkey).await?;
+                }
+
+                for (index, pipe) in self.account_deletion_pipes.iter_mut().enumerate() {
+                    resource_metrics::run_pipe(
+                        self.resource_metrics,
+                        "account_deletion",
+                        index,
+                        &self.metrics,
+                        pipe.run(account_deletion.clone(), self.metrics.clone()),
+                    )
+                    .await?;
                 }
 
                 self.metrics
@@ -560,15 +828,37 @@ impl Pipeline {
                     .await?;
             }
             Update::BlockDetails(block_details) => {
-                for pipe in self.block_details_pipes.iter_mut() {
-                    pipe.run(block_details.clone(), self.metrics.clone())
-                        .await?;
+                for (index, pipe) in self.block_details_pipes.iter_mut().enumerate() {
+                    resource_metrics::run_pipe(
+                        self.resource_metrics,
+                        "block_details",
+                        index,
+                        &self.metrics,
+                        pipe.run(block_details.clone(), self.metrics.clone()),
+                    )
+                    .await?;
                 }
 
                 self.metrics
                     .increment_counter("block_details_processed", 1)
                     .await?;
             }
+            Update::SlotStatus(slot_status) => {
+                for (index, pipe) in self.slot_status_pipes.iter_mut().enumerate() {
+                    resource_metrics::run_pipe(
+                        self.resource_metrics,
+                        "slot_status",
+                        index,
+                        &self.metrics,
+                        pipe.run(slot_status.clone(), self.metrics.clone()),
+                    )
+                    .await?;
+                }
+
+                self.metrics
+                    .increment_counter("slot_status_updates_processed", 1)
+                    .await?;
+            }
         };
 
         Ok(())
@@ -626,6 +916,10 @@ impl Pipeline {
 /// - `account_pipes`: A collection of `AccountPipes` to handle account updates.
 /// - `account_deletion_pipes`: A collection of `AccountDeletionPipes` for
 ///   processing account deletions.
+/// - `block_bundle_pipes`: A collection of `BlockBundlePipes` delivering the
+///   updates of each slot as a single bundle.
+/// - `slot_status_pipes`: A collection of `SlotStatusPipes` to handle slot
+///   status updates.
 /// - `instruction_pipes`: A collection of `InstructionPipes` to process
 ///   instructions in transactions.
 /// - `transaction_pipes`: A collection of `TransactionPipes` to process full
@@ -639,6 +933,23 @@ impl Pipeline {
 ///   used.
 /// - `channel_buffer_size`: The size of the channel buffer for the pipeline. If
 ///   not set, a default size of 10_000 will be used.
+/// - `overflow_policy`: The `OverflowPolicy` applied when the channel buffer is
+///   full.
+/// - `backpressure_watermarks`: Optional queue depths at which datasources are
+///   signaled to pause and resume.
+/// - `program_id_filter`: An optional set of program ids used to skip
+///   irrelevant transactions before decoding.
+/// - `address_lookup_table_resolver`: An optional resolver for the addresses
+///   loaded from lookup tables by v0 transactions.
+/// - `resource_metrics`: Whether the resource usage of each pipe is recorded.
+/// - `seen_accounts`: An optional store of seen accounts, used to flag account
+///   creations.
+/// - `dedupe_store`: An optional store used to skip duplicate updates.
+/// - `slo_tracker`: An optional tracker of the latency and freshness
+///   objectives.
+/// - `max_account_data_size`: An optional limit on the data size of accounts
+///   passed to account pipes not decoding them in chunks.
+/// - `shutdown_flushes`: The buffering processors flushed on shutdown.
 ///
 /// # Returns
 ///
@@ -658,6 +969,8 @@ pub struct PipelineBuilder {
     pub account_pipes: Vec<Box<dyn AccountPipes>>,
     pub account_deletion_pipes: Vec<Box<dyn AccountDeletionPipes>>,
     pub block_details_pipes: Vec<Box<dyn BlockDetailsPipes>>,
+    pub block_bundle_pipes: Vec<Box<dyn BlockBundlePipes>>,
+    pub slot_status_pipes: Vec<Box<dyn SlotStatusPipes>>,
     pub instruction_pipes: Vec<Box<dyn for<'a> InstructionPipes<'a>>>,
     pub transaction_pipes: Vec<Box<dyn for<'a> TransactionPipes<'a>>>,
     pub metrics: MetricsCollection,
@@ -665,6 +978,16 @@ pub struct PipelineBuilder {
     pub datasource_cancellation_token: Option<CancellationToken>,
     pub shutdown_strategy: ShutdownStrategy,
     pub channel_buffer_size: usize,
+    pub overflow_policy: OverflowPolicy,
+    pub backpressure_watermarks: Option<BackpressureWatermarks>,
+    pub program_id_filter: Option<HashSet<Pubkey>>,
+    pub address_lookup_table_resolver: Option<Arc<AddressLookupTableResolver>>,
+    pub resource_metrics: bool,
+    pub seen_accounts: Option<Arc<dyn SeenAccountsStore>>,
+    pub dedupe_store: Option<Arc<dyn DedupeStore>>,
+    pub slo_tracker: Option<SloTracker>,
+    pub max_account_data_size: Option<usize>,
+    pub shutdown_flushes: Vec<Box<dyn Flush>>,
 }
 
 impl PipelineBuilder {
@@ -782,6 +1105,205 @@ impl PipelineBuilder {
         self
     }
 
+    /// Adds an account pipe decoding the fixed-size records of accounts in
+    /// chunks.
+    ///
+    /// The records of each account handled by `decoder` are passed to
+    /// `processor` in chunks of `DEFAULT_RECORDS_PER_CHUNK` records, so large
+    /// accounts such as event queues are never decoded at once. Chunked pipes
+    /// still receive the accounts over the `max_account_data_size`.
+    ///
+    /// # Parameters
+    ///
+    /// - `decoder`: A `ChunkedAccountDecoder` that decodes the records.
+    /// - `processor`: A `Processor` that processes the chunks of records.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::pipeline::PipelineBuilder;
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .max_account_data_size(1024 * 1024)
+    ///     .account_chunks(EventQueueDecoder, EventQueueProcessor);
+    /// ```
+    pub fn account_chunks<T: Send + Sync + 'static>(
+        mut self,
+        decoder: impl ChunkedAccountDecoder<Record = T> + 'static,
+        processor: impl Processor<InputType = AccountChunk<T>> + Send + Sync + 'static,
+    ) -> Self {
+        log::trace!(
+            "account_chunks(self, decoder: {:?}, processor: {:?})",
+            stringify!(decoder),
+            stringify!(processor)
+        );
+        self.account_pipes.push(Box::new(ChunkedAccountPipe {
+            decoder: Box::new(decoder),
+            processor: Box::new(processor),
+            records_per_chunk: DEFAULT_RECORDS_PER_CHUNK,
+        }));
+        self
+    }
+
+    /// Adds an account pipe which keeps the latest decoded accounts in an
+    /// `AccountCache`.
+    ///
+    /// Every decoded account is inserted into `cache` before being passed to
+    /// `processor`. Processors of other pipes holding a clone of the cache can
+    /// look up the latest state of related accounts, such as the pool targeted
+    /// by a swap instruction.
+    ///
+    /// # Parameters
+    ///
+    /// - `decoder`: An `AccountDecoder` that decodes the account data.
+    /// - `processor`: A `Processor` that processes the decoded account data.
+    /// - `cache`: The `AccountCache` populated by the pipe.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::{account_cache::AccountCache, pipeline::PipelineBuilder};
+    ///
+    /// let cache = AccountCache::new(10_000, None);
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .account_with_cache(MyAccountDecoder, MyAccountProcessor, cache.clone())
+    ///     .instruction(MyInstructionDecoder, MyInstructionProcessor { cache });
+    /// ```
+    pub fn account_with_cache<T: Clone + Send + Sync + 'static>(
+        self,
+        decoder: impl for<'a> AccountDecoder<'a, AccountType = T> + Send + Sync + 'static,
+        processor: impl Processor<InputType = AccountProcessorInputType<T>> + Send + Sync + 'static,
+        cache: AccountCache<T>,
+    ) -> Self {
+        log::trace!(
+            "account_with_cache(self, decoder: {:?}, processor: {:?})",
+            stringify!(decoder),
+            stringify!(processor)
+        );
+        self.account(decoder, cache.processor(processor))
+    }
+
+    /// Adds an account pipe which delivers the previous decoded value of each
+    /// account along with the new one.
+    ///
+    /// The last decoded value of every account is kept by the pipe, and passed
+    /// to `processor` with the next update of the account. Types deriving
+    /// `CarbonDiff` can compare both values field by field. An account deletion
+    /// pipe is added as well, so that closed and deleted accounts are
+    /// forgotten.
+    ///
+    /// # Parameters
+    ///
+    /// - `decoder`: An `AccountDecoder` that decodes the account data.
+    /// - `processor`: A `Processor` that processes the previous and new decoded
+    ///   account data.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::pipeline::PipelineBuilder;
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .account_deltas(MyAccountDecoder, MyAccountDeltaProcessor);
+    /// ```
+    pub fn account_deltas<T: Clone + Send + Sync + 'static>(
+        self,
+        decoder: impl for<'a> AccountDecoder<'a, AccountType = T> + Send + Sync + 'static,
+        processor: impl Processor<InputType = AccountDeltaInputType<T>> + Send + Sync + 'static,
+    ) -> Self {
+        log::trace!(
+            "account_deltas(self, decoder: {:?}, processor: {:?})",
+            stringify!(decoder),
+            stringify!(processor)
+        );
+        let processor = AccountDeltaProcessor::new(processor);
+        let deletions = processor.deletions();
+        self.account(decoder, processor).account_deletions(deletions)
+    }
+
+    /// Adds an account pipe which updates a `PriceCache` with the prices of
+    /// decoded oracle accounts.
+    ///
+    /// The price extracted from every decoded account is stored in `cache`,
+    /// keyed by the account address, before the account is passed to
+    /// `processor`. Processors of other pipes holding a clone of the cache can
+    /// convert decoded token amounts to their USD value.
+    ///
+    /// # Parameters
+    ///
+    /// - `decoder`: An `AccountDecoder` that decodes the oracle accounts.
+    /// - `processor`: A `Processor` that processes the decoded account data.
+    /// - `cache`: The `PriceCache` populated by the pipe.
+    /// - `extract`: Extracts the `Price` of a decoded account, or returns
+    ///   `None` for accounts that aren't price feeds.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::{price_cache::PriceCache, pipeline::PipelineBuilder};
+    ///
+    /// let prices = PriceCache::with_mints(None, mint_feeds);
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .account_with_prices(MyOracleDecoder, MyOracleProcessor, prices.clone(), |account| {
+    ///         account.price()
+    ///     })
+    ///     .instruction(MyInstructionDecoder, MyInstructionProcessor { prices });
+    /// ```
+    pub fn account_with_prices<T: Send + Sync + 'static>(
+        self,
+        decoder: impl for<'a> AccountDecoder<'a, AccountType = T> + Send + Sync + 'static,
+        processor: impl Processor<InputType = AccountProcessorInputType<T>> + Send + Sync + 'static,
+        cache: PriceCache,
+        extract: impl Fn(&T) -> Option<Price> + Send + Sync + 'static,
+    ) -> Self {
+        log::trace!(
+            "account_with_prices(self, decoder: {:?}, processor: {:?})",
+            stringify!(decoder),
+            stringify!(processor)
+        );
+        self.account(decoder, cache.processor(extract, processor))
+    }
+
+    /// Adds an account pipe whose processor is wrapped with middlewares.
+    ///
+    /// The `before` hooks of the middlewares run in order before each
+    /// invocation of `processor`, and their `after` hooks in reverse order
+    /// after it.
+    ///
+    /// # Parameters
+    ///
+    /// - `decoder`: An `AccountDecoder` that decodes the account data.
+    /// - `processor`: A `Processor` that processes the decoded account data.
+    /// - `middlewares`: The `ProcessorMiddleware`s wrapping `processor`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::{middleware::Timing, pipeline::PipelineBuilder};
+    ///
+    /// let builder = PipelineBuilder::new().account_with_middleware(
+    ///     MyAccountDecoder,
+    ///     MyAccountProcessor,
+    ///     vec![Box::new(Timing::new("my_account_processor"))],
+    /// );
+    /// ```
+    pub fn account_with_middleware<T: Send + Sync + 'static>(
+        self,
+        decoder: impl for<'a> AccountDecoder<'a, AccountType = T> + Send + Sync + 'static,
+        processor: impl Processor<InputType = AccountProcessorInputType<T>> + Send + Sync + 'static,
+        middlewares: Vec<Box<dyn ProcessorMiddleware<AccountProcessorInputType<T>>>>,
+    ) -> Self {
+        log::trace!(
+            "account_with_middleware(self, decoder: {:?}, processor: {:?}, middlewares: {})",
+            stringify!(decoder),
+            stringify!(processor),
+            middlewares.len()
+        );
+        self.account(decoder, WithMiddleware::new(processor).layers(middlewares))
+    }
+
     /// Adds an account deletion pipe to handle account deletion events.
     ///
     /// Account deletion pipes process deletions of accounts, with a `Processor`
@@ -845,6 +1367,76 @@ impl PipelineBuilder {
         self
     }
 
+    /// Adds a block bundle pipe, delivering every update of a slot to the
+    /// processor as a single `BlockBundle`.
+    ///
+    /// Block bundles suit consumers whose invariants only hold at block
+    /// boundaries, such as per-block accounting. Updates are still passed to
+    /// the other pipes as they arrive.
+    ///
+    /// # Parameters
+    ///
+    /// - `slot_lag`: How many slots behind the highest received slot a slot
+    ///   stays open. Use `0` for datasources delivering slots in order.
+    /// - `processor`: A `Processor` that processes complete block bundles.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::pipeline::PipelineBuilder;
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .block_bundles(2, MyBlockBundleProcessor);
+    /// ```
+    pub fn block_bundles(
+        mut self,
+        slot_lag: u64,
+        processor: impl Processor<InputType = BlockBundle> + Send + Sync + 'static,
+    ) -> Self {
+        log::trace!(
+            "block_bundles(self, slot_lag: {:?}, processor: {:?})",
+            slot_lag,
+            stringify!(processor)
+        );
+        self.block_bundle_pipes.push(Box::new(BlockBundlePipe {
+            bundler: BlockBundler::new(slot_lag),
+            processor: Box::new(processor),
+        }));
+        self
+    }
+
+    /// Adds a slot status pipe to handle slot status updates.
+    ///
+    /// Slot status pipes receive the commitment changes of slots, including
+    /// slots dropped by a fork. They drive the `FinalityGate`s holding back
+    /// updates until their slot is finalized.
+    ///
+    /// # Parameters
+    ///
+    /// - `processor`: A `Processor` that processes slot status updates.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::{finality::FinalityGate, pipeline::PipelineBuilder};
+    ///
+    /// let gate = FinalityGate::finalized(MyInstructionProcessor);
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .instruction(MyInstructionDecoder, gate.clone())
+    ///     .slot_status(gate.status_processor());
+    /// ```
+    pub fn slot_status(
+        mut self,
+        processor: impl Processor<InputType = SlotStatusUpdate> + Send + Sync + 'static,
+    ) -> Self {
+        log::trace!("slot_status(self, processor: {:?})", stringify!(processor));
+        self.slot_status_pipes.push(Box::new(SlotStatusPipe {
+            processor: Box::new(processor),
+        }));
+        self
+    }
+
     /// Adds an instruction pipe to process instructions within transactions.
     ///
     /// Instruction pipes decode and process individual instructions,
@@ -881,6 +1473,50 @@ impl PipelineBuilder {
         self
     }
 
+    /// Adds an instruction pipe whose processor is wrapped with middlewares.
+    ///
+    /// The `before` hooks of the middlewares run in order before each
+    /// invocation of `processor`, and their `after` hooks in reverse order
+    /// after it.
+    ///
+    /// # Parameters
+    ///
+    /// - `decoder`: An `InstructionDecoder` for parsing instructions.
+    /// - `processor`: A `Processor` that processes decoded instruction data.
+    /// - `middlewares`: The `ProcessorMiddleware`s wrapping `processor`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::{
+    ///     middleware::{RateLimit, Timing},
+    ///     pipeline::PipelineBuilder,
+    /// };
+    ///
+    /// let builder = PipelineBuilder::new().instruction_with_middleware(
+    ///     MyDecoder,
+    ///     MyInstructionProcessor,
+    ///     vec![
+    ///         Box::new(Timing::new("my_instruction_processor")),
+    ///         Box::new(RateLimit::per_second(100)),
+    ///     ],
+    /// );
+    /// ```
+    pub fn instruction_with_middleware<T: Send + Sync + 'static>(
+        self,
+        decoder: impl for<'a> InstructionDecoder<'a, InstructionType = T> + Send + Sync + 'static,
+        processor: impl Processor<InputType = InstructionProcessorInputType<T>> + Send + Sync + 'static,
+        middlewares: Vec<Box<dyn ProcessorMiddleware<InstructionProcessorInputType<T>>>>,
+    ) -> Self {
+        log::trace!(
+            "instruction_with_middleware(self, decoder: {:?}, processor: {:?}, middlewares: {})",
+            stringify!(decoder),
+            stringify!(processor),
+            middlewares.len()
+        );
+        self.instruction(decoder, WithMiddleware::new(processor).layers(middlewares))
+    }
+
     /// Adds a transaction pipe for processing full transaction data.
     ///
     /// This method requires a transaction schema for decoding and a `Processor`
@@ -923,6 +1559,50 @@ impl PipelineBuilder {
         self
     }
 
+    /// Adds a pipe processing all instructions of a transaction decoded by a
+    /// single decoder together.
+    ///
+    /// The processor receives the outer and inner instructions decoded in a
+    /// transaction in execution order, which makes it possible to match
+    /// instruction patterns with `transaction::match_instruction_pattern`.
+    /// Transactions without any decoded instruction are skipped.
+    ///
+    /// # Parameters
+    ///
+    /// - `decoder`: An `InstructionDecoder` for decoding instructions from
+    ///   transaction data.
+    /// - `processor`: A `Processor` that processes the decoded instructions of
+    ///   a transaction.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::pipeline::PipelineBuilder;
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .transaction_instructions(MyDecoder, MyTransactionInstructionsProcessor);
+    /// ```
+    pub fn transaction_instructions<T: Send + Sync + 'static>(
+        mut self,
+        decoder: impl for<'a> InstructionDecoder<'a, InstructionType = T> + Send + Sync + 'static,
+        processor: impl Processor<InputType = TransactionInstructionsInputType<T>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        log::trace!(
+            "transaction_instructions(self, decoder: {:?}, processor: {:?})",
+            stringify!(decoder),
+            stringify!(processor)
+        );
+        self.transaction_pipes
+            .push(Box::new(TransactionInstructionsPipe {
+                decoder: Box::new(decoder),
+                processor: Box::new(processor),
+            }));
+        self
+    }
+
     /// Adds a metrics component to the pipeline for performance tracking.
     ///
     /// This component collects and reports on pipeline metrics, providing
@@ -1022,6 +1702,311 @@ impl PipelineBuilder {
         self
     }
 
+    /// Sets what happens to the updates arriving while the channel buffer is
+    /// full.
+    ///
+    /// By default, datasources wait until the pipeline makes room in the
+    /// buffer. The drop policies keep datasources going instead, dropping the
+    /// oldest or the arriving updates and counting them in the
+    /// `updates_dropped` metric.
+    ///
+    /// # Parameters
+    ///
+    /// - `overflow_policy`: The `OverflowPolicy` applied when the buffer is
+    ///   full.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use carbon_core::{overflow::OverflowPolicy, pipeline::PipelineBuilder};
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .channel_buffer_size(1000)
+    ///     .overflow_policy(OverflowPolicy::DropOldest);
+    /// ```
+    pub fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        log::trace!(
+            "overflow_policy(self, overflow_policy: {:?})",
+            overflow_policy
+        );
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// Sets the queue depths at which the pipeline signals overload to its
+    /// datasources, and at which it recovers.
+    ///
+    /// Datasources supporting backpressure pause their subscriptions while
+    /// the pipeline is overloaded. By default, the pipeline is overloaded at
+    /// 80% of the channel buffer size, and recovers at 50%.
+    ///
+    /// # Parameters
+    ///
+    /// - `high`: The number of queued updates at which the pipeline is
+    ///   overloaded.
+    /// - `low`: The number of queued updates at which an overloaded pipeline
+    ///   recovers, lower than `high`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use carbon_core::pipeline::PipelineBuilder;
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .channel_buffer_size(10_000)
+    ///     .backpressure_watermarks(9_000, 2_000);
+    /// ```
+    pub fn backpressure_watermarks(mut self, high: usize, low: usize) -> Self {
+        log::trace!(
+            "backpressure_watermarks(self, high: {}, low: {})",
+            high,
+            low
+        );
+        self.backpressure_watermarks = Some(BackpressureWatermarks { high, low });
+        self
+    }
+
+    /// Restricts transaction processing to transactions touching at least one
+    /// of the given program ids.
+    ///
+    /// Firehose datasources deliver every transaction of a slot, most of which
+    /// are irrelevant to the registered decoders. With a program id filter set,
+    /// the pipeline checks each transaction's static account keys against the
+    /// set and skips non-matching transactions before resolving their address
+    /// lookup tables and decoding any instruction. Skipped transactions are
+    /// counted by the `transaction_updates_filtered` metric.
+    ///
+    /// The filter applies to both instruction and transaction pipes. Account,
+    /// account deletion and block details updates are not affected.
+    ///
+    /// # Parameters
+    ///
+    /// - `program_ids`: The program ids whose transactions should be processed.
+    ///   Calling this method several times extends the set.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::pipeline::PipelineBuilder;
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .program_id_filter([PUMPFUN_PROGRAM_ID, PUMP_SWAP_PROGRAM_ID]);
+    /// ```
+    pub fn program_id_filter(mut self, program_ids: impl IntoIterator<Item = Pubkey>) -> Self {
+        let program_ids: Vec<Pubkey> = program_ids.into_iter().collect();
+        log::trace!("program_id_filter(self, program_ids: {:?})", program_ids);
+        self.program_id_filter
+            .get_or_insert_with(HashSet::new)
+            .extend(program_ids);
+        self
+    }
+
+    /// Sets the resolver filling in the addresses loaded from address lookup
+    /// tables by v0 transactions.
+    ///
+    /// Some datasources deliver v0 transactions without their loaded
+    /// addresses, so instructions referencing accounts from lookup tables
+    /// can't be decoded. The resolver fetches the lookup tables of these
+    /// transactions, caching them, before instructions are extracted. Only the
+    /// transactions passing the program id filter are resolved. Transactions
+    /// already holding their loaded addresses are left untouched.
+    ///
+    /// # Parameters
+    ///
+    /// - `resolver`: The `AddressLookupTableResolver` resolving the loaded
+    ///   addresses.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::{
+    ///     address_lookup_table::AddressLookupTableResolver, pipeline::PipelineBuilder,
+    /// };
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .address_lookup_table_resolver(AddressLookupTableResolver::new(source));
+    /// ```
+    pub fn address_lookup_table_resolver(mut self, resolver: AddressLookupTableResolver) -> Self {
+        log::trace!("address_lookup_table_resolver(self, resolver)");
+        self.address_lookup_table_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Enables recording the resource usage of every pipe.
+    ///
+    /// Each time a pipe runs, the time spent polling it and the allocations
+    /// it made are recorded under the pipe's kind and registration order,
+    /// such as `instruction_pipe_0_poll_time_nanoseconds`,
+    /// `instruction_pipe_0_allocations` and
+    /// `instruction_pipe_0_allocated_bytes`. Allocations are only counted
+    /// when a `resource_metrics::TrackingAllocator` is installed as the
+    /// global allocator. Individual processors can be measured with
+    /// `resource_metrics::ResourceTracked`.
+    ///
+    /// # Parameters
+    ///
+    /// - `enabled`: Whether the resource usage of pipes is recorded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use carbon_core::pipeline::PipelineBuilder;
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .resource_metrics(true);
+    /// ```
+    pub fn resource_metrics(mut self, enabled: bool) -> Self {
+        log::trace!("resource_metrics(self, enabled: {:?})", enabled);
+        self.resource_metrics = enabled;
+        self
+    }
+
+    /// Flags the first update of each account as a creation.
+    ///
+    /// The pipeline records every account it receives an update for in
+    /// `store`, and sets `AccountMetadata::created` on updates of accounts
+    /// the store hadn't seen yet. Accounts closed by a deletion, or by an
+    /// update leaving them without lamports, are removed from the store.
+    ///
+    /// # Parameters
+    ///
+    /// - `store`: The `SeenAccountsStore` keeping the seen accounts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use carbon_core::{account_creation::InMemorySeenAccounts, pipeline::PipelineBuilder};
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .track_account_creation(InMemorySeenAccounts::new());
+    /// ```
+    pub fn track_account_creation(mut self, store: impl SeenAccountsStore + 'static) -> Self {
+        log::trace!("track_account_creation(self, store)");
+        self.seen_accounts = Some(Arc::new(store));
+        self
+    }
+
+    /// Skips transaction and account updates already processed.
+    ///
+    /// The pipeline records the key of every transaction and account update in
+    /// `store`, and skips the updates whose key was recorded within the window
+    /// of the store, such as updates delivered by several datasources. Stores
+    /// shared by several pipelines deduplicate updates across all of them.
+    ///
+    /// # Parameters
+    ///
+    /// - `store`: The `DedupeStore` recording the keys of processed updates.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     carbon_core::{deduplication::InMemoryDedupeStore, pipeline::PipelineBuilder},
+    ///     std::time::Duration,
+    /// };
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .deduplicate(InMemoryDedupeStore::new(Duration::from_secs(120)));
+    /// ```
+    pub fn deduplicate(mut self, store: impl DedupeStore + 'static) -> Self {
+        log::trace!("deduplicate(self, store)");
+        self.dedupe_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Tracks the latency and freshness objectives of the pipeline.
+    ///
+    /// The pipeline records the block time of every transaction it processes
+    /// successfully in `slo_tracker`, and evaluates the objectives each time it
+    /// flushes its metrics, exporting their burn rates and notifying the alert
+    /// sinks of the tracker.
+    ///
+    /// # Parameters
+    ///
+    /// - `slo_tracker`: The `SloTracker` configured with the objectives.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     carbon_core::{
+    ///         pipeline::PipelineBuilder,
+    ///         slo::{LogAlertSink, SloTracker},
+    ///     },
+    ///     std::time::Duration,
+    /// };
+    ///
+    /// let builder = PipelineBuilder::new().slo(
+    ///     SloTracker::new()
+    ///         .latency(Duration::from_secs(2), 0.99)
+    ///         .alert_sink(LogAlertSink),
+    /// );
+    /// ```
+    pub fn slo(mut self, slo_tracker: SloTracker) -> Self {
+        log::trace!("slo(self, slo_tracker)");
+        self.slo_tracker = Some(slo_tracker);
+        self
+    }
+
+    /// Limits the data size of the accounts passed to account pipes.
+    ///
+    /// Accounts with more than `max_account_data_size` bytes of data are only
+    /// passed to the pipes added with `account_chunks`, which decode them in
+    /// chunks, and skip the other account pipes and the block bundle pipes.
+    /// They are counted by the `accounts_oversized` metric.
+    ///
+    /// # Parameters
+    ///
+    /// - `max_account_data_size`: The maximum data size, in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use carbon_core::pipeline::PipelineBuilder;
+    ///
+    /// let builder = PipelineBuilder::new().max_account_data_size(1024 * 1024);
+    /// ```
+    pub fn max_account_data_size(mut self, max_account_data_size: usize) -> Self {
+        log::trace!(
+            "max_account_data_size(self, max_account_data_size: {})",
+            max_account_data_size
+        );
+        self.max_account_data_size = Some(max_account_data_size);
+        self
+    }
+
+    /// Flushes a buffering processor when the pipeline shuts down.
+    ///
+    /// Processors such as `BatchProcessor` hold their inputs until a batch is
+    /// complete. Once the datasources finished and every pending update was
+    /// processed, the inputs they still buffer are processed as well. They
+    /// aren't flushed with `ShutdownStrategy::Immediate`.
+    ///
+    /// # Parameters
+    ///
+    /// - `processor`: A handle to the buffering processor, usually a clone of
+    ///   the one passed to a pipe.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use carbon_core::{batch::BatchProcessor, pipeline::PipelineBuilder};
+    ///
+    /// let swaps = BatchProcessor::new(PostgresSwapWriter::new(pool), 1_000).slot_aligned();
+    ///
+    /// let builder = PipelineBuilder::new()
+    ///     .instruction(RaydiumAmmV4Decoder, swaps.clone())
+    ///     .flush_on_shutdown(swaps);
+    /// ```
+    pub fn flush_on_shutdown(mut self, processor: impl Flush + 'static) -> Self {
+        log::trace!(
+            "flush_on_shutdown(self, processor: {:?})",
+            stringify!(processor)
+        );
+        self.shutdown_flushes.push(Box::new(processor));
+        self
+    }
+
     /// Builds and returns a `Pipeline` configured with the specified
     /// components.
     ///
@@ -1065,6 +2050,8 @@ impl PipelineBuilder {
             account_pipes: self.account_pipes,
             account_deletion_pipes: self.account_deletion_pipes,
             block_details_pipes: self.block_details_pipes,
+            block_bundle_pipes: self.block_bundle_pipes,
+            slot_status_pipes: self.slot_status_pipes,
             instruction_pipes: self.instruction_pipes,
             transaction_pipes: self.transaction_pipes,
             shutdown_strategy: self.shutdown_strategy,
@@ -1072,6 +2059,34 @@ impl PipelineBuilder {
             metrics_flush_interval: self.metrics_flush_interval,
             datasource_cancellation_token: self.datasource_cancellation_token,
             channel_buffer_size: self.channel_buffer_size,
+            overflow_policy: self.overflow_policy,
+            backpressure_watermarks: self.backpressure_watermarks,
+            program_id_filter: self.program_id_filter,
+            address_lookup_table_resolver: self.address_lookup_table_resolver,
+            resource_metrics: self.resource_metrics,
+            seen_accounts: self.seen_accounts,
+            dedupe_store: self.dedupe_store,
+            slo_tracker: self.slo_tracker,
+            max_account_data_size: self.max_account_data_size,
+            shutdown_flushes: self.shutdown_flushes,
+            instruction_dispatch: InstructionDispatch::default(),
         })
     }
 }
+
+/// Checks whether a transaction references any of the given program ids.
+///
+/// Only the static account keys are checked, since program ids can't be loaded
+/// from address lookup tables. This lets the filter run before the loaded
+/// addresses are resolved.
+fn touches_program_ids(
+    transaction_update: &TransactionUpdate,
+    program_ids: &HashSet<Pubkey>,
+) -> bool {
+    transaction_update
+        .transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .any(|account_key| program_ids.contains(account_key))
+}
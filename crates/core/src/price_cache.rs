@@ -0,0 +1,350 @@
+//! Provides a cache of the latest oracle prices, used to enrich decoded token
+//! amounts with their USD value.
+//!
+//! Most indexers reporting swaps or transfers also need the USD value of the
+//! amounts involved. Querying a price API for every instruction is slow and
+//! rarely matches the slot of the transaction, while the on-chain price feeds
+//! of oracles such as Pyth or Switchboard can be indexed alongside the
+//! program itself. The `price_cache` module keeps the latest price of each
+//! feed, updated by an account pipe decoding the oracle accounts, and maps
+//! mints to the feeds pricing them.
+//!
+//! # Overview
+//!
+//! - **`Price`**: An oracle price, expressed as a mantissa and a decimal
+//!   exponent, along with its confidence interval and slot.
+//! - **`MintFeed`**: The price feed of a mint, and the number of decimals of
+//!   the mint used to convert raw token amounts.
+//! - **`PriceCache`**: A cloneable handle to the shared cache, holding the
+//!   latest price of every feed and the mint to feed mapping.
+//! - **`PriceCacheProcessor`**: A `Processor` extracting prices from decoded
+//!   oracle accounts into the cache before forwarding them to the wrapped
+//!   processor. It is set up automatically by
+//!   `PipelineBuilder::account_with_prices`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::price_cache::{MintFeed, Price, PriceCache};
+//!
+//! let prices = PriceCache::new(Some(Duration::from_secs(60)));
+//! prices
+//!     .add_mint(WSOL_MINT, MintFeed::new(SOL_USD_FEED, 9))
+//!     .await;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .account_with_prices(PythDecoder, OracleProcessor, prices.clone(), |account| {
+//!         match account {
+//!             PythAccount::PriceUpdateV2(update) => Some(Price::new(
+//!                 update.price_message.price,
+//!                 update.price_message.exponent,
+//!                 update.price_message.conf,
+//!                 update.posted_slot,
+//!             )),
+//!             _ => None,
+//!         }
+//!     })
+//!     .instruction(JupiterSwapDecoder, SwapProcessor { prices })
+//!     .build()?
+//!     .run()
+//!     .await?;
+//!
+//! // Inside `SwapProcessor::process`:
+//! let usd_value = self.prices.usd_value(&swap.input_mint, swap.in_amount).await;
+//! ```
+//!
+//! # Notes
+//!
+//! - Prices from a slot older than the cached one are ignored, so an oracle
+//!   update arriving out of order never overwrites a newer price.
+//! - Prices older than the configured maximum age are treated as missing, so
+//!   amounts are never enriched with a price from a stalled feed.
+//! - Several mints can share a feed, such as wrapped and bridged variants of
+//!   the same asset.
+
+use {
+    crate::{
+        account::AccountProcessorInputType, error::CarbonResult, metrics::MetricsCollection,
+        processor::Processor,
+    },
+    async_trait::async_trait,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::RwLock,
+};
+
+/// An oracle price, equal to `price * 10^exponent`.
+///
+/// # Fields
+///
+/// - `price`: The price mantissa.
+/// - `exponent`: The decimal exponent of the price and confidence.
+/// - `confidence`: The confidence interval around the price, as a mantissa.
+/// - `slot`: The slot the price was published at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Price {
+    pub price: i64,
+    pub exponent: i32,
+    pub confidence: u64,
+    pub slot: u64,
+}
+
+impl Price {
+    pub fn new(price: i64, exponent: i32, confidence: u64, slot: u64) -> Self {
+        Self {
+            price,
+            exponent,
+            confidence,
+            slot,
+        }
+    }
+
+    /// Returns the price as a floating point number.
+    pub fn value(&self) -> f64 {
+        self.price as f64 * 10f64.powi(self.exponent)
+    }
+
+    /// Returns the confidence interval as a floating point number.
+    pub fn confidence_value(&self) -> f64 {
+        self.confidence as f64 * 10f64.powi(self.exponent)
+    }
+}
+
+/// The price feed of a mint.
+///
+/// # Fields
+///
+/// - `feed`: The address of the oracle account pricing the mint.
+/// - `decimals`: The number of decimals of the mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MintFeed {
+    pub feed: Pubkey,
+    pub decimals: u8,
+}
+
+impl MintFeed {
+    pub fn new(feed: Pubkey, decimals: u8) -> Self {
+        Self { feed, decimals }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedPrice {
+    price: Price,
+    updated_at: Instant,
+}
+
+struct PriceState {
+    max_age: Option<Duration>,
+    prices: HashMap<Pubkey, CachedPrice>,
+    mints: HashMap<Pubkey, MintFeed>,
+}
+
+impl PriceState {
+    fn new(max_age: Option<Duration>) -> Self {
+        Self {
+            max_age,
+            prices: HashMap::new(),
+            mints: HashMap::new(),
+        }
+    }
+
+    fn is_stale(&self, cached: &CachedPrice, now: Instant) -> bool {
+        self.max_age
+            .is_some_and(|max_age| now.duration_since(cached.updated_at) > max_age)
+    }
+
+    fn price(&self, feed: &Pubkey, now: Instant) -> Option<Price> {
+        self.prices
+            .get(feed)
+            .filter(|cached| !self.is_stale(cached, now))
+            .map(|cached| cached.price)
+    }
+
+    /// Inserts a price, returning `false` if a newer price is already cached.
+    fn update(&mut self, feed: Pubkey, price: Price, now: Instant) -> bool {
+        if let Some(existing) = self.prices.get(&feed) {
+            if existing.price.slot > price.slot && !self.is_stale(existing, now) {
+                return false;
+            }
+        }
+
+        self.prices.insert(
+            feed,
+            CachedPrice {
+                price,
+                updated_at: now,
+            },
+        );
+
+        true
+    }
+
+    fn usd_value(&self, mint: &Pubkey, amount: u64, now: Instant) -> Option<f64> {
+        let mint_feed = self.mints.get(mint)?;
+        let price = self.price(&mint_feed.feed, now)?;
+
+        Some(amount as f64 / 10f64.powi(mint_feed.decimals as i32) * price.value())
+    }
+}
+
+/// A cloneable handle to a cache of oracle prices.
+///
+/// Clones share the same cache, so a handle can be given to the account pipe
+/// populating the cache and to any number of processors reading from it.
+#[derive(Clone)]
+pub struct PriceCache {
+    state: Arc<RwLock<PriceState>>,
+}
+
+impl PriceCache {
+    /// Creates a new, empty price cache.
+    ///
+    /// # Parameters
+    ///
+    /// - `max_age`: The maximum age of cached prices. Prices updated longer ago
+    ///   are treated as missing. `None` keeps prices until they are replaced.
+    pub fn new(max_age: Option<Duration>) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(PriceState::new(max_age))),
+        }
+    }
+
+    /// Creates a new price cache with the given mint to feed mapping.
+    pub fn with_mints(
+        max_age: Option<Duration>,
+        mints: impl IntoIterator<Item = (Pubkey, MintFeed)>,
+    ) -> Self {
+        let mut state = PriceState::new(max_age);
+        state.mints.extend(mints);
+
+        Self {
+            state: Arc::new(RwLock::new(state)),
+        }
+    }
+
+    /// Maps a mint to the feed pricing it, replacing any previous mapping.
+    pub async fn add_mint(&self, mint: Pubkey, mint_feed: MintFeed) {
+        self.state.write().await.mints.insert(mint, mint_feed);
+    }
+
+    /// Removes the feed mapping of a mint.
+    pub async fn remove_mint(&self, mint: &Pubkey) -> Option<MintFeed> {
+        self.state.write().await.mints.remove(mint)
+    }
+
+    /// Returns the latest price of a feed, unless it is stale.
+    pub async fn price(&self, feed: &Pubkey) -> Option<Price> {
+        self.state.read().await.price(feed, Instant::now())
+    }
+
+    /// Returns the latest price of the feed mapped to a mint, unless it is
+    /// stale.
+    pub async fn mint_price(&self, mint: &Pubkey) -> Option<Price> {
+        let state = self.state.read().await;
+        let mint_feed = state.mints.get(mint)?;
+        state.price(&mint_feed.feed, Instant::now())
+    }
+
+    /// Converts a raw token amount of a mint to its USD value, using the
+    /// decimals and the latest price of the mint.
+    ///
+    /// Returns `None` if the mint isn't mapped to a feed or if its price is
+    /// missing or stale.
+    pub async fn usd_value(&self, mint: &Pubkey, amount: u64) -> Option<f64> {
+        self.state
+            .read()
+            .await
+            .usd_value(mint, amount, Instant::now())
+    }
+
+    /// Updates the price of a feed. Returns `false` if the cache already
+    /// holds a price from a newer slot.
+    pub async fn update(&self, feed: Pubkey, price: Price) -> bool {
+        self.state.write().await.update(feed, price, Instant::now())
+    }
+
+    /// Creates a processor updating this cache with the prices extracted from
+    /// decoded oracle accounts before forwarding them to `processor`.
+    ///
+    /// # Parameters
+    ///
+    /// - `extract`: Extracts the price from a decoded oracle account, or
+    ///   returns `None` for accounts that aren't price feeds.
+    /// - `processor`: The processor receiving every decoded account.
+    pub fn processor<T>(
+        &self,
+        extract: impl Fn(&T) -> Option<Price> + Send + Sync + 'static,
+        processor: impl Processor<InputType = AccountProcessorInputType<T>> + Send + Sync + 'static,
+    ) -> PriceCacheProcessor<T> {
+        PriceCacheProcessor {
+            cache: self.clone(),
+            extract: Box::new(extract),
+            processor: Box::new(processor),
+        }
+    }
+}
+
+/// A processor updating a `PriceCache` with the prices of decoded oracle
+/// accounts before forwarding them to a wrapped processor.
+pub struct PriceCacheProcessor<T> {
+    cache: PriceCache,
+    extract: Box<dyn Fn(&T) -> Option<Price> + Send + Sync>,
+    processor: Box<dyn Processor<InputType = AccountProcessorInputType<T>> + Send + Sync>,
+}
+
+#[async_trait]
+impl<T> Processor for PriceCacheProcessor<T>
+where
+    T: Send + Sync + 'static,
+{
+    type InputType = AccountProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, decoded_account, _) = &data;
+
+        if let Some(price) = (self.extract)(&decoded_account.data) {
+            if self.cache.update(metadata.pubkey, price).await {
+                metrics.increment_counter("price_cache_updates", 1).await?;
+            } else {
+                metrics
+                    .increment_counter("price_cache_outdated_updates", 1)
+                    .await?;
+            }
+        }
+
+        self.processor.process(data, metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usd_value_uses_latest_fresh_price() {
+        let mut state = PriceState::new(Some(Duration::from_secs(30)));
+        let (mint, feed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        state.mints.insert(mint, MintFeed::new(feed, 6));
+        let now = Instant::now();
+
+        assert_eq!(state.usd_value(&mint, 1_000_000, now), None);
+
+        assert!(state.update(feed, Price::new(15_000_000_000, -8, 0, 10), now));
+        assert!(!state.update(feed, Price::new(14_000_000_000, -8, 0, 9), now));
+        let usd_value = state.usd_value(&mint, 2_500_000, now).unwrap();
+        assert!((usd_value - 375.0).abs() < 1e-9);
+
+        let later = now + Duration::from_secs(31);
+        assert_eq!(state.usd_value(&mint, 2_500_000, later), None);
+    }
+}
@@ -11,6 +11,8 @@
 //! - **Processor**: A trait that defines a single method, `process`, which
 //!   asynchronously handles data of a specified type. This allows different
 //!   stages of the pipeline to implement custom data handling logic.
+//! - **Flush**: A trait for processors buffering their inputs, whose remaining
+//!   inputs are processed when the pipeline shuts down.
 //! - **Metrics**: Metrics are collected during processing, offering visibility
 //!   into processing duration, success, failure rates, and other relevant
 //!   statistics.
@@ -117,3 +119,14 @@ pub trait Processor {
         metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()>;
 }
+
+/// A trait for processors buffering their inputs.
+///
+/// Processors registered with `PipelineBuilder::flush_on_shutdown` are flushed
+/// once the datasources finished and every pending update was processed, so
+/// the inputs they still buffer aren't lost.
+#[async_trait]
+pub trait Flush: Send + Sync {
+    /// Processes every buffered input.
+    async fn flush_pending(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()>;
+}
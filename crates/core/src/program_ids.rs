@@ -0,0 +1,198 @@
+//! Runs decoders against other deployments of their program, such as devnet
+//! deployments or forks, without editing their generated program ID.
+//!
+//! Generated decoders only decode the accounts owned by, and the instructions
+//! of, the program ID they were generated for. A `ProgramIdOverride` wraps a
+//! decoder with the program IDs it should decode instead: accounts and
+//! instructions of these programs are handed to the decoder as if they
+//! belonged to its program ID, and the decoded outputs carry their actual
+//! program ID.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::program_ids::ProgramIdOverride;
+//! use carbon_pumpfun_decoder::{PumpfunDecoder, PROGRAM_ID as PUMPFUN_PROGRAM_ID};
+//!
+//! let devnet_and_fork = [PUMPFUN_DEVNET_PROGRAM_ID, PUMPFUN_FORK_PROGRAM_ID];
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .instruction(
+//!         ProgramIdOverride::new(PumpfunDecoder, PUMPFUN_PROGRAM_ID, devnet_and_fork),
+//!         PumpfunInstructionProcessor,
+//!     )
+//!     .account(
+//!         ProgramIdOverride::new(PumpfunDecoder, PUMPFUN_PROGRAM_ID, devnet_and_fork),
+//!         PumpfunAccountProcessor,
+//!     )
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Only the listed program IDs are decoded, so the generated program ID must
+//!   be listed too for the decoder to keep decoding it.
+//! - Accounts and instructions of overridden programs are copied before being
+//!   decoded, so the decoded types can't borrow from them.
+//! - A `program_id_filter` set on the pipeline must list the overridden program
+//!   IDs, or their transactions are dropped before being decoded.
+
+use {
+    crate::{
+        account::{AccountDecoder, DecodedAccount},
+        instruction::{DecodedInstruction, InstructionDecoder},
+    },
+    solana_pubkey::Pubkey,
+    std::marker::PhantomData,
+};
+
+/// A decoder decoding the accounts and instructions of other program IDs than
+/// the one it was generated for.
+pub struct ProgramIdOverride<D, T> {
+    decoder: D,
+    program_id: Pubkey,
+    program_ids: Vec<Pubkey>,
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<D, T> ProgramIdOverride<D, T> {
+    /// Wraps `decoder`, generated for `program_id`, to decode the accounts and
+    /// instructions of `program_ids` instead.
+    pub fn new(
+        decoder: D,
+        program_id: Pubkey,
+        program_ids: impl IntoIterator<Item = Pubkey>,
+    ) -> Self {
+        let mut program_ids: Vec<Pubkey> = program_ids.into_iter().collect();
+        program_ids.sort_unstable();
+        program_ids.dedup();
+
+        Self {
+            decoder,
+            program_id,
+            program_ids,
+            _output: PhantomData,
+        }
+    }
+
+    /// Returns the program IDs the decoder decodes.
+    pub fn ids(&self) -> impl Iterator<Item = &Pubkey> {
+        self.program_ids.iter()
+    }
+}
+
+impl<D, T> AccountDecoder<'_> for ProgramIdOverride<D, T>
+where
+    D: for<'a> AccountDecoder<'a, AccountType = T>,
+{
+    type AccountType = T;
+
+    fn decode_account(&self, account: &solana_account::Account) -> Option<DecodedAccount<T>> {
+        if !self.program_ids.contains(&account.owner) {
+            return None;
+        }
+        if account.owner == self.program_id {
+            return self.decoder.decode_account(account);
+        }
+
+        let overridden = solana_account::Account {
+            owner: self.program_id,
+            ..account.clone()
+        };
+        let mut decoded = self.decoder.decode_account(&overridden)?;
+        decoded.owner = account.owner;
+        Some(decoded)
+    }
+
+    fn decoder_name(&self) -> &'static str {
+        self.decoder.decoder_name()
+    }
+}
+
+impl<D, T> InstructionDecoder<'_> for ProgramIdOverride<D, T>
+where
+    D: for<'a> InstructionDecoder<'a, InstructionType = T>,
+{
+    type InstructionType = T;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<DecodedInstruction<T>> {
+        if !self.program_ids.contains(&instruction.program_id) {
+            return None;
+        }
+        if instruction.program_id == self.program_id {
+            return self.decoder.decode_instruction(instruction);
+        }
+
+        let overridden = solana_instruction::Instruction {
+            program_id: self.program_id,
+            accounts: instruction.accounts.clone(),
+            data: instruction.data.clone(),
+        };
+        let mut decoded = self.decoder.decode_instruction(&overridden)?;
+        decoded.program_id = instruction.program_id;
+        Some(decoded)
+    }
+
+    fn program_ids(&self) -> Option<&[Pubkey]> {
+        Some(&self.program_ids)
+    }
+
+    fn decoder_name(&self) -> &'static str {
+        self.decoder.decoder_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM_ID: Pubkey = Pubkey::new_from_array([1; 32]);
+
+    struct FirstByteDecoder;
+
+    impl InstructionDecoder<'_> for FirstByteDecoder {
+        type InstructionType = u8;
+
+        fn decode_instruction(
+            &self,
+            instruction: &solana_instruction::Instruction,
+        ) -> Option<DecodedInstruction<u8>> {
+            if instruction.program_id != PROGRAM_ID {
+                return None;
+            }
+
+            Some(DecodedInstruction {
+                program_id: instruction.program_id,
+                data: *instruction.data.first()?,
+                accounts: instruction.accounts.clone(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_decodes_overridden_program_ids() {
+        let devnet = Pubkey::new_unique();
+        let decoder = ProgramIdOverride::new(FirstByteDecoder, PROGRAM_ID, [devnet]);
+        let instruction = |program_id| solana_instruction::Instruction {
+            program_id,
+            accounts: vec![],
+            data: vec![7],
+        };
+
+        let decoded = decoder.decode_instruction(&instruction(devnet)).unwrap();
+        assert_eq!(decoded.program_id, devnet);
+        assert_eq!(decoded.data, 7);
+        assert!(decoder
+            .decode_instruction(&instruction(PROGRAM_ID))
+            .is_none());
+        assert!(decoder
+            .decode_instruction(&instruction(Pubkey::new_unique()))
+            .is_none());
+    }
+}
@@ -0,0 +1,350 @@
+//! Defines sinks, the destinations of the outputs of a pipeline, and
+//! combinators composing them.
+//!
+//! Writing decoded data to several destinations, or only part of it, usually
+//! means writing a custom processor for every combination. A `Sink` receives
+//! typed items, and the combinators of `SinkExt` build new sinks from existing
+//! ones: filtering and converting the items they receive, sending them to
+//! several sinks, or buffering them into batches. The composed sink is then
+//! registered on a pipe with `SinkExt::into_processor`.
+//!
+//! # Overview
+//!
+//! - **`Sink`**: Receives items one at a time, and flushes them on demand.
+//! - **`SinkExt::filter_map`**: Converts the items sent to a sink, dropping
+//!   those it returns `None` for.
+//! - **`SinkExt::tee`**: Sends every item to two sinks.
+//! - **`SinkExt::buffer`**: Groups items into batches for a sink of batches,
+//!   such as a database sink writing each batch in a single statement.
+//! - **`SinkProcessor`**: A cloneable `Processor` sending its inputs to a sink.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::sink::SinkExt;
+//! use carbon_postgres_client::PgSink;
+//!
+//! let swaps = PgSink::<SwapRow>::new(pg_client.pool.clone())
+//!     .buffer(1_000)
+//!     .tee(LogSink)
+//!     .filter_map(|(metadata, instruction, _, _): InstructionProcessorInputType<_>| {
+//!         SwapRow::from_instruction(&metadata, instruction.data)
+//!     })
+//!     .into_processor();
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .instruction(RaydiumAmmV4Decoder, swaps.clone())
+//!     .build()?
+//!     .run()
+//!     .await?;
+//!
+//! // Write the items still buffered once the pipeline stopped.
+//! swaps.flush().await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Combinators wrap the sink they are called on, so the sink built last is
+//!   the one receiving the items first.
+//! - Every sink of batches of cloneable items is also a `BatchWriter`, so it
+//!   can be used with a `BatchProcessor` for slot-aligned batches.
+//! - Buffered items are lost if the process exits without flushing the sink. A
+//!   batch the wrapped sink fails to receive stays buffered, and is sent again
+//!   with the next items or on flush.
+
+use {
+    crate::{
+        batch::BatchWriter, error::CarbonResult, metrics::MetricsCollection, processor::Processor,
+    },
+    async_trait::async_trait,
+    std::{marker::PhantomData, sync::Arc},
+    tokio::sync::Mutex,
+};
+
+/// A destination of items, such as a database table or a message queue.
+#[async_trait]
+pub trait Sink<T: Send>: Send + Sync {
+    /// Sends an item to the sink.
+    async fn send(&mut self, item: T) -> CarbonResult<()>;
+
+    /// Writes the items buffered by the sink, if any.
+    async fn flush(&mut self) -> CarbonResult<()> {
+        Ok(())
+    }
+}
+
+/// Combinators building sinks from other sinks.
+pub trait SinkExt<T: Send>: Sink<T> + Sized {
+    /// Returns a sink converting its items with `f` before sending them to
+    /// this sink, dropping the items `f` returns `None` for.
+    fn filter_map<U, F>(self, f: F) -> FilterMap<Self, F, T>
+    where
+        F: FnMut(U) -> Option<T> + Send + Sync,
+    {
+        FilterMap {
+            sink: self,
+            f,
+            _item: PhantomData,
+        }
+    }
+
+    /// Returns a sink sending a clone of every item to this sink, then to
+    /// `other`.
+    fn tee<S: Sink<T>>(self, other: S) -> Tee<Self, S>
+    where
+        T: Clone,
+    {
+        Tee {
+            first: self,
+            second: other,
+        }
+    }
+
+    /// Returns a sink sending its items to this sink in batches of
+    /// `batch_size` items.
+    fn buffer<U>(self, batch_size: usize) -> Buffer<Self, U>
+    where
+        Self: Sink<Vec<U>>,
+        U: Clone + Send + Sync,
+    {
+        let batch_size = batch_size.max(1);
+        Buffer {
+            sink: self,
+            buffer: Vec::with_capacity(batch_size),
+            batch_size,
+        }
+    }
+
+    /// Returns a processor sending its inputs to this sink.
+    fn into_processor(self) -> SinkProcessor<Self, T> {
+        SinkProcessor {
+            sink: Arc::new(Mutex::new(self)),
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<T: Send, S: Sink<T>> SinkExt<T> for S {}
+
+/// A sink converting its items before sending them to another sink.
+pub struct FilterMap<S, F, T> {
+    sink: S,
+    f: F,
+    _item: PhantomData<fn() -> T>,
+}
+
+#[async_trait]
+impl<U, T, S, F> Sink<U> for FilterMap<S, F, T>
+where
+    U: Send + 'static,
+    T: Send,
+    S: Sink<T>,
+    F: FnMut(U) -> Option<T> + Send + Sync,
+{
+    async fn send(&mut self, item: U) -> CarbonResult<()> {
+        match (self.f)(item) {
+            Some(item) => self.sink.send(item).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn flush(&mut self) -> CarbonResult<()> {
+        self.sink.flush().await
+    }
+}
+
+/// A sink sending every item to two sinks.
+pub struct Tee<A, B> {
+    first: A,
+    second: B,
+}
+
+#[async_trait]
+impl<T, A, B> Sink<T> for Tee<A, B>
+where
+    T: Clone + Send + 'static,
+    A: Sink<T>,
+    B: Sink<T>,
+{
+    async fn send(&mut self, item: T) -> CarbonResult<()> {
+        self.first.send(item.clone()).await?;
+        self.second.send(item).await
+    }
+
+    async fn flush(&mut self) -> CarbonResult<()> {
+        self.first.flush().await?;
+        self.second.flush().await
+    }
+}
+
+/// A sink grouping its items into batches for a sink of batches.
+pub struct Buffer<S, T> {
+    sink: S,
+    buffer: Vec<T>,
+    batch_size: usize,
+}
+
+impl<S, T> Buffer<S, T>
+where
+    T: Clone + Send + Sync + 'static,
+    S: Sink<Vec<T>>,
+{
+    /// Sends the buffered items as a batch, keeping them buffered if the send
+    /// fails.
+    async fn send_buffer(&mut self) -> CarbonResult<()> {
+        self.sink.send(self.buffer.clone()).await?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T, S> Sink<T> for Buffer<S, T>
+where
+    T: Clone + Send + Sync + 'static,
+    S: Sink<Vec<T>>,
+{
+    async fn send(&mut self, item: T) -> CarbonResult<()> {
+        self.buffer.push(item);
+        if self.buffer.len() < self.batch_size {
+            return Ok(());
+        }
+
+        self.send_buffer().await
+    }
+
+    async fn flush(&mut self) -> CarbonResult<()> {
+        if !self.buffer.is_empty() {
+            self.send_buffer().await?;
+        }
+        self.sink.flush().await
+    }
+}
+
+#[async_trait]
+impl<T, S> BatchWriter<T> for S
+where
+    T: Clone + Send + Sync + 'static,
+    S: Sink<Vec<T>>,
+{
+    async fn write_batch(&mut self, batch: &[T]) -> CarbonResult<()> {
+        self.send(batch.to_vec()).await
+    }
+}
+
+/// A processor sending its inputs to a sink.
+///
+/// Clones share the same sink, so a handle can be kept to flush the sink once
+/// the pipeline stopped.
+pub struct SinkProcessor<S, T> {
+    sink: Arc<Mutex<S>>,
+    _item: PhantomData<fn(T)>,
+}
+
+impl<S, T> Clone for SinkProcessor<S, T> {
+    fn clone(&self) -> Self {
+        Self {
+            sink: self.sink.clone(),
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<S: Sink<T>, T: Send> SinkProcessor<S, T> {
+    /// Writes the items buffered by the sink.
+    pub async fn flush(&self) -> CarbonResult<()> {
+        self.sink.lock().await.flush().await
+    }
+}
+
+#[async_trait]
+impl<S, T> Processor for SinkProcessor<S, T>
+where
+    S: Sink<T>,
+    T: Send + 'static,
+{
+    type InputType = T;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        self.sink.lock().await.send(data).await?;
+
+        metrics.increment_counter("sink_items_sent", 1).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::sync::Mutex as StdMutex};
+
+    struct Recorder<T>(Arc<StdMutex<Vec<T>>>);
+
+    #[async_trait]
+    impl<T: Send + 'static> Sink<T> for Recorder<T> {
+        async fn send(&mut self, item: T) -> CarbonResult<()> {
+            self.0.lock().unwrap().push(item);
+            Ok(())
+        }
+    }
+
+    struct Failing<T> {
+        failures: usize,
+        sent: Arc<StdMutex<Vec<T>>>,
+    }
+
+    #[async_trait]
+    impl<T: Send + 'static> Sink<T> for Failing<T> {
+        async fn send(&mut self, item: T) -> CarbonResult<()> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(crate::error::Error::Custom("sink unavailable".to_string()));
+            }
+            self.sent.lock().unwrap().push(item);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buffer_keeps_batch_on_failed_send() {
+        let batches = Arc::new(StdMutex::new(Vec::<Vec<u64>>::new()));
+        let mut sink = Failing {
+            failures: 2,
+            sent: batches.clone(),
+        }
+        .buffer(2);
+
+        sink.send(0).await.unwrap();
+        assert!(sink.send(1).await.is_err());
+        assert!(sink.flush().await.is_err());
+        assert!(batches.lock().unwrap().is_empty());
+
+        sink.send(2).await.unwrap();
+        sink.send(3).await.unwrap();
+        sink.flush().await.unwrap();
+        assert_eq!(*batches.lock().unwrap(), [vec![0, 1, 2], vec![3]]);
+    }
+
+    #[tokio::test]
+    async fn test_combinators() {
+        let batches = Arc::new(StdMutex::new(Vec::<Vec<u64>>::new()));
+        let evens = Arc::new(StdMutex::new(Vec::<u64>::new()));
+        let mut sink = Recorder(batches.clone())
+            .buffer(2)
+            .tee(Recorder(evens.clone()))
+            .filter_map(|item: u64| (item % 2 == 0).then_some(item));
+
+        for item in 0..7 {
+            sink.send(item).await.unwrap();
+        }
+        assert_eq!(*batches.lock().unwrap(), [vec![0, 2], vec![4, 6]]);
+
+        sink.send(8).await.unwrap();
+        sink.flush().await.unwrap();
+        assert_eq!(*batches.lock().unwrap(), [vec![0, 2], vec![4, 6], vec![8]]);
+        assert_eq!(*evens.lock().unwrap(), [0, 2, 4, 6, 8]);
+    }
+}
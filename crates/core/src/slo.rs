@@ -0,0 +1,431 @@
+//! Tracks service level objectives on the latency and freshness of the
+//! pipeline, and computes how fast their error budget burns.
+//!
+//! Raw processing times tell how long an update took once received, but not
+//! whether the indexed data meets the expectations of its consumers. An
+//! `SloTracker` measures two objectives: the end-to-end latency of each
+//! transaction, from its block time until it's processed, and the freshness of
+//! the indexed data, the age of the latest block processed. Their burn rates
+//! are exported as gauges and, past a threshold, notified to alert sinks, so
+//! operators get paging signals without deriving them from raw metrics.
+//!
+//! # Overview
+//!
+//! - **`SloTracker`**: Records the outcome of each objective and computes its
+//!   burn rate over several windows.
+//! - **`SloAlertSink`**: Receives an `SloAlert` when an objective starts and
+//!   stops burning its budget too fast. `LogAlertSink` logs them.
+//!
+//! The burn rate of a window is the share of bad events in the window, divided
+//! by the share the objective tolerates. A burn rate of 1 consumes the budget
+//! exactly over the period of the objective, and a burn rate of 14.4 consumes
+//! 2% of a 30 days budget in an hour.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::slo::{LogAlertSink, SloTracker};
+//! use std::time::Duration;
+//!
+//! let slo = SloTracker::new()
+//!     // 99% of the transactions processed within 2 seconds of their block.
+//!     .latency(Duration::from_secs(2), 0.99)
+//!     // The latest block processed less than 30 seconds old 99.9% of the time.
+//!     .freshness(Duration::from_secs(30), 0.999)
+//!     .alert_sink(LogAlertSink);
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(yellowstone_grpc)
+//!     .slo(slo)
+//!     .instruction(PumpfunDecoder, PumpfunInstructionProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Block times have a precision of one second, so latencies below a second
+//!   can't be measured, and objectives should use thresholds of a few seconds.
+//! - Only transaction updates carry a block time. Pipelines only receiving
+//!   account updates can't track either objective.
+//! - Freshness is sampled each time metrics are flushed, so its events are
+//!   spaced by the `metrics_flush_interval` of the pipeline.
+//! - An alert fires when the burn rate exceeds the threshold over every window,
+//!   so a short spike doesn't page while a sustained one pages quickly, and is
+//!   resolved once the burn rate of any window falls back below it.
+
+use {
+    crate::{error::CarbonResult, metrics::MetricsCollection},
+    async_trait::async_trait,
+    std::{
+        collections::VecDeque,
+        sync::Arc,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// The windows burn rates are computed over unless configured otherwise.
+pub const DEFAULT_WINDOWS: [Duration; 2] =
+    [Duration::from_secs(5 * 60), Duration::from_secs(60 * 60)];
+
+/// The burn rate alerts fire at unless configured otherwise.
+pub const DEFAULT_ALERT_THRESHOLD: f64 = 14.4;
+
+/// The objectives tracked by an `SloTracker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SloObjective {
+    /// Transactions are processed within a threshold of their block time.
+    Latency,
+    /// The latest block processed is younger than a threshold.
+    Freshness,
+}
+
+impl SloObjective {
+    /// Returns the name of the objective, used in metric names.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SloObjective::Latency => "latency",
+            SloObjective::Freshness => "freshness",
+        }
+    }
+}
+
+/// A notification that an objective started or stopped burning its budget
+/// faster than the alert threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloAlert {
+    pub objective: SloObjective,
+    /// The latency or age of a good event.
+    pub threshold: Duration,
+    /// The share of good events targeted, such as 0.99.
+    pub target: f64,
+    /// The burn rate over each window, shortest window first.
+    pub burn_rates: Vec<(Duration, f64)>,
+    /// Whether the alert fires, or is resolved.
+    pub firing: bool,
+}
+
+/// Receives the alerts of an `SloTracker`, such as to page an operator.
+#[async_trait]
+pub trait SloAlertSink: Send + Sync {
+    async fn notify(&self, alert: &SloAlert) -> CarbonResult<()>;
+}
+
+/// Logs SLO alerts, firing alerts as warnings.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogAlertSink;
+
+#[async_trait]
+impl SloAlertSink for LogAlertSink {
+    async fn notify(&self, alert: &SloAlert) -> CarbonResult<()> {
+        if alert.firing {
+            log::warn!(
+                "SLO {} burning its budget too fast: {:?}",
+                alert.objective.name(),
+                alert.burn_rates
+            );
+        } else {
+            log::info!(
+                "SLO {} burn rate back to normal: {:?}",
+                alert.objective.name(),
+                alert.burn_rates
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The good and bad events recorded in one second.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    second: u64,
+    good: u64,
+    bad: u64,
+}
+
+/// The events of an objective over the longest window.
+#[derive(Debug)]
+struct ErrorBudget {
+    objective: SloObjective,
+    threshold: Duration,
+    target: f64,
+    buckets: VecDeque<Bucket>,
+    firing: bool,
+}
+
+impl ErrorBudget {
+    fn new(objective: SloObjective, threshold: Duration, target: f64) -> Self {
+        Self {
+            objective,
+            threshold,
+            target: target.clamp(0.0, 1.0),
+            buckets: VecDeque::new(),
+            firing: false,
+        }
+    }
+
+    fn record(&mut self, second: u64, value: Duration, retention: Duration) {
+        let good = value <= self.threshold;
+        match self.buckets.back_mut() {
+            Some(bucket) if bucket.second == second => {
+                if good {
+                    bucket.good += 1;
+                } else {
+                    bucket.bad += 1;
+                }
+            }
+            _ => self.buckets.push_back(Bucket {
+                second,
+                good: good as u64,
+                bad: !good as u64,
+            }),
+        }
+
+        while self
+            .buckets
+            .front()
+            .is_some_and(|bucket| bucket.second + retention.as_secs() <= second)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    fn burn_rate(&self, second: u64, window: Duration) -> f64 {
+        let (good, bad) = self
+            .buckets
+            .iter()
+            .rev()
+            .take_while(|bucket| bucket.second + window.as_secs() > second)
+            .fold((0, 0), |(good, bad), bucket| {
+                (good + bucket.good, bad + bucket.bad)
+            });
+
+        if good + bad == 0 {
+            return 0.0;
+        }
+
+        let bad_ratio = bad as f64 / (good + bad) as f64;
+        let budget = 1.0 - self.target;
+        if budget <= 0.0 {
+            return if bad > 0 { f64::INFINITY } else { 0.0 };
+        }
+
+        bad_ratio / budget
+    }
+}
+
+/// Tracks the latency and freshness objectives of a pipeline.
+///
+/// The pipeline records the block time of each processed transaction with
+/// `record_block_time`, and calls `evaluate` each time it flushes its metrics.
+pub struct SloTracker {
+    started: Instant,
+    latency: Option<ErrorBudget>,
+    freshness: Option<ErrorBudget>,
+    windows: Vec<Duration>,
+    alert_threshold: f64,
+    alert_sinks: Vec<Arc<dyn SloAlertSink>>,
+    latest_block_time: Option<i64>,
+}
+
+impl Default for SloTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SloTracker {
+    /// Creates a tracker without objectives, computing burn rates over the
+    /// `DEFAULT_WINDOWS`.
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            latency: None,
+            freshness: None,
+            windows: DEFAULT_WINDOWS.to_vec(),
+            alert_threshold: DEFAULT_ALERT_THRESHOLD,
+            alert_sinks: Vec::new(),
+            latest_block_time: None,
+        }
+    }
+
+    /// Tracks the share of transactions processed within `threshold` of their
+    /// block time, targeting `target`, such as 0.99.
+    pub fn latency(mut self, threshold: Duration, target: f64) -> Self {
+        self.latency = Some(ErrorBudget::new(SloObjective::Latency, threshold, target));
+        self
+    }
+
+    /// Tracks the share of the time the latest block processed is younger
+    /// than `threshold`, targeting `target`, such as 0.999.
+    pub fn freshness(mut self, threshold: Duration, target: f64) -> Self {
+        self.freshness = Some(ErrorBudget::new(SloObjective::Freshness, threshold, target));
+        self
+    }
+
+    /// Sets the windows burn rates are computed over.
+    pub fn windows(mut self, windows: impl IntoIterator<Item = Duration>) -> Self {
+        self.windows = windows.into_iter().collect();
+        self.windows.sort();
+        self
+    }
+
+    /// Sets the burn rate alerts fire at.
+    pub fn alert_threshold(mut self, alert_threshold: f64) -> Self {
+        self.alert_threshold = alert_threshold;
+        self
+    }
+
+    /// Adds a sink notified when an alert fires or is resolved.
+    pub fn alert_sink(mut self, alert_sink: impl SloAlertSink + 'static) -> Self {
+        self.alert_sinks.push(Arc::new(alert_sink));
+        self
+    }
+
+    fn second(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+
+    fn retention(&self) -> Duration {
+        self.windows.last().copied().unwrap_or_default()
+    }
+
+    /// Records that a transaction of a block with `block_time` was processed.
+    pub async fn record_block_time(
+        &mut self,
+        block_time: i64,
+        metrics: &MetricsCollection,
+    ) -> CarbonResult<()> {
+        let latency = age(block_time);
+        metrics
+            .record_histogram(
+                "slo_end_to_end_latency_milliseconds",
+                latency.as_millis() as f64,
+            )
+            .await?;
+
+        let (second, retention) = (self.second(), self.retention());
+        if let Some(latency_budget) = &mut self.latency {
+            latency_budget.record(second, latency, retention);
+        }
+
+        self.latest_block_time = self.latest_block_time.max(Some(block_time));
+
+        Ok(())
+    }
+
+    /// Samples the freshness of the indexed data, exports the burn rate of
+    /// each objective and notifies the alert sinks of alerts firing or
+    /// resolved.
+    ///
+    /// Freshness isn't sampled until a block time was recorded.
+    pub async fn evaluate(&mut self, metrics: &MetricsCollection) -> CarbonResult<()> {
+        let (second, retention) = (self.second(), self.retention());
+
+        if let Some(latest_block_time) = self.latest_block_time {
+            let freshness = age(latest_block_time);
+            metrics
+                .update_gauge("slo_freshness_seconds", freshness.as_secs_f64())
+                .await?;
+            if let Some(freshness_budget) = &mut self.freshness {
+                freshness_budget.record(second, freshness, retention);
+            }
+        }
+
+        for budget in [&mut self.latency, &mut self.freshness]
+            .into_iter()
+            .flatten()
+        {
+            let burn_rates: Vec<(Duration, f64)> = self
+                .windows
+                .iter()
+                .map(|window| (*window, budget.burn_rate(second, *window)))
+                .collect();
+
+            for (window, burn_rate) in &burn_rates {
+                metrics
+                    .update_gauge(
+                        &format!(
+                            "slo_{}_burn_rate_{}s",
+                            budget.objective.name(),
+                            window.as_secs()
+                        ),
+                        *burn_rate,
+                    )
+                    .await?;
+            }
+
+            let firing = !burn_rates.is_empty()
+                && burn_rates
+                    .iter()
+                    .all(|(_, burn_rate)| *burn_rate >= self.alert_threshold);
+            metrics
+                .update_gauge(
+                    &format!("slo_{}_alert_firing", budget.objective.name()),
+                    if firing { 1.0 } else { 0.0 },
+                )
+                .await?;
+
+            if firing == budget.firing {
+                continue;
+            }
+            budget.firing = firing;
+
+            let alert = SloAlert {
+                objective: budget.objective,
+                threshold: budget.threshold,
+                target: budget.target,
+                burn_rates,
+                firing,
+            };
+            for alert_sink in &self.alert_sinks {
+                if let Err(error) = alert_sink.notify(&alert).await {
+                    log::error!("error notifying SLO alert: {:?}", error);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the time elapsed since a Unix timestamp, or zero for timestamps in
+/// the future.
+fn age(unix_timestamp: i64) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    now.saturating_sub(Duration::from_secs(unix_timestamp.max(0) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burn_rate() {
+        let retention = Duration::from_secs(3600);
+        let mut budget = ErrorBudget::new(SloObjective::Latency, Duration::from_secs(2), 0.99);
+
+        budget.record(0, Duration::from_secs(10), retention);
+        for second in 400..490 {
+            budget.record(second, Duration::from_secs(1), retention);
+        }
+        for second in 490..500 {
+            budget.record(second, Duration::from_secs(3), retention);
+        }
+
+        // 10 bad events out of 100 in the last 5 minutes, ten times the budget.
+        let short = budget.burn_rate(499, Duration::from_secs(300));
+        assert!((short - 10.0).abs() < 1e-9);
+
+        // The bad event of the first second counts over the last hour.
+        let long = budget.burn_rate(499, retention);
+        assert!((long - 11.0 / 101.0 / 0.01).abs() < 1e-9);
+
+        budget.record(4100, Duration::from_secs(1), retention);
+        assert_eq!(budget.burn_rate(4100, Duration::from_secs(300)), 0.0);
+        assert_eq!(budget.buckets.len(), 1);
+    }
+}
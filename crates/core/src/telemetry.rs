@@ -0,0 +1,185 @@
+//! Traces updates through the pipeline with `tracing` spans, and exports them
+//! to OpenTelemetry.
+//!
+//! The pipeline processes each update within an `update` span carrying its
+//! kind, its slot, and the signature of transactions or the pubkey of
+//! accounts. Decoding runs within this span, and each decoded account or
+//! instruction is handed to its processor within a child `process` span
+//! carrying the program id and the name of the decoder. Failed updates and
+//! processors mark their spans as errors, so slow processors and error hotspots
+//! can be found in APM tooling.
+//!
+//! # Overview
+//!
+//! - **`update_span`**: Creates the span the pipeline processes an update in.
+//! - **`init_opentelemetry`**: Installs a `tracing` subscriber exporting spans
+//!   to an OTLP collector, and printing events to the terminal. Requires the
+//!   `opentelemetry` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::telemetry;
+//!
+//! let _telemetry = telemetry::init_opentelemetry("pumpfun-indexer", "http://localhost:4317")?;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .instruction(PumpfunDecoder, PumpfunInstructionProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Spans are only recorded while a `tracing` subscriber is installed, and
+//!   otherwise cost a check of the global dispatcher.
+//! - `init_opentelemetry` forwards `log` records to `tracing`, so it replaces
+//!   loggers like `env_logger`. Records are filtered with `RUST_LOG`, at the
+//!   `info` level by default, and attached to the span they are logged in.
+//! - Spans are exported in batches, and the pending ones are flushed when the
+//!   returned `OpenTelemetryGuard` is dropped.
+
+use {
+    crate::datasource::Update,
+    solana_pubkey::Pubkey,
+    tracing::{field, Span},
+};
+
+/// Creates the span the pipeline processes `update` in.
+pub fn update_span(update: &Update) -> Span {
+    let span = tracing::info_span!(
+        "update",
+        kind = update_kind(update),
+        slot = update.slot(),
+        signature = field::Empty,
+        pubkey = field::Empty,
+        otel.status_code = field::Empty,
+    );
+    match update {
+        Update::Account(account_update) => {
+            span.record("pubkey", field::display(&account_update.pubkey));
+        }
+        Update::Transaction(transaction_update) => {
+            span.record("signature", field::display(&transaction_update.signature));
+        }
+        Update::AccountDeletion(account_deletion) => {
+            span.record("pubkey", field::display(&account_deletion.pubkey));
+        }
+        Update::BlockDetails(_) | Update::SlotStatus(_) => {}
+    }
+
+    span
+}
+
+/// Creates the span a processor handles the output of `decoder` in.
+pub(crate) fn process_span(program_id: &Pubkey, decoder: &str) -> Span {
+    tracing::info_span!(
+        "process",
+        program_id = %program_id,
+        decoder = decoder,
+        otel.status_code = field::Empty,
+    )
+}
+
+/// Marks a span as failed.
+pub(crate) fn record_error(span: &Span) {
+    span.record("otel.status_code", "ERROR");
+}
+
+fn update_kind(update: &Update) -> &'static str {
+    match update {
+        Update::Account(_) => "account",
+        Update::Transaction(_) => "transaction",
+        Update::AccountDeletion(_) => "account_deletion",
+        Update::BlockDetails(_) => "block_details",
+        Update::SlotStatus(_) => "slot_status",
+    }
+}
+
+#[cfg(feature = "opentelemetry")]
+pub use exporter::{init_opentelemetry, OpenTelemetryGuard};
+
+#[cfg(feature = "opentelemetry")]
+mod exporter {
+    use {
+        crate::error::{CarbonResult, Error},
+        opentelemetry::{trace::TracerProvider as _, KeyValue},
+        opentelemetry_otlp::WithExportConfig,
+        opentelemetry_sdk::{runtime, trace::TracerProvider, Resource},
+        tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter},
+    };
+
+    /// Flushes the spans pending export when dropped.
+    pub struct OpenTelemetryGuard {
+        provider: TracerProvider,
+    }
+
+    impl Drop for OpenTelemetryGuard {
+        fn drop(&mut self) {
+            if let Err(e) = self.provider.shutdown() {
+                log::error!("failed to flush OpenTelemetry spans: {:?}", e);
+            }
+        }
+    }
+
+    /// Installs a global `tracing` subscriber exporting spans over OTLP/gRPC
+    /// to the collector at `endpoint`, under the `service_name` resource.
+    ///
+    /// Must be called from within a Tokio runtime, which exports the batches
+    /// of spans.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the exporter can't be built, or if a `tracing`
+    /// subscriber or a `log` logger is already installed.
+    pub fn init_opentelemetry(
+        service_name: &str,
+        endpoint: &str,
+    ) -> CarbonResult<OpenTelemetryGuard> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| Error::Custom(format!("Failed to build OTLP exporter: {}", e)))?;
+
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new([KeyValue::new(
+                "service.name",
+                service_name.to_string(),
+            )]))
+            .build();
+
+        tracing_subscriber::registry()
+            .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+            .with(tracing_subscriber::fmt::layer())
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("carbon")))
+            .try_init()
+            .map_err(|e| Error::Custom(format!("Failed to install tracing subscriber: {}", e)))?;
+
+        Ok(OpenTelemetryGuard { provider })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::datasource::AccountDeletion};
+
+    #[test]
+    fn test_update_span_fields() {
+        tracing::subscriber::with_default(tracing_subscriber::registry(), || {
+            let span = update_span(&Update::AccountDeletion(AccountDeletion {
+                pubkey: Pubkey::new_unique(),
+                slot: 42,
+            }));
+
+            let metadata = span.metadata().expect("span is enabled");
+            assert_eq!(metadata.name(), "update");
+            for name in ["kind", "slot", "signature", "pubkey", "otel.status_code"] {
+                assert!(metadata.fields().field(name).is_some(), "missing {}", name);
+            }
+        });
+    }
+}
@@ -0,0 +1,498 @@
+//! Provides a small Handlebars-like templating engine to format decoded data
+//! into message bodies, such as Slack blocks or generic JSON payloads.
+//!
+//! Notification sinks need to turn decoded events into human-readable
+//! messages, and hardcoding the format of each message requires a rebuild for
+//! every wording change. The `template` module renders templates loaded at
+//! runtime against a JSON context, so message bodies can be configured per
+//! event type in a file.
+//!
+//! # Overview
+//!
+//! - **`Template`**: A text template supporting `{{path.to.field}}`
+//!   substitutions, `{{#if field}}...{{else}}...{{/if}}` conditionals and
+//!   `{{#each items}}...{{/each}}` loops, in which `{{this}}` and `{{@index}}`
+//!   refer to the current item and its position.
+//! - **`JsonTemplate`**: A JSON document whose string values are templates. A
+//!   string consisting of a single `{{path}}` expression is replaced by the
+//!   referenced value itself, preserving numbers, booleans and objects.
+//! - **`TemplateSet`**: The `JsonTemplate` of each event type, with an optional
+//!   `default` template for event types without one.
+//!
+//! # Example
+//!
+//! A template set file, keyed by event type:
+//!
+//! ```json
+//! {
+//!     "Buy": {
+//!         "text": "{{event.sol_amount}} lamports buy of {{event.token_amount}} tokens",
+//!         "blocks": [{
+//!             "type": "section",
+//!             "text": {
+//!                 "type": "mrkdwn",
+//!                 "text": "*Buy* in <https://solscan.io/tx/{{signature}}|{{signature}}>"
+//!             }
+//!         }]
+//!     },
+//!     "default": { "event_type": "{{event_type}}", "data": "{{event}}" }
+//! }
+//! ```
+//!
+//! ```ignore
+//! use carbon_core::template::TemplateSet;
+//!
+//! let templates = TemplateSet::from_file("templates.json")?;
+//! let body = templates.render("Buy", &context);
+//! ```
+//!
+//! # Notes
+//!
+//! - Missing fields render as an empty string, or as `null` for single
+//!   expression JSON strings.
+//! - Inside an `{{#each}}` block, paths are looked up on the current item
+//!   first, then on the enclosing contexts.
+//! - `false`, `null`, `0`, empty strings, arrays and objects are falsy in
+//!   `{{#if}}` blocks.
+
+use {
+    crate::error::{CarbonResult, Error},
+    serde_json::{Map, Value},
+    std::{collections::HashMap, fs, path::Path},
+};
+
+/// The key of the template used for event types without their own template.
+pub const DEFAULT_TEMPLATE: &str = "default";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Expression(String),
+    If {
+        path: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        path: String,
+        body: Vec<Node>,
+    },
+}
+
+enum Block {
+    Root,
+    If(String),
+    Each(String),
+}
+
+struct Frame {
+    block: Block,
+    nodes: Vec<Node>,
+    otherwise: Option<Vec<Node>>,
+}
+
+impl Frame {
+    fn new(block: Block) -> Self {
+        Self {
+            block,
+            nodes: Vec::new(),
+            otherwise: None,
+        }
+    }
+
+    fn push(&mut self, node: Node) {
+        match &mut self.otherwise {
+            Some(otherwise) => otherwise.push(node),
+            None => self.nodes.push(node),
+        }
+    }
+}
+
+/// A compiled text template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Compiles a template, failing on unterminated tags and unbalanced
+    /// blocks.
+    pub fn parse(source: &str) -> CarbonResult<Self> {
+        let mut stack = vec![Frame::new(Block::Root)];
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                current(&mut stack).push(Node::Text(rest[..start].to_string()));
+            }
+
+            let after_open = &rest[start + 2..];
+            let end = after_open.find("}}").ok_or_else(|| {
+                Error::Custom(format!("Unterminated template tag in \"{source}\""))
+            })?;
+            let tag = after_open[..end].trim();
+            rest = &after_open[end + 2..];
+
+            if let Some(path) = tag.strip_prefix("#if ") {
+                stack.push(Frame::new(Block::If(path.trim().to_string())));
+            } else if let Some(path) = tag.strip_prefix("#each ") {
+                stack.push(Frame::new(Block::Each(path.trim().to_string())));
+            } else if tag == "else" {
+                let frame = current(&mut stack);
+                if !matches!(frame.block, Block::If(_)) || frame.otherwise.is_some() {
+                    return Err(Error::Custom(format!(
+                        "Unexpected {{{{else}}}} in template \"{source}\""
+                    )));
+                }
+                frame.otherwise = Some(Vec::new());
+            } else if let Some(closed) = tag.strip_prefix('/') {
+                let frame = stack.pop().expect("the root frame is never popped");
+                let node = match (frame.block, closed.trim()) {
+                    (Block::If(path), "if") => Node::If {
+                        path,
+                        then: frame.nodes,
+                        otherwise: frame.otherwise.unwrap_or_default(),
+                    },
+                    (Block::Each(path), "each") => Node::Each {
+                        path,
+                        body: frame.nodes,
+                    },
+                    _ => {
+                        return Err(Error::Custom(format!(
+                            "Unexpected {{{{/{closed}}}}} in template \"{source}\""
+                        )))
+                    }
+                };
+                if stack.is_empty() {
+                    return Err(Error::Custom(format!(
+                        "Unexpected {{{{/{closed}}}}} in template \"{source}\""
+                    )));
+                }
+                current(&mut stack).push(node);
+            } else {
+                current(&mut stack).push(Node::Expression(tag.to_string()));
+            }
+        }
+
+        if !rest.is_empty() {
+            current(&mut stack).push(Node::Text(rest.to_string()));
+        }
+
+        if stack.len() != 1 {
+            return Err(Error::Custom(format!(
+                "Unclosed block in template \"{source}\""
+            )));
+        }
+
+        let root = stack.pop().expect("the root frame is never popped");
+        Ok(Self { nodes: root.nodes })
+    }
+
+    /// Renders the template against `context`.
+    pub fn render(&self, context: &Value) -> String {
+        let mut output = String::new();
+        render_nodes(&self.nodes, &Scope::root(context), &mut output);
+        output
+    }
+
+    /// Returns the path of the template if it consists of a single
+    /// expression.
+    fn single_expression(&self) -> Option<&str> {
+        match self.nodes.as_slice() {
+            [Node::Expression(path)] => Some(path),
+            _ => None,
+        }
+    }
+}
+
+fn current(stack: &mut [Frame]) -> &mut Frame {
+    stack.last_mut().expect("the root frame is never popped")
+}
+
+/// The values visible while rendering, innermost last.
+struct Scope<'a> {
+    values: Vec<&'a Value>,
+    index: Option<usize>,
+}
+
+impl<'a> Scope<'a> {
+    fn root(context: &'a Value) -> Self {
+        Self {
+            values: vec![context],
+            index: None,
+        }
+    }
+
+    fn with_item(&self, item: &'a Value, index: usize) -> Self {
+        let mut values = self.values.clone();
+        values.push(item);
+        Self {
+            values,
+            index: Some(index),
+        }
+    }
+
+    fn lookup(&self, path: &str) -> Option<Value> {
+        if path == "@index" {
+            return self.index.map(Value::from);
+        }
+
+        let innermost = self.values.last().copied()?;
+        if path == "this" {
+            return Some(innermost.clone());
+        }
+        if let Some(path) = path.strip_prefix("this.") {
+            return resolve(innermost, path).cloned();
+        }
+
+        self.values
+            .iter()
+            .rev()
+            .find_map(|value| resolve(value, path))
+            .cloned()
+    }
+}
+
+/// Resolves a dotted path such as `reserves.base`, `fees.1` or `fees[1]`.
+fn resolve<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.replace('[', ".")
+        .replace(']', "")
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(value, |value, segment| match value {
+            Value::Object(fields) => fields.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+        Value::String(string) => !string.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+fn render_nodes(nodes: &[Node], scope: &Scope, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Expression(path) => match scope.lookup(path) {
+                Some(Value::String(string)) => output.push_str(&string),
+                Some(Value::Null) | None => {}
+                Some(value) => output.push_str(&value.to_string()),
+            },
+            Node::If {
+                path,
+                then,
+                otherwise,
+            } => {
+                if scope.lookup(path).as_ref().is_some_and(is_truthy) {
+                    render_nodes(then, scope, output);
+                } else {
+                    render_nodes(otherwise, scope, output);
+                }
+            }
+            Node::Each { path, body } => {
+                let Some(Value::Array(items)) = scope.lookup(path) else {
+                    continue;
+                };
+                for (index, item) in items.iter().enumerate() {
+                    render_nodes(body, &scope.with_item(item, index), output);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum JsonNode {
+    Value(Value),
+    Expression(String),
+    Text(Template),
+    Array(Vec<JsonNode>),
+    Object(Vec<(String, JsonNode)>),
+}
+
+/// A JSON document whose string values are templates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonTemplate {
+    root: JsonNode,
+}
+
+impl JsonTemplate {
+    /// Compiles every string value of `document` as a template.
+    pub fn parse(document: &Value) -> CarbonResult<Self> {
+        Ok(Self {
+            root: parse_json_node(document)?,
+        })
+    }
+
+    /// Renders the document against `context`.
+    pub fn render(&self, context: &Value) -> Value {
+        render_json_node(&self.root, &Scope::root(context))
+    }
+}
+
+fn parse_json_node(value: &Value) -> CarbonResult<JsonNode> {
+    Ok(match value {
+        Value::String(source) if source.contains("{{") => {
+            let template = Template::parse(source)?;
+            match template.single_expression() {
+                Some(path) => JsonNode::Expression(path.to_string()),
+                None => JsonNode::Text(template),
+            }
+        }
+        Value::Array(items) => JsonNode::Array(
+            items
+                .iter()
+                .map(parse_json_node)
+                .collect::<CarbonResult<_>>()?,
+        ),
+        Value::Object(fields) => JsonNode::Object(
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), parse_json_node(value)?)))
+                .collect::<CarbonResult<_>>()?,
+        ),
+        value => JsonNode::Value(value.clone()),
+    })
+}
+
+fn render_json_node(node: &JsonNode, scope: &Scope) -> Value {
+    match node {
+        JsonNode::Value(value) => value.clone(),
+        JsonNode::Expression(path) => scope.lookup(path).unwrap_or(Value::Null),
+        JsonNode::Text(template) => {
+            let mut output = String::new();
+            render_nodes(&template.nodes, scope, &mut output);
+            Value::String(output)
+        }
+        JsonNode::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_json_node(item, scope))
+                .collect(),
+        ),
+        JsonNode::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render_json_node(value, scope)))
+                .collect::<Map<_, _>>(),
+        ),
+    }
+}
+
+/// The message templates of each event type.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSet {
+    templates: HashMap<String, JsonTemplate>,
+    default: Option<JsonTemplate>,
+}
+
+impl TemplateSet {
+    /// Compiles a template set from a JSON object keyed by event type. The
+    /// `default` key holds the template of event types without their own.
+    pub fn from_value(value: &Value) -> CarbonResult<Self> {
+        let Value::Object(templates) = value else {
+            return Err(Error::Custom(
+                "A template set must be a JSON object keyed by event type".to_string(),
+            ));
+        };
+
+        let mut template_set = Self::default();
+        for (event_type, document) in templates {
+            let template = JsonTemplate::parse(document)?;
+            if event_type == DEFAULT_TEMPLATE {
+                template_set.default = Some(template);
+            } else {
+                template_set.templates.insert(event_type.clone(), template);
+            }
+        }
+
+        Ok(template_set)
+    }
+
+    /// Reads and compiles a template set from a JSON file.
+    pub fn from_file(path: impl AsRef<Path>) -> CarbonResult<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|error| {
+            Error::Custom(format!(
+                "Failed to read templates {}: {error}",
+                path.display()
+            ))
+        })?;
+        let value: Value = serde_json::from_str(&content).map_err(|error| {
+            Error::Custom(format!(
+                "Failed to parse templates {}: {error}",
+                path.display()
+            ))
+        })?;
+
+        Self::from_value(&value)
+    }
+
+    /// Sets the template of an event type.
+    pub fn insert(&mut self, event_type: impl Into<String>, template: JsonTemplate) {
+        self.templates.insert(event_type.into(), template);
+    }
+
+    /// Renders the template of `event_type`, falling back to the default
+    /// template. Returns `None` if neither exists.
+    pub fn render(&self, event_type: &str, context: &Value) -> Option<Value> {
+        self.templates
+            .get(event_type)
+            .or(self.default.as_ref())
+            .map(|template| template.render(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn test_render_blocks_and_paths() {
+        let template = Template::parse(
+            "{{#if event.is_buy}}Buy{{else}}Sell{{/if}} of {{event.amounts[0]}}:\
+             {{#each event.legs}} {{@index}}={{mint}}/{{signature}}{{/each}}",
+        )
+        .unwrap();
+
+        let context = json!({
+            "signature": "sig",
+            "event": {
+                "is_buy": false,
+                "amounts": [42, 7],
+                "legs": [{ "mint": "a" }, { "mint": "b" }],
+            },
+        });
+
+        assert_eq!(template.render(&context), "Sell of 42: 0=a/sig 1=b/sig");
+        assert!(Template::parse("{{#if a}}unclosed").is_err());
+        assert!(Template::parse("{{#each a}}{{/if}}").is_err());
+    }
+
+    #[test]
+    fn test_template_set_preserves_single_expression_values() {
+        let templates = TemplateSet::from_value(&json!({
+            "Swap": { "text": "Swap {{event.amount}}", "amount": "{{event.amount}}" },
+            "default": { "type": "{{event_type}}" },
+        }))
+        .unwrap();
+
+        let context = json!({ "event_type": "Swap", "event": { "amount": 5 } });
+        assert_eq!(
+            templates.render("Swap", &context),
+            Some(json!({ "text": "Swap 5", "amount": 5 }))
+        );
+        assert_eq!(
+            templates.render("Deposit", &json!({ "event_type": "Deposit" })),
+            Some(json!({ "type": "Deposit" }))
+        );
+    }
+}
@@ -8,6 +8,9 @@
 //! - **TransactionPipe**: Represents a processing pipe for transactions, with
 //!   functionality to parse and match instructions against a schema and handle
 //!   matched data with a specified processor.
+//! - **TransactionInstructionsPipe**: Delivers every instruction of a
+//!   transaction decoded by a single decoder in one call, to be matched against
+//!   instruction patterns with `match_instruction_pattern`.
 //! - **TransactionMetadata**: Metadata associated with a transaction, including
 //!   slot, signature, and fee payer information.
 //! - **ParsedTransaction**: Represents a transaction with its metadata and
@@ -33,8 +36,11 @@ use solana_program::hash::Hash;
 use {
     crate::{
         collection::InstructionDecoderCollection,
+        compute_budget::ComputeBudget,
         error::CarbonResult,
-        instruction::{DecodedInstruction, InstructionMetadata, NestedInstruction},
+        instruction::{
+            DecodedInstruction, InstructionDecoder, InstructionMetadata, NestedInstruction,
+        },
         metrics::MetricsCollection,
         processor::Processor,
         schema::{ParsedInstruction, TransactionSchema},
@@ -61,6 +67,11 @@ use {
 /// - `message`: The versioned message containing the transaction instructions
 ///   and account keys
 /// - `block_time`: The Unix timestamp of when the transaction was processed.
+/// - `compute_budget`: The compute unit limit and price set by the
+///   transaction's ComputeBudget instructions
+/// - `fee`: The fee charged for the transaction, in lamports
+/// - `compute_units_consumed`: The compute units consumed by the transaction,
+///   if reported in its status metadata
 ///
 /// Note: The `block_time` field may not be returned in all scenarios.
 #[derive(Debug, Clone)]
@@ -72,6 +83,9 @@ pub struct TransactionMetadata {
     pub message: solana_program::message::VersionedMessage,
     pub block_time: Option<i64>,
     pub block_hash: Option<Hash>,
+    pub compute_budget: ComputeBudget,
+    pub fee: u64,
+    pub compute_units_consumed: Option<u64>,
 }
 
 impl Default for TransactionMetadata {
@@ -84,9 +98,93 @@ impl Default for TransactionMetadata {
             message: solana_message::VersionedMessage::Legacy(solana_message::Message::default()),
             block_time: None,
             block_hash: None,
+            compute_budget: ComputeBudget::default(),
+            fee: 0,
+            compute_units_consumed: None,
         }
     }
 }
+
+impl TransactionMetadata {
+    /// Returns the account keys of the transaction, followed by the writable
+    /// and readonly addresses loaded from address lookup tables, in the order
+    /// instruction account indexes refer to them.
+    pub fn account_keys(&self) -> Vec<Pubkey> {
+        let loaded_addresses = &self.meta.loaded_addresses;
+
+        self.message
+            .static_account_keys()
+            .iter()
+            .chain(&loaded_addresses.writable)
+            .chain(&loaded_addresses.readonly)
+            .copied()
+            .collect()
+    }
+
+    /// Returns the balance of a token account before and after the
+    /// transaction, or `None` if the transaction metadata doesn't record it.
+    ///
+    /// Accounts created by the transaction have a balance of zero before it,
+    /// and accounts closed by it a balance of zero after it.
+    pub fn token_balance_change(&self, token_account: &Pubkey) -> Option<TokenBalanceChange> {
+        let account_index = self
+            .account_keys()
+            .iter()
+            .position(|key| key == token_account)?;
+        let find = |balances: &Option<Vec<solana_transaction_status::TransactionTokenBalance>>| {
+            balances.as_ref().and_then(|balances| {
+                balances
+                    .iter()
+                    .find(|balance| balance.account_index as usize == account_index)
+            })
+        };
+
+        let pre = find(&self.meta.pre_token_balances);
+        let post = find(&self.meta.post_token_balances);
+        let balance = post.or(pre)?;
+        let amount = |balance: Option<&solana_transaction_status::TransactionTokenBalance>| {
+            balance.map_or(Some(0), |balance| {
+                balance.ui_token_amount.amount.parse::<u64>().ok()
+            })
+        };
+
+        Some(TokenBalanceChange {
+            mint: balance.mint.parse().ok()?,
+            owner: balance.owner.parse().ok(),
+            decimals: balance.ui_token_amount.decimals,
+            pre_amount: amount(pre)?,
+            post_amount: amount(post)?,
+        })
+    }
+}
+
+/// The balance of a token account before and after a transaction, in base
+/// units of its mint.
+///
+/// `owner` is the wallet owning the account, if the transaction metadata
+/// records it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBalanceChange {
+    pub mint: Pubkey,
+    pub owner: Option<Pubkey>,
+    pub decimals: u8,
+    pub pre_amount: u64,
+    pub post_amount: u64,
+}
+
+impl TokenBalanceChange {
+    /// Returns the amount received by the account, or zero if its balance
+    /// decreased.
+    pub fn increase(&self) -> u64 {
+        self.post_amount.saturating_sub(self.pre_amount)
+    }
+
+    /// Returns the amount sent by the account, or zero if its balance
+    /// increased.
+    pub fn decrease(&self) -> u64 {
+        self.pre_amount.saturating_sub(self.post_amount)
+    }
+}
 /// Tries convert transaction update into the metadata.
 ///
 /// This function retrieves core metadata such as the transaction's slot,
@@ -120,6 +218,9 @@ impl TryFrom<crate::datasource::TransactionUpdate> for TransactionMetadata {
             fee_payer: *accounts
                 .first()
                 .ok_or(crate::error::Error::MissingFeePayer)?,
+            compute_budget: ComputeBudget::from_message(&value.transaction.message),
+            fee: value.meta.fee,
+            compute_units_consumed: value.meta.compute_units_consumed,
             meta: value.meta.clone(),
             message: value.transaction.message.clone(),
             block_time: value.block_time,
@@ -322,3 +423,174 @@ where
         Ok(())
     }
 }
+
+/// The input type for the transaction instructions processor.
+///
+/// - `T`: The instruction type, as determined by the decoder.
+pub type TransactionInstructionsInputType<T> = (
+    Arc<TransactionMetadata>,
+    Vec<(InstructionMetadata, DecodedInstruction<T>)>,
+);
+
+/// A pipe delivering every instruction of a transaction decoded by a single
+/// decoder as one unit.
+///
+/// Unlike `InstructionPipe`, which processes one instruction at a time, the
+/// `TransactionInstructionsPipe` collects all outer and inner instructions
+/// decoded in a transaction, in execution order, before running the processor.
+/// This makes cross-instruction logic, such as matching a swap with its
+/// transfer legs, straightforward. Transactions without any decoded
+/// instruction are skipped.
+///
+/// ## Generics
+///
+/// - `T`: The instruction type, as determined by the decoder.
+pub struct TransactionInstructionsPipe<T: Send> {
+    pub decoder:
+        Box<dyn for<'a> InstructionDecoder<'a, InstructionType = T> + Send + Sync + 'static>,
+    pub processor:
+        Box<dyn Processor<InputType = TransactionInstructionsInputType<T>> + Send + Sync>,
+}
+
+impl<T: Send> TransactionInstructionsPipe<T> {
+    fn decode_nested(
+        &self,
+        instructions: &[NestedInstruction],
+        decoded: &mut Vec<(InstructionMetadata, DecodedInstruction<T>)>,
+    ) {
+        for nested_instruction in instructions {
+            if let Some(decoded_instruction) = self
+                .decoder
+                .decode_instruction(&nested_instruction.instruction)
+            {
+                decoded.push((nested_instruction.metadata.clone(), decoded_instruction));
+            }
+
+            self.decode_nested(&nested_instruction.inner_instructions, decoded);
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Send + 'static> TransactionPipes<'_> for TransactionInstructionsPipe<T> {
+    async fn run(
+        &mut self,
+        transaction_metadata: Arc<TransactionMetadata>,
+        instructions: &[NestedInstruction],
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        log::trace!(
+            "TransactionInstructionsPipe::run(instructions: {:?}, metrics)",
+            instructions,
+        );
+
+        let mut decoded_instructions = Vec::new();
+        self.decode_nested(instructions, &mut decoded_instructions);
+
+        if decoded_instructions.is_empty() {
+            return Ok(());
+        }
+
+        self.processor
+            .process((transaction_metadata, decoded_instructions), metrics)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Matches decoded instructions against an ordered pattern of predicates.
+///
+/// Each predicate must match an instruction following the instruction matched
+/// by the previous predicate; unrelated instructions in between are skipped.
+/// The first matching instruction is taken for every predicate.
+///
+/// # Parameters
+///
+/// - `instructions`: The decoded instructions of a transaction, in execution
+///   order, as delivered by `TransactionInstructionsPipe` or `TransactionPipe`.
+/// - `pattern`: The predicates describing the expected instructions.
+///
+/// # Returns
+///
+/// The matched instructions in pattern order, or `None` if the pattern could
+/// not be matched.
+///
+/// # Example
+///
+/// ```ignore
+/// use carbon_core::transaction::match_instruction_pattern;
+///
+/// let Some([swap, transfer_in, transfer_out]) = match_instruction_pattern(
+///     &instructions,
+///     &[
+///         &|ix| matches!(ix.data, MyInstruction::Swap(_)),
+///         &|ix| matches!(ix.data, MyInstruction::Transfer(_)),
+///         &|ix| matches!(ix.data, MyInstruction::Transfer(_)),
+///     ],
+/// )
+/// .and_then(|matched| <[_; 3]>::try_from(matched).ok()) else {
+///     return Ok(());
+/// };
+/// ```
+pub fn match_instruction_pattern<'a, T>(
+    instructions: &'a [(InstructionMetadata, DecodedInstruction<T>)],
+    pattern: &[&dyn Fn(&DecodedInstruction<T>) -> bool],
+) -> Option<Vec<&'a (InstructionMetadata, DecodedInstruction<T>)>> {
+    let mut remaining = instructions.iter();
+
+    pattern
+        .iter()
+        .map(|predicate| remaining.find(|(_, instruction)| predicate(instruction)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instructions(data: &[u8]) -> Vec<(InstructionMetadata, DecodedInstruction<u8>)> {
+        data.iter()
+            .enumerate()
+            .map(|(index, data)| {
+                (
+                    InstructionMetadata {
+                        transaction_metadata: Arc::new(TransactionMetadata::default()),
+                        stack_height: 1,
+                        index: index as u32,
+                        absolute_path: vec![index as u8],
+                    },
+                    DecodedInstruction {
+                        program_id: Pubkey::default(),
+                        data: *data,
+                        accounts: vec![],
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_match_instruction_pattern_skips_unrelated_instructions() {
+        let instructions = instructions(&[7, 1, 9, 2, 2]);
+
+        let matched = match_instruction_pattern(
+            &instructions,
+            &[&|ix| ix.data == 1, &|ix| ix.data == 2, &|ix| ix.data == 2],
+        )
+        .expect("pattern matches");
+
+        assert_eq!(
+            matched
+                .iter()
+                .map(|(metadata, _)| metadata.index)
+                .collect::<Vec<_>>(),
+            [1, 3, 4]
+        );
+        assert!(match_instruction_pattern(
+            &instructions,
+            &[&|ix| ix.data == 2, &|ix| ix.data == 1]
+        )
+        .is_none());
+    }
+}
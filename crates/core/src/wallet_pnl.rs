@@ -0,0 +1,538 @@
+//! Maintains the positions and the realized and unrealized PnL of a set of
+//! wallets, from their DEX trades and token transfers.
+//!
+//! Tracking the PnL of a wallet combines several streams: the swaps of every
+//! venue it trades on, the transfers moving tokens in and out of it, and the
+//! prices valuing both. A `WalletPnl` consumes the unified stream of a
+//! `DexEventStream` and the transfers extracted from the token program,
+//! values them with a `PriceCache`, and exports the updated positions of the
+//! configured wallets to `PositionSink`s.
+//!
+//! # Overview
+//!
+//! - **`WalletPnl`**: A cloneable handle to the positions of the tracked
+//!   wallets. It is the `Processor` of a `DexEventStream`, and creates the
+//!   processor of transfers with `WalletPnl::transfers`.
+//! - **`TokenTransfer`**: A transfer of tokens between two wallets, with the
+//!   transaction it was executed in.
+//! - **`Position`**: The holdings of a wallet in a mint, with their cost basis
+//!   and PnL in USD.
+//! - **`PositionSink`**: Receives the positions updated by each trade or
+//!   transfer. `LogPositionSink` logs them.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_core::{
+//!     dex_trade::DexEventStream,
+//!     wallet_pnl::{LogPositionSink, TokenTransfer, WalletPnl},
+//! };
+//!
+//! let pnl = WalletPnl::new([WALLET], prices.clone()).sink(LogPositionSink);
+//! let dex_events = DexEventStream::new(pnl.clone());
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .account_with_prices(PythDecoder, OracleProcessor, prices, extract_price)
+//!     .instruction(RaydiumAmmV4Decoder, dex_events.venue())
+//!     .instruction(OrcaWhirlpoolDecoder, dex_events.venue())
+//!     .instruction(
+//!         TokenProgramDecoder,
+//!         pnl.transfers(|instruction, transaction_metadata| {
+//!             match &instruction.data {
+//!                 TokenProgramInstruction::TransferChecked(transfer) => {
+//!                     TokenTransfer::from_token_accounts(
+//!                         &instruction.accounts[0].pubkey,
+//!                         &instruction.accounts[2].pubkey,
+//!                         transfer.amount,
+//!                         transaction_metadata,
+//!                     )
+//!                 }
+//!                 _ => None,
+//!             }
+//!         }),
+//!     )
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Cost bases use the average cost method: selling or sending tokens removes
+//!   their share of the cost basis of the position.
+//! - Trades are valued with the price of the tokens received, or of the tokens
+//!   sent if the former is missing. Trades and transfers of tokens without a
+//!   price move the amounts held without changing cost bases or PnL, and are
+//!   counted by the `wallet_pnl_unpriced_events` counter.
+//! - Tokens received by transfer are valued at their price when received, so
+//!   they carry no PnL until the price moves. Transfers between two tracked
+//!   wallets carry the cost basis over instead.
+//! - Tokens sold beyond the amount held, such as tokens acquired before the
+//!   pipeline started, realize no PnL.
+//! - Unrealized PnL is computed with the latest price of the cache when a
+//!   position is exported, and is `None` while the price is missing.
+
+use {
+    crate::{
+        dex_trade::{DexEvent, NormalizedTrade},
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionProcessorInputType},
+        metrics::MetricsCollection,
+        price_cache::PriceCache,
+        processor::Processor,
+        transaction::TransactionMetadata,
+    },
+    async_trait::async_trait,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    },
+    tokio::sync::Mutex,
+};
+
+/// A transfer of tokens between two wallets, with the transaction it was
+/// executed in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenTransfer {
+    pub signature: Signature,
+    pub slot: u64,
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+impl TokenTransfer {
+    /// Captures a transfer between two token accounts, attributed to the
+    /// wallets owning them.
+    ///
+    /// Returns `None` if the transaction metadata lacks the balance or the
+    /// owner of either account.
+    pub fn from_token_accounts(
+        source: &Pubkey,
+        destination: &Pubkey,
+        amount: u64,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<Self> {
+        let source = transaction_metadata.token_balance_change(source)?;
+        let destination = transaction_metadata.token_balance_change(destination)?;
+
+        Some(Self {
+            signature: transaction_metadata.signature,
+            slot: transaction_metadata.slot,
+            mint: source.mint,
+            source: source.owner?,
+            destination: destination.owner?,
+            amount,
+        })
+    }
+}
+
+/// The holdings of a wallet in a mint.
+///
+/// # Fields
+///
+/// - `amount`: The amount held, in base units of the mint.
+/// - `cost_basis`: The USD value paid for the amount held.
+/// - `realized_pnl`: The USD gains of the tokens sold, net of their cost.
+/// - `unrealized_pnl`: The USD value of the amount held net of its cost basis,
+///   or `None` if the mint has no price.
+/// - `slot`: The slot of the latest trade or transfer of the position.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Position {
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub cost_basis: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: Option<f64>,
+    pub slot: u64,
+}
+
+/// Receives the positions updated by the trades and transfers of the tracked
+/// wallets, such as to store them in a database.
+#[async_trait]
+pub trait PositionSink: Send + Sync {
+    async fn export(&self, position: &Position) -> CarbonResult<()>;
+}
+
+/// Logs updated positions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogPositionSink;
+
+#[async_trait]
+impl PositionSink for LogPositionSink {
+    async fn export(&self, position: &Position) -> CarbonResult<()> {
+        log::info!(
+            "{} holds {} of {}: realized PnL {:.2} USD, unrealized PnL {:?} USD",
+            position.wallet,
+            position.amount,
+            position.mint,
+            position.realized_pnl,
+            position.unrealized_pnl,
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Holding {
+    amount: u64,
+    cost_basis: f64,
+    realized_pnl: f64,
+    slot: u64,
+}
+
+impl Holding {
+    /// Adds tokens acquired for `cost`.
+    fn acquire(&mut self, amount: u64, cost: Option<f64>, slot: u64) {
+        self.amount = self.amount.saturating_add(amount);
+        self.cost_basis += cost.unwrap_or_default();
+        self.slot = slot;
+    }
+
+    /// Removes tokens, returning the cost basis of the amount held among
+    /// them and the share of `amount` it covers.
+    fn release(&mut self, amount: u64, slot: u64) -> (f64, f64) {
+        self.slot = slot;
+        if amount == 0 || self.amount == 0 {
+            return (0.0, 0.0);
+        }
+
+        let released = amount.min(self.amount);
+        let cost = self.cost_basis * released as f64 / self.amount as f64;
+        self.amount -= released;
+        self.cost_basis = if self.amount == 0 {
+            0.0
+        } else {
+            self.cost_basis - cost
+        };
+
+        (cost, released as f64 / amount as f64)
+    }
+}
+
+/// The holdings of the tracked wallets, updated with values computed by the
+/// caller so the accounting doesn't depend on the price cache.
+struct PnlState {
+    wallets: HashSet<Pubkey>,
+    holdings: HashMap<(Pubkey, Pubkey), Holding>,
+}
+
+impl PnlState {
+    fn holding(&mut self, wallet: Pubkey, mint: Pubkey) -> &mut Holding {
+        self.holdings.entry((wallet, mint)).or_default()
+    }
+
+    /// Applies a trade valued at `value`, returning the positions it updated.
+    fn apply_trade(
+        &mut self,
+        trade: &NormalizedTrade,
+        value: Option<f64>,
+    ) -> Vec<(Pubkey, Pubkey)> {
+        if !self.wallets.contains(&trade.trader) {
+            return Vec::new();
+        }
+
+        let sold = self.holding(trade.trader, trade.mint_in);
+        let (cost, share) = sold.release(trade.amount_in, trade.slot);
+        if let Some(value) = value {
+            sold.realized_pnl += value * share - cost;
+        }
+        self.holding(trade.trader, trade.mint_out)
+            .acquire(trade.amount_out, value, trade.slot);
+
+        vec![
+            (trade.trader, trade.mint_in),
+            (trade.trader, trade.mint_out),
+        ]
+    }
+
+    /// Applies a transfer valued at `value`, returning the positions it
+    /// updated.
+    fn apply_transfer(
+        &mut self,
+        transfer: &TokenTransfer,
+        value: Option<f64>,
+    ) -> Vec<(Pubkey, Pubkey)> {
+        let mut updated = Vec::new();
+        let mut cost = value;
+
+        if self.wallets.contains(&transfer.source) {
+            let (released_cost, share) = self
+                .holding(transfer.source, transfer.mint)
+                .release(transfer.amount, transfer.slot);
+            if share == 1.0 {
+                cost = Some(released_cost);
+            }
+            updated.push((transfer.source, transfer.mint));
+        }
+        if self.wallets.contains(&transfer.destination) {
+            self.holding(transfer.destination, transfer.mint).acquire(
+                transfer.amount,
+                cost,
+                transfer.slot,
+            );
+            updated.push((transfer.destination, transfer.mint));
+        }
+
+        updated
+    }
+}
+
+/// A cloneable handle to the positions of a set of wallets.
+///
+/// Clones share the same positions, so a handle can be given to the DEX event
+/// stream, to the transfer pipe and to any processor reading positions.
+#[derive(Clone)]
+pub struct WalletPnl {
+    state: Arc<Mutex<PnlState>>,
+    prices: PriceCache,
+    sinks: Vec<Arc<dyn PositionSink>>,
+}
+
+impl WalletPnl {
+    /// Creates a tracker of the positions of `wallets`, valued with `prices`.
+    pub fn new(wallets: impl IntoIterator<Item = Pubkey>, prices: PriceCache) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(PnlState {
+                wallets: wallets.into_iter().collect(),
+                holdings: HashMap::new(),
+            })),
+            prices,
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Adds a sink receiving the positions updated by each trade or transfer.
+    pub fn sink(mut self, sink: impl PositionSink + 'static) -> Self {
+        self.sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// Starts tracking a wallet.
+    pub async fn add_wallet(&self, wallet: Pubkey) {
+        self.state.lock().await.wallets.insert(wallet);
+    }
+
+    /// Returns the positions of a wallet, with their unrealized PnL at the
+    /// latest prices.
+    pub async fn positions(&self, wallet: &Pubkey) -> Vec<Position> {
+        let holdings: Vec<_> = self
+            .state
+            .lock()
+            .await
+            .holdings
+            .iter()
+            .filter(|((holder, _), _)| holder == wallet)
+            .map(|((_, mint), holding)| (*mint, *holding))
+            .collect();
+
+        let mut positions = Vec::with_capacity(holdings.len());
+        for (mint, holding) in holdings {
+            positions.push(self.position(*wallet, mint, holding).await);
+        }
+        positions
+    }
+
+    async fn position(&self, wallet: Pubkey, mint: Pubkey, holding: Holding) -> Position {
+        let market_value = self.prices.usd_value(&mint, holding.amount).await;
+
+        Position {
+            wallet,
+            mint,
+            amount: holding.amount,
+            cost_basis: holding.cost_basis,
+            realized_pnl: holding.realized_pnl,
+            unrealized_pnl: market_value.map(|value| value - holding.cost_basis),
+            slot: holding.slot,
+        }
+    }
+
+    /// Updates the positions of the trader of a trade.
+    pub async fn record_trade(
+        &self,
+        trade: &NormalizedTrade,
+        metrics: &MetricsCollection,
+    ) -> CarbonResult<()> {
+        let value = match self
+            .prices
+            .usd_value(&trade.mint_out, trade.amount_out)
+            .await
+        {
+            Some(value) => Some(value),
+            None => self.prices.usd_value(&trade.mint_in, trade.amount_in).await,
+        };
+
+        let updated = self.state.lock().await.apply_trade(trade, value);
+        if updated.is_empty() {
+            return Ok(());
+        }
+
+        metrics.increment_counter("wallet_pnl_trades", 1).await?;
+        self.export(updated, value, metrics).await
+    }
+
+    /// Updates the positions of the wallets sending and receiving a transfer.
+    pub async fn record_transfer(
+        &self,
+        transfer: &TokenTransfer,
+        metrics: &MetricsCollection,
+    ) -> CarbonResult<()> {
+        let value = self.prices.usd_value(&transfer.mint, transfer.amount).await;
+
+        let updated = self.state.lock().await.apply_transfer(transfer, value);
+        if updated.is_empty() {
+            return Ok(());
+        }
+
+        metrics.increment_counter("wallet_pnl_transfers", 1).await?;
+        self.export(updated, value, metrics).await
+    }
+
+    async fn export(
+        &self,
+        updated: Vec<(Pubkey, Pubkey)>,
+        value: Option<f64>,
+        metrics: &MetricsCollection,
+    ) -> CarbonResult<()> {
+        if value.is_none() {
+            metrics
+                .increment_counter("wallet_pnl_unpriced_events", 1)
+                .await?;
+        }
+
+        for (wallet, mint) in updated {
+            let holding = *self.state.lock().await.holding(wallet, mint);
+            let position = self.position(wallet, mint, holding).await;
+            for sink in &self.sinks {
+                sink.export(&position).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a processor recording the transfers extracted from decoded
+    /// instructions, such as the transfers of the token program.
+    ///
+    /// # Parameters
+    ///
+    /// - `extract`: Extracts the transfer executed by an instruction, or
+    ///   returns `None` for instructions that aren't transfers.
+    pub fn transfers<T>(
+        &self,
+        extract: impl Fn(&DecodedInstruction<T>, &TransactionMetadata) -> Option<TokenTransfer>
+            + Send
+            + Sync
+            + 'static,
+    ) -> TransferProcessor<T> {
+        TransferProcessor {
+            pnl: self.clone(),
+            extract: Box::new(extract),
+        }
+    }
+}
+
+#[async_trait]
+impl Processor for WalletPnl {
+    type InputType = DexEvent;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        match data {
+            DexEvent::Trade(trade) => self.record_trade(&trade, &metrics).await,
+            DexEvent::Liquidity(_) => Ok(()),
+        }
+    }
+}
+
+/// A processor recording the transfers of decoded instructions into a
+/// `WalletPnl`.
+pub struct TransferProcessor<T> {
+    pnl: WalletPnl,
+    extract: Box<
+        dyn Fn(&DecodedInstruction<T>, &TransactionMetadata) -> Option<TokenTransfer> + Send + Sync,
+    >,
+}
+
+#[async_trait]
+impl<T> Processor for TransferProcessor<T>
+where
+    T: Send + Sync + 'static,
+{
+    type InputType = InstructionProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, instruction, _, _) = data;
+
+        match (self.extract)(&instruction, &metadata.transaction_metadata) {
+            Some(transfer) => self.pnl.record_transfer(&transfer, &metrics).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_cost_pnl() {
+        let (wallet, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (usdc, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut state = PnlState {
+            wallets: HashSet::from([wallet]),
+            holdings: HashMap::new(),
+        };
+        let trade = |mint_in, mint_out, amount_in, amount_out| NormalizedTrade {
+            venue: "test",
+            signature: Signature::default(),
+            slot: 1,
+            block_time: None,
+            pool: Pubkey::default(),
+            trader: wallet,
+            mint_in,
+            mint_out,
+            amount_in,
+            amount_out,
+            fee: None,
+        };
+
+        state.apply_trade(&trade(usdc, token, 100, 10), Some(100.0));
+        state.apply_trade(&trade(usdc, token, 300, 10), Some(300.0));
+        state.apply_trade(&trade(token, usdc, 10, 250), Some(250.0));
+
+        let holding = state.holdings[&(wallet, token)];
+        assert_eq!(holding.amount, 10);
+        assert!((holding.cost_basis - 200.0).abs() < 1e-9);
+        assert!((holding.realized_pnl - 50.0).abs() < 1e-9);
+
+        let transfer = TokenTransfer {
+            signature: Signature::default(),
+            slot: 2,
+            mint: token,
+            source: wallet,
+            destination: other,
+            amount: 5,
+        };
+        assert_eq!(
+            state.apply_transfer(&transfer, Some(1_000.0)),
+            [(wallet, token)]
+        );
+        let holding = state.holdings[&(wallet, token)];
+        assert_eq!(holding.amount, 5);
+        assert!((holding.cost_basis - 100.0).abs() < 1e-9);
+        assert!((holding.realized_pnl - 50.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,225 @@
+use carbon_core::{
+    account_diff::CarbonDiff as _,
+    borsh, bytemuck,
+    deserialize::{CarbonDeserialize as _, LayoutVersion, ZeroCopy},
+    discriminator::DiscriminatorStrategy,
+    CarbonDeserialize, CarbonDiff,
+};
+
+#[derive(CarbonDeserialize, Debug, PartialEq)]
+#[carbon(discriminator = "0x02", strategy = "FirstByte")]
+struct TaggedAccount {
+    value: u32,
+}
+
+#[derive(CarbonDeserialize, Debug, PartialEq)]
+#[carbon(discriminator = "0x07", strategy = "ByteAtOffset<4>")]
+struct TagAtOffsetAccount {
+    value: u32,
+    tag: u8,
+}
+
+#[derive(CarbonDeserialize, Debug, PartialEq)]
+#[carbon(strategy = "DataSize<8>")]
+struct SizedAccount {
+    first: u32,
+    second: u32,
+}
+
+/// A tag byte suffixing the data.
+struct LastByte;
+
+impl DiscriminatorStrategy for LastByte {
+    fn strip<'a>(discriminator: &[u8], data: &'a [u8]) -> Option<&'a [u8]> {
+        let [tag] = discriminator else {
+            return None;
+        };
+
+        match data.split_last() {
+            Some((last, rest)) if last == tag => Some(rest),
+            _ => None,
+        }
+    }
+}
+
+#[derive(CarbonDeserialize, Debug, PartialEq)]
+#[carbon(discriminator = "0xff", strategy = "crate::LastByte")]
+struct SuffixedAccount {
+    value: u32,
+}
+
+#[derive(CarbonDeserialize, Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[bytemuck(crate = "carbon_core::bytemuck")]
+#[repr(C)]
+#[carbon(discriminator = "0x0102030405060708", zero_copy)]
+struct Bitmap {
+    id: u64,
+    bits: [u64; 2],
+}
+
+#[derive(CarbonDeserialize, Debug, PartialEq)]
+#[carbon(discriminator = "0x01")]
+struct Pool {
+    fee_bps: u16,
+    #[carbon(since_version = 1)]
+    fee_recipient: Option<u8>,
+    #[carbon(since_version = 2)]
+    protocol_fee_bps: Option<u16>,
+}
+
+#[derive(CarbonDiff, Debug, Clone, PartialEq)]
+struct Reserves {
+    base: u64,
+    quote: u64,
+    paused: bool,
+}
+
+#[derive(CarbonDiff, Debug, Clone, PartialEq)]
+struct Labeled<T> {
+    label: String,
+    value: T,
+}
+
+#[test]
+fn test_builtin_strategies() {
+    assert_eq!(
+        TaggedAccount::deserialize(&[2, 42, 0, 0, 0]),
+        Some(TaggedAccount { value: 42 })
+    );
+    assert_eq!(TaggedAccount::deserialize(&[3, 42, 0, 0, 0]), None);
+
+    assert_eq!(
+        TagAtOffsetAccount::deserialize(&[42, 0, 0, 0, 7]),
+        Some(TagAtOffsetAccount { value: 42, tag: 7 })
+    );
+    assert_eq!(TagAtOffsetAccount::deserialize(&[42, 0, 0, 0, 8]), None);
+
+    assert_eq!(
+        SizedAccount::deserialize(&[1, 0, 0, 0, 2, 0, 0, 0]),
+        Some(SizedAccount {
+            first: 1,
+            second: 2
+        })
+    );
+    assert_eq!(
+        SizedAccount::deserialize(&[1, 0, 0, 0, 2, 0, 0, 0, 0]),
+        None
+    );
+}
+
+#[test]
+fn test_custom_strategy() {
+    assert_eq!(
+        SuffixedAccount::deserialize(&[42, 0, 0, 0, 0xff]),
+        Some(SuffixedAccount { value: 42 })
+    );
+    assert_eq!(SuffixedAccount::deserialize(&[42, 0, 0, 0, 0xfe]), None);
+}
+
+#[test]
+fn test_zero_copy() {
+    let bitmap = Bitmap {
+        id: 9,
+        bits: [u64::MAX, 1],
+    };
+    let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+    data.extend_from_slice(bytemuck::bytes_of(&bitmap));
+
+    assert_eq!(Bitmap::load(&data).as_deref(), Some(&bitmap));
+    assert_eq!(Bitmap::deserialize(&data), Some(bitmap));
+
+    // Unaligned data is copied.
+    let mut unaligned = vec![0];
+    unaligned.extend_from_slice(&data);
+    assert_eq!(Bitmap::load(&unaligned[1..]).as_deref(), Some(&bitmap));
+
+    assert!(Bitmap::load(&data[..data.len() - 1]).is_none());
+    data[0] = 0;
+    assert!(Bitmap::load(&data).is_none());
+}
+
+#[test]
+fn test_layout_versions() {
+    let old = Pool::deserialize(&[1, 30, 0]).expect("old layout");
+    assert_eq!(
+        old,
+        Pool {
+            fee_bps: 30,
+            fee_recipient: None,
+            protocol_fee_bps: None,
+        }
+    );
+    assert_eq!(old.layout_version(), 0);
+
+    let upgraded = Pool::deserialize(&[1, 30, 0, 7]).expect("version 1 layout");
+    assert_eq!(upgraded.fee_recipient, Some(7));
+    assert_eq!(upgraded.protocol_fee_bps, None);
+    assert_eq!(upgraded.layout_version(), 1);
+
+    let new = Pool::deserialize(&[1, 30, 0, 7, 5, 0]).expect("new layout");
+    assert_eq!(
+        new,
+        Pool {
+            fee_bps: 30,
+            fee_recipient: Some(7),
+            protocol_fee_bps: Some(5),
+        }
+    );
+    assert_eq!(new.layout_version(), 2);
+
+    // A truncated versioned field isn't silently dropped.
+    assert_eq!(Pool::deserialize(&[1, 30, 0, 7, 5]), None);
+}
+
+#[test]
+fn test_diff() {
+    let old = Reserves {
+        base: 10,
+        quote: 20,
+        paused: false,
+    };
+    let new = Reserves {
+        base: 15,
+        quote: 20,
+        paused: true,
+    };
+
+    let changeset = old.diff(&new);
+    assert_eq!(
+        changeset,
+        ReservesChangeset {
+            base: Some(15),
+            quote: None,
+            paused: Some(true),
+        }
+    );
+    assert!(!changeset.is_empty());
+    assert_eq!(
+        changeset.changed_fields().collect::<Vec<_>>(),
+        ["base", "paused"]
+    );
+
+    let unchanged = old.diff(&old);
+    assert!(unchanged.is_empty());
+    assert_eq!(unchanged.changed_fields().count(), 0);
+}
+
+#[test]
+fn test_diff_generic() {
+    let old = Labeled {
+        label: "pool".to_string(),
+        value: vec![1u8, 2],
+    };
+    let new = Labeled {
+        label: "pool".to_string(),
+        value: vec![1u8, 3],
+    };
+
+    assert_eq!(
+        old.diff(&new),
+        LabeledChangeset {
+            label: None,
+            value: Some(vec![1, 3]),
+        }
+    );
+}
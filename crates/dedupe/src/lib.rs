@@ -0,0 +1,60 @@
+//! Deduplication stores shared by several Carbon pipelines.
+//!
+//! `carbon-core` deduplicates updates within a single pipeline with its
+//! `InMemoryDedupeStore`. Horizontally scaled indexers, where several instances
+//! receive the same updates, need a store shared by all of them so each update
+//! is processed once. This crate provides `DedupeStore` implementations backed
+//! by Redis and RocksDB, enabled by the `redis` and `rocksdb` features.
+//!
+//! # Overview
+//!
+//! - **`RedisDedupeStore`**: Records keys in Redis with an expiration, shared
+//!   by every instance connected to the same Redis server.
+//! - **`RocksDbDedupeStore`**: Records keys in a RocksDB database, keeping the
+//!   dedupe window of an instance across restarts.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_dedupe::redis::RedisDedupeStore;
+//!
+//! let store = RedisDedupeStore::connect("redis://127.0.0.1/", "pumpfun-indexer")
+//!     .await?
+//!     .window(Duration::from_secs(120));
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(yellowstone_grpc)
+//!     .deduplicate(store)
+//!     .instruction(PumpfunDecoder, PumpfunInstructionProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Keys are prefixed with the namespace given to the store, so indexers
+//!   sharing a Redis server or a database don't drop each other's updates.
+//! - A RocksDB database can only be opened by one process at a time, so the
+//!   RocksDB store deduplicates the updates of a single instance.
+
+use std::time::Duration;
+
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "rocksdb")]
+pub mod rocksdb;
+
+/// The window of the stores unless configured otherwise.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Prefixes the bytes of a key with a namespace.
+#[cfg_attr(not(any(feature = "redis", feature = "rocksdb")), allow(dead_code))]
+fn namespaced_key(namespace: &[u8], key: &carbon_core::deduplication::DedupeKey) -> Vec<u8> {
+    let key = key.to_bytes();
+    let mut namespaced = Vec::with_capacity(namespace.len() + 1 + key.len());
+    namespaced.extend_from_slice(namespace);
+    namespaced.push(b':');
+    namespaced.extend_from_slice(&key);
+    namespaced
+}
@@ -0,0 +1,70 @@
+//! A `DedupeStore` backed by Redis.
+
+use {
+    crate::{namespaced_key, DEFAULT_WINDOW},
+    async_trait::async_trait,
+    carbon_core::{
+        deduplication::{DedupeKey, DedupeStore},
+        error::{CarbonResult, Error},
+    },
+    redis::aio::ConnectionManager,
+    std::time::Duration,
+};
+
+/// Records keys in Redis, with an expiration set to the window of the store.
+///
+/// Keys are recorded with `SET NX`, so exactly one of the instances sharing
+/// the server processes each update.
+#[derive(Clone)]
+pub struct RedisDedupeStore {
+    connection: ConnectionManager,
+    namespace: String,
+    window: Duration,
+}
+
+impl RedisDedupeStore {
+    /// Connects to the Redis server at `url`, recording keys under
+    /// `namespace`.
+    pub async fn connect(url: &str, namespace: impl Into<String>) -> CarbonResult<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| Error::Custom(format!("Invalid Redis URL: {e}")))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| Error::Custom(format!("Failed to connect to Redis: {e}")))?;
+
+        Ok(Self::new(connection, namespace))
+    }
+
+    /// Creates a store from an existing connection.
+    pub fn new(connection: ConnectionManager, namespace: impl Into<String>) -> Self {
+        Self {
+            connection,
+            namespace: namespace.into(),
+            window: DEFAULT_WINDOW,
+        }
+    }
+
+    /// Sets how long keys are recorded.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+#[async_trait]
+impl DedupeStore for RedisDedupeStore {
+    async fn insert(&self, key: DedupeKey) -> CarbonResult<bool> {
+        let mut connection = self.connection.clone();
+        let recorded: Option<String> = redis::cmd("SET")
+            .arg(namespaced_key(self.namespace.as_bytes(), &key))
+            .arg(1)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.window.as_millis() as u64)
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| Error::Custom(format!("Failed to record dedupe key in Redis: {e}")))?;
+
+        Ok(recorded.is_some())
+    }
+}
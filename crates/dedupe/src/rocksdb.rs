@@ -0,0 +1,95 @@
+//! A `DedupeStore` backed by RocksDB.
+
+use {
+    crate::{namespaced_key, DEFAULT_WINDOW},
+    async_trait::async_trait,
+    carbon_core::{
+        deduplication::{DedupeKey, DedupeStore},
+        error::{CarbonResult, Error},
+    },
+    rocksdb::{Options, DB},
+    std::{
+        path::Path,
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Records keys in a RocksDB database, with the time they expire at.
+///
+/// The database is opened with a TTL equal to the window, so expired keys are
+/// dropped by compactions.
+#[derive(Clone)]
+pub struct RocksDbDedupeStore {
+    db: Arc<DB>,
+    namespace: Vec<u8>,
+    window: Duration,
+    // Serializes the check and the write of a key.
+    lock: Arc<Mutex<()>>,
+}
+
+impl RocksDbDedupeStore {
+    /// Opens or creates the database at `path`, recording keys under
+    /// `namespace` for `window`.
+    pub fn open(
+        path: impl AsRef<Path>,
+        namespace: impl Into<Vec<u8>>,
+        window: Duration,
+    ) -> CarbonResult<Self> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        let db = DB::open_with_ttl(&options, path, window)
+            .map_err(|e| Error::Custom(format!("Failed to open RocksDB database: {e}")))?;
+
+        Ok(Self {
+            db: Arc::new(db),
+            namespace: namespace.into(),
+            window,
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Opens or creates the database at `path` with the default window.
+    pub fn open_default(
+        path: impl AsRef<Path>,
+        namespace: impl Into<Vec<u8>>,
+    ) -> CarbonResult<Self> {
+        Self::open(path, namespace, DEFAULT_WINDOW)
+    }
+
+    fn insert_blocking(&self, key: &[u8]) -> CarbonResult<bool> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let recorded_until = self
+            .db
+            .get(key)
+            .map_err(|e| Error::Custom(format!("Failed to read dedupe key from RocksDB: {e}")))?
+            .and_then(|value| value.try_into().ok())
+            .map(u64::from_be_bytes);
+        if recorded_until.is_some_and(|expiration| expiration > now) {
+            return Ok(false);
+        }
+
+        let expiration = now + self.window.as_millis() as u64;
+        self.db
+            .put(key, expiration.to_be_bytes())
+            .map_err(|e| Error::Custom(format!("Failed to write dedupe key to RocksDB: {e}")))?;
+
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl DedupeStore for RocksDbDedupeStore {
+    async fn insert(&self, key: DedupeKey) -> CarbonResult<bool> {
+        let store = self.clone();
+        let key = namespaced_key(&self.namespace, &key);
+        tokio::task::spawn_blocking(move || store.insert_blocking(&key))
+            .await
+            .map_err(|e| Error::Custom(format!("RocksDB dedupe task failed: {e}")))?
+    }
+}
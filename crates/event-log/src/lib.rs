@@ -0,0 +1,159 @@
+//! Records decoded updates in an append-only event log, and builds materialized
+//! views from it.
+//!
+//! Processors usually write their views as updates arrive, so changing how a
+//! view is computed means crawling the chain again. Recording the decoded
+//! updates in an immutable log instead keeps them available: views are
+//! projections of the log, which can be rebuilt from its start with new logic
+//! at any time.
+//!
+//! # Overview
+//!
+//! - **[`log`]**: `EventLog` appends the decoded instructions and accounts of
+//!   pipes to the log, each event getting the next offset.
+//! - **[`projection`]**: `ProjectionRunner` applies the events of the log to
+//!   `Projection`s, tracking the offset each of them reached.
+//! - **[`storage`]**: Where the log lives, a local directory with
+//!   `LocalStorage`, or an S3 bucket with `ObjectStorage` when the `s3` feature
+//!   is enabled.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_event_log::{EventLog, LocalStorage, ProjectionRunner};
+//!
+//! let storage = Arc::new(LocalStorage::open("./event-log")?);
+//! let event_log = EventLog::open(storage.clone()).await?;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(yellowstone_grpc)
+//!     .instruction(PumpfunDecoder, event_log.instructions())
+//!     .account(PumpfunDecoder, event_log.accounts())
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! event_log.flush().await?;
+//!
+//! // Rebuild the bonding curve view with its new logic.
+//! let mut runner = ProjectionRunner::new(storage).projection(BondingCurves::default());
+//! runner.rebuild("bonding-curves").await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Events are written in segments of `segment_size` events. Buffered events
+//!   are lost if the process stops before `EventLog::flush` is called, and a
+//!   log must only be appended to by one process at a time.
+//! - Segments are never modified once written, so a segment can be read, copied
+//!   or cached without coordination with the writer.
+//! - Projections resume from their checkpoint, saved after each segment, so
+//!   `Projection::apply` may see the events of a segment again after a crash
+//!   and should be idempotent.
+
+pub mod log;
+pub mod projection;
+pub mod storage;
+
+pub use {
+    log::{EventLog, EventLogSink, LogEvent},
+    projection::{Projection, ProjectionRunner},
+    storage::{LocalStorage, LogStorage},
+};
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        async_trait::async_trait,
+        carbon_core::error::CarbonResult,
+        std::{
+            sync::{
+                atomic::{AtomicU64, Ordering},
+                Arc,
+            },
+            time::{SystemTime, UNIX_EPOCH},
+        },
+    };
+
+    #[derive(Default)]
+    struct SlotSum {
+        sum: Arc<AtomicU64>,
+    }
+
+    #[async_trait]
+    impl Projection for SlotSum {
+        fn name(&self) -> &str {
+            "slot-sum"
+        }
+
+        async fn apply(&mut self, event: &LogEvent) -> CarbonResult<()> {
+            self.sum.fetch_add(event.slot, Ordering::Relaxed);
+            Ok(())
+        }
+
+        async fn reset(&mut self) -> CarbonResult<()> {
+            self.sum.store(0, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_append_and_project() {
+        let dir = std::env::temp_dir().join(format!(
+            "carbon-event-log-test-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        let storage = Arc::new(LocalStorage::open(&dir).unwrap());
+        let event_log = EventLog::open(storage.clone())
+            .await
+            .unwrap()
+            .segment_size(2);
+
+        for slot in 1..=3 {
+            event_log
+                .append(
+                    slot,
+                    "account",
+                    slot.to_string(),
+                    String::new(),
+                    serde_json::Value::Null,
+                )
+                .await
+                .unwrap();
+        }
+        event_log.flush().await.unwrap();
+
+        let reopened = EventLog::open(storage.clone()).await.unwrap();
+        assert_eq!(reopened.next_offset().await, 3);
+
+        let mut runner = ProjectionRunner::new(storage.clone()).projection(SlotSum::default());
+        assert_eq!(runner.catch_up().await.unwrap(), 3);
+        assert_eq!(runner.catch_up().await.unwrap(), 0);
+
+        reopened
+            .append(
+                4,
+                "account",
+                "4".to_string(),
+                String::new(),
+                serde_json::Value::Null,
+            )
+            .await
+            .unwrap();
+        reopened.flush().await.unwrap();
+
+        let sum = Arc::new(AtomicU64::new(0));
+        let mut resumed =
+            ProjectionRunner::new(storage.clone()).projection(SlotSum { sum: sum.clone() });
+        assert_eq!(resumed.catch_up().await.unwrap(), 1);
+        assert_eq!(sum.load(Ordering::Relaxed), 4);
+        assert_eq!(resumed.rebuild("slot-sum").await.unwrap(), 4);
+        assert_eq!(sum.load(Ordering::Relaxed), 10);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,283 @@
+//! Appends decoded updates to the event log.
+
+use {
+    crate::storage::LogStorage,
+    async_trait::async_trait,
+    carbon_core::{
+        account::AccountProcessorInputType,
+        error::{CarbonResult, Error},
+        instruction::InstructionProcessorInputType,
+        metrics::MetricsCollection,
+        processor::Processor,
+    },
+    serde::{Deserialize, Serialize},
+    std::{marker::PhantomData, sync::Arc},
+    tokio::sync::Mutex,
+};
+
+pub(crate) const SEGMENTS_PREFIX: &str = "segments/";
+const SEGMENT_EXTENSION: &str = ".jsonl";
+const DEFAULT_SEGMENT_SIZE: usize = 10_000;
+
+/// A decoded update recorded in the log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEvent {
+    /// The position of the event in the log, starting at zero.
+    pub offset: u64,
+    pub slot: u64,
+    /// The kind of update, `instruction` or `account`.
+    pub kind: String,
+    /// The signature of the transaction of an instruction, or the address of
+    /// an account, base58 encoded.
+    pub key: String,
+    /// The program which owns the instruction or account, base58 encoded.
+    pub program_id: String,
+    /// The decoded instruction or account, as JSON.
+    pub data: serde_json::Value,
+}
+
+/// A segment of the log, holding the events from `first_offset` to
+/// `last_offset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Segment {
+    pub(crate) name: String,
+    pub(crate) first_offset: u64,
+    pub(crate) last_offset: u64,
+}
+
+impl Segment {
+    fn name(first_offset: u64, last_offset: u64) -> String {
+        format!(
+            "{}{:020}-{:020}{}",
+            SEGMENTS_PREFIX, first_offset, last_offset, SEGMENT_EXTENSION
+        )
+    }
+
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        let offsets = name
+            .strip_prefix(SEGMENTS_PREFIX)?
+            .strip_suffix(SEGMENT_EXTENSION)?;
+        let (first_offset, last_offset) = offsets.split_once('-')?;
+
+        Some(Self {
+            name: name.to_string(),
+            first_offset: first_offset.parse().ok()?,
+            last_offset: last_offset.parse().ok()?,
+        })
+    }
+
+    pub(crate) async fn list(storage: &dyn LogStorage) -> CarbonResult<Vec<Self>> {
+        let mut segments: Vec<Self> = storage
+            .list(SEGMENTS_PREFIX)
+            .await?
+            .iter()
+            .filter_map(|name| Self::parse(name))
+            .collect();
+        segments.sort_by_key(|segment| segment.first_offset);
+
+        Ok(segments)
+    }
+
+    pub(crate) async fn read(&self, storage: &dyn LogStorage) -> CarbonResult<Vec<LogEvent>> {
+        let data = storage
+            .get(&self.name)
+            .await?
+            .ok_or_else(|| Error::Custom(format!("Event log segment {} disappeared", self.name)))?;
+
+        data.split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                serde_json::from_slice(line).map_err(|e| {
+                    Error::Custom(format!("Invalid event in segment {}: {}", self.name, e))
+                })
+            })
+            .collect()
+    }
+}
+
+struct LogState {
+    storage: Arc<dyn LogStorage>,
+    next_offset: u64,
+    buffer: Vec<LogEvent>,
+}
+
+impl LogState {
+    async fn flush(&mut self) -> CarbonResult<()> {
+        let (Some(first), Some(last)) = (self.buffer.first(), self.buffer.last()) else {
+            return Ok(());
+        };
+
+        let name = Segment::name(first.offset, last.offset);
+        let mut data = Vec::new();
+        for event in &self.buffer {
+            serde_json::to_writer(&mut data, event)
+                .map_err(|e| Error::Custom(format!("Failed to encode event: {}", e)))?;
+            data.push(b'\n');
+        }
+
+        self.storage.put(&name, data).await?;
+        self.buffer.clear();
+
+        Ok(())
+    }
+}
+
+/// A cloneable handle to an append-only log of decoded updates.
+///
+/// Clones share the same log, so a single log can record the updates of
+/// several pipes. Events are buffered and written as segments of
+/// `segment_size` events.
+#[derive(Clone)]
+pub struct EventLog {
+    state: Arc<Mutex<LogState>>,
+    segment_size: usize,
+}
+
+impl EventLog {
+    /// Opens the log stored in `storage`, appending after its last event.
+    pub async fn open(storage: Arc<dyn LogStorage>) -> CarbonResult<Self> {
+        let next_offset = Segment::list(storage.as_ref())
+            .await?
+            .last()
+            .map_or(0, |segment| segment.last_offset + 1);
+
+        Ok(Self {
+            state: Arc::new(Mutex::new(LogState {
+                storage,
+                next_offset,
+                buffer: Vec::new(),
+            })),
+            segment_size: DEFAULT_SEGMENT_SIZE,
+        })
+    }
+
+    /// Sets the number of events of each segment.
+    pub fn segment_size(mut self, segment_size: usize) -> Self {
+        self.segment_size = segment_size.max(1);
+        self
+    }
+
+    /// Returns the offset the next event will be appended at.
+    pub async fn next_offset(&self) -> u64 {
+        self.state.lock().await.next_offset
+    }
+
+    /// Appends an event, assigning its offset, and returns the offset.
+    pub async fn append(
+        &self,
+        slot: u64,
+        kind: &str,
+        key: String,
+        program_id: String,
+        data: serde_json::Value,
+    ) -> CarbonResult<u64> {
+        let mut state = self.state.lock().await;
+        let offset = state.next_offset;
+        state.buffer.push(LogEvent {
+            offset,
+            slot,
+            kind: kind.to_string(),
+            key,
+            program_id,
+            data,
+        });
+        state.next_offset += 1;
+
+        if state.buffer.len() >= self.segment_size {
+            state.flush().await?;
+        }
+
+        Ok(offset)
+    }
+
+    /// Writes the buffered events as a segment.
+    ///
+    /// Should be called once the pipeline stopped, so no event is lost.
+    pub async fn flush(&self) -> CarbonResult<()> {
+        self.state.lock().await.flush().await
+    }
+
+    /// Creates a processor appending the decoded instructions of a pipe.
+    pub fn instructions<T>(&self) -> EventLogSink<InstructionProcessorInputType<T>> {
+        EventLogSink {
+            log: self.clone(),
+            _input: PhantomData,
+        }
+    }
+
+    /// Creates a processor appending the decoded accounts of a pipe.
+    pub fn accounts<T>(&self) -> EventLogSink<AccountProcessorInputType<T>> {
+        EventLogSink {
+            log: self.clone(),
+            _input: PhantomData,
+        }
+    }
+}
+
+/// A processor appending the outputs of a pipe to an `EventLog`.
+pub struct EventLogSink<I> {
+    log: EventLog,
+    _input: PhantomData<fn(I)>,
+}
+
+fn encode(data: &impl Serialize) -> CarbonResult<serde_json::Value> {
+    serde_json::to_value(data).map_err(|e| Error::Custom(format!("Failed to encode event: {}", e)))
+}
+
+#[async_trait]
+impl<T> Processor for EventLogSink<InstructionProcessorInputType<T>>
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    type InputType = InstructionProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, instruction, _, _) = data;
+        self.log
+            .append(
+                metadata.transaction_metadata.slot,
+                "instruction",
+                metadata.transaction_metadata.signature.to_string(),
+                instruction.program_id.to_string(),
+                encode(&instruction.data)?,
+            )
+            .await?;
+
+        metrics
+            .increment_counter("event_log_events_appended", 1)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T> Processor for EventLogSink<AccountProcessorInputType<T>>
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    type InputType = AccountProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, account, _) = data;
+        self.log
+            .append(
+                metadata.slot,
+                "account",
+                metadata.pubkey.to_string(),
+                account.owner.to_string(),
+                encode(&account.data)?,
+            )
+            .await?;
+
+        metrics
+            .increment_counter("event_log_events_appended", 1)
+            .await
+    }
+}
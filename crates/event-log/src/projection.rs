@@ -0,0 +1,160 @@
+//! Builds materialized views from the event log.
+
+use {
+    crate::{
+        log::{LogEvent, Segment},
+        storage::LogStorage,
+    },
+    async_trait::async_trait,
+    carbon_core::error::{CarbonResult, Error},
+    std::{sync::Arc, time::Duration},
+    tokio_util::sync::CancellationToken,
+};
+
+const CHECKPOINTS_PREFIX: &str = "checkpoints/";
+
+/// A materialized view built from the events of the log.
+#[async_trait]
+pub trait Projection: Send {
+    /// Returns the name of the projection, under which its checkpoint is
+    /// stored.
+    fn name(&self) -> &str;
+
+    /// Applies an event to the view.
+    async fn apply(&mut self, event: &LogEvent) -> CarbonResult<()>;
+
+    /// Clears the view, before it is rebuilt from the start of the log.
+    async fn reset(&mut self) -> CarbonResult<()>;
+}
+
+struct ProjectionState {
+    projection: Box<dyn Projection>,
+    /// The offset of the next event to apply.
+    next_offset: Option<u64>,
+}
+
+/// Applies the events of a log to projections, tracking the offset each of
+/// them reached in a checkpoint stored along with the log.
+pub struct ProjectionRunner {
+    storage: Arc<dyn LogStorage>,
+    projections: Vec<ProjectionState>,
+}
+
+impl ProjectionRunner {
+    pub fn new(storage: Arc<dyn LogStorage>) -> Self {
+        Self {
+            storage,
+            projections: Vec::new(),
+        }
+    }
+
+    /// Adds a projection, which resumes from its checkpoint.
+    pub fn projection(mut self, projection: impl Projection + 'static) -> Self {
+        self.projections.push(ProjectionState {
+            projection: Box::new(projection),
+            next_offset: None,
+        });
+        self
+    }
+
+    /// Applies the events appended since the last run to every projection,
+    /// and returns the number of events applied.
+    ///
+    /// Checkpoints are saved after each segment, so a projection interrupted
+    /// mid-segment applies the events of that segment again.
+    pub async fn catch_up(&mut self) -> CarbonResult<usize> {
+        let segments = Segment::list(self.storage.as_ref()).await?;
+        let mut applied = 0;
+
+        for state in &mut self.projections {
+            let mut next_offset = match state.next_offset {
+                Some(next_offset) => next_offset,
+                None => load_checkpoint(self.storage.as_ref(), state.projection.name()).await?,
+            };
+
+            for segment in segments
+                .iter()
+                .filter(|segment| segment.last_offset >= next_offset)
+            {
+                for event in segment.read(self.storage.as_ref()).await? {
+                    if event.offset < next_offset {
+                        continue;
+                    }
+                    state.projection.apply(&event).await?;
+                    next_offset = event.offset + 1;
+                    applied += 1;
+                }
+
+                save_checkpoint(self.storage.as_ref(), state.projection.name(), next_offset)
+                    .await?;
+            }
+
+            state.next_offset = Some(next_offset);
+        }
+
+        Ok(applied)
+    }
+
+    /// Resets a projection and rebuilds it from the start of the log, to apply
+    /// new logic to past events without crawling the chain again.
+    pub async fn rebuild(&mut self, name: &str) -> CarbonResult<usize> {
+        let state = self
+            .projections
+            .iter_mut()
+            .find(|state| state.projection.name() == name)
+            .ok_or_else(|| Error::Custom(format!("Unknown projection {}", name)))?;
+
+        state.projection.reset().await?;
+        save_checkpoint(self.storage.as_ref(), name, 0).await?;
+        state.next_offset = Some(0);
+
+        self.catch_up().await
+    }
+
+    /// Catches up with the log every `interval` until `cancellation_token` is
+    /// cancelled.
+    pub async fn run(
+        &mut self,
+        interval: Duration,
+        cancellation_token: CancellationToken,
+    ) -> CarbonResult<()> {
+        loop {
+            let applied = self.catch_up().await?;
+            if applied > 0 {
+                log::debug!("Applied {} event log events to projections", applied);
+            }
+
+            tokio::select! {
+                _ = cancellation_token.cancelled() => return Ok(()),
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+}
+
+async fn load_checkpoint(storage: &dyn LogStorage, name: &str) -> CarbonResult<u64> {
+    let Some(data) = storage
+        .get(&format!("{}{}", CHECKPOINTS_PREFIX, name))
+        .await?
+    else {
+        return Ok(0);
+    };
+
+    String::from_utf8_lossy(&data)
+        .trim()
+        .parse()
+        .map_err(|e| Error::Custom(format!("Invalid checkpoint of projection {}: {}", name, e)))
+}
+
+async fn save_checkpoint(
+    storage: &dyn LogStorage,
+    name: &str,
+    next_offset: u64,
+) -> CarbonResult<()> {
+    storage
+        .put(
+            &format!("{}{}", CHECKPOINTS_PREFIX, name),
+            next_offset.to_string().into_bytes(),
+        )
+        .await
+}
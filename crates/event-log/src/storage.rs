@@ -0,0 +1,221 @@
+//! Storage backends of the event log.
+//!
+//! The log is stored as named objects: segments of events under `segments/`
+//! and projection checkpoints under `checkpoints/`. `LogStorage` abstracts
+//! over where those objects live.
+
+use {
+    async_trait::async_trait,
+    carbon_core::error::{CarbonResult, Error},
+    std::{
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Stores the objects of an event log.
+#[async_trait]
+pub trait LogStorage: Send + Sync {
+    /// Writes an object, replacing any object with the same name.
+    ///
+    /// Readers must never see a partially written object.
+    async fn put(&self, name: &str, data: Vec<u8>) -> CarbonResult<()>;
+
+    /// Reads an object, or returns `None` if it doesn't exist.
+    async fn get(&self, name: &str) -> CarbonResult<Option<Vec<u8>>>;
+
+    /// Returns the names of the objects starting with `prefix`, sorted.
+    async fn list(&self, prefix: &str) -> CarbonResult<Vec<String>>;
+}
+
+/// Stores the objects of the log as files in a local directory.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    /// Stores the log in `root`, creating the directory if needed.
+    pub fn open(root: impl Into<PathBuf>) -> CarbonResult<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| io_error(&root, e))?;
+
+        Ok(Self { root })
+    }
+
+    fn list_dir(&self, dir: &Path, names: &mut Vec<String>) -> CarbonResult<()> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(io_error(dir, e)),
+        };
+
+        for entry in entries {
+            let path = entry.map_err(|e| io_error(dir, e))?.path();
+            if path.is_dir() {
+                self.list_dir(&path, names)?;
+            } else if path.extension().is_some_and(|extension| extension == "tmp") {
+                continue;
+            } else if let Ok(name) = path.strip_prefix(&self.root) {
+                names.push(name.to_string_lossy().replace('\\', "/"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LogStorage for LocalStorage {
+    async fn put(&self, name: &str, data: Vec<u8>) -> CarbonResult<()> {
+        let path = self.root.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+        }
+
+        let temporary_path = path.with_extension("tmp");
+        let mut file =
+            fs::File::create(&temporary_path).map_err(|e| io_error(&temporary_path, e))?;
+        file.write_all(&data)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| io_error(&temporary_path, e))?;
+        fs::rename(&temporary_path, &path).map_err(|e| io_error(&path, e))
+    }
+
+    async fn get(&self, name: &str) -> CarbonResult<Option<Vec<u8>>> {
+        let path = self.root.join(name);
+        match fs::read(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(&path, e)),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> CarbonResult<Vec<String>> {
+        let mut names = Vec::new();
+        self.list_dir(&self.root, &mut names)?;
+        names.retain(|name| name.starts_with(prefix));
+        names.sort();
+
+        Ok(names)
+    }
+}
+
+fn io_error(path: &Path, error: io::Error) -> Error {
+    Error::Custom(format!(
+        "Event log I/O error on {}: {}",
+        path.display(),
+        error
+    ))
+}
+
+#[cfg(feature = "s3")]
+pub use object_storage::ObjectStorage;
+
+#[cfg(feature = "s3")]
+mod object_storage {
+    use {
+        super::LogStorage,
+        async_trait::async_trait,
+        carbon_core::error::{CarbonResult, Error},
+        futures::TryStreamExt,
+        object_store::{path::Path, ObjectStore},
+        std::sync::Arc,
+    };
+
+    /// Stores the objects of the log in an object store, such as an S3
+    /// bucket, under a prefix.
+    #[derive(Debug, Clone)]
+    pub struct ObjectStorage {
+        store: Arc<dyn ObjectStore>,
+        prefix: String,
+    }
+
+    impl ObjectStorage {
+        /// Stores the log in `store`, under `prefix`.
+        pub fn new(store: Arc<dyn ObjectStore>, prefix: impl Into<String>) -> Self {
+            Self {
+                store,
+                prefix: prefix.into().trim_matches('/').to_string(),
+            }
+        }
+
+        /// Stores the log in an S3 bucket under `prefix`, with the credentials
+        /// and region configured by the `AWS_*` environment variables.
+        pub fn s3_from_env(bucket: &str, prefix: impl Into<String>) -> CarbonResult<Self> {
+            let store = object_store::aws::AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .build()
+                .map_err(|e| object_store_error("configure S3", e))?;
+
+            Ok(Self::new(Arc::new(store), prefix))
+        }
+
+        fn path(&self, name: &str) -> Path {
+            if self.prefix.is_empty() {
+                Path::from(name)
+            } else {
+                Path::from(format!("{}/{}", self.prefix, name))
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LogStorage for ObjectStorage {
+        async fn put(&self, name: &str, data: Vec<u8>) -> CarbonResult<()> {
+            self.store
+                .put(&self.path(name), data.into())
+                .await
+                .map_err(|e| object_store_error("write", e))?;
+
+            Ok(())
+        }
+
+        async fn get(&self, name: &str) -> CarbonResult<Option<Vec<u8>>> {
+            let result = match self.store.get(&self.path(name)).await {
+                Ok(result) => result,
+                Err(object_store::Error::NotFound { .. }) => return Ok(None),
+                Err(e) => return Err(object_store_error("read", e)),
+            };
+            let data = result
+                .bytes()
+                .await
+                .map_err(|e| object_store_error("read", e))?;
+
+            Ok(Some(data.to_vec()))
+        }
+
+        async fn list(&self, prefix: &str) -> CarbonResult<Vec<String>> {
+            let root = if self.prefix.is_empty() {
+                None
+            } else {
+                Some(Path::from(self.prefix.as_str()))
+            };
+            let objects: Vec<_> = self
+                .store
+                .list(root.as_ref())
+                .try_collect()
+                .await
+                .map_err(|e| object_store_error("list", e))?;
+
+            let root_length = if self.prefix.is_empty() {
+                0
+            } else {
+                self.prefix.len() + 1
+            };
+            let mut names: Vec<String> = objects
+                .into_iter()
+                .map(|object| object.location.to_string()[root_length..].to_string())
+                .filter(|name| name.starts_with(prefix))
+                .collect();
+            names.sort();
+
+            Ok(names)
+        }
+    }
+
+    fn object_store_error(action: &str, error: object_store::Error) -> Error {
+        Error::Custom(format!("Failed to {} event log object: {}", action, error))
+    }
+}
@@ -0,0 +1,140 @@
+//! The layout of the buffers exchanged with plugins, shared by every backend.
+//!
+//! Plugins receive a single input buffer per call, and return the decoded
+//! value as a JSON document of the form `{"type": "<name>", "data": <value>}`.
+//!
+//! - Account input: the owner (32 bytes), followed by the account data.
+//! - Instruction input: the program ID (32 bytes), the number of accounts as a
+//!   little-endian `u32`, each account as its address (32 bytes) followed by a
+//!   flags byte (`FLAG_SIGNER`, `FLAG_WRITABLE`), and the instruction data.
+//!
+//! Plugins written in Rust can parse their input with `AccountInput::parse`
+//! and `InstructionInput::parse`.
+
+use {
+    serde::{Deserialize, Serialize},
+    solana_instruction::AccountMeta,
+    solana_pubkey::Pubkey,
+};
+
+/// The version of the ABI, returned by the `carbon_plugin_abi_version`
+/// export of plugins. Plugins built for another version are rejected.
+pub const ABI_VERSION: u32 = 1;
+
+/// Set in the flags of the accounts signing an instruction.
+pub const FLAG_SIGNER: u8 = 1;
+/// Set in the flags of the accounts written by an instruction.
+pub const FLAG_WRITABLE: u8 = 1 << 1;
+
+/// Returned by the decode exports of plugins when they decoded their input.
+pub const STATUS_DECODED: i32 = 0;
+/// Returned by the decode exports of plugins when they don't recognize their
+/// input. Any other status is an error.
+pub const STATUS_UNRECOGNIZED: i32 = 1;
+
+/// A value decoded by a plugin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginValue {
+    /// The name of the decoded type, such as `BondingCurve` or `Buy`.
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub data: serde_json::Value,
+}
+
+/// An account, as received by a plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountInput<'a> {
+    pub owner: Pubkey,
+    pub data: &'a [u8],
+}
+
+impl<'a> AccountInput<'a> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut input = Vec::with_capacity(32 + self.data.len());
+        input.extend_from_slice(self.owner.as_ref());
+        input.extend_from_slice(self.data);
+        input
+    }
+
+    pub fn parse(input: &'a [u8]) -> Option<Self> {
+        let (owner, data) = input.split_at_checked(32)?;
+
+        Some(Self {
+            owner: Pubkey::try_from(owner).ok()?,
+            data,
+        })
+    }
+}
+
+/// An instruction, as received by a plugin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionInput<'a> {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMeta>,
+    pub data: &'a [u8],
+}
+
+impl<'a> InstructionInput<'a> {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut input = Vec::with_capacity(36 + self.accounts.len() * 33 + self.data.len());
+        input.extend_from_slice(self.program_id.as_ref());
+        input.extend_from_slice(&(self.accounts.len() as u32).to_le_bytes());
+        for account in &self.accounts {
+            input.extend_from_slice(account.pubkey.as_ref());
+            let mut flags = 0;
+            if account.is_signer {
+                flags |= FLAG_SIGNER;
+            }
+            if account.is_writable {
+                flags |= FLAG_WRITABLE;
+            }
+            input.push(flags);
+        }
+        input.extend_from_slice(self.data);
+        input
+    }
+
+    pub fn parse(input: &'a [u8]) -> Option<Self> {
+        let (program_id, rest) = input.split_at_checked(32)?;
+        let (count, mut rest) = rest.split_at_checked(4)?;
+        let count = u32::from_le_bytes(count.try_into().ok()?) as usize;
+
+        let mut accounts = Vec::with_capacity(count.min(rest.len() / 33));
+        for _ in 0..count {
+            let (account, remaining) = rest.split_at_checked(33)?;
+            accounts.push(AccountMeta {
+                pubkey: Pubkey::try_from(&account[..32]).ok()?,
+                is_signer: account[32] & FLAG_SIGNER != 0,
+                is_writable: account[32] & FLAG_WRITABLE != 0,
+            });
+            rest = remaining;
+        }
+
+        Some(Self {
+            program_id: Pubkey::try_from(program_id).ok()?,
+            accounts,
+            data: rest,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_input_roundtrip() {
+        let input = InstructionInput {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![
+                AccountMeta::new(Pubkey::new_unique(), true),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            ],
+            data: &[1, 2, 3],
+        };
+
+        let encoded = input.encode();
+        assert_eq!(InstructionInput::parse(&encoded), Some(input));
+        assert_eq!(InstructionInput::parse(&encoded[..40]), None);
+    }
+}
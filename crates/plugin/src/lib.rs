@@ -0,0 +1,184 @@
+//! Loads decoders compiled to WebAssembly modules or dynamic libraries at
+//! runtime, and registers them on pipelines like native decoders.
+//!
+//! Adding support for a program usually means adding its decoder crate to the
+//! indexer and rebuilding it. A plugin is a decoder built separately, possibly
+//! in another language, and loaded by path: `PluginDecoder` implements
+//! `AccountDecoder` and `InstructionDecoder` by handing the raw account or
+//! instruction to the plugin, which returns the decoded value as JSON.
+//!
+//! # Overview
+//!
+//! - **[`abi`]**: The layout of the buffers exchanged with plugins, and the
+//!   statuses they return.
+//! - **[`native`]**: Plugins compiled to dynamic libraries exporting the C ABI,
+//!   enabled by the `native` feature.
+//! - **[`wasm`]**: Plugins compiled to WebAssembly modules, run in a sandbox,
+//!   enabled by the `wasm` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_plugin::PluginDecoder;
+//!
+//! let decoder = PluginDecoder::wasm("./plugins/my_program.wasm")?;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .instruction(decoder.clone(), MyProgramInstructionProcessor)
+//!     .account(decoder, MyProgramAccountProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Plugins are handed every account and instruction of the pipes they are
+//!   registered on, and return `STATUS_UNRECOGNIZED` for those of other
+//!   programs, so they should check the owner or program ID first.
+//! - Native plugins run in the process of the indexer with no isolation, so
+//!   loading them is `unsafe`. Prefer WebAssembly plugins for untrusted code.
+//! - Plugin failures are logged and the account or instruction is skipped, as
+//!   with native decoders failing to decode their input.
+
+pub mod abi;
+#[cfg(feature = "native")]
+pub mod native;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use abi::PluginValue;
+use {
+    abi::{AccountInput, InstructionInput},
+    carbon_core::{
+        account::{AccountDecoder, DecodedAccount},
+        error::CarbonResult,
+        instruction::{DecodedInstruction, InstructionDecoder},
+    },
+    std::sync::Arc,
+};
+
+/// The decode exports of a plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginExport {
+    Account,
+    Instruction,
+}
+
+/// A loaded plugin, calling its decode exports.
+pub trait PluginBackend: Send + Sync {
+    /// Calls the decode export of the plugin with an input encoded as
+    /// described in [`abi`], returning its output unless the plugin didn't
+    /// recognize the input.
+    fn decode(&self, export: PluginExport, input: &[u8]) -> CarbonResult<Option<Vec<u8>>>;
+}
+
+/// A decoder handing accounts and instructions to a plugin.
+#[derive(Clone)]
+pub struct PluginDecoder {
+    name: String,
+    backend: Arc<dyn PluginBackend>,
+}
+
+impl PluginDecoder {
+    /// Creates a decoder from a loaded plugin, `name` identifying it in logs.
+    pub fn new(name: impl Into<String>, backend: impl PluginBackend + 'static) -> Self {
+        Self {
+            name: name.into(),
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Loads the plugin compiled to the WebAssembly module at `path`.
+    #[cfg(feature = "wasm")]
+    pub fn wasm(path: impl AsRef<std::path::Path>) -> CarbonResult<Self> {
+        let path = path.as_ref();
+        Ok(Self::new(
+            path.display().to_string(),
+            wasm::WasmPlugin::load(path)?,
+        ))
+    }
+
+    /// Loads the plugin compiled to the dynamic library at `path`.
+    ///
+    /// # Safety
+    ///
+    /// See [`native::NativePlugin::load`].
+    #[cfg(feature = "native")]
+    pub unsafe fn native(path: impl AsRef<std::path::Path>) -> CarbonResult<Self> {
+        let path = path.as_ref();
+        Ok(Self::new(
+            path.display().to_string(),
+            native::NativePlugin::load(path)?,
+        ))
+    }
+
+    /// Returns the name of the plugin, its path for loaded plugins.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn decode(&self, export: PluginExport, input: &[u8]) -> Option<PluginValue> {
+        let output = match self.backend.decode(export, input) {
+            Ok(output) => output?,
+            Err(e) => {
+                log::error!("Plugin {} failed to decode: {:?}", self.name, e);
+                return None;
+            }
+        };
+
+        match serde_json::from_slice(&output) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log::error!("Plugin {} returned invalid output: {}", self.name, e);
+                None
+            }
+        }
+    }
+}
+
+impl AccountDecoder<'_> for PluginDecoder {
+    type AccountType = PluginValue;
+
+    fn decode_account(
+        &self,
+        account: &solana_account::Account,
+    ) -> Option<DecodedAccount<Self::AccountType>> {
+        let input = AccountInput {
+            owner: account.owner,
+            data: &account.data,
+        };
+        let data = self.decode(PluginExport::Account, &input.encode())?;
+
+        Some(DecodedAccount {
+            lamports: account.lamports,
+            data,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        })
+    }
+}
+
+impl InstructionDecoder<'_> for PluginDecoder {
+    type InstructionType = PluginValue;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<DecodedInstruction<Self::InstructionType>> {
+        let input = InstructionInput {
+            program_id: instruction.program_id,
+            accounts: instruction.accounts.clone(),
+            data: &instruction.data,
+        };
+        let data = self.decode(PluginExport::Instruction, &input.encode())?;
+
+        Some(DecodedInstruction {
+            program_id: instruction.program_id,
+            data,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
@@ -0,0 +1,120 @@
+//! Loads plugins compiled to dynamic libraries exporting the C ABI.
+//!
+//! The library must export the following functions:
+//!
+//! ```c
+//! uint32_t carbon_plugin_abi_version(void);
+//! int32_t carbon_plugin_decode_account(const uint8_t *input, size_t input_len,
+//!                                      uint8_t **output, size_t *output_len);
+//! int32_t carbon_plugin_decode_instruction(const uint8_t *input, size_t input_len,
+//!                                          uint8_t **output, size_t *output_len);
+//! void carbon_plugin_free(uint8_t *output, size_t output_len);
+//! ```
+//!
+//! The decode functions return a status and, when they decoded their input,
+//! set `output` to a JSON document allocated by the plugin. The host hands it
+//! back to `carbon_plugin_free` once read. They may be called concurrently
+//! from several threads.
+
+use {
+    crate::{abi, PluginBackend, PluginExport},
+    carbon_core::error::{CarbonResult, Error},
+    libloading::Library,
+    std::path::Path,
+};
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type DecodeFn = unsafe extern "C" fn(*const u8, usize, *mut *mut u8, *mut usize) -> i32;
+type FreeFn = unsafe extern "C" fn(*mut u8, usize);
+
+/// A plugin loaded from a dynamic library.
+pub struct NativePlugin {
+    decode_account: DecodeFn,
+    decode_instruction: DecodeFn,
+    free: FreeFn,
+    // Keeps the library loaded while the function pointers above are used.
+    _library: Library,
+}
+
+impl NativePlugin {
+    /// Loads the plugin compiled to the dynamic library at `path`.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization code, and the plugin is
+    /// trusted to implement the ABI correctly: a library exporting functions
+    /// with the expected names but other signatures is undefined behavior.
+    pub unsafe fn load(path: &Path) -> CarbonResult<Self> {
+        let library = Library::new(path).map_err(|e| load_error(path, e))?;
+
+        let abi_version = *library
+            .get::<AbiVersionFn>(b"carbon_plugin_abi_version\0")
+            .map_err(|e| load_error(path, e))?;
+        let version = abi_version();
+        if version != abi::ABI_VERSION {
+            return Err(Error::Custom(format!(
+                "Plugin {} implements ABI version {}, expected {}",
+                path.display(),
+                version,
+                abi::ABI_VERSION
+            )));
+        }
+
+        Ok(Self {
+            decode_account: *library
+                .get::<DecodeFn>(b"carbon_plugin_decode_account\0")
+                .map_err(|e| load_error(path, e))?,
+            decode_instruction: *library
+                .get::<DecodeFn>(b"carbon_plugin_decode_instruction\0")
+                .map_err(|e| load_error(path, e))?,
+            free: *library
+                .get::<FreeFn>(b"carbon_plugin_free\0")
+                .map_err(|e| load_error(path, e))?,
+            _library: library,
+        })
+    }
+}
+
+impl PluginBackend for NativePlugin {
+    fn decode(&self, export: PluginExport, input: &[u8]) -> CarbonResult<Option<Vec<u8>>> {
+        let decode = match export {
+            PluginExport::Account => self.decode_account,
+            PluginExport::Instruction => self.decode_instruction,
+        };
+
+        let mut output = std::ptr::null_mut();
+        let mut output_len = 0;
+        // SAFETY: The plugin was loaded by `NativePlugin::load`, whose caller
+        // vouched for its implementation of the ABI.
+        let status = unsafe { decode(input.as_ptr(), input.len(), &mut output, &mut output_len) };
+
+        let decoded = if output.is_null() {
+            None
+        } else {
+            // SAFETY: The plugin returned a buffer of `output_len` bytes,
+            // valid until it is handed back to `carbon_plugin_free`.
+            let decoded = unsafe { std::slice::from_raw_parts(output, output_len) }.to_vec();
+            unsafe { (self.free)(output, output_len) };
+            Some(decoded)
+        };
+
+        match status {
+            abi::STATUS_DECODED => decoded
+                .map(Some)
+                .ok_or_else(|| Error::Custom("Plugin returned no output".to_string())),
+            abi::STATUS_UNRECOGNIZED => Ok(None),
+            status => Err(Error::Custom(format!(
+                "Plugin failed with status {}",
+                status
+            ))),
+        }
+    }
+}
+
+fn load_error(path: &Path, error: libloading::Error) -> Error {
+    Error::Custom(format!(
+        "Failed to load plugin {}: {}",
+        path.display(),
+        error
+    ))
+}
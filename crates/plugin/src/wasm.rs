@@ -0,0 +1,174 @@
+//! Loads plugins compiled to WebAssembly.
+//!
+//! The module must not import anything, so it can be built for
+//! `wasm32-unknown-unknown` but not WASI, and must export its `memory` along
+//! with the following functions, pointers and lengths being `i32`:
+//!
+//! - `carbon_plugin_abi_version() -> i32`
+//! - `carbon_plugin_alloc(len) -> ptr`, allocating a buffer in the memory of
+//!   the module.
+//! - `carbon_plugin_free(ptr, len)`, freeing a buffer allocated by the module.
+//! - `carbon_plugin_decode_account(input, input_len, output) -> status` and
+//!   `carbon_plugin_decode_instruction(input, input_len, output) -> status`,
+//!   where `output` points to 8 bytes receiving the little-endian pointer and
+//!   length of the JSON document allocated by the module.
+//!
+//! The module runs in a sandbox: it can't access anything but its own memory.
+
+use {
+    crate::{abi, PluginBackend, PluginExport},
+    carbon_core::error::{CarbonResult, Error},
+    std::{path::Path, sync::Mutex},
+    wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc},
+};
+
+struct WasmState {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    free: TypedFunc<(i32, i32), ()>,
+    decode_account: TypedFunc<(i32, i32, i32), i32>,
+    decode_instruction: TypedFunc<(i32, i32, i32), i32>,
+}
+
+impl WasmState {
+    fn write(&mut self, data: &[u8]) -> CarbonResult<i32> {
+        let ptr = self
+            .alloc
+            .call(&mut self.store, data.len() as i32)
+            .map_err(wasm_error)?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, data)
+            .map_err(wasm_error)?;
+        Ok(ptr)
+    }
+
+    fn read(&self, ptr: i32, len: usize) -> CarbonResult<Vec<u8>> {
+        let mut data = vec![0; len];
+        self.memory
+            .read(&self.store, ptr as u32 as usize, &mut data)
+            .map_err(wasm_error)?;
+        Ok(data)
+    }
+
+    fn free(&mut self, ptr: i32, len: usize) -> CarbonResult<()> {
+        self.free
+            .call(&mut self.store, (ptr, len as i32))
+            .map_err(wasm_error)
+    }
+
+    fn decode(&mut self, export: PluginExport, input: &[u8]) -> CarbonResult<Option<Vec<u8>>> {
+        let decode = match export {
+            PluginExport::Account => self.decode_account.clone(),
+            PluginExport::Instruction => self.decode_instruction.clone(),
+        };
+
+        let input_ptr = self.write(input)?;
+        let output_ptr = self.write(&[0; 8])?;
+        let status = decode
+            .call(&mut self.store, (input_ptr, input.len() as i32, output_ptr))
+            .map_err(wasm_error)?;
+
+        let output = self.read(output_ptr, 8)?;
+        let decoded_ptr = i32::from_le_bytes(output[..4].try_into().expect("4 bytes"));
+        let decoded_len = u32::from_le_bytes(output[4..].try_into().expect("4 bytes")) as usize;
+        let decoded = if decoded_ptr == 0 {
+            None
+        } else {
+            let decoded = self.read(decoded_ptr, decoded_len)?;
+            self.free(decoded_ptr, decoded_len)?;
+            Some(decoded)
+        };
+        self.free(output_ptr, 8)?;
+        self.free(input_ptr, input.len())?;
+
+        match status {
+            abi::STATUS_DECODED => decoded
+                .map(Some)
+                .ok_or_else(|| Error::Custom("Plugin returned no output".to_string())),
+            abi::STATUS_UNRECOGNIZED => Ok(None),
+            status => Err(Error::Custom(format!(
+                "Plugin failed with status {}",
+                status
+            ))),
+        }
+    }
+}
+
+/// A plugin loaded from a WebAssembly module.
+///
+/// Calls are serialized, since a module instance runs one call at a time.
+pub struct WasmPlugin {
+    state: Mutex<WasmState>,
+}
+
+impl WasmPlugin {
+    /// Compiles and instantiates the WebAssembly module at `path`.
+    pub fn load(path: &Path) -> CarbonResult<Self> {
+        let engine = Engine::default();
+        let module = Module::from_file(&engine, path).map_err(|e| load_error(path, e))?;
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[]).map_err(|e| load_error(path, e))?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&mut store, "carbon_plugin_abi_version")
+            .and_then(|abi_version| abi_version.call(&mut store, ()))
+            .map_err(|e| load_error(path, e))?;
+        if version as u32 != abi::ABI_VERSION {
+            return Err(Error::Custom(format!(
+                "Plugin {} implements ABI version {}, expected {}",
+                path.display(),
+                version,
+                abi::ABI_VERSION
+            )));
+        }
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| Error::Custom(format!("Plugin {} exports no memory", path.display())))?;
+        let alloc = instance
+            .get_typed_func(&mut store, "carbon_plugin_alloc")
+            .map_err(|e| load_error(path, e))?;
+        let free = instance
+            .get_typed_func(&mut store, "carbon_plugin_free")
+            .map_err(|e| load_error(path, e))?;
+        let decode_account = instance
+            .get_typed_func(&mut store, "carbon_plugin_decode_account")
+            .map_err(|e| load_error(path, e))?;
+        let decode_instruction = instance
+            .get_typed_func(&mut store, "carbon_plugin_decode_instruction")
+            .map_err(|e| load_error(path, e))?;
+
+        Ok(Self {
+            state: Mutex::new(WasmState {
+                store,
+                memory,
+                alloc,
+                free,
+                decode_account,
+                decode_instruction,
+            }),
+        })
+    }
+}
+
+impl PluginBackend for WasmPlugin {
+    fn decode(&self, export: PluginExport, input: &[u8]) -> CarbonResult<Option<Vec<u8>>> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .decode(export, input)
+    }
+}
+
+fn load_error(path: &Path, error: impl std::fmt::Display) -> Error {
+    Error::Custom(format!(
+        "Failed to load plugin {}: {}",
+        path.display(),
+        error
+    ))
+}
+
+fn wasm_error(error: impl std::fmt::Display) -> Error {
+    Error::Custom(format!("Plugin call failed: {}", error))
+}
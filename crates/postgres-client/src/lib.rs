@@ -1,3 +1,6 @@
+mod sink;
+
+pub use sink::{PgRow, PgSink};
 use sqlx::{postgres::PgPoolOptions, Error, PgPool, Postgres};
 use sqlx_migrator::{
     migrator::{Info, Migrate, Migrator},
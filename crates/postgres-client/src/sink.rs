@@ -0,0 +1,87 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::{CarbonResult, Error},
+        sink::Sink,
+    },
+    sqlx::{query_builder::Separated, PgPool, Postgres, QueryBuilder},
+    std::marker::PhantomData,
+};
+
+/// Postgres accepts at most this many bind parameters per statement.
+const MAX_BIND_PARAMETERS: usize = 65_535;
+
+/// A row inserted into a table by a `PgSink`.
+pub trait PgRow: Send + Sync + 'static {
+    /// The table rows are inserted into.
+    const TABLE: &'static str;
+    /// The columns of the table, in the order `bind` binds their values.
+    const COLUMNS: &'static [&'static str];
+
+    /// Binds the value of each column of the row.
+    fn bind(self, row: &mut Separated<'_, '_, Postgres, &'static str>);
+}
+
+/// A sink inserting batches of rows into a table, each batch in a single
+/// transaction.
+pub struct PgSink<T> {
+    pool: PgPool,
+    on_conflict: Option<String>,
+    _row: PhantomData<fn(T)>,
+}
+
+impl<T: PgRow> PgSink<T> {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            on_conflict: None,
+            _row: PhantomData,
+        }
+    }
+
+    /// Appends an `ON CONFLICT` clause to the inserts, such as
+    /// `(signature) DO NOTHING`.
+    pub fn on_conflict(mut self, on_conflict: impl Into<String>) -> Self {
+        self.on_conflict = Some(on_conflict.into());
+        self
+    }
+}
+
+#[async_trait]
+impl<T: PgRow> Sink<Vec<T>> for PgSink<T> {
+    async fn send(&mut self, mut batch: Vec<T>) -> CarbonResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let rows_per_statement = (MAX_BIND_PARAMETERS / T::COLUMNS.len().max(1)).max(1);
+        let mut transaction = self.pool.begin().await.map_err(pg_error)?;
+
+        while !batch.is_empty() {
+            let rest = batch.split_off(batch.len().min(rows_per_statement));
+            let rows = std::mem::replace(&mut batch, rest);
+
+            let mut query = QueryBuilder::<Postgres>::new(format!(
+                "INSERT INTO {} ({}) ",
+                T::TABLE,
+                T::COLUMNS.join(", ")
+            ));
+            query.push_values(rows, |mut row, item| item.bind(&mut row));
+            if let Some(on_conflict) = &self.on_conflict {
+                query.push(" ON CONFLICT ").push(on_conflict);
+            }
+
+            query
+                .build()
+                .execute(&mut *transaction)
+                .await
+                .map_err(pg_error)?;
+        }
+
+        transaction.commit().await.map_err(pg_error)
+    }
+}
+
+fn pg_error(error: sqlx::Error) -> Error {
+    Error::Custom(format!("Postgres sink error: {}", error))
+}
@@ -24,6 +24,8 @@
 //! - **`InstructionType` Derivation**: Derive `InstructionType` enums that
 //!   mirror existing enum structures, providing a simplified, data-free version
 //!   of each variant.
+//! - **`CarbonDiff`**: Compare two values of a struct field by field, into a
+//!   generated changeset holding the new value of each changed field.
 //!
 //! ## Usage
 //!
@@ -49,8 +51,8 @@ use {
     quote::{format_ident, quote},
     syn::{
         parse::{Parse, ParseStream},
-        parse_macro_input, DeriveInput, Ident, Item, ItemEnum, Lit, Meta, NestedMeta, Token,
-        TypePath,
+        parse_macro_input, parse_quote, DeriveInput, Ident, Item, ItemEnum, Lit, Meta, NestedMeta,
+        Token, TypePath,
     },
 };
 
@@ -112,10 +114,24 @@ use {
 ///
 /// - The `#[carbon(discriminator = "0x...")]` attribute is optional. If not
 ///   provided, the deserialization proceeds without a discriminator check.
+/// - The `#[carbon(strategy = "...")]` attribute sets how the discriminator is
+///   matched, as a `DiscriminatorStrategy` of `carbon_core::discriminator`,
+///   such as `FirstByte` or `ByteAtOffset<165>`, or the path of a custom
+///   strategy. By default, the discriminator prefixes the data.
+/// - Fields added by a program upgrade can be marked `#[carbon(since_version =
+///   N)]`. Such fields must be `Option`s at the end of the struct, and are
+///   `None` when the data ends before them, so a single struct decodes both the
+///   old and the new layout. The layout seen is reported by the generated
+///   `carbon_core::deserialize::LayoutVersion` implementation.
+/// - `#[repr(C)]` structs deriving `bytemuck::Pod` can be marked
+///   `#[carbon(zero_copy)]`. They are then read in a single copy, and borrowed
+///   in place through the generated `carbon_core::deserialize::ZeroCopy`
+///   implementation.
 /// - Ensure the discriminator matches the data's format exactly, as the
 ///   deserialization will return `None` if there is a mismatch.
 /// - The macro will panic if the discriminator is invalid or not provided
-///   correctly as a hex string when expected.
+///   correctly as a hex string when expected. An invalid `strategy` is reported
+///   as a compile error.
 ///
 /// # Errors
 ///
@@ -129,24 +145,71 @@ pub fn carbon_deserialize_derive(input_token_stream: TokenStream) -> TokenStream
     let name = &input.ident;
 
     let discriminator = get_discriminator(&input.attrs).unwrap_or(quote! { &[] });
-    let deser = gen_borsh_deserialize(input_token_stream);
+    let strategy = match get_strategy(&input.attrs).transpose() {
+        Ok(strategy) => strategy.unwrap_or(quote! { carbon_core::discriminator::Prefix }),
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let deser =
+        versioned_struct_de(&input).unwrap_or_else(|| gen_borsh_deserialize(input_token_stream));
+
+    // Generic types are only deserializable when their parameters are.
+    let mut generics = input.generics.clone();
+    if !generics.params.is_empty() {
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote! { Self: carbon_core::borsh::BorshDeserialize });
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    if has_zero_copy(&input.attrs) {
+        if versioned_struct_de(&input).is_some() {
+            return syn::Error::new_spanned(
+                name,
+                "`zero_copy` types can't have `since_version` fields",
+            )
+            .to_compile_error()
+            .into();
+        }
+
+        let expanded = quote! {
+            #deser
+
+            #[automatically_derived]
+            impl #impl_generics carbon_core::deserialize::ZeroCopy for #name #ty_generics #where_clause {
+                fn load(data: &[u8]) -> Option<std::borrow::Cow<'_, Self>> {
+                    let discriminator: &[u8] = #discriminator;
+                    let rest = <#strategy as carbon_core::discriminator::DiscriminatorStrategy>::strip(
+                        discriminator,
+                        data,
+                    )?;
+
+                    carbon_core::deserialize::load_pod(rest)
+                }
+            }
+
+            #[automatically_derived]
+            impl #impl_generics carbon_core::deserialize::CarbonDeserialize for #name #ty_generics #where_clause {
+                fn deserialize(data: &[u8]) -> Option<Self> {
+                    <Self as carbon_core::deserialize::ZeroCopy>::load(data).map(|value| value.into_owned())
+                }
+            }
+        };
+
+        return TokenStream::from(expanded);
+    }
 
     let expanded = quote! {
         #deser
 
         #[automatically_derived]
-        impl carbon_core::deserialize::CarbonDeserialize for #name {
+        impl #impl_generics carbon_core::deserialize::CarbonDeserialize for #name #ty_generics #where_clause {
             fn deserialize(data: &[u8]) -> Option<Self> {
                 let discriminator: &[u8] = #discriminator;
-                if data.len() < discriminator.len() {
-                    return None;
-                }
-
-
-                let (disc, mut rest) = data.split_at(discriminator.len());
-                if disc != discriminator {
-                    return None;
-                }
+                let mut rest = <#strategy as carbon_core::discriminator::DiscriminatorStrategy>::strip(
+                    discriminator,
+                    data,
+                )?;
 
                  match carbon_core::borsh::BorshDeserialize::deserialize(&mut rest) {
                     Ok(res) => {
@@ -327,6 +390,272 @@ fn get_discriminator(attrs: &[syn::Attribute]) -> Option<quote::__private::Token
     })
 }
 
+/// Extracts the discriminator strategy from a set of attributes.
+///
+/// The strategy is given as `carbon(strategy = "...")`, naming a strategy of
+/// `carbon_core::discriminator`, like `FirstByte` or `DataSize<355>`, or the
+/// path of a custom `DiscriminatorStrategy`. Returns `None` if the attribute is
+/// not present.
+///
+/// # Errors
+///
+/// Returns an error spanning the value if it isn't a type.
+fn get_strategy(attrs: &[syn::Attribute]) -> Option<syn::Result<quote::__private::TokenStream>> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("carbon"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .find_map(|meta| {
+            let Meta::List(list) = meta else {
+                return None;
+            };
+
+            list.nested.iter().find_map(|nested| match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("strategy") => {
+                    let Lit::Str(lit_str) = &nv.lit else {
+                        return Some(Err(syn::Error::new_spanned(
+                            &nv.lit,
+                            "`strategy` must be a string",
+                        )));
+                    };
+                    let strategy: syn::Type = match syn::parse_str(&lit_str.value()) {
+                        Ok(strategy) => strategy,
+                        Err(_) => {
+                            return Some(Err(syn::Error::new_spanned(
+                                lit_str,
+                                "Invalid discriminator strategy",
+                            )))
+                        }
+                    };
+
+                    // Single segment names refer to the built-in strategies.
+                    Some(Ok(match &strategy {
+                        syn::Type::Path(path)
+                            if path.qself.is_none() && path.path.segments.len() == 1 =>
+                        {
+                            quote! { carbon_core::discriminator::#strategy }
+                        }
+                        _ => quote! { #strategy },
+                    }))
+                }
+                _ => None,
+            })
+        })
+}
+
+/// Extracts the version of a field from its `carbon(since_version = N)`
+/// attribute, or returns `None` if the attribute is not present.
+fn get_since_version(attrs: &[syn::Attribute]) -> Option<syn::Result<u32>> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("carbon"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .find_map(|meta| {
+            let Meta::List(list) = meta else {
+                return None;
+            };
+
+            list.nested.iter().find_map(|nested| match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("since_version") => {
+                    Some(match &nv.lit {
+                        Lit::Int(lit_int) => lit_int.base10_parse::<u32>(),
+                        lit => Err(syn::Error::new_spanned(
+                            lit,
+                            "`since_version` must be an integer",
+                        )),
+                    })
+                }
+                _ => None,
+            })
+        })
+}
+
+/// Returns `true` if the attributes contain `carbon(zero_copy)`.
+fn has_zero_copy(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("carbon"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .any(|meta| {
+            let Meta::List(list) = meta else {
+                return false;
+            };
+
+            list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(Meta::Path(path)) => path.is_ident("zero_copy"),
+                _ => false,
+            })
+        })
+}
+
+/// Returns `T` if `ty` is `Option<T>`.
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        syn::GenericArgument::Type(inner) if arguments.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// Generates the `BorshDeserialize` and `LayoutVersion` implementations of a
+/// struct with fields marked `#[carbon(since_version = N)]`, or returns `None`
+/// if no field is versioned.
+///
+/// The versioned fields are grouped by version. The fields of a group are only
+/// read if data remains once the previous fields are read, and are `None`
+/// otherwise. Once a group is absent, the following groups are absent too.
+fn versioned_struct_de(input: &DeriveInput) -> Option<TokenStream2> {
+    let syn::Data::Struct(data) = &input.data else {
+        return None;
+    };
+    let versions = data
+        .fields
+        .iter()
+        .map(|field| get_since_version(&field.attrs).transpose())
+        .collect::<syn::Result<Vec<_>>>();
+    let versions = match versions {
+        Ok(versions) if versions.iter().all(Option::is_none) => return None,
+        Ok(versions) => versions,
+        Err(err) => return Some(err.to_compile_error()),
+    };
+
+    Some(
+        versioned_struct_impls(input, &data.fields, &versions)
+            .unwrap_or_else(|err| err.to_compile_error()),
+    )
+}
+
+fn versioned_struct_impls(
+    input: &DeriveInput,
+    fields: &syn::Fields,
+    versions: &[Option<u32>],
+) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let syn::Fields::Named(_) = fields else {
+        return Err(syn::Error::new_spanned(
+            fields,
+            "`since_version` is only supported on structs with named fields",
+        ));
+    };
+
+    let mut base_reads = Vec::new();
+    let mut group_reads = Vec::new();
+    let mut field_names = Vec::new();
+    let mut field_types = Vec::new();
+    // The versioned fields, grouped by version.
+    let mut groups: Vec<(u32, Vec<(&Ident, &syn::Type)>)> = Vec::new();
+
+    for (field, version) in fields.iter().zip(versions) {
+        let field_name = field.ident.as_ref().expect("Named fields have a name");
+        field_names.push(field_name);
+
+        match version {
+            None if !groups.is_empty() => {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "fields without `since_version` must come before the versioned fields",
+                ));
+            }
+            None => {
+                let ty = &field.ty;
+                field_types.push(ty);
+                base_reads.push(quote! {
+                    let #field_name: #ty = borsh::BorshDeserialize::deserialize_reader(reader)?;
+                });
+            }
+            Some(version) => {
+                let inner = option_inner_type(&field.ty).ok_or_else(|| {
+                    syn::Error::new_spanned(&field.ty, "versioned fields must be `Option`s")
+                })?;
+                field_types.push(inner);
+
+                match groups.last_mut() {
+                    Some((last_version, group)) if last_version == version => {
+                        group.push((field_name, inner));
+                    }
+                    Some((last_version, _)) if *last_version > *version => {
+                        return Err(syn::Error::new_spanned(
+                            field,
+                            "versioned fields must be ordered by `since_version`",
+                        ));
+                    }
+                    _ => groups.push((*version, vec![(field_name, inner)])),
+                }
+            }
+        }
+    }
+
+    for (_, group) in &groups {
+        let names = group.iter().map(|(name, _)| name);
+        let nones = group.iter().map(|_| quote! { None });
+        let somes = group.iter().map(|(_, ty)| {
+            quote! {
+                Some(<#ty as borsh::BorshDeserialize>::deserialize_reader(&mut tail)?)
+            }
+        });
+        group_reads.push(quote! {
+            let (#(#names,)*) = if tail.is_empty() {
+                (#(#nones,)*)
+            } else {
+                (#(#somes,)*)
+            };
+        });
+    }
+
+    let version_checks = groups.iter().rev().map(|(version, group)| {
+        let (first_field, _) = group[0];
+        quote! {
+            if self.#first_field.is_some() {
+                return #version;
+            }
+        }
+    });
+
+    let mut generics = input.generics.clone();
+    if !generics.params.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for ty in &field_types {
+            where_clause
+                .predicates
+                .push(parse_quote! { #ty: borsh::BorshDeserialize });
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics borsh::BorshDeserialize for #name #ty_generics #where_clause {
+            fn deserialize_reader<R: borsh::maybestd::io::Read>(reader: &mut R) -> borsh::maybestd::io::Result<Self> {
+                #(#base_reads)*
+
+                // The versioned fields are only read if data remains.
+                let mut remaining = Vec::new();
+                borsh::maybestd::io::Read::read_to_end(reader, &mut remaining)?;
+                let mut tail = remaining.as_slice();
+                #(#group_reads)*
+
+                Ok(Self { #(#field_names,)* })
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics carbon_core::deserialize::LayoutVersion for #name #ty_generics #where_clause {
+            fn layout_version(&self) -> u32 {
+                #(#version_checks)*
+                0
+            }
+        }
+    })
+}
+
 /// Represents the parsed input for the `instruction_decoder_collection!` macro.
 ///
 /// The `InstructionMacroInput` struct holds the essential elements required
@@ -848,3 +1177,143 @@ pub fn instruction_type_derive(input: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+/// Automatically generates an implementation of the `CarbonDiff` trait.
+///
+/// This derive macro compares two values of a struct field by field. It
+/// generates a `<Name>Changeset` struct holding an `Option` of each field,
+/// which is set to the new value of the fields that changed, and implements
+/// `carbon_core::account_diff::CarbonDiff` returning it.
+///
+/// # Syntax
+///
+/// ```ignore
+/// #[derive(CarbonDiff, Debug, Clone, PartialEq)]
+/// pub struct Pool {
+///     pub base_reserve: u64,
+///     pub quote_reserve: u64,
+///     pub paused: bool,
+/// }
+/// ```
+///
+/// # Example
+///
+/// ```ignore
+/// use carbon_core::account_diff::CarbonDiff as _;
+///
+/// let changeset = old_pool.diff(&new_pool);
+///
+/// if changeset.paused == Some(true) {
+///     println!("pool paused");
+/// }
+/// assert_eq!(
+///     changeset.changed_fields().collect::<Vec<_>>(),
+///     ["base_reserve", "paused"]
+/// );
+/// ```
+///
+/// # Parameters
+///
+/// - `input`: A `TokenStream` representing the input struct, whose named fields
+///   are compared.
+///
+/// # Return
+///
+/// Returns a `TokenStream` containing the `<Name>Changeset` struct, its
+/// `is_empty` and `changed_fields` methods, and the `CarbonDiff`
+/// implementation.
+///
+/// # Notes
+///
+/// - The fields must implement `Clone`, `PartialEq` and `Debug`.
+/// - The changeset has the visibility and the generics of the input struct.
+/// - Only structs with named fields are supported. Decoder account enums are
+///   matched first, and their variants compared.
+#[proc_macro_derive(CarbonDiff)]
+pub fn carbon_diff_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let changeset_name = format_ident!("{}Changeset", name);
+
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => &fields.named,
+        _ => {
+            return syn::Error::new_spanned(
+                name,
+                "`CarbonDiff` can only be derived for structs with named fields",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+
+    let field_names = fields
+        .iter()
+        .map(|field| field.ident.as_ref().expect("named field"))
+        .collect::<Vec<_>>();
+    let field_types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let field_name_strings = field_names
+        .iter()
+        .map(|field_name| field_name.to_string())
+        .collect::<Vec<_>>();
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // The compared fields must be comparable and cloneable.
+    let mut diff_generics = input.generics.clone();
+    if !diff_generics.params.is_empty() {
+        let diff_where_clause = diff_generics.make_where_clause();
+        for ty in &field_types {
+            diff_where_clause
+                .predicates
+                .push(parse_quote! { #ty: ::core::clone::Clone + ::core::cmp::PartialEq });
+        }
+    }
+    let (_, _, diff_where_clause) = diff_generics.split_for_impl();
+
+    let expanded = quote! {
+        #[derive(Debug, Clone, Default, PartialEq)]
+        #vis struct #changeset_name #impl_generics #where_clause {
+            #(pub #field_names: Option<#field_types>,)*
+        }
+
+        impl #impl_generics #changeset_name #ty_generics #where_clause {
+            /// Returns `true` if no field changed.
+            pub fn is_empty(&self) -> bool {
+                true #(&& self.#field_names.is_none())*
+            }
+
+            /// Returns the names of the fields that changed, in declaration
+            /// order.
+            pub fn changed_fields(&self) -> impl Iterator<Item = &'static str> {
+                [#((#field_name_strings, self.#field_names.is_some()),)*]
+                    .into_iter()
+                    .filter_map(|(field, changed)| changed.then_some(field))
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics carbon_core::account_diff::CarbonDiff for #name #ty_generics #diff_where_clause {
+            type Changeset = #changeset_name #ty_generics;
+
+            fn diff(&self, new: &Self) -> Self::Changeset {
+                #changeset_name {
+                    #(
+                        #field_names: if self.#field_names != new.#field_names {
+                            Some(new.#field_names.clone())
+                        } else {
+                            None
+                        },
+                    )*
+                }
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
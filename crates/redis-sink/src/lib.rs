@@ -0,0 +1,384 @@
+//! Publishes decoded updates to Redis Streams or Pub/Sub channels, for
+//! real-time consumers such as dashboards.
+//!
+//! Database sinks suit indexers queried after the fact, but consumers reacting
+//! to updates as they happen need them pushed. This crate provides a sink
+//! writing decoded accounts and instructions to Redis, on a stream or a channel
+//! per program and update type, and optionally caching the latest state of
+//! each account under its pubkey.
+//!
+//! # Overview
+//!
+//! - **`RedisSink`**: Writes batches of updates in a single Redis pipeline,
+//!   appending them to streams with `XADD`, or publishing them with `PUBLISH`.
+//! - **`RedisProcessor`**: A cloneable `Processor` buffering its inputs, and
+//!   writing them to a `RedisSink` whenever the slot advances or the batch is
+//!   full.
+//! - **`RedisUpdate`**: The inputs a `RedisSink` can write, implemented for
+//!   decoded accounts and instructions whose data implements `Serialize`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_redis_sink::{RedisProcessor, RedisSink};
+//!
+//! let sink = RedisSink::connect("redis://127.0.0.1/", "pumpfun")
+//!     .await?
+//!     .max_stream_len(100_000)
+//!     .cache_accounts(Some(Duration::from_secs(3_600)));
+//!
+//! let accounts = RedisProcessor::new(sink.clone(), 500);
+//! let instructions = RedisProcessor::new(sink, 500);
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .account(PumpfunDecoder, accounts.clone())
+//!     .instruction(PumpfunDecoder, instructions.clone())
+//!     // Write the updates still buffered once the datasources finished.
+//!     .flush_on_shutdown(accounts)
+//!     .flush_on_shutdown(instructions)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Updates are written as JSON to `{namespace}:{program_id}:account` and
+//!   `{namespace}:{program_id}:instruction`, in the `payload` field of stream
+//!   entries, or as the message of Pub/Sub channels.
+//! - Cached accounts are stored under `{namespace}:account:{pubkey}`, with the
+//!   same JSON as their updates. Updates delivered out of order can overwrite a
+//!   later state of an account.
+//! - Pub/Sub messages are only received by the subscribers connected when they
+//!   are published, while streams keep their entries until trimmed.
+//! - Buffered updates are lost if the process exits without flushing them,
+//!   either through `PipelineBuilder::flush_on_shutdown` or `flush`.
+
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        account::AccountProcessorInputType,
+        batch::{BatchProcessor, BatchWriter},
+        error::{CarbonResult, Error},
+        finality::HasSlot,
+        instruction::InstructionProcessorInputType,
+        metrics::MetricsCollection,
+        processor::{Flush, Processor},
+    },
+    redis::aio::ConnectionManager,
+    serde::Serialize,
+    solana_pubkey::Pubkey,
+    std::{sync::Arc, time::Duration},
+};
+
+/// A processor input written to Redis by a `RedisSink`.
+pub trait RedisUpdate: HasSlot + Send + Sync + 'static {
+    /// Returns the program the update belongs to.
+    fn program_id(&self) -> Pubkey;
+
+    /// Returns the type of the update, such as `account` or `instruction`.
+    fn update_type(&self) -> &'static str;
+
+    /// Returns the account whose latest state is cached with this update, if
+    /// any.
+    fn cached_account(&self) -> Option<Pubkey> {
+        None
+    }
+
+    /// Serializes the update to JSON.
+    fn to_json(&self) -> CarbonResult<String>;
+}
+
+#[derive(Serialize)]
+struct AccountPayload<'a, T> {
+    slot: u64,
+    pubkey: String,
+    owner: String,
+    lamports: u64,
+    data: &'a T,
+}
+
+impl<T: Serialize + Send + Sync + 'static> RedisUpdate for AccountProcessorInputType<T> {
+    fn program_id(&self) -> Pubkey {
+        self.1.owner
+    }
+
+    fn update_type(&self) -> &'static str {
+        "account"
+    }
+
+    fn cached_account(&self) -> Option<Pubkey> {
+        Some(self.0.pubkey)
+    }
+
+    fn to_json(&self) -> CarbonResult<String> {
+        let (metadata, account, _) = self;
+        to_json(&AccountPayload {
+            slot: metadata.slot,
+            pubkey: metadata.pubkey.to_string(),
+            owner: account.owner.to_string(),
+            lamports: account.lamports,
+            data: &account.data,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct InstructionPayload<'a, T> {
+    slot: u64,
+    signature: String,
+    stack_height: u32,
+    index: u32,
+    program_id: String,
+    accounts: Vec<String>,
+    data: &'a T,
+}
+
+impl<T: Serialize + Send + Sync + 'static> RedisUpdate for InstructionProcessorInputType<T> {
+    fn program_id(&self) -> Pubkey {
+        self.1.program_id
+    }
+
+    fn update_type(&self) -> &'static str {
+        "instruction"
+    }
+
+    fn to_json(&self) -> CarbonResult<String> {
+        let (metadata, instruction, _, _) = self;
+        to_json(&InstructionPayload {
+            slot: metadata.transaction_metadata.slot,
+            signature: metadata.transaction_metadata.signature.to_string(),
+            stack_height: metadata.stack_height,
+            index: metadata.index,
+            program_id: instruction.program_id.to_string(),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account| account.pubkey.to_string())
+                .collect(),
+            data: &instruction.data,
+        })
+    }
+}
+
+fn to_json(payload: &impl Serialize) -> CarbonResult<String> {
+    serde_json::to_string(payload)
+        .map_err(|e| Error::Custom(format!("Failed to serialize update: {e}")))
+}
+
+/// Returns the stream or channel `update` is written to.
+fn update_key(namespace: &str, update: &impl RedisUpdate) -> String {
+    format!(
+        "{}:{}:{}",
+        namespace,
+        update.program_id(),
+        update.update_type()
+    )
+}
+
+/// Returns the key the latest state of `pubkey` is cached under.
+fn account_key(namespace: &str, pubkey: &Pubkey) -> String {
+    format!("{}:account:{}", namespace, pubkey)
+}
+
+/// Writes batches of updates to Redis Streams or Pub/Sub channels.
+///
+/// Each batch is sent in a single pipeline, so it takes one round trip to the
+/// server. Clones share the same connection.
+#[derive(Clone)]
+pub struct RedisSink {
+    connection: ConnectionManager,
+    namespace: String,
+    pub_sub: bool,
+    max_stream_len: Option<usize>,
+    cache_accounts: bool,
+    cache_ttl: Option<Duration>,
+}
+
+impl RedisSink {
+    /// Connects to the Redis server at `url`, writing updates under
+    /// `namespace`.
+    pub async fn connect(url: &str, namespace: impl Into<String>) -> CarbonResult<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| Error::Custom(format!("Invalid Redis URL: {e}")))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| Error::Custom(format!("Failed to connect to Redis: {e}")))?;
+
+        Ok(Self::new(connection, namespace))
+    }
+
+    /// Creates a sink from an existing connection, appending updates to
+    /// streams.
+    pub fn new(connection: ConnectionManager, namespace: impl Into<String>) -> Self {
+        Self {
+            connection,
+            namespace: namespace.into(),
+            pub_sub: false,
+            max_stream_len: None,
+            cache_accounts: false,
+            cache_ttl: None,
+        }
+    }
+
+    /// Publishes updates to Pub/Sub channels instead of appending them to
+    /// streams.
+    pub fn pub_sub(mut self) -> Self {
+        self.pub_sub = true;
+        self
+    }
+
+    /// Trims streams to about `max_stream_len` entries as updates are
+    /// appended.
+    pub fn max_stream_len(mut self, max_stream_len: usize) -> Self {
+        self.max_stream_len = Some(max_stream_len);
+        self
+    }
+
+    /// Caches the latest state of every account written, expiring after `ttl`
+    /// if set.
+    pub fn cache_accounts(mut self, ttl: Option<Duration>) -> Self {
+        self.cache_accounts = true;
+        self.cache_ttl = ttl;
+        self
+    }
+
+    fn pipeline<U: RedisUpdate>(&self, updates: &[U]) -> CarbonResult<redis::Pipeline> {
+        let mut pipe = redis::pipe();
+        for update in updates {
+            let key = update_key(&self.namespace, update);
+            let payload = update.to_json()?;
+
+            if self.pub_sub {
+                pipe.cmd("PUBLISH").arg(&key).arg(&payload).ignore();
+            } else {
+                pipe.cmd("XADD").arg(&key);
+                if let Some(max_stream_len) = self.max_stream_len {
+                    pipe.arg("MAXLEN").arg("~").arg(max_stream_len);
+                }
+                pipe.arg("*").arg("payload").arg(&payload).ignore();
+            }
+
+            if let Some(pubkey) = update.cached_account().filter(|_| self.cache_accounts) {
+                pipe.cmd("SET")
+                    .arg(account_key(&self.namespace, &pubkey))
+                    .arg(&payload);
+                if let Some(ttl) = self.cache_ttl {
+                    pipe.arg("PX").arg(ttl.as_millis() as u64);
+                }
+                pipe.ignore();
+            }
+        }
+
+        Ok(pipe)
+    }
+}
+
+#[async_trait]
+impl<U: RedisUpdate> BatchWriter<U> for RedisSink {
+    async fn write_batch(&mut self, updates: &[U]) -> CarbonResult<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        let () = self
+            .pipeline(updates)?
+            .query_async(&mut self.connection)
+            .await
+            .map_err(|e| Error::Custom(format!("Failed to write updates to Redis: {e}")))?;
+
+        Ok(())
+    }
+}
+
+/// A processor writing its inputs to a `RedisSink`.
+///
+/// Inputs are buffered until an input of a later slot arrives, or until
+/// `max_batch_size` inputs are buffered, so each slot is written in as few
+/// round trips as possible. Clones share the same buffer, so a handle can be
+/// passed to `PipelineBuilder::flush_on_shutdown` to write the remaining
+/// inputs once the pipeline stops.
+pub struct RedisProcessor<U> {
+    batch: BatchProcessor<U>,
+}
+
+impl<U> Clone for RedisProcessor<U> {
+    fn clone(&self) -> Self {
+        Self {
+            batch: self.batch.clone(),
+        }
+    }
+}
+
+impl<U: RedisUpdate> RedisProcessor<U> {
+    /// Creates a processor writing batches of at most `max_batch_size` inputs
+    /// to `sink`.
+    pub fn new(sink: RedisSink, max_batch_size: usize) -> Self {
+        Self {
+            batch: BatchProcessor::new(sink, max_batch_size).slot_aligned(),
+        }
+    }
+
+    /// Writes every buffered input, including those of the slot in progress.
+    pub async fn flush(&self) -> CarbonResult<()> {
+        self.batch.flush().await
+    }
+}
+
+#[async_trait]
+impl<U: RedisUpdate> Processor for RedisProcessor<U> {
+    type InputType = U;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        self.batch.process(data, metrics).await
+    }
+}
+
+#[async_trait]
+impl<U: RedisUpdate> Flush for RedisProcessor<U> {
+    async fn flush_pending(&self, metrics: Arc<MetricsCollection>) -> CarbonResult<()> {
+        self.batch.flush_pending(metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        carbon_core::account::{AccountMetadata, DecodedAccount},
+    };
+
+    #[test]
+    fn test_account_update() {
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let update: AccountProcessorInputType<u64> = (
+            AccountMetadata::new(42, pubkey),
+            DecodedAccount {
+                lamports: 1_000,
+                data: 7,
+                owner,
+                executable: false,
+                rent_epoch: 0,
+            },
+            solana_account::Account::default(),
+        );
+
+        assert_eq!(
+            update_key("pumpfun", &update),
+            format!("pumpfun:{}:account", owner)
+        );
+        assert_eq!(update.cached_account(), Some(pubkey));
+
+        let payload: serde_json::Value = serde_json::from_str(&update.to_json().unwrap()).unwrap();
+        assert_eq!(payload["slot"], 42);
+        assert_eq!(payload["pubkey"], pubkey.to_string());
+        assert_eq!(payload["lamports"], 1_000);
+        assert_eq!(payload["data"], 7);
+    }
+}
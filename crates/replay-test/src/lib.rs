@@ -0,0 +1,351 @@
+//! Checks properties of decoded data while replaying recordings through
+//! processors, turning recorded production traffic into a property-testing
+//! corpus.
+//!
+//! Unit tests cover the inputs their authors thought of, while recordings of
+//! live pipelines hold the inputs programs actually produce. A `ReplayTest`
+//! replays recordings written by an `UpdateRecorder` through a pipeline whose
+//! processors are wrapped in `CheckedProcessor`s. Each input is checked
+//! against the invariants declared on its processor, such as "pool reserves
+//! are never negative" or "the transfers of a swap sum to its amounts", before
+//! being forwarded to the processor, and the violations are collected in a
+//! `ReplayReport`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_replay_test::ReplayTest;
+//!
+//! #[tokio::test]
+//! async fn test_pool_invariants() {
+//!     let replay = ReplayTest::new("tests/recordings/raydium.jsonl.gz");
+//!
+//!     let pools = replay
+//!         .check(PoolProcessor)
+//!         .invariant("reserves are never negative", |(_, account, _)| {
+//!             match &account.data {
+//!                 RaydiumAmmV4Account::AmmInfo(pool) => pool.base_reserve >= 0,
+//!                 _ => true,
+//!             }
+//!         });
+//!     let swaps = replay
+//!         .check(SwapProcessor)
+//!         .property("transfers match the swap amounts", check_swap_transfers);
+//!
+//!     let report = replay
+//!         .run(
+//!             replay
+//!                 .pipeline()
+//!                 .account(RaydiumAmmV4Decoder, pools)
+//!                 .transaction(SWAP_SCHEMA.clone(), swaps),
+//!         )
+//!         .await
+//!         .unwrap();
+//!
+//!     report.assert_holds();
+//! }
+//! ```
+//!
+//! # Notes
+//!
+//! - Inputs are forwarded to the wrapped processors whether they hold or not,
+//!   so stateful processors see the whole replay.
+//! - Only the first `MAX_REPORTED_VIOLATIONS` violations are kept in the
+//!   report, all of them being counted.
+//! - Properties are checked in the order of the pipeline, so a property keeping
+//!   state across inputs, like running balances, sees them in recording order.
+
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::CarbonResult,
+        finality::HasSlot,
+        metrics::MetricsCollection,
+        pipeline::{Pipeline, PipelineBuilder, ShutdownStrategy},
+        processor::Processor,
+    },
+    carbon_file_replay_datasource::FileReplayDatasource,
+    std::{
+        fmt,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// The number of violations kept in a `ReplayReport`.
+pub const MAX_REPORTED_VIOLATIONS: usize = 100;
+
+/// A property of processor inputs, checked against every input of a replay.
+///
+/// Closures returning `Err` with a description of the violation implement
+/// this trait, and may keep state across inputs.
+pub trait Property<I>: Send + Sync {
+    /// Returns a description of the violation if `input` breaks the property.
+    fn check(&mut self, input: &I) -> Result<(), String>;
+}
+
+impl<I, F> Property<I> for F
+where
+    F: FnMut(&I) -> Result<(), String> + Send + Sync,
+{
+    fn check(&mut self, input: &I) -> Result<(), String> {
+        self(input)
+    }
+}
+
+/// An input breaking a property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub property: String,
+    pub slot: u64,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` violated at slot {}: {}",
+            self.property, self.slot, self.message
+        )
+    }
+}
+
+/// The outcome of the checks of a replay.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    /// The number of inputs checked.
+    pub checked: u64,
+    /// The number of violations, including the ones not kept.
+    pub violation_count: u64,
+    /// The first `MAX_REPORTED_VIOLATIONS` violations.
+    pub violations: Vec<Violation>,
+}
+
+impl ReplayReport {
+    /// Returns `true` if every input held every property.
+    pub fn holds(&self) -> bool {
+        self.violation_count == 0
+    }
+
+    /// Panics with the violations if any input broke a property.
+    pub fn assert_holds(&self) {
+        if self.holds() {
+            return;
+        }
+
+        let violations = self
+            .violations
+            .iter()
+            .map(|violation| format!("  - {}", violation))
+            .collect::<Vec<_>>()
+            .join("\n");
+        panic!(
+            "{} of {} checked inputs broke a property:\n{}",
+            self.violation_count, self.checked, violations
+        );
+    }
+
+    fn record(&mut self, violation: Violation) {
+        self.violation_count += 1;
+        if self.violations.len() < MAX_REPORTED_VIOLATIONS {
+            self.violations.push(violation);
+        }
+    }
+}
+
+/// Replays recordings through a pipeline of checked processors.
+pub struct ReplayTest {
+    paths: Vec<PathBuf>,
+    report: Arc<Mutex<ReplayReport>>,
+}
+
+impl ReplayTest {
+    /// Creates a test replaying a recording.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            paths: vec![path.into()],
+            report: Arc::new(Mutex::new(ReplayReport::default())),
+        }
+    }
+
+    /// Replays another recording after the previous ones.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// Wraps a processor to check its inputs against the properties declared
+    /// on the returned `CheckedProcessor`.
+    pub fn check<P: Processor>(&self, processor: P) -> CheckedProcessor<P> {
+        CheckedProcessor {
+            processor,
+            properties: Vec::new(),
+            report: self.report.clone(),
+        }
+    }
+
+    /// Returns a pipeline builder replaying the recordings as fast as
+    /// possible, and processing every update before shutting down.
+    pub fn pipeline(&self) -> PipelineBuilder {
+        let datasource = self.paths[1..].iter().fold(
+            FileReplayDatasource::new(&self.paths[0]),
+            |datasource, path| datasource.file(path),
+        );
+
+        Pipeline::builder()
+            .datasource(datasource)
+            .shutdown_strategy(ShutdownStrategy::ProcessPending)
+    }
+
+    /// Runs the pipeline until the recordings are replayed, and returns the
+    /// report of its checked processors.
+    pub async fn run(&self, pipeline: PipelineBuilder) -> CarbonResult<ReplayReport> {
+        pipeline.build()?.run().await?;
+
+        Ok(self.report())
+    }
+
+    /// Returns the report of the inputs checked so far.
+    pub fn report(&self) -> ReplayReport {
+        self.report
+            .lock()
+            .expect("replay report lock poisoned")
+            .clone()
+    }
+}
+
+/// A processor checking its inputs against properties before forwarding them
+/// to the wrapped processor.
+pub struct CheckedProcessor<P: Processor> {
+    processor: P,
+    properties: Vec<(String, Box<dyn Property<P::InputType>>)>,
+    report: Arc<Mutex<ReplayReport>>,
+}
+
+impl<P: Processor> CheckedProcessor<P> {
+    /// Checks that every input satisfies `predicate`.
+    pub fn invariant(
+        self,
+        name: impl Into<String>,
+        predicate: impl Fn(&P::InputType) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.property(name, move |input: &P::InputType| {
+            if predicate(input) {
+                Ok(())
+            } else {
+                Err("the invariant doesn't hold".to_string())
+            }
+        })
+    }
+
+    /// Checks every input against `property`.
+    pub fn property(
+        mut self,
+        name: impl Into<String>,
+        property: impl Property<P::InputType> + 'static,
+    ) -> Self {
+        self.properties.push((name.into(), Box::new(property)));
+        self
+    }
+}
+
+#[async_trait]
+impl<P> Processor for CheckedProcessor<P>
+where
+    P: Processor + Send,
+    P::InputType: HasSlot + Send,
+{
+    type InputType = P::InputType;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let violations = self
+            .properties
+            .iter_mut()
+            .filter_map(|(name, property)| {
+                property.check(&data).err().map(|message| Violation {
+                    property: name.clone(),
+                    slot: data.slot(),
+                    message,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let mut report = self.report.lock().expect("replay report lock poisoned");
+            report.checked += 1;
+            for violation in violations {
+                log::warn!("{}", violation);
+                report.record(violation);
+            }
+        }
+
+        self.processor.process(data, metrics).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, carbon_core::datasource::AccountDeletion, solana_pubkey::Pubkey};
+
+    struct CountingProcessor(u64);
+
+    #[async_trait]
+    impl Processor for CountingProcessor {
+        type InputType = AccountDeletion;
+
+        async fn process(
+            &mut self,
+            _data: AccountDeletion,
+            _metrics: Arc<MetricsCollection>,
+        ) -> CarbonResult<()> {
+            self.0 += 1;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checked_processor_reports_violations() {
+        let replay = ReplayTest::new("recording.jsonl");
+        let mut last_slot = 0;
+        let mut processor = replay
+            .check(CountingProcessor(0))
+            .invariant("slot is even", |deletion| deletion.slot % 2 == 0)
+            .property("slots increase", move |deletion: &AccountDeletion| {
+                let previous = std::mem::replace(&mut last_slot, deletion.slot);
+                if deletion.slot >= previous {
+                    Ok(())
+                } else {
+                    Err(format!("slot {} follows slot {}", deletion.slot, previous))
+                }
+            });
+
+        for slot in [2, 4, 3] {
+            let deletion = AccountDeletion {
+                pubkey: Pubkey::new_unique(),
+                slot,
+            };
+            processor
+                .process(deletion, Arc::new(MetricsCollection::new(vec![])))
+                .await
+                .unwrap();
+        }
+
+        let report = replay.report();
+        assert_eq!(processor.processor.0, 3);
+        assert_eq!(report.checked, 3);
+        assert!(!report.holds());
+        assert_eq!(
+            report
+                .violations
+                .iter()
+                .map(|violation| violation.property.as_str())
+                .collect::<Vec<_>>(),
+            ["slot is even", "slots increase"]
+        );
+    }
+}
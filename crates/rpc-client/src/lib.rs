@@ -0,0 +1,480 @@
+//! A rate-limited RPC client spreading requests over multiple endpoints.
+//!
+//! RPC datasources issue a large number of requests, which a single endpoint
+//! quickly rate-limits. `MultiRpcConfig` describes a set of endpoints, each
+//! with a weight and an optional request rate limit, and builds a regular
+//! `RpcClient` sending requests through a `MultiRpcSender`:
+//!
+//! - Requests are distributed with a smooth weighted round-robin.
+//! - Each endpoint is throttled to its configured requests per second.
+//! - Requests failing with a timeout, a connection error, a 429 or a 5xx
+//!   response are retried on another endpoint, and the failing endpoint is
+//!   skipped for a cooldown period.
+//! - Once every endpoint failed, retries are delayed with a jittered
+//!   exponential backoff.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_rpc_client::{MultiRpcConfig, RpcEndpoint};
+//!
+//! let rpc = MultiRpcConfig::new(vec![
+//!     RpcEndpoint::new("https://my-paid-rpc.example.com").weight(3).rate_limit(50),
+//!     RpcEndpoint::new("https://api.mainnet-beta.solana.com").rate_limit(5),
+//! ]);
+//!
+//! let rpc_client = rpc.rpc_client(CommitmentConfig::confirmed());
+//! ```
+//!
+//! # Notes
+//!
+//! - Errors returned by the RPC node itself, such as an invalid parameter, are
+//!   returned as is and never retried.
+//! - The underlying `HttpSender` briefly retries 429 responses on its own
+//!   before the request fails over to another endpoint.
+//! - `RpcLookupTableSource` fetches address lookup tables for the
+//!   `AddressLookupTableResolver` of `carbon-core`.
+//! - `RpcDomainNameSource` looks up the primary `.sol` domain of wallets for
+//!   the `DomainNameResolver` of `carbon-core`.
+
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        address_lookup_table::LookupTableSource,
+        domain_names::DomainNameSource,
+        error::{CarbonResult, Error},
+    },
+    carbon_name_service_decoder::{
+        accounts::{name_record_header::NameRecordHeader, reverse_lookup::ReverseLookup},
+        domain,
+    },
+    serde_json::Value,
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+        http_sender::HttpSender,
+        nonblocking::rpc_client::RpcClient,
+        rpc_client::RpcClientConfig,
+        rpc_request::RpcRequest,
+        rpc_sender::{RpcSender, RpcTransportStats},
+    },
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+        sync::{Arc, Mutex, MutexGuard},
+        time::{Duration, Instant},
+    },
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_RETRIES: u32 = 3;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+const FAILOVER_COOLDOWN: Duration = Duration::from_secs(10);
+/// The maximum number of accounts of a `getMultipleAccounts` request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// An RPC endpoint of a `MultiRpcConfig`.
+#[derive(Debug, Clone)]
+pub struct RpcEndpoint {
+    pub url: String,
+    pub weight: u32,
+    pub requests_per_second: Option<u32>,
+}
+
+impl RpcEndpoint {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            weight: 1,
+            requests_per_second: None,
+        }
+    }
+
+    /// Sets the share of requests sent to this endpoint, relative to the
+    /// weights of the other endpoints.
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = weight.max(1);
+        self
+    }
+
+    /// Limits the number of requests sent to this endpoint per second.
+    pub fn rate_limit(mut self, requests_per_second: u32) -> Self {
+        self.requests_per_second = Some(requests_per_second.max(1));
+        self
+    }
+}
+
+/// The endpoints and retry policy of a multi-endpoint RPC client.
+#[derive(Debug, Clone)]
+pub struct MultiRpcConfig {
+    pub endpoints: Vec<RpcEndpoint>,
+    pub request_timeout: Duration,
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub failover_cooldown: Duration,
+}
+
+impl MultiRpcConfig {
+    pub fn new(endpoints: Vec<RpcEndpoint>) -> Self {
+        Self {
+            endpoints,
+            request_timeout: REQUEST_TIMEOUT,
+            max_retries: MAX_RETRIES,
+            initial_backoff: INITIAL_BACKOFF,
+            max_backoff: MAX_BACKOFF,
+            failover_cooldown: FAILOVER_COOLDOWN,
+        }
+    }
+
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets how long a failing endpoint is skipped for.
+    pub fn failover_cooldown(mut self, failover_cooldown: Duration) -> Self {
+        self.failover_cooldown = failover_cooldown;
+        self
+    }
+
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Builds an `RpcClient` sending its requests through a `MultiRpcSender`.
+    pub fn rpc_client(&self, commitment: CommitmentConfig) -> RpcClient {
+        RpcClient::new_sender(
+            MultiRpcSender::new(self.clone()),
+            RpcClientConfig::with_commitment(commitment),
+        )
+    }
+}
+
+struct EndpointState {
+    endpoint: RpcEndpoint,
+    sender: HttpSender,
+    next_request: Mutex<Instant>,
+    cooldown_until: Mutex<Option<Instant>>,
+}
+
+impl EndpointState {
+    fn is_cooling_down(&self, now: Instant) -> bool {
+        lock(&self.cooldown_until).is_some_and(|until| until > now)
+    }
+
+    /// Waits until the rate limit of the endpoint allows another request.
+    async fn acquire(&self) {
+        let Some(requests_per_second) = self.endpoint.requests_per_second else {
+            return;
+        };
+        let interval = Duration::from_secs(1) / requests_per_second;
+
+        let wait = {
+            let mut next_request = lock(&self.next_request);
+            let now = Instant::now();
+            let start = (*next_request).max(now);
+            *next_request = start + interval;
+            start - now
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// An `RpcSender` distributing requests over multiple endpoints, with
+/// per-endpoint rate limits and failover.
+pub struct MultiRpcSender {
+    config: MultiRpcConfig,
+    endpoints: Vec<EndpointState>,
+    current_weights: Mutex<Vec<i64>>,
+}
+
+impl MultiRpcSender {
+    pub fn new(config: MultiRpcConfig) -> Self {
+        let endpoints: Vec<EndpointState> = config
+            .endpoints
+            .iter()
+            .map(|endpoint| EndpointState {
+                endpoint: endpoint.clone(),
+                sender: HttpSender::new_with_timeout(endpoint.url.clone(), config.request_timeout),
+                next_request: Mutex::new(Instant::now()),
+                cooldown_until: Mutex::new(None),
+            })
+            .collect();
+
+        Self {
+            current_weights: Mutex::new(vec![0; endpoints.len()]),
+            config,
+            endpoints,
+        }
+    }
+
+    /// Picks the next endpoint with a smooth weighted round-robin, skipping
+    /// the endpoints already tried for the request and, unless all of them
+    /// are, the endpoints cooling down.
+    fn select(&self, tried: &[usize]) -> Option<usize> {
+        let now = Instant::now();
+        let untried: Vec<usize> = (0..self.endpoints.len())
+            .filter(|index| !tried.contains(index))
+            .collect();
+        let healthy: Vec<usize> = untried
+            .iter()
+            .copied()
+            .filter(|index| !self.endpoints[*index].is_cooling_down(now))
+            .collect();
+        let candidates = if healthy.is_empty() { untried } else { healthy };
+
+        let mut current_weights = lock(&self.current_weights);
+        let mut total_weight = 0;
+        let mut selected: Option<usize> = None;
+        for index in candidates {
+            let weight = i64::from(self.endpoints[index].endpoint.weight);
+            total_weight += weight;
+            current_weights[index] += weight;
+
+            if selected.is_none_or(|selected| current_weights[index] > current_weights[selected]) {
+                selected = Some(index);
+            }
+        }
+
+        let selected = selected?;
+        current_weights[selected] -= total_weight;
+
+        Some(selected)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.config.max_backoff);
+
+        // Up to 50% of jitter, so that concurrent requests don't retry in
+        // lockstep.
+        let jitter = RandomState::new().build_hasher().finish() % 500;
+        backoff + backoff.mul_f64(jitter as f64 / 1000.0)
+    }
+}
+
+#[async_trait]
+impl RpcSender for MultiRpcSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let mut tried = Vec::new();
+        let mut attempt = 0;
+
+        loop {
+            let index = match self.select(&tried) {
+                Some(index) => index,
+                None => {
+                    // Every endpoint failed, back off before another round.
+                    if self.endpoints.is_empty() {
+                        return Err(ClientErrorKind::Custom(
+                            "No RPC endpoints configured".to_string(),
+                        )
+                        .into());
+                    }
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    tried.clear();
+                    continue;
+                }
+            };
+            let endpoint = &self.endpoints[index];
+
+            endpoint.acquire().await;
+
+            let error = match endpoint.sender.send(request, params.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(error) if is_retryable(&error) => error,
+                Err(error) => return Err(error),
+            };
+
+            *lock(&endpoint.cooldown_until) = Some(Instant::now() + self.config.failover_cooldown);
+
+            if attempt >= self.config.max_retries {
+                return Err(error);
+            }
+
+            log::warn!(
+                "RPC request {} to {} failed, retrying on another endpoint: {}",
+                request,
+                endpoint.endpoint.url,
+                error
+            );
+            tried.push(index);
+            attempt += 1;
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.endpoints
+            .iter()
+            .fold(RpcTransportStats::default(), |mut total_stats, endpoint| {
+                let stats = endpoint.sender.get_transport_stats();
+                total_stats.request_count += stats.request_count;
+                total_stats.elapsed_time += stats.elapsed_time;
+                total_stats.rate_limited_time += stats.rate_limited_time;
+                total_stats
+            })
+    }
+
+    fn url(&self) -> String {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.endpoint.url.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// A `LookupTableSource` fetching address lookup tables over RPC.
+pub struct RpcLookupTableSource {
+    rpc_client: Arc<RpcClient>,
+}
+
+impl RpcLookupTableSource {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self { rpc_client }
+    }
+}
+
+#[async_trait]
+impl LookupTableSource for RpcLookupTableSource {
+    async fn fetch_lookup_tables(&self, tables: &[Pubkey]) -> CarbonResult<Vec<Option<Vec<u8>>>> {
+        fetch_accounts_data(&self.rpc_client, tables, "address lookup tables").await
+    }
+}
+
+/// A `DomainNameSource` resolving the primary `.sol` domain of wallets from
+/// the Solana Name Service over RPC.
+///
+/// The primary domain of a wallet is the favourite domain it registered, as
+/// long as the wallet still owns it.
+pub struct RpcDomainNameSource {
+    rpc_client: Arc<RpcClient>,
+}
+
+impl RpcDomainNameSource {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self { rpc_client }
+    }
+}
+
+#[async_trait]
+impl DomainNameSource for RpcDomainNameSource {
+    async fn primary_domains(&self, wallets: &[Pubkey]) -> CarbonResult<Vec<Option<String>>> {
+        let favourite_domains: Vec<Pubkey> =
+            wallets.iter().map(domain::favourite_domain_key).collect();
+        let domain_accounts: Vec<Option<Pubkey>> =
+            fetch_accounts_data(&self.rpc_client, &favourite_domains, "favourite domains")
+                .await?
+                .into_iter()
+                .map(|data| data.as_deref().and_then(domain::parse_favourite_domain))
+                .collect();
+
+        // Each domain is fetched along with its reverse lookup record.
+        let records: Vec<Pubkey> = domain_accounts
+            .iter()
+            .flatten()
+            .flat_map(|domain_account| {
+                [*domain_account, domain::reverse_lookup_key(domain_account)]
+            })
+            .collect();
+        let mut records = fetch_accounts_data(&self.rpc_client, &records, "domain name records")
+            .await?
+            .into_iter();
+
+        Ok(wallets
+            .iter()
+            .zip(domain_accounts)
+            .map(|(wallet, domain_account)| {
+                domain_account?;
+                let (domain_record, reverse_lookup) = (records.next()?, records.next()?);
+                let (header, _) = NameRecordHeader::unpack(domain_record.as_deref()?)?;
+                if header.owner != *wallet {
+                    return None;
+                }
+
+                ReverseLookup::unpack(reverse_lookup.as_deref()?)
+                    .map(|reverse_lookup| format!("{}.sol", reverse_lookup.name))
+            })
+            .collect())
+    }
+}
+
+/// Returns the data of each account, in order, or `None` for accounts that
+/// don't exist.
+async fn fetch_accounts_data(
+    rpc_client: &RpcClient,
+    accounts: &[Pubkey],
+    description: &str,
+) -> CarbonResult<Vec<Option<Vec<u8>>>> {
+    let mut accounts_data = Vec::with_capacity(accounts.len());
+
+    for chunk in accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = rpc_client
+            .get_multiple_accounts(chunk)
+            .await
+            .map_err(|e| Error::Custom(format!("Failed to fetch {}: {}", description, e)))?;
+        accounts_data.extend(
+            accounts
+                .into_iter()
+                .map(|account| account.map(|account| account.data)),
+        );
+    }
+
+    Ok(accounts_data)
+}
+
+/// Timeouts, connection errors, 429 and 5xx responses are worth retrying on
+/// another endpoint. Errors returned by the RPC node itself aren't.
+fn is_retryable(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(error) => {
+            error.is_timeout()
+                || error.is_connect()
+                || error
+                    .status()
+                    .is_some_and(|status| status.as_u16() == 429 || status.is_server_error())
+        }
+        _ => false,
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_round_robin_skips_tried_endpoints() {
+        let sender = MultiRpcSender::new(MultiRpcConfig::new(vec![
+            RpcEndpoint::new("http://first.example.com").weight(2),
+            RpcEndpoint::new("http://second.example.com"),
+        ]));
+
+        let selected: Vec<usize> = (0..6).filter_map(|_| sender.select(&[])).collect();
+        assert_eq!(selected, [0, 1, 0, 0, 1, 0]);
+
+        assert_eq!(sender.select(&[0]), Some(1));
+        assert_eq!(sender.select(&[0, 1]), None);
+    }
+}
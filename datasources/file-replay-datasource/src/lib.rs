@@ -0,0 +1,404 @@
+//! Replays updates recorded to files, so pipelines can run without any RPC or
+//! Geyser connection.
+//!
+//! An `UpdateRecorder` writes the raw updates received by the pipes of a live
+//! pipeline to a recording, and a `FileReplayDatasource` sends them to another
+//! pipeline later, at the pace they were recorded at, faster, or as fast as
+//! possible. Replaying the same recording always produces the same updates in
+//! the same order, which makes integration tests deterministic.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_file_replay_datasource::{FileReplayDatasource, ReplaySpeed, UpdateRecorder};
+//!
+//! let recorder = UpdateRecorder::create("./recordings/pumpfun.jsonl.gz")?;
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(yellowstone_grpc)
+//!     .account(PumpfunDecoder, recorder.accounts())
+//!     .instruction(PumpfunDecoder, recorder.instructions())
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! recorder.finish()?;
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(
+//!         FileReplayDatasource::new("./recordings/pumpfun.jsonl.gz")
+//!             .speed(ReplaySpeed::Multiplier(2.0)),
+//!     )
+//!     .instruction(PumpfunDecoder, PumpfunInstructionProcessor)
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! Long-running recorders write to an `Archive` instead, which compacts and
+//! prunes its files according to its retention policy:
+//!
+//! ```ignore
+//! use carbon_file_replay_datasource::{Archive, FileReplayDatasource, UpdateRecorder};
+//!
+//! let archive = Archive::open("./archive")?.max_size(100 * 1024 * 1024 * 1024);
+//! let recorder = UpdateRecorder::archive(archive.clone(), 1_000);
+//!
+//! // Later, replay everything the archive still holds.
+//! let datasource = FileReplayDatasource::from_archive(&archive)?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Recordings are newline-delimited JSON files, gzip compressed when their
+//!   name ends with `.gz`. See [`record`] for the format of the records.
+//! - Processors only receive the updates matched by their pipe, so a recorder
+//!   only records the accounts and transactions of the programs of its pipes.
+//!   Transactions recorded from instruction or transaction pipes only keep
+//!   their first signature.
+//! - Recordings are replayed in the order they were added, and the updates of
+//!   each recording in the order they were recorded. The files of an archive
+//!   are replayed in slot order, as listed when it is added.
+
+pub mod record;
+pub mod recorder;
+
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        backpressure::Backpressure,
+        datasource::{Datasource, Update, UpdateType},
+        error::{CarbonResult, Error},
+        metrics::MetricsCollection,
+    },
+    record::{io_error, read_records},
+    std::{
+        path::PathBuf,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::mpsc::{self, Sender},
+    tokio_util::sync::CancellationToken,
+};
+pub use {
+    carbon_archive::Archive,
+    recorder::{RecordingProcessor, UpdateRecorder},
+};
+
+const CHANNEL_BUFFER_SIZE: usize = 1000;
+
+/// The pace updates are replayed at.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ReplaySpeed {
+    /// Updates are sent as fast as the pipeline accepts them.
+    #[default]
+    Unthrottled,
+    /// Updates are spaced like they were recorded, divided by the multiplier,
+    /// so `1.0` replays in real time and `2.0` twice as fast.
+    Multiplier(f64),
+}
+
+/// A datasource replaying the updates of recordings written by an
+/// `UpdateRecorder`.
+pub struct FileReplayDatasource {
+    pub paths: Vec<PathBuf>,
+    pub speed: ReplaySpeed,
+}
+
+impl FileReplayDatasource {
+    /// Creates a datasource replaying a recording as fast as possible.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            paths: vec![path.into()],
+            speed: ReplaySpeed::default(),
+        }
+    }
+
+    /// Creates a datasource replaying the files of an archive written by an
+    /// `UpdateRecorder`, as fast as possible.
+    pub fn from_archive(archive: &Archive) -> CarbonResult<Self> {
+        Self {
+            paths: Vec::new(),
+            speed: ReplaySpeed::default(),
+        }
+        .archive(archive)
+    }
+
+    /// Replays another recording after the previous ones.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// Replays the files currently held by an archive after the previous
+    /// recordings, in slot order.
+    pub fn archive(mut self, archive: &Archive) -> CarbonResult<Self> {
+        self.paths
+            .extend(archive.replay_files()?.into_iter().map(|file| file.path));
+        Ok(self)
+    }
+
+    /// Sets the pace updates are replayed at.
+    pub fn speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+}
+
+/// Spaces updates according to their recording time and a `ReplaySpeed`.
+struct Pacer {
+    speed: ReplaySpeed,
+    start: Option<(Instant, i64)>,
+}
+
+impl Pacer {
+    /// Returns when an update recorded at `recorded_at` should be sent, or
+    /// `None` to send it immediately.
+    fn deadline(&mut self, recorded_at: i64) -> Option<Instant> {
+        let ReplaySpeed::Multiplier(multiplier) = self.speed else {
+            return None;
+        };
+        let (started_at, first_recorded_at) =
+            *self.start.get_or_insert((Instant::now(), recorded_at));
+
+        let elapsed = (recorded_at - first_recorded_at).max(0) as f64 / 1_000.0;
+        Some(started_at + Duration::from_secs_f64(elapsed / multiplier.max(f64::EPSILON)))
+    }
+}
+
+#[async_trait]
+impl Datasource for FileReplayDatasource {
+    async fn consume(
+        &self,
+        sender: Sender<Update>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        self.consume_with_backpressure(
+            sender,
+            cancellation_token,
+            metrics,
+            Backpressure::disabled(),
+        )
+        .await
+    }
+
+    /// Replays the recordings, pausing while the pipeline is overloaded.
+    async fn consume_with_backpressure(
+        &self,
+        sender: Sender<Update>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+        backpressure: Backpressure,
+    ) -> CarbonResult<()> {
+        let (record_sender, mut record_receiver) = mpsc::channel(CHANNEL_BUFFER_SIZE);
+        let paths = self.paths.clone();
+
+        // Files are read on a blocking thread, at most `CHANNEL_BUFFER_SIZE`
+        // records ahead of the pipeline.
+        let reader = tokio::task::spawn_blocking(move || -> CarbonResult<()> {
+            for path in paths {
+                let records = read_records(&path).map_err(|e| io_error(&path, e))?;
+                for record in records {
+                    let record = record.map_err(|e| io_error(&path, e))?;
+                    let update = record.update.into_update()?;
+                    if record_sender
+                        .blocking_send((record.recorded_at, update))
+                        .is_err()
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        });
+
+        let mut pacer = Pacer {
+            speed: self.speed,
+            start: None,
+        };
+
+        loop {
+            let (recorded_at, update) = tokio::select! {
+                _ = cancellation_token.cancelled() => {
+                    log::info!("Cancelling file replay...");
+                    break;
+                }
+                record = record_receiver.recv() => match record {
+                    Some(record) => record,
+                    None => break,
+                },
+            };
+
+            if let Some(deadline) = pacer.deadline(recorded_at) {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => break,
+                    _ = tokio::time::sleep_until(deadline.into()) => {}
+                }
+            }
+            tokio::select! {
+                _ = cancellation_token.cancelled() => break,
+                _ = backpressure.ready() => {}
+            }
+
+            if sender.send(update).await.is_err() {
+                break;
+            }
+            metrics
+                .increment_counter("file_replay_updates_replayed", 1)
+                .await?;
+        }
+
+        drop(record_receiver);
+        reader
+            .await
+            .map_err(|e| Error::Custom(format!("File replay reader failed: {}", e)))?
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        vec![
+            UpdateType::AccountUpdate,
+            UpdateType::Transaction,
+            UpdateType::AccountDeletion,
+            UpdateType::SlotStatus,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        carbon_core::datasource::{AccountUpdate, SlotStatus, SlotStatusUpdate},
+        solana_account::Account,
+        solana_pubkey::Pubkey,
+    };
+
+    #[tokio::test]
+    async fn test_replays_recorded_updates() {
+        let path = std::env::temp_dir().join(format!(
+            "carbon-file-replay-test-{}.jsonl.gz",
+            std::process::id()
+        ));
+        let account_update = AccountUpdate {
+            pubkey: Pubkey::new_unique(),
+            account: Account {
+                lamports: 1_000,
+                data: vec![1, 2, 3],
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 7,
+            },
+            slot: 42,
+        };
+
+        let recorder = UpdateRecorder::create(&path).unwrap();
+        recorder
+            .record(&Update::Account(account_update.clone()))
+            .unwrap();
+        recorder
+            .record(&Update::SlotStatus(SlotStatusUpdate {
+                slot: 42,
+                parent: Some(41),
+                status: SlotStatus::Confirmed,
+            }))
+            .unwrap();
+        recorder.finish().unwrap();
+
+        let (sender, mut receiver) = mpsc::channel(10);
+        FileReplayDatasource::new(&path)
+            .speed(ReplaySpeed::Multiplier(1_000.0))
+            .consume(
+                sender,
+                CancellationToken::new(),
+                Arc::new(MetricsCollection::new(vec![])),
+            )
+            .await
+            .unwrap();
+
+        match receiver.recv().await {
+            Some(Update::Account(replayed)) => {
+                assert_eq!(replayed.pubkey, account_update.pubkey);
+                assert_eq!(replayed.account, account_update.account);
+                assert_eq!(replayed.slot, account_update.slot);
+            }
+            update => panic!("unexpected update: {:?}", update),
+        }
+        match receiver.recv().await {
+            Some(Update::SlotStatus(replayed)) => {
+                assert_eq!(replayed.status, SlotStatus::Confirmed)
+            }
+            update => panic!("unexpected update: {:?}", update),
+        }
+        assert!(receiver.recv().await.is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn slot_status(slot: u64) -> Update {
+        Update::SlotStatus(SlotStatusUpdate {
+            slot,
+            parent: Some(slot - 1),
+            status: SlotStatus::Confirmed,
+        })
+    }
+
+    async fn replayed_slots(datasource: FileReplayDatasource) -> Vec<u64> {
+        let (sender, mut receiver) = mpsc::channel(10);
+        datasource
+            .consume(
+                sender,
+                CancellationToken::new(),
+                Arc::new(MetricsCollection::new(vec![])),
+            )
+            .await
+            .unwrap();
+
+        let mut slots = Vec::new();
+        while let Some(update) = receiver.recv().await {
+            slots.push(update.slot());
+        }
+        slots
+    }
+
+    #[tokio::test]
+    async fn test_replays_archive() {
+        let dir = std::env::temp_dir().join(format!(
+            "carbon-file-replay-archive-test-{}",
+            std::process::id()
+        ));
+        let archive = Archive::open(&dir).unwrap();
+
+        let recorder = UpdateRecorder::archive(archive.clone(), 10);
+        for slot in [1, 5, 12, 25] {
+            recorder.record(&slot_status(slot)).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        let files = archive.files().unwrap();
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| (file.first_slot, file.last_slot))
+                .collect::<Vec<_>>(),
+            [(1, 5), (12, 12), (25, 25)]
+        );
+        assert!(archive.verify().unwrap().is_empty());
+        assert_eq!(
+            replayed_slots(FileReplayDatasource::from_archive(&archive).unwrap()).await,
+            [1, 5, 12, 25]
+        );
+
+        // Daily files concatenate the compressed chunks of a day.
+        let daily_path = dir.join("concatenated.bin");
+        let daily: Vec<u8> = files
+            .iter()
+            .flat_map(|file| std::fs::read(&file.path).unwrap())
+            .collect();
+        std::fs::write(&daily_path, daily).unwrap();
+        assert_eq!(
+            replayed_slots(FileReplayDatasource::new(&daily_path)).await,
+            [1, 5, 12, 25]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,393 @@
+//! The format of recorded updates.
+//!
+//! A recording is a file of newline-delimited JSON records, each holding an
+//! update and the time it was recorded at. Files whose name ends with `.gz`
+//! are gzip compressed, which shrinks recordings of transactions severalfold.
+//!
+//! The chunks of an `Archive` hold records in the same format, always gzip
+//! compressed. Compressed files may be made of several concatenated gzip
+//! members, like the daily files an archive compacts its chunks into.
+
+use {
+    base64::{engine::general_purpose::STANDARD, Engine},
+    carbon_archive::Archive,
+    carbon_core::{
+        datasource::{
+            AccountDeletion, AccountUpdate, BlockDetails, SlotStatus, SlotStatusUpdate,
+            TransactionUpdate, Update,
+        },
+        error::{CarbonResult, Error},
+        transformers::transaction_metadata_from_original_meta,
+    },
+    flate2::{read::MultiGzDecoder, write::GzEncoder, Compression},
+    serde::{Deserialize, Serialize},
+    solana_account::Account,
+    solana_transaction_status::{Rewards, UiTransactionStatusMeta},
+    std::{
+        fs::File,
+        io::{self, BufRead, BufReader, BufWriter, Write},
+        path::Path,
+        str::FromStr,
+    },
+};
+
+const GZIP_EXTENSION: &str = "gz";
+/// The first bytes of a gzip member.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// An update recorded at `recorded_at`, in milliseconds since the Unix epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub recorded_at: i64,
+    pub update: RecordedUpdate,
+}
+
+/// An update in its recorded form, with keys and hashes base58 encoded and
+/// binary data base64 encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordedUpdate {
+    Account {
+        pubkey: String,
+        slot: u64,
+        lamports: u64,
+        owner: String,
+        executable: bool,
+        rent_epoch: u64,
+        data: String,
+    },
+    Transaction {
+        signature: String,
+        slot: u64,
+        block_time: Option<i64>,
+        block_hash: Option<String>,
+        is_vote: bool,
+        /// The bincode encoded `VersionedTransaction`.
+        transaction: String,
+        meta: Box<UiTransactionStatusMeta>,
+    },
+    AccountDeletion {
+        pubkey: String,
+        slot: u64,
+    },
+    BlockDetails {
+        slot: u64,
+        block_hash: Option<String>,
+        previous_block_hash: Option<String>,
+        rewards: Option<Rewards>,
+        num_reward_partitions: Option<u64>,
+        block_time: Option<i64>,
+        block_height: Option<u64>,
+    },
+    SlotStatus {
+        slot: u64,
+        parent: Option<u64>,
+        status: String,
+    },
+}
+
+impl RecordedUpdate {
+    /// Returns the slot of the update.
+    pub fn slot(&self) -> u64 {
+        match self {
+            RecordedUpdate::Account { slot, .. }
+            | RecordedUpdate::Transaction { slot, .. }
+            | RecordedUpdate::AccountDeletion { slot, .. }
+            | RecordedUpdate::BlockDetails { slot, .. }
+            | RecordedUpdate::SlotStatus { slot, .. } => *slot,
+        }
+    }
+
+    /// Converts an update to its recorded form.
+    pub fn from_update(update: &Update) -> CarbonResult<Self> {
+        Ok(match update {
+            Update::Account(account_update) => RecordedUpdate::Account {
+                pubkey: account_update.pubkey.to_string(),
+                slot: account_update.slot,
+                lamports: account_update.account.lamports,
+                owner: account_update.account.owner.to_string(),
+                executable: account_update.account.executable,
+                rent_epoch: account_update.account.rent_epoch,
+                data: STANDARD.encode(&account_update.account.data),
+            },
+            Update::Transaction(transaction_update) => RecordedUpdate::Transaction {
+                signature: transaction_update.signature.to_string(),
+                slot: transaction_update.slot,
+                block_time: transaction_update.block_time,
+                block_hash: transaction_update.block_hash.map(|hash| hash.to_string()),
+                is_vote: transaction_update.is_vote,
+                transaction: STANDARD.encode(
+                    bincode::serialize(&transaction_update.transaction).map_err(|e| {
+                        Error::Custom(format!("Failed to encode transaction: {}", e))
+                    })?,
+                ),
+                meta: Box::new(transaction_update.meta.clone().into()),
+            },
+            Update::AccountDeletion(account_deletion) => RecordedUpdate::AccountDeletion {
+                pubkey: account_deletion.pubkey.to_string(),
+                slot: account_deletion.slot,
+            },
+            Update::BlockDetails(block_details) => RecordedUpdate::BlockDetails {
+                slot: block_details.slot,
+                block_hash: block_details.block_hash.map(|hash| hash.to_string()),
+                previous_block_hash: block_details
+                    .previous_block_hash
+                    .map(|hash| hash.to_string()),
+                rewards: block_details.rewards.clone(),
+                num_reward_partitions: block_details.num_reward_partitions,
+                block_time: block_details.block_time,
+                block_height: block_details.block_height,
+            },
+            Update::SlotStatus(slot_status) => RecordedUpdate::SlotStatus {
+                slot: slot_status.slot,
+                parent: slot_status.parent,
+                status: match slot_status.status {
+                    SlotStatus::Processed => "processed",
+                    SlotStatus::Confirmed => "confirmed",
+                    SlotStatus::Finalized => "finalized",
+                    SlotStatus::Forked => "forked",
+                }
+                .to_string(),
+            },
+            update => {
+                return Err(Error::Custom(format!(
+                    "Recording unsupported update: {:?}",
+                    update
+                )))
+            }
+        })
+    }
+
+    /// Converts a recorded update back to the update it was recorded from.
+    pub fn into_update(self) -> CarbonResult<Update> {
+        Ok(match self {
+            RecordedUpdate::Account {
+                pubkey,
+                slot,
+                lamports,
+                owner,
+                executable,
+                rent_epoch,
+                data,
+            } => Update::Account(AccountUpdate {
+                pubkey: parse(&pubkey)?,
+                account: Account {
+                    lamports,
+                    data: decode_base64(&data)?,
+                    owner: parse(&owner)?,
+                    executable,
+                    rent_epoch,
+                },
+                slot,
+            }),
+            RecordedUpdate::Transaction {
+                signature,
+                slot,
+                block_time,
+                block_hash,
+                is_vote,
+                transaction,
+                meta,
+            } => Update::Transaction(Box::new(TransactionUpdate {
+                signature: parse(&signature)?,
+                transaction: bincode::deserialize(&decode_base64(&transaction)?)
+                    .map_err(|e| Error::Custom(format!("Invalid recorded transaction: {}", e)))?,
+                meta: transaction_metadata_from_original_meta(*meta)?,
+                is_vote,
+                slot,
+                block_time,
+                block_hash: block_hash.as_deref().map(parse).transpose()?,
+            })),
+            RecordedUpdate::AccountDeletion { pubkey, slot } => {
+                Update::AccountDeletion(AccountDeletion {
+                    pubkey: parse(&pubkey)?,
+                    slot,
+                })
+            }
+            RecordedUpdate::BlockDetails {
+                slot,
+                block_hash,
+                previous_block_hash,
+                rewards,
+                num_reward_partitions,
+                block_time,
+                block_height,
+            } => Update::BlockDetails(BlockDetails {
+                slot,
+                block_hash: block_hash.as_deref().map(parse).transpose()?,
+                previous_block_hash: previous_block_hash.as_deref().map(parse).transpose()?,
+                rewards,
+                num_reward_partitions,
+                block_time,
+                block_height,
+            }),
+            RecordedUpdate::SlotStatus {
+                slot,
+                parent,
+                status,
+            } => Update::SlotStatus(SlotStatusUpdate {
+                slot,
+                parent,
+                status: match status.as_str() {
+                    "processed" => SlotStatus::Processed,
+                    "confirmed" => SlotStatus::Confirmed,
+                    "finalized" => SlotStatus::Finalized,
+                    "forked" => SlotStatus::Forked,
+                    status => {
+                        return Err(Error::Custom(format!(
+                            "Invalid recorded slot status: {}",
+                            status
+                        )))
+                    }
+                },
+            }),
+        })
+    }
+}
+
+fn parse<T: FromStr>(value: &str) -> CarbonResult<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| Error::Custom(format!("Invalid recorded value {}: {}", value, e)))
+}
+
+fn decode_base64(data: &str) -> CarbonResult<Vec<u8>> {
+    STANDARD
+        .decode(data)
+        .map_err(|e| Error::Custom(format!("Invalid recorded data: {}", e)))
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == GZIP_EXTENSION)
+}
+
+pub(crate) fn io_error(path: &Path, error: io::Error) -> Error {
+    Error::Custom(format!("Recording {}: {}", path.display(), error))
+}
+
+fn write_record(writer: &mut dyn Write, record: &Record) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writer.write_all(b"\n")
+}
+
+/// Writes records to a recording, compressing them if its name ends with
+/// `.gz`.
+pub(crate) enum RecordWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl RecordWriter {
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+
+        Ok(if is_gzip(path) {
+            RecordWriter::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            RecordWriter::Plain(file)
+        })
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            RecordWriter::Plain(file) => file,
+            RecordWriter::Gzip(encoder) => encoder,
+        }
+    }
+
+    pub(crate) fn write(&mut self, record: &Record) -> io::Result<()> {
+        write_record(self.writer(), record)
+    }
+
+    /// Writes the buffered records, and the gzip trailer of compressed
+    /// recordings.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        match self {
+            RecordWriter::Plain(file) => file.flush(),
+            RecordWriter::Gzip(encoder) => {
+                encoder.try_finish()?;
+                encoder.get_mut().flush()
+            }
+        }
+    }
+}
+
+/// Writes records to the chunks of an `Archive`, each holding the gzip
+/// compressed records of up to `chunk_slots` consecutive slots.
+pub(crate) struct ChunkWriter {
+    archive: Archive,
+    chunk_slots: u64,
+    encoder: GzEncoder<Vec<u8>>,
+    /// The lowest and highest slots of the buffered records.
+    slots: Option<(u64, u64)>,
+}
+
+impl ChunkWriter {
+    pub(crate) fn new(archive: Archive, chunk_slots: u64) -> Self {
+        Self {
+            archive,
+            chunk_slots: chunk_slots.max(1),
+            encoder: GzEncoder::new(Vec::new(), Compression::default()),
+            slots: None,
+        }
+    }
+
+    /// Buffers a record, first writing the buffered records to a chunk if the
+    /// slot of the record is past it.
+    pub(crate) fn write(&mut self, record: &Record) -> CarbonResult<()> {
+        let slot = record.update.slot();
+        if self
+            .slots
+            .is_some_and(|(first_slot, _)| slot >= first_slot.saturating_add(self.chunk_slots))
+        {
+            self.finish()?;
+        }
+
+        write_record(&mut self.encoder, record).map_err(|e| io_error(&self.archive.dir, e))?;
+        self.slots = Some(self.slots.map_or((slot, slot), |(first_slot, last_slot)| {
+            (first_slot.min(slot), last_slot.max(slot))
+        }));
+
+        Ok(())
+    }
+
+    /// Writes the buffered records to a chunk.
+    pub(crate) fn finish(&mut self) -> CarbonResult<()> {
+        let Some((first_slot, last_slot)) = self.slots.take() else {
+            return Ok(());
+        };
+
+        let encoder = std::mem::replace(
+            &mut self.encoder,
+            GzEncoder::new(Vec::new(), Compression::default()),
+        );
+        let data = encoder
+            .finish()
+            .map_err(|e| io_error(&self.archive.dir, e))?;
+        self.archive.write_chunk(first_slot, last_slot, &data)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the records of a recording, in the order they were recorded.
+///
+/// Compressed recordings are recognized by their content, since archive files
+/// don't have a `.gz` extension.
+pub(crate) fn read_records(path: &Path) -> io::Result<impl Iterator<Item = io::Result<Record>>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let reader: Box<dyn BufRead + Send> = if file.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(file)
+    };
+
+    Ok(reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?)))
+}
@@ -0,0 +1,254 @@
+//! Records the raw updates received by the pipes of a pipeline.
+
+use {
+    crate::record::{io_error, ChunkWriter, Record, RecordWriter, RecordedUpdate},
+    async_trait::async_trait,
+    carbon_archive::Archive,
+    carbon_core::{
+        account::AccountProcessorInputType,
+        datasource::{AccountUpdate, TransactionUpdate, Update},
+        error::CarbonResult,
+        instruction::InstructionProcessorInputType,
+        metrics::MetricsCollection,
+        processor::Processor,
+        transaction::{TransactionMetadata, TransactionProcessorInputType},
+    },
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{
+        marker::PhantomData,
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Where an `UpdateRecorder` writes its records.
+enum RecordOutput {
+    File(PathBuf, RecordWriter),
+    Archive(ChunkWriter),
+}
+
+impl RecordOutput {
+    fn write(&mut self, record: &Record) -> CarbonResult<()> {
+        match self {
+            RecordOutput::File(path, writer) => writer.write(record).map_err(|e| io_error(path, e)),
+            RecordOutput::Archive(writer) => writer.write(record),
+        }
+    }
+
+    fn finish(&mut self) -> CarbonResult<()> {
+        match self {
+            RecordOutput::File(path, writer) => writer.finish().map_err(|e| io_error(path, e)),
+            RecordOutput::Archive(writer) => writer.finish(),
+        }
+    }
+}
+
+/// A cloneable handle to a recording being written.
+///
+/// Clones share the same file, so a single recording can hold the updates of
+/// several pipes.
+#[derive(Clone)]
+pub struct UpdateRecorder {
+    output: Arc<Mutex<RecordOutput>>,
+}
+
+impl UpdateRecorder {
+    /// Creates a recording at `path`, replacing any existing file. The
+    /// recording is gzip compressed if the name of the file ends with `.gz`.
+    pub fn create(path: impl AsRef<Path>) -> CarbonResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let writer = RecordWriter::create(&path).map_err(|e| io_error(&path, e))?;
+
+        Ok(Self {
+            output: Arc::new(Mutex::new(RecordOutput::File(path, writer))),
+        })
+    }
+
+    /// Creates a recording written to the chunks of `archive`, each holding
+    /// the updates of up to `chunk_slots` consecutive slots.
+    ///
+    /// A chunk is written once an update past its slots is recorded, so
+    /// updates delivered slots out of order may land in the next chunk.
+    pub fn archive(archive: Archive, chunk_slots: u64) -> Self {
+        Self {
+            output: Arc::new(Mutex::new(RecordOutput::Archive(ChunkWriter::new(
+                archive,
+                chunk_slots,
+            )))),
+        }
+    }
+
+    /// Appends an update to the recording.
+    pub fn record(&self, update: &Update) -> CarbonResult<()> {
+        let record = Record {
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as i64,
+            update: RecordedUpdate::from_update(update)?,
+        };
+
+        self.output
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .write(&record)
+    }
+
+    /// Writes the buffered updates to the file, or to a last archive chunk.
+    ///
+    /// Must be called once the pipeline stopped, so compressed recordings are
+    /// complete. Updates recorded afterwards are lost, unless recorded to an
+    /// archive.
+    pub fn finish(&self) -> CarbonResult<()> {
+        self.output
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .finish()
+    }
+
+    /// Creates a processor recording the updates of an account pipe.
+    pub fn accounts<T>(&self) -> RecordingProcessor<AccountProcessorInputType<T>> {
+        RecordingProcessor::new(self.clone())
+    }
+
+    /// Creates a processor recording the transactions of an instruction pipe,
+    /// once for all the instructions of a transaction.
+    pub fn instructions<T>(&self) -> RecordingProcessor<InstructionProcessorInputType<T>> {
+        RecordingProcessor::new(self.clone())
+    }
+
+    /// Creates a processor recording the transactions of a transaction pipe.
+    pub fn transactions<T, U>(&self) -> RecordingProcessor<TransactionProcessorInputType<T, U>> {
+        RecordingProcessor::new(self.clone())
+    }
+}
+
+/// A processor recording the raw updates of a pipe to an `UpdateRecorder`.
+pub struct RecordingProcessor<I> {
+    recorder: UpdateRecorder,
+    last_signature: Option<Signature>,
+    _input: PhantomData<fn(I)>,
+}
+
+impl<I> RecordingProcessor<I> {
+    fn new(recorder: UpdateRecorder) -> Self {
+        Self {
+            recorder,
+            last_signature: None,
+            _input: PhantomData,
+        }
+    }
+
+    /// Records the transaction of `transaction_metadata`, unless it is the
+    /// transaction recorded last.
+    async fn record_transaction(
+        &mut self,
+        transaction_metadata: &TransactionMetadata,
+        metrics: &MetricsCollection,
+    ) -> CarbonResult<()> {
+        if self.last_signature == Some(transaction_metadata.signature) {
+            return Ok(());
+        }
+        self.last_signature = Some(transaction_metadata.signature);
+
+        self.recorder
+            .record(&Update::Transaction(Box::new(transaction_update(
+                transaction_metadata,
+            ))))?;
+
+        metrics
+            .increment_counter("file_replay_updates_recorded", 1)
+            .await
+    }
+}
+
+/// Rebuilds the transaction update a transaction metadata was created from.
+///
+/// Only the first signature of a transaction is kept in its metadata, so the
+/// other signatures are left empty, and vote transactions aren't flagged.
+fn transaction_update(transaction_metadata: &TransactionMetadata) -> TransactionUpdate {
+    let num_signatures = transaction_metadata
+        .message
+        .header()
+        .num_required_signatures
+        .max(1) as usize;
+    let mut signatures = vec![Signature::default(); num_signatures];
+    signatures[0] = transaction_metadata.signature;
+
+    TransactionUpdate {
+        signature: transaction_metadata.signature,
+        transaction: VersionedTransaction {
+            signatures,
+            message: transaction_metadata.message.clone(),
+        },
+        meta: transaction_metadata.meta.clone(),
+        is_vote: false,
+        slot: transaction_metadata.slot,
+        block_time: transaction_metadata.block_time,
+        block_hash: transaction_metadata.block_hash,
+    }
+}
+
+#[async_trait]
+impl<T> Processor for RecordingProcessor<AccountProcessorInputType<T>>
+where
+    T: Send + Sync + 'static,
+{
+    type InputType = AccountProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, _, account) = data;
+        self.recorder.record(&Update::Account(AccountUpdate {
+            pubkey: metadata.pubkey,
+            account,
+            slot: metadata.slot,
+        }))?;
+
+        metrics
+            .increment_counter("file_replay_updates_recorded", 1)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T> Processor for RecordingProcessor<InstructionProcessorInputType<T>>
+where
+    T: Send + Sync + 'static,
+{
+    type InputType = InstructionProcessorInputType<T>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (metadata, _, _, _) = data;
+        self.record_transaction(&metadata.transaction_metadata, &metrics)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T, U> Processor for RecordingProcessor<TransactionProcessorInputType<T, U>>
+where
+    T: Send + Sync + 'static,
+    U: Send + Sync + 'static,
+{
+    type InputType = TransactionProcessorInputType<T, U>;
+
+    async fn process(
+        &mut self,
+        data: Self::InputType,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        let (transaction_metadata, _, _) = data;
+        self.record_transaction(&transaction_metadata, &metrics)
+            .await
+    }
+}
@@ -0,0 +1,477 @@
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        datasource::{
+            AccountDeletion, AccountUpdate, Datasource, TransactionUpdate, Update, UpdateType,
+        },
+        error::CarbonResult,
+        metrics::MetricsCollection,
+    },
+    futures::{sink::SinkExt, StreamExt},
+    solana_account::Account,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{
+        collections::{HashMap, HashSet},
+        convert::TryFrom,
+        sync::Arc,
+        time::Duration,
+    },
+    tokio::sync::{mpsc::Sender, RwLock},
+    tokio_util::sync::CancellationToken,
+    yellowstone_grpc_client::{GeyserGrpcClient, InterceptorXToken},
+    yellowstone_grpc_proto::{
+        convert_from::{create_tx_meta, create_tx_versioned},
+        geyser::{
+            subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+            SubscribeRequestFilterAccounts, SubscribeRequestFilterTransactions,
+            SubscribeRequestPing, SubscribeUpdateAccountInfo, SubscribeUpdateTransactionInfo,
+        },
+        tonic::transport::ClientTlsConfig,
+    },
+};
+
+const MAX_RECONNECTION_ATTEMPTS: u32 = 30;
+const RECONNECTION_DELAY_MS: u64 = 3000;
+
+/// The LaserStream endpoint to connect to. Pick the region closest to the
+/// pipeline to minimize latency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaserStreamEndpoint {
+    MainnetEwr,
+    MainnetPitt,
+    MainnetSlc,
+    MainnetLax,
+    MainnetLon,
+    MainnetAms,
+    MainnetFra,
+    MainnetTyo,
+    MainnetSgp,
+    Devnet,
+    Custom(String),
+}
+
+impl LaserStreamEndpoint {
+    pub fn url(&self) -> String {
+        let region = match self {
+            LaserStreamEndpoint::MainnetEwr => "mainnet-ewr",
+            LaserStreamEndpoint::MainnetPitt => "mainnet-pitt",
+            LaserStreamEndpoint::MainnetSlc => "mainnet-slc",
+            LaserStreamEndpoint::MainnetLax => "mainnet-lax",
+            LaserStreamEndpoint::MainnetLon => "mainnet-lon",
+            LaserStreamEndpoint::MainnetAms => "mainnet-ams",
+            LaserStreamEndpoint::MainnetFra => "mainnet-fra",
+            LaserStreamEndpoint::MainnetTyo => "mainnet-tyo",
+            LaserStreamEndpoint::MainnetSgp => "mainnet-sgp",
+            LaserStreamEndpoint::Devnet => "devnet-ewr",
+            LaserStreamEndpoint::Custom(url) => return url.clone(),
+        };
+
+        format!("https://laserstream-{region}.helius-rpc.com")
+    }
+}
+
+/// A datasource streaming transactions and account updates from Helius
+/// LaserStream.
+///
+/// Subscription filters are built from the program ids of the decoders
+/// registered in the pipeline: transactions mentioning any of the programs are
+/// streamed, along with updates of the accounts they own when
+/// `subscribe_accounts` is set.
+///
+/// When `from_slot` is set, the stream starts by replaying updates from that
+/// slot. After a disconnection the stream is resumed from the last received
+/// slot, so updates of that slot may be delivered twice.
+#[derive(Debug)]
+pub struct HeliusLaserStream {
+    pub api_key: String,
+    pub endpoint: LaserStreamEndpoint,
+    pub program_ids: Vec<Pubkey>,
+    pub subscribe_accounts: bool,
+    pub failed_transactions: bool,
+    pub commitment: Option<CommitmentLevel>,
+    pub from_slot: Option<u64>,
+    pub account_deletions_tracked: Arc<RwLock<HashSet<Pubkey>>>,
+}
+
+impl HeliusLaserStream {
+    pub fn new(
+        api_key: String,
+        endpoint: LaserStreamEndpoint,
+        program_ids: impl IntoIterator<Item = Pubkey>,
+        account_deletions_tracked: Arc<RwLock<HashSet<Pubkey>>>,
+    ) -> Self {
+        Self {
+            api_key,
+            endpoint,
+            program_ids: program_ids.into_iter().collect(),
+            subscribe_accounts: true,
+            failed_transactions: false,
+            commitment: Some(CommitmentLevel::Confirmed),
+            from_slot: None,
+            account_deletions_tracked,
+        }
+    }
+
+    /// Replays updates starting from `slot` before streaming new ones.
+    pub fn from_slot(mut self, slot: u64) -> Self {
+        self.from_slot = Some(slot);
+        self
+    }
+
+    pub fn commitment(mut self, commitment: CommitmentLevel) -> Self {
+        self.commitment = Some(commitment);
+        self
+    }
+
+    /// Whether updates of the accounts owned by the programs are streamed.
+    pub fn subscribe_accounts(mut self, subscribe_accounts: bool) -> Self {
+        self.subscribe_accounts = subscribe_accounts;
+        self
+    }
+
+    /// Whether failed transactions are streamed.
+    pub fn failed_transactions(mut self, failed_transactions: bool) -> Self {
+        self.failed_transactions = failed_transactions;
+        self
+    }
+
+    fn subscribe_request(&self, from_slot: Option<u64>) -> SubscribeRequest {
+        let program_ids: Vec<String> = self.program_ids.iter().map(ToString::to_string).collect();
+
+        let transactions = HashMap::from([(
+            "carbon_programs".to_string(),
+            SubscribeRequestFilterTransactions {
+                vote: Some(false),
+                failed: Some(self.failed_transactions),
+                account_include: program_ids.clone(),
+                ..Default::default()
+            },
+        )]);
+
+        let accounts = if self.subscribe_accounts {
+            HashMap::from([(
+                "carbon_program_accounts".to_string(),
+                SubscribeRequestFilterAccounts {
+                    owner: program_ids,
+                    ..Default::default()
+                },
+            )])
+        } else {
+            HashMap::new()
+        };
+
+        SubscribeRequest {
+            accounts,
+            transactions,
+            commitment: self.commitment.map(|x| x as i32),
+            from_slot,
+            ..Default::default()
+        }
+    }
+
+    async fn connect(&self) -> CarbonResult<GeyserGrpcClient<InterceptorXToken>> {
+        GeyserGrpcClient::build_from_shared(self.endpoint.url())
+            .map_err(|err| carbon_core::error::Error::FailedToConsumeDatasource(err.to_string()))?
+            .x_token(Some(self.api_key.clone()))
+            .map_err(|err| carbon_core::error::Error::FailedToConsumeDatasource(err.to_string()))?
+            .connect_timeout(Duration::from_secs(15))
+            .timeout(Duration::from_secs(15))
+            .tls_config(ClientTlsConfig::new().with_enabled_roots())
+            .map_err(|err| carbon_core::error::Error::FailedToConsumeDatasource(err.to_string()))?
+            .connect()
+            .await
+            .map_err(|err| carbon_core::error::Error::FailedToConsumeDatasource(err.to_string()))
+    }
+}
+
+#[async_trait]
+impl Datasource for HeliusLaserStream {
+    async fn consume(
+        &self,
+        sender: Sender<Update>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        if self.program_ids.is_empty() {
+            return Err(carbon_core::error::Error::Custom(
+                "Error creating Helius LaserStream subscription: program ids can't be empty"
+                    .to_string(),
+            ));
+        }
+
+        let mut reconnection_attempts = 0;
+        let mut from_slot = self.from_slot;
+
+        loop {
+            if cancellation_token.is_cancelled() {
+                log::info!("Cancellation requested, stopping reconnection attempts");
+                break;
+            }
+
+            let mut geyser_client = match self.connect().await {
+                Ok(client) => client,
+                Err(err) => {
+                    log::error!("Failed to connect to Helius LaserStream: {:?}", err);
+                    reconnection_attempts += 1;
+                    if reconnection_attempts >= MAX_RECONNECTION_ATTEMPTS {
+                        return Err(carbon_core::error::Error::Custom(format!(
+                            "Failed to connect to Helius LaserStream after {} attempts: {:?}",
+                            MAX_RECONNECTION_ATTEMPTS, err
+                        )));
+                    }
+                    tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
+                    continue;
+                }
+            };
+
+            let subscription = tokio::select! {
+                _ = cancellation_token.cancelled() => break,
+                subscription = geyser_client.subscribe_with_request(Some(self.subscribe_request(from_slot))) => subscription,
+            };
+
+            match subscription {
+                Ok((mut subscribe_tx, mut stream)) => {
+                    reconnection_attempts = 0;
+
+                    loop {
+                        let message = tokio::select! {
+                            _ = cancellation_token.cancelled() => {
+                                log::info!("Cancelling Helius LaserStream subscription.");
+                                return Ok(());
+                            }
+                            message = stream.next() => message,
+                        };
+
+                        match message {
+                            Some(Ok(msg)) => match msg.update_oneof {
+                                Some(UpdateOneof::Account(account_update)) => {
+                                    from_slot = Some(account_update.slot);
+                                    send_subscribe_account_update_info(
+                                        account_update.account,
+                                        &metrics,
+                                        &sender,
+                                        account_update.slot,
+                                        &self.account_deletions_tracked,
+                                    )
+                                    .await
+                                }
+                                Some(UpdateOneof::Transaction(transaction_update)) => {
+                                    from_slot = Some(transaction_update.slot);
+                                    send_subscribe_update_transaction_info(
+                                        transaction_update.transaction,
+                                        &metrics,
+                                        &sender,
+                                        transaction_update.slot,
+                                    )
+                                    .await
+                                }
+                                Some(UpdateOneof::Ping(_)) => {
+                                    if let Err(error) = subscribe_tx
+                                        .send(SubscribeRequest {
+                                            ping: Some(SubscribeRequestPing { id: 1 }),
+                                            ..Default::default()
+                                        })
+                                        .await
+                                    {
+                                        log::error!("Failed to send ping error: {error:?}");
+                                        break;
+                                    }
+                                }
+                                _ => {}
+                            },
+                            Some(Err(error)) => {
+                                log::error!("Helius LaserStream error: {error:?}");
+                                break;
+                            }
+                            None => {
+                                log::info!("Helius LaserStream stream has been closed");
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(err) => {
+                    log::error!("Failed to subscribe to Helius LaserStream: {:?}", err);
+                    reconnection_attempts += 1;
+                    if reconnection_attempts >= MAX_RECONNECTION_ATTEMPTS {
+                        return Err(carbon_core::error::Error::Custom(format!(
+                            "Failed to subscribe to Helius LaserStream after {} attempts: {:?}",
+                            MAX_RECONNECTION_ATTEMPTS, err
+                        )));
+                    }
+                }
+            }
+
+            metrics
+                .increment_counter("helius_laserstream_reconnections", 1)
+                .await
+                .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
+
+            log::info!(
+                "Reconnecting to Helius LaserStream from slot {:?}",
+                from_slot
+            );
+            tokio::time::sleep(Duration::from_millis(RECONNECTION_DELAY_MS)).await;
+        }
+
+        Ok(())
+    }
+
+    fn update_types(&self) -> Vec<UpdateType> {
+        vec![
+            UpdateType::AccountUpdate,
+            UpdateType::Transaction,
+            UpdateType::AccountDeletion,
+        ]
+    }
+}
+
+async fn send_subscribe_account_update_info(
+    account_update_info: Option<SubscribeUpdateAccountInfo>,
+    metrics: &MetricsCollection,
+    sender: &Sender<Update>,
+    slot: u64,
+    account_deletions_tracked: &RwLock<HashSet<Pubkey>>,
+) {
+    let start_time = std::time::Instant::now();
+
+    let Some(account_info) = account_update_info else {
+        log::error!("No account info in UpdateOneof::Account at slot {}", slot);
+        return;
+    };
+
+    let Ok(account_pubkey) = Pubkey::try_from(account_info.pubkey) else {
+        return;
+    };
+
+    let Ok(account_owner_pubkey) = Pubkey::try_from(account_info.owner) else {
+        return;
+    };
+
+    let account = Account {
+        lamports: account_info.lamports,
+        data: account_info.data,
+        owner: account_owner_pubkey,
+        executable: account_info.executable,
+        rent_epoch: account_info.rent_epoch,
+    };
+
+    if account.lamports == 0
+        && account.data.is_empty()
+        && account_owner_pubkey == solana_program::system_program::ID
+    {
+        let accounts = account_deletions_tracked.read().await;
+        if accounts.contains(&account_pubkey) {
+            let account_deletion = AccountDeletion {
+                pubkey: account_pubkey,
+                slot,
+            };
+            if let Err(e) = sender.try_send(Update::AccountDeletion(account_deletion)) {
+                log::error!(
+                    "Failed to send account deletion update for pubkey {:?} at slot {}: {:?}",
+                    account_pubkey,
+                    slot,
+                    e
+                );
+            }
+        }
+    } else {
+        let update = Update::Account(AccountUpdate {
+            pubkey: account_pubkey,
+            account,
+            slot,
+        });
+
+        if let Err(e) = sender.try_send(update) {
+            log::error!(
+                "Failed to send account update for pubkey {:?} at slot {}: {:?}",
+                account_pubkey,
+                slot,
+                e
+            );
+        }
+    }
+
+    metrics
+        .record_histogram(
+            "helius_laserstream_account_process_time_nanoseconds",
+            start_time.elapsed().as_nanos() as f64,
+        )
+        .await
+        .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
+
+    metrics
+        .increment_counter("helius_laserstream_account_updates_received", 1)
+        .await
+        .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
+}
+
+async fn send_subscribe_update_transaction_info(
+    transaction_info: Option<SubscribeUpdateTransactionInfo>,
+    metrics: &MetricsCollection,
+    sender: &Sender<Update>,
+    slot: u64,
+) {
+    let start_time = std::time::Instant::now();
+
+    let Some(transaction_info) = transaction_info else {
+        log::error!(
+            "No transaction info in `UpdateOneof::Transaction` at slot {}",
+            slot
+        );
+        return;
+    };
+
+    let Ok(signature) = Signature::try_from(transaction_info.signature) else {
+        return;
+    };
+    let Some(yellowstone_transaction) = transaction_info.transaction else {
+        return;
+    };
+    let Some(yellowstone_tx_meta) = transaction_info.meta else {
+        return;
+    };
+    let Ok(versioned_transaction) = create_tx_versioned(yellowstone_transaction) else {
+        return;
+    };
+    let meta_original = match create_tx_meta(yellowstone_tx_meta) {
+        Ok(meta) => meta,
+        Err(err) => {
+            log::error!("Failed to create transaction meta: {:?}", err);
+            return;
+        }
+    };
+
+    let update = Update::Transaction(Box::new(TransactionUpdate {
+        signature,
+        transaction: versioned_transaction,
+        meta: meta_original,
+        is_vote: transaction_info.is_vote,
+        slot,
+        block_time: None,
+        block_hash: None,
+    }));
+    if let Err(e) = sender.try_send(update) {
+        log::error!(
+            "Failed to send transaction update with signature {:?} at slot {}: {:?}",
+            signature,
+            slot,
+            e
+        );
+        return;
+    }
+
+    metrics
+        .record_histogram(
+            "helius_laserstream_transaction_process_time_nanoseconds",
+            start_time.elapsed().as_nanos() as f64,
+        )
+        .await
+        .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
+
+    metrics
+        .increment_counter("helius_laserstream_transaction_updates_received", 1)
+        .await
+        .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
+}
@@ -1,14 +1,17 @@
 pub use solana_client::rpc_config::RpcBlockConfig;
 use solana_hash::Hash;
+pub use solana_transaction_status::TransactionDetails;
 use std::str::FromStr;
 use {
     async_trait::async_trait,
     carbon_core::{
-        datasource::{Datasource, TransactionUpdate, Update, UpdateType},
+        backpressure::Backpressure,
+        datasource::{BlockDetails, Datasource, TransactionUpdate, Update, UpdateType},
         error::CarbonResult,
         metrics::MetricsCollection,
         transformers::transaction_metadata_from_original_meta,
     },
+    carbon_rpc_client::MultiRpcConfig,
     futures::StreamExt,
     solana_client::{nonblocking::rpc_client::RpcClient, rpc_client::SerializableTransaction},
     solana_commitment_config::CommitmentConfig,
@@ -38,6 +41,7 @@ pub struct RpcBlockCrawler {
     pub block_config: RpcBlockConfig,
     pub max_concurrent_requests: usize,
     pub channel_buffer_size: usize,
+    pub rpc_config: Option<MultiRpcConfig>,
 }
 
 impl RpcBlockCrawler {
@@ -58,8 +62,41 @@ impl RpcBlockCrawler {
             block_interval: block_interval.unwrap_or(BLOCK_INTERVAL),
             max_concurrent_requests: max_concurrent_requests.unwrap_or(MAX_CONCURRENT_REQUESTS),
             channel_buffer_size: channel_buffer_size.unwrap_or(CHANNEL_BUFFER_SIZE),
+            rpc_config: None,
         }
     }
+
+    /// Spreads requests over multiple rate-limited endpoints with failover,
+    /// instead of sending them all to `rpc_url`.
+    pub fn with_rpc_endpoints(mut self, rpc_config: MultiRpcConfig) -> Self {
+        self.rpc_config = Some(rpc_config);
+        self
+    }
+
+    /// Sets the level of transaction detail of fetched blocks.
+    ///
+    /// Only `TransactionDetails::Full` blocks produce transaction updates.
+    /// With the other levels, only the block details of each block are sent,
+    /// which saves bandwidth when transactions aren't needed.
+    pub fn transaction_details(mut self, transaction_details: TransactionDetails) -> Self {
+        self.block_config.transaction_details = Some(transaction_details);
+        self
+    }
+
+    /// Sets whether the rewards of each block are fetched and included in its
+    /// block details.
+    pub fn rewards(mut self, rewards: bool) -> Self {
+        self.block_config.rewards = Some(rewards);
+        self
+    }
+
+    /// Sets the maximum transaction version to fetch. Blocks containing a
+    /// transaction with a higher version fail to be fetched. `None` only
+    /// supports legacy transactions.
+    pub fn max_supported_transaction_version(mut self, version: Option<u8>) -> Self {
+        self.block_config.max_supported_transaction_version = version;
+        self
+    }
 }
 
 #[async_trait]
@@ -70,12 +107,32 @@ impl Datasource for RpcBlockCrawler {
         cancellation_token: CancellationToken,
         metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
-        let rpc_client = Arc::new(RpcClient::new_with_commitment(
-            self.rpc_url.clone(),
-            self.block_config
-                .commitment
-                .unwrap_or(CommitmentConfig::confirmed()),
-        ));
+        self.consume_with_backpressure(
+            sender,
+            cancellation_token,
+            metrics,
+            Backpressure::disabled(),
+        )
+        .await
+    }
+
+    /// Crawls blocks, pausing the requests for new blocks while the pipeline
+    /// is overloaded.
+    async fn consume_with_backpressure(
+        &self,
+        sender: Sender<Update>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+        backpressure: Backpressure,
+    ) -> CarbonResult<()> {
+        let commitment = self
+            .block_config
+            .commitment
+            .unwrap_or(CommitmentConfig::confirmed());
+        let rpc_client = Arc::new(match &self.rpc_config {
+            Some(rpc_config) => rpc_config.rpc_client(commitment),
+            None => RpcClient::new_with_commitment(self.rpc_url.clone(), commitment),
+        });
         let (block_sender, block_receiver) = mpsc::channel(self.channel_buffer_size);
 
         let block_fetcher = block_fetcher(
@@ -88,11 +145,16 @@ impl Datasource for RpcBlockCrawler {
             self.max_concurrent_requests,
             cancellation_token.clone(),
             metrics.clone(),
+            backpressure,
         );
 
         let task_processor = task_processor(
             block_receiver,
             sender,
+            matches!(
+                self.block_config.transaction_details,
+                None | Some(TransactionDetails::Full)
+            ),
             cancellation_token.clone(),
             metrics.clone(),
         );
@@ -108,7 +170,10 @@ impl Datasource for RpcBlockCrawler {
     }
 
     fn update_types(&self) -> Vec<UpdateType> {
-        vec![UpdateType::Transaction]
+        match self.block_config.transaction_details {
+            None | Some(TransactionDetails::Full) => vec![UpdateType::Transaction],
+            Some(_) => vec![],
+        }
     }
 }
 
@@ -123,6 +188,7 @@ fn block_fetcher(
     max_concurrent_requests: usize,
     cancellation_token: CancellationToken,
     metrics: Arc<MetricsCollection>,
+    backpressure: Backpressure,
 ) -> JoinHandle<()> {
     let rpc_client_clone = rpc_client.clone();
     tokio::spawn(async move {
@@ -131,6 +197,7 @@ fn block_fetcher(
                 let mut current_slot = start_slot;
                 let mut latest_slot = current_slot;
                 loop {
+                    backpressure.ready().await;
                     if let Some(end) = end_slot {
                         if current_slot > end {
                             break;
@@ -244,10 +311,11 @@ fn block_fetcher(
     })
 }
 
-/// Process the block and send the transactions to the sender
+/// Process the block and send its details and transactions to the sender
 fn task_processor(
     block_receiver: Receiver<(u64, UiConfirmedBlock)>,
     sender: Sender<Update>,
+    full_transactions: bool,
     cancellation_token: CancellationToken,
     metrics: Arc<MetricsCollection>,
 ) -> JoinHandle<()> {
@@ -272,7 +340,22 @@ fn task_processor(
                             });
                         let block_start_time = Instant::now();
                         let block_hash = Hash::from_str(&block.blockhash).ok();
-                        if let Some(transactions) = block.transactions {
+
+                        let block_details = Update::BlockDetails(BlockDetails {
+                            slot,
+                            block_hash,
+                            previous_block_hash: Hash::from_str(&block.previous_blockhash).ok(),
+                            rewards: block.rewards,
+                            num_reward_partitions: block.num_reward_partitions,
+                            block_time: block.block_time,
+                            block_height: block.block_height,
+                        });
+
+                        if let Err(err) = sender.try_send(block_details) {
+                            log::error!("Error sending block details: {:?}", err);
+                        }
+
+                        if let Some(transactions) = block.transactions.filter(|_| full_transactions) {
                             for encoded_transaction_with_status_meta in transactions {
                                 let start_time = std::time::Instant::now();
 
@@ -1,11 +1,13 @@
 use {
     async_trait::async_trait,
     carbon_core::{
+        backpressure::Backpressure,
         datasource::{Datasource, TransactionUpdate, Update, UpdateType},
         error::CarbonResult,
         metrics::MetricsCollection,
         transformers::transaction_metadata_from_original_meta,
     },
+    carbon_rpc_client::MultiRpcConfig,
     futures::StreamExt,
     solana_client::{
         nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
@@ -136,6 +138,7 @@ pub struct RpcTransactionCrawler {
     pub connection_config: ConnectionConfig,
     pub filters: Filters,
     pub commitment: Option<CommitmentConfig>,
+    pub rpc_config: Option<MultiRpcConfig>,
 }
 
 impl RpcTransactionCrawler {
@@ -152,8 +155,16 @@ impl RpcTransactionCrawler {
             connection_config,
             filters,
             commitment,
+            rpc_config: None,
         }
     }
+
+    /// Spreads requests over multiple rate-limited endpoints with failover,
+    /// instead of sending them all to `rpc_url`.
+    pub fn with_rpc_endpoints(mut self, rpc_config: MultiRpcConfig) -> Self {
+        self.rpc_config = Some(rpc_config);
+        self
+    }
 }
 
 #[async_trait]
@@ -164,10 +175,29 @@ impl Datasource for RpcTransactionCrawler {
         cancellation_token: CancellationToken,
         metrics: Arc<MetricsCollection>,
     ) -> CarbonResult<()> {
-        let rpc_client = Arc::new(RpcClient::new_with_commitment(
-            self.rpc_url.clone(),
-            self.commitment.unwrap_or(CommitmentConfig::confirmed()),
-        ));
+        self.consume_with_backpressure(
+            sender,
+            cancellation_token,
+            metrics,
+            Backpressure::disabled(),
+        )
+        .await
+    }
+
+    /// Crawls transactions, pausing the requests for new signatures while the
+    /// pipeline is overloaded.
+    async fn consume_with_backpressure(
+        &self,
+        sender: Sender<Update>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+        backpressure: Backpressure,
+    ) -> CarbonResult<()> {
+        let commitment_config = self.commitment.unwrap_or(CommitmentConfig::confirmed());
+        let rpc_client = Arc::new(match &self.rpc_config {
+            Some(rpc_config) => rpc_config.rpc_client(commitment_config),
+            None => RpcClient::new_with_commitment(self.rpc_url.clone(), commitment_config),
+        });
         let account = self.account;
         let filters = self.filters.clone();
         let sender = sender.clone();
@@ -193,6 +223,7 @@ impl Datasource for RpcTransactionCrawler {
             commitment,
             cancellation_token.clone(),
             metrics.clone(),
+            backpressure,
         );
 
         let transaction_fetcher = transaction_fetcher(
@@ -239,6 +270,7 @@ fn signature_fetcher(
     commitment: Option<CommitmentConfig>,
     cancellation_token: CancellationToken,
     metrics: Arc<MetricsCollection>,
+    backpressure: Backpressure,
 ) -> JoinHandle<()> {
     let rpc_client = Arc::clone(&rpc_client);
     let filters = filters.clone();
@@ -259,6 +291,7 @@ fn signature_fetcher(
                     let mut backoff = connection_config.retry_config.initial_backoff_ms;
 
                     loop {
+                        backpressure.ready().await;
                         match rpc_client.get_signatures_for_address_with_config(
                             &account,
                             GetConfirmedSignaturesForAddress2Config {
@@ -1,8 +1,10 @@
 use {
     async_trait::async_trait,
     carbon_core::{
+        backpressure::Backpressure,
         datasource::{
-            AccountDeletion, AccountUpdate, Datasource, TransactionUpdate, Update, UpdateType,
+            AccountDeletion, AccountUpdate, Datasource, SlotStatus, SlotStatusUpdate,
+            TransactionUpdate, Update, UpdateType,
         },
         error::CarbonResult,
         metrics::MetricsCollection,
@@ -23,10 +25,10 @@ use {
     yellowstone_grpc_proto::{
         convert_from::{create_tx_meta, create_tx_versioned},
         geyser::{
-            subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
-            SubscribeRequestFilterAccounts, SubscribeRequestFilterBlocks,
-            SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdateAccountInfo,
-            SubscribeUpdateTransactionInfo,
+            subscribe_update::UpdateOneof, CommitmentLevel, SlotStatus as GeyserSlotStatus,
+            SubscribeRequest, SubscribeRequestFilterAccounts, SubscribeRequestFilterBlocks,
+            SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions, SubscribeRequestPing,
+            SubscribeUpdateAccountInfo, SubscribeUpdateSlot, SubscribeUpdateTransactionInfo,
         },
         tonic::transport::ClientTlsConfig,
     },
@@ -41,6 +43,7 @@ pub struct YellowstoneGrpcGeyserClient {
     pub transaction_filters: HashMap<String, SubscribeRequestFilterTransactions>,
     pub block_filters: BlockFilters,
     pub account_deletions_tracked: Arc<RwLock<HashSet<Pubkey>>>,
+    pub slot_status_tracked: bool,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -67,8 +70,15 @@ impl YellowstoneGrpcGeyserClient {
             transaction_filters,
             block_filters,
             account_deletions_tracked,
+            slot_status_tracked: false,
         }
     }
+
+    /// Subscribes to slot status updates, emitted as `Update::SlotStatus`.
+    pub fn with_slot_status(mut self) -> Self {
+        self.slot_status_tracked = true;
+        self
+    }
 }
 
 #[async_trait]
@@ -78,6 +88,24 @@ impl Datasource for YellowstoneGrpcGeyserClient {
         sender: Sender<Update>,
         cancellation_token: CancellationToken,
         metrics: Arc<MetricsCollection>,
+    ) -> CarbonResult<()> {
+        self.consume_with_backpressure(
+            sender,
+            cancellation_token,
+            metrics,
+            Backpressure::disabled(),
+        )
+        .await
+    }
+
+    /// Streams updates, pausing the reads of the stream while the pipeline is
+    /// overloaded, so that gRPC flow control slows down the server.
+    async fn consume_with_backpressure(
+        &self,
+        sender: Sender<Update>,
+        cancellation_token: CancellationToken,
+        metrics: Arc<MetricsCollection>,
+        backpressure: Backpressure,
     ) -> CarbonResult<()> {
         let endpoint = self.endpoint.clone();
         let x_token = self.x_token.clone();
@@ -90,6 +118,17 @@ impl Datasource for YellowstoneGrpcGeyserClient {
             failed_transactions: block_failed_transactions,
         } = self.block_filters.clone();
         let retain_block_failed_transactions = block_failed_transactions.unwrap_or(true);
+        let slot_filters = if self.slot_status_tracked {
+            HashMap::from([(
+                "slots".to_string(),
+                SubscribeRequestFilterSlots {
+                    filter_by_commitment: Some(false),
+                    ..Default::default()
+                },
+            )])
+        } else {
+            HashMap::new()
+        };
 
         let mut geyser_client = GeyserGrpcClient::build_from_shared(endpoint)
             .map_err(|err| carbon_core::error::Error::FailedToConsumeDatasource(err.to_string()))?
@@ -105,7 +144,7 @@ impl Datasource for YellowstoneGrpcGeyserClient {
 
         tokio::spawn(async move {
             let subscribe_request = SubscribeRequest {
-                slots: HashMap::new(),
+                slots: slot_filters,
                 accounts: account_filters,
                 transactions: transaction_filters,
                 transactions_status: HashMap::new(),
@@ -127,7 +166,11 @@ impl Datasource for YellowstoneGrpcGeyserClient {
                     result = geyser_client.subscribe_with_request(Some(subscribe_request.clone())) => {
                         match result {
                             Ok((mut subscribe_tx, mut stream)) => {
-                                while let Some(message) = stream.next().await {
+                                loop {
+                                    backpressure.ready().await;
+                                    let Some(message) = stream.next().await else {
+                                        break;
+                                    };
                                     match message {
                                         Ok(msg) => match msg.update_oneof {
                                             Some(UpdateOneof::Account(account_update)) => {
@@ -165,6 +208,10 @@ impl Datasource for YellowstoneGrpcGeyserClient {
                                                 }
                                             }
 
+                                            Some(UpdateOneof::Slot(slot_update)) => {
+                                                send_subscribe_update_slot(slot_update, &metrics, &sender).await
+                                            }
+
                                             Some(UpdateOneof::Ping(_)) => {
                                                 match subscribe_tx
                                                     .send(SubscribeRequest {
@@ -202,11 +249,15 @@ impl Datasource for YellowstoneGrpcGeyserClient {
     }
 
     fn update_types(&self) -> Vec<UpdateType> {
-        vec![
+        let mut update_types = vec![
             UpdateType::AccountUpdate,
             UpdateType::Transaction,
             UpdateType::AccountDeletion,
-        ]
+        ];
+        if self.slot_status_tracked {
+            update_types.push(UpdateType::SlotStatus);
+        }
+        update_types
     }
 }
 
@@ -356,3 +407,37 @@ async fn send_subscribe_update_transaction_info(
         );
     }
 }
+
+async fn send_subscribe_update_slot(
+    slot_update: SubscribeUpdateSlot,
+    metrics: &MetricsCollection,
+    sender: &Sender<Update>,
+) {
+    let status = match GeyserSlotStatus::try_from(slot_update.status) {
+        Ok(GeyserSlotStatus::SlotProcessed) => SlotStatus::Processed,
+        Ok(GeyserSlotStatus::SlotConfirmed) => SlotStatus::Confirmed,
+        Ok(GeyserSlotStatus::SlotFinalized) => SlotStatus::Finalized,
+        Ok(GeyserSlotStatus::SlotDead) => SlotStatus::Forked,
+        // Intermediate statuses of the slot being replayed.
+        _ => return,
+    };
+
+    let update = Update::SlotStatus(SlotStatusUpdate {
+        slot: slot_update.slot,
+        parent: slot_update.parent,
+        status,
+    });
+    if let Err(e) = sender.try_send(update) {
+        log::error!(
+            "Failed to send slot status update at slot {}: {:?}",
+            slot_update.slot,
+            e
+        );
+        return;
+    }
+
+    metrics
+        .increment_counter("yellowstone_grpc_slot_updates_received", 1)
+        .await
+        .unwrap_or_else(|value| log::error!("Error recording metric: {}", value));
+}
@@ -73,4 +73,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for AddressLookupTableDeco
             data: entry,
         })
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
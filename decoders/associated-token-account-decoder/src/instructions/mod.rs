@@ -38,4 +38,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for SplAssociatedTokenAcco
             SplAssociatedTokenAccountInstruction::RecoverNested => recover_nested::RecoverNested,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
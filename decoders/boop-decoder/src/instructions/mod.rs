@@ -184,4 +184,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for BoopDecoder {
             BoopInstruction::TradingFeesSplitEvent => trading_fees_split_event::TradingFeesSplitEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -0,0 +1,82 @@
+use {super::ComputeBudgetDecoder, crate::PROGRAM_ID};
+pub mod request_heap_frame;
+pub mod request_units_deprecated;
+pub mod set_compute_unit_limit;
+pub mod set_compute_unit_price;
+pub mod set_loaded_accounts_data_size_limit;
+
+#[derive(
+    carbon_core::InstructionType,
+    serde::Serialize,
+    serde::Deserialize,
+    PartialEq,
+    Eq,
+    Debug,
+    Clone,
+    Hash,
+)]
+pub enum ComputeBudgetInstruction {
+    RequestUnitsDeprecated(request_units_deprecated::RequestUnitsDeprecated),
+    RequestHeapFrame(request_heap_frame::RequestHeapFrame),
+    SetComputeUnitLimit(set_compute_unit_limit::SetComputeUnitLimit),
+    SetComputeUnitPrice(set_compute_unit_price::SetComputeUnitPrice),
+    SetLoadedAccountsDataSizeLimit(
+        set_loaded_accounts_data_size_limit::SetLoadedAccountsDataSizeLimit,
+    ),
+}
+
+impl carbon_core::instruction::InstructionDecoder<'_> for ComputeBudgetDecoder {
+    type InstructionType = ComputeBudgetInstruction;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        if !instruction.program_id.eq(&PROGRAM_ID) {
+            return None;
+        }
+
+        carbon_core::try_decode_instructions!(instruction,
+            ComputeBudgetInstruction::RequestUnitsDeprecated => request_units_deprecated::RequestUnitsDeprecated,
+            ComputeBudgetInstruction::RequestHeapFrame => request_heap_frame::RequestHeapFrame,
+            ComputeBudgetInstruction::SetComputeUnitLimit => set_compute_unit_limit::SetComputeUnitLimit,
+            ComputeBudgetInstruction::SetComputeUnitPrice => set_compute_unit_price::SetComputeUnitPrice,
+            ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit => set_loaded_accounts_data_size_limit::SetLoadedAccountsDataSizeLimit,
+        )
+    }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec, carbon_core::instruction::InstructionDecoder};
+
+    #[test]
+    fn test_decode_set_compute_unit_price() {
+        // Arrange
+        let mut data = vec![3];
+        data.extend_from_slice(&50_000u64.to_le_bytes());
+        let instruction = solana_instruction::Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![],
+            data,
+        };
+        let expected_ix = ComputeBudgetInstruction::SetComputeUnitPrice(
+            set_compute_unit_price::SetComputeUnitPrice {
+                micro_lamports: 50_000,
+            },
+        );
+
+        // Act
+        let decoded = ComputeBudgetDecoder
+            .decode_instruction(&instruction)
+            .expect("decode instruction");
+
+        // Assert
+        assert_eq!(decoded.data, expected_ix);
+        assert_eq!(decoded.program_id, PROGRAM_ID);
+    }
+}
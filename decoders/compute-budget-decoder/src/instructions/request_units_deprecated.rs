@@ -0,0 +1,22 @@
+use carbon_core::{borsh, CarbonDeserialize};
+
+#[derive(
+    CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
+)]
+#[carbon(discriminator = "0x00")]
+pub struct RequestUnitsDeprecated {
+    pub units: u32,
+    pub additional_fee: u32,
+}
+
+pub struct RequestUnitsDeprecatedInstructionAccounts {}
+
+impl carbon_core::deserialize::ArrangeAccounts for RequestUnitsDeprecated {
+    type ArrangedAccounts = RequestUnitsDeprecatedInstructionAccounts;
+
+    fn arrange_accounts(
+        _accounts: &[solana_instruction::AccountMeta],
+    ) -> Option<Self::ArrangedAccounts> {
+        Some(RequestUnitsDeprecatedInstructionAccounts {})
+    }
+}
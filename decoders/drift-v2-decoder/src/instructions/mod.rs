@@ -675,4 +675,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for DriftDecoder {
             DriftInstruction::FuelSeasonRecordEvent => fuel_season_record_event::FuelSeasonRecordEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
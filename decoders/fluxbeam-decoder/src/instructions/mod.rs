@@ -51,6 +51,10 @@ impl carbon_core::instruction::InstructionDecoder<'_> for FluxbeamDecoder {
             FluxbeamInstruction::WithdrawSingleTokenTypeExactAmountOut => withdraw_single_token_type_exact_amount_out::WithdrawSingleTokenTypeExactAmountOut,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
 
 #[cfg(test)]
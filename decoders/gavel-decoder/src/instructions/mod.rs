@@ -59,4 +59,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for GavelDecoder {
             GavelInstruction::TransferLiquidity => transfer_liquidity::TransferLiquidity,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -83,6 +83,10 @@ impl carbon_core::instruction::InstructionDecoder<'_> for JupiterDcaDecoder {
             JupiterDcaInstruction::DepositEvent => deposit_event::DepositEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
 
 #[cfg(test)]
@@ -56,4 +56,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for JupiterLimitOrder2Deco
             JupiterLimitOrder2Instruction::CreateOrderEvent => create_order_event::CreateOrderEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
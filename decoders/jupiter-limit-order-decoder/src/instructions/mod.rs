@@ -65,4 +65,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for JupiterLimitOrderDecod
             JupiterLimitOrderInstruction::CreateOrderEvent => create_order_event::CreateOrderEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
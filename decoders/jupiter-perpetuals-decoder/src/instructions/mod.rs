@@ -208,4 +208,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for PerpetualsDecoder {
             PerpetualsInstruction::InstantDecreasePositionEvent => instant_decrease_position_event::InstantDecreasePositionEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -101,4 +101,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for KaminoFarmsDecoder {
             KaminoFarmsInstruction::IdlMissingTypes => idl_missing_types::IdlMissingTypes,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -65,4 +65,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for KaminoLimitOrderDecode
             KaminoLimitOrderInstruction::UserSwapBalancesEvent => user_swap_balances_event::UserSwapBalancesEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -65,4 +65,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for KaminoVaultDecoder {
             KaminoVaultInstruction::WithdrawFromAvailable => withdraw_from_available::WithdrawFromAvailable,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
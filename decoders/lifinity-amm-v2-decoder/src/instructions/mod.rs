@@ -38,6 +38,10 @@ impl carbon_core::instruction::InstructionDecoder<'_> for LifinityAmmV2Decoder {
             LifinityAmmV2Instruction::WithdrawAllTokenTypes => withdraw_all_token_types::WithdrawAllTokenTypes,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
 
 #[cfg(test)]
@@ -133,4 +133,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for MarginfiV2Decoder {
             MarginfiV2Instruction::MarginfiAccountTransferAccountAuthorityEvent => marginfi_account_transfer_account_authority_event::MarginfiAccountTransferAccountAuthorityEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -185,4 +185,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for MarinadeFinanceDecoder
             MarinadeFinanceInstruction::WithdrawStakeAccountEvent => withdraw_stake_account_event::WithdrawStakeAccountEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -0,0 +1,99 @@
+//! Normalizes the swaps of Meteora DLMM pools into the unified DEX stream of
+//! `carbon_core::dex_trade`.
+
+use {
+    crate::instructions::{
+        swap::Swap, swap2::Swap2, swap_exact_out::SwapExactOut, swap_exact_out2::SwapExactOut2,
+        swap_with_price_impact::SwapWithPriceImpact, swap_with_price_impact2::SwapWithPriceImpact2,
+        MeteoraDlmmInstruction,
+    },
+    carbon_core::{
+        deserialize::ArrangeAccounts,
+        dex_trade::{DexInstruction, NormalizedTrade},
+        instruction::DecodedInstruction,
+        transaction::TransactionMetadata,
+    },
+};
+
+impl DexInstruction for MeteoraDlmmInstruction {
+    fn trade(
+        instruction: &DecodedInstruction<Self>,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<NormalizedTrade> {
+        // Exact output swaps are measured by the balance change of the input
+        // account.
+        let (amount_in, lb_pair, user, user_token_in, user_token_out) = match &instruction.data {
+            MeteoraDlmmInstruction::Swap(swap) => {
+                let accounts = Swap::arrange_accounts(&instruction.accounts)?;
+                (
+                    Some(swap.amount_in),
+                    accounts.lb_pair,
+                    accounts.user,
+                    accounts.user_token_in,
+                    accounts.user_token_out,
+                )
+            }
+            MeteoraDlmmInstruction::Swap2(swap) => {
+                let accounts = Swap2::arrange_accounts(&instruction.accounts)?;
+                (
+                    Some(swap.amount_in),
+                    accounts.lb_pair,
+                    accounts.user,
+                    accounts.user_token_in,
+                    accounts.user_token_out,
+                )
+            }
+            MeteoraDlmmInstruction::SwapExactOut(_) => {
+                let accounts = SwapExactOut::arrange_accounts(&instruction.accounts)?;
+                (
+                    None,
+                    accounts.lb_pair,
+                    accounts.user,
+                    accounts.user_token_in,
+                    accounts.user_token_out,
+                )
+            }
+            MeteoraDlmmInstruction::SwapExactOut2(_) => {
+                let accounts = SwapExactOut2::arrange_accounts(&instruction.accounts)?;
+                (
+                    None,
+                    accounts.lb_pair,
+                    accounts.user,
+                    accounts.user_token_in,
+                    accounts.user_token_out,
+                )
+            }
+            MeteoraDlmmInstruction::SwapWithPriceImpact(swap) => {
+                let accounts = SwapWithPriceImpact::arrange_accounts(&instruction.accounts)?;
+                (
+                    Some(swap.amount_in),
+                    accounts.lb_pair,
+                    accounts.user,
+                    accounts.user_token_in,
+                    accounts.user_token_out,
+                )
+            }
+            MeteoraDlmmInstruction::SwapWithPriceImpact2(swap) => {
+                let accounts = SwapWithPriceImpact2::arrange_accounts(&instruction.accounts)?;
+                (
+                    Some(swap.amount_in),
+                    accounts.lb_pair,
+                    accounts.user,
+                    accounts.user_token_in,
+                    accounts.user_token_out,
+                )
+            }
+            _ => return None,
+        };
+
+        NormalizedTrade::from_token_accounts(
+            "meteora-dlmm",
+            lb_pair,
+            user,
+            &user_token_in,
+            &user_token_out,
+            amount_in,
+            transaction_metadata,
+        )
+    }
+}
@@ -2,6 +2,7 @@ use solana_pubkey::Pubkey;
 
 pub struct MeteoraDlmmDecoder;
 pub mod accounts;
+pub mod dex_trade;
 pub mod instructions;
 pub mod types;
 
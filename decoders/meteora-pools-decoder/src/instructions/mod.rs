@@ -154,4 +154,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for MeteoraPoolsDecoder {
             MeteoraPoolsProgramInstruction::PartnerClaimFeesEvent => partner_claim_fees_event::PartnerClaimFeesEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
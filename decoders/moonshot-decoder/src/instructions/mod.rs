@@ -46,6 +46,10 @@ impl carbon_core::instruction::InstructionDecoder<'_> for MoonshotDecoder {
             MoonshotInstruction::MigrationEvent => migration_event::MigrationEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
 
 #[cfg(test)]
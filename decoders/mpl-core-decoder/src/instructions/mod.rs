@@ -125,4 +125,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for MplCoreProgramDecoder
             MplCoreProgramInstruction::ExecuteV1 => execute_v1::ExecuteV1,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
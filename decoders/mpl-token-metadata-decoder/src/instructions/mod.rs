@@ -203,4 +203,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for TokenMetadataDecoder {
             TokenMetadataInstruction::CloseAccounts => close_accounts::CloseAccounts,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
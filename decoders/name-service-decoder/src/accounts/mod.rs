@@ -1,12 +1,10 @@
-use {
-    super::NameDecoder,
-    crate::PROGRAM_ID,
-    carbon_core::{account::AccountDecoder, deserialize::CarbonDeserialize},
-};
+use {super::NameDecoder, crate::PROGRAM_ID, carbon_core::account::AccountDecoder};
 pub mod name_record_header;
+pub mod reverse_lookup;
 
 pub enum NameAccount {
     NameRecordHeader(name_record_header::NameRecordHeader),
+    ReverseLookup(reverse_lookup::ReverseLookup),
 }
 
 impl AccountDecoder<'_> for NameDecoder {
@@ -19,18 +17,22 @@ impl AccountDecoder<'_> for NameDecoder {
             return None;
         }
 
-        if let Some(decoded_account) =
-            name_record_header::NameRecordHeader::deserialize(account.data.as_slice())
+        let data = if let Some(reverse_lookup) =
+            reverse_lookup::ReverseLookup::unpack(account.data.as_slice())
         {
-            return Some(carbon_core::account::DecodedAccount {
-                lamports: account.lamports,
-                data: NameAccount::NameRecordHeader(decoded_account),
-                owner: account.owner,
-                executable: account.executable,
-                rent_epoch: account.rent_epoch,
-            });
-        }
+            NameAccount::ReverseLookup(reverse_lookup)
+        } else {
+            let (header, _record) =
+                name_record_header::NameRecordHeader::unpack(account.data.as_slice())?;
+            NameAccount::NameRecordHeader(header)
+        };
 
-        None
+        Some(carbon_core::account::DecodedAccount {
+            lamports: account.lamports,
+            data,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        })
     }
 }
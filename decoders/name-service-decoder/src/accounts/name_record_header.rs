@@ -1,9 +1,35 @@
-use carbon_core::{borsh, CarbonDeserialize};
+use solana_pubkey::Pubkey;
 
-#[derive(CarbonDeserialize, Debug)]
-#[carbon(discriminator = "0x319893826f292f59")]
+/// The header of a name record, followed by the record data.
+///
+/// Name records are raw accounts without a discriminator, so the header is
+/// read from the start of the account data.
+#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash)]
 pub struct NameRecordHeader {
-    pub parent_name: solana_pubkey::Pubkey,
-    pub owner: solana_pubkey::Pubkey,
-    pub class: solana_pubkey::Pubkey,
+    pub parent_name: Pubkey,
+    pub owner: Pubkey,
+    pub class: Pubkey,
+}
+
+impl NameRecordHeader {
+    pub const LEN: usize = 96;
+
+    /// Reads the header of a name record, returning it along with the record
+    /// data.
+    pub fn unpack(data: &[u8]) -> Option<(Self, &[u8])> {
+        let (header, record) = data.split_at_checked(Self::LEN)?;
+        let pubkey = |index: usize| {
+            let bytes: [u8; 32] = header[index * 32..(index + 1) * 32].try_into().ok()?;
+            Some(Pubkey::new_from_array(bytes))
+        };
+
+        Some((
+            Self {
+                parent_name: pubkey(0)?,
+                owner: pubkey(1)?,
+                class: pubkey(2)?,
+            },
+            record,
+        ))
+    }
 }
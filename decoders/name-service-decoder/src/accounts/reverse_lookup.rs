@@ -0,0 +1,26 @@
+use {super::name_record_header::NameRecordHeader, crate::domain::REVERSE_LOOKUP_CLASS};
+
+/// A reverse lookup record, mapping a domain account back to its name.
+#[derive(Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash)]
+pub struct ReverseLookup {
+    pub header: NameRecordHeader,
+    /// The name of the domain, without the `.sol` suffix.
+    pub name: String,
+}
+
+impl ReverseLookup {
+    /// Reads a reverse lookup record, or returns `None` if the account is
+    /// another kind of name record.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let (header, record) = NameRecordHeader::unpack(data)?;
+        if header.class != REVERSE_LOOKUP_CLASS {
+            return None;
+        }
+
+        let (len, record) = record.split_at_checked(4)?;
+        let len = u32::from_le_bytes(len.try_into().ok()?) as usize;
+        let name = String::from_utf8(record.get(..len)?.to_vec()).ok()?;
+
+        Some(Self { header, name })
+    }
+}
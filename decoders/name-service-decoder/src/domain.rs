@@ -0,0 +1,111 @@
+//! Derives the addresses of Solana Name Service domains and their records.
+//!
+//! `.sol` domains are name records whose parent is the `.sol` top-level
+//! domain, addressed by the hash of their name. A wallet's primary domain is
+//! stored in its favourite domain account, and the name of a domain account is
+//! stored in its reverse lookup record.
+
+use {crate::PROGRAM_ID, sha2::Digest, solana_pubkey::Pubkey};
+
+/// The prefix of hashed names.
+pub const HASH_PREFIX: &str = "SPL Name Service";
+
+/// The `.sol` top-level domain, parent of every `.sol` domain.
+pub const SOL_TLD: Pubkey = Pubkey::from_str_const("58PwtjSDuFHuUkYjH9BYnnQKHfwo9reZhC2zMJv9JPkx");
+
+/// The class of reverse lookup records.
+pub const REVERSE_LOOKUP_CLASS: Pubkey =
+    Pubkey::from_str_const("33m47vH6Eav6jr5Ry86XjhRft2jRBLDnDgPSHoquXi2Z");
+
+/// The program storing the favourite domain of wallets.
+pub const NAME_OFFERS_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("85iDfUvr3HJyLM2zcq5BXSiDvUWfw6cSE1FfNBo8Ap29");
+
+const FAVOURITE_DOMAIN_SEED: &[u8] = b"favourite_domain";
+
+/// Hashes a name the way the name service program addresses it.
+pub fn hashed_name(name: &str) -> [u8; 32] {
+    sha2::Sha256::new()
+        .chain_update(HASH_PREFIX)
+        .chain_update(name)
+        .finalize()
+        .into()
+}
+
+/// Returns the address of the name record with the given hashed name, class
+/// and parent.
+pub fn name_account_key(
+    hashed_name: &[u8; 32],
+    class: Option<&Pubkey>,
+    parent: Option<&Pubkey>,
+) -> Pubkey {
+    let default = Pubkey::default();
+    let (key, _) = Pubkey::find_program_address(
+        &[
+            hashed_name,
+            class.unwrap_or(&default).as_ref(),
+            parent.unwrap_or(&default).as_ref(),
+        ],
+        &PROGRAM_ID,
+    );
+
+    key
+}
+
+/// Returns the address of a `.sol` domain, given with or without its suffix.
+pub fn domain_key(domain: &str) -> Pubkey {
+    let name = domain.strip_suffix(".sol").unwrap_or(domain);
+    name_account_key(&hashed_name(name), None, Some(&SOL_TLD))
+}
+
+/// Returns the address of the reverse lookup record of a domain account.
+pub fn reverse_lookup_key(domain_account: &Pubkey) -> Pubkey {
+    name_account_key(
+        &hashed_name(&domain_account.to_string()),
+        Some(&REVERSE_LOOKUP_CLASS),
+        None,
+    )
+}
+
+/// Returns the address of the favourite domain account of a wallet.
+pub fn favourite_domain_key(wallet: &Pubkey) -> Pubkey {
+    let (key, _) = Pubkey::find_program_address(
+        &[FAVOURITE_DOMAIN_SEED, wallet.as_ref()],
+        &NAME_OFFERS_PROGRAM_ID,
+    );
+
+    key
+}
+
+/// Reads the domain account stored in a favourite domain account, following
+/// its tag byte.
+pub fn parse_favourite_domain(data: &[u8]) -> Option<Pubkey> {
+    let bytes: [u8; 32] = data.get(1..33)?.try_into().ok()?;
+    Some(Pubkey::new_from_array(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::accounts::{name_record_header::NameRecordHeader, reverse_lookup::ReverseLookup},
+    };
+
+    #[test]
+    fn test_domain_key_and_reverse_lookup() {
+        let domain = Pubkey::from_str_const("Crf8hzfthWGbGbLTVCiqRqV5MVnbpHB1L9KQMd6gsinb");
+        assert_eq!(domain_key("bonfida.sol"), domain);
+        assert_eq!(domain_key("bonfida"), domain);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(Pubkey::default().as_ref());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(REVERSE_LOOKUP_CLASS.as_ref());
+        data.extend_from_slice(&7u32.to_le_bytes());
+        data.extend_from_slice(b"bonfida");
+        assert_eq!(data.len(), NameRecordHeader::LEN + 11);
+
+        let reverse_lookup = ReverseLookup::unpack(&data).expect("unpack reverse lookup");
+        assert_eq!(reverse_lookup.name, "bonfida");
+    }
+}
@@ -44,4 +44,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for NameDecoder {
             NameInstruction::Realloc => realloc::Realloc,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -2,6 +2,7 @@ use solana_pubkey::Pubkey;
 
 pub struct NameDecoder;
 pub mod accounts;
+pub mod domain;
 pub mod instructions;
 pub mod types;
 
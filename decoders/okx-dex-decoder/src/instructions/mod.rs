@@ -68,4 +68,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for OkxDexDecoder {
             OkxDexInstruction::SwapEvent => swap_event::SwapEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
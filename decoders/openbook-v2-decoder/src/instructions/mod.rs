@@ -133,4 +133,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for OpenbookV2Decoder {
             OpenbookV2Instruction::OpenOrdersPositionLogEvent => open_orders_position_log_event::OpenOrdersPositionLogEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
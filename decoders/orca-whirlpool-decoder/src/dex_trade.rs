@@ -0,0 +1,134 @@
+//! Normalizes the swaps and liquidity changes of Orca Whirlpools into the
+//! unified DEX stream of `carbon_core::dex_trade`.
+
+use {
+    crate::instructions::{
+        decrease_liquidity::DecreaseLiquidity, decrease_liquidity_v2::DecreaseLiquidityV2,
+        increase_liquidity::IncreaseLiquidity, increase_liquidity_v2::IncreaseLiquidityV2,
+        swap::Swap, swap_v2::SwapV2, OrcaWhirlpoolInstruction,
+    },
+    carbon_core::{
+        deserialize::ArrangeAccounts,
+        dex_trade::{
+            DexInstruction, LiquidityChangeKind, NormalizedLiquidityChange, NormalizedTrade,
+        },
+        instruction::DecodedInstruction,
+        transaction::TransactionMetadata,
+    },
+};
+
+const VENUE: &str = "orca-whirlpool";
+
+impl DexInstruction for OrcaWhirlpoolInstruction {
+    fn trade(
+        instruction: &DecodedInstruction<Self>,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<NormalizedTrade> {
+        let (swap_amount, amount_specified_is_input, a_to_b, accounts) = match &instruction.data {
+            OrcaWhirlpoolInstruction::Swap(swap) => {
+                let accounts = Swap::arrange_accounts(&instruction.accounts)?;
+                (
+                    swap.amount,
+                    swap.amount_specified_is_input,
+                    swap.a_to_b,
+                    (
+                        accounts.whirlpool,
+                        accounts.token_authority,
+                        accounts.token_owner_account_a,
+                        accounts.token_owner_account_b,
+                    ),
+                )
+            }
+            OrcaWhirlpoolInstruction::SwapV2(swap) => {
+                let accounts = SwapV2::arrange_accounts(&instruction.accounts)?;
+                (
+                    swap.amount,
+                    swap.amount_specified_is_input,
+                    swap.a_to_b,
+                    (
+                        accounts.whirlpool,
+                        accounts.token_authority,
+                        accounts.token_owner_account_a,
+                        accounts.token_owner_account_b,
+                    ),
+                )
+            }
+            _ => return None,
+        };
+
+        let (whirlpool, trader, token_a, token_b) = accounts;
+        let (token_in, token_out) = if a_to_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+
+        NormalizedTrade::from_token_accounts(
+            VENUE,
+            whirlpool,
+            trader,
+            &token_in,
+            &token_out,
+            amount_specified_is_input.then_some(swap_amount),
+            transaction_metadata,
+        )
+    }
+
+    fn liquidity_change(
+        instruction: &DecodedInstruction<Self>,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<NormalizedLiquidityChange> {
+        let (kind, whirlpool, provider, token_a, token_b) = match &instruction.data {
+            OrcaWhirlpoolInstruction::IncreaseLiquidity(_) => {
+                let accounts = IncreaseLiquidity::arrange_accounts(&instruction.accounts)?;
+                (
+                    LiquidityChangeKind::Deposit,
+                    accounts.whirlpool,
+                    accounts.position_authority,
+                    accounts.token_owner_account_a,
+                    accounts.token_owner_account_b,
+                )
+            }
+            OrcaWhirlpoolInstruction::IncreaseLiquidityV2(_) => {
+                let accounts = IncreaseLiquidityV2::arrange_accounts(&instruction.accounts)?;
+                (
+                    LiquidityChangeKind::Deposit,
+                    accounts.whirlpool,
+                    accounts.position_authority,
+                    accounts.token_owner_account_a,
+                    accounts.token_owner_account_b,
+                )
+            }
+            OrcaWhirlpoolInstruction::DecreaseLiquidity(_) => {
+                let accounts = DecreaseLiquidity::arrange_accounts(&instruction.accounts)?;
+                (
+                    LiquidityChangeKind::Withdrawal,
+                    accounts.whirlpool,
+                    accounts.position_authority,
+                    accounts.token_owner_account_a,
+                    accounts.token_owner_account_b,
+                )
+            }
+            OrcaWhirlpoolInstruction::DecreaseLiquidityV2(_) => {
+                let accounts = DecreaseLiquidityV2::arrange_accounts(&instruction.accounts)?;
+                (
+                    LiquidityChangeKind::Withdrawal,
+                    accounts.whirlpool,
+                    accounts.position_authority,
+                    accounts.token_owner_account_a,
+                    accounts.token_owner_account_b,
+                )
+            }
+            _ => return None,
+        };
+
+        NormalizedLiquidityChange::from_token_accounts(
+            VENUE,
+            whirlpool,
+            provider,
+            kind,
+            &[token_a, token_b],
+            transaction_metadata,
+        )
+    }
+}
@@ -117,4 +117,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for PhoenixDecoder {
             PhoenixInstruction::ChangeFeeRecipient => change_fee_recipient::ChangeFeeRecipient,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
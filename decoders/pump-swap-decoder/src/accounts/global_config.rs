@@ -10,3 +10,46 @@ pub struct GlobalConfig {
     pub protocol_fee_recipients: [solana_pubkey::Pubkey; 8],
     pub coin_creator_fee_basis_points: u64,
 }
+
+const BASIS_POINTS: u128 = 10_000;
+
+impl GlobalConfig {
+    /// Returns the fees charged on swaps, in basis points, LP, protocol and
+    /// coin creator fees included.
+    pub fn total_fee_basis_points(&self) -> u64 {
+        self.lp_fee_basis_points
+            + self.protocol_fee_basis_points
+            + self.coin_creator_fee_basis_points
+    }
+
+    /// Returns the amount of base tokens bought when spending `quote_in`,
+    /// fees included, from a pool holding the given reserves.
+    ///
+    /// The reserves are the balances of the pool's base and quote token
+    /// accounts.
+    pub fn base_out(&self, quote_in: u64, base_reserves: u64, quote_reserves: u64) -> u64 {
+        let quote_in = quote_in as u128 * BASIS_POINTS
+            / (BASIS_POINTS + self.total_fee_basis_points() as u128);
+
+        constant_product_out(quote_in, quote_reserves, base_reserves)
+    }
+
+    /// Returns the amount of quote tokens received for selling `base_in`,
+    /// after fees, to a pool holding the given reserves.
+    pub fn quote_out(&self, base_in: u64, base_reserves: u64, quote_reserves: u64) -> u64 {
+        let quote_out =
+            constant_product_out(base_in as u128, base_reserves, quote_reserves) as u128;
+        let fee = quote_out * self.total_fee_basis_points() as u128 / BASIS_POINTS;
+
+        (quote_out - fee) as u64
+    }
+}
+
+fn constant_product_out(amount_in: u128, reserves_in: u64, reserves_out: u64) -> u64 {
+    let denominator = reserves_in as u128 + amount_in;
+    if denominator == 0 {
+        return 0;
+    }
+
+    (amount_in * reserves_out as u128 / denominator) as u64
+}
@@ -1,5 +1,14 @@
 use carbon_core::{borsh, CarbonDeserialize};
 
+/// The number of decimals of the tokens launched on pump.fun.
+pub const TOKEN_DECIMALS: u8 = 6;
+
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// The number of base units of a token, with `TOKEN_DECIMALS` decimals.
+const UNITS_PER_TOKEN: f64 = 1_000_000.0;
+
+const BASIS_POINTS: u128 = 10_000;
+
 #[derive(CarbonDeserialize, Debug)]
 #[carbon(discriminator = "0x17b7f83760d8ac60")]
 pub struct BondingCurve {
@@ -10,3 +19,75 @@ pub struct BondingCurve {
     pub token_total_supply: u64,
     pub complete: bool,
 }
+
+impl BondingCurve {
+    /// Returns the spot price of a token, in SOL, from the virtual reserves.
+    pub fn price(&self) -> f64 {
+        if self.virtual_token_reserves == 0 {
+            return 0.0;
+        }
+
+        (self.virtual_sol_reserves as f64 / LAMPORTS_PER_SOL)
+            / (self.virtual_token_reserves as f64 / UNITS_PER_TOKEN)
+    }
+
+    /// Returns the market cap of the token, in SOL, at the spot price.
+    pub fn market_cap(&self) -> f64 {
+        self.price() * self.token_total_supply as f64 / UNITS_PER_TOKEN
+    }
+
+    /// Returns the amount of tokens bought with `sol_in` lamports, before
+    /// fees, capped by the real token reserves.
+    ///
+    /// Returns `0` once the curve is complete.
+    pub fn tokens_out(&self, sol_in: u64) -> u64 {
+        if self.complete || sol_in == 0 {
+            return 0;
+        }
+
+        let virtual_sol_reserves = self.virtual_sol_reserves as u128;
+        let virtual_token_reserves = self.virtual_token_reserves as u128;
+        let product = virtual_sol_reserves * virtual_token_reserves;
+        let remaining_token_reserves = product / (virtual_sol_reserves + sol_in as u128) + 1;
+        let tokens_out = virtual_token_reserves.saturating_sub(remaining_token_reserves);
+
+        tokens_out.min(self.real_token_reserves as u128) as u64
+    }
+
+    /// Returns the amount of tokens bought when spending `sol_in` lamports,
+    /// fees included.
+    ///
+    /// Fees are charged on top of the SOL cost of a buy. `fee_basis_points`
+    /// is the sum of the protocol and creator fees of the `Global` account.
+    pub fn tokens_out_after_fees(&self, sol_in: u64, fee_basis_points: u64) -> u64 {
+        let sol_cost = sol_in as u128 * BASIS_POINTS / (BASIS_POINTS + fee_basis_points as u128);
+        self.tokens_out(sol_cost as u64)
+    }
+
+    /// Returns the amount of lamports received for selling `tokens_in`
+    /// tokens, after deducting `fee_basis_points` of fees.
+    ///
+    /// Returns `0` once the curve is complete.
+    pub fn sol_out(&self, tokens_in: u64, fee_basis_points: u64) -> u64 {
+        if self.complete || tokens_in == 0 {
+            return 0;
+        }
+
+        let sol_out = tokens_in as u128 * self.virtual_sol_reserves as u128
+            / (self.virtual_token_reserves as u128 + tokens_in as u128);
+        let fee = sol_out * fee_basis_points as u128 / BASIS_POINTS;
+
+        (sol_out - fee).min(self.real_sol_reserves as u128) as u64
+    }
+
+    /// Returns the share of the initial real token reserves sold so far, from
+    /// `0.0` to `1.0`.
+    pub fn progress(&self, initial_real_token_reserves: u64) -> f64 {
+        if initial_real_token_reserves == 0 {
+            return 0.0;
+        }
+
+        let sold = initial_real_token_reserves.saturating_sub(self.real_token_reserves);
+        sold as f64 / initial_real_token_reserves as f64
+    }
+}
@@ -20,6 +20,14 @@ pub struct Global {
     pub set_creator_authority: solana_pubkey::Pubkey,
 }
 
+impl Global {
+    /// Returns the fees charged on trades, protocol and creator fees included,
+    /// in basis points.
+    pub fn total_fee_basis_points(&self) -> u64 {
+        self.fee_basis_points + self.creator_fee_basis_points
+    }
+}
+
 /*
 use carbon_core::{borsh, CarbonDeserialize};
 
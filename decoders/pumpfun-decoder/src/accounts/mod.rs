@@ -171,4 +171,26 @@ mod tests {
             _ => panic!("Expected Global"),
         }
     }
+
+    #[test]
+    fn test_bonding_curve_math() {
+        let bonding_curve = bonding_curve::BondingCurve {
+            virtual_token_reserves: 1072911112000000,
+            virtual_sol_reserves: 30002485430,
+            real_token_reserves: 793011112000000,
+            real_sol_reserves: 2485430000,
+            token_total_supply: 1000000000000000,
+            complete: false,
+        };
+
+        assert_eq!(bonding_curve.tokens_out(1_000_000_000), 34607261228216);
+        assert_eq!(bonding_curve.sol_out(1_000_000_000_000, 100), 27658212);
+        assert!((bonding_curve.price() - 2.7963626e-8).abs() < 1e-14);
+
+        let complete = bonding_curve::BondingCurve {
+            complete: true,
+            ..bonding_curve
+        };
+        assert_eq!(complete.tokens_out(1_000_000_000), 0);
+    }
 }
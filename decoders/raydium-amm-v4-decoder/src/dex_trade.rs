@@ -0,0 +1,57 @@
+//! Normalizes the swaps of Raydium AMM v4 pools into the unified DEX stream
+//! of `carbon_core::dex_trade`.
+
+use {
+    crate::instructions::{
+        swap_base_in::SwapBaseIn, swap_base_out::SwapBaseOut, RaydiumAmmV4Instruction,
+    },
+    carbon_core::{
+        deserialize::ArrangeAccounts,
+        dex_trade::{DexInstruction, NormalizedTrade},
+        instruction::DecodedInstruction,
+        transaction::TransactionMetadata,
+    },
+};
+
+impl DexInstruction for RaydiumAmmV4Instruction {
+    fn trade(
+        instruction: &DecodedInstruction<Self>,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<NormalizedTrade> {
+        let (amount_in, amm, owner, source, destination) = match &instruction.data {
+            RaydiumAmmV4Instruction::SwapBaseIn(swap) => {
+                let accounts = SwapBaseIn::arrange_accounts(&instruction.accounts)?;
+                (
+                    Some(swap.amount_in),
+                    accounts.amm,
+                    accounts.user_source_owner,
+                    accounts.user_source_token_account,
+                    accounts.user_destination_token_account,
+                )
+            }
+            // Exact output swaps are measured by the balance change of the
+            // source account.
+            RaydiumAmmV4Instruction::SwapBaseOut(_) => {
+                let accounts = SwapBaseOut::arrange_accounts(&instruction.accounts)?;
+                (
+                    None,
+                    accounts.amm,
+                    accounts.user_source_owner,
+                    accounts.user_source_token_account,
+                    accounts.user_destination_token_account,
+                )
+            }
+            _ => return None,
+        };
+
+        NormalizedTrade::from_token_accounts(
+            "raydium-amm-v4",
+            amm,
+            owner,
+            &source,
+            &destination,
+            amount_in,
+            transaction_metadata,
+        )
+    }
+}
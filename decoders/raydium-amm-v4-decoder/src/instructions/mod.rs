@@ -77,4 +77,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for RaydiumAmmV4Decoder {
             RaydiumAmmV4Instruction::UpdateConfigAccount => update_config_account::UpdateConfigAccount,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
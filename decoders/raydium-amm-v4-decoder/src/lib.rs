@@ -4,6 +4,7 @@ use solana_pubkey::Pubkey;
 
 pub struct RaydiumAmmV4Decoder;
 pub mod accounts;
+pub mod dex_trade;
 pub mod instructions;
 pub mod types;
 
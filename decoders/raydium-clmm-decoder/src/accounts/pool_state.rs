@@ -1,5 +1,7 @@
 use {
-    super::super::types::*,
+    super::{super::types::*, tick_array_bitmap_extension::TickArrayBitmapExtension},
+    crate::tick_math::{self, TICK_ARRAY_BITMAP_SIZE},
+    alloc::vec::Vec,
     carbon_core::{borsh, CarbonDeserialize},
 };
 
@@ -45,3 +47,80 @@ pub struct PoolState {
     pub padding1: [u64; 24],
     pub padding2: [u64; 32],
 }
+
+impl PoolState {
+    /// Returns the price of a whole token 0 in whole tokens 1.
+    pub fn price(&self) -> f64 {
+        tick_math::sqrt_price_x64_to_price(
+            self.sqrt_price_x64,
+            self.mint_decimals0,
+            self.mint_decimals1,
+        )
+    }
+
+    /// Returns the start index of the tick array containing the current tick.
+    pub fn current_tick_array_start_index(&self) -> i32 {
+        tick_math::tick_array_start_index(self.tick_current, self.tick_spacing)
+    }
+
+    /// Returns the start indexes of the initialized tick arrays of the pool,
+    /// in ascending order.
+    ///
+    /// The bitmap of the pool only tracks the `TICK_ARRAY_BITMAP_SIZE` tick
+    /// arrays on each side of tick 0. Tick arrays further away are tracked by
+    /// the tick array bitmap extension of the pool, which should be given for
+    /// pools with a wide price range.
+    pub fn tick_array_start_indexes(
+        &self,
+        extension: Option<&TickArrayBitmapExtension>,
+    ) -> Vec<i32> {
+        let ticks_in_array = tick_math::ticks_in_tick_array(self.tick_spacing);
+        let mut start_indexes: Vec<i32> = tick_math::set_bits(&self.tick_array_bitmap)
+            .map(|bit| (bit - TICK_ARRAY_BITMAP_SIZE) * ticks_in_array)
+            .collect();
+
+        if let Some(extension) = extension {
+            start_indexes.extend(extension.tick_array_start_indexes(self.tick_spacing));
+            start_indexes.sort_unstable();
+        }
+
+        start_indexes
+    }
+
+    /// Returns the start index of the first initialized tick array a swap
+    /// would cross: the current tick array if it is initialized, or the next
+    /// initialized one in the direction of the swap.
+    pub fn first_initialized_tick_array_start_index(
+        &self,
+        extension: Option<&TickArrayBitmapExtension>,
+        zero_for_one: bool,
+    ) -> Option<i32> {
+        let current = self.current_tick_array_start_index();
+        if self.tick_array_start_indexes(extension).contains(&current) {
+            return Some(current);
+        }
+
+        self.next_initialized_tick_array_start_index(extension, current, zero_for_one)
+    }
+
+    /// Returns the start index of the next initialized tick array after
+    /// `start_tick_index`, below it if `zero_for_one`, or above it otherwise.
+    pub fn next_initialized_tick_array_start_index(
+        &self,
+        extension: Option<&TickArrayBitmapExtension>,
+        start_tick_index: i32,
+        zero_for_one: bool,
+    ) -> Option<i32> {
+        let start_indexes = self.tick_array_start_indexes(extension);
+        if zero_for_one {
+            start_indexes
+                .into_iter()
+                .rev()
+                .find(|index| *index < start_tick_index)
+        } else {
+            start_indexes
+                .into_iter()
+                .find(|index| *index > start_tick_index)
+        }
+    }
+}
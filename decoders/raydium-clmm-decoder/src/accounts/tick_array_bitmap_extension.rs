@@ -1,4 +1,8 @@
-use carbon_core::{borsh, CarbonDeserialize};
+use {
+    crate::tick_math::{self, TICK_ARRAY_BITMAP_SIZE},
+    alloc::vec::Vec,
+    carbon_core::{borsh, CarbonDeserialize},
+};
 
 #[derive(CarbonDeserialize, Debug)]
 #[carbon(discriminator = "0x3c9624db61808b99")]
@@ -7,3 +11,39 @@ pub struct TickArrayBitmapExtension {
     pub positive_tick_array_bitmap: [[u64; 8]; 14],
     pub negative_tick_array_bitmap: [[u64; 8]; 14],
 }
+
+impl TickArrayBitmapExtension {
+    /// Returns the start indexes of the initialized tick arrays tracked by the
+    /// extension, in ascending order.
+    ///
+    /// The extension tracks the tick arrays out of range of the bitmap of the
+    /// pool, each of its bitmaps covering `TICK_ARRAY_BITMAP_SIZE` tick arrays
+    /// further from tick 0.
+    pub fn tick_array_start_indexes(&self, tick_spacing: u16) -> Vec<i32> {
+        let ticks_in_array = tick_math::ticks_in_tick_array(tick_spacing);
+        let ticks_in_bitmap = tick_math::ticks_in_tick_array_bitmap(tick_spacing);
+
+        let negative = self
+            .negative_tick_array_bitmap
+            .iter()
+            .enumerate()
+            .rev()
+            .flat_map(|(index, bitmap)| {
+                tick_math::set_bits(bitmap).map(move |bit| {
+                    -(index as i32 + 1) * ticks_in_bitmap
+                        - (TICK_ARRAY_BITMAP_SIZE - bit) * ticks_in_array
+                })
+            });
+        let positive =
+            self.positive_tick_array_bitmap
+                .iter()
+                .enumerate()
+                .flat_map(|(index, bitmap)| {
+                    let bitmap_start_index = (index as i32 + 1) * ticks_in_bitmap;
+                    tick_math::set_bits(bitmap)
+                        .map(move |bit| bitmap_start_index + bit * ticks_in_array)
+                });
+
+        negative.chain(positive).collect()
+    }
+}
@@ -1,5 +1,6 @@
 use {
     super::super::types::*,
+    crate::tick_math,
     carbon_core::{borsh, CarbonDeserialize},
 };
 
@@ -13,3 +14,56 @@ pub struct TickArrayState {
     pub recent_epoch: u64,
     pub padding: [u8; 107],
 }
+
+impl TickArrayState {
+    /// Returns the state of a tick, or `None` if the tick is out of the array
+    /// or not a multiple of the tick spacing.
+    pub fn tick(&self, tick_index: i32, tick_spacing: u16) -> Option<&TickState> {
+        self.ticks.get(self.tick_offset(tick_index, tick_spacing)?)
+    }
+
+    /// Returns the next initialized tick of the array from `tick_index`, the
+    /// highest one at or below it if `zero_for_one`, or the lowest one above
+    /// it otherwise, as crossed by a swap in that direction.
+    pub fn next_initialized_tick(
+        &self,
+        tick_index: i32,
+        tick_spacing: u16,
+        zero_for_one: bool,
+    ) -> Option<&TickState> {
+        let spacing = tick_spacing as i32;
+        let offset = (tick_index - self.start_tick_index).div_euclid(spacing);
+        let is_initialized = |tick: &&TickState| tick.liquidity_gross != 0;
+
+        if zero_for_one {
+            let last = usize::try_from(offset).ok()?.min(self.ticks.len() - 1);
+            self.ticks[..=last].iter().rev().find(is_initialized)
+        } else {
+            let first = usize::try_from(offset + 1).unwrap_or(0);
+            self.ticks.get(first..)?.iter().find(is_initialized)
+        }
+    }
+
+    /// Returns the start index of the next tick array in the direction of a
+    /// swap.
+    pub fn next_start_tick_index(&self, tick_spacing: u16, zero_for_one: bool) -> i32 {
+        let ticks_in_array = tick_math::ticks_in_tick_array(tick_spacing);
+        if zero_for_one {
+            self.start_tick_index - ticks_in_array
+        } else {
+            self.start_tick_index + ticks_in_array
+        }
+    }
+
+    fn tick_offset(&self, tick_index: i32, tick_spacing: u16) -> Option<usize> {
+        let spacing = tick_spacing as i32;
+        let offset = tick_index.checked_sub(self.start_tick_index)?;
+        if spacing == 0 || offset % spacing != 0 {
+            return None;
+        }
+
+        usize::try_from(offset / spacing)
+            .ok()
+            .filter(|offset| *offset < self.ticks.len())
+    }
+}
@@ -7,6 +7,7 @@ use solana_pubkey::Pubkey;
 pub struct RaydiumClmmDecoder;
 pub mod accounts;
 pub mod instructions;
+pub mod tick_math;
 pub mod types;
 
 pub const PROGRAM_ID: Pubkey =
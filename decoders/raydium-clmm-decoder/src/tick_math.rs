@@ -0,0 +1,245 @@
+//! Tick, price and tick array helpers matching the on-chain math of the
+//! Raydium CLMM program.
+//!
+//! Prices are stored as the square root of the price of token 0 in token 1,
+//! as a Q64.64 fixed point number. Each tick is a 0.01% price step, and ticks
+//! are grouped in tick arrays of `TICK_ARRAY_SIZE` ticks spaced by the tick
+//! spacing of their pool.
+
+use {crate::PROGRAM_ID, solana_pubkey::Pubkey};
+
+/// The lowest tick of a pool.
+pub const MIN_TICK: i32 = -443636;
+/// The highest tick of a pool.
+pub const MAX_TICK: i32 = -MIN_TICK;
+/// The square root price, as a Q64.64 number, at `MIN_TICK`.
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+/// The square root price, as a Q64.64 number, at `MAX_TICK`.
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+
+/// The number of ticks of a tick array.
+pub const TICK_ARRAY_SIZE: i32 = 60;
+/// The number of tick arrays tracked by each bitmap, on each side of tick 0
+/// for the bitmap of a pool.
+pub const TICK_ARRAY_BITMAP_SIZE: i32 = 512;
+
+pub const TICK_ARRAY_SEED: &[u8] = b"tick_array";
+pub const POOL_TICK_ARRAY_BITMAP_SEED: &[u8] = b"pool_tick_array_bitmap_extension";
+
+/// `2^64 / sqrt(1.0001)^(2^i)`, as used by the program to compute square
+/// root prices.
+const SQRT_PRICE_FACTORS: [u128; 19] = [
+    0xfffcb933bd6fb800,
+    0xfff97272373d4000,
+    0xfff2e50f5f657000,
+    0xffe5caca7e10f000,
+    0xffcb9843d60f7000,
+    0xff973b41fa98e800,
+    0xff2ea16466c9b000,
+    0xfe5dee046a9a3800,
+    0xfcbe86c7900bb000,
+    0xf987a7253ac65800,
+    0xf3392b0822bb6000,
+    0xe7159475a2caf000,
+    0xd097f3bdfd2f2000,
+    0xa9f746462d9f8000,
+    0x70d869a156f31c00,
+    0x31be135f97ed3200,
+    0x9aa508b5b85a500,
+    0x5d6af8dedc582c,
+    0x2216e584f5fa,
+];
+
+const Q64: f64 = 18446744073709551616.0;
+
+/// Returns the square root price, as a Q64.64 number, at a tick, or `None`
+/// if the tick is out of bounds.
+pub fn sqrt_price_x64_at_tick(tick: i32) -> Option<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio: u128 = if abs_tick & 1 != 0 {
+        SQRT_PRICE_FACTORS[0]
+    } else {
+        1 << 64
+    };
+    for (bit, factor) in SQRT_PRICE_FACTORS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+
+    Some(ratio)
+}
+
+/// Returns the highest tick whose square root price is lower than or equal
+/// to `sqrt_price_x64`, or `None` if the price is out of bounds.
+pub fn tick_at_sqrt_price_x64(sqrt_price_x64: u128) -> Option<i32> {
+    if !(MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        return None;
+    }
+
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if sqrt_price_x64_at_tick(middle)? <= sqrt_price_x64 {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+
+    Some(low)
+}
+
+/// Converts a square root price to the price of a whole token 0 in whole
+/// tokens 1.
+pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128, decimals0: u8, decimals1: u8) -> f64 {
+    let sqrt_price = sqrt_price_x64 as f64 / Q64;
+    sqrt_price * sqrt_price * pow10(decimals0 as i32 - decimals1 as i32)
+}
+
+/// Returns the price of a whole token 0 in whole tokens 1 at a tick, or
+/// `None` if the tick is out of bounds.
+pub fn tick_to_price(tick: i32, decimals0: u8, decimals1: u8) -> Option<f64> {
+    Some(sqrt_price_x64_to_price(
+        sqrt_price_x64_at_tick(tick)?,
+        decimals0,
+        decimals1,
+    ))
+}
+
+/// Returns the highest tick whose price is lower than or equal to the price
+/// of a whole token 0 in whole tokens 1, or `None` if the price is out of
+/// bounds.
+pub fn price_to_tick(price: f64, decimals0: u8, decimals1: u8) -> Option<i32> {
+    let raw_price = price * pow10(decimals1 as i32 - decimals0 as i32);
+    if !raw_price.is_finite() || raw_price <= 0.0 {
+        return None;
+    }
+
+    tick_at_sqrt_price_x64((sqrt(raw_price) * Q64) as u128)
+}
+
+/// Returns the number of ticks covered by a tick array.
+pub fn ticks_in_tick_array(tick_spacing: u16) -> i32 {
+    TICK_ARRAY_SIZE * tick_spacing as i32
+}
+
+/// Returns the start index of the tick array containing a tick.
+pub fn tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    tick.div_euclid(ticks_in_tick_array(tick_spacing)) * ticks_in_tick_array(tick_spacing)
+}
+
+/// Returns the number of ticks covered by the tick arrays of a bitmap.
+pub fn ticks_in_tick_array_bitmap(tick_spacing: u16) -> i32 {
+    TICK_ARRAY_BITMAP_SIZE * ticks_in_tick_array(tick_spacing)
+}
+
+/// Derives the address of the tick array of a pool starting at a tick.
+pub fn tick_array_address(pool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED,
+            pool.as_ref(),
+            &start_tick_index.to_be_bytes(),
+        ],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+/// Derives the address of the tick array bitmap extension of a pool.
+pub fn tick_array_bitmap_extension_address(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[POOL_TICK_ARRAY_BITMAP_SEED, pool.as_ref()], &PROGRAM_ID).0
+}
+
+/// Returns the positions of the set bits of a little-endian bitmap.
+pub(crate) fn set_bits(words: &[u64]) -> impl Iterator<Item = i32> + '_ {
+    words.iter().enumerate().flat_map(|(index, word)| {
+        (0..64)
+            .filter(move |bit| word & (1 << bit) != 0)
+            .map(move |bit| (index * 64 + bit) as i32)
+    })
+}
+
+fn pow10(exponent: i32) -> f64 {
+    let mut value = 1.0;
+    for _ in 0..exponent.unsigned_abs() {
+        value *= 10.0;
+    }
+
+    if exponent < 0 {
+        1.0 / value
+    } else {
+        value
+    }
+}
+
+/// Computes a square root with Newton's method, as `f64::sqrt` requires `std`.
+fn sqrt(value: f64) -> f64 {
+    let mut root = if value > 1.0 { value / 2.0 } else { 1.0 };
+    for _ in 0..128 {
+        let next = (root + value / root) / 2.0;
+        if next == root {
+            break;
+        }
+        root = next;
+    }
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::accounts::tick_array_bitmap_extension::TickArrayBitmapExtension};
+
+    #[test]
+    fn test_tick_and_sqrt_price_conversions() {
+        assert_eq!(sqrt_price_x64_at_tick(MIN_TICK), Some(MIN_SQRT_PRICE_X64));
+        assert_eq!(sqrt_price_x64_at_tick(MAX_TICK), Some(MAX_SQRT_PRICE_X64));
+        assert_eq!(sqrt_price_x64_at_tick(0), Some(1 << 64));
+        assert_eq!(sqrt_price_x64_at_tick(1), Some(18447666387855957090));
+        assert_eq!(sqrt_price_x64_at_tick(MAX_TICK + 1), None);
+
+        for tick in [MIN_TICK, -60_000, -1, 0, 1, 12_345, MAX_TICK - 1] {
+            let sqrt_price_x64 = sqrt_price_x64_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price_x64(sqrt_price_x64), Some(tick));
+            assert_eq!(tick_at_sqrt_price_x64(sqrt_price_x64 + 1), Some(tick));
+        }
+
+        // 1 SOL (9 decimals) for 150 USDC (6 decimals).
+        let tick = price_to_tick(150.0, 9, 6).unwrap();
+        assert!(tick_to_price(tick, 9, 6).unwrap() <= 150.0);
+        assert!(tick_to_price(tick + 1, 9, 6).unwrap() > 150.0);
+
+        assert_eq!(tick_array_start_index(59, 1), 0);
+        assert_eq!(tick_array_start_index(-1, 1), -60);
+        assert_eq!(tick_array_start_index(-600, 10), -600);
+    }
+
+    #[test]
+    fn test_bitmap_extension_start_indexes() {
+        let mut extension = TickArrayBitmapExtension {
+            pool_id: Pubkey::default(),
+            positive_tick_array_bitmap: [[0; 8]; 14],
+            negative_tick_array_bitmap: [[0; 8]; 14],
+        };
+        extension.positive_tick_array_bitmap[0][0] = 1 << 1;
+        extension.negative_tick_array_bitmap[0][0] = 1;
+        extension.negative_tick_array_bitmap[0][7] = 1 << 63;
+
+        // With a tick spacing of 1, tick arrays span 60 ticks and bitmaps span
+        // 30720 ticks.
+        assert_eq!(
+            extension.tick_array_start_indexes(1),
+            [-61440, -30780, 30780]
+        );
+    }
+}
@@ -65,4 +65,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for RaydiumCpmmDecoder {
             RaydiumCpmmInstruction::SwapEvent => swap_event::SwapEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -89,4 +89,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for RaydiumLaunchpadDecode
             RaydiumLaunchpadInstruction::TradeEvent => trade_event::TradeEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -44,4 +44,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for RaydiumLiquidityLockin
             RaydiumLiquidityLockingInstruction::SettleCpFeeEvent => settle_cp_fee_event::SettleCpFeeEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -83,4 +83,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for SharkyDecoder {
             SharkyInstruction::UpdateProgramVersion => update_program_version::UpdateProgramVersion,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
@@ -38,4 +38,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for SolayerRestakingProgra
             SolayerRestakingProgramInstruction::BatchThawLstAccounts => batch_thaw_lst_accounts::BatchThawLstAccounts,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
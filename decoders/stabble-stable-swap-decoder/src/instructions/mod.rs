@@ -86,4 +86,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for StableSwapDecoder {
             StableSwapInstruction::PoolUpdatedEvent => pool_updated_event::PoolUpdatedEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
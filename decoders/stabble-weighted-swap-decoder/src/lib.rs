@@ -3,6 +3,7 @@ use solana_pubkey::Pubkey;
 pub struct WeightedSwapDecoder;
 pub mod accounts;
 pub mod instructions;
+pub mod swap_event;
 pub mod types;
 
 pub const PROGRAM_ID: Pubkey =
@@ -0,0 +1,143 @@
+//! Normalized swaps of the Stabble weighted swap program.
+//!
+//! The `Swap` and `SwapV2` instructions only carry the input amount, which is
+//! optional, and the minimum output amount. The amounts actually traded are
+//! computed from the token balance changes of the accounts of the instruction.
+
+use {
+    crate::instructions::{swap::Swap, swap_v2::SwapV2, WeightedSwapInstruction},
+    carbon_core::{
+        deserialize::ArrangeAccounts,
+        dex_trade::{DexInstruction, DexTrade, NormalizedTrade, TradeFee},
+        instruction::DecodedInstruction,
+        transaction::TransactionMetadata,
+    },
+    solana_pubkey::Pubkey,
+};
+
+/// A swap executed by the Stabble weighted swap program.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// The share of the swap fee sent to the beneficiary, in the output token.
+    pub beneficiary_fee: u64,
+}
+
+/// The accounts of a swap, common to `Swap` and `SwapV2`.
+struct SwapAccounts {
+    user: Pubkey,
+    user_token_in: Pubkey,
+    user_token_out: Pubkey,
+    beneficiary_token_out: Pubkey,
+    pool: Pubkey,
+}
+
+impl SwapEvent {
+    /// Computes the swap executed by a decoded instruction, or returns `None`
+    /// if the instruction isn't a swap or the transaction metadata lacks the
+    /// token balances of its accounts.
+    pub fn from_instruction(
+        instruction: &DecodedInstruction<WeightedSwapInstruction>,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<Self> {
+        let (amount_in, accounts) = match &instruction.data {
+            WeightedSwapInstruction::Swap(swap) => {
+                let accounts = Swap::arrange_accounts(&instruction.accounts)?;
+                (
+                    swap.amount_in,
+                    SwapAccounts {
+                        user: accounts.user,
+                        user_token_in: accounts.user_token_in,
+                        user_token_out: accounts.user_token_out,
+                        beneficiary_token_out: accounts.beneficiary_token_out,
+                        pool: accounts.pool,
+                    },
+                )
+            }
+            WeightedSwapInstruction::SwapV2(swap) => {
+                let accounts = SwapV2::arrange_accounts(&instruction.accounts)?;
+                (
+                    swap.amount_in,
+                    SwapAccounts {
+                        user: accounts.user,
+                        user_token_in: accounts.user_token_in,
+                        user_token_out: accounts.user_token_out,
+                        beneficiary_token_out: accounts.beneficiary_token_out,
+                        pool: accounts.pool,
+                    },
+                )
+            }
+            _ => return None,
+        };
+
+        let token_in = transaction_metadata.token_balance_change(&accounts.user_token_in)?;
+        let token_out = transaction_metadata.token_balance_change(&accounts.user_token_out)?;
+        let beneficiary_fee = transaction_metadata
+            .token_balance_change(&accounts.beneficiary_token_out)
+            .map_or(0, |change| change.increase());
+
+        Some(Self {
+            pool: accounts.pool,
+            user: accounts.user,
+            mint_in: token_in.mint,
+            mint_out: token_out.mint,
+            // Swaps without an input amount are measured by the balance change
+            // of the input account.
+            amount_in: amount_in.unwrap_or_else(|| token_in.decrease()),
+            amount_out: token_out.increase(),
+            beneficiary_fee,
+        })
+    }
+}
+
+impl DexTrade for SwapEvent {
+    fn venue(&self) -> &'static str {
+        "stabble-weighted-swap"
+    }
+
+    fn pool(&self) -> Pubkey {
+        self.pool
+    }
+
+    fn trader(&self) -> Pubkey {
+        self.user
+    }
+
+    fn mint_in(&self) -> Pubkey {
+        self.mint_in
+    }
+
+    fn mint_out(&self) -> Pubkey {
+        self.mint_out
+    }
+
+    fn amount_in(&self) -> u64 {
+        self.amount_in
+    }
+
+    fn amount_out(&self) -> u64 {
+        self.amount_out
+    }
+
+    fn fee(&self) -> Option<TradeFee> {
+        Some(TradeFee {
+            mint: self.mint_out,
+            amount: self.beneficiary_fee,
+        })
+    }
+}
+
+impl DexInstruction for WeightedSwapInstruction {
+    fn trade(
+        instruction: &DecodedInstruction<Self>,
+        transaction_metadata: &TransactionMetadata,
+    ) -> Option<NormalizedTrade> {
+        SwapEvent::from_instruction(instruction, transaction_metadata)
+            .map(|swap| NormalizedTrade::new(&swap, transaction_metadata))
+    }
+}
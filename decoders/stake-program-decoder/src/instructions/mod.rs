@@ -74,4 +74,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for StakeProgramDecoder {
             StakeProgramInstruction::DeactivateDelinquent => deactivate_delinquent::DeactivateDelinquent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
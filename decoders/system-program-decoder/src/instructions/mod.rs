@@ -62,6 +62,10 @@ impl carbon_core::instruction::InstructionDecoder<'_> for SystemProgramDecoder {
             SystemProgramInstruction::UpgradeNonceAccount => upgrade_nonce_account::UpgradeNonceAccount,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[solana_program::system_program::ID])
+    }
 }
 
 #[cfg(test)]
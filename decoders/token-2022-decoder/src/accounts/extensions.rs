@@ -0,0 +1,303 @@
+//! Unpacks Token-2022 account state, including the TLV encoded extensions
+//! following the base mint and token account layouts.
+//!
+//! Accounts with extensions hold the base state, padded to the size of a
+//! token account, followed by an account type byte and a sequence of
+//! extensions, each encoded as a `u16` type, a `u16` length and the extension
+//! data.
+
+use {
+    crate::types::{AccountState, Extension, ExtensionType, TransferFee},
+    alloc::{string::String, vec::Vec},
+    solana_pubkey::Pubkey,
+};
+
+/// The size of a token account, which mints with extensions are padded to.
+pub(crate) const BASE_ACCOUNT_LEN: usize = 165;
+
+/// The account type byte following the base state of accounts with
+/// extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AccountType {
+    Mint = 1,
+    Account = 2,
+}
+
+/// Returns the TLV data of an account with extensions of the given type, or
+/// `None` if the account doesn't have extensions or is of another type.
+pub(crate) fn tlv_data(data: &[u8], account_type: AccountType) -> Option<&[u8]> {
+    match data.get(BASE_ACCOUNT_LEN) {
+        Some(byte) if *byte == account_type as u8 => data.get(BASE_ACCOUNT_LEN + 1..),
+        _ => None,
+    }
+}
+
+/// Parses the extensions of an account from its TLV data.
+///
+/// Extensions unknown to this decoder are returned as `Extension::Unknown`.
+/// Returns `None` if an entry is truncated or malformed.
+pub fn parse_extensions(mut tlv_data: &[u8]) -> Option<Vec<Extension>> {
+    let mut extensions = Vec::new();
+
+    while tlv_data.len() >= 4 {
+        let extension_type = u16::from_le_bytes([tlv_data[0], tlv_data[1]]);
+        let length = u16::from_le_bytes([tlv_data[2], tlv_data[3]]) as usize;
+        // The remaining data is zeroed padding.
+        if extension_type == 0 {
+            break;
+        }
+
+        let value = tlv_data.get(4..4 + length)?;
+        extensions.push(parse_extension(extension_type, value)?);
+        tlv_data = &tlv_data[4 + length..];
+    }
+
+    Some(extensions)
+}
+
+fn parse_extension(extension_type: u16, value: &[u8]) -> Option<Extension> {
+    let mut reader = Reader(value);
+
+    let extension = match extension_type {
+        1 => Extension::TransferFeeConfig {
+            transfer_fee_config_authority: reader.pubkey()?,
+            withdraw_withheld_authority: reader.pubkey()?,
+            withheld_amount: reader.u64()?,
+            older_transfer_fee: reader.transfer_fee()?,
+            newer_transfer_fee: reader.transfer_fee()?,
+        },
+        2 => Extension::TransferFeeAmount {
+            withheld_amount: reader.u64()?,
+        },
+        3 => Extension::MintCloseAuthority {
+            close_authority: reader.pubkey()?,
+        },
+        4 => Extension::ConfidentialTransferMint {
+            authority: reader.optional_pubkey()?,
+            auto_approve_new_accounts: reader.bool()?,
+            auditor_elgamal_pubkey: reader.optional_pubkey()?,
+        },
+        5 => Extension::ConfidentialTransferAccount {
+            approved: reader.bool()?,
+            elgamal_pubkey: reader.pubkey()?,
+            pending_balance_low: reader.array()?,
+            pending_balance_high: reader.array()?,
+            available_balance: reader.array()?,
+            decryptable_available_balance: reader.array()?,
+            allow_confidential_credits: reader.bool()?,
+            allow_non_confidential_credits: reader.bool()?,
+            pending_balance_credit_counter: reader.u64()?,
+            maximum_pending_balance_credit_counter: reader.u64()?,
+            expected_pending_balance_credit_counter: reader.u64()?,
+            actual_pending_balance_credit_counter: reader.u64()?,
+        },
+        6 => Extension::DefaultAccountState {
+            state: reader.account_state()?,
+        },
+        7 => Extension::ImmutableOwner {},
+        8 => Extension::MemoTransfer {
+            require_incoming_transfer_memos: reader.bool()?,
+        },
+        9 => Extension::NonTransferable {},
+        10 => Extension::InterestBearingConfig {
+            rate_authority: reader.pubkey()?,
+            initialization_timestamp: reader.u64()?,
+            pre_update_average_rate: reader.i16()?,
+            last_update_timestamp: reader.u64()?,
+            current_rate: reader.i16()?,
+        },
+        11 => Extension::CpiGuard {
+            lock_cpi: reader.bool()?,
+        },
+        12 => Extension::PermanentDelegate {
+            delegate: reader.pubkey()?,
+        },
+        13 => Extension::NonTransferableAccount {},
+        14 => Extension::TransferHook {
+            authority: reader.pubkey()?,
+            program_id: reader.pubkey()?,
+        },
+        15 => Extension::TransferHookAccount {
+            transferring: reader.bool()?,
+        },
+        16 => Extension::ConfidentialTransferFee {
+            authority: reader.optional_pubkey()?,
+            elgamal_pubkey: reader.pubkey()?,
+            harvest_to_mint_enabled: reader.bool()?,
+            withheld_amount: reader.array()?,
+        },
+        17 => Extension::ConfidentialTransferFeeAmount {
+            withheld_amount: reader.array()?,
+        },
+        18 => Extension::MetadataPointer {
+            authority: reader.optional_pubkey()?,
+            metadata_address: reader.optional_pubkey()?,
+        },
+        19 => Extension::TokenMetadata {
+            update_authority: reader.optional_pubkey()?,
+            mint: reader.pubkey()?,
+            name: reader.string()?,
+            symbol: reader.string()?,
+            uri: reader.string()?,
+            additional_metadata: {
+                let count = reader.u32()?;
+                (0..count)
+                    .map(|_| Some((reader.string()?, reader.string()?)))
+                    .collect::<Option<Vec<_>>>()?
+            },
+        },
+        20 => Extension::GroupPointer {
+            authority: reader.optional_pubkey()?,
+            group_address: reader.optional_pubkey()?,
+        },
+        21 => Extension::TokenGroup {
+            update_authority: reader.optional_pubkey()?,
+            mint: reader.pubkey()?,
+            size: reader.u64()?,
+            max_size: reader.u64()?,
+        },
+        22 => Extension::GroupMemberPointer {
+            authority: reader.optional_pubkey()?,
+            member_address: reader.optional_pubkey()?,
+        },
+        23 => Extension::TokenGroupMember {
+            mint: reader.pubkey()?,
+            group: reader.pubkey()?,
+            member_number: reader.u64()?,
+        },
+        extension_type => Extension::Unknown {
+            extension_type,
+            data: value.to_vec(),
+        },
+    };
+
+    Some(extension)
+}
+
+impl Extension {
+    /// Returns the type of the extension, or `None` for extensions unknown to
+    /// this decoder.
+    pub fn extension_type(&self) -> Option<ExtensionType> {
+        Some(match self {
+            Extension::Uninitialized => ExtensionType::Uninitialized,
+            Extension::TransferFeeConfig { .. } => ExtensionType::TransferFeeConfig,
+            Extension::TransferFeeAmount { .. } => ExtensionType::TransferFeeAmount,
+            Extension::MintCloseAuthority { .. } => ExtensionType::MintCloseAuthority,
+            Extension::ConfidentialTransferMint { .. } => ExtensionType::ConfidentialTransferMint,
+            Extension::ConfidentialTransferAccount { .. } => {
+                ExtensionType::ConfidentialTransferAccount
+            }
+            Extension::DefaultAccountState { .. } => ExtensionType::DefaultAccountState,
+            Extension::ImmutableOwner {} => ExtensionType::ImmutableOwner,
+            Extension::MemoTransfer { .. } => ExtensionType::MemoTransfer,
+            Extension::NonTransferable {} => ExtensionType::NonTransferable,
+            Extension::InterestBearingConfig { .. } => ExtensionType::InterestBearingConfig,
+            Extension::CpiGuard { .. } => ExtensionType::CpiGuard,
+            Extension::PermanentDelegate { .. } => ExtensionType::PermanentDelegate,
+            Extension::NonTransferableAccount {} => ExtensionType::NonTransferableAccount,
+            Extension::TransferHook { .. } => ExtensionType::TransferHook,
+            Extension::TransferHookAccount { .. } => ExtensionType::TransferHookAccount,
+            Extension::ConfidentialTransferFee { .. } => ExtensionType::ConfidentialTransferFee,
+            Extension::ConfidentialTransferFeeAmount { .. } => {
+                ExtensionType::ConfidentialTransferFeeAmount
+            }
+            Extension::MetadataPointer { .. } => ExtensionType::MetadataPointer,
+            Extension::TokenMetadata { .. } => ExtensionType::TokenMetadata,
+            Extension::GroupPointer { .. } => ExtensionType::GroupPointer,
+            Extension::TokenGroup { .. } => ExtensionType::TokenGroup,
+            Extension::GroupMemberPointer { .. } => ExtensionType::GroupMemberPointer,
+            Extension::TokenGroupMember { .. } => ExtensionType::TokenGroupMember,
+            Extension::Unknown { .. } => return None,
+        })
+    }
+}
+
+/// Reads the little-endian, packed fields of account state.
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (bytes, rest) = self.0.split_at_checked(len)?;
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        self.array().map(i16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn pubkey(&mut self) -> Option<Pubkey> {
+        self.array().map(Pubkey::new_from_array)
+    }
+
+    /// Reads a pubkey of an extension, where the default pubkey means none.
+    fn optional_pubkey(&mut self) -> Option<Option<Pubkey>> {
+        let pubkey = self.pubkey()?;
+        Some((pubkey != Pubkey::default()).then_some(pubkey))
+    }
+
+    /// Reads a pubkey of the base state, prefixed with a `u32` tag.
+    pub(crate) fn coption_pubkey(&mut self) -> Option<Option<Pubkey>> {
+        match self.u32()? {
+            0 => self.pubkey().map(|_| None),
+            1 => self.pubkey().map(Some),
+            _ => None,
+        }
+    }
+
+    /// Reads an amount of the base state, prefixed with a `u32` tag.
+    pub(crate) fn coption_u64(&mut self) -> Option<Option<u64>> {
+        match self.u32()? {
+            0 => self.u64().map(|_| None),
+            1 => self.u64().map(Some),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn account_state(&mut self) -> Option<AccountState> {
+        match self.u8()? {
+            0 => Some(AccountState::Uninitialized),
+            1 => Some(AccountState::Initialized),
+            2 => Some(AccountState::Frozen),
+            _ => None,
+        }
+    }
+
+    fn transfer_fee(&mut self) -> Option<TransferFee> {
+        Some(TransferFee {
+            epoch: self.u64()?,
+            maximum_fee: self.u64()?,
+            transfer_fee_basis_points: u16::from_le_bytes(self.array()?),
+        })
+    }
+
+    /// Reads a borsh string, prefixed with its `u32` length.
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
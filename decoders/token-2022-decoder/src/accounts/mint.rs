@@ -1,11 +1,48 @@
-use carbon_core::{borsh, CarbonDeserialize};
+use {
+    super::extensions::{parse_extensions, tlv_data, AccountType, Reader},
+    crate::types::{Extension, ExtensionType},
+    alloc::vec::Vec,
+};
 
-#[derive(CarbonDeserialize, Debug)]
-#[carbon(discriminator = "0x6a5edd53c00a4a4a")]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Mint {
     pub mint_authority: Option<solana_pubkey::Pubkey>,
     pub supply: u64,
     pub decimals: u8,
     pub is_initialized: bool,
     pub freeze_authority: Option<solana_pubkey::Pubkey>,
+    pub extensions: Vec<Extension>,
+}
+
+impl Mint {
+    /// The size of a mint without extensions.
+    pub const LEN: usize = 82;
+
+    /// Unpacks an initialized mint along with its extensions.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let extensions = if data.len() == Self::LEN {
+            Vec::new()
+        } else {
+            parse_extensions(tlv_data(data, AccountType::Mint)?)?
+        };
+
+        let mut reader = Reader(data);
+        let mint = Mint {
+            mint_authority: reader.coption_pubkey()?,
+            supply: reader.u64()?,
+            decimals: reader.u8()?,
+            is_initialized: reader.bool()?,
+            freeze_authority: reader.coption_pubkey()?,
+            extensions,
+        };
+
+        mint.is_initialized.then_some(mint)
+    }
+
+    /// Returns the extension of the given type, if the mint has it.
+    pub fn extension(&self, extension_type: ExtensionType) -> Option<&Extension> {
+        self.extensions
+            .iter()
+            .find(|extension| extension.extension_type().as_ref() == Some(&extension_type))
+    }
 }
@@ -1,8 +1,5 @@
-use {
-    super::Token2022Decoder,
-    crate::PROGRAM_ID,
-    carbon_core::{account::AccountDecoder, deserialize::CarbonDeserialize},
-};
+use {super::Token2022Decoder, crate::PROGRAM_ID, carbon_core::account::AccountDecoder};
+pub mod extensions;
 pub mod mint;
 pub mod multisig;
 pub mod token;
@@ -23,36 +20,109 @@ impl AccountDecoder<'_> for Token2022Decoder {
             return None;
         }
 
-        if let Some(decoded_account) = mint::Mint::deserialize(account.data.as_slice()) {
-            return Some(carbon_core::account::DecodedAccount {
-                lamports: account.lamports,
-                data: Token2022Account::Mint(decoded_account),
-                owner: account.owner,
-                executable: account.executable,
-                rent_epoch: account.rent_epoch,
-            });
-        }
+        // Accounts with extensions are never the size of a multisig, so that
+        // the size tells them apart.
+        let data = if account.data.len() == multisig::Multisig::LEN {
+            Token2022Account::Multisig(multisig::Multisig::unpack(&account.data)?)
+        } else if let Some(decoded_account) = mint::Mint::unpack(&account.data) {
+            Token2022Account::Mint(decoded_account)
+        } else {
+            Token2022Account::Token(token::Token::unpack(&account.data)?)
+        };
 
-        if let Some(decoded_account) = token::Token::deserialize(account.data.as_slice()) {
-            return Some(carbon_core::account::DecodedAccount {
-                lamports: account.lamports,
-                data: Token2022Account::Token(decoded_account),
-                owner: account.owner,
-                executable: account.executable,
-                rent_epoch: account.rent_epoch,
-            });
-        }
+        Some(carbon_core::account::DecodedAccount {
+            lamports: account.lamports,
+            data,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::types::{Extension, ExtensionType, TransferFee},
+        alloc::vec,
+        solana_pubkey::Pubkey,
+    };
 
-        if let Some(decoded_account) = multisig::Multisig::deserialize(account.data.as_slice()) {
-            return Some(carbon_core::account::DecodedAccount {
-                lamports: account.lamports,
-                data: Token2022Account::Multisig(decoded_account),
-                owner: account.owner,
-                executable: account.executable,
-                rent_epoch: account.rent_epoch,
-            });
+    #[test]
+    fn test_decode_mint_with_extensions() {
+        // Arrange
+        let mint_authority = Pubkey::new_unique();
+        let metadata_address = Pubkey::new_unique();
+
+        let mut data = vec![0; 165];
+        data[..4].copy_from_slice(&1u32.to_le_bytes());
+        data[4..36].copy_from_slice(mint_authority.as_ref());
+        data[36..44].copy_from_slice(&1_000_000u64.to_le_bytes());
+        data[44] = 6;
+        data[45] = 1;
+        data.push(1);
+
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&108u16.to_le_bytes());
+        data.extend_from_slice(&[0; 72]);
+        for (epoch, basis_points) in [(10u64, 50u16), (12, 100)] {
+            data.extend_from_slice(&epoch.to_le_bytes());
+            data.extend_from_slice(&5_000u64.to_le_bytes());
+            data.extend_from_slice(&basis_points.to_le_bytes());
         }
 
-        None
+        data.extend_from_slice(&18u16.to_le_bytes());
+        data.extend_from_slice(&64u16.to_le_bytes());
+        data.extend_from_slice(&[0; 32]);
+        data.extend_from_slice(metadata_address.as_ref());
+
+        let account = solana_account::Account {
+            lamports: 1,
+            data,
+            owner: PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+
+        // Act
+        let decoded_account = Token2022Decoder
+            .decode_account(&account)
+            .expect("decode account");
+
+        // Assert
+        let Token2022Account::Mint(mint) = decoded_account.data else {
+            panic!("expected a mint");
+        };
+        assert_eq!(mint.mint_authority, Some(mint_authority));
+        assert_eq!(mint.supply, 1_000_000);
+        assert_eq!(mint.decimals, 6);
+        assert_eq!(mint.freeze_authority, None);
+        assert_eq!(mint.extensions.len(), 2);
+        assert_eq!(
+            mint.extension(ExtensionType::TransferFeeConfig),
+            Some(&Extension::TransferFeeConfig {
+                transfer_fee_config_authority: Pubkey::default(),
+                withdraw_withheld_authority: Pubkey::default(),
+                withheld_amount: 0,
+                older_transfer_fee: TransferFee {
+                    epoch: 10,
+                    maximum_fee: 5_000,
+                    transfer_fee_basis_points: 50,
+                },
+                newer_transfer_fee: TransferFee {
+                    epoch: 12,
+                    maximum_fee: 5_000,
+                    transfer_fee_basis_points: 100,
+                },
+            })
+        );
+        assert_eq!(
+            mint.extension(ExtensionType::MetadataPointer),
+            Some(&Extension::MetadataPointer {
+                authority: None,
+                metadata_address: Some(metadata_address),
+            })
+        );
     }
 }
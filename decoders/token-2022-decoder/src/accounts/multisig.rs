@@ -1,10 +1,36 @@
-use carbon_core::{borsh, CarbonDeserialize};
+use super::extensions::Reader;
 
-#[derive(CarbonDeserialize, Debug)]
-#[carbon(discriminator = "0x032c5eecdd46ac85")]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Multisig {
     pub m: u8,
     pub n: u8,
     pub is_initialized: bool,
     pub signers: [solana_pubkey::Pubkey; 11],
 }
+
+impl Multisig {
+    pub const LEN: usize = 355;
+
+    /// Unpacks an initialized multisig.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN {
+            return None;
+        }
+
+        let mut reader = Reader(data);
+        let m = reader.u8()?;
+        let n = reader.u8()?;
+        let is_initialized = reader.bool()?;
+        let mut signers = [solana_pubkey::Pubkey::default(); 11];
+        for signer in signers.iter_mut() {
+            *signer = reader.pubkey()?;
+        }
+
+        is_initialized.then_some(Multisig {
+            m,
+            n,
+            is_initialized,
+            signers,
+        })
+    }
+}
@@ -1,10 +1,10 @@
 use {
-    super::super::types::*,
-    carbon_core::{borsh, CarbonDeserialize},
+    super::extensions::{parse_extensions, tlv_data, AccountType, Reader, BASE_ACCOUNT_LEN},
+    crate::types::{AccountState, Extension, ExtensionType},
+    alloc::vec::Vec,
 };
 
-#[derive(CarbonDeserialize, Debug)]
-#[carbon(discriminator = "0xc971463ece9dd1b2")]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Token {
     pub mint: solana_pubkey::Pubkey,
     pub owner: solana_pubkey::Pubkey,
@@ -14,4 +14,41 @@ pub struct Token {
     pub is_native: Option<u64>,
     pub delegated_amount: u64,
     pub close_authority: Option<solana_pubkey::Pubkey>,
+    pub extensions: Vec<Extension>,
+}
+
+impl Token {
+    /// The size of a token account without extensions.
+    pub const LEN: usize = BASE_ACCOUNT_LEN;
+
+    /// Unpacks an initialized token account along with its extensions.
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let extensions = if data.len() == Self::LEN {
+            Vec::new()
+        } else {
+            parse_extensions(tlv_data(data, AccountType::Account)?)?
+        };
+
+        let mut reader = Reader(data);
+        let token = Token {
+            mint: reader.pubkey()?,
+            owner: reader.pubkey()?,
+            amount: reader.u64()?,
+            delegate: reader.coption_pubkey()?,
+            state: reader.account_state()?,
+            is_native: reader.coption_u64()?,
+            delegated_amount: reader.u64()?,
+            close_authority: reader.coption_pubkey()?,
+            extensions,
+        };
+
+        (token.state != AccountState::Uninitialized).then_some(token)
+    }
+
+    /// Returns the extension of the given type, if the account has it.
+    pub fn extension(&self, extension_type: ExtensionType) -> Option<&Extension> {
+        self.extensions
+            .iter()
+            .find(|extension| extension.extension_type().as_ref() == Some(&extension_type))
+    }
 }
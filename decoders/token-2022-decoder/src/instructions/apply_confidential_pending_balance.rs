@@ -6,9 +6,8 @@ use {
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1b")]
+#[carbon(discriminator = "0x1b08")]
 pub struct ApplyConfidentialPendingBalance {
-    pub confidential_transfer_discriminator: u8,
     pub expected_pending_balance_credit_counter: u64,
     #[serde(with = "BigArray")]
     pub new_decryptable_available_balance: [u8; 36],
@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1b")]
+#[carbon(discriminator = "0x1b05")]
 pub struct ConfidentialDeposit {
-    pub confidential_transfer_discriminator: u8,
     pub amount: u64,
     pub decimals: u8,
 }
@@ -6,9 +6,8 @@ use {
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1b")]
+#[carbon(discriminator = "0x1b0d")]
 pub struct ConfidentialTransferWithFee {
-    pub confidential_transfer_discriminator: u8,
     #[serde(with = "BigArray")]
     pub new_source_decryptable_available_balance: [u8; 36],
     pub equality_proof_instruction_offset: i8,
@@ -6,9 +6,8 @@ use {
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1b")]
+#[carbon(discriminator = "0x1b06")]
 pub struct ConfidentialWithdraw {
-    pub confidential_transfer_discriminator: u8,
     pub amount: u64,
     pub decimals: u8,
     #[serde(with = "BigArray")]
@@ -6,9 +6,8 @@ use {
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1b")]
+#[carbon(discriminator = "0x1b02")]
 pub struct ConfigureConfidentialTransferAccount {
-    pub confidential_transfer_discriminator: u8,
     #[serde(with = "BigArray")]
     pub decryptable_zero_balance: [u8; 36],
     pub maximum_pending_balance_credit_counter: u64,
@@ -3,10 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x22")]
-pub struct DisableCpiGuard {
-    pub cpi_guard_discriminator: u8,
-}
+#[carbon(discriminator = "0x2201")]
+pub struct DisableCpiGuard {}
 
 pub struct DisableCpiGuardInstructionAccounts {
     pub token: solana_pubkey::Pubkey,
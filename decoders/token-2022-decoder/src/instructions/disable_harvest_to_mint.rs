@@ -3,10 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x25")]
-pub struct DisableHarvestToMint {
-    pub confidential_transfer_fee_discriminator: u8,
-}
+#[carbon(discriminator = "0x2505")]
+pub struct DisableHarvestToMint {}
 
 pub struct DisableHarvestToMintInstructionAccounts {
     pub mint: solana_pubkey::Pubkey,
@@ -3,10 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1e")]
-pub struct DisableMemoTransfers {
-    pub memo_transfers_discriminator: u8,
-}
+#[carbon(discriminator = "0x1e01")]
+pub struct DisableMemoTransfers {}
 
 pub struct DisableMemoTransfersInstructionAccounts {
     pub token: solana_pubkey::Pubkey,
@@ -3,10 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1b")]
-pub struct EnableConfidentialCredits {
-    pub confidential_transfer_discriminator: u8,
-}
+#[carbon(discriminator = "0x1b09")]
+pub struct EnableConfidentialCredits {}
 
 pub struct EnableConfidentialCreditsInstructionAccounts {
     pub token: solana_pubkey::Pubkey,
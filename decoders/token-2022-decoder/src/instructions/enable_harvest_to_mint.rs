@@ -3,10 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x25")]
-pub struct EnableHarvestToMint {
-    pub confidential_transfer_fee_discriminator: u8,
-}
+#[carbon(discriminator = "0x2504")]
+pub struct EnableHarvestToMint {}
 
 pub struct EnableHarvestToMintInstructionAccounts {
     pub mint: solana_pubkey::Pubkey,
@@ -3,10 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1a")]
-pub struct HarvestWithheldTokensToMint {
-    pub transfer_fee_discriminator: u8,
-}
+#[carbon(discriminator = "0x1a04")]
+pub struct HarvestWithheldTokensToMint {}
 
 pub struct HarvestWithheldTokensToMintInstructionAccounts {
     pub mint: solana_pubkey::Pubkey,
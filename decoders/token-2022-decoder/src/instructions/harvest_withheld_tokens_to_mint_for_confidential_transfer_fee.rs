@@ -3,10 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x25")]
-pub struct HarvestWithheldTokensToMintForConfidentialTransferFee {
-    pub confidential_transfer_fee_discriminator: u8,
-}
+#[carbon(discriminator = "0x2503")]
+pub struct HarvestWithheldTokensToMintForConfidentialTransferFee {}
 
 pub struct HarvestWithheldTokensToMintForConfidentialTransferFeeInstructionAccounts {
     pub mint: solana_pubkey::Pubkey,
@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x25")]
+#[carbon(discriminator = "0x2500")]
 pub struct InitializeConfidentialTransferFee {
-    pub confidential_transfer_fee_discriminator: u8,
     pub authority: Option<solana_pubkey::Pubkey>,
     pub withdraw_withheld_authority_el_gamal_pubkey: Option<solana_pubkey::Pubkey>,
 }
@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1b")]
+#[carbon(discriminator = "0x1b00")]
 pub struct InitializeConfidentialTransferMint {
-    pub confidential_transfer_discriminator: u8,
     pub authority: Option<solana_pubkey::Pubkey>,
     pub auto_approve_new_accounts: bool,
     pub auditor_elgamal_pubkey: Option<solana_pubkey::Pubkey>,
@@ -6,9 +6,8 @@ use {
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1c")]
+#[carbon(discriminator = "0x1c00")]
 pub struct InitializeDefaultAccountState {
-    pub default_account_state_discriminator: u8,
     pub state: AccountState,
 }
 
@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x29")]
+#[carbon(discriminator = "0x2900")]
 pub struct InitializeGroupMemberPointer {
-    pub group_member_pointer_discriminator: u8,
     pub authority: Option<solana_pubkey::Pubkey>,
     pub member_address: Option<solana_pubkey::Pubkey>,
 }
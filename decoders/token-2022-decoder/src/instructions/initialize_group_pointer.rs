@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x28")]
+#[carbon(discriminator = "0x2800")]
 pub struct InitializeGroupPointer {
-    pub group_pointer_discriminator: u8,
     pub authority: Option<solana_pubkey::Pubkey>,
     pub group_address: Option<solana_pubkey::Pubkey>,
 }
@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x21")]
+#[carbon(discriminator = "0x2100")]
 pub struct InitializeInterestBearingMint {
-    pub interest_bearing_mint_discriminator: u8,
     pub rate_authority: Option<solana_pubkey::Pubkey>,
     pub rate: i16,
 }
@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x27")]
+#[carbon(discriminator = "0x2700")]
 pub struct InitializeMetadataPointer {
-    pub metadata_pointer_discriminator: u8,
     pub authority: Option<solana_pubkey::Pubkey>,
     pub metadata_address: Option<solana_pubkey::Pubkey>,
 }
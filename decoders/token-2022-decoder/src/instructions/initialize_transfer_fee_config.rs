@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1a")]
+#[carbon(discriminator = "0x1a00")]
 pub struct InitializeTransferFeeConfig {
-    pub transfer_fee_discriminator: u8,
     pub transfer_fee_config_authority: Option<solana_pubkey::Pubkey>,
     pub withdraw_withheld_authority: Option<solana_pubkey::Pubkey>,
     pub transfer_fee_basis_points: u16,
@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x24")]
+#[carbon(discriminator = "0x2400")]
 pub struct InitializeTransferHook {
-    pub transfer_hook_discriminator: u8,
     pub authority: Option<solana_pubkey::Pubkey>,
     pub program_id: Option<solana_pubkey::Pubkey>,
 }
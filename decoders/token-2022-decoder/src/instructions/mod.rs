@@ -275,4 +275,67 @@ impl carbon_core::instruction::InstructionDecoder<'_> for Token2022Decoder {
             Token2022Instruction::InitializeTokenGroupMember => initialize_token_group_member::InitializeTokenGroupMember,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        alloc::vec,
+        carbon_core::{deserialize::ArrangeAccounts, instruction::InstructionDecoder},
+        solana_instruction::AccountMeta,
+        solana_pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_decode_transfer_checked_with_fee_and_hook() {
+        // Arrange
+        let mut data = vec![0x1a, 0x01];
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.push(6);
+        data.extend_from_slice(&10u64.to_le_bytes());
+        let extra_account = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        let hook_program = Pubkey::new_unique();
+        let validation_state = Pubkey::new_unique();
+        let accounts = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), true),
+            extra_account.clone(),
+            AccountMeta::new_readonly(hook_program, false),
+            AccountMeta::new_readonly(validation_state, false),
+        ];
+        let instruction = solana_instruction::Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts.clone(),
+            data,
+        };
+        let expected_ix = Token2022Instruction::TransferCheckedWithFee(
+            transfer_checked_with_fee::TransferCheckedWithFee {
+                amount: 1_000,
+                decimals: 6,
+                fee: 10,
+            },
+        );
+
+        // Act
+        let decoded = Token2022Decoder
+            .decode_instruction(&instruction)
+            .expect("decode instruction");
+        let transfer_hook =
+            transfer_checked_with_fee::TransferCheckedWithFee::arrange_accounts(&accounts)
+                .and_then(|accounts| accounts.transfer_hook())
+                .expect("transfer hook accounts");
+
+        // Assert
+        assert_eq!(decoded.data, expected_ix);
+        assert_eq!(transfer_hook.program_id, hook_program);
+        assert_eq!(transfer_hook.validation_state, validation_state);
+        assert_eq!(transfer_hook.extra_accounts, vec![extra_account]);
+    }
 }
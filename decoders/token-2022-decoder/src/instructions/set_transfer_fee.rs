@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1a")]
+#[carbon(discriminator = "0x1a05")]
 pub struct SetTransferFee {
-    pub transfer_fee_discriminator: u8,
     pub transfer_fee_basis_points: u16,
     pub maximum_fee: u64,
 }
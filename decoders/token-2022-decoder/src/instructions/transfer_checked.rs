@@ -1,4 +1,8 @@
-use carbon_core::{borsh, CarbonDeserialize};
+use {
+    crate::transfer_hook::TransferHookAccounts,
+    alloc::vec::Vec,
+    carbon_core::{borsh, CarbonDeserialize},
+};
 
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
@@ -9,12 +13,13 @@ pub struct TransferChecked {
     pub decimals: u8,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TransferCheckedInstructionAccounts {
     pub source: solana_pubkey::Pubkey,
     pub mint: solana_pubkey::Pubkey,
     pub destination: solana_pubkey::Pubkey,
     pub authority: solana_pubkey::Pubkey,
+    pub remaining_accounts: Vec<solana_instruction::AccountMeta>,
 }
 
 impl carbon_core::deserialize::ArrangeAccounts for TransferChecked {
@@ -23,7 +28,7 @@ impl carbon_core::deserialize::ArrangeAccounts for TransferChecked {
     fn arrange_accounts(
         accounts: &[solana_instruction::AccountMeta],
     ) -> Option<Self::ArrangedAccounts> {
-        let [source, mint, destination, authority, _remaining @ ..] = accounts else {
+        let [source, mint, destination, authority, remaining_accounts @ ..] = accounts else {
             return None;
         };
 
@@ -32,6 +37,15 @@ impl carbon_core::deserialize::ArrangeAccounts for TransferChecked {
             mint: mint.pubkey,
             destination: destination.pubkey,
             authority: authority.pubkey,
+            remaining_accounts: remaining_accounts.to_vec(),
         })
     }
 }
+
+impl TransferCheckedInstructionAccounts {
+    /// Returns the transfer-hook accounts of the transfer, if its mint has a
+    /// transfer hook.
+    pub fn transfer_hook(&self) -> Option<TransferHookAccounts> {
+        TransferHookAccounts::from_remaining_accounts(&self.remaining_accounts)
+    }
+}
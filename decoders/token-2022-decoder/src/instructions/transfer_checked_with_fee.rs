@@ -1,21 +1,26 @@
-use carbon_core::{borsh, CarbonDeserialize};
+use {
+    crate::transfer_hook::TransferHookAccounts,
+    alloc::vec::Vec,
+    carbon_core::{borsh, CarbonDeserialize},
+};
 
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1a")]
+#[carbon(discriminator = "0x1a01")]
 pub struct TransferCheckedWithFee {
-    pub transfer_fee_discriminator: u8,
     pub amount: u64,
     pub decimals: u8,
     pub fee: u64,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
 pub struct TransferCheckedWithFeeInstructionAccounts {
     pub source: solana_pubkey::Pubkey,
     pub mint: solana_pubkey::Pubkey,
     pub destination: solana_pubkey::Pubkey,
     pub authority: solana_pubkey::Pubkey,
+    pub remaining_accounts: Vec<solana_instruction::AccountMeta>,
 }
 
 impl carbon_core::deserialize::ArrangeAccounts for TransferCheckedWithFee {
@@ -24,7 +29,7 @@ impl carbon_core::deserialize::ArrangeAccounts for TransferCheckedWithFee {
     fn arrange_accounts(
         accounts: &[solana_instruction::AccountMeta],
     ) -> Option<Self::ArrangedAccounts> {
-        let [source, mint, destination, authority, _remaining @ ..] = accounts else {
+        let [source, mint, destination, authority, remaining_accounts @ ..] = accounts else {
             return None;
         };
 
@@ -33,6 +38,15 @@ impl carbon_core::deserialize::ArrangeAccounts for TransferCheckedWithFee {
             mint: mint.pubkey,
             destination: destination.pubkey,
             authority: authority.pubkey,
+            remaining_accounts: remaining_accounts.to_vec(),
         })
     }
 }
+
+impl TransferCheckedWithFeeInstructionAccounts {
+    /// Returns the transfer-hook accounts of the transfer, if its mint has a
+    /// transfer hook.
+    pub fn transfer_hook(&self) -> Option<TransferHookAccounts> {
+        TransferHookAccounts::from_remaining_accounts(&self.remaining_accounts)
+    }
+}
@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x24")]
+#[carbon(discriminator = "0x2401")]
 pub struct UpdateTransferHook {
-    pub transfer_hook_discriminator: u8,
     pub program_id: Option<solana_pubkey::Pubkey>,
 }
 
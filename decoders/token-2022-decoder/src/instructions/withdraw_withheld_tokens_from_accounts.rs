@@ -3,9 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1a")]
+#[carbon(discriminator = "0x1a03")]
 pub struct WithdrawWithheldTokensFromAccounts {
-    pub transfer_fee_discriminator: u8,
     pub num_token_accounts: u8,
 }
 
@@ -6,9 +6,8 @@ use {
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x25")]
+#[carbon(discriminator = "0x2502")]
 pub struct WithdrawWithheldTokensFromAccountsForConfidentialTransferFee {
-    pub confidential_transfer_fee_discriminator: u8,
     pub num_token_accounts: u8,
     pub proof_instruction_offset: i8,
     #[serde(with = "BigArray")]
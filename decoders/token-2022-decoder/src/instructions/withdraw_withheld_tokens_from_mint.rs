@@ -3,10 +3,8 @@ use carbon_core::{borsh, CarbonDeserialize};
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x1a")]
-pub struct WithdrawWithheldTokensFromMint {
-    pub transfer_fee_discriminator: u8,
-}
+#[carbon(discriminator = "0x1a02")]
+pub struct WithdrawWithheldTokensFromMint {}
 
 pub struct WithdrawWithheldTokensFromMintInstructionAccounts {
     pub mint: solana_pubkey::Pubkey,
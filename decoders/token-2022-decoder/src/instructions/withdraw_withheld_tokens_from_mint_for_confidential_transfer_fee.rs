@@ -6,9 +6,8 @@ use {
 #[derive(
     CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
 )]
-#[carbon(discriminator = "0x25")]
+#[carbon(discriminator = "0x2501")]
 pub struct WithdrawWithheldTokensFromMintForConfidentialTransferFee {
-    pub confidential_transfer_fee_discriminator: u8,
     pub proof_instruction_offset: i8,
     #[serde(with = "BigArray")]
     pub new_decryptable_available_balance: [u8; 36],
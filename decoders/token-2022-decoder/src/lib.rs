@@ -5,6 +5,7 @@ use solana_pubkey::Pubkey;
 pub struct Token2022Decoder;
 pub mod accounts;
 pub mod instructions;
+pub mod transfer_hook;
 pub mod types;
 
 pub const PROGRAM_ID: Pubkey =
@@ -0,0 +1,116 @@
+//! Transfer-hook invocations of Token-2022 transfers.
+//!
+//! Transfers of a mint with a transfer hook carry, after their standard
+//! accounts and multisig signers, the extra accounts required by the hook,
+//! followed by the hook program and its validation state account. Token-2022
+//! then invokes the `Execute` instruction of the hook program with these
+//! accounts.
+//!
+//! `TransferHookAccounts` splits these accounts out of the `TransferChecked`
+//! and `TransferCheckedWithFee` instructions, and `TransferHookDecoder`
+//! decodes the `Execute` instructions invoked on any hook program.
+
+use {
+    alloc::vec::Vec,
+    carbon_core::{
+        borsh,
+        deserialize::{ArrangeAccounts, CarbonDeserialize as _},
+        CarbonDeserialize,
+    },
+    solana_instruction::AccountMeta,
+    solana_pubkey::Pubkey,
+};
+
+/// The accounts a transfer passes on to its transfer hook.
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TransferHookAccounts {
+    pub program_id: Pubkey,
+    pub validation_state: Pubkey,
+    pub extra_accounts: Vec<AccountMeta>,
+}
+
+impl TransferHookAccounts {
+    /// Splits the transfer-hook accounts out of the accounts following the
+    /// authority of a transfer.
+    ///
+    /// Multisig signers come first and are skipped. The transfer has no hook
+    /// if fewer than two accounts follow them.
+    pub fn from_remaining_accounts(remaining_accounts: &[AccountMeta]) -> Option<Self> {
+        let signers = remaining_accounts
+            .iter()
+            .take_while(|account| account.is_signer)
+            .count();
+        let [extra_accounts @ .., program_id, validation_state] = &remaining_accounts[signers..]
+        else {
+            return None;
+        };
+
+        Some(TransferHookAccounts {
+            program_id: program_id.pubkey,
+            validation_state: validation_state.pubkey,
+            extra_accounts: extra_accounts.to_vec(),
+        })
+    }
+}
+
+/// The `Execute` instruction of the transfer-hook interface, invoked by
+/// Token-2022 on the hook program of each transfer of a hooked mint.
+#[derive(
+    CarbonDeserialize, Debug, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone, Hash,
+)]
+#[carbon(discriminator = "0x692565c54bfb661a")]
+pub struct TransferHookExecute {
+    pub amount: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TransferHookExecuteInstructionAccounts {
+    pub source: Pubkey,
+    pub mint: Pubkey,
+    pub destination: Pubkey,
+    pub authority: Pubkey,
+    pub validation_state: Pubkey,
+    pub extra_accounts: Vec<AccountMeta>,
+}
+
+impl ArrangeAccounts for TransferHookExecute {
+    type ArrangedAccounts = TransferHookExecuteInstructionAccounts;
+
+    fn arrange_accounts(accounts: &[AccountMeta]) -> Option<Self::ArrangedAccounts> {
+        let [source, mint, destination, authority, validation_state, extra_accounts @ ..] =
+            accounts
+        else {
+            return None;
+        };
+
+        Some(TransferHookExecuteInstructionAccounts {
+            source: source.pubkey,
+            mint: mint.pubkey,
+            destination: destination.pubkey,
+            authority: authority.pubkey,
+            validation_state: validation_state.pubkey,
+            extra_accounts: extra_accounts.to_vec(),
+        })
+    }
+}
+
+/// Decodes the `Execute` instructions of transfer-hook programs.
+///
+/// Hook programs are deployed by mint creators, so the decoder matches the
+/// instruction of any program.
+pub struct TransferHookDecoder;
+
+impl carbon_core::instruction::InstructionDecoder<'_> for TransferHookDecoder {
+    type InstructionType = TransferHookExecute;
+
+    fn decode_instruction(
+        &self,
+        instruction: &solana_instruction::Instruction,
+    ) -> Option<carbon_core::instruction::DecodedInstruction<Self::InstructionType>> {
+        Some(carbon_core::instruction::DecodedInstruction {
+            program_id: instruction.program_id,
+            data: TransferHookExecute::deserialize(&instruction.data)?,
+            accounts: instruction.accounts.clone(),
+        })
+    }
+}
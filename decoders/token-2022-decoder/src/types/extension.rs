@@ -116,4 +116,9 @@ pub enum Extension {
         group: solana_pubkey::Pubkey,
         member_number: u64,
     },
+    /// An extension this decoder doesn't know of, with its raw data.
+    Unknown {
+        extension_type: u16,
+        data: Vec<u8>,
+    },
 }
@@ -103,4 +103,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for TokenProgramDecoder {
             TokenProgramInstruction::UiAmountToAmount => ui_amount_to_amount::UiAmountToAmount,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[spl_token::ID])
+    }
 }
@@ -65,4 +65,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for VirtualsDecoder {
             VirtualsInstruction::SellEvent => sell_event::SellEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
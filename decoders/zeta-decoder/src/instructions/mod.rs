@@ -512,4 +512,8 @@ impl carbon_core::instruction::InstructionDecoder<'_> for ZetaDecoder {
             ZetaInstruction::PlaceMultiOrdersEvent => place_multi_orders_event::PlaceMultiOrdersEvent,
         )
     }
+
+    fn program_ids(&self) -> Option<&[solana_pubkey::Pubkey]> {
+        Some(&[PROGRAM_ID])
+    }
 }
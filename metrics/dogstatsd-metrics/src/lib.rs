@@ -0,0 +1,269 @@
+//! Exports pipeline metrics to a Datadog agent, or any StatsD server
+//! supporting the DogStatsD protocol.
+//!
+//! Counters, gauges and histograms are sent as DogStatsD counters, gauges and
+//! distributions, over UDP or a Unix domain socket. Metrics are buffered and
+//! packed into datagrams of at most `max_packet_size` bytes, which are sent
+//! when the buffer is full and whenever the pipeline flushes its metrics.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use carbon_dogstatsd_metrics::DogstatsdMetrics;
+//!
+//! let metrics = DogstatsdMetrics::udp("127.0.0.1:8125")
+//!     .prefix("carbon")
+//!     .tag("env", "production")
+//!     .tag("service", "pumpfun-indexer");
+//!
+//! carbon_core::pipeline::Pipeline::builder()
+//!     .datasource(datasource)
+//!     .metrics(Arc::new(metrics))
+//!     .metrics(Arc::new(LogMetrics::new()))
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! # Notes
+//!
+//! - Failing to send a datagram is logged and doesn't fail the pipeline, as
+//!   DogStatsD is a best effort protocol.
+//! - The characters `|`, `:`, `,`, `#`, `@` and newlines are replaced with `_`
+//!   in metric names and tags.
+
+#[cfg(unix)]
+use tokio::net::UnixDatagram;
+use {
+    async_trait::async_trait,
+    carbon_core::{
+        error::{CarbonResult, Error},
+        metrics::Metrics,
+    },
+    std::path::PathBuf,
+    tokio::{net::UdpSocket, sync::Mutex},
+};
+
+/// The recommended maximum datagram size over UDP, fitting in a single
+/// Ethernet frame.
+const UDP_MAX_PACKET_SIZE: usize = 1432;
+/// The default maximum datagram size of the Datadog agent over UDS.
+const UDS_MAX_PACKET_SIZE: usize = 8192;
+
+/// Where metrics are sent to.
+#[derive(Debug, Clone)]
+pub enum DogstatsdTarget {
+    /// A `host:port` address, usually `127.0.0.1:8125`.
+    Udp(String),
+    /// The path of the agent's Unix domain socket, usually
+    /// `/var/run/datadog/dsd.socket`.
+    Uds(PathBuf),
+}
+
+enum Socket {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Uds(UnixDatagram),
+}
+
+impl Socket {
+    async fn connect(target: &DogstatsdTarget) -> CarbonResult<Self> {
+        match target {
+            DogstatsdTarget::Udp(address) => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await.map_err(|e| {
+                    Error::Custom(format!("Failed to bind DogStatsD UDP socket: {}", e))
+                })?;
+                socket.connect(address).await.map_err(|e| {
+                    Error::Custom(format!(
+                        "Failed to connect to DogStatsD at {}: {}",
+                        address, e
+                    ))
+                })?;
+                Ok(Socket::Udp(socket))
+            }
+            #[cfg(unix)]
+            DogstatsdTarget::Uds(path) => {
+                let socket = UnixDatagram::unbound().map_err(|e| {
+                    Error::Custom(format!("Failed to create DogStatsD socket: {}", e))
+                })?;
+                socket.connect(path).map_err(|e| {
+                    Error::Custom(format!(
+                        "Failed to connect to DogStatsD at {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                Ok(Socket::Uds(socket))
+            }
+            #[cfg(not(unix))]
+            DogstatsdTarget::Uds(_) => Err(Error::Custom(
+                "Unix domain sockets are not supported on this platform".to_string(),
+            )),
+        }
+    }
+
+    async fn send(&self, packet: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Socket::Udp(socket) => socket.send(packet).await,
+            #[cfg(unix)]
+            Socket::Uds(socket) => socket.send(packet).await,
+        }
+    }
+}
+
+pub struct DogstatsdMetrics {
+    pub target: DogstatsdTarget,
+    pub prefix: Option<String>,
+    pub tags: Vec<String>,
+    pub max_packet_size: usize,
+    socket: Mutex<Option<Socket>>,
+    buffer: Mutex<String>,
+}
+
+impl DogstatsdMetrics {
+    pub fn new(target: DogstatsdTarget) -> Self {
+        let max_packet_size = match target {
+            DogstatsdTarget::Udp(_) => UDP_MAX_PACKET_SIZE,
+            DogstatsdTarget::Uds(_) => UDS_MAX_PACKET_SIZE,
+        };
+
+        Self {
+            target,
+            prefix: None,
+            tags: Vec::new(),
+            max_packet_size,
+            socket: Mutex::new(None),
+            buffer: Mutex::new(String::new()),
+        }
+    }
+
+    /// Sends metrics over UDP to `address`, such as `127.0.0.1:8125`.
+    pub fn udp(address: impl Into<String>) -> Self {
+        Self::new(DogstatsdTarget::Udp(address.into()))
+    }
+
+    /// Sends metrics over the Unix domain socket at `path`.
+    pub fn uds(path: impl Into<PathBuf>) -> Self {
+        Self::new(DogstatsdTarget::Uds(path.into()))
+    }
+
+    /// Prepends `prefix.` to every metric name.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Adds a `key:value` tag to every metric.
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        self.tags
+            .push(format!("{}:{}", sanitize(key), sanitize(value)));
+        self
+    }
+
+    pub fn max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    /// Formats a metric as a DogStatsD line, such as
+    /// `carbon.updates_processed:1|c|#env:production`.
+    fn line(&self, name: &str, value: &str, metric_type: &str) -> String {
+        let mut line = match &self.prefix {
+            Some(prefix) => format!("{}.{}", prefix, sanitize(name)),
+            None => sanitize(name),
+        };
+        line.push(':');
+        line.push_str(value);
+        line.push('|');
+        line.push_str(metric_type);
+
+        if !self.tags.is_empty() {
+            line.push_str("|#");
+            line.push_str(&self.tags.join(","));
+        }
+
+        line
+    }
+
+    async fn push(&self, line: String) -> CarbonResult<()> {
+        let packet = {
+            let mut buffer = self.buffer.lock().await;
+            let packet =
+                if !buffer.is_empty() && buffer.len() + 1 + line.len() > self.max_packet_size {
+                    Some(std::mem::take(&mut *buffer))
+                } else {
+                    None
+                };
+
+            if !buffer.is_empty() {
+                buffer.push('\n');
+            }
+            buffer.push_str(&line);
+            packet
+        };
+
+        if let Some(packet) = packet {
+            self.send(&packet).await;
+        }
+
+        Ok(())
+    }
+
+    async fn send(&self, packet: &str) {
+        let socket = self.socket.lock().await;
+        let Some(socket) = socket.as_ref() else {
+            log::warn!("DogStatsD metrics sent before initialization were dropped");
+            return;
+        };
+
+        if let Err(e) = socket.send(packet.as_bytes()).await {
+            log::warn!("Failed to send metrics to DogStatsD: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl Metrics for DogstatsdMetrics {
+    async fn initialize(&self) -> CarbonResult<()> {
+        let socket = Socket::connect(&self.target).await?;
+        *self.socket.lock().await = Some(socket);
+
+        log::info!("DogStatsD exporter sending metrics to {:?}", self.target);
+        Ok(())
+    }
+
+    async fn flush(&self) -> CarbonResult<()> {
+        let packet = std::mem::take(&mut *self.buffer.lock().await);
+        if !packet.is_empty() {
+            self.send(&packet).await;
+        }
+
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> CarbonResult<()> {
+        self.flush().await
+    }
+
+    async fn update_gauge(&self, name: &str, value: f64) -> CarbonResult<()> {
+        self.push(self.line(name, &value.to_string(), "g")).await
+    }
+
+    async fn increment_counter(&self, name: &str, value: u64) -> CarbonResult<()> {
+        self.push(self.line(name, &value.to_string(), "c")).await
+    }
+
+    async fn record_histogram(&self, name: &str, value: f64) -> CarbonResult<()> {
+        self.push(self.line(name, &value.to_string(), "d")).await
+    }
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '|' | ':' | ',' | '#' | '@' | '\n' => '_',
+            c => c,
+        })
+        .collect()
+}
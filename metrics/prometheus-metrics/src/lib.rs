@@ -6,7 +6,11 @@ use {
     },
     metrics::{counter, gauge, histogram},
     metrics_exporter_prometheus::PrometheusBuilder,
-    std::{collections::HashMap, net::SocketAddrV4, sync::Once},
+    std::{
+        collections::HashMap,
+        net::{Ipv4Addr, SocketAddr},
+        sync::Once,
+    },
     tokio::sync::RwLock,
 };
 
@@ -14,6 +18,7 @@ pub struct PrometheusMetrics {
     pub counters: RwLock<HashMap<String, metrics::Counter>>,
     pub gauges: RwLock<HashMap<String, metrics::Gauge>>,
     pub histograms: RwLock<HashMap<String, metrics::Histogram>>,
+    listen_address: SocketAddr,
 }
 
 impl Default for PrometheusMetrics {
@@ -22,6 +27,7 @@ impl Default for PrometheusMetrics {
             counters: RwLock::new(HashMap::new()),
             gauges: RwLock::new(HashMap::new()),
             histograms: RwLock::new(HashMap::new()),
+            listen_address: SocketAddr::from((Ipv4Addr::LOCALHOST, 9100)),
         }
     }
 }
@@ -29,6 +35,15 @@ impl PrometheusMetrics {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Sets the address the exporter listens on, `127.0.0.1:9100` by default.
+    ///
+    /// Containerized indexers listen on `0.0.0.0` for Prometheus to scrape them
+    /// from another container.
+    pub fn with_listen_address(mut self, listen_address: SocketAddr) -> Self {
+        self.listen_address = listen_address;
+        self
+    }
 }
 
 #[async_trait]
@@ -38,15 +53,14 @@ impl Metrics for PrometheusMetrics {
 
         let mut result = Ok(());
         INIT.call_once(|| {
-            let builder = PrometheusBuilder::new().with_http_listener(
-                "127.0.0.1:9100"
-                    .parse::<SocketAddrV4>()
-                    .expect("Failed to parse address"),
-            );
+            let builder = PrometheusBuilder::new().with_http_listener(self.listen_address);
 
             match builder.install() {
                 Ok(_handle) => {
-                    log::info!("Prometheus exporter installed and listening on 127.0.0.1:9100");
+                    log::info!(
+                        "Prometheus exporter installed and listening on {}",
+                        self.listen_address
+                    );
                 }
                 Err(e) => {
                     result = Err(Error::Custom(format!(